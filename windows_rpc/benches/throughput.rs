@@ -0,0 +1,102 @@
+//! Measures calls of a reference interface in-process and over ALPC.
+//!
+//! Run with `cargo bench -p windows-rpc`. Comparing the `loopback` group, which calls the
+//! implementation directly, with the `alpc` group isolates the cost of marshalling and
+//! dispatch.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+use windows_rpc::rpc_interface;
+use windows_rpc::testing::pair;
+
+#[rpc_interface(guid(0x5b0e7f3a_91c4_4d26_8e5b_c73a0d1f6e98), version(1.0))]
+trait Bench {
+    fn noop();
+    fn add(a: i32, b: i32) -> i32;
+    fn echo(text: &str) -> String;
+    fn checksum(data: &[u8]) -> u32;
+}
+
+/// Implementation doing as little work as possible
+struct BenchImpl;
+
+impl BenchServerImpl for BenchImpl {
+    fn noop() {}
+
+    fn add(a: i32, b: i32) -> i32 {
+        a.wrapping_add(b)
+    }
+
+    fn echo(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn checksum(data: &[u8]) -> u32 {
+        data.iter().map(|&byte| byte as u32).sum()
+    }
+}
+
+const ECHO_LENGTHS: [usize; 2] = [16, 4096];
+const CHECKSUM_LENGTHS: [usize; 2] = [64, 64 * 1024];
+
+fn loopback(c: &mut Criterion) {
+    let mut group = c.benchmark_group("loopback");
+    group.bench_function("noop", |b| b.iter(BenchImpl::noop));
+    group.bench_function("add", |b| {
+        b.iter(|| BenchImpl::add(black_box(1), black_box(2)))
+    });
+    for len in ECHO_LENGTHS {
+        let text = "x".repeat(len);
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("echo", len), &text, |b, text| {
+            b.iter(|| BenchImpl::echo(black_box(text)))
+        });
+    }
+    for len in CHECKSUM_LENGTHS {
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("checksum", len), &data, |b, data| {
+            b.iter(|| BenchImpl::checksum(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+fn alpc(c: &mut Criterion) {
+    let (_server, client) =
+        pair::<BenchServer<_>, _>(BenchImpl).expect("Failed to create client/server pair");
+
+    let mut group = c.benchmark_group("alpc");
+    group.bench_function("noop", |b| {
+        b.iter(|| client.noop().expect("benchmark call failed"))
+    });
+    group.bench_function("add", |b| {
+        b.iter(|| {
+            client
+                .add(black_box(1), black_box(2))
+                .expect("benchmark call failed")
+        })
+    });
+    for len in ECHO_LENGTHS {
+        let text = "x".repeat(len);
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("echo", len), &text, |b, text| {
+            b.iter(|| client.echo(black_box(text)).expect("benchmark call failed"))
+        });
+    }
+    for len in CHECKSUM_LENGTHS {
+        let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("checksum", len), &data, |b, data| {
+            b.iter(|| {
+                client
+                    .checksum(black_box(data))
+                    .expect("benchmark call failed")
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, loopback, alpc);
+criterion_main!(benches);
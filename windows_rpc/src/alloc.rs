@@ -1,22 +1,180 @@
+//! MIDL memory allocator used by the generated stubs.
+//!
+//! NDR marshalling performs many short-lived small allocations per call. Small
+//! blocks are served from per-thread pools of fixed size classes and returned to them
+//! on free, avoiding a heap round-trip for each of them. Larger blocks, and all blocks
+//! when pooling is disabled with [`set_pooling()`], go straight to the global allocator.
+//!
+//! Allocations can be bounded with [`with_quota()`], which servers use to limit the
+//! memory a single call may make the stubs allocate.
+
 use std::alloc::Layout;
+use std::cell::{Cell, RefCell};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Alignment of every returned block. The RPC runtime requires at least 8 bytes.
+const ALIGN: usize = 16;
+/// Space reserved before every block for its `Layout`, keeping the block aligned.
+const HEADER_SIZE: usize = 16;
+/// Usable sizes of the pooled blocks.
+const SIZE_CLASSES: [usize; 5] = [32, 64, 128, 256, 512];
+/// Maximum number of free blocks kept per size class and thread.
+const MAX_CACHED_PER_CLASS: usize = 64;
+
+const _: () = assert!(std::mem::size_of::<Layout>() <= HEADER_SIZE);
+
+static POOLING: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables pooling of small allocations for the whole process.
+///
+/// Pooling is enabled by default. Blocks allocated while pooling was enabled can
+/// still be freed after disabling it, and vice versa.
+pub fn set_pooling(enabled: bool) {
+    POOLING.store(enabled, Ordering::Relaxed);
+}
+
+struct Pool {
+    free: [Vec<NonNull<u8>>; SIZE_CLASSES.len()],
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        for (class, blocks) in self.free.iter_mut().enumerate() {
+            for block in blocks.drain(..) {
+                unsafe { std::alloc::dealloc(block.as_ptr(), block_layout(SIZE_CLASSES[class])) };
+            }
+        }
+    }
+}
 
+thread_local! {
+    static POOL: RefCell<Pool> = RefCell::new(Pool {
+        free: Default::default(),
+    });
+}
+
+thread_local! {
+    // Bytes left to allocate on this thread, if limited
+    static QUOTA: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+/// Limits allocations on this thread until dropped, restoring the previous limit.
+pub(crate) struct QuotaScope {
+    previous: Option<usize>,
+}
+
+impl QuotaScope {
+    pub(crate) fn enter(quota: Option<usize>) -> Self {
+        let previous = QUOTA.with(|remaining| remaining.replace(quota));
+        Self { previous }
+    }
+}
+
+impl Drop for QuotaScope {
+    fn drop(&mut self) {
+        QUOTA.with(|remaining| remaining.set(self.previous));
+    }
+}
+
+/// Runs `f` with the allocations it makes through [`midl_alloc()`] on this thread
+/// limited to `quota` bytes in total, or unlimited if `None`.
+///
+/// Freeing memory does not restore the quota. Once it is exhausted, [`midl_alloc()`]
+/// returns null, which the NDR engine reports as a fault of the current call. A nested
+/// call replaces the quota until it returns.
+pub fn with_quota<R>(quota: Option<usize>, f: impl FnOnce() -> R) -> R {
+    let _scope = QuotaScope::enter(quota);
+    f()
+}
+
+/// Deducts `size` from the current quota, returning false if it is exceeded.
+fn charge(size: usize) -> bool {
+    QUOTA
+        .try_with(|remaining| match remaining.get() {
+            None => true,
+            Some(left) if size <= left => {
+                remaining.set(Some(left - size));
+                true
+            }
+            Some(_) => false,
+        })
+        .unwrap_or(true)
+}
+
+fn block_layout(size: usize) -> Layout {
+    // Cannot fail for the sizes checked in `midl_alloc`
+    Layout::from_size_align(size + HEADER_SIZE, ALIGN).unwrap()
+}
+
+fn size_class(size: usize) -> Option<usize> {
+    SIZE_CLASSES.iter().position(|&class| size <= class)
+}
+
+fn take_pooled(class: usize) -> Option<NonNull<u8>> {
+    POOL.try_with(|pool| pool.try_borrow_mut().ok()?.free[class].pop())
+        .ok()
+        .flatten()
+}
+
+/// Returns `block` to the pool, or gives it back if the pool is full or unavailable.
+fn put_pooled(class: usize, block: NonNull<u8>) -> Result<(), NonNull<u8>> {
+    POOL.try_with(|pool| {
+        let Ok(mut pool) = pool.try_borrow_mut() else {
+            return Err(block);
+        };
+        let blocks = &mut pool.free[class];
+        if blocks.len() >= MAX_CACHED_PER_CLASS {
+            return Err(block);
+        }
+        blocks.push(block);
+        Ok(())
+    })
+    .unwrap_or(Err(block))
+}
+
+/// Allocates `size` bytes for the RPC runtime (`MIDL_user_allocate`).
+///
+/// Returns null if the allocation fails or exceeds the quota set by [`with_quota()`].
 pub extern "system" fn midl_alloc(size: usize) -> *mut core::ffi::c_void {
-    let layout =
-        unsafe { Layout::from_size_align_unchecked(size + std::mem::size_of::<Layout>(), 1) };
-    let ptr = unsafe { std::alloc::alloc(layout) };
-    assert!(!ptr.is_null());
+    if size > isize::MAX as usize - HEADER_SIZE - ALIGN || !charge(size) {
+        return std::ptr::null_mut();
+    }
+
+    let class = size_class(size).filter(|_| POOLING.load(Ordering::Relaxed));
+    let layout = block_layout(class.map_or(size, |class| SIZE_CLASSES[class]));
+    let block = class
+        .and_then(take_pooled)
+        .or_else(|| NonNull::new(unsafe { std::alloc::alloc(layout) }));
+    let Some(block) = block else {
+        return std::ptr::null_mut();
+    };
 
     unsafe {
-        let layout_ptr = ptr.cast::<Layout>();
-        layout_ptr.write(layout);
+        block.as_ptr().cast::<Layout>().write(layout);
+        block.as_ptr().add(HEADER_SIZE) as *mut core::ffi::c_void
     }
-
-    unsafe { ptr.add(std::mem::size_of::<Layout>()) as *mut core::ffi::c_void }
 }
 
+/// Frees memory returned by [`midl_alloc()`] (`MIDL_user_free`).
 pub extern "system" fn midl_free(ptr: *mut core::ffi::c_void) {
-    let ptr = ptr as *mut u8;
-    let ptr = unsafe { ptr.sub(std::mem::size_of::<Layout>()) };
-    let layout_ptr = unsafe { *ptr.cast::<Layout>() };
-    unsafe { std::alloc::dealloc(ptr, layout_ptr) };
+    if ptr.is_null() {
+        return;
+    }
+
+    let block = unsafe { (ptr as *mut u8).sub(HEADER_SIZE) };
+    let layout = unsafe { block.cast::<Layout>().read() };
+    let block = unsafe { NonNull::new_unchecked(block) };
+
+    let class = SIZE_CLASSES
+        .iter()
+        .position(|&class| class + HEADER_SIZE == layout.size());
+    let block = match class {
+        Some(class) => match put_pooled(class, block) {
+            Ok(()) => return,
+            Err(block) => block,
+        },
+        None => block,
+    };
+    unsafe { std::alloc::dealloc(block.as_ptr(), layout) };
 }
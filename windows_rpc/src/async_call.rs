@@ -0,0 +1,232 @@
+//! Client side of asynchronous procedures, declared with `#[rpc(async)]`.
+//!
+//! The client stub of an asynchronous procedure sends the request and returns, and the
+//! reply is received later with `RpcAsyncCompleteCall`. An [`AsyncCall`] owns the
+//! `RPC_ASYNC_STATE` of such a call, whose notification wakes the thread waiting for the
+//! reply, or completes the future awaiting it.
+
+use std::cell::UnsafeCell;
+use std::ffi::c_void;
+use std::mem::ManuallyDrop;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Condvar, Mutex};
+
+use windows_sys::Win32::System::Rpc::{
+    CLIENT_CALL_RETURN, RPC_ASYNC_EVENT, RPC_ASYNC_STATE, RpcAsyncCancelCall, RpcAsyncCompleteCall,
+    RpcAsyncInitializeHandle, RpcCallComplete, RpcNotificationTypeCallback,
+};
+
+use crate::RpcError;
+use crate::executor::{CallFuture, pending};
+use crate::ndr::call_result;
+
+/// An asynchronous call, from the client stub sending its request to its completion.
+///
+/// The call is cancelled if it is dropped before it completes.
+pub struct AsyncCall {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    state: UnsafeCell<RPC_ASYNC_STATE>,
+    progress: Mutex<Progress>,
+    replied: Condvar,
+}
+
+// The state is used by the runtime, and by the client under the `progress` lock once the
+// request is sent
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+#[derive(Default)]
+struct Progress {
+    /// Set once the client stub sent the request
+    started: bool,
+    /// Set once the runtime notified the reply
+    replied: bool,
+    /// Set once `RpcAsyncCompleteCall` received the reply, or the call was cancelled
+    completed: bool,
+    /// Completes the future awaiting the call, once it is replied
+    on_reply: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl AsyncCall {
+    /// Initializes the `RPC_ASYNC_STATE` of a call.
+    ///
+    /// # Errors
+    ///
+    /// Returns the status of `RpcAsyncInitializeHandle()` if it fails.
+    pub fn new() -> Result<Self, RpcError> {
+        let shared = Arc::new(Shared {
+            state: UnsafeCell::new(unsafe { std::mem::zeroed() }),
+            progress: Mutex::default(),
+            replied: Condvar::new(),
+        });
+        let state = shared.state.get();
+        call_result(unsafe {
+            RpcAsyncInitializeHandle(state, std::mem::size_of::<RPC_ASYNC_STATE>() as u32)
+        } as u32)?;
+        unsafe {
+            (*state).NotificationType = RpcNotificationTypeCallback;
+            (*state).u.NotificationRoutine = Some(notify);
+            // The runtime's reference, released by the notification
+            (*state).UserInfo = Arc::into_raw(shared.clone()) as *mut c_void;
+        }
+        Ok(Self { shared })
+    }
+
+    /// Returns the `RPC_ASYNC_STATE` passed to the client stub.
+    pub fn state(&self) -> *mut c_void {
+        self.shared.state.get().cast()
+    }
+
+    /// Records that the client stub returned, with the `status` it wrote if the call
+    /// failed to start instead of raising an exception.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of a call that failed to start, which is then over.
+    pub fn start(&self, status: u32) -> Result<(), RpcError> {
+        let mut progress = self.shared.progress.lock().unwrap();
+        if let Err(error) = call_result(status) {
+            // A call failing to start is never notified
+            progress.completed = true;
+            drop(progress);
+            drop(unsafe { Arc::from_raw(Arc::as_ptr(&self.shared)) });
+            return Err(error);
+        }
+        progress.started = true;
+        Ok(())
+    }
+
+    /// Blocks until the server replied, and receives the reply into the outputs passed to
+    /// the client stub.
+    ///
+    /// # Errors
+    ///
+    /// Returns the status of the call if it failed.
+    pub fn wait(self) -> Result<CLIENT_CALL_RETURN, RpcError> {
+        let progress = self.shared.progress.lock().unwrap();
+        drop(
+            self.shared
+                .replied
+                .wait_while(progress, |progress| !progress.replied)
+                .unwrap(),
+        );
+        self.shared.complete()
+    }
+
+    /// Returns a future that completes with `finish` applied to the reply, which it
+    /// receives into the outputs passed to the client stub, or to the error of the call.
+    ///
+    /// `finish` owns the outputs, which it drops without reading them if the future is
+    /// dropped first.
+    ///
+    /// # Safety
+    ///
+    /// `finish` runs on a thread of the runtime, and must only own the outputs of the call,
+    /// whose pointers aren't `Send`.
+    pub unsafe fn into_future<R: Send + 'static>(
+        self,
+        finish: impl FnOnce(Result<CLIENT_CALL_RETURN, RpcError>) -> R + 'static,
+    ) -> CallFuture<R> {
+        struct AssumeSend<F>(F);
+        unsafe impl<F> Send for AssumeSend<F> {}
+
+        let finish = AssumeSend(finish);
+        let this = ManuallyDrop::new(self);
+        let shared = unsafe { std::ptr::read(&this.shared) };
+        let cancelled = shared.clone();
+        let (completion, future) = pending(Some(Box::new(move || cancelled.cancel())));
+        let completed = shared.clone();
+        let on_reply = move || {
+            // A panic converting the reply fails the future instead of unwinding into the
+            // runtime
+            let finish = finish;
+            let result =
+                std::panic::catch_unwind(AssertUnwindSafe(|| (finish.0)(completed.complete())));
+            if let Ok(result) = result {
+                completion.complete(result);
+            }
+        };
+
+        let mut progress = shared.progress.lock().unwrap();
+        if progress.replied || progress.completed {
+            drop(progress);
+            on_reply();
+        } else {
+            progress.on_reply = Some(Box::new(on_reply));
+        }
+        future
+    }
+}
+
+/// Returns the future of the call `start` starts, or a future that is already failed
+/// with its error.
+pub fn begin<T, E>(
+    start: impl FnOnce() -> Result<CallFuture<Result<T, E>>, E>,
+) -> CallFuture<Result<T, E>> {
+    start().unwrap_or_else(|error| CallFuture::ready(Err(error)))
+}
+
+impl Drop for AsyncCall {
+    fn drop(&mut self) {
+        self.shared.cancel();
+    }
+}
+
+impl Shared {
+    /// Receives the reply, unless the call was already completed or cancelled
+    fn complete(&self) -> Result<CLIENT_CALL_RETURN, RpcError> {
+        let mut progress = self.progress.lock().unwrap();
+        if progress.completed {
+            return Err(RpcError::from_status(
+                windows::Win32::System::Rpc::RPC_S_CALL_CANCELLED,
+            ));
+        }
+        progress.completed = true;
+        let mut reply = CLIENT_CALL_RETURN { Simple: 0 };
+        call_result(
+            unsafe { RpcAsyncCompleteCall(self.state.get(), (&raw mut reply).cast()) } as u32,
+        )?;
+        Ok(reply)
+    }
+
+    /// Cancels the call if it is still running, and drops the outputs of its future
+    fn cancel(&self) {
+        let mut progress = self.progress.lock().unwrap();
+        if progress.started && !progress.completed {
+            progress.completed = true;
+            let state = self.state.get();
+            unsafe {
+                RpcAsyncCancelCall(state, true.into());
+                // The runtime no longer writes to the outputs once the call is completed
+                RpcAsyncCompleteCall(state, std::ptr::null_mut());
+            }
+        }
+        let on_reply = progress.on_reply.take();
+        drop(progress);
+        drop(on_reply);
+    }
+}
+
+/// Notification routine of the calls, run on a thread of the runtime
+unsafe extern "system" fn notify(
+    state: *mut RPC_ASYNC_STATE,
+    _context: *mut c_void,
+    event: RPC_ASYNC_EVENT,
+) {
+    if event != RpcCallComplete {
+        return;
+    }
+    let shared = unsafe { Arc::from_raw((*state).UserInfo as *const Shared) };
+    let on_reply = {
+        let mut progress = shared.progress.lock().unwrap();
+        progress.replied = true;
+        progress.on_reply.take()
+    };
+    shared.replied.notify_all();
+    if let Some(on_reply) = on_reply {
+        on_reply();
+    }
+}
@@ -0,0 +1,146 @@
+//! Callbacks, the `#[rpc(callback)]` procedures a server calls on the client whose call it
+//! is handling.
+//!
+//! A client gives its implementation of the generated `{Interface}Callbacks` trait to
+//! `set_callbacks()`, and the runtime dispatches the callbacks to it on the thread blocked
+//! in the call that led to them. Handlers make the callbacks through the
+//! `{Interface}CallbacksClient` that `current()` returns for the call being handled.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Copier {
+//!     fn copy(files: u32) -> u32;
+//!     #[rpc(callback)]
+//!     fn progress(copied: u32) -> u32;
+//! }
+//!
+//! struct CopierImpl;
+//! impl CopierServerImpl for CopierImpl {
+//!     fn copy(files: u32) -> u32 {
+//!         let client = CopierCallbacksClient::current().unwrap();
+//!         for copied in 1..=files {
+//!             // The client cancels the copy by returning 0
+//!             if client.progress(copied).unwrap_or(0) == 0 {
+//!                 return copied;
+//!             }
+//!         }
+//!         files
+//!     }
+//! }
+//!
+//! struct Progress;
+//! impl CopierCallbacks for Progress {
+//!     fn progress(&self, copied: u32) -> u32 {
+//!         println!("{copied} files copied");
+//!         1
+//!     }
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! # let binding = windows_rpc::client_binding::ClientBinding::new(
+//! #     windows_rpc::ProtocolSequence::Alpc,
+//! #     "copier",
+//! # )?;
+//! let mut client = CopierClient::new(binding);
+//! client.set_callbacks(Progress);
+//! client.copy(10).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use windows::Win32::System::Rpc::{RPC_S_NO_CALL_ACTIVE, RPC_S_PROCNUM_OUT_OF_RANGE};
+
+use crate::{RpcError, TransferSyntax, dispatch};
+
+thread_local! {
+    /// Callbacks of the clients whose calls are in progress on this thread, innermost last,
+    /// by interface GUID, as pointers to the `Arc` the client holds
+    static CALLBACKS: RefCell<Vec<(u128, *const c_void)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Makes a client's callbacks current on this thread until dropped, for the duration of
+/// one of its calls.
+#[doc(hidden)]
+pub struct CallbackScope<'a>(PhantomData<&'a ()>);
+
+/// Makes `callbacks` the handler of the callbacks of the interface `interface` that arrive
+/// on this thread, until the scope is dropped. With `None`, they fault.
+#[doc(hidden)]
+pub fn enter<T: ?Sized>(interface: u128, callbacks: Option<&Arc<T>>) -> CallbackScope<'_> {
+    let callbacks = callbacks.map_or(std::ptr::null(), |callbacks| {
+        (callbacks as *const Arc<T>).cast()
+    });
+    CALLBACKS.with(|current| current.borrow_mut().push((interface, callbacks)));
+    CallbackScope(PhantomData)
+}
+
+impl Drop for CallbackScope<'_> {
+    fn drop(&mut self) {
+        CALLBACKS.with(|current| current.borrow_mut().pop());
+    }
+}
+
+/// Returns the handler of the callbacks of the interface `interface` for the innermost call
+/// in progress on this thread, faulting the callback with `RPC_S_PROCNUM_OUT_OF_RANGE` if
+/// the client has none.
+///
+/// # Safety
+///
+/// The handler must have been given to [`enter()`] as an `Arc<T>`.
+#[doc(hidden)]
+pub unsafe fn handler<T: ?Sized>(interface: u128) -> Arc<T> {
+    let callbacks = CALLBACKS.with(|current| {
+        current
+            .borrow()
+            .iter()
+            .rev()
+            .find(|(guid, _)| *guid == interface)
+            .map(|(_, callbacks)| *callbacks)
+    });
+    match callbacks {
+        // The scope, and the client holding the `Arc`, outlive the callback
+        Some(callbacks) if !callbacks.is_null() => unsafe { (*callbacks.cast::<Arc<T>>()).clone() },
+        _ => dispatch::raise_fault(RPC_S_PROCNUM_OUT_OF_RANGE.0),
+    }
+}
+
+/// The binding of the call being handled, which callbacks to its client are made on.
+#[doc(hidden)]
+pub struct CallBinding {
+    handle: *mut c_void,
+    syntax: TransferSyntax,
+}
+
+impl CallBinding {
+    /// Returns the binding of the call being handled on this thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_NO_CALL_ACTIVE` outside of a call.
+    pub fn current() -> Result<Self, RpcError> {
+        let no_call = || RpcError::new(RPC_S_NO_CALL_ACTIVE);
+        Ok(Self {
+            handle: dispatch::current_call().ok_or_else(no_call)?,
+            syntax: dispatch::current_syntax().ok_or_else(no_call)?,
+        })
+    }
+
+    /// Returns the binding handle of the call.
+    pub fn handle(&self) -> *mut c_void {
+        self.handle
+    }
+
+    /// Returns the transfer syntax of the call, which its callbacks are encoded with.
+    pub fn transfer_syntax(&self) -> TransferSyntax {
+        self.syntax
+    }
+}
@@ -3,14 +3,21 @@
 //! This module provides types for creating and managing RPC client bindings,
 //! which are used to connect to RPC servers.
 
-use std::{ffi::c_void, ptr};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::{ffi::c_void, mem::ManuallyDrop, ptr, time::Duration};
 
 use windows::{
-    Win32::System::Rpc::{RpcBindingFromStringBindingW, RpcStringBindingComposeW},
-    core::HSTRING,
+    Win32::System::Rpc::{
+        I_RpcFreeBuffer, I_RpcNegotiateTransferSyntax, RPC_C_OPT_CALL_TIMEOUT, RPC_FLAGS_VALID_BIT,
+        RPC_MESSAGE, RPC_S_OK, RpcBindingCopy, RpcBindingFree, RpcBindingFromStringBindingW,
+        RpcBindingInqObject, RpcBindingInqOption, RpcBindingSetObject, RpcBindingSetOption,
+        RpcBindingToStringBindingW, RpcMgmtIsServerListening, RpcStringBindingComposeW,
+        RpcStringFreeW,
+    },
+    core::{GUID, HSTRING, PCWSTR},
 };
 
-use crate::ProtocolSequence;
+use crate::{ProtocolSequence, RpcError, TransferSyntax};
 
 /// An RPC client binding handle.
 ///
@@ -38,8 +45,14 @@ use crate::ProtocolSequence;
 ///
 /// The binding handle must remain valid for the lifetime of any client using it.
 /// The generated client structs take ownership of the binding.
+///
+/// The binding owns its handle and frees it with `RpcBindingFree` when dropped. Clones
+/// are independent handles to the same server, copied with `RpcBindingCopy`, so each
+/// can be dropped or given to a client on its own.
 pub struct ClientBinding {
     handle: *mut c_void,
+    /// The string binding the handle was created from
+    string_binding: String,
 }
 
 impl ClientBinding {
@@ -67,24 +80,322 @@ impl ClientBinding {
     /// # }
     /// ```
     pub fn new(protocol: ProtocolSequence, endpoint: &str) -> windows::core::Result<Self> {
+        Self::compose(protocol, None, endpoint, None)
+    }
+
+    /// Creates a binding to the endpoint of a server on another machine.
+    ///
+    /// `server` is the host name or IP address of the server, e.g. `fileserver` or
+    /// `10.0.0.5` for a named pipe endpoint like `\pipe\lsarpc`. ALPC is local only, so
+    /// `ncalrpc` bindings reject any server other than the local machine.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the binding string cannot be composed or the binding handle
+    /// cannot be created from it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+    ///
+    /// # fn main() -> windows::core::Result<()> {
+    /// let binding = ClientBinding::with_server(ProtocolSequence::NamedPipe, "fileserver", r"\pipe\calculator")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_server(
+        protocol: ProtocolSequence,
+        server: &str,
+        endpoint: &str,
+    ) -> windows::core::Result<Self> {
+        Self::compose(protocol, Some(server), endpoint, None)
+    }
+
+    /// Creates an RPC over HTTP (`ncacn_http`) binding to `port` on `server`, reached
+    /// through the proxies in `options`, for servers behind firewalls that only let HTTP
+    /// through.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the binding string cannot be composed or the binding handle
+    /// cannot be created from it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use windows_rpc::client_binding::{ClientBinding, HttpOptions};
+    ///
+    /// # fn main() -> windows::core::Result<()> {
+    /// let options = HttpOptions {
+    ///     rpc_proxy: Some("gateway.contoso.com:443".to_string()),
+    ///     ..HttpOptions::default()
+    /// };
+    /// let binding = ClientBinding::with_http("calculator.corp.contoso.com", 593, &options)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_http(
+        server: &str,
+        port: u16,
+        options: &HttpOptions,
+    ) -> windows::core::Result<Self> {
+        Self::compose(
+            ProtocolSequence::Http,
+            Some(server),
+            &port.to_string(),
+            Some(&options.network_options()),
+        )
+    }
+
+    /// The VM ID of the host, for Hyper-V socket bindings from a guest.
+    pub const VM_ID_PARENT: u128 = 0xa42e7cda_d03f_480c_9cc2_a4de20abb878;
+    /// The VM ID of the local partition, for Hyper-V socket bindings to the same machine.
+    pub const VM_ID_LOOPBACK: u128 = 0xe0e16197_dd56_4a10_9195_5ee7a155a838;
+
+    /// Creates a Hyper-V socket (`ncacn_hvsocket`) binding to the service `service_id` in
+    /// the VM `vm_id`, for RPC between a Hyper-V host and its guests.
+    ///
+    /// Hosts address a guest by its VM ID, and guests address the host with
+    /// [`VM_ID_PARENT`](Self::VM_ID_PARENT). Servers register the service ID, formatted as
+    /// a GUID, as their endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the binding string cannot be composed or the binding handle
+    /// cannot be created from it.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use windows_rpc::client_binding::ClientBinding;
+    ///
+    /// # fn main() -> windows::core::Result<()> {
+    /// const SERVICE_ID: u128 = 0x3c1b7e52_94d8_4f2a_b6e0_71d5a9c48f13;
+    /// let binding = ClientBinding::with_vm(ClientBinding::VM_ID_PARENT, SERVICE_ID)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_vm(vm_id: u128, service_id: u128) -> windows::core::Result<Self> {
+        Self::compose(
+            ProtocolSequence::HyperVSocket,
+            Some(&format!("{:?}", GUID::from_u128(vm_id))),
+            &format!("{:?}", GUID::from_u128(service_id)),
+            None,
+        )
+    }
+
+    fn compose(
+        protocol: ProtocolSequence,
+        server: Option<&str>,
+        endpoint: &str,
+        options: Option<&str>,
+    ) -> windows::core::Result<Self> {
+        let server = server.map(HSTRING::from);
+        let options = options.map(HSTRING::from);
         let mut string_binding = windows::core::PWSTR::null();
         unsafe {
             RpcStringBindingComposeW(
                 // TODO: pass obj uuid, could replace the endpoint/network addr
                 None,
                 protocol.to_pcwstr(),
-                None,
+                server
+                    .as_ref()
+                    .map_or(PCWSTR::null(), |server| PCWSTR(server.as_ptr())),
                 &HSTRING::from(endpoint),
-                None,
+                options
+                    .as_ref()
+                    .map_or(PCWSTR::null(), |options| PCWSTR(options.as_ptr())),
                 Some(&raw mut string_binding),
             )
         }
         .ok()?;
 
+        let composed = unsafe { string_binding.to_string() };
+        let _ = unsafe { RpcStringFreeW(&raw mut string_binding) };
+        Self::from_string_binding(&composed?)
+    }
+
+    /// Creates a binding from a complete string binding, e.g. `ncacn_ip_tcp:host[port]`
+    /// or `ncalrpc:[calculator_endpoint]`, as found in configuration files or printed by
+    /// other RPC tools.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_INVALID_STRING_BINDING` if the string binding is malformed,
+    /// `RPC_S_PROTSEQ_NOT_SUPPORTED` if its protocol sequence is not supported, or another
+    /// error if the binding handle cannot be created from it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use windows_rpc::client_binding::ClientBinding;
+    ///
+    /// # fn main() -> windows::core::Result<()> {
+    /// let binding = ClientBinding::from_string_binding("ncalrpc:[calculator_endpoint]")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_string_binding(string_binding: &str) -> windows::core::Result<Self> {
         let mut handle: *mut core::ffi::c_void = ptr::null_mut();
-        unsafe { RpcBindingFromStringBindingW(string_binding, &raw mut handle) }.ok()?;
+        unsafe { RpcBindingFromStringBindingW(&HSTRING::from(string_binding), &raw mut handle) }
+            .ok()?;
+
+        Ok(Self {
+            handle,
+            string_binding: string_binding.to_string(),
+        })
+    }
+
+    /// Takes ownership of a raw RPC binding handle, e.g. one created by another library,
+    /// so generated clients can make calls over it. The handle is freed when the binding
+    /// is dropped.
+    ///
+    /// [`string_binding()`](Self::string_binding) returns the handle's string binding as
+    /// rendered by the runtime, or an empty string if it cannot be rendered.
+    ///
+    /// # Safety
+    ///
+    /// `handle` must be a valid client binding handle that is not freed or used as owned
+    /// elsewhere, as with a handle returned by [`into_raw()`](Self::into_raw).
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+    ///
+    /// # fn main() -> windows::core::Result<()> {
+    /// let handle = ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")?.into_raw();
+    /// // ... pass the handle through code that only knows raw handles ...
+    /// let binding = unsafe { ClientBinding::from_raw(handle) };
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub unsafe fn from_raw(handle: *mut c_void) -> Self {
+        let mut binding = Self {
+            handle,
+            string_binding: String::new(),
+        };
+        binding.string_binding = binding.to_string_binding().unwrap_or_default();
+        binding
+    }
+
+    /// Releases ownership of the raw RPC binding handle, which is no longer freed on drop.
+    ///
+    /// The caller is responsible for freeing the handle with `RpcBindingFree`, or for
+    /// passing it back to [`from_raw()`](Self::from_raw).
+    pub fn into_raw(self) -> *mut c_void {
+        let mut binding = ManuallyDrop::new(self);
+        drop(std::mem::take(&mut binding.string_binding));
+        binding.handle
+    }
 
-        Ok(Self { handle })
+    /// Renders the binding back to a string binding with `RpcBindingToStringBindingW`.
+    ///
+    /// Unlike [`string_binding()`](Self::string_binding), this is the runtime's current
+    /// view of the binding, which includes the endpoint once it was resolved, so it can
+    /// be stored and passed to [`from_string_binding()`](Self::from_string_binding) later.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime fails to render the binding.
+    pub fn to_string_binding(&self) -> windows::core::Result<String> {
+        let mut string_binding = windows::core::PWSTR::null();
+        unsafe { RpcBindingToStringBindingW(self.handle, &raw mut string_binding) }.ok()?;
+
+        let result = unsafe { string_binding.to_string() };
+        let _ = unsafe { RpcStringFreeW(&raw mut string_binding) };
+        Ok(result?)
+    }
+
+    /// Creates an independent copy of the binding to the same server.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the binding cannot be copied.
+    pub fn try_clone(&self) -> windows::core::Result<Self> {
+        let mut handle: *mut c_void = ptr::null_mut();
+        unsafe { RpcBindingCopy(self.handle, &raw mut handle) }.ok()?;
+        Ok(Self {
+            handle,
+            string_binding: self.string_binding.clone(),
+        })
+    }
+
+    /// Returns the string binding the binding was created from, e.g.
+    /// `ncacn_np:fileserver[\pipe\calculator]`, for logging and debugging.
+    pub fn string_binding(&self) -> &str {
+        &self.string_binding
+    }
+
+    /// Establishes the connection to the server now, instead of on the first call.
+    ///
+    /// Contacts the server with a management call, which also authenticates the
+    /// association when the binding carries authentication information, so that
+    /// connectivity problems surface at startup. Gives up after `timeout`; calls made
+    /// afterwards keep the binding's previous timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_SERVER_UNAVAILABLE` if the server cannot be reached,
+    /// `RPC_S_CALL_CANCELLED` if it does not respond within `timeout`, and
+    /// `RPC_S_NOT_LISTENING` if the server is not listening for calls.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use std::time::Duration;
+    /// use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+    ///
+    /// # fn main() -> Result<(), windows_rpc::RpcError> {
+    /// let binding = ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")?;
+    /// binding.bind(Duration::from_secs(5))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bind(&self, timeout: Duration) -> Result<(), RpcError> {
+        let mut previous_timeout = 0;
+        unsafe {
+            RpcBindingInqOption(
+                self.handle,
+                RPC_C_OPT_CALL_TIMEOUT,
+                &raw mut previous_timeout,
+            )
+        }
+        .ok()?;
+        // A timeout of 0 means no timeout
+        let timeout_ms = timeout.as_millis().clamp(1, u32::MAX as u128) as usize;
+        unsafe { RpcBindingSetOption(self.handle, RPC_C_OPT_CALL_TIMEOUT, timeout_ms) }.ok()?;
+
+        let status = unsafe { RpcMgmtIsServerListening(Some(self.handle)) };
+        let error = (status != RPC_S_OK).then(|| RpcError::from_status(status));
+
+        unsafe { RpcBindingSetOption(self.handle, RPC_C_OPT_CALL_TIMEOUT, previous_timeout) }
+            .ok()?;
+        error.map_or(Ok(()), Err)
+    }
+
+    /// Makes the calls of the binding to the object `object`, which the server maps to
+    /// the implementation of the interface that handles them. The nil UUID, the default,
+    /// calls the server's default implementation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime fails to set the object UUID.
+    pub fn set_object(&mut self, object: u128) -> windows::core::Result<()> {
+        let object = GUID::from_u128(object);
+        unsafe { RpcBindingSetObject(self.handle, &object) }.ok()
+    }
+
+    /// Returns the object UUID the calls of the binding are made to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime fails to query the object UUID.
+    pub fn object(&self) -> windows::core::Result<u128> {
+        let mut object = GUID::zeroed();
+        unsafe { RpcBindingInqObject(self.handle, &mut object) }.ok()?;
+        Ok(object.to_u128())
     }
 
     /// Returns the raw RPC binding handle.
@@ -95,3 +406,184 @@ impl ClientBinding {
         self.handle
     }
 }
+
+impl Clone for ClientBinding {
+    /// Copies the binding with [`try_clone()`](Self::try_clone).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the runtime fails to copy the binding, which only happens when it is out
+    /// of memory.
+    fn clone(&self) -> Self {
+        self.try_clone().expect("Failed to copy the binding handle")
+    }
+}
+
+impl Drop for ClientBinding {
+    fn drop(&mut self) {
+        // Best effort cleanup
+        let _ = unsafe { RpcBindingFree(&raw mut self.handle) };
+    }
+}
+
+/// Proxies an RPC over HTTP binding goes through, see [`ClientBinding::with_http()`].
+///
+/// Proxies are given as `host:port`. Without an RPC proxy, the server itself acts as the
+/// RPC proxy on port 80.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpOptions {
+    /// The RPC proxy (IIS with the RPC over HTTP proxy) that forwards calls to the server.
+    pub rpc_proxy: Option<String>,
+    /// An HTTP proxy in front of the RPC proxy.
+    pub http_proxy: Option<String>,
+}
+
+impl HttpOptions {
+    /// The network options of the string binding, e.g. `RpcProxy=gateway:443`
+    fn network_options(&self) -> String {
+        let proxies = [
+            ("RpcProxy", &self.rpc_proxy),
+            ("HttpProxy", &self.http_proxy),
+        ];
+        proxies
+            .iter()
+            .filter_map(|(name, proxy)| Some(format!("{name}={}", proxy.as_ref()?)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// A user-defined binding handle, like a MIDL generic handle declared in an ACF.
+///
+/// Interfaces declared with `generic_handle(Type)` take a `&Type` as the first parameter
+/// of every client method. The binding is produced by [`bind()`](Self::bind) when the
+/// call is made and released by [`unbind()`](Self::unbind) once it completes, so
+/// resolving the endpoint can be deferred until it is actually needed.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use windows_rpc::client_binding::{ClientBinding, GenericHandle};
+/// use windows_rpc::{ProtocolSequence, rpc_interface};
+///
+/// struct Tenant {
+///     name: String,
+/// }
+///
+/// impl GenericHandle for Tenant {
+///     fn bind(&self) -> windows::core::Result<ClientBinding> {
+///         ClientBinding::new(ProtocolSequence::Alpc, &format!("tenant_{}", self.name))
+///     }
+/// }
+///
+/// #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0), generic_handle(Tenant))]
+/// trait Calculator {
+///     fn add(a: i32, b: i32) -> i32;
+/// }
+///
+/// let client = CalculatorClient::new();
+/// let tenant = Tenant { name: "contoso".to_string() };
+/// assert_eq!(client.add(&tenant, 1, 2).unwrap(), 3);
+/// ```
+pub trait GenericHandle {
+    /// Produces the binding for a call made with this handle.
+    ///
+    /// # Errors
+    ///
+    /// A failure fails the call, as it cannot be made without a binding.
+    fn bind(&self) -> windows::core::Result<ClientBinding>;
+
+    /// Releases the binding once the call completes. The default frees it.
+    fn unbind(&self, binding: ClientBinding) {
+        drop(binding);
+    }
+}
+
+/// A binding produced from a [`GenericHandle`] for the duration of a call.
+#[doc(hidden)]
+pub struct GenericBinding<'a, H: GenericHandle + ?Sized> {
+    handle: &'a H,
+    binding: Option<ClientBinding>,
+}
+
+impl<'a, H: GenericHandle + ?Sized> GenericBinding<'a, H> {
+    /// Binds `handle`, failing the call if it fails.
+    pub fn bind(handle: &'a H) -> Result<Self, RpcError> {
+        let binding = handle.bind()?;
+        Ok(Self {
+            handle,
+            binding: Some(binding),
+        })
+    }
+
+    pub fn handle(&self) -> *mut c_void {
+        // Only taken when dropped
+        self.binding.as_ref().unwrap().handle()
+    }
+}
+
+impl<H: GenericHandle + ?Sized> Drop for GenericBinding<'_, H> {
+    fn drop(&mut self) {
+        if let Some(binding) = self.binding.take() {
+            self.handle.unbind(binding);
+        }
+    }
+}
+
+/// The transfer syntax the RPC runtime negotiated for the calls of a client that offers
+/// both, recorded after its first successful call.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct NegotiatedSyntax(AtomicU8);
+
+impl NegotiatedSyntax {
+    const UNKNOWN: u8 = 0;
+    const NDR20: u8 = 1;
+    const NDR64: u8 = 2;
+
+    const NDR64_GUID: u128 = 0x71710533_beba_4937_8319_b5dbef9ccc36;
+
+    pub const fn new() -> Self {
+        Self(AtomicU8::new(Self::UNKNOWN))
+    }
+
+    /// Returns the recorded syntax, `None` before the first successful call.
+    pub fn get(&self) -> Option<TransferSyntax> {
+        match self.0.load(Ordering::Relaxed) {
+            Self::NDR20 => Some(TransferSyntax::Ndr20),
+            Self::NDR64 => Some(TransferSyntax::Ndr64),
+            _ => None,
+        }
+    }
+
+    /// Records the syntax of the association a call of `interface` made over `binding`,
+    /// once. The runtime negotiates the syntax when the association is established, so
+    /// asking it again after the call returns the syntax the call used.
+    pub fn record(&self, binding: *mut c_void, interface: *const c_void) {
+        if self.0.load(Ordering::Relaxed) != Self::UNKNOWN {
+            return;
+        }
+
+        let mut message = RPC_MESSAGE {
+            Handle: binding,
+            ProcNum: RPC_FLAGS_VALID_BIT,
+            RpcInterfaceInformation: interface.cast_mut(),
+            ..Default::default()
+        };
+        if unsafe { I_RpcNegotiateTransferSyntax(&raw mut message) } != RPC_S_OK {
+            return;
+        }
+        let syntax = unsafe { message.TransferSyntax.as_ref() }.map(|syntax| {
+            if syntax.SyntaxGUID.to_u128() == Self::NDR64_GUID {
+                Self::NDR64
+            } else {
+                Self::NDR20
+            }
+        });
+        // Releases the call the negotiation started, which never got a buffer
+        let _ = unsafe { I_RpcFreeBuffer(&raw mut message) };
+        if let Some(syntax) = syntax {
+            self.0.store(syntax, Ordering::Relaxed);
+        }
+    }
+}
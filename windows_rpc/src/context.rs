@@ -0,0 +1,272 @@
+//! Context handles, which keep per-client state on the server between calls.
+//!
+//! A method returning a [`ContextHandle`] opens a context: the server's handler returns a
+//! [`ServerContext`] holding its state, and the client gets a handle to it, which later
+//! calls pass as `&ContextHandle`. A `#[in_out] &mut ContextHandle` parameter lets the
+//! handler replace or close the context, which closes the client's handle too.
+//!
+//! The runtime runs a context down when its client goes away without closing it, e.g. when
+//! the client process exits, calling the callback given to
+//! [`ServerContext::with_rundown()`] with the state, so per-client resources are cleaned up.
+
+use std::any::Any;
+use std::ffi::c_void;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::panic::AssertUnwindSafe;
+
+use windows::Win32::Foundation::RPC_X_SS_IN_NULL_CONTEXT;
+use windows::Win32::System::Rpc::RPC_STATUS;
+use windows_sys::Win32::System::Rpc::{NDR_RUNDOWN, RpcSsDestroyClientContext};
+
+use crate::RpcError;
+
+/// A client's handle to a context opened by a server.
+///
+/// A closed handle, e.g. the default one, fails the calls it is passed to. Dropping an open
+/// handle frees it without telling the server, which runs the context down once the client's
+/// connection closes.
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct ContextHandle(*mut c_void);
+
+// The handle is only used by the runtime, which synchronizes the calls using it
+unsafe impl Send for ContextHandle {}
+unsafe impl Sync for ContextHandle {}
+
+impl Default for ContextHandle {
+    fn default() -> Self {
+        Self(std::ptr::null_mut())
+    }
+}
+
+impl ContextHandle {
+    /// Whether the handle is closed, either never opened or closed by the server.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_null()
+    }
+
+    /// Checks that the handle is open, before passing it to a call.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_X_SS_IN_NULL_CONTEXT` if the handle is closed.
+    pub fn check_open(&self) -> Result<(), RpcError> {
+        if self.is_closed() {
+            return Err(RpcError::new(RPC_STATUS(RPC_X_SS_IN_NULL_CONTEXT)));
+        }
+        Ok(())
+    }
+
+    /// Returns the `NDR_CCONTEXT` of the handle.
+    pub fn as_raw(&self) -> *mut c_void {
+        self.0
+    }
+
+    /// Takes ownership of an `NDR_CCONTEXT`, e.g. one opened by a MIDL-generated stub.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be null or a context handle that the caller owns.
+    pub unsafe fn from_raw(raw: *mut c_void) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the `NDR_CCONTEXT` of the handle, which the caller then owns.
+    pub fn into_raw(self) -> *mut c_void {
+        ManuallyDrop::new(self).0
+    }
+}
+
+impl Drop for ContextHandle {
+    fn drop(&mut self) {
+        if !self.is_closed() {
+            unsafe { RpcSsDestroyClientContext((&raw const self.0).cast()) };
+        }
+    }
+}
+
+/// The state a server keeps for a context handle, or a closed context.
+///
+/// The state is dropped when the context is closed or replaced by a handler, and handed
+/// to the rundown callback, if any, when the client goes away without closing it.
+#[derive(Default)]
+pub struct ServerContext(Option<Box<Context>>);
+
+type Value = Box<dyn Any + Send + Sync>;
+
+struct Context {
+    value: Value,
+    on_rundown: Option<Box<dyn FnOnce(Value) + Send>>,
+}
+
+// The rundown callback is only used by value, once the context is run down
+unsafe impl Sync for Context {}
+
+impl ServerContext {
+    /// Opens a context holding `value`, which is dropped if the context is run down.
+    pub fn new<T: Any + Send + Sync>(value: T) -> Self {
+        Self(Some(Box::new(Context {
+            value: Box::new(value),
+            on_rundown: None,
+        })))
+    }
+
+    /// Opens a context holding `value`, which is passed to `on_rundown` if the client goes
+    /// away without closing the context.
+    ///
+    /// `on_rundown` runs on a thread of the runtime, and isn't called when the context is
+    /// closed or replaced by a handler.
+    pub fn with_rundown<T: Any + Send + Sync>(
+        value: T,
+        on_rundown: impl FnOnce(T) + Send + 'static,
+    ) -> Self {
+        let on_rundown = move |value: Value| {
+            if let Ok(value) = value.downcast::<T>() {
+                on_rundown(*value);
+            }
+        };
+        Self(Some(Box::new(Context {
+            value: Box::new(value),
+            on_rundown: Some(Box::new(on_rundown)),
+        })))
+    }
+
+    /// A closed context, returned by handlers that open none, which closes the client's
+    /// handle when written to a `#[in_out]` parameter.
+    pub fn closed() -> Self {
+        Self(None)
+    }
+
+    /// Whether the context is closed.
+    pub fn is_closed(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns the state of the context, if it is open and holds a `T`.
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.0.as_ref()?.value.downcast_ref()
+    }
+
+    /// Returns the state of the context mutably, if it is open and holds a `T`.
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.0.as_mut()?.value.downcast_mut()
+    }
+
+    /// Closes the context, dropping its state.
+    pub fn close(&mut self) {
+        self.0 = None;
+    }
+
+    /// Closes the context, returning its state if it holds a `T`.
+    pub fn take<T: Any>(&mut self) -> Option<T> {
+        let context = self.0.take()?;
+        context.value.downcast().ok().map(|value| *value)
+    }
+
+    /// Returns the server context a stub writes to the runtime, null when closed.
+    #[doc(hidden)]
+    pub fn into_raw(self) -> *mut c_void {
+        self.0.map_or(std::ptr::null_mut(), |context| {
+            Box::into_raw(context).cast()
+        })
+    }
+
+    /// Takes ownership of a server context the runtime held.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be null or returned by [`into_raw()`](Self::into_raw), and not used again.
+    #[doc(hidden)]
+    pub unsafe fn from_raw(raw: *mut c_void) -> Self {
+        Self((!raw.is_null()).then(|| unsafe { Box::from_raw(raw.cast()) }))
+    }
+
+    /// Borrows a server context the runtime holds, for an `[in]` context handle parameter.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be null or returned by [`into_raw()`](Self::into_raw), and stay held by
+    /// the runtime while it is borrowed.
+    #[doc(hidden)]
+    pub unsafe fn borrow_raw(raw: *mut c_void) -> ManuallyDrop<Self> {
+        ManuallyDrop::new(unsafe { Self::from_raw(raw) })
+    }
+
+    /// Hands the state to the rundown callback, or drops it
+    fn run_down(self) {
+        if let Some(context) = self.0 {
+            let Context { value, on_rundown } = *context;
+            if let Some(on_rundown) = on_rundown {
+                on_rundown(value);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ServerContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerContext")
+            .field("closed", &self.is_closed())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The server context of a `#[in_out]` context handle parameter, written back to the
+/// runtime once the handler returned, including when it fails.
+#[doc(hidden)]
+pub struct ContextSlot {
+    slot: *mut *mut c_void,
+    context: ManuallyDrop<ServerContext>,
+}
+
+// The slot belongs to the call, which the runtime doesn't touch until the handler returned
+unsafe impl Send for ContextSlot {}
+
+impl ContextSlot {
+    /// Takes the server context out of the runtime's `slot`.
+    ///
+    /// # Safety
+    ///
+    /// `slot` must point to a server context the runtime holds for the call.
+    pub unsafe fn new(slot: *mut *mut c_void) -> Self {
+        Self {
+            slot,
+            context: ManuallyDrop::new(unsafe { ServerContext::from_raw(*slot) }),
+        }
+    }
+}
+
+impl Deref for ContextSlot {
+    type Target = ServerContext;
+
+    fn deref(&self) -> &ServerContext {
+        &self.context
+    }
+}
+
+impl DerefMut for ContextSlot {
+    fn deref_mut(&mut self) -> &mut ServerContext {
+        &mut self.context
+    }
+}
+
+impl Drop for ContextSlot {
+    fn drop(&mut self) {
+        let context = unsafe { ManuallyDrop::take(&mut self.context) };
+        unsafe { *self.slot = context.into_raw() };
+    }
+}
+
+/// Rundown routine of the context handles, run by the runtime for the contexts of a client
+/// that went away
+unsafe extern "system" fn rundown(context: *mut c_void) {
+    let context = unsafe { ServerContext::from_raw(context) };
+    // A panicking callback must not unwind into the runtime
+    let _ = std::panic::catch_unwind(AssertUnwindSafe(move || context.run_down()));
+}
+
+/// The rundown routines of the servers' stub descriptors, all context handles using the
+/// first one
+#[doc(hidden)]
+pub static RUNDOWN_ROUTINES: [NDR_RUNDOWN; 1] = [Some(rundown)];
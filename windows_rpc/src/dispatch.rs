@@ -0,0 +1,444 @@
+//! Per-server dispatch state used by the generated server code.
+//!
+//! The generated wrapper functions are stateless, so the state of a server instance
+//! is looked up by its `RPC_SERVER_INTERFACE` address. The dispatch functions placed
+//! in the interface's dispatch tables make that state current for the duration of the
+//! call before handing the message to the NDR engine.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+
+use windows::Win32::System::Rpc::{RPC_S_ACCESS_DENIED, RPC_S_CALL_FAILED, RPC_STATUS};
+use windows_sys::Win32::System::Rpc::{
+    RPC_ASYNC_STATE, RPC_MESSAGE, RpcAsyncAbortCall, RpcAsyncCompleteCall,
+};
+
+use crate::TransferSyntax;
+use crate::alloc::QuotaScope;
+use crate::executor::{CallExecutor, Job, JobDropped, run_blocking};
+use crate::security::{Authorization, MethodAuthorizer};
+use crate::server;
+
+/// Runtime configuration of a single server instance.
+#[derive(Default)]
+pub struct ServerState {
+    executor: RwLock<Option<Arc<dyn CallExecutor>>>,
+    memory_quota: RwLock<Option<usize>>,
+    instance: RwLock<Option<Arc<dyn Any + Send + Sync>>>,
+    authorizer: RwLock<Option<Arc<dyn MethodAuthorizer>>>,
+    // Zero means the default, `RPC_S_CALL_FAILED`
+    panic_fault: AtomicI32,
+}
+
+impl ServerState {
+    /// Limits the bytes the stubs may allocate while dispatching a single call.
+    ///
+    /// `None` removes the limit. A call exceeding the quota is faulted.
+    pub fn set_memory_quota(&self, quota: Option<usize>) {
+        *self.memory_quota.write().unwrap() = quota;
+    }
+
+    /// Returns the per-call memory quota, if any.
+    pub fn memory_quota(&self) -> Option<usize> {
+        *self.memory_quota.read().unwrap()
+    }
+
+    /// Shares `instance` with the handlers, see [`server::instance()`](crate::server::instance).
+    pub fn set_instance(&self, instance: Option<Arc<dyn Any + Send + Sync>>) {
+        *self.instance.write().unwrap() = instance;
+    }
+
+    /// Returns the shared implementation object, if it is a `T`.
+    pub fn instance<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        let instance = self.instance.read().unwrap().clone()?;
+        instance.downcast().ok()
+    }
+
+    /// Sets the status that faults calls whose handler panics.
+    pub fn set_panic_fault(&self, status: RPC_STATUS) {
+        self.panic_fault.store(status.0, Ordering::Relaxed);
+    }
+
+    /// Returns the status that faults calls whose handler panics.
+    pub fn panic_fault(&self) -> RPC_STATUS {
+        match self.panic_fault.load(Ordering::Relaxed) {
+            0 => RPC_S_CALL_FAILED,
+            status => RPC_STATUS(status),
+        }
+    }
+
+    /// Sets the authorizer consulted before each call is dispatched.
+    pub fn set_authorizer(&self, authorizer: Option<Arc<dyn MethodAuthorizer>>) {
+        *self.authorizer.write().unwrap() = authorizer;
+    }
+
+    fn authorizer(&self) -> Option<Arc<dyn MethodAuthorizer>> {
+        self.authorizer.read().unwrap().clone()
+    }
+
+    pub fn set_executor(&self, executor: Option<Arc<dyn CallExecutor>>) {
+        *self.executor.write().unwrap() = executor;
+    }
+
+    fn executor(&self) -> Option<Arc<dyn CallExecutor>> {
+        self.executor.read().unwrap().clone()
+    }
+}
+
+// The NDR engine reports faults by raising SEH exceptions that unwind through the
+// dispatch functions, so they are imported with an unwinding ABI.
+#[link(name = "rpcrt4.dll", kind = "raw-dylib", modifiers = "+verbatim")]
+unsafe extern "system-unwind" {
+    fn NdrServerCall2(message: *mut RPC_MESSAGE);
+    fn NdrServerCallAll(message: *mut RPC_MESSAGE);
+    fn NdrAsyncServerCall(message: *mut RPC_MESSAGE);
+    fn Ndr64AsyncServerCallAll(message: *mut RPC_MESSAGE);
+    fn RpcRaiseException(exception: i32) -> !;
+}
+
+static SERVERS: LazyLock<RwLock<HashMap<usize, Arc<ServerState>>>> =
+    LazyLock::new(Default::default);
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<ServerState>>> = const { RefCell::new(None) };
+    /// Binding handle of the call being handled, null outside of calls
+    static CALL: Cell<*mut c_void> = const { Cell::new(std::ptr::null_mut()) };
+    /// Transfer syntax of the call being handled, `None` outside of calls
+    static SYNTAX: Cell<Option<TransferSyntax>> = const { Cell::new(None) };
+    /// Set while the server routine of an asynchronous procedure runs, whose faults abort
+    /// the call instead of raising an exception
+    static ASYNC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Associates `state` with the interface at `interface`, or with the manager entry-point
+/// vector at `interface` for servers registered as one of several managers.
+pub fn register_state(interface: *const c_void, state: Arc<ServerState>) {
+    SERVERS.write().unwrap().insert(interface as usize, state);
+}
+
+/// Removes the state associated with the interface at `interface`.
+pub fn unregister_state(interface: *const c_void) {
+    SERVERS.write().unwrap().remove(&(interface as usize));
+}
+
+/// Makes a server's state current on this thread until dropped.
+struct CallScope {
+    previous: Option<Arc<ServerState>>,
+    previous_call: *mut c_void,
+    previous_syntax: Option<TransferSyntax>,
+    _quota: QuotaScope,
+}
+
+impl CallScope {
+    fn enter(message: *const RPC_MESSAGE, syntax: TransferSyntax) -> Self {
+        let (epv, interface, call) = unsafe {
+            let message = &*message;
+            (
+                message.ManagerEpv,
+                message.RpcInterfaceInformation,
+                message.Handle,
+            )
+        };
+        // The runtime passes the interface of the first registration to every manager of
+        // an interface, so the manager's entry-point vector identifies the server
+        let servers = SERVERS.read().unwrap();
+        let state = servers
+            .get(&(epv as usize))
+            .or_else(|| servers.get(&(interface as usize)))
+            .cloned();
+        drop(servers);
+        let quota = QuotaScope::enter(state.as_ref().and_then(|state| state.memory_quota()));
+        let previous = CURRENT.with(|current| current.replace(state));
+        Self {
+            previous,
+            previous_call: CALL.replace(call),
+            previous_syntax: SYNTAX.replace(Some(syntax)),
+            _quota: quota,
+        }
+    }
+}
+
+impl Drop for CallScope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.previous.take());
+        CALL.set(self.previous_call);
+        SYNTAX.set(self.previous_syntax);
+    }
+}
+
+/// Returns the state of the server whose call is being handled on this thread.
+pub(crate) fn current() -> Option<Arc<ServerState>> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Returns the binding handle of the call being handled on this thread.
+pub(crate) fn current_call() -> Option<*mut c_void> {
+    Some(CALL.get()).filter(|call| !call.is_null())
+}
+
+/// Returns the transfer syntax of the call being handled on this thread.
+pub(crate) fn current_syntax() -> Option<TransferSyntax> {
+    SYNTAX.get()
+}
+
+/// Faults the call in `message` with `RPC_S_ACCESS_DENIED` if the current server's
+/// authorizer denies it.
+fn authorize(message: *const RPC_MESSAGE) {
+    let Some(authorizer) = current().and_then(|state| state.authorizer()) else {
+        return;
+    };
+    let opnum = unsafe { (*message).ProcNum };
+    let allowed = server::call_context().is_ok_and(|context| {
+        std::panic::catch_unwind(AssertUnwindSafe(|| authorizer.authorize(opnum, &context)))
+            .is_ok_and(|authorization| authorization == Authorization::Allow)
+    });
+    if !allowed {
+        raise_fault(RPC_S_ACCESS_DENIED.0);
+    }
+}
+
+/// NDR 2.0 dispatch function.
+///
+/// # Safety
+///
+/// Must only be called by the RPC runtime with a valid `RPC_MESSAGE`.
+pub unsafe extern "system-unwind" fn ndr_dispatch(message: *mut RPC_MESSAGE) {
+    let _scope = CallScope::enter(message, TransferSyntax::Ndr20);
+    authorize(message);
+    unsafe { NdrServerCall2(message) }
+}
+
+/// NDR64 dispatch function.
+///
+/// # Safety
+///
+/// Must only be called by the RPC runtime with a valid `RPC_MESSAGE`.
+pub unsafe extern "system-unwind" fn ndr64_dispatch(message: *mut RPC_MESSAGE) {
+    let _scope = CallScope::enter(message, TransferSyntax::Ndr64);
+    authorize(message);
+    unsafe { NdrServerCallAll(message) }
+}
+
+/// NDR 2.0 dispatch function of asynchronous procedures.
+///
+/// # Safety
+///
+/// Must only be called by the RPC runtime with a valid `RPC_MESSAGE`.
+pub unsafe extern "system-unwind" fn ndr_async_dispatch(message: *mut RPC_MESSAGE) {
+    let _scope = CallScope::enter(message, TransferSyntax::Ndr20);
+    authorize(message);
+    unsafe { NdrAsyncServerCall(message) }
+}
+
+/// NDR64 dispatch function of asynchronous procedures.
+///
+/// # Safety
+///
+/// Must only be called by the RPC runtime with a valid `RPC_MESSAGE`.
+pub unsafe extern "system-unwind" fn ndr64_async_dispatch(message: *mut RPC_MESSAGE) {
+    let _scope = CallScope::enter(message, TransferSyntax::Ndr64);
+    authorize(message);
+    unsafe { Ndr64AsyncServerCallAll(message) }
+}
+
+/// Faults the call being dispatched with `status`, which the client receives as the
+/// failure of the call.
+///
+/// Must only be called from the generated wrapper functions, which the exception unwinds
+/// through to the NDR engine, or from the server routines of asynchronous procedures,
+/// whose call is aborted instead.
+pub fn raise_fault(status: i32) -> ! {
+    if ASYNC.get() {
+        std::panic::resume_unwind(Box::new(AsyncFault(status)));
+    }
+    raise_exception(status)
+}
+
+/// Raises `status` as the SEH exception the NDR engine catches, e.g. from the pipe
+/// procedures a client stub calls, to abort its call.
+pub(crate) fn raise_exception(status: i32) -> ! {
+    unsafe { RpcRaiseException(status) }
+}
+
+/// Panic payload of a fault raised by the server routine of an asynchronous procedure
+struct AsyncFault(i32);
+
+/// Lets [`invoke()`] move a handler taking `NdrStruct` parameters to the executor's
+/// thread, although their pointer fields aren't `Send`.
+///
+/// # Safety
+///
+/// The handler must only borrow data of the call, e.g. structs pointing into its buffer,
+/// which outlives the handler as `invoke()` waits for it.
+pub unsafe fn assume_send<R>(handler: impl FnOnce() -> R) -> impl FnOnce() -> R + Send {
+    struct AssumeSend<F>(F);
+    unsafe impl<F> Send for AssumeSend<F> {}
+
+    let handler = AssumeSend(handler);
+    move || {
+        // Moves the whole wrapper into the closure, rather than only its field
+        let handler = handler;
+        (handler.0)()
+    }
+}
+
+/// Runs a server handler, on the server's executor if one is configured.
+///
+/// A panic of the handler faults the call with the server's panic fault status, instead
+/// of unwinding into the RPC runtime, so the client gets an error and the server keeps
+/// serving.
+pub fn invoke<R: Send>(handler: impl FnOnce() -> R + Send) -> R {
+    let state = current();
+    let fault = state
+        .as_ref()
+        .map_or(RPC_S_CALL_FAILED, |state| state.panic_fault());
+    let handler = move || std::panic::catch_unwind(AssertUnwindSafe(handler));
+    let result = match state.as_ref().and_then(|state| state.executor()) {
+        // The handler still sees the server's state and call on the executor's thread
+        Some(executor) => {
+            let call = CALL.get() as usize;
+            let syntax = SYNTAX.get();
+            let result = run_blocking(&*executor, move || {
+                in_call(state, call as *mut c_void, syntax, handler)
+            });
+            // A job the executor dropped faults the call like a panic of the handler
+            match result {
+                Ok(result) => result,
+                Err(JobDropped) => raise_fault(fault.0),
+            }
+        }
+        None => handler(),
+    };
+
+    match result {
+        Ok(result) => result,
+        Err(payload) => {
+            // The fault does not return, so the payload is dropped first
+            drop(payload);
+            raise_fault(fault.0)
+        }
+    }
+}
+
+/// Runs a server handler on the thread dispatching the call, ignoring the server's
+/// executor, for handlers streaming the call's pipes: the pipe procedures fail by raising
+/// exceptions, which only the server stub on this thread catches.
+///
+/// A panic of the handler faults the call like with [`invoke()`].
+pub fn invoke_here<R>(handler: impl FnOnce() -> R) -> R {
+    let fault = current().map_or(RPC_S_CALL_FAILED, |state| state.panic_fault());
+    match std::panic::catch_unwind(AssertUnwindSafe(handler)) {
+        Ok(result) => result,
+        Err(payload) => {
+            // The fault does not return, so the payload is dropped first
+            drop(payload);
+            raise_fault(fault.0)
+        }
+    }
+}
+
+/// Runs `f` with the server's state, call and syntax of a call current on this thread
+fn in_call<R>(
+    state: Option<Arc<ServerState>>,
+    call: *mut c_void,
+    syntax: Option<TransferSyntax>,
+    f: impl FnOnce() -> R,
+) -> R {
+    let previous = CURRENT.with(|current| current.replace(state));
+    let previous_call = CALL.replace(call);
+    let previous_syntax = SYNTAX.replace(syntax);
+    let result = f();
+    CURRENT.with(|current| *current.borrow_mut() = previous);
+    CALL.set(previous_call);
+    SYNTAX.set(previous_syntax);
+    result
+}
+
+/// Runs the server routine of an asynchronous procedure, and completes the call with the
+/// value `routine` returns, or aborts it if `routine` faults or panics.
+///
+/// With an executor, `routine` runs on it and the RPC worker thread returns at once,
+/// leaving the call pending until the executor's job completes it. Otherwise `routine`
+/// runs before this returns.
+///
+/// # Safety
+///
+/// `async_state` must be the `RPC_ASYNC_STATE` of the call being dispatched. `routine`
+/// must only use the parameters of the call, which the server stub keeps until the call
+/// is completed.
+pub unsafe fn invoke_async<R>(async_state: *mut c_void, routine: impl FnOnce() -> R + Send) {
+    let state = current();
+    let pending = PendingCall {
+        state: async_state as usize,
+        fault: state
+            .as_ref()
+            .map_or(RPC_S_CALL_FAILED, |state| state.panic_fault()),
+    };
+    let executor = state.as_ref().and_then(|state| state.executor());
+    let call = CALL.get() as usize;
+    let syntax = SYNTAX.get();
+    let job = move || {
+        let result = in_call(state, call as *mut c_void, syntax, || {
+            let previous = ASYNC.replace(true);
+            let result = std::panic::catch_unwind(AssertUnwindSafe(routine));
+            ASYNC.set(previous);
+            result
+        });
+        match result {
+            Ok(reply) => pending.complete(reply),
+            Err(payload) => {
+                // A panic leaves the call to be aborted with the panic fault status
+                if let Ok(fault) = payload.downcast::<AsyncFault>() {
+                    pending.abort(fault.0);
+                }
+            }
+        }
+    };
+    match executor {
+        // A job the executor drops aborts the call like a panic of the handler
+        Some(executor) => {
+            let job: Box<dyn FnOnce() + Send + '_> = Box::new(job);
+            // SAFETY: The job only uses the parameters of the call, which the server stub
+            // keeps until the job completes or aborts the call.
+            let job: Job = unsafe { std::mem::transmute(job) };
+            executor.execute(job);
+        }
+        None => job(),
+    }
+}
+
+/// An asynchronous call that is yet to be completed, aborted with the server's panic
+/// fault status if dropped first
+struct PendingCall {
+    state: usize,
+    fault: RPC_STATUS,
+}
+
+impl PendingCall {
+    fn complete<R>(self, mut reply: R) {
+        let reply = if std::mem::size_of::<R>() == 0 {
+            std::ptr::null_mut()
+        } else {
+            (&raw mut reply).cast()
+        };
+        // Fails if the client cancelled the call, which is then over
+        unsafe { RpcAsyncCompleteCall(self.state as *mut RPC_ASYNC_STATE, reply) };
+        std::mem::forget(self);
+    }
+
+    fn abort(self, status: i32) {
+        unsafe { RpcAsyncAbortCall(self.state as *mut RPC_ASYNC_STATE, status as u32) };
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for PendingCall {
+    fn drop(&mut self) {
+        unsafe { RpcAsyncAbortCall(self.state as *mut RPC_ASYNC_STATE, self.fault.0 as u32) };
+    }
+}
@@ -0,0 +1,333 @@
+//! Errors of failed RPC operations, with extended error information.
+//!
+//! When an RPC operation fails, the runtime records a chain of extended error
+//! information (EEInfo) on the failing thread: which component detected the error,
+//! where, on which computer and process, and with what parameters. [`RpcError`] captures
+//! that chain together with the status code, so failures can be traced to the layer that
+//! actually caused them.
+//!
+//! Extended error information must be enabled on the machine (it is by default for
+//! local RPC) and only describes the most recent failure on the current thread, so it
+//! is captured right after an RPC operation fails, and cleared from the thread so a later
+//! error can't pick it up. Errors that aren't failures of an RPC operation, such as
+//! converted [`windows::core::Error`]s, carry none.
+//!
+//! Methods declared as returning `Result<T, E>` fail with a [`ServiceError`] instead,
+//! which also carries the error codes their handlers return.
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use windows::Win32::System::Rpc::{
+    EEInfoUseFileTime, RPC_EEINFO_VERSION, RPC_ERROR_ENUM_HANDLE, RPC_EXTENDED_ERROR_INFO,
+    RPC_STATUS, RpcErrorClearInformation, RpcErrorEndEnumeration, RpcErrorGetNextRecord,
+    RpcErrorStartEnumeration, eeptAnsiString, eeptBinary, eeptLongVal, eeptPointerVal,
+    eeptShortVal, eeptUnicodeString,
+};
+use windows::core::HRESULT;
+
+/// A failed RPC operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    status: i32,
+    extended_info: Vec<ExtendedErrorRecord>,
+}
+
+/// A failed call of a method declared as returning `Result<T, E>`: the call itself
+/// failed, or the server's handler returned an error code.
+///
+/// `E` is `u32` or an enum deriving `NdrEnum`, sent as the value of its variant. Zero
+/// means success, as for Win32 error codes, so handlers don't return `Err(0)` or a
+/// variant of value 0, which the client would receive as `Ok` with an empty value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServiceError<E = u32> {
+    /// The call failed.
+    Rpc(RpcError),
+    /// The handler returned this error.
+    Service(E),
+}
+
+impl<E> ServiceError<E> {
+    /// Returns the error the handler returned, or `None` if the call failed.
+    pub fn service_error(&self) -> Option<&E> {
+        match self {
+            ServiceError::Rpc(_) => None,
+            ServiceError::Service(error) => Some(error),
+        }
+    }
+}
+
+impl<E> From<RpcError> for ServiceError<E> {
+    fn from(error: RpcError) -> Self {
+        ServiceError::Rpc(error)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for ServiceError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::Rpc(error) => error.fmt(f),
+            ServiceError::Service(error) => write!(f, "The server returned the error {error:?}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for ServiceError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ServiceError::Rpc(error) => Some(error),
+            ServiceError::Service(_) => None,
+        }
+    }
+}
+
+/// A record of the extended error information chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedErrorRecord {
+    /// Computer the record was generated on, if it was not the local one.
+    pub computer_name: Option<String>,
+    /// Process the record was generated in.
+    pub process_id: u32,
+    /// When the record was generated.
+    pub time: SystemTime,
+    /// Component that generated the record, see [`component_name()`](Self::component_name).
+    pub generating_component: u32,
+    /// Status code reported by the component.
+    pub status: u32,
+    /// Code location in the component that detected the error.
+    pub detection_location: u16,
+    /// `EEInfoPreviousRecordsMissing` and `EEInfoNextRecordsMissing` flags.
+    pub flags: u16,
+    /// Parameters of the record, whose meaning depends on the detection location.
+    pub parameters: Vec<ExtendedErrorParam>,
+}
+
+/// A parameter of an [`ExtendedErrorRecord`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtendedErrorParam {
+    /// An ANSI string, converted to UTF-8.
+    AnsiString(String),
+    /// A Unicode string.
+    UnicodeString(String),
+    /// A 32-bit integer.
+    Long(i32),
+    /// A 16-bit integer.
+    Short(i16),
+    /// A pointer value.
+    Pointer(u64),
+    /// A binary blob.
+    Binary(Vec<u8>),
+}
+
+impl RpcError {
+    /// Creates an error from the status of an RPC operation that just failed on the
+    /// current thread, capturing the extended error information it recorded.
+    pub fn from_status(status: RPC_STATUS) -> Self {
+        Self {
+            status: status.0,
+            extended_info: capture_extended_info(),
+        }
+    }
+
+    /// Creates an error from a status, without extended error information, for failures
+    /// detected outside of an RPC operation.
+    pub fn new(status: RPC_STATUS) -> Self {
+        Self {
+            status: status.0,
+            extended_info: Vec::new(),
+        }
+    }
+
+    /// The RPC status code, e.g. `RPC_S_SERVER_UNAVAILABLE`.
+    pub fn status(&self) -> RPC_STATUS {
+        RPC_STATUS(self.status)
+    }
+
+    /// The status code as an `HRESULT`.
+    pub fn code(&self) -> HRESULT {
+        self.status().to_hresult()
+    }
+
+    /// The extended error information chain, from the most recent record to the
+    /// original cause. Empty if none was recorded.
+    pub fn extended_info(&self) -> &[ExtendedErrorRecord] {
+        &self.extended_info
+    }
+
+    /// The last record of the extended error information chain, generated by the
+    /// component where the failure originated, e.g. the security provider for a failed
+    /// authentication. `None` if no information was recorded.
+    pub fn origin(&self) -> Option<&ExtendedErrorRecord> {
+        self.extended_info.last()
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RPC operation failed with status {}: {}",
+            self.status,
+            windows::core::Error::from_hresult(self.code()).message()
+        )?;
+        for record in &self.extended_info {
+            write!(f, "\n  {record}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl From<RpcError> for windows::core::Error {
+    fn from(error: RpcError) -> Self {
+        windows::core::Error::from_hresult(error.code())
+    }
+}
+
+impl From<windows::core::Error> for RpcError {
+    fn from(error: windows::core::Error) -> Self {
+        // RPC statuses are Win32 error codes, wrapped as FACILITY_WIN32 HRESULTs
+        let code = error.code().0;
+        let status = if (code as u32) & 0xffff_0000 == 0x8007_0000 {
+            code & 0xffff
+        } else {
+            code
+        };
+        // Not necessarily the failure of an RPC operation, so the thread's extended error
+        // information may be that of an earlier one
+        Self::new(RPC_STATUS(status))
+    }
+}
+
+impl ExtendedErrorRecord {
+    /// Returns the name of the generating component, if it is a known one.
+    pub fn component_name(&self) -> Option<&'static str> {
+        Some(match self.generating_component {
+            1 => "application",
+            2 => "RPC runtime",
+            3 => "security provider",
+            4 => "NPFS",
+            5 => "redirector",
+            6 => "named pipe system",
+            7 => "IO",
+            8 => "Winsock",
+            9 => "authorization API",
+            10 => "LPC",
+            11 => "COM",
+            12 => "FRS",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for ExtendedErrorRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.component_name() {
+            Some(name) => write!(f, "{name}")?,
+            None => write!(f, "component {}", self.generating_component)?,
+        }
+        write!(f, " (process {}", self.process_id)?;
+        if let Some(computer_name) = &self.computer_name {
+            write!(f, " on {computer_name}")?;
+        }
+        write!(
+            f,
+            "): status {} at location {}",
+            self.status, self.detection_location
+        )?;
+        for (i, param) in self.parameters.iter().enumerate() {
+            write!(
+                f,
+                "{}{param:?}",
+                if i == 0 { ", parameters " } else { ", " }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn capture_extended_info() -> Vec<ExtendedErrorRecord> {
+    let mut records = Vec::new();
+    let mut handle = RPC_ERROR_ENUM_HANDLE::default();
+    unsafe {
+        // Fails when no information was recorded
+        if RpcErrorStartEnumeration(&mut handle).is_err() {
+            return records;
+        }
+
+        loop {
+            let mut info = RPC_EXTENDED_ERROR_INFO {
+                Version: RPC_EEINFO_VERSION,
+                Flags: EEInfoUseFileTime as u16,
+                ..Default::default()
+            };
+            // Strings point into the chain, which stays valid until the enumeration ends
+            if RpcErrorGetNextRecord(&handle, false, &mut info).is_err() {
+                break;
+            }
+            records.push(convert_record(&info));
+        }
+
+        let _ = RpcErrorEndEnumeration(&mut handle);
+        RpcErrorClearInformation();
+    }
+    records
+}
+
+// The parameter type constants keep their Windows SDK names
+#[allow(non_upper_case_globals)]
+unsafe fn convert_record(info: &RPC_EXTENDED_ERROR_INFO) -> ExtendedErrorRecord {
+    let computer_name = (!info.ComputerName.is_null())
+        .then(|| String::from_utf16_lossy(unsafe { info.ComputerName.as_wide() }));
+
+    // FILETIME counts 100ns intervals since 1601-01-01
+    const UNIX_EPOCH_AS_FILETIME: u64 = 116_444_736_000_000_000;
+    let file_time = unsafe { info.u.FileTime };
+    let ticks = ((file_time.dwHighDateTime as u64) << 32) | file_time.dwLowDateTime as u64;
+    let time = SystemTime::UNIX_EPOCH
+        + Duration::from_nanos(ticks.saturating_sub(UNIX_EPOCH_AS_FILETIME) * 100);
+
+    let count = (info.NumberOfParameters.max(0) as usize).min(info.Parameters.len());
+    let parameters = info.Parameters[..count]
+        .iter()
+        .filter_map(|param| unsafe {
+            Some(match param.ParameterType {
+                eeptAnsiString => {
+                    ExtendedErrorParam::AnsiString(param.u.AnsiString.to_string().ok()?)
+                }
+                eeptUnicodeString => {
+                    ExtendedErrorParam::UnicodeString(param.u.UnicodeString.to_string().ok()?)
+                }
+                eeptLongVal => ExtendedErrorParam::Long(param.u.LVal),
+                eeptShortVal => ExtendedErrorParam::Short(param.u.SVal),
+                eeptPointerVal => ExtendedErrorParam::Pointer(param.u.PVal),
+                eeptBinary => {
+                    let binary = param.u.BVal;
+                    let bytes = if binary.Buffer.is_null() {
+                        Vec::new()
+                    } else {
+                        std::slice::from_raw_parts(
+                            binary.Buffer as *const u8,
+                            binary.Size.max(0) as usize,
+                        )
+                        .to_vec()
+                    };
+                    ExtendedErrorParam::Binary(bytes)
+                }
+                _ => return None,
+            })
+        })
+        .collect();
+
+    ExtendedErrorRecord {
+        computer_name,
+        process_id: info.ProcessID,
+        time,
+        generating_component: info.GeneratingComponent,
+        status: info.Status,
+        detection_location: info.DetectionLocation,
+        flags: info.Flags,
+        parameters,
+    }
+}
@@ -0,0 +1,437 @@
+//! Running server handlers on a user-provided executor.
+//!
+//! By default, server handlers run directly on the RPC runtime's worker threads.
+//! A [`CallExecutor`] lets the application move handler work onto its own thread
+//! pool or async runtime, so the CPU work obeys the application's scheduling and
+//! instrumentation. The RPC worker thread stays blocked until the handler completes.
+//!
+//! Handlers of asynchronous procedures, declared with `#[rpc(async)]`, complete their
+//! calls asynchronously instead: the RPC worker thread hands the call to the executor and
+//! returns to the runtime, and the executor's job completes the call with
+//! `RpcAsyncCompleteCall` once the handler returns. Such calls don't hold an RPC thread
+//! while they wait for the executor or run on it.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! struct CalculatorImpl;
+//! impl CalculatorServerImpl for CalculatorImpl {
+//!     fn add(a: i32, b: i32) -> i32 {
+//!         a + b
+//!     }
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let mut server = CalculatorServer::<CalculatorImpl>::new();
+//! // Any `Fn(Job)` can be used, e.g. `|job| { runtime.spawn_blocking(job); }` with tokio
+//! server.set_executor(|job: windows_rpc::executor::Job| {
+//!     std::thread::spawn(job);
+//! });
+//! server.register("calculator_endpoint")?;
+//! server.listen_async()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Awaiting client calls
+//!
+//! Client calls block the calling thread until the server replies. The `{method}_async`
+//! variants generated for clients run the call on the client's executor instead and
+//! return a [`CallFuture`] that completes with its result, so async applications can
+//! await calls without stalling their runtime. Clients without an executor run calls on
+//! tokio's blocking pool when called within a tokio runtime (with the `tokio` feature),
+//! and on a shared [`ThreadPool`] otherwise.
+//!
+//! Asynchronous procedures, declared with `#[rpc(async)]`, need no executor: their
+//! `{method}_async` variants send the request and return a [`CallFuture`] that the runtime
+//! completes once the reply arrives, and dropping it cancels the call.
+//!
+//! # Single-threaded dispatch
+//!
+//! [`SingleThreadExecutor`] serializes all calls through one dedicated worker thread.
+//! This allows hosting implementations whose state isn't `Sync` (for example a COM STA
+//! object or a non-thread-safe C library kept in a `thread_local!`).
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, OnceLock, mpsc};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+/// A unit of handler work handed to a [`CallExecutor`].
+pub type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Schedules server handler calls on an application-owned executor.
+///
+/// Implementations must eventually either run or drop every job they receive.
+/// The RPC worker thread that dispatched the call waits until that happens.
+pub trait CallExecutor: Send + Sync {
+    /// Schedules `job` for execution.
+    fn execute(&self, job: Job);
+}
+
+impl<F> CallExecutor for F
+where
+    F: Fn(Job) + Send + Sync,
+{
+    fn execute(&self, job: Job) {
+        self(job)
+    }
+}
+
+/// The error of a job its executor dropped without running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobDropped;
+
+impl std::fmt::Display for JobDropped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("the executor dropped the call without running it")
+    }
+}
+
+impl std::error::Error for JobDropped {}
+
+/// Runs `f` on `executor` and blocks until it returns.
+///
+/// # Errors
+///
+/// Returns [`JobDropped`] if the executor drops the job without running it, or if `f`
+/// panics.
+pub fn run_blocking<R: Send>(
+    executor: &dyn CallExecutor,
+    f: impl FnOnce() -> R + Send,
+) -> Result<R, JobDropped> {
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+        let _ = sender.send(f());
+    });
+
+    // SAFETY: The job owns the only sender. `recv` returns only after the job ran
+    // or was dropped, so nothing it borrows outlives this frame.
+    let job: Job = unsafe { std::mem::transmute(job) };
+    executor.execute(job);
+
+    receiver.recv().map_err(|_| JobDropped)
+}
+
+/// Runs `f` on `executor`, or on the default executor if there is none, and returns a
+/// future that completes with its result.
+///
+/// The default executor is tokio's blocking pool when called within a tokio runtime
+/// (with the `tokio` feature), and a [`ThreadPool`] shared by all calls otherwise.
+///
+/// # Panics
+///
+/// The future panics when polled if the executor drops the job without running it, or
+/// if `f` panics.
+pub fn spawn<R: Send + 'static>(
+    executor: Option<&dyn CallExecutor>,
+    f: impl FnOnce() -> R + Send + 'static,
+) -> CallFuture<R> {
+    let (completion, future) = pending(None);
+    let job: Job = Box::new(move || completion.complete(f()));
+    match executor {
+        Some(executor) => executor.execute(job),
+        None => execute_default(job),
+    }
+    future
+}
+
+/// Returns a future that completes with the result given to its [`Completion`], and
+/// runs `cancel` if it is dropped first.
+pub(crate) fn pending<R>(
+    cancel: Option<Box<dyn FnOnce() + Send + Sync>>,
+) -> (Completion<R>, CallFuture<R>) {
+    let shared = Arc::new(Mutex::new(CallState {
+        result: None,
+        finished: false,
+        waker: None,
+    }));
+    (Completion(shared.clone()), CallFuture { shared, cancel })
+}
+
+/// Runs `job` on the default executor of [`spawn()`]
+fn execute_default(job: Job) {
+    #[cfg(feature = "tokio")]
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        handle.spawn_blocking(job);
+        return;
+    }
+
+    static POOL: OnceLock<ThreadPool> = OnceLock::new();
+    POOL.get_or_init(ThreadPool::default).execute(job);
+}
+
+/// The result of a call running on an executor, returned by [`spawn()`], or of an
+/// asynchronous procedure, which is cancelled if the future is dropped before it
+/// completes.
+pub struct CallFuture<R> {
+    shared: Arc<Mutex<CallState<R>>>,
+    cancel: Option<Box<dyn FnOnce() + Send + Sync>>,
+}
+
+impl<R> CallFuture<R> {
+    /// Returns a future that is already complete, e.g. with the error of a call that
+    /// failed to start.
+    pub fn ready(result: R) -> Self {
+        let (completion, future) = pending(None);
+        completion.complete(result);
+        future
+    }
+}
+
+struct CallState<R> {
+    result: Option<R>,
+    /// Set once the job ran or was dropped
+    finished: bool,
+    waker: Option<Waker>,
+}
+
+/// Marks the call finished when the job is dropped, whether it ran, panicked or was
+/// discarded by the executor
+pub(crate) struct Completion<R>(Arc<Mutex<CallState<R>>>);
+
+impl<R> Completion<R> {
+    /// Completes the future with `result`
+    pub(crate) fn complete(self, result: R) {
+        self.0.lock().unwrap().result = Some(result);
+        // Dropping `self` wakes the future
+    }
+}
+
+impl<R> Drop for Completion<R> {
+    fn drop(&mut self) {
+        let waker = {
+            // A panic of the job poisons nothing: the lock is not held while it runs
+            let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+            state.finished = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<R> Future for CallFuture<R> {
+    type Output = R;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        let mut state = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.finished {
+            state.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => panic!("the call panicked or its executor dropped it without running it"),
+        }
+    }
+}
+
+impl<R> Drop for CallFuture<R> {
+    fn drop(&mut self) {
+        let Some(cancel) = self.cancel.take() else {
+            return;
+        };
+        let finished = self
+            .shared
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .finished;
+        if !finished {
+            cancel();
+        }
+    }
+}
+
+/// Runs every job on a single dedicated worker thread, one at a time.
+///
+/// The worker thread exits once the executor is dropped and all queued jobs have run.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use windows_rpc::executor::SingleThreadExecutor;
+///
+/// // Initialize per-thread state (e.g. enter a COM STA) before the first call
+/// let executor = SingleThreadExecutor::with_init(|| {
+///     // CoInitializeEx(None, COINIT_APARTMENTTHREADED)
+/// });
+/// ```
+pub struct SingleThreadExecutor {
+    sender: mpsc::Sender<Job>,
+}
+
+impl SingleThreadExecutor {
+    /// Spawns the worker thread.
+    pub fn new() -> Self {
+        Self::with_init(|| {})
+    }
+
+    /// Spawns the worker thread, running `init` on it before any job.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the worker thread cannot be spawned.
+    pub fn with_init(init: impl FnOnce() + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        thread::Builder::new()
+            .name("windows-rpc-dispatch".to_string())
+            .spawn(move || {
+                init();
+                for job in receiver {
+                    job();
+                }
+            })
+            .expect("failed to spawn the dispatch thread");
+
+        Self { sender }
+    }
+}
+
+impl Default for SingleThreadExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CallExecutor for SingleThreadExecutor {
+    fn execute(&self, job: Job) {
+        // If the worker is gone the job is dropped, which fails the call
+        let _ = self.sender.send(job);
+    }
+}
+
+/// Runs jobs on up to a fixed number of worker threads, queueing the others.
+///
+/// Workers are spawned when a job arrives and none is idle, and exit after being idle for
+/// [`ThreadPool::KEEP_ALIVE`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use windows_rpc::executor::ThreadPool;
+///
+/// // At most 8 calls run at the same time
+/// let executor = ThreadPool::new(8);
+/// ```
+pub struct ThreadPool {
+    shared: Arc<PoolShared>,
+}
+
+struct PoolShared {
+    state: Mutex<PoolState>,
+    available: Condvar,
+    max_threads: usize,
+}
+
+struct PoolState {
+    queue: VecDeque<Job>,
+    threads: usize,
+    /// Workers waiting for a job
+    idle: usize,
+}
+
+impl ThreadPool {
+    /// The number of workers of [`ThreadPool::default()`].
+    pub const DEFAULT_MAX_THREADS: usize = 64;
+
+    /// How long an idle worker waits for a job before exiting.
+    pub const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+    /// Creates a pool running up to `max_threads` jobs at the same time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_threads` is 0.
+    pub fn new(max_threads: usize) -> Self {
+        assert!(max_threads > 0, "a thread pool needs at least one thread");
+        Self {
+            shared: Arc::new(PoolShared {
+                state: Mutex::new(PoolState {
+                    queue: VecDeque::new(),
+                    threads: 0,
+                    idle: 0,
+                }),
+                available: Condvar::new(),
+                max_threads,
+            }),
+        }
+    }
+
+    /// Returns the maximum number of jobs running at the same time.
+    pub fn max_threads(&self) -> usize {
+        self.shared.max_threads
+    }
+}
+
+impl Default for ThreadPool {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_THREADS)
+    }
+}
+
+impl CallExecutor for ThreadPool {
+    fn execute(&self, job: Job) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.queue.push_back(job);
+        if state.queue.len() <= state.idle || state.threads == self.shared.max_threads {
+            self.shared.available.notify_one();
+            return;
+        }
+
+        state.threads += 1;
+        let shared = self.shared.clone();
+        let spawned = thread::Builder::new()
+            .name("windows-rpc-pool".to_string())
+            .spawn(move || shared.work());
+        if spawned.is_err() {
+            state.threads -= 1;
+            if state.threads == 0 {
+                // Nothing will run the queued jobs, dropping them fails their calls
+                let dropped = std::mem::take(&mut state.queue);
+                drop(state);
+                drop(dropped);
+            }
+        }
+    }
+}
+
+impl PoolShared {
+    /// Runs queued jobs until idle for `KEEP_ALIVE`
+    fn work(&self) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+                // A panicking job fails its call, the worker keeps running
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            state.idle += 1;
+            let (guard, timeout) = self
+                .available
+                .wait_timeout(state, ThreadPool::KEEP_ALIVE)
+                .unwrap();
+            state = guard;
+            state.idle -= 1;
+            if timeout.timed_out() && state.queue.is_empty() {
+                state.threads -= 1;
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Several interfaces served on one endpoint.
+//!
+//! An [`RpcServerHost`] owns a group of servers, registers them all on a single endpoint
+//! and starts and stops listening once for the whole group, instead of each server
+//! managing its own registration and listening.
+//!
+//! Several versions of an interface, generated from traits with the same GUID and
+//! different versions, can be hosted side by side with separate implementations, so that
+//! clients of an old version keep being served by the implementation of their version
+//! after methods were added in a new one. The RPC runtime dispatches a call to the first
+//! registered version compatible with the client's, so minor versions of a major version
+//! must be added oldest first.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::ProtocolSequence;
+//! use windows_rpc::host::RpcServerHost;
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! #[rpc_interface(guid(0x87654321_4321_4321_4321_cba987654321), version(1.0))]
+//! trait Greeter {
+//!     fn greet(name: &str) -> String;
+//! }
+//!
+//! struct CalculatorImpl;
+//! impl CalculatorServerImpl for CalculatorImpl {
+//!     fn add(a: i32, b: i32) -> i32 {
+//!         a + b
+//!     }
+//! }
+//!
+//! struct GreeterImpl;
+//! impl GreeterServerImpl for GreeterImpl {
+//!     fn greet(name: &str) -> String {
+//!         format!("Hello, {name}!")
+//!     }
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let mut host = RpcServerHost::new(ProtocolSequence::Alpc, "services_endpoint");
+//! host.add(CalculatorServer::<CalculatorImpl>::new())?;
+//! host.add(GreeterServer::<GreeterImpl>::new())?;
+//! host.listen_async()?;
+//! // ... both interfaces are served on `services_endpoint` ...
+//! host.stop()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::any::Any;
+
+use windows::Win32::System::Rpc::{
+    RPC_S_INVALID_VERS_OPTION, RPC_S_NO_INTERFACES, RpcMgmtWaitServerListen,
+};
+use windows::core::{Error, Result};
+
+use crate::ProtocolSequence;
+use crate::interface::{RpcClient, RpcServer};
+use crate::server_binding::EndpointMode;
+
+/// Serves several generated `{Interface}Server` instances on one endpoint.
+///
+/// The first server added registers the endpoint and controls listening; the others
+/// reuse it according to [`EndpointMode::ReuseExisting`]. Dropping the host stops and
+/// drops the servers in reverse order, so the first one stops listening last.
+pub struct RpcServerHost {
+    protocol: ProtocolSequence,
+    endpoint: String,
+    servers: Vec<Box<dyn HostedServer>>,
+}
+
+impl RpcServerHost {
+    /// Creates a host serving on `endpoint` over `protocol`. The endpoint is registered
+    /// when the first server is added.
+    pub fn new(protocol: ProtocolSequence, endpoint: impl Into<String>) -> Self {
+        Self {
+            protocol,
+            endpoint: endpoint.into(),
+            servers: Vec::new(),
+        }
+    }
+
+    /// Returns the endpoint the servers are registered on.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Registers `server` on the host's endpoint and takes ownership of it.
+    ///
+    /// Servers added after [`listen_async()`](Self::listen_async) are served right away.
+    /// Servers of several minor versions of an interface must be added oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_INVALID_VERS_OPTION` if a newer minor version of the interface is
+    /// already hosted, which would take the calls of this version's clients, or the error
+    /// of the server's `register()`, e.g. `RPC_S_DUPLICATE_ENDPOINT` if the first server
+    /// finds the endpoint already registered in the process.
+    pub fn add<S: RpcServer + 'static>(&mut self, mut server: S) -> Result<()> {
+        let (guid, (major, minor)) = (S::Client::GUID, S::Client::VERSION);
+        let shadowed = self.servers.iter().any(|hosted| {
+            let (hosted_guid, (hosted_major, hosted_minor)) = hosted.interface();
+            hosted_guid == guid && hosted_major == major && hosted_minor > minor
+        });
+        if shadowed {
+            return Err(Error::from_hresult(RPC_S_INVALID_VERS_OPTION.to_hresult()));
+        }
+
+        let mode = if self.servers.is_empty() {
+            EndpointMode::Exclusive
+        } else {
+            EndpointMode::ReuseExisting
+        };
+        server.set_protocol_sequence(self.protocol);
+        server.set_endpoint_mode(mode);
+        server.register(&self.endpoint)?;
+        self.servers.push(Box::new(server));
+        Ok(())
+    }
+
+    /// Returns the hosted server of type `S`, if any.
+    pub fn get<S: RpcServer + 'static>(&self) -> Option<&S> {
+        self.servers
+            .iter()
+            .find_map(|server| server.as_any().downcast_ref())
+    }
+
+    /// Returns the versions of the interface `guid` that are hosted, as `(major, minor)`,
+    /// in the order they were added.
+    pub fn versions(&self, guid: u128) -> Vec<(u16, u16)> {
+        self.servers
+            .iter()
+            .map(|server| server.interface())
+            .filter(|&(hosted_guid, _)| hosted_guid == guid)
+            .map(|(_, version)| version)
+            .collect()
+    }
+
+    /// Returns the number of hosted servers.
+    pub fn len(&self) -> usize {
+        self.servers.len()
+    }
+
+    /// Returns whether no server was added yet.
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+
+    /// Starts listening for calls to all hosted servers without blocking.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_NO_INTERFACES` if no server was added, or the error of
+    /// `RpcServerListen`.
+    pub fn listen_async(&self) -> Result<()> {
+        let Some(owner) = self.servers.first() else {
+            return Err(Error::from_hresult(RPC_S_NO_INTERFACES.to_hresult()));
+        };
+        owner.listen_async()
+    }
+
+    /// Blocks until the host is stopped, by [`stop()`](Self::stop) from another thread or
+    /// by a management call, after [`listen_async()`](Self::listen_async).
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_NOT_LISTENING` if the process is not listening.
+    pub fn wait_until_stopped(&self) -> Result<()> {
+        unsafe { RpcMgmtWaitServerListen() }.ok()
+    }
+
+    /// Stops all hosted servers, the first one, which stops listening, last.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the servers' `stop()`; the remaining servers are
+    /// stopped regardless.
+    pub fn stop(&self) -> Result<()> {
+        let mut result = Ok(());
+        for server in self.servers.iter().rev() {
+            let stopped = server.stop();
+            if result.is_ok() {
+                result = stopped;
+            }
+        }
+        result
+    }
+}
+
+impl Drop for RpcServerHost {
+    fn drop(&mut self) {
+        // The servers stop themselves when dropped, the endpoint's owner last
+        while let Some(server) = self.servers.pop() {
+            drop(server);
+        }
+    }
+}
+
+/// The object-safe part of [`RpcServer`] the host needs
+trait HostedServer {
+    fn interface(&self) -> (u128, (u16, u16));
+    fn listen_async(&self) -> Result<()>;
+    fn stop(&self) -> Result<()>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<S: RpcServer + 'static> HostedServer for S {
+    fn interface(&self) -> (u128, (u16, u16)) {
+        (S::Client::GUID, S::Client::VERSION)
+    }
+
+    fn listen_async(&self) -> Result<()> {
+        RpcServer::listen_async(self)
+    }
+
+    fn stop(&self) -> Result<()> {
+        RpcServer::stop(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,58 @@
+//! Traits implemented by the generated client and server types.
+//!
+//! These allow writing code that is generic over any interface generated by
+//! [`rpc_interface`](crate::rpc_interface), such as the helpers in [`testing`](crate::testing).
+
+use std::sync::Arc;
+
+use windows::core::Result;
+
+use crate::ProtocolSequence;
+use crate::client_binding::ClientBinding;
+use crate::server_binding::EndpointMode;
+
+/// Implemented by the generated `{Interface}Client` types.
+pub trait RpcClient: Sized {
+    /// The interface identifier.
+    const GUID: u128;
+    /// The interface version, as `(major, minor)`.
+    const VERSION: (u16, u16);
+    /// Stable textual identifier of the interface, e.g.
+    /// `12345678-1234-1234-1234-123456789abc:1.0`.
+    const INTERFACE_ID: &'static str;
+
+    /// Creates a client that makes calls over `binding`.
+    fn new(binding: ClientBinding) -> Self;
+}
+
+/// Implemented by the generated `{Interface}Server<T>` types.
+pub trait RpcServer: Sized {
+    /// The client type generated for the same interface.
+    type Client: RpcClient;
+    /// The implementation of the interface the server dispatches to.
+    type Impl;
+
+    /// Creates the server and all of its RPC metadata.
+    fn new() -> Self;
+
+    /// Creates the server with the implementation object its handlers reach through
+    /// [`server::instance()`](crate::server::instance).
+    fn with_instance(instance: Arc<Self::Impl>) -> Self
+    where
+        Self::Impl: Send + Sync + 'static;
+
+    /// Sets the protocol sequence `register()` serves the interface on.
+    fn set_protocol_sequence(&mut self, protocol: ProtocolSequence);
+
+    /// Sets how `register()` handles an endpoint that is already registered.
+    fn set_endpoint_mode(&mut self, mode: EndpointMode);
+
+    /// Registers the interface on `endpoint`.
+    fn register(&mut self, endpoint: &str) -> Result<()>;
+
+    /// Starts listening for calls without blocking.
+    fn listen_async(&self) -> Result<()>;
+
+    /// Stops listening for calls.
+    fn stop(&self) -> Result<()>;
+}
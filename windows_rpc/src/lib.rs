@@ -13,6 +13,7 @@
 //! - **NDR marshalling** - Automatic Network Data Representation encoding/decoding
 //! - **String support** - Native handling of string parameters and return values
 //! - **Integer types** - Support for i8, i16, i32, i64, u8, u16, u32, u64
+//! - **Floating point types** - Support for f32 and f64
 //! - **ALPC protocol** - Fast local RPC using Advanced Local Procedure Call
 //!
 //! # Quick Start
@@ -32,11 +33,31 @@
 //! }
 //! ```
 //!
-//! This generates three types:
+//! This generates four types:
 //! - `CalculatorClient` - for making RPC calls
+//! - `Calculator` - object-safe trait with the client's methods, implemented by `CalculatorClient`
 //! - `CalculatorServerImpl` - trait to implement for the server
 //! - `CalculatorServer<T>` - generic server wrapper for RPC dispatch
 //!
+//! The interface identity can also be declared as associated consts of the trait, so it
+//! lives in regular Rust items. Either way, the generated types expose it as
+//! `CalculatorClient::GUID` and `CalculatorClient::VERSION`:
+//!
+//! ```rust
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface]
+//! trait Calculator {
+//!     const GUID: u128 = 0x12345678_1234_1234_1234_123456789abc;
+//!     const VERSION: (u16, u16) = (1, 0);
+//!
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! assert_eq!(CalculatorClient::VERSION, (1, 0));
+//! assert_eq!(CalculatorClient::INTERFACE_ID, "12345678-1234-1234-1234-123456789abc:1.0");
+//! ```
+//!
 //! # Server Example
 //!
 //! Implement the generated `ServerImpl` trait with static methods:
@@ -72,15 +93,16 @@
 //!
 //!     println!("Server is running...");
 //!
-//!     // Keep the server running
-//!     std::thread::sleep(std::time::Duration::from_secs(60));
-//!
-//!     // Clean shutdown
-//!     server.stop()?;
+//!     // Serve calls until another thread or a management call stops the server
+//!     server.wait_until_stopped()?;
 //!     Ok(())
 //! }
 //! ```
 //!
+//! A handler that panics faults its call with `RPC_S_CALL_FAILED`, or the status set with
+//! `set_panic_fault()`, instead of unwinding into the RPC runtime. The client gets the status
+//! as the error of the call, and the server keeps serving.
+//!
 //! # Client Example
 //!
 //! Make RPC calls using the generated client:
@@ -101,17 +123,46 @@
 //!     let client = CalculatorClient::new(binding);
 //!
 //!     // Make RPC calls - integers
-//!     let result = client.add(10, 20);
+//!     let result = client.add(10, 20)?;
 //!     println!("10 + 20 = {result}");  // Prints: 10 + 20 = 30
 //!
 //!     // Make RPC calls - strings
-//!     let greeting = client.greet("Alice");
+//!     let greeting = client.greet("Alice")?;
 //!     println!("{greeting}");  // Prints: Hello, Alice!
 //!
 //!     Ok(())
 //! }
 //! ```
 //!
+//! Client methods return `Result<T, RpcError>`. A call that fails, because the server is
+//! unreachable, denies access or faults the call, returns the RPC status code with the
+//! extended error information recorded for it (see [`error`]), instead of raising an SEH
+//! exception. [`RpcError::extended_info()`] lists the records of the components the
+//! failure went through, and [`RpcError::origin()`] the one where it originated.
+//!
+//! Code that already has a raw RPC binding handle, e.g. from another library, can hand it to
+//! a client with [`ClientBinding::from_raw()`](client_binding::ClientBinding::from_raw).
+//!
+//! To find connectivity problems at startup instead of on the first call, connect eagerly
+//! with a bounded timeout using
+//! [`ClientBinding::bind()`](client_binding::ClientBinding::bind) before creating the client.
+//!
+//! Calls rejected by a busy or unavailable server can be retried with backoff by wrapping
+//! the client in a [`ResilientClient`](retry::ResilientClient), or reconnect to a restarted
+//! server with a [`RobustClient`](retry::RobustClient), see [`retry`].
+//!
+//! Threads making many calls can each get their own client, over a copy of the binding,
+//! from a [`BindingPool`](pool::BindingPool) instead of sharing one handle.
+//!
+//! Creating a client is cheap: the stub descriptor and format strings of an interface are
+//! built by its first client and shared by the later ones.
+//!
+//! Each method also has an `_async` variant, called on an `Arc` of the client, which makes
+//! the call on the client's executor and returns a future of its result, so async
+//! applications can await calls without blocking their runtime. See [`executor`]. Those
+//! of `#[rpc(async)]` methods await the reply without occupying a thread, see
+//! [Method Attributes](#method-attributes).
+//!
 //! # Complete Example with String Operations
 //!
 //! Here's a more comprehensive example showcasing various string operations:
@@ -159,10 +210,10 @@
 //!     );
 //!
 //!     // Test string operations
-//!     println!("{}", client.to_uppercase("hello"));              // Output: HELLO
-//!     println!("{}", client.reverse("hello"));                   // Output: olleh
-//!     println!("{}", client.count_words("hello world"));         // Output: 2
-//!     println!("{}", client.concat("Hello, ", "World!"));        // Output: Hello, World!
+//!     println!("{}", client.to_uppercase("hello")?);             // Output: HELLO
+//!     println!("{}", client.reverse("hello")?);                  // Output: olleh
+//!     println!("{}", client.count_words("hello world")?);        // Output: 2
+//!     println!("{}", client.concat("Hello, ", "World!")?);       // Output: Hello, World!
 //!
 //!     server.stop()?;
 //!     Ok(())
@@ -179,13 +230,848 @@
 //! | `i16`, `u16` | ✓ | ✓ | 16-bit integers |
 //! | `i32`, `u32` | ✓ | ✓ | 32-bit integers |
 //! | `i64`, `u64` | ✓ | ✓ | 64-bit integers |
+//! | `f32`, `f64` | ✓ | ✓ | Floating point numbers (`float`/`double`) |
 //! | `&str` | ✓ | ✗ | String input parameters |
-//! | `String` | ✗ | ✓ | String return values |
+//! | `&U16CStr` | ✓ | ✗ | Wide string input parameters, borrowed by the server without decoding (see `wstr`) |
+//! | `#[rpc(ansi)] &str` / `Option<&str>` | ✓ | ✗ | String input parameters passed as ANSI `char` strings, null for `None` |
+//! | `String` | ✓ | ✓ | String return values, and input parameters taken by value |
+//! | `&String`, `Cow<'_, str>` | ✓ | ✗ | String input parameters, sent like `&str` |
+//! | `&OsStr`, `OsString`, `&Path`, `PathBuf` | ✓ | ✗ | OS string and path input parameters, converted without loss |
+//! | `U16CString` | ✗ | ✓ | Wide string return values (see `wstr`) |
+//! | `&HSTRING` / `HSTRING` | ✓ | ✓ | Windows strings, passed without UTF-8 conversion |
+//! | `&[u8]`, `&[i32]`, ... | ✓ | ✗ | Integer array input parameters, borrowed from the RPC buffer on the server |
+//! | `Vec<u8>`, `Vec<i32>`, ... | ✗ | ✓ | Integer array return values |
+//! | `Vec<String>` / `&mut Vec<String>` | ✓ | ✓ | String array return values and output parameters |
+//! | `&mut i32`, `&mut u64`, ... | ✓ | ✗ | Integer output parameters, written by the server and copied back to the client |
+//! | `#[in_out] &mut i32`, ... | ✓ | ✗ | Integer input-output parameters, sent to the server and copied back |
+//! | `&T` / `&mut T` / `T` | ✓ | ✓ | Structs deriving `NdrStruct`, as input, output (or `#[in_out]`) parameters and return values |
+//! | `&FILETIME`, `&SYSTEMTIME`, `&LARGE_INTEGER` / ... | ✓ | ✓ | Win32 structs of `wtypes.idl`, passed like structs deriving `NdrStruct` |
+//! | `T` | ✓ | ✓ | Fieldless enums deriving `NdrEnum`, listed in the interface's `enums(...)` |
+//! | `&T` / `T` | ✓ | ✓ | Enums with integer or floating point payloads deriving `NdrUnion`, as input parameters and return values |
+//! | `Option<i32>`, `Option<f64>`, ... | ✓ | ✓ | Optional numbers, passed as `[unique]` pointers that are null for `None` |
+//! | `Option<&str>` | ✓ | ✗ | Optional string input parameters, null for `None` |
+//! | `Option<String>` | ✗ | ✓ | Optional string return values, null for `None` |
+//! | `T` | ✓ | ✓ | Types implementing `RpcTransmittable`, listed in the interface's `transmit_as(...)` |
+//! | `BorrowedHandle<'_>` / `OwnedHandle` | ✓ | ✓ | Kernel object handles marked `#[rpc(system_handle(kind))]`, passed over ALPC only |
+//! | `&ContextHandle` / `ContextHandle` | ✓ | ✓ | Context handles to per-client state on the server, also `#[in_out] &mut ContextHandle` |
+//! | `InPipe<u8>`, `OutPipe<i64>`, ... | ✓ | ✗ | Pipes streaming numbers in chunks, `[in]` or `[out]`, on 64-bit targets |
+//!
+//! Handlers taking a string parameter as `String` get the decoded string without a copy,
+//! and ones taking `Cow<'_, str>` borrow it. Client methods take these parameters by value
+//! (`impl AsWideStr`), and `&String` ones like `&str`. Parameters taken as `&OsStr`,
+//! `OsString`, `&Path` or `PathBuf` keep file names that aren't valid UTF-16 (unpaired
+//! surrogates) intact, where `&str` ones would fail the call:
+//!
+//! ```rust,no_run
+//! use std::borrow::Cow;
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Names {
+//!     fn rename(old: &str, new: String) -> u32;
+//!     fn normalize(name: Cow<'_, str>) -> String;
+//!     fn delete(path: &std::path::Path) -> u32;
+//! }
+//! ```
+//!
+//! Strings are UTF-16 `wchar_t` strings on the wire. Peers declaring a parameter
+//! `[string] char*` take `&str` parameters marked `#[rpc(ansi)]`, which are converted to
+//! the system's ANSI code page; characters it lacks arrive as `?`. `Option<&str>` parameters
+//! marked `#[rpc(ansi)]` are `[unique, string] char*`, null for `None`:
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Legacy {
+//!     fn open(#[rpc(ansi)] path: &str, #[rpc(ansi)] mode: Option<&str>) -> u32;
+//! }
+//! ```
+//!
+//! Methods listing names return `Vec<String>`, an `[out, size_is(, *length)] LPWSTR**`
+//! array of unique string pointers with a hidden length, like other vectors:
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Directory {
+//!     fn list(#[rpc(ansi)] pattern: Option<&str>) -> Vec<String>;
+//! }
+//! ```
+//!
+//! A `&mut Vec<String>` output parameter is declared alike, its `[out] unsigned long*`
+//! length before it, for methods returning something else or several lists. The server
+//! fills an empty vector, which replaces the caller's once the call succeeds:
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Directory {
+//!     fn list(files: &mut Vec<String>, folders: &mut Vec<String>) -> u32;
+//! }
+//! ```
+//!
+//! Structs of integer fields derive `NdrStruct`, and must be `#[repr(C)]` and `Copy`:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrStruct, rpc_interface};
+//!
+//! #[derive(NdrStruct, Clone, Copy)]
+//! #[repr(C)]
+//! pub struct Point {
+//!     pub x: i32,
+//!     pub y: i32,
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Geometry {
+//!     fn translate(point: &Point, dx: i32, dy: i32) -> Point;
+//! }
+//! ```
+//!
+//! Their layout is only known to the compiler, so a lockfile detects renamed structs but
+//! not changed fields.
+//!
+//! Fields may also be `PCWSTR` and `PCSTR` strings, or `*const T` arrays of numbers
+//! sized by a `u32` field named in `#[ndr(size_is(...))]`. NDR marshals what they point to,
+//! and they may be null. Such structs are input parameters only, and their `_async` methods
+//! need the struct to be `Send`, which raw pointers aren't:
+//!
+//! ```rust,no_run
+//! use windows::core::PCWSTR;
+//! use windows_rpc::{NdrStruct, rpc_interface};
+//!
+//! #[derive(NdrStruct, Clone, Copy)]
+//! #[repr(C)]
+//! pub struct Entry {
+//!     pub name: PCWSTR,
+//!     pub count: u32,
+//!     #[ndr(size_is(count))]
+//!     pub values: *const u16,
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abd), version(1.0))]
+//! trait Catalog {
+//!     fn total(entry: &Entry) -> u32;
+//! }
+//! ```
+//!
+//! As for MIDL, a `*const u16` or `*const u8` field is either a NUL-terminated string,
+//! marked `#[ndr(string)]`, or an array, marked `#[ndr(size_is(...))]`.
+//!
+//! Structs owning their strings and arrays have `Vec<u16>` or `Vec<u8>` fields with the same
+//! attributes. The derive then generates a `{Name}Wire` struct of pointers into the vectors,
+//! and converts to and from it with `RpcTransmittable`, so interfaces send the struct
+//! with `transmit_as` (see below). `to_wire` sets each `size_is` field to the length of
+//! its vector, and panics if a non-empty string doesn't end with a NUL; empty strings are
+//! sent as null. Servers receive copies of the vectors, strings keeping their NUL:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrStruct, rpc_interface};
+//!
+//! #[derive(NdrStruct, Clone)]
+//! pub struct Record {
+//!     #[ndr(string)]
+//!     pub name: Vec<u16>,
+//!     pub count: u32,
+//!     #[ndr(size_is(count))]
+//!     pub values: Vec<u8>,
+//! }
+//!
+//! #[rpc_interface(
+//!     guid(0x12345678_1234_1234_1234_123456789abe),
+//!     version(1.0),
+//!     transmit_as(Record = RecordWire)
+//! )]
+//! trait Records {
+//!     fn store(record: Record) -> u32;
+//! }
+//! ```
+//!
+//! The Win32 structs `FILETIME` and `SYSTEMTIME` of the `windows` crate, and
+//! `windows_rpc::LARGE_INTEGER` (which the `windows` crate maps to `i64`), are passed like
+//! structs deriving `NdrStruct`, as declared by MIDL's `wtypes.idl`. The generated IDL
+//! imports `wtypes.idl` for them, and `rpc_interface_from_idl!` maps them back to these types:
+//!
+//! ```rust
+//! use windows::Win32::Foundation::{FILETIME, SYSTEMTIME};
+//! use windows_rpc::{LARGE_INTEGER, rpc_interface};
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Clock {
+//!     fn to_system_time(time: &FILETIME) -> SYSTEMTIME;
+//!     fn uptime(ticks: &mut LARGE_INTEGER);
+//! }
+//! ```
+//!
+//! Fieldless `#[repr(u16)]` and `#[repr(u32)]` enums derive `NdrEnum`, and are listed in
+//! `enums(...)` so the interface doesn't take them for structs. A value matching no variant
+//! faults the call, unless a variant is marked `#[ndr(fallback)]`:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrEnum, rpc_interface};
+//!
+//! #[derive(NdrEnum, Clone, Copy)]
+//! #[repr(u32)]
+//! pub enum Color {
+//!     Red = 1,
+//!     Green = 2,
+//!     #[ndr(fallback)]
+//!     Unknown = 0xffff,
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0), enums(Color))]
+//! trait Palette {
+//!     fn mix(a: Color, b: Color) -> Color;
+//! }
+//! ```
+//!
+//! Enums whose variants carry an integer or floating point number derive `NdrUnion`, and
+//! must be `#[repr(C, u16)]` or `#[repr(C, u32)]`. They are passed like structs, and NDR
+//! transmits them as encapsulated unions, rejecting unknown discriminants. Variants
+//! can't carry strings, which are passed as separate parameters instead:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrUnion, rpc_interface};
+//!
+//! #[derive(NdrUnion, Clone, Copy)]
+//! #[repr(C, u32)]
+//! pub enum Value {
+//!     Empty,
+//!     Int(i32),
+//!     Real(f64),
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Values {
+//!     fn negate(value: &Value) -> Value;
+//! }
+//! ```
+//!
+//! Other types are passed as a number or struct with `transmit_as(Type = Wire, ...)`, like
+//! MIDL's `[transmit_as]`. They implement [`RpcTransmittable<Wire>`](RpcTransmittable),
+//! which converts them to and from the type that is sent, and are taken by value as
+//! parameters and return values. The runtime implements it for `Duration`, sent as a `u64`
+//! of 100-nanosecond intervals. The wire type being a parameter of the trait, a crate can
+//! implement it for another crate's type when the wire type is its own struct:
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use windows_rpc::{NdrStruct, RpcTransmittable, rpc_interface};
+//!
+//! # mod uuid {
+//! #     pub struct Uuid(u64, u64);
+//! #     impl Uuid {
+//! #         pub fn as_u64_pair(&self) -> (u64, u64) { (self.0, self.1) }
+//! #         pub fn from_u64_pair(high: u64, low: u64) -> Self { Uuid(high, low) }
+//! #     }
+//! # }
+//! #[derive(NdrStruct, Clone, Copy)]
+//! #[repr(C)]
+//! pub struct WireUuid {
+//!     pub high: u64,
+//!     pub low: u64,
+//! }
+//!
+//! impl RpcTransmittable<WireUuid> for uuid::Uuid {
+//!     fn to_wire(&self) -> WireUuid {
+//!         let (high, low) = self.as_u64_pair();
+//!         WireUuid { high, low }
+//!     }
+//!
+//!     fn from_wire(wire: WireUuid) -> Self {
+//!         uuid::Uuid::from_u64_pair(wire.high, wire.low)
+//!     }
+//! }
+//!
+//! #[rpc_interface(
+//!     guid(0x12345678_1234_1234_1234_123456789abc),
+//!     version(1.0),
+//!     transmit_as(Duration = u64, uuid::Uuid = WireUuid)
+//! )]
+//! trait Jobs {
+//!     fn schedule(id: uuid::Uuid, delay: Duration) -> Duration;
+//! }
+//! ```
+//!
+//! Handles to kernel objects, such as events, sections or processes, are lent to the server
+//! as `BorrowedHandle<'_>` parameters and returned as `OwnedHandle`, like MIDL's
+//! `[system_handle]`. The parameter, or the method for a returned handle, is marked
+//! `#[rpc(system_handle(kind))]` with one of `file`, `semaphore`, `event`, `mutex`,
+//! `process`, `token`, `section`, `reg_key`, `thread`, `composition`, `socket`, `job` or
+//! `pipe`. The runtime duplicates the handle into the receiving process, with the access
+//! mask that may follow the kind, or the same access when it's omitted, so handles can only
+//! be passed over ALPC (`ProtocolSequence::Alpc`). The server's duplicate of a parameter is
+//! closed when the call returns, and methods with handle parameters have no `_async` variant.
+//!
+//! ```rust
+//! use std::os::windows::io::{BorrowedHandle, OwnedHandle};
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Broker {
+//!     // EVENT_MODIFY_STATE
+//!     fn signal(#[rpc(system_handle(event, 0x0002))] event: BorrowedHandle<'_>);
+//!     #[rpc(system_handle(section))]
+//!     fn shared_memory(size: u32) -> OwnedHandle;
+//! }
+//! ```
+//!
+//! Context handles keep per-client state on the server between calls, like MIDL's
+//! `[context_handle]`. A method returning `ContextHandle` opens a context: its handler
+//! returns a `ServerContext` holding the state, and the client gets a handle to pass to later
+//! calls as `&ContextHandle`, which handlers receive as `&ServerContext`. A
+//! `#[in_out] &mut ContextHandle` parameter lets the handler replace the context or close it,
+//! which closes the client's handle too. When a client goes away without closing its
+//! contexts, e.g. when its process exits, the runtime runs them down, calling the callback
+//! given to `ServerContext::with_rundown()` with the state so per-client resources are
+//! released. Methods with context handle parameters have no `_async` variant.
+//!
+//! ```rust
+//! use windows_rpc::context::ServerContext;
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Files {
+//!     fn open(path: &str) -> ContextHandle;
+//!     fn size(file: &ContextHandle) -> u64;
+//!     fn close(#[in_out] file: &mut ContextHandle);
+//! }
+//!
+//! struct OpenFile {
+//!     size: u64,
+//! }
+//!
+//! struct FilesImpl;
+//! impl FilesServerImpl for FilesImpl {
+//!     fn open(path: &str) -> ServerContext {
+//!         let file = OpenFile { size: path.len() as u64 };
+//!         ServerContext::with_rundown(file, |_file: OpenFile| {
+//!             // The client went away without closing the file
+//!         })
+//!     }
+//!
+//!     fn size(file: &ServerContext) -> u64 {
+//!         file.get::<OpenFile>().map_or(0, |file| file.size)
+//!     }
+//!
+//!     fn close(file: &mut ServerContext) {
+//!         file.close();
+//!     }
+//! }
+//! ```
+//!
+//! Pipes stream large amounts of numbers in chunks, like MIDL's `pipe` types. Clients pass
+//! a [`pipe::PipeSource`], e.g. any iterator, for an `InPipe<T>` parameter, and a
+//! [`pipe::PipeSink`], e.g. a `Vec<T>`, for an `OutPipe<T>` one; handlers read and write
+//! them through a [`pipe::PipeReader`] and a [`pipe::PipeWriter`], see [`pipe`]. Methods
+//! with pipes run their handler on the RPC thread and have no `_async` variant.
+//!
+//! # Pickling
+//!
+//! Structs and unions can also be serialized without a call ("pickled") with the runtime's
+//! type serialization engine, e.g. to persist them or carry them over another channel. The
+//! buffers are in the standard type serialization format, interchangeable with types pickled
+//! by MIDL-generated `_Encode`/`_Decode` routines, and `decode()` rejects truncated data and
+//! unknown union discriminants with `RPC_X_BAD_STUB_DATA`:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrStruct, pickle};
+//!
+//! #[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+//! #[repr(C)]
+//! pub struct Point {
+//!     pub x: i32,
+//!     pub y: i32,
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let bytes = pickle::encode(&Point { x: 1, y: 2 })?;
+//! assert_eq!(pickle::decode::<Point>(&bytes)?, Point { x: 1, y: 2 });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Method Attributes
+//!
+//! An `#[rpc(...)]` attribute on a method sets flags of its procedure. `#[rpc(maybe)]` makes
+//! a fire-and-forget notification, like MIDL's `[maybe]`: the client sends the call and
+//! returns without waiting for the server, with no guarantee that it is delivered. Such
+//! methods cannot return values or have output parameters.
+//!
+//! `#[rpc(idempotent)]` marks a method, like MIDL's `[idempotent]`, whose calls can safely be
+//! executed more than once, so the runtime is allowed to retry them over connectionless or
+//! unreliable transports. Attributes combine, as in `#[rpc(maybe, idempotent)]`.
+//!
+//! `#[rpc(broadcast)]`, like MIDL's `[broadcast]`, sends calls to every server on the local
+//! network, and the client gets the first reply. Broadcast methods are idempotent, and
+//! are called over a `ProtocolSequence::Udp` binding to the servers' port.
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Notifier {
+//!     #[rpc(maybe)]
+//!     fn notify(event: &str);
+//!     #[rpc(idempotent)]
+//!     fn pending() -> u32;
+//! }
+//! ```
+//!
+//! `#[rpc(async)]` makes an asynchronous procedure, like MIDL's ACF `[async]`, which leaves
+//! the wire format unchanged. Its `_async` method, called on the client itself, sends the
+//! request and returns a future that the runtime completes once the reply arrives, without a
+//! thread waiting for it; dropping the future cancels the call with `RpcAsyncCancelCall`.
+//! The blocking method waits for the reply instead. On the server, the call completes with
+//! `RpcAsyncCompleteCall` once the handler returns, and a fault or a panic aborts it.
+//! Asynchronous methods cannot be `maybe`.
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Jobs {
+//!     #[rpc(async)]
+//!     fn run(name: &str) -> u32;
+//! }
+//!
+//! # async fn example(client: JobsClient) -> Result<(), windows_rpc::RpcError> {
+//! let status = client.run_async("backup").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `#[rpc(callback)]` declares a callback, like MIDL's `[callback]`: a procedure the server
+//! calls on the client during one of the client's calls. The client implements the generated
+//! `{Interface}Callbacks` trait and passes it to `set_callbacks()`, and handlers make the
+//! callbacks through `{Interface}CallbacksClient::current()`; see [`callback`].
+//!
+//! On a parameter, `#[rpc(range(min, max))]` bounds an integer of up to 32 bits, or the
+//! length of a slice, like MIDL's `[range]`: the server stub fails calls with a value
+//! outside of `min..=max` with `RPC_S_INVALID_BOUND` before they reach the handler.
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Storage {
+//!     fn read(#[rpc(range(1, 4096))] size: u32) -> Vec<u8>;
+//!     fn write(#[rpc(range(0, 4096))] data: &[u8]);
+//! }
+//! ```
+//!
+//! A slice is sized by a hidden length by default. `#[rpc(size_is(length))]` sizes it by a
+//! `u32` parameter of the method instead, like MIDL's `[size_is]`: only that many elements
+//! are marshalled, and the client fails calls whose length exceeds the slice with
+//! `RPC_S_INVALID_BOUND`. `&mut [T]` slices are `[out]` buffers the server fills, or
+//! `[in, out]` with `#[in_out]`:
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Storage {
+//!     fn read(offset: u64, #[rpc(size_is(length))] buffer: &mut [u8], length: u32) -> u32;
+//! }
+//! ```
+//!
+//! `#[rpc(ptr)]` passes an `Option<T>`, `Option<&str>` or `&T` struct parameter through a
+//! full pointer, like MIDL's `[ptr]`: the stubs keep a table of the pointers of a call, so a
+//! value that several parameters point to is sent once, and the server's parameters point
+//! to the same value again.
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrStruct, rpc_interface};
+//!
+//! #[derive(NdrStruct, Clone, Copy)]
+//! #[repr(C)]
+//! struct Node {
+//!     id: u32,
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Graph {
+//!     fn link(#[rpc(ptr)] from: &Node, #[rpc(ptr)] to: &Node);
+//! }
+//! ```
+//!
+//! # Interface Compatibility
+//!
+//! By default interfaces offer both NDR 2.0 and NDR64 with the interface flags MIDL
+//! generates for `/protocol all`. [`InterfaceCompatibility`] selects other presets: `Legacy`
+//! offers NDR 2.0 only for OSF DCE peers and older systems, and `AutoListen` registers the
+//! server interface with `RPC_IF_AUTOLISTEN` so it serves calls without `listen()`:
+//!
+//! ```rust,no_run
+//! # use windows_rpc::rpc_interface;
+//! use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+//! # #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! # trait Calculator {
+//! #     fn add(a: i32, b: i32) -> i32;
+//! # }
+//! # struct CalculatorImpl;
+//! # impl CalculatorServerImpl for CalculatorImpl {
+//! #     fn add(a: i32, b: i32) -> i32 { a + b }
+//! # }
+//! # fn main() -> windows::core::Result<()> {
+//! let mut server = CalculatorServer::<CalculatorImpl>::new();
+//! server.set_compatibility(InterfaceCompatibility::Legacy);
+//! server.register("calculator_endpoint")?;
+//! server.listen_async()?;
+//!
+//! let client = CalculatorClient::with_compatibility(
+//!     ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")?,
+//!     InterfaceCompatibility::Legacy,
+//! );
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Interfaces declared with `transfer_syntax(ndr64)` generate only the NDR64 tables,
+//! halving the generated metadata for 64-bit deployments whose peers all support NDR64.
+//! They do not build for 32-bit targets and cannot use the `Legacy` preset.
+//!
+//! Conversely, `transfer_syntax(ndr20)` generates only the NDR 2.0 tables, for services
+//! whose peers predate NDR64. Such interfaces are offered like the `Legacy` preset
+//! whatever the preset, though `AutoListen` still registers servers with
+//! `RPC_IF_AUTOLISTEN`.
+//!
+//! Clients of dual-syntax interfaces can also be restricted to one syntax with
+//! `{Interface}Client::with_transfer_syntax()`, and `transfer_syntax()` returns the only
+//! syntax a client offers, if any. Handlers see the syntax of the call they handle through
+//! [`server::transfer_syntax()`].
+//!
+//! `set_autolisten(true)` registers the server interface with `RPC_IF_AUTOLISTEN` whatever
+//! its preset. Components such as DLLs, which cannot own the process's listen loop, serve
+//! calls from `register()` on and stop serving on `stop()`, without affecting other
+//! servers in the process.
+//!
+//! # Evolving Interfaces
+//!
+//! Parameters appended to a method in a later minor version are marked with
+//! `#[since(major.minor, default = value)]`. Clients created with `connect()` query the
+//! server's version and omit the parameters it does not know, and servers also serve
+//! clients of older versions, passing the defaults for the parameters they omit:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.1))]
+//! trait Search {
+//!     fn search(query: &str, #[since(1.1, default = 10)] limit: u32) -> String;
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let binding = ClientBinding::new(ProtocolSequence::Alpc, "search_endpoint")?;
+//! let client = SearchClient::connect(binding)?;
+//! // `limit` is only sent to 1.1 servers
+//! let results = client.search("rust", 5)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Added parameters must be trailing and in version order.
+//!
+//! To keep serving old clients with their own implementation instead, define each version
+//! as a trait with the same GUID and add their servers to a [`host::RpcServerHost`],
+//! minor versions oldest first, as the RPC runtime dispatches a call to the first
+//! registered version compatible with the client's. See [`host`].
+//!
+//! # Locking the Wire Contract
+//!
+//! Renaming a method is harmless, but reordering methods or changing a parameter type
+//! silently breaks peers built from older revisions. With `lockfile("path")`, the macro
+//! hashes the interface's GUID, version and NDR descriptors and fails the build when the
+//! hash differs from the one recorded in the lockfile:
+//!
+//! ```rust,ignore
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0), lockfile("rpc.lock"))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//! ```
+//!
+//! The path is relative to the crate's manifest directory. Build with
+//! `WINDOWS_RPC_UPDATE_LOCKFILE=1` to record the current contracts, and commit the
+//! lockfile.
+//!
+//! # Generating IDL
+//!
+//! The generated client and server expose the MIDL definition of the interface as their
+//! `IDL` const, describing the same wire format as the generated stubs, so C/C++ teams can
+//! compile matching stubs with MIDL. With `idl("path")`, the macro also writes it to a file,
+//! relative to the crate's manifest directory:
+//!
+//! ```rust,ignore
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0), idl("calculator.idl"))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//!     fn greet(name: &str) -> String;
+//! }
+//! ```
+//!
+//! ```text
+//! [
+//!     uuid(12345678-1234-1234-1234-123456789abc),
+//!     version(1.0),
+//!     pointer_default(unique)
+//! ]
+//! interface Calculator
+//! {
+//!     long add([in] handle_t binding, [in] long a, [in] long b);
+//!     void greet([in] handle_t binding, [in, string] const wchar_t* name, [out, string] wchar_t** return_value);
+//! }
+//! ```
+//!
+//! Procedures take an explicit binding handle, slices are preceded by their length, and
+//! return values other than integers, floats and enums are returned through a trailing
+//! `[out]` parameter. Structs, unions and enums are referred to by name: provide their
+//! typedefs to MIDL separately, e.g. in an imported file.
+//!
+//! For C callers, the `HEADER` const holds a C header in the layout of MIDL's, declaring
+//! the interface GUID (`Calculator_GUID`), version (`Calculator_VERSION_MAJOR`/`_MINOR`),
+//! the procedure prototypes and the interface handles, and `header("path")` writes it to a
+//! file. The prototypes are implemented by the client stubs MIDL generates from the IDL
+//! (`midl /client stub /header none calculator.idl`), so C callers of a Rust server never
+//! write IDL by hand. Declare the structs and enums before including the header.
+//!
+//! # Importing IDL
+//!
+//! To bind to an interface defined in MIDL, `rpc_interface_from_idl!` generates the client
+//! and server from the `.idl` file instead of a hand-written trait, with the path relative
+//! to the crate's manifest directory:
+//!
+//! ```rust,ignore
+//! #[derive(NdrStruct, Clone, Copy)]
+//! #[repr(C)]
+//! struct Position {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! // interface directory_service { void Move([in] handle_t hBinding, [in] Position* pFrom); ... }
+//! rpc_interface_from_idl!("idl/directory.idl");
+//!
+//! struct Directory;
+//! impl DirectoryServiceServerImpl for Directory {
+//!     fn move_(p_from: &Position) {}
+//! }
+//! ```
+//!
+//! The interface name is converted to upper camel case, and method and parameter names to
+//! snake case (Rust keywords get a trailing `_`). A subset of MIDL is supported, the one
+//! `IDL` is generated in: base types and their Windows typedefs such as `DWORD`, enums,
+//! pointers to structs, wide strings, `[unique]` pointers and `size_is` arrays, `[out]`
+//! pointers, and trailing `[out]` parameters of `void` procedures, which become the return
+//! value. Structs and enums are referred to by name and must be defined in Rust. Typedefs,
+//! preprocessor directives and object (COM) interfaces are rejected. Further
+//! `rpc_interface` arguments can follow the path, e.g.
+//! `rpc_interface_from_idl!("idl/directory.idl", lockfile("rpc.lock"))`.
+//!
+//! # Generating Code in Build Scripts
+//!
+//! Tools that describe interfaces some other way, e.g. in a configuration file, can generate
+//! the same code as `rpc_interface` in a build script with the `windows-rpc-codegen` crate.
+//! Its `Interface`, `Method` and `Parameter` model is what the macro builds from a trait, and
+//! `compile_interface()` generates the client and server (`compile_client()` and
+//! `compile_server()` generate one side):
+//!
+//! ```rust,ignore
+//! // build.rs
+//! use windows_rpc_codegen::{BaseType, Interface, InterfaceVersion, Method, Parameter, Type};
+//!
+//! let interface = Interface {
+//!     name: "Calculator".to_string(),
+//!     uuid: 0x12345678_1234_1234_1234_123456789abc,
+//!     version: InterfaceVersion { major: 1, minor: 0 },
+//!     methods: vec![Method {
+//!         name: "add".to_string(),
+//!         parameters: vec![
+//!             Parameter::new("a", Type::Simple(BaseType::I32)),
+//!             Parameter::new("b", Type::Simple(BaseType::I32)),
+//!         ],
+//!         return_type: Some(Type::Simple(BaseType::I32)),
+//!         ..Method::default()
+//!     }],
+//!     ..Interface::default()
+//! };
+//! let code = windows_rpc_codegen::compile_interface(&interface);
+//! let out_dir = std::path::PathBuf::from(std::env::var("OUT_DIR").unwrap());
+//! std::fs::write(out_dir.join("calculator.rs"), code.to_string()).unwrap();
+//!
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/calculator.rs"));
+//! ```
+//!
+//! The interface must follow the rules the macro checks, e.g. `#[since]` parameters are
+//! trailing. `generate_idl()` and `generate_header()` produce the `IDL` and `HEADER` of the
+//! interface.
+//!
+//! # Inspecting Generated Code
+//!
+//! The `windows-rpc-gen` binary prints what the macros generate for the interfaces of a Rust
+//! or MIDL file, to read or diff it without expanding macros:
+//!
+//! ```sh
+//! cargo install windows-rpc-gen
+//! windows-rpc-gen src/calculator.rs           # everything
+//! windows-rpc-gen --code service.idl          # the generated client and server
+//! windows-rpc-gen --formats src/calculator.rs # the NDR 2.0 format strings
+//! windows-rpc-gen --ndr64 src/calculator.rs   # the NDR64 type format and descriptors
+//! ```
+//!
+//! The format strings are listed one field per line in the layout of MIDL's stub files,
+//! e.g. `/*   30 */ NdrFcShort( 0x48 ), /* Flags:  in, base type, */`, so they can be compared
+//! with MIDL's output. Struct and union fragments are appended when the generated code runs,
+//! so the listing names the positions they are patched into. The listings are also available
+//! from `windows_rpc_codegen::formats`.
+//!
+//! # Shared Implementations
+//!
+//! Handlers are static methods. To share an implementation object with the rest of the
+//! application, create the server with `with_instance(Arc<T>)`; handlers get it with
+//! [`server::instance::<T>()`](server::instance). See [`server`] for an example.
+//!
+//! # Hosting Several Interfaces
+//!
+//! A [`host::RpcServerHost`] registers several servers on one endpoint and starts and stops
+//! listening once for all of them. See [`host`] for an example.
+//!
+//! # Several Implementations of an Interface
+//!
+//! Servers of different implementations of one interface can share an endpoint, each
+//! registered with `set_manager_type()` as the manager of a type UUID. Clients choose the
+//! implementation with [`client_binding::ClientBinding::set_object()`], whose object UUID
+//! the server maps to a type with [`server_binding::set_object_type()`]. The RPC runtime
+//! keeps using the server registered first for every implementation, so drop it last.
+//!
+//! # Access Control
+//!
+//! `set_security_descriptor()` attaches a [`security::SecurityDescriptor`] to the server's
+//! interface, passed to `RpcServerRegisterIf3`. The RPC runtime checks callers against
+//! it, separately for each interface sharing the endpoint. `set_security_callback()`
+//! installs a [`security::SecurityCallback`] that receives the interface UUID and the
+//! call's binding handle and can reject each call. `set_endpoint_security_descriptor()`
+//! registers the endpoints with a descriptor instead, restricting who may connect to them
+//! at all, for every interface on them. See [`security`].
+//!
+//! `set_require_encryption(true)` rejects every call that is not authenticated at the
+//! `RPC_C_AUTHN_LEVEL_PKT_PRIVACY` level, so the interface is only reachable over
+//! encrypted connections. `set_registration_flags()` adds `RPC_IF_*` flags such as
+//! `RPC_IF_ALLOW_LOCAL_ONLY` or `RPC_IF_ALLOW_SECURE_ONLY` to the interface's registration.
+//! `set_method_authorizer()` allows or denies each call given the method's opnum and the
+//! caller's [`server::CallContext`], see [`security`].
+//!
+//! Handlers act on behalf of the caller with [`server::impersonate_client()`], which runs
+//! a closure under the client's token and reverts to the server's afterwards.
+//! [`server::call_context()`] describes the client of the current call: its SID, principal
+//! name, authentication level, whether it is local, and its process ID for local calls.
 //!
 //! # Protocol Support
 //!
-//! Currently only ALPC (Advanced Local Procedure Call) is supported via the `ncalrpc`
-//! protocol sequence. This allows RPC communication between processes on the same machine.
+//! Five protocol sequences are supported:
+//!
+//! - `ProtocolSequence::Alpc` (`ncalrpc`), ALPC (Advanced Local Procedure Call) between
+//!   processes on the same machine. This is the default.
+//! - `ProtocolSequence::NamedPipe` (`ncacn_np`), named pipes with `\pipe\name` endpoints,
+//!   which many Windows services expose. Servers select it with `set_protocol_sequence()`
+//!   before `register()`, and `ClientBinding::with_server()` connects to the pipe of a
+//!   remote server, given by host name or IP address.
+//! - `ProtocolSequence::Http` (`ncacn_http`), RPC over HTTP for servers behind firewalls.
+//!   Servers register a port number as the endpoint, and `ClientBinding::with_http()`
+//!   connects through the RPC and HTTP proxies given in `HttpOptions`.
+//! - `ProtocolSequence::HyperVSocket` (`ncacn_hvsocket`), Hyper-V sockets between a host and
+//!   its guest VMs. Servers register a service ID formatted as a GUID as the endpoint, and
+//!   `ClientBinding::with_vm()` addresses the service in a VM by its VM ID, or the host with
+//!   `ClientBinding::VM_ID_PARENT`.
+//! - `ProtocolSequence::Udp` (`ncadg_ip_udp`), connectionless datagrams with port numbers as
+//!   endpoints, needed for `#[rpc(broadcast)]` methods. Windows dropped datagram protocols
+//!   in Windows Vista, so it is only available on older systems and other DCE runtimes;
+//!   `is_supported()` tells whether the local runtime has it.
+//!
+//! ```rust,no_run
+//! # use windows_rpc::rpc_interface;
+//! # #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! # trait Calculator {
+//! #     fn add(a: i32, b: i32) -> i32;
+//! # }
+//! # struct CalculatorImpl;
+//! # impl CalculatorServerImpl for CalculatorImpl {
+//! #     fn add(a: i32, b: i32) -> i32 { a + b }
+//! # }
+//! use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut server = CalculatorServer::<CalculatorImpl>::new();
+//! server.set_protocol_sequence(ProtocolSequence::NamedPipe);
+//! server.register(r"\pipe\calculator")?;
+//! server.listen_async()?;
+//!
+//! let binding = ClientBinding::with_server(ProtocolSequence::NamedPipe, "fileserver", r"\pipe\calculator")?;
+//! let client = CalculatorClient::new(binding);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `ClientBinding::string_binding()` returns the string binding a binding was created
+//! from, e.g. `ncacn_np:fileserver[\pipe\calculator]`, for logging connection problems.
+//! `ClientBinding::from_string_binding()` creates a binding from such a string, e.g. one
+//! read from a configuration file, and `to_string_binding()` renders a binding back to it.
+//!
+//! `supported_protocol_sequences()` lists the network protocol sequences the local RPC
+//! runtime supports, and `ProtocolSequence::is_supported()` checks a single one, for
+//! choosing a transport at startup.
+//!
+//! Endpoints are owned by the server that registers them. Registering another server on
+//! an endpoint already registered in the process fails with `RPC_S_DUPLICATE_ENDPOINT`,
+//! unless it calls `set_endpoint_mode(EndpointMode::ReuseExisting)` first.
+//!
+//! Listening is process-wide but reference counted: the first server to listen starts
+//! it, and stopping a server while others are still listening only unregisters its
+//! interface. The process stops listening when the last listening server is stopped.
+//!
+//! `set_server_options(ServerOptions { min_threads, max_calls, max_rpc_size })` sets the
+//! number of call threads kept ready, the concurrent calls accepted and the largest
+//! request accepted. The runtime has one pool of call threads per process, so the thread
+//! count only takes effect for the server that starts listening.
+//!
+//! Dropping a server stops it and unregisters its interface. `set_on_drop()` selects
+//! `OnDrop::Unregister` to keep the process listening, or `OnDrop::Nothing` for servers
+//! that are only shut down explicitly.
+//!
+//! A server can serve its interface on several endpoints, e.g. a stable name and a
+//! versioned one, with `add_endpoint()` after `register()`. `remove_endpoint()` stops
+//! serving one of them while the others keep working.
+//!
+//! The endpoints can also belong to different protocol sequences, making the interface
+//! reachable e.g. over ALPC and named pipes at the same time. `register_endpoints()` takes
+//! the `(ProtocolSequence, endpoint)` pairs at once, and `add_protocol_endpoint()` adds one
+//! after `register()`:
+//!
+//! ```rust,no_run
+//! # use windows_rpc::rpc_interface;
+//! # #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! # trait Calculator {
+//! #     fn add(a: i32, b: i32) -> i32;
+//! # }
+//! # struct CalculatorImpl;
+//! # impl CalculatorServerImpl for CalculatorImpl {
+//! #     fn add(a: i32, b: i32) -> i32 { a + b }
+//! # }
+//! use windows_rpc::ProtocolSequence;
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let mut server = CalculatorServer::<CalculatorImpl>::new();
+//! server.register_endpoints(&[
+//!     (ProtocolSequence::Alpc, "calculator"),
+//!     (ProtocolSequence::NamedPipe, r"\pipe\calculator"),
+//! ])?;
+//! # Ok(())
+//! # }
+//! ```
 //!
 //! # What This Library Does
 //!
@@ -199,16 +1085,20 @@
 //!
 //! This library is currently limited in scope:
 //!
-//! - **Protocol**: Only local RPC (ALPC/ncalrpc) is supported. TCP, UDP, and named pipes
-//!   are not yet implemented.
-//! - **Parameter direction**: Only input (`[in]`) parameters and return values (`[out]`) are
-//!   supported. Input-output parameters are not available.
-//! - **Types**: Only primitive integers and strings are supported. No pointers, structs,
-//!   arrays, unions, or other complex types.
-//! - **Security**: No interface security (authentication, authorization, encryption) is
-//!   implemented.
-//! - **Exceptions**: SEH exceptions from the RPC runtime are not caught or handled.
-//! - **Callbacks**: RPC callbacks from server to client are not supported.
+//! - **Protocol**: Only ALPC (`ncalrpc`), named pipes (`ncacn_np`), RPC over HTTP
+//!   (`ncacn_http`), Hyper-V sockets (`ncacn_hvsocket`) and UDP (`ncadg_ip_udp`) are
+//!   supported. TCP is not yet implemented.
+//! - **Parameter direction**: Output (`&mut`) and input-output (`#[in_out]`) parameters are
+//!   limited to integers, structs and `&mut [T]` slices of numbers, plus output-only
+//!   `&mut Vec<String>`.
+//! - **Types**: Only primitive integers and floating point numbers, strings, arrays, structs
+//!   and enums of them, and pointers to them (`Option` and `#[rpc(ptr)]`) are supported. Enums
+//!   with payloads (unions) only carry integers and floating point numbers, not strings.
+//!   Other complex types, like non-encapsulated unions, are not supported.
+//! - **Security**: Access is controlled with security descriptors, callbacks and method
+//!   authorizers, and servers can require encrypted calls (`RPC_C_AUTHN_LEVEL_PKT_PRIVACY`).
+//!   Clients cannot set authentication information on their bindings yet, so encrypted
+//!   calls come from other clients, e.g. MIDL-generated ones.
 //!
 //! # Interoperability
 //!
@@ -237,12 +1127,35 @@
 //! - **Extern "C" wrappers**: Generated wrapper functions bridge the RPC runtime to Rust static methods
 #![cfg(windows)]
 
-#[doc(hidden)]
 pub mod alloc;
+#[doc(hidden)]
+pub mod async_call;
+pub mod callback;
 pub mod client_binding;
+pub mod context;
+#[doc(hidden)]
+pub mod dispatch;
+pub mod error;
+pub mod executor;
+pub mod host;
+pub mod interface;
+pub mod metadata;
+pub mod mgmt;
+pub mod ndr;
+pub mod negotiate;
+pub mod pickle;
+pub mod pipe;
+pub mod pool;
+pub mod retry;
+pub mod security;
+pub mod server;
 pub mod server_binding;
+pub mod testing;
+pub mod wstr;
 
-pub use windows_rpc_macros::rpc_interface;
+pub use error::{RpcError, ServiceError};
+pub use ndr::{LARGE_INTEGER, NdrEnum, NdrStruct, NdrUnion, RpcTransmittable};
+pub use windows_rpc_macros::{NdrEnum, NdrStruct, NdrUnion, rpc_interface, rpc_interface_from_idl};
 
 /// Protocol sequence for RPC communication.
 ///
@@ -266,16 +1179,211 @@ pub enum ProtocolSequence {
     /// Uses the `ncalrpc` protocol sequence. This is the fastest option for
     /// communication between processes on the same Windows machine.
     Alpc,
+    /// Named pipes, local or to a remote server.
+    ///
+    /// Uses the `ncacn_np` protocol sequence, with endpoints of the form `\pipe\name`.
+    /// Many Windows services only expose their RPC interfaces over named pipes.
+    NamedPipe,
+    /// RPC over HTTP, tunneled through an RPC proxy to traverse firewalls.
+    ///
+    /// Uses the `ncacn_http` protocol sequence, with port numbers as endpoints, e.g.
+    /// `593`. Clients configure the proxies with
+    /// [`ClientBinding::with_http()`](client_binding::ClientBinding::with_http).
+    Http,
+    /// Hyper-V sockets, between a Hyper-V host and its guest VMs.
+    ///
+    /// Uses the `ncacn_hvsocket` protocol sequence, with service IDs formatted as GUIDs
+    /// as endpoints. Clients address the VM with
+    /// [`ClientBinding::with_vm()`](client_binding::ClientBinding::with_vm).
+    HyperVSocket,
+    /// Connectionless datagrams over UDP, the only transport for broadcast calls.
+    ///
+    /// Uses the `ncadg_ip_udp` protocol sequence, with port numbers as endpoints.
+    /// Windows dropped datagram protocols in Windows Vista, so check
+    /// [`is_supported()`](Self::is_supported) before using it.
+    Udp,
     // TODO: test and add
     //Tcp,
-    //Udp,
-    //NamedPipe
+}
+
+/// Transfer syntax used to encode the data of an RPC call.
+///
+/// The generated clients and servers support both syntaxes unless declared with
+/// `transfer_syntax(ndr20)` or `transfer_syntax(ndr64)`, and the RPC runtime negotiates
+/// which one is used for each association. Handlers get the syntax of their call from
+/// [`server::transfer_syntax()`], and clients from `{Interface}Client::transfer_syntax()`
+/// after their first call. Clients can be restricted to one syntax with
+/// `{Interface}Client::with_transfer_syntax()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TransferSyntax {
+    /// NDR 2.0, the classic transfer syntax supported on all platforms.
+    Ndr20,
+    /// NDR64, the transfer syntax optimized for 64-bit platforms.
+    Ndr64,
+}
+
+/// Interface flag presets, to match what a particular peer or OS version expects.
+///
+/// Clients are created with a preset through `{Interface}Client::with_compatibility()`,
+/// and servers configured through `{Interface}Server::set_compatibility()` before
+/// registering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum InterfaceCompatibility {
+    /// Offers both NDR 2.0 and NDR64, with the interface flags MIDL generates for
+    /// `/protocol all`. This is the default.
+    #[default]
+    Ndr64,
+    /// Offers only NDR 2.0 without extended interface flags, like MIDL's `/protocol dce`,
+    /// for OSF DCE peers and systems that predate NDR64.
+    Legacy,
+    /// Like [`Ndr64`](Self::Ndr64), but servers register the interface with
+    /// `RPC_IF_AUTOLISTEN`, so it accepts calls as soon as it is registered without
+    /// starting the process-wide listener. Equivalent to `Ndr64` on clients.
+    AutoListen,
+}
+
+impl InterfaceCompatibility {
+    /// `RPC_CLIENT_INTERFACE::Flags`
+    #[doc(hidden)]
+    pub fn client_interface_flags(self) -> u32 {
+        use windows::Win32::System::Rpc::RPCFLG_HAS_MULTI_SYNTAXES;
+        match self {
+            InterfaceCompatibility::Legacy => 0,
+            _ => RPCFLG_HAS_MULTI_SYNTAXES,
+        }
+    }
+
+    /// `RPC_SERVER_INTERFACE::Flags`
+    #[doc(hidden)]
+    pub fn server_interface_flags(self) -> u32 {
+        use windows::Win32::System::Rpc::{RPCFLG_HAS_CALLBACK, RPCFLG_HAS_MULTI_SYNTAXES};
+        match self {
+            InterfaceCompatibility::Legacy => 0,
+            _ => RPCFLG_HAS_MULTI_SYNTAXES | RPCFLG_HAS_CALLBACK,
+        }
+    }
+
+    /// `MIDL_STUB_DESC::mFlags`
+    #[doc(hidden)]
+    pub fn stub_desc_flags(self) -> u32 {
+        use windows::Win32::System::Rpc::RPCFLG_HAS_MULTI_SYNTAXES;
+        // RPCFLG_HAS_MULTI_SYNTAXES, and bit 0 which MIDL always sets
+        match self {
+            InterfaceCompatibility::Legacy => 1,
+            _ => RPCFLG_HAS_MULTI_SYNTAXES | 1,
+        }
+    }
+
+    /// Number of transfer syntaxes offered, NDR 2.0 being the first.
+    #[doc(hidden)]
+    pub fn syntax_count(self) -> u32 {
+        match self {
+            InterfaceCompatibility::Legacy => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// A value built on first use for each [`InterfaceCompatibility`] preset, which generated
+/// clients keep their stub descriptor and format strings in, so that creating a client
+/// doesn't build them again.
+#[doc(hidden)]
+pub struct PerPreset<T>([std::sync::OnceLock<T>; 3]);
+
+impl<T> PerPreset<T> {
+    pub const fn new() -> Self {
+        Self([const { std::sync::OnceLock::new() }; 3])
+    }
+
+    pub fn get_or_init(
+        &self,
+        compatibility: InterfaceCompatibility,
+        init: impl FnOnce() -> T,
+    ) -> &T {
+        let index = match compatibility {
+            InterfaceCompatibility::Ndr64 => 0,
+            InterfaceCompatibility::Legacy => 1,
+            InterfaceCompatibility::AutoListen => 2,
+        };
+        self.0[index].get_or_init(init)
+    }
+}
+
+impl<T> Default for PerPreset<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProtocolSequence {
     fn to_pcwstr(self) -> windows::core::PCWSTR {
         match self {
             ProtocolSequence::Alpc => windows::core::w!("ncalrpc"),
+            ProtocolSequence::NamedPipe => windows::core::w!("ncacn_np"),
+            ProtocolSequence::Http => windows::core::w!("ncacn_http"),
+            ProtocolSequence::HyperVSocket => windows::core::w!("ncacn_hvsocket"),
+            ProtocolSequence::Udp => windows::core::w!("ncadg_ip_udp"),
         }
     }
+
+    /// Returns the RPC runtime's name of the protocol sequence, e.g. `ncalrpc`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolSequence::Alpc => "ncalrpc",
+            ProtocolSequence::NamedPipe => "ncacn_np",
+            ProtocolSequence::Http => "ncacn_http",
+            ProtocolSequence::HyperVSocket => "ncacn_hvsocket",
+            ProtocolSequence::Udp => "ncadg_ip_udp",
+        }
+    }
+
+    /// Returns whether the local RPC runtime supports the protocol sequence.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime fails to check the protocol sequence.
+    pub fn is_supported(self) -> windows::core::Result<bool> {
+        use windows::Win32::System::Rpc::{RPC_S_PROTSEQ_NOT_SUPPORTED, RpcNetworkIsProtseqValidW};
+        match unsafe { RpcNetworkIsProtseqValidW(self.to_pcwstr()) } {
+            RPC_S_PROTSEQ_NOT_SUPPORTED => Ok(false),
+            status => status.ok().map(|()| true),
+        }
+    }
+}
+
+/// Returns the names of the network protocol sequences the local RPC runtime supports,
+/// e.g. `ncacn_ip_tcp` or `ncacn_hvsocket`, so applications can choose a transport at
+/// startup.
+///
+/// The list comes from `RpcNetworkInqProtseqsW`, which only reports protocol sequences
+/// the network is configured for; check the local `ncalrpc` with
+/// [`ProtocolSequence::is_supported()`]. An empty list means none are available.
+///
+/// # Errors
+///
+/// Returns an error if the runtime fails to enumerate the protocol sequences.
+pub fn supported_protocol_sequences() -> windows::core::Result<Vec<String>> {
+    use windows::Win32::System::Rpc::{
+        RPC_PROTSEQ_VECTORW, RPC_S_NO_PROTSEQS, RpcNetworkInqProtseqsW, RpcProtseqVectorFreeW,
+    };
+
+    let mut vector: *mut RPC_PROTSEQ_VECTORW = std::ptr::null_mut();
+    match unsafe { RpcNetworkInqProtseqsW(&raw mut vector) } {
+        RPC_S_NO_PROTSEQS => return Ok(vec![]),
+        status => status.ok()?,
+    }
+
+    let names = unsafe {
+        let count = (*vector).Count as usize;
+        let protseqs = std::ptr::slice_from_raw_parts(
+            (&raw const (*vector).Protseq).cast::<*mut u16>(),
+            count,
+        );
+        (*protseqs)
+            .iter()
+            .map(|&name| windows::core::PCWSTR::from_raw(name).to_string())
+            .collect::<Result<Vec<_>, _>>()
+    };
+    let _ = unsafe { RpcProtseqVectorFreeW(&raw mut vector) };
+    Ok(names?)
 }
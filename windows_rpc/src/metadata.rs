@@ -0,0 +1,217 @@
+//! Interface metadata for external tooling.
+//!
+//! Every generated client and server exposes the definition of its interface as a
+//! `METADATA` associated const, which [`InterfaceMetadata::to_json()`] serializes so that
+//! fuzzers, documentation generators or monitoring configuration can consume the
+//! interface without parsing Rust.
+//!
+//! # Example
+//!
+//! ```rust
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! let metadata = CalculatorClient::METADATA;
+//! assert_eq!(metadata.methods[0].opnum, 0);
+//! println!("{}", metadata.to_json());
+//! ```
+//!
+//! The JSON has the following shape:
+//!
+//! ```json
+//! {
+//!   "name": "Calculator",
+//!   "guid": "12345678-1234-1234-1234-123456789abc",
+//!   "version": { "major": 1, "minor": 0 },
+//!   "methods": [
+//!     {
+//!       "name": "add",
+//!       "opnum": 0,
+//!       "parameters": [
+//!         { "name": "a", "rust_type": "i32", "idl_type": "long", "direction": "in" },
+//!         { "name": "b", "rust_type": "i32", "idl_type": "long", "direction": "in" }
+//!       ],
+//!       "return": { "rust_type": "i32", "idl_type": "long" },
+//!       "error": null
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `return` is `null` for methods without a return value, and `error` for methods not
+//! returning `Result<T, E>`, whose `return` is the type of the value.
+
+use std::fmt::Write;
+
+/// The definition of an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterfaceMetadata {
+    /// Name of the trait the interface was declared with.
+    pub name: &'static str,
+    /// The interface identifier.
+    pub guid: u128,
+    /// The interface version, as `(major, minor)`.
+    pub version: (u16, u16),
+    /// Methods in opnum order.
+    pub methods: &'static [MethodMetadata],
+}
+
+/// A method of an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodMetadata {
+    /// Name of the method.
+    pub name: &'static str,
+    /// Procedure number identifying the method on the wire.
+    pub opnum: u32,
+    /// Parameters in declaration order.
+    pub parameters: &'static [ParameterMetadata],
+    /// Type of the return value, if any.
+    pub return_type: Option<TypeMetadata>,
+    /// Type of the errors returned in place of the value, for methods declared as
+    /// returning `Result<T, E>`.
+    pub error_type: Option<TypeMetadata>,
+}
+
+/// A parameter of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParameterMetadata {
+    /// Name of the parameter.
+    pub name: &'static str,
+    /// Type of the parameter.
+    pub r#type: TypeMetadata,
+    /// Whether the parameter is sent to the server.
+    pub is_in: bool,
+    /// Whether the parameter is sent back to the client.
+    pub is_out: bool,
+}
+
+/// The type of a parameter or return value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeMetadata {
+    /// The type as written in the interface trait, e.g. `&str`.
+    pub rust_type: &'static str,
+    /// The equivalent MIDL type, e.g. `[string] wchar_t*`.
+    pub idl_type: &'static str,
+}
+
+impl InterfaceMetadata {
+    /// Returns the interface identifier in its canonical form,
+    /// e.g. `12345678-1234-1234-1234-123456789abc`.
+    pub fn guid_string(&self) -> String {
+        let guid = self.guid;
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            guid >> 96,
+            (guid >> 80) & 0xffff,
+            (guid >> 64) & 0xffff,
+            (guid >> 48) & 0xffff,
+            guid & 0xffff_ffff_ffff
+        )
+    }
+
+    /// Serializes the metadata as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        json.push_str("{\n");
+        let _ = writeln!(json, "  \"name\": {},", quoted(self.name));
+        let _ = writeln!(json, "  \"guid\": {},", quoted(&self.guid_string()));
+        let _ = writeln!(
+            json,
+            "  \"version\": {{ \"major\": {}, \"minor\": {} }},",
+            self.version.0, self.version.1
+        );
+        json.push_str("  \"methods\": [");
+        for (i, method) in self.methods.iter().enumerate() {
+            json.push_str(if i == 0 { "\n" } else { ",\n" });
+            method.write_json(&mut json);
+        }
+        json.push_str(if self.methods.is_empty() {
+            "]\n"
+        } else {
+            "\n  ]\n"
+        });
+        json.push('}');
+        json
+    }
+}
+
+impl MethodMetadata {
+    fn write_json(&self, json: &mut String) {
+        json.push_str("    {\n");
+        let _ = writeln!(json, "      \"name\": {},", quoted(self.name));
+        let _ = writeln!(json, "      \"opnum\": {},", self.opnum);
+        json.push_str("      \"parameters\": [");
+        for (i, param) in self.parameters.iter().enumerate() {
+            json.push_str(if i == 0 { "\n" } else { ",\n" });
+            let direction = match (param.is_in, param.is_out) {
+                (true, true) => "in_out",
+                (false, true) => "out",
+                _ => "in",
+            };
+            let _ = write!(
+                json,
+                "        {{ \"name\": {}, {}, \"direction\": {} }}",
+                quoted(param.name),
+                param.r#type.json_fields(),
+                quoted(direction)
+            );
+        }
+        json.push_str(if self.parameters.is_empty() {
+            "],\n"
+        } else {
+            "\n      ],\n"
+        });
+        match &self.return_type {
+            Some(return_type) => {
+                let _ = writeln!(
+                    json,
+                    "      \"return\": {{ {} }},",
+                    return_type.json_fields()
+                );
+            }
+            None => json.push_str("      \"return\": null,\n"),
+        }
+        match &self.error_type {
+            Some(error_type) => {
+                let _ = writeln!(json, "      \"error\": {{ {} }}", error_type.json_fields());
+            }
+            None => json.push_str("      \"error\": null\n"),
+        }
+        json.push_str("    }");
+    }
+}
+
+impl TypeMetadata {
+    fn json_fields(&self) -> String {
+        format!(
+            "\"rust_type\": {}, \"idl_type\": {}",
+            quoted(self.rust_type),
+            quoted(self.idl_type)
+        )
+    }
+}
+
+/// Quotes `value` as a JSON string.
+fn quoted(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,52 @@
+//! Remote management queries of RPC servers.
+//!
+//! Servers answer management calls from the RPC runtime itself, whatever interfaces they
+//! register, so clients and tooling can inspect an endpoint before calling it.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, mgmt};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let binding = ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")?;
+//! for (guid, (major, minor)) in mgmt::inq_if_ids(&binding)? {
+//!     println!("{guid:032x} {major}.{minor}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::RpcError;
+use crate::client_binding::ClientBinding;
+use windows::Win32::System::Rpc::{RPC_IF_ID_VECTOR, RpcIfIdVectorFree, RpcMgmtInqIfIds};
+
+/// An interface registered by a server, as `(guid, (major, minor))`.
+pub type InterfaceId = (u128, (u16, u16));
+
+/// Returns the interfaces registered by the server behind `binding`, with their versions.
+///
+/// # Errors
+///
+/// Returns the error from `RpcMgmtInqIfIds` if the server cannot be queried, e.g.
+/// `RPC_S_SERVER_UNAVAILABLE` if nothing listens on the endpoint, or `RPC_S_ACCESS_DENIED`
+/// if the server's management authorization callback rejects the query.
+pub fn inq_if_ids(binding: &ClientBinding) -> Result<Vec<InterfaceId>, RpcError> {
+    let mut vector: *mut RPC_IF_ID_VECTOR = std::ptr::null_mut();
+    unsafe {
+        let status = RpcMgmtInqIfIds(Some(binding.handle()), &mut vector);
+        if status.is_err() {
+            return Err(RpcError::from_status(status));
+        }
+
+        let count = (*vector).Count as usize;
+        let ids = std::slice::from_raw_parts((*vector).IfId.as_ptr(), count);
+        let interfaces = ids
+            .iter()
+            .map(|&id| ((*id).Uuid.to_u128(), ((*id).VersMajor, (*id).VersMinor)))
+            .collect();
+
+        let _ = RpcIfIdVectorFree(&mut vector);
+        Ok(interfaces)
+    }
+}
@@ -0,0 +1,786 @@
+//! Marshalling of user-defined types.
+//!
+//! Structs deriving [`NdrStruct`](macro@crate::NdrStruct) can be used by interfaces as
+//! `&T` input parameters, `&mut T` output parameters and `T` return values. They must be
+//! `#[repr(C)]`; structs of integer and floating point fields are copied by NDR as a block:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrStruct, rpc_interface};
+//!
+//! #[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+//! #[repr(C)]
+//! pub struct Point {
+//!     pub x: i32,
+//!     pub y: i32,
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Geometry {
+//!     fn translate(point: &Point, dx: i32, dy: i32) -> Point;
+//!     fn bounds(a: &Point, b: &Point, min: &mut Point, max: &mut Point);
+//! }
+//! ```
+//!
+//! `PCWSTR` and `PCSTR` fields, and `*const T` fields pointing to `#[ndr(size_is(...))]`
+//! numbers, make the struct an `FC_BOGUS_STRUCT` (`FC64_PSTRUCT` over NDR64) with a
+//! pointer layout, and NDR marshals the strings and arrays they point to. The server
+//! receives pointers to its own copies, valid for the duration of the call. As the stubs
+//! don't free what such structs point to, they can only be input parameters:
+//!
+//! ```rust,no_run
+//! use windows::core::{PCSTR, PCWSTR};
+//! use windows_rpc::{NdrStruct, rpc_interface};
+//!
+//! #[derive(NdrStruct, Clone, Copy)]
+//! #[repr(C)]
+//! pub struct Entry {
+//!     pub name: PCWSTR,
+//!     pub label: PCSTR,
+//!     pub count: u32,
+//!     #[ndr(size_is(count))]
+//!     pub values: *const u16,
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abe), version(1.0))]
+//! trait Catalog {
+//!     fn describe(entry: &Entry) -> String;
+//! }
+//! ```
+//!
+//! The Win32 structs `FILETIME` and `SYSTEMTIME` of the `windows` crate, and
+//! [`LARGE_INTEGER`], implement [`NdrStruct`] and are passed like them, as declared by
+//! MIDL's `wtypes.idl`:
+//!
+//! ```rust,no_run
+//! use windows::Win32::Foundation::{FILETIME, SYSTEMTIME};
+//! use windows_rpc::{LARGE_INTEGER, rpc_interface};
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abb), version(1.0))]
+//! trait Clock {
+//!     fn to_system_time(time: &FILETIME) -> SYSTEMTIME;
+//!     fn uptime(ticks: &mut LARGE_INTEGER);
+//! }
+//! ```
+//!
+//! Fieldless enums deriving [`NdrEnum`](macro@crate::NdrEnum) are passed by value, once
+//! listed in the `enums(...)` argument of the interface:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrEnum, rpc_interface};
+//!
+//! #[derive(NdrEnum, Clone, Copy, Debug, PartialEq)]
+//! #[repr(u16)]
+//! pub enum Shape {
+//!     Circle = 1,
+//!     Square = 2,
+//!     #[ndr(fallback)]
+//!     Other = 0x7fff,
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abd), version(1.0), enums(Shape))]
+//! trait Shapes {
+//!     fn sides(shape: Shape) -> u32;
+//!     fn with_sides(sides: u32) -> Shape;
+//! }
+//! ```
+//!
+//! Enums whose variants carry an integer or floating point number derive
+//! [`NdrUnion`](macro@crate::NdrUnion), and are passed like structs, as `&T` input
+//! parameters and `T` return values. NDR transmits them as encapsulated unions, the
+//! discriminant followed by the payload of the variant:
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrUnion, rpc_interface};
+//!
+//! #[derive(NdrUnion, Clone, Copy, Debug, PartialEq)]
+//! #[repr(C, u32)]
+//! pub enum Value {
+//!     Empty,
+//!     Int(i32),
+//!     Real(f64),
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abe), version(1.0))]
+//! trait Values {
+//!     fn negate(value: &Value) -> Value;
+//! }
+//! ```
+//!
+//! `Option<T>` of integers and floating point numbers, `Option<&str>` and `Option<String>`
+//! are unique pointers, which NDR transmits as null for `None`. They are input parameters
+//! and return values:
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abf), version(1.0))]
+//! trait Directory {
+//!     fn find(name: Option<&str>, max_depth: Option<u32>) -> Option<u64>;
+//!     fn owner(id: u64) -> Option<String>;
+//! }
+//! ```
+//!
+//! Arrays of integers and floating point numbers are passed as `&[T]` input parameters,
+//! `&mut [T]` output parameters and `Vec<T>` return values, with their length transmitted
+//! alongside them, or given by a `u32` parameter named by `#[rpc(size_is(length))]`.
+
+use windows::Win32::Foundation::{FILETIME, SYSTEMTIME};
+use windows::Win32::System::Rpc::{
+    NDR64_ARRAY_ELEMENT_INFO, NDR64_ARRAY_FLAGS, NDR64_CONF_ARRAY_HEADER_FORMAT,
+    NDR64_CONFORMANT_STRING_FORMAT, NDR64_ENCAPSULATED_UNION, NDR64_EXPR_VAR,
+    NDR64_NO_REPEAT_FORMAT, NDR64_POINTER_FORMAT, NDR64_POINTER_INSTANCE_HEADER_FORMAT,
+    NDR64_STRING_FLAGS, NDR64_STRING_HEADER_FORMAT, NDR64_STRUCTURE_FLAGS,
+    NDR64_STRUCTURE_HEADER_FORMAT, NDR64_UNION_ARM, NDR64_UNION_ARM_SELECTOR,
+};
+
+/// A struct that interfaces can pass by value, implemented with
+/// `#[derive(NdrStruct)]`.
+///
+/// # Safety
+///
+/// The type must be `#[repr(C)]`, consist of integer, floating point and pointer fields
+/// only (so that all bit patterns, including all zeroes, are valid), and be described by
+/// the format fragments.
+pub unsafe trait NdrStruct: Copy + 'static {
+    /// The `FC_STRUCT` type format fragment for NDR 2.0, or the `FC_BOGUS_STRUCT` one of
+    /// a struct with pointer fields, which interfaces copy into their type format string.
+    const NDR_FORMAT: &'static [u8];
+
+    /// The `FC64_STRUCT` format fragment for NDR64, or the `FC64_PSTRUCT` header of a
+    /// struct with pointer fields.
+    const NDR64_FORMAT: NDR64_STRUCTURE_HEADER_FORMAT;
+
+    /// The pointer fields of the struct, which NDR64 describes after the header: the
+    /// offset of each, and what it points to.
+    const NDR64_POINTERS: &'static [(u32, NdrPointee)] = &[];
+}
+
+/// What a pointer field of an [`NdrStruct`] points to. The pointers are unique pointers,
+/// which may be null.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NdrPointee {
+    /// A null-terminated UTF-16 string, `[string] wchar_t*`.
+    WideString,
+    /// A null-terminated ANSI string, `[string] char*`.
+    AnsiString,
+    /// An array of the elements of NDR64 format character `format_char`, as many as the
+    /// `u32` field at `size_offset` of the struct holds, `[size_is(field)] T*`.
+    Array {
+        format_char: u8,
+        element_size: u32,
+        size_offset: u32,
+    },
+}
+
+const FC_STRUCT: u8 = 0x15;
+const FC_SHORT: u8 = 0x06;
+const FC_LONG: u8 = 0x08;
+const FC_HYPER: u8 = 0x0b;
+const FC_END: u8 = 0x5b;
+const FC_PAD: u8 = 0x5c;
+const NDR64_FC_STRUCT: u8 = 0x30;
+const NDR64_FC_UP: u8 = 0x21;
+const NDR64_FC_CONF_ARRAY: u8 = 0x41;
+const NDR64_FC_CONF_CHAR_STRING: u8 = 0x63;
+const NDR64_FC_CONF_WCHAR_STRING: u8 = 0x64;
+const NDR64_FC_EXPR_VAR: u8 = 0x03;
+const NDR64_FC_UINT32: u8 = 0x06;
+const NDR64_FC_NO_REPEAT: u8 = 0x80;
+const NDR64_FC_END: u8 = 0x93;
+
+/// The `FC64_STRUCT` format of a struct of `size` bytes, aligned to `alignment`.
+const fn ndr64_struct_format(alignment: u8, size: u32) -> NDR64_STRUCTURE_HEADER_FORMAT {
+    NDR64_STRUCTURE_HEADER_FORMAT {
+        FormatCode: NDR64_FC_STRUCT,
+        Alignment: alignment - 1,
+        Flags: NDR64_STRUCTURE_FLAGS { _bitfield: 0 },
+        Reserve: 0,
+        MemorySize: size,
+    }
+}
+
+/// `FILETIME`, two `DWORD`s, as declared by MIDL's `wtypes.idl`.
+unsafe impl NdrStruct for FILETIME {
+    const NDR_FORMAT: &'static [u8] = &[FC_STRUCT, 3, 8, 0, FC_LONG, FC_LONG, FC_PAD, FC_END];
+    const NDR64_FORMAT: NDR64_STRUCTURE_HEADER_FORMAT = ndr64_struct_format(4, 8);
+}
+
+/// `SYSTEMTIME`, eight `WORD`s, as declared by MIDL's `wtypes.idl`.
+unsafe impl NdrStruct for SYSTEMTIME {
+    const NDR_FORMAT: &'static [u8] = &[
+        FC_STRUCT, 1, 16, 0, FC_SHORT, FC_SHORT, FC_SHORT, FC_SHORT, FC_SHORT, FC_SHORT, FC_SHORT,
+        FC_SHORT, FC_PAD, FC_END,
+    ];
+    const NDR64_FORMAT: NDR64_STRUCTURE_HEADER_FORMAT = ndr64_struct_format(2, 16);
+}
+
+/// A 64-bit signed integer passed as a struct, as MIDL's `wtypes.idl` declares
+/// `LARGE_INTEGER`, for interfaces mirroring Windows services that use it.
+///
+/// The `windows` crate maps `LARGE_INTEGER` to `i64`, which is sent as a base type
+/// instead, with different format strings.
+#[allow(non_camel_case_types, non_snake_case)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(C)]
+pub struct LARGE_INTEGER {
+    pub QuadPart: i64,
+}
+
+impl From<i64> for LARGE_INTEGER {
+    fn from(value: i64) -> Self {
+        Self { QuadPart: value }
+    }
+}
+
+impl From<LARGE_INTEGER> for i64 {
+    fn from(value: LARGE_INTEGER) -> Self {
+        value.QuadPart
+    }
+}
+
+unsafe impl NdrStruct for LARGE_INTEGER {
+    const NDR_FORMAT: &'static [u8] = &[FC_STRUCT, 7, 8, 0, FC_HYPER, FC_END];
+    const NDR64_FORMAT: NDR64_STRUCTURE_HEADER_FORMAT = ndr64_struct_format(8, 8);
+}
+
+/// An enum with payloads that interfaces can pass like a struct, implemented with
+/// `#[derive(NdrUnion)]`.
+///
+/// # Safety
+///
+/// The type must be a `#[repr(C, u16)]` or `#[repr(C, u32)]` enum, whose variants carry
+/// at most one integer or floating point field, and be described by the format fragments.
+pub unsafe trait NdrUnion: Copy + 'static {
+    /// The `FC_ENCAPSULATED_UNION` type format fragment for NDR 2.0, which interfaces copy
+    /// into their type format string.
+    const NDR_FORMAT: &'static [u8];
+
+    /// The `FC64_ENCAPSULATED_UNION` header of the NDR64 format.
+    const NDR64_FORMAT: NDR64_ENCAPSULATED_UNION;
+
+    /// The arms of the NDR64 format: the discriminant of each variant, and the NDR64
+    /// format character of its payload, if it has one.
+    const NDR64_ARMS: &'static [(i64, Option<u8>)];
+}
+
+/// Format fragments of a struct or union, as used by the generated interfaces.
+///
+/// Implemented for all [`NdrStruct`] types, and by `#[derive(NdrUnion)]`.
+#[doc(hidden)]
+pub trait NdrType {
+    /// The NDR 2.0 type format fragment, copied into the type format string.
+    fn ndr_format() -> &'static [u8];
+
+    /// The NDR64 format, allocated and leaked like the other runtime fragments.
+    fn ndr64_format() -> *mut core::ffi::c_void;
+
+    /// Whether the type has pointer fields, which the stubs would allocate and free with
+    /// `midl_user_allocate`, so it can only be an input parameter.
+    const HAS_POINTERS: bool = false;
+
+    /// Whether the type has arrays sized by another field, whose NDR 2.0 correlation
+    /// descriptors have no range, so it can't be a parameter of a procedure with ranged
+    /// slices.
+    const HAS_CONFORMANCE: bool = false;
+}
+
+impl<T: NdrStruct> NdrType for T {
+    fn ndr_format() -> &'static [u8] {
+        T::NDR_FORMAT
+    }
+
+    fn ndr64_format() -> *mut core::ffi::c_void {
+        if T::NDR64_POINTERS.is_empty() {
+            Box::into_raw(Box::new(T::NDR64_FORMAT)) as *mut core::ffi::c_void
+        } else {
+            ndr64_pointer_struct_format::<T>()
+        }
+    }
+
+    const HAS_POINTERS: bool = !T::NDR64_POINTERS.is_empty();
+
+    const HAS_CONFORMANCE: bool = has_array(T::NDR64_POINTERS);
+}
+
+const fn has_array(pointers: &[(u32, NdrPointee)]) -> bool {
+    let mut index = 0;
+    while index < pointers.len() {
+        if let NdrPointee::Array { .. } = pointers[index].1 {
+            return true;
+        }
+        index += 1;
+    }
+    false
+}
+
+/// Lays out the NDR64 format of a struct with pointer fields: its header, followed by
+/// the pointer layout, an instance of each pointer field with its pointer format, then
+/// `FC64_END`. The formats the pointers point to are leaked like the other runtime
+/// fragments.
+fn ndr64_pointer_struct_format<T: NdrStruct>() -> *mut core::ffi::c_void {
+    #[repr(C)]
+    struct PointerInstance {
+        no_repeat: NDR64_NO_REPEAT_FORMAT,
+        instance: NDR64_POINTER_INSTANCE_HEADER_FORMAT,
+        pointer: NDR64_POINTER_FORMAT,
+    }
+
+    #[repr(C)]
+    struct ConfArrayFormat {
+        header: NDR64_CONF_ARRAY_HEADER_FORMAT,
+        element: NDR64_ARRAY_ELEMENT_INFO,
+    }
+
+    fn leak<F>(format: F) -> *mut core::ffi::c_void {
+        Box::into_raw(Box::new(format)) as *mut core::ffi::c_void
+    }
+
+    let pointers = T::NDR64_POINTERS;
+    // Stored as `u64`s to keep the pointers of the layout aligned
+    let layout_offset = std::mem::size_of::<NDR64_STRUCTURE_HEADER_FORMAT>();
+    let end_offset = layout_offset + pointers.len() * std::mem::size_of::<PointerInstance>();
+    let size = end_offset + 1;
+    let buffer: &'static mut [u64] = Box::leak(vec![0u64; size.div_ceil(8)].into_boxed_slice());
+    let base = buffer.as_mut_ptr() as *mut u8;
+
+    unsafe {
+        (base as *mut NDR64_STRUCTURE_HEADER_FORMAT).write(T::NDR64_FORMAT);
+        let instances = base.add(layout_offset) as *mut PointerInstance;
+        for (index, &(offset, pointee)) in pointers.iter().enumerate() {
+            let pointee = match pointee {
+                NdrPointee::WideString => leak(NDR64_CONFORMANT_STRING_FORMAT {
+                    Header: NDR64_STRING_HEADER_FORMAT {
+                        FormatCode: NDR64_FC_CONF_WCHAR_STRING,
+                        Flags: NDR64_STRING_FLAGS { _bitfield: 0 },
+                        ElementSize: 2,
+                    },
+                }),
+                NdrPointee::AnsiString => leak(NDR64_CONFORMANT_STRING_FORMAT {
+                    Header: NDR64_STRING_HEADER_FORMAT {
+                        FormatCode: NDR64_FC_CONF_CHAR_STRING,
+                        Flags: NDR64_STRING_FLAGS { _bitfield: 0 },
+                        ElementSize: 1,
+                    },
+                }),
+                NdrPointee::Array {
+                    format_char,
+                    element_size,
+                    size_offset,
+                } => leak(ConfArrayFormat {
+                    header: NDR64_CONF_ARRAY_HEADER_FORMAT {
+                        FormatCode: NDR64_FC_CONF_ARRAY,
+                        Alignment: (element_size - 1) as u8,
+                        Flags: NDR64_ARRAY_FLAGS { _bitfield: 0 },
+                        Reserved: 0,
+                        ElementSize: element_size,
+                        // The array size is read from the field of the struct
+                        ConfDescriptor: leak(NDR64_EXPR_VAR {
+                            ExprType: NDR64_FC_EXPR_VAR,
+                            VarType: NDR64_FC_UINT32,
+                            Reserved: 0,
+                            Offset: size_offset,
+                        }),
+                    },
+                    element: NDR64_ARRAY_ELEMENT_INFO {
+                        ElementMemSize: element_size,
+                        Element: Box::leak(Box::new(format_char)) as *mut u8
+                            as *mut core::ffi::c_void,
+                    },
+                }),
+            };
+            instances.add(index).write(PointerInstance {
+                no_repeat: NDR64_NO_REPEAT_FORMAT {
+                    FormatCode: NDR64_FC_NO_REPEAT,
+                    Flags: 0,
+                    Reserved1: 0,
+                    Reserved2: 0,
+                },
+                instance: NDR64_POINTER_INSTANCE_HEADER_FORMAT {
+                    Offset: offset,
+                    Reserved: 0,
+                },
+                pointer: NDR64_POINTER_FORMAT {
+                    FormatCode: NDR64_FC_UP,
+                    Flags: 0,
+                    Reserved: 0,
+                    Pointee: pointee,
+                },
+            });
+        }
+        base.add(end_offset).write(NDR64_FC_END);
+    }
+    base as *mut core::ffi::c_void
+}
+
+/// Lays out the NDR64 format of a union: its header, the arm selector, the arms and the
+/// (absent) default arm, followed by the format characters the arms point to. The format
+/// is leaked like the other runtime fragments.
+#[doc(hidden)]
+pub fn ndr64_union_format<T: NdrUnion>() -> *mut core::ffi::c_void {
+    #[repr(C)]
+    struct Header {
+        union: NDR64_ENCAPSULATED_UNION,
+        selector: NDR64_UNION_ARM_SELECTOR,
+    }
+
+    let arms = T::NDR64_ARMS;
+    let format_chars: &'static [u8] = Box::leak(
+        arms.iter()
+            .map(|(_, format_char)| format_char.unwrap_or(0))
+            .collect::<Box<[u8]>>(),
+    );
+
+    // Stored as `u64`s to keep the pointers of the arms aligned
+    let arms_offset = std::mem::size_of::<Header>();
+    let default_offset = arms_offset + arms.len() * std::mem::size_of::<NDR64_UNION_ARM>();
+    let size = default_offset + std::mem::size_of::<u32>();
+    let buffer: &'static mut [u64] = Box::leak(vec![0u64; size.div_ceil(8)].into_boxed_slice());
+    let base = buffer.as_mut_ptr() as *mut u8;
+
+    unsafe {
+        (base as *mut Header).write(Header {
+            union: T::NDR64_FORMAT,
+            selector: NDR64_UNION_ARM_SELECTOR {
+                Reserved1: 0,
+                Alignment: T::NDR64_FORMAT.Alignment,
+                Reserved2: 0,
+                Arms: arms.len() as u32,
+            },
+        });
+        let arm_formats = base.add(arms_offset) as *mut NDR64_UNION_ARM;
+        for (index, &(case_value, format_char)) in arms.iter().enumerate() {
+            arm_formats.add(index).write(NDR64_UNION_ARM {
+                CaseValue: case_value,
+                // Empty arms have no format
+                Type: match format_char {
+                    Some(_) => &format_chars[index] as *const u8 as *mut core::ffi::c_void,
+                    None => std::ptr::null_mut(),
+                },
+                Reserved: 0,
+            });
+        }
+        // No default arm: other discriminants are rejected
+        (base.add(default_offset) as *mut u32).write(u32::MAX);
+    }
+    base as *mut core::ffi::c_void
+}
+
+/// Borrows a struct out parameter on the server.
+///
+/// Stubs zero the structs they allocate for out parameters, which is only a valid value
+/// of [`NdrStruct`] types, so unions can't be out parameters.
+///
+/// # Safety
+///
+/// `ptr` must point to a struct allocated by the server stub for the call.
+#[doc(hidden)]
+pub unsafe fn out_struct<'a, T: NdrStruct>(ptr: *mut T) -> &'a mut T {
+    unsafe { &mut *ptr }
+}
+
+/// Borrows a slice out parameter on the server, sized by its length parameter.
+///
+/// Stubs zero the arrays they allocate for `[out]` slices, and unmarshal `[in, out]` ones
+/// in place. Their elements are integers or floating point numbers, which all bit
+/// patterns are valid values of.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to `len` elements allocated by the server stub for the
+/// call.
+#[doc(hidden)]
+pub unsafe fn out_slice<'a, T: Copy>(ptr: *mut T, len: u32) -> &'a mut [T] {
+    if len == 0 || ptr.is_null() {
+        &mut []
+    } else {
+        unsafe { std::slice::from_raw_parts_mut(ptr, len as usize) }
+    }
+}
+
+/// A fieldless enum that interfaces can pass by value, implemented with
+/// `#[derive(NdrEnum)]`.
+///
+/// Stubs hold enums as 32-bit integers, so they are converted to and from `u32`. NDR 2.0
+/// transmits `FC_ENUM16` enums as 16 bits and rejects values from `0x8000` up, NDR64
+/// transmits all enums as 32 bits.
+pub trait NdrEnum: Copy + 'static {
+    /// `FC_ENUM16` or `FC_ENUM32`, the NDR 2.0 format character of the enum.
+    const NDR_FORMAT_CHAR: u8;
+
+    /// Returns the value of the variant.
+    fn to_wire(self) -> u32;
+
+    /// Returns the variant of `value`, the `#[ndr(fallback)]` variant if none matches, or
+    /// `None` without one.
+    fn from_wire(value: u32) -> Option<Self>;
+}
+
+/// Converts an enum parameter received by a server, faulting the call with
+/// `RPC_X_ENUM_VALUE_OUT_OF_RANGE` if it matches no variant.
+#[doc(hidden)]
+pub fn enum_from_wire<T: NdrEnum>(value: u32) -> T {
+    T::from_wire(value).unwrap_or_else(|| {
+        crate::dispatch::raise_fault(windows::Win32::Foundation::RPC_X_ENUM_VALUE_OUT_OF_RANGE)
+    })
+}
+
+/// A type that interfaces pass as the number or struct `W`, declared with
+/// `transmit_as(T = W)` on the interface, like MIDL's `[transmit_as]`.
+///
+/// Clients convert the parameters with [`to_wire()`](Self::to_wire) and the return value
+/// with [`from_wire()`](Self::from_wire), servers the other way around. The wire type is
+/// a parameter of the trait, so that it can be implemented for types of other crates
+/// when `W` is a struct of the implementing crate:
+///
+/// ```rust,no_run
+/// use windows_rpc::{NdrStruct, RpcTransmittable};
+///
+/// # mod uuid { #[derive(Clone, Copy)] pub struct Uuid(pub u128); }
+/// #[derive(NdrStruct, Clone, Copy)]
+/// #[repr(C)]
+/// pub struct WireUuid {
+///     pub high: u64,
+///     pub low: u64,
+/// }
+///
+/// impl RpcTransmittable<WireUuid> for uuid::Uuid {
+///     fn to_wire(&self) -> WireUuid {
+///         WireUuid { high: (self.0 >> 64) as u64, low: self.0 as u64 }
+///     }
+///
+///     fn from_wire(wire: WireUuid) -> Self {
+///         uuid::Uuid((u128::from(wire.high) << 64) | u128::from(wire.low))
+///     }
+/// }
+/// ```
+pub trait RpcTransmittable<W: Copy>: Sized {
+    /// Returns the value sent for `self`.
+    fn to_wire(&self) -> W;
+
+    /// Returns the value that `wire` was sent for.
+    fn from_wire(wire: W) -> Self;
+}
+
+/// Durations are sent as the number of 100-nanosecond intervals they span, the unit of
+/// `FILETIME`, saturating at `u64::MAX`.
+impl RpcTransmittable<u64> for std::time::Duration {
+    fn to_wire(&self) -> u64 {
+        u64::try_from(self.as_nanos() / 100).unwrap_or(u64::MAX)
+    }
+
+    fn from_wire(wire: u64) -> Self {
+        std::time::Duration::new(wire / 10_000_000, (wire % 10_000_000 * 100) as u32)
+    }
+}
+
+/// Points a `#[ndr(string)]` wire field at the elements of a `Vec` field, null if it's
+/// empty.
+///
+/// # Panics
+///
+/// If the vector isn't terminated by a NUL, as NDR would read past its end.
+#[doc(hidden)]
+pub fn string_field<T: Copy + Default + PartialEq>(value: &[T]) -> *const T {
+    match value.last() {
+        None => std::ptr::null(),
+        Some(last) if *last == T::default() => value.as_ptr(),
+        Some(_) => panic!("`#[ndr(string)]` fields must end with a NUL"),
+    }
+}
+
+/// Copies a `#[ndr(string)]` wire field into a `Vec`, with its NUL, empty if it's null.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+#[doc(hidden)]
+pub unsafe fn string_from_field<T: Copy + Default + PartialEq>(ptr: *const T) -> Vec<T> {
+    if ptr.is_null() {
+        return Vec::new();
+    }
+    let mut len = 0;
+    while unsafe { *ptr.add(len) } != T::default() {
+        len += 1;
+    }
+    unsafe { std::slice::from_raw_parts(ptr, len + 1) }.to_vec()
+}
+
+/// Copies a `#[ndr(size_is(field))]` wire field into a `Vec`.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to `len` elements.
+#[doc(hidden)]
+pub unsafe fn array_field<T: Copy>(ptr: *const T, len: u32) -> Vec<T> {
+    if len == 0 || ptr.is_null() {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(ptr, len as usize) }.to_vec()
+    }
+}
+
+/// Converts a struct received by a server through a full pointer, which may be null
+/// unlike the reference pointers of other struct parameters, faulting the call with
+/// `RPC_X_NULL_REF_POINTER` if it is.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a `T` that outlives `'a`.
+#[doc(hidden)]
+pub unsafe fn full_pointer_ref<'a, T>(ptr: *const T) -> &'a T {
+    unsafe { ptr.as_ref() }.unwrap_or_else(|| {
+        crate::dispatch::raise_fault(windows::Win32::Foundation::RPC_X_NULL_REF_POINTER)
+    })
+}
+
+/// Converts an enum returned by a server, failing with `RPC_X_ENUM_VALUE_OUT_OF_RANGE`
+/// if it matches no variant.
+#[doc(hidden)]
+pub fn enum_from_return<T: NdrEnum>(value: u32) -> Result<T, crate::RpcError> {
+    T::from_wire(value).ok_or_else(|| {
+        crate::RpcError::new(windows::Win32::System::Rpc::RPC_STATUS(
+            windows::Win32::Foundation::RPC_X_ENUM_VALUE_OUT_OF_RANGE,
+        ))
+    })
+}
+
+/// Borrows a handle received by a server, which the server stub duplicated into this
+/// process and closes once the call returns, faulting the call with
+/// `RPC_X_NULL_REF_POINTER` if it is null.
+///
+/// # Safety
+///
+/// `handle` must be null or a handle that stays open for `'a`.
+#[doc(hidden)]
+pub unsafe fn borrow_system_handle<'a>(
+    handle: *mut core::ffi::c_void,
+) -> std::os::windows::io::BorrowedHandle<'a> {
+    if handle.is_null() {
+        crate::dispatch::raise_fault(windows::Win32::Foundation::RPC_X_NULL_REF_POINTER)
+    }
+    unsafe { std::os::windows::io::BorrowedHandle::borrow_raw(handle) }
+}
+
+/// Takes ownership of a handle returned by a server, which the client stub duplicated
+/// into this process, failing with `RPC_X_BAD_STUB_DATA` if it is null.
+///
+/// # Safety
+///
+/// `handle` must be null or a handle that the caller owns.
+#[doc(hidden)]
+pub unsafe fn system_handle_from_return(
+    handle: *mut core::ffi::c_void,
+) -> Result<std::os::windows::io::OwnedHandle, crate::RpcError> {
+    if handle.is_null() {
+        return Err(crate::RpcError::new(
+            windows::Win32::System::Rpc::RPC_STATUS(
+                windows::Win32::Foundation::RPC_X_BAD_STUB_DATA,
+            ),
+        ));
+    }
+    Ok(unsafe { std::os::windows::io::FromRawHandle::from_raw_handle(handle) })
+}
+
+/// Converts the status written by the client stub into the result of the call, capturing
+/// the extended error information of a failed one.
+#[doc(hidden)]
+pub fn call_result(status: u32) -> Result<(), crate::RpcError> {
+    match status {
+        0 => Ok(()),
+        _ => Err(crate::RpcError::from_status(
+            windows::Win32::System::Rpc::RPC_STATUS(status as i32),
+        )),
+    }
+}
+
+/// Returns the hidden length of a slice of `len` elements, failing with
+/// `RPC_X_BAD_STUB_DATA` if it doesn't fit the 32-bit length sent on the wire.
+#[doc(hidden)]
+pub fn slice_length(len: usize) -> Result<u32, crate::RpcError> {
+    u32::try_from(len).map_err(|_| {
+        crate::RpcError::new(windows::Win32::System::Rpc::RPC_STATUS(
+            windows::Win32::Foundation::RPC_X_BAD_STUB_DATA,
+        ))
+    })
+}
+
+/// Checks that a slice of `len` elements holds the `size` its `size_is` parameter gives,
+/// which the client stub reads or writes, failing with `RPC_S_INVALID_BOUND` otherwise.
+#[doc(hidden)]
+pub fn check_size_is(size: u32, len: usize) -> Result<(), crate::RpcError> {
+    if size as usize <= len {
+        Ok(())
+    } else {
+        Err(crate::RpcError::new(
+            windows::Win32::System::Rpc::RPC_S_INVALID_BOUND,
+        ))
+    }
+}
+
+/// Copies a returned option into memory allocated with `midl_user_allocate`, returning
+/// its address, or null for `None`.
+///
+/// Also returns null if the allocation fails.
+#[doc(hidden)]
+pub fn to_midl_value<T: Copy>(value: Option<T>) -> *mut T {
+    let Some(value) = value else {
+        return std::ptr::null_mut();
+    };
+    let ptr = crate::alloc::midl_alloc(std::mem::size_of::<T>()) as *mut T;
+    if !ptr.is_null() {
+        unsafe { ptr.write(value) };
+    }
+    ptr
+}
+
+/// Converts an option returned by a server and frees it with `midl_user_free`.
+///
+/// # Safety
+///
+/// `ptr` must be null or a value allocated by `midl_user_allocate`.
+#[doc(hidden)]
+pub unsafe fn from_midl_value<T: Copy>(ptr: *mut T) -> Option<T> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    let value = unsafe { ptr.read() };
+    crate::alloc::midl_free(ptr as *mut core::ffi::c_void);
+    Some(value)
+}
+
+/// Copies a returned vector into memory allocated with `midl_user_allocate`, returning its
+/// length and address.
+///
+/// Returns an empty null array if the vector is empty or the allocation fails.
+#[doc(hidden)]
+pub fn to_midl_array<T: Copy>(values: &[T]) -> (u32, *mut T) {
+    let Ok(len) = u32::try_from(values.len()) else {
+        return (0, std::ptr::null_mut());
+    };
+    if len == 0 {
+        return (0, std::ptr::null_mut());
+    }
+    let ptr = crate::alloc::midl_alloc(std::mem::size_of_val(values)) as *mut T;
+    if ptr.is_null() {
+        return (0, std::ptr::null_mut());
+    }
+    unsafe { std::ptr::copy_nonoverlapping(values.as_ptr(), ptr, values.len()) };
+    (len, ptr)
+}
+
+/// Converts an array returned by a server and frees it with `midl_user_free`.
+///
+/// # Safety
+///
+/// `ptr` must be null or an array of `len` elements allocated by `midl_user_allocate`.
+#[doc(hidden)]
+pub unsafe fn from_midl_array<T: Copy>(ptr: *mut T, len: u32) -> Vec<T> {
+    if ptr.is_null() {
+        return Vec::new();
+    }
+
+    let result = unsafe { std::slice::from_raw_parts(ptr, len as usize) }.to_vec();
+    crate::alloc::midl_free(ptr as *mut core::ffi::c_void);
+    result
+}
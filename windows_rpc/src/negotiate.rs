@@ -0,0 +1,187 @@
+//! Client-side interface version negotiation.
+//!
+//! When a server may implement any of several versions of an interface, each declared
+//! as its own [`rpc_interface`](crate::rpc_interface) trait with the same GUID,
+//! [`Negotiated`] makes each call with the newest version and falls back to older ones
+//! when the server rejects it with `RPC_S_UNKNOWN_IF`.
+//!
+//! The version the server accepted is remembered, so later calls go straight to it,
+//! and [`Negotiate::version()`] and [`Negotiate::supports()`] tell what it provides.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::negotiate::{Negotiate, Negotiated};
+//! use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait CalculatorLegacy {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.1))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//!     fn multiply(x: i32, y: i32) -> i32;
+//! }
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let binding = ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")?;
+//! let client = Negotiated::<CalculatorClient, CalculatorLegacyClient>::connect(binding)?;
+//!
+//! // Either version serves the call
+//! let sum = client.call(|client| client.add(2, 3), |client| client.add(2, 3))?;
+//! assert_eq!(sum, 5);
+//!
+//! // Methods added by the newer version are only available when the server has it
+//! let product = client.call(|client| client.multiply(2, 3).map(Some), |_| Ok(None))?;
+//! if client.supports((1, 1)) {
+//!     assert_eq!(product, Some(6));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Chains of more than two versions nest: `Negotiated<V3Client, Negotiated<V2Client, V1Client>>`,
+//! whose fallback calls use [`Negotiated::call()`] again.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::RpcError;
+use crate::client_binding::ClientBinding;
+use crate::interface::RpcClient;
+use crate::mgmt::inq_if_ids;
+use windows::Win32::System::Rpc::RPC_S_UNKNOWN_IF;
+
+pub use crate::mgmt::InterfaceId;
+
+/// No call was accepted yet
+const UNKNOWN: u8 = 0;
+/// The server accepted a call of the newer version
+const NEWEST: u8 = 1;
+/// The server rejected the newer version and accepted a call of an older one
+const FALLBACK: u8 = 2;
+
+/// A client for whichever of two interface versions the server accepts.
+#[derive(Debug)]
+pub struct Negotiated<N, O> {
+    newest: N,
+    fallback: O,
+    state: AtomicU8,
+}
+
+impl<N: RpcClient, O: Negotiate> Negotiated<N, O> {
+    /// Creates a client for each version over copies of `binding`. The server is only
+    /// contacted by the first [`call()`](Self::call).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the binding cannot be copied.
+    pub fn connect(binding: ClientBinding) -> Result<Self, RpcError> {
+        Self::from_binding(binding)
+    }
+
+    /// Makes a call with `newest`, or with `fallback` if the server rejects the newer
+    /// version with `RPC_S_UNKNOWN_IF`. Once a call of either version succeeds, the
+    /// following calls go straight to that version.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the call that was made last, `RPC_S_UNKNOWN_IF` if the
+    /// server accepts none of the versions.
+    pub fn call<R>(
+        &self,
+        newest: impl FnOnce(&N) -> Result<R, RpcError>,
+        fallback: impl FnOnce(&O) -> Result<R, RpcError>,
+    ) -> Result<R, RpcError> {
+        if self.state.load(Ordering::Relaxed) != FALLBACK {
+            match newest(&self.newest) {
+                Err(error) if error.status() == RPC_S_UNKNOWN_IF => {}
+                result => {
+                    if result.is_ok() {
+                        self.state.store(NEWEST, Ordering::Relaxed);
+                    }
+                    return result;
+                }
+            }
+        }
+
+        let result = fallback(&self.fallback);
+        if result.is_ok() {
+            self.state.store(FALLBACK, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+/// Implemented by clients that can be selected during negotiation: every generated
+/// `{Interface}Client` and nested [`Negotiated`] chains.
+pub trait Negotiate: Sized {
+    /// Creates the client over `binding`, without contacting the server.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the binding cannot be copied for the clients of a chain.
+    fn from_binding(binding: ClientBinding) -> Result<Self, RpcError>;
+
+    /// The interface version the server accepted, as `(major, minor)`, or `None` while
+    /// no call was accepted.
+    fn version(&self) -> Option<(u16, u16)>;
+
+    /// Returns whether the accepted version provides everything `version` does, that is,
+    /// the majors are equal and the accepted minor is at least as high. Always `false`
+    /// while no call was accepted.
+    fn supports(&self, version: (u16, u16)) -> bool {
+        self.version()
+            .is_some_and(|(major, minor)| major == version.0 && minor >= version.1)
+    }
+}
+
+impl<C: RpcClient> Negotiate for C {
+    fn from_binding(binding: ClientBinding) -> Result<Self, RpcError> {
+        Ok(C::new(binding))
+    }
+
+    /// A single version is always the one used, even before a call
+    fn version(&self) -> Option<(u16, u16)> {
+        Some(C::VERSION)
+    }
+}
+
+impl<N: RpcClient, O: Negotiate> Negotiate for Negotiated<N, O> {
+    fn from_binding(binding: ClientBinding) -> Result<Self, RpcError> {
+        Ok(Self {
+            newest: N::new(binding.try_clone()?),
+            fallback: O::from_binding(binding)?,
+            state: AtomicU8::new(UNKNOWN),
+        })
+    }
+
+    fn version(&self) -> Option<(u16, u16)> {
+        match self.state.load(Ordering::Relaxed) {
+            NEWEST => Some(N::VERSION),
+            FALLBACK => self.fallback.version(),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the newest version of the interface `guid` with the given `major` version
+/// registered by the server behind `binding`, as `(major, minor)`.
+///
+/// # Errors
+///
+/// Returns `RPC_S_UNKNOWN_IF` if the server has no such version registered, or the
+/// error from `RpcMgmtInqIfIds` if the server cannot be queried.
+pub fn server_version(
+    binding: &ClientBinding,
+    guid: u128,
+    major: u16,
+) -> Result<(u16, u16), RpcError> {
+    inq_if_ids(binding)?
+        .into_iter()
+        .filter(|&(id, (server_major, _))| id == guid && server_major == major)
+        .map(|(_, version)| version)
+        .max()
+        .ok_or_else(|| RpcError::new(RPC_S_UNKNOWN_IF))
+}
@@ -0,0 +1,262 @@
+//! NDR type serialization ("pickling") without an RPC call.
+//!
+//! [`encode()`] marshals a struct deriving [`NdrStruct`](macro@crate::NdrStruct) or an enum
+//! deriving [`NdrUnion`](macro@crate::NdrUnion) into a byte buffer with the RPC runtime's
+//! serialization engine (`NdrMesTypeEncode2`), and [`decode()`] reads it back, so NDR data
+//! can be persisted or carried over custom channels. The buffers are in the standard type
+//! serialization format (MS-RPCE 2.2.6): an 8-byte common header, an 8-byte private header
+//! with the length of the data, then the NDR 2.0 data, padded to 8 bytes. Types pickled by
+//! MIDL-generated `_Encode`/`_Decode` routines with the same layout are interchangeable.
+//!
+//! ```rust,no_run
+//! use windows_rpc::{NdrStruct, pickle};
+//!
+//! #[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+//! #[repr(C)]
+//! pub struct Point {
+//!     pub x: i32,
+//!     pub y: i32,
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let bytes = pickle::encode(&Point { x: 1, y: 2 })?;
+//! assert_eq!(pickle::decode::<Point>(&bytes)?, Point { x: 1, y: 2 });
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The engine raises exceptions on malformed data, which abort the process, so
+//! [`decode()`] checks the headers, the length of the data and union discriminants first,
+//! and fails with `RPC_X_BAD_STUB_DATA` instead.
+
+use std::ffi::c_void;
+use std::mem::MaybeUninit;
+
+use windows::Win32::Foundation::RPC_X_BAD_STUB_DATA;
+use windows::Win32::System::Rpc::RPC_STATUS;
+use windows::core::Error;
+use windows_sys::Win32::System::Rpc::{
+    MIDL_STUB_DESC, MIDL_STUB_DESC_0, MIDL_TYPE_PICKLING_INFO, MesDecodeBufferHandleCreate,
+    MesEncodeDynBufferHandleCreate, MesHandleFree, NdrMesTypeDecode2, NdrMesTypeEncode2,
+    RPC_CLIENT_INTERFACE, RPC_SYNTAX_IDENTIFIER, RPC_VERSION,
+};
+
+use crate::ndr::NdrType;
+
+/// Signature and version of `MIDL_TYPE_PICKLING_INFO` ("TP 1")
+const PICKLING_VERSION: u32 = 0x3320_5054;
+/// `Oicf` and `NewCorrDesc` pickling flags, as set by MIDL
+const PICKLING_FLAGS: u32 = 0x3;
+/// Version of the common header of the serialization format
+const HEADER_VERSION: u8 = 1;
+/// Little-endian data representation
+const HEADER_LITTLE_ENDIAN: u8 = 0x10;
+/// Length of the common header, followed by the 8-byte private header
+const COMMON_HEADER_LENGTH: u16 = 8;
+const HEADERS_LENGTH: usize = 16;
+
+const RPC_TRANSFER_SYNTAX_NDR_GUID: u128 = 0x8A885D04_1CEB_11C9_9FE8_08002B104860;
+
+const FC_STRUCT: u8 = 0x15;
+const FC_ENCAPSULATED_UNION: u8 = 0x2a;
+
+/// Serializes `value` into a buffer in the type serialization format.
+pub fn encode<T: NdrType>(value: &T) -> Result<Vec<u8>, Error> {
+    let format = T::ndr_format();
+    let interface = pickling_interface();
+    let stub_desc = stub_desc(&interface, format);
+
+    let mut buffer: *mut i8 = std::ptr::null_mut();
+    let mut size = 0u32;
+    let mut handle: *mut c_void = std::ptr::null_mut();
+    RPC_STATUS(unsafe { MesEncodeDynBufferHandleCreate(&mut buffer, &mut size, &mut handle) })
+        .ok()?;
+    unsafe {
+        NdrMesTypeEncode2(
+            handle,
+            &PICKLING_INFO,
+            &stub_desc,
+            format.as_ptr() as *mut u8,
+            value as *const T as *const c_void,
+        );
+        MesHandleFree(handle);
+    }
+
+    // The engine allocates the buffer with the stub descriptor's allocator
+    let encoded = unsafe { std::slice::from_raw_parts(buffer as *const u8, size as usize) };
+    let encoded = encoded.to_vec();
+    crate::alloc::midl_free(buffer as *mut c_void);
+    Ok(encoded)
+}
+
+/// Deserializes a `T` from a buffer in the type serialization format.
+pub fn decode<T: NdrType>(data: &[u8]) -> Result<T, Error> {
+    let format = T::ndr_format();
+    if !is_complete(format, data) {
+        return Err(Error::from_hresult(
+            RPC_STATUS(RPC_X_BAD_STUB_DATA).to_hresult(),
+        ));
+    }
+    let interface = pickling_interface();
+    let stub_desc = stub_desc(&interface, format);
+
+    // The engine requires the buffer to be 8-byte aligned
+    let mut aligned = vec![0u64; data.len().div_ceil(8)];
+    unsafe {
+        std::ptr::copy_nonoverlapping(data.as_ptr(), aligned.as_mut_ptr() as *mut u8, data.len())
+    };
+
+    let mut handle: *mut c_void = std::ptr::null_mut();
+    RPC_STATUS(unsafe {
+        MesDecodeBufferHandleCreate(
+            aligned.as_ptr() as *const u8,
+            data.len() as u32,
+            &mut handle,
+        )
+    })
+    .ok()?;
+    let mut value = MaybeUninit::<T>::zeroed();
+    unsafe {
+        NdrMesTypeDecode2(
+            handle,
+            &PICKLING_INFO,
+            &stub_desc,
+            format.as_ptr() as *mut u8,
+            value.as_mut_ptr() as *mut c_void,
+        );
+        MesHandleFree(handle);
+        Ok(value.assume_init())
+    }
+}
+
+static PICKLING_INFO: MIDL_TYPE_PICKLING_INFO = MIDL_TYPE_PICKLING_INFO {
+    Version: PICKLING_VERSION,
+    Flags: PICKLING_FLAGS,
+    Reserved: [0; 3],
+};
+
+/// The interface the engine takes the transfer syntax from. The type serialization
+/// headers don't identify the interface, so it is nil.
+fn pickling_interface() -> RPC_CLIENT_INTERFACE {
+    RPC_CLIENT_INTERFACE {
+        Length: std::mem::size_of::<RPC_CLIENT_INTERFACE>() as u32,
+        InterfaceId: RPC_SYNTAX_IDENTIFIER {
+            SyntaxGUID: windows_sys::core::GUID::from_u128(0),
+            SyntaxVersion: RPC_VERSION {
+                MajorVersion: 0,
+                MinorVersion: 0,
+            },
+        },
+        TransferSyntax: RPC_SYNTAX_IDENTIFIER {
+            SyntaxGUID: windows_sys::core::GUID::from_u128(RPC_TRANSFER_SYNTAX_NDR_GUID),
+            SyntaxVersion: RPC_VERSION {
+                MajorVersion: 2,
+                MinorVersion: 0,
+            },
+        },
+        DispatchTable: std::ptr::null_mut(),
+        RpcProtseqEndpointCount: 0,
+        RpcProtseqEndpoint: std::ptr::null_mut(),
+        Reserved: 0,
+        InterpreterInfo: std::ptr::null(),
+        Flags: 0,
+    }
+}
+
+/// A stub descriptor whose type format string is the fragment of the pickled type
+fn stub_desc(interface: &RPC_CLIENT_INTERFACE, format: &'static [u8]) -> MIDL_STUB_DESC {
+    MIDL_STUB_DESC {
+        RpcInterfaceInformation: interface as *const RPC_CLIENT_INTERFACE as *mut c_void,
+        pfnAllocate: Some(crate::alloc::midl_alloc),
+        pfnFree: Some(crate::alloc::midl_free),
+        IMPLICIT_HANDLE_INFO: MIDL_STUB_DESC_0 {
+            pAutoHandle: std::ptr::null_mut(),
+        },
+        apfnNdrRundownRoutines: std::ptr::null(),
+        aGenericBindingRoutinePairs: std::ptr::null(),
+        apfnExprEval: std::ptr::null(),
+        aXmitQuintuple: std::ptr::null(),
+        pFormatTypes: format.as_ptr(),
+        fCheckBounds: 1,
+        Version: 0x60001 as _,
+        pMallocFreeStruct: std::ptr::null_mut(),
+        MIDLVersion: 0x8010274 as _,
+        CommFaultOffsets: std::ptr::null(),
+        aUserMarshalQuadruple: std::ptr::null(),
+        NotifyRoutineTable: std::ptr::null(),
+        mFlags: 1 as _,
+        CsRoutineTables: std::ptr::null(),
+        ProxyServerInfo: std::ptr::null_mut(),
+        pExprInfo: std::ptr::null(),
+    }
+}
+
+/// Whether `data` holds the headers and enough data for a type described by the
+/// `format` fragment, with a known discriminant for unions
+fn is_complete(format: &[u8], data: &[u8]) -> bool {
+    let read_u16 =
+        |bytes: &[u8], offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+    let read_u32 = |bytes: &[u8], offset: usize| {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    };
+
+    if data.len() < HEADERS_LENGTH
+        || data[0] != HEADER_VERSION
+        || data[1] != HEADER_LITTLE_ENDIAN
+        || read_u16(data, 2) != COMMON_HEADER_LENGTH
+    {
+        return false;
+    }
+    let object_length = read_u32(data, 8) as usize;
+    let Some(object) = data[HEADERS_LENGTH..].get(..object_length) else {
+        return false;
+    };
+
+    match format[0] {
+        FC_STRUCT => object.len() >= usize::from(read_u16(format, 2)),
+        FC_ENCAPSULATED_UNION => {
+            let Some(discriminant_size) = base_type_size(format[1] & 0x0f) else {
+                return false;
+            };
+            let Some(discriminant) = object.get(..discriminant_size) else {
+                return false;
+            };
+            let mut discriminant_bytes = [0u8; 4];
+            discriminant_bytes[..discriminant_size].copy_from_slice(discriminant);
+            let discriminant = u32::from_le_bytes(discriminant_bytes);
+
+            let arm_count = usize::from(read_u16(format, 4) & 0x0fff);
+            (0..arm_count)
+                .map(|arm| 6 + 6 * arm)
+                .find(|&offset| read_u32(format, offset) == discriminant)
+                .is_some_and(|offset| {
+                    // The payload follows the discriminant, aligned to its size
+                    let payload_size = match read_u16(format, offset + 4) {
+                        0 => 0,
+                        description => match base_type_size(description as u8) {
+                            Some(size) => size,
+                            None => return false,
+                        },
+                    };
+                    object.len()
+                        >= discriminant_size.next_multiple_of(payload_size.max(1)) + payload_size
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Size of a base type by its format character
+fn base_type_size(format_char: u8) -> Option<usize> {
+    match format_char {
+        // FC_BYTE, FC_CHAR, FC_SMALL, FC_USMALL
+        0x01..=0x04 => Some(1),
+        // FC_WCHAR, FC_SHORT, FC_USHORT
+        0x05..=0x07 => Some(2),
+        // FC_LONG, FC_ULONG, FC_FLOAT
+        0x08..=0x0a => Some(4),
+        // FC_HYPER, FC_DOUBLE
+        0x0b | 0x0c => Some(8),
+        _ => None,
+    }
+}
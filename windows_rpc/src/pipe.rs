@@ -0,0 +1,364 @@
+//! Pipes, which stream numbers in chunks instead of sending them as one buffer.
+//!
+//! An `InPipe<T>` parameter streams elements from the client to the server, and an
+//! `OutPipe<T>` one from the server back to the client, once the other parameters are
+//! sent. Clients pass a [`PipeSource`] that the elements to send are pulled from, e.g. any
+//! iterator, or a [`PipeSink`] that the received elements are pushed to, e.g. a `Vec<T>`.
+//! Handlers read `[in]` pipes through a [`PipeReader`], an iterator over the chunks it
+//! receives, and write `[out]` pipes through a [`PipeWriter`]. Byte pipes are also
+//! `io::Read` and `io::Write`.
+//!
+//! Handlers must read their `[in]` pipes before writing to their `[out]` pipes, in the
+//! order of the parameters, or the runtime faults the call. Whatever a handler leaves of
+//! its `[in]` pipes is read once it returns, and its `[out]` pipes are then ended.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::pipe::{PipeReader, PipeWriter};
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Storage {
+//!     fn upload(data: InPipe<u8>) -> u64;
+//!     fn download(size: u32, data: OutPipe<u8>);
+//! }
+//!
+//! struct StorageImpl;
+//! impl StorageServerImpl for StorageImpl {
+//!     fn upload(data: &mut PipeReader<'_, u8>) -> u64 {
+//!         data.map(|chunk| chunk.len() as u64).sum()
+//!     }
+//!
+//!     fn download(size: u32, data: &mut PipeWriter<'_, u8>) {
+//!         for _ in 0..size / 4096 {
+//!             data.push(&[0xaa; 4096]);
+//!         }
+//!     }
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! # let binding = windows_rpc::client_binding::ClientBinding::new(
+//! #     windows_rpc::ProtocolSequence::Alpc,
+//! #     "storage",
+//! # )?;
+//! let client = StorageClient::new(binding);
+//! let size = client.upload(&mut std::iter::repeat_n(7u8, 1 << 20)).unwrap();
+//! let mut received: Vec<u8> = vec![];
+//! client.download(1 << 20, &mut received).unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::any::Any;
+use std::ffi::c_void;
+use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
+
+use windows::Win32::System::Rpc::RPC_S_CALL_FAILED;
+
+use crate::dispatch;
+
+/// Number of bytes [`PipeReader`] receives per chunk when iterated
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The procedures pulling and pushing the elements of a pipe, and their state, laid out
+/// like the C structure of a MIDL pipe type, e.g. `typedef pipe long LONG_PIPE;`
+#[repr(C)]
+struct RawPipe<T> {
+    pull: unsafe extern "system-unwind" fn(*mut c_void, *mut T, u32, *mut u32),
+    push: unsafe extern "system-unwind" fn(*mut c_void, *mut T, u32),
+    alloc: unsafe extern "system-unwind" fn(*mut c_void, u32, *mut *mut T, *mut u32),
+    state: *mut c_void,
+}
+
+/// The elements a client sends through an `[in]` pipe.
+///
+/// Implemented by iterators, which send their items.
+pub trait PipeSource<T> {
+    /// Writes the next elements to send at the start of `buffer`, returning how many were
+    /// written, or 0 once all of them were sent.
+    fn pull(&mut self, buffer: &mut [T]) -> usize;
+}
+
+impl<T, I: Iterator<Item = T>> PipeSource<T> for I {
+    fn pull(&mut self, buffer: &mut [T]) -> usize {
+        let mut count = 0;
+        for (slot, element) in buffer.iter_mut().zip(self) {
+            *slot = element;
+            count += 1;
+        }
+        count
+    }
+}
+
+/// The receiver of the elements a server sends through an `[out]` pipe.
+///
+/// Implemented by `Vec<T>`, which appends them.
+pub trait PipeSink<T> {
+    /// Receives the next chunk of elements.
+    fn push(&mut self, chunk: &[T]);
+}
+
+impl<T: Copy> PipeSink<T> for Vec<T> {
+    fn push(&mut self, chunk: &[T]) {
+        self.extend_from_slice(chunk);
+    }
+}
+
+/// The client's end of a pipe for the duration of a call, as the pipe structure the client
+/// stub pulls the elements to send from, or pushes the received ones to.
+#[doc(hidden)]
+pub struct ClientPipe<'a, T> {
+    /// Boxed, as the pipe structure points to it
+    state: *mut ClientPipeState<'a, T>,
+}
+
+struct ClientPipeState<'a, T> {
+    raw: RawPipe<T>,
+    end: ClientEnd<'a, T>,
+    /// The buffer the client stub has the elements written to, or read from
+    buffer: Vec<T>,
+    /// The panic of the source or sink, which aborted the call
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+enum ClientEnd<'a, T> {
+    Source(&'a mut dyn PipeSource<T>),
+    Sink(&'a mut dyn PipeSink<T>),
+}
+
+impl<'a, T: Copy + Default> ClientPipe<'a, T> {
+    /// Sends the elements of `source` through an `[in]` pipe.
+    pub fn source(source: &'a mut dyn PipeSource<T>) -> Self {
+        Self::new(ClientEnd::Source(source))
+    }
+
+    /// Receives the elements of an `[out]` pipe into `sink`.
+    pub fn sink(sink: &'a mut dyn PipeSink<T>) -> Self {
+        Self::new(ClientEnd::Sink(sink))
+    }
+
+    fn new(end: ClientEnd<'a, T>) -> Self {
+        let state = Box::into_raw(Box::new(ClientPipeState {
+            raw: RawPipe {
+                pull: client_pull::<T>,
+                push: client_push::<T>,
+                alloc: client_alloc::<T>,
+                state: std::ptr::null_mut(),
+            },
+            end,
+            buffer: Vec::new(),
+            panic: None,
+        }));
+        unsafe { (*state).raw.state = state.cast() };
+        Self { state }
+    }
+
+    /// Returns the pipe structure to pass to the client stub.
+    pub fn as_raw(&self) -> *mut c_void {
+        unsafe { (&raw mut (*self.state).raw).cast() }
+    }
+
+    /// Resumes the panic of the source or sink, if it aborted the call.
+    pub fn finish(self) {
+        if let Some(payload) = unsafe { (*self.state).panic.take() } {
+            std::panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl<T> Drop for ClientPipe<'_, T> {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.state) });
+    }
+}
+
+/// Runs a source or sink, aborting the call if it panics, with the panic kept for
+/// [`ClientPipe::finish()`] to resume once the client stub returned
+fn guard<R>(panic: &mut Option<Box<dyn Any + Send>>, f: impl FnOnce() -> R) -> R {
+    match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            *panic = Some(payload);
+            dispatch::raise_exception(RPC_S_CALL_FAILED.0)
+        }
+    }
+}
+
+/// Pull procedure of client `[in]` pipes, filling `buffer` with up to `capacity` elements
+unsafe extern "system-unwind" fn client_pull<T>(
+    state: *mut c_void,
+    buffer: *mut T,
+    capacity: u32,
+    count: *mut u32,
+) {
+    let state = unsafe { &mut *state.cast::<ClientPipeState<'_, T>>() };
+    let ClientEnd::Source(source) = &mut state.end else {
+        dispatch::raise_exception(RPC_S_CALL_FAILED.0);
+    };
+    // The client stub pulls into the buffer `client_alloc()` gave it
+    let buffer = unsafe { std::slice::from_raw_parts_mut(buffer, capacity as usize) };
+    let pulled = guard(&mut state.panic, || source.pull(buffer));
+    unsafe { *count = pulled.min(buffer.len()) as u32 };
+}
+
+/// Push procedure of client `[out]` pipes, receiving `count` elements, or the end of the
+/// pipe when 0
+unsafe extern "system-unwind" fn client_push<T>(state: *mut c_void, buffer: *mut T, count: u32) {
+    let state = unsafe { &mut *state.cast::<ClientPipeState<'_, T>>() };
+    let ClientEnd::Sink(sink) = &mut state.end else {
+        dispatch::raise_exception(RPC_S_CALL_FAILED.0);
+    };
+    if count == 0 {
+        return;
+    }
+    let chunk = unsafe { std::slice::from_raw_parts(buffer, count as usize) };
+    guard(&mut state.panic, || sink.push(chunk));
+}
+
+/// Alloc procedure of client pipes, lending the client stub a buffer of about `size` bytes
+unsafe extern "system-unwind" fn client_alloc<T: Copy + Default>(
+    state: *mut c_void,
+    size: u32,
+    buffer: *mut *mut T,
+    allocated: *mut u32,
+) {
+    let state = unsafe { &mut *state.cast::<ClientPipeState<'_, T>>() };
+    let elements = (size as usize / size_of::<T>()).max(1);
+    state.buffer.resize(elements, T::default());
+    unsafe {
+        *buffer = state.buffer.as_mut_ptr();
+        *allocated = (elements * size_of::<T>()) as u32;
+    }
+}
+
+/// A handler's end of an `[in]` pipe, receiving the elements the client sends.
+///
+/// As an iterator, it yields the chunks received until the pipe ends. Failures to receive,
+/// e.g. when the client goes away, fault the call.
+pub struct PipeReader<'a, T> {
+    pipe: *mut RawPipe<T>,
+    ended: bool,
+    _call: PhantomData<&'a mut ()>,
+}
+
+impl<T: Copy + Default> PipeReader<'_, T> {
+    /// Reads the pipe the server stub passed.
+    ///
+    /// # Safety
+    ///
+    /// `pipe` must be the `[in]` pipe of the call being handled on this thread, of elements
+    /// `T`, and outlive the reader.
+    #[doc(hidden)]
+    pub unsafe fn new(pipe: *mut c_void) -> Self {
+        Self {
+            pipe: pipe.cast(),
+            ended: false,
+            _call: PhantomData,
+        }
+    }
+
+    /// Receives the next elements into the start of `buffer`, returning how many were
+    /// received, or 0 once the pipe ended. An empty buffer receives nothing.
+    pub fn pull(&mut self, buffer: &mut [T]) -> usize {
+        if self.ended || buffer.is_empty() {
+            return 0;
+        }
+        let capacity = buffer.len().min(u32::MAX as usize) as u32;
+        let mut count = 0;
+        unsafe {
+            let pipe = &*self.pipe;
+            (pipe.pull)(pipe.state, buffer.as_mut_ptr(), capacity, &raw mut count);
+        }
+        self.ended = count == 0;
+        count as usize
+    }
+
+    /// Whether all the elements were received.
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Receives what the handler left of the pipe, which the server stub requires before
+    /// the next pipe is read or the reply is sent.
+    #[doc(hidden)]
+    pub fn finish(&mut self) {
+        let mut buffer = vec![T::default(); (CHUNK_SIZE / size_of::<T>()).max(1)];
+        while self.pull(&mut buffer) != 0 {}
+    }
+}
+
+impl<T: Copy + Default> Iterator for PipeReader<'_, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        let mut chunk = vec![T::default(); (CHUNK_SIZE / size_of::<T>()).max(1)];
+        let count = self.pull(&mut chunk);
+        chunk.truncate(count);
+        (count != 0).then_some(chunk)
+    }
+}
+
+impl std::io::Read for PipeReader<'_, u8> {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        Ok(self.pull(buffer))
+    }
+}
+
+/// A handler's end of an `[out]` pipe, sending elements to the client.
+///
+/// Failures to send, e.g. when the client goes away, fault the call.
+pub struct PipeWriter<'a, T> {
+    pipe: *mut RawPipe<T>,
+    _call: PhantomData<&'a mut ()>,
+}
+
+impl<T> PipeWriter<'_, T> {
+    /// Writes to the pipe the server stub passed.
+    ///
+    /// # Safety
+    ///
+    /// `pipe` must be the `[out]` pipe of the call being handled on this thread, of
+    /// elements `T`, and outlive the writer.
+    #[doc(hidden)]
+    pub unsafe fn new(pipe: *mut c_void) -> Self {
+        Self {
+            pipe: pipe.cast(),
+            _call: PhantomData,
+        }
+    }
+
+    /// Sends `chunk` to the client. Empty chunks aren't sent, as they would end the pipe.
+    pub fn push(&mut self, chunk: &[T]) {
+        for part in chunk.chunks(u32::MAX as usize) {
+            self.push_raw(part);
+        }
+    }
+
+    /// Ends the pipe, which the server stub requires before the next pipe is written or
+    /// the reply is sent.
+    #[doc(hidden)]
+    pub fn finish(&mut self) {
+        self.push_raw(&[]);
+    }
+
+    fn push_raw(&mut self, chunk: &[T]) {
+        unsafe {
+            let pipe = &*self.pipe;
+            // The stub only reads the elements
+            (pipe.push)(pipe.state, chunk.as_ptr().cast_mut(), chunk.len() as u32);
+        }
+    }
+}
+
+impl std::io::Write for PipeWriter<'_, u8> {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        self.push(buffer);
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
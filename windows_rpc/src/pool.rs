@@ -0,0 +1,103 @@
+//! Per-thread clients for multi-threaded callers.
+//!
+//! Calls made on one binding handle from several threads share the handle's state in
+//! the RPC runtime. A [`BindingPool`] gives each calling thread its own client, over an
+//! independent copy of the binding made with `RpcBindingCopy`, so threads making many
+//! calls do not contend on a single handle.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::thread;
+//! use windows_rpc::pool::BindingPool;
+//! use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let binding = ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")?;
+//! let pool = BindingPool::<CalculatorClient>::new(binding);
+//! thread::scope(|scope| {
+//!     for i in 0..4 {
+//!         let pool = &pool;
+//!         scope.spawn(move || pool.client()?.add(i, i));
+//!     }
+//! });
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+
+use windows::core::Result;
+
+use crate::client_binding::ClientBinding;
+use crate::interface::RpcClient;
+
+/// Hands out a client per thread, each over its own copy of a binding.
+///
+/// The client of a thread is created on its first [`client()`](Self::client) call and
+/// kept until the pool is dropped or [`clear()`](Self::clear)ed, so pools suit a fixed
+/// set of threads, like a thread pool, rather than short-lived threads.
+pub struct BindingPool<C: RpcClient> {
+    binding: ClientBinding,
+    clients: Mutex<HashMap<ThreadId, Arc<C>>>,
+}
+
+// The runtime lets binding handles be used and copied from any thread
+unsafe impl<C: RpcClient + Send + Sync> Send for BindingPool<C> {}
+unsafe impl<C: RpcClient + Send + Sync> Sync for BindingPool<C> {}
+
+impl<C: RpcClient> BindingPool<C> {
+    /// Creates a pool whose clients use copies of `binding`.
+    pub fn new(binding: ClientBinding) -> Self {
+        Self {
+            binding,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the client of the current thread, creating it over a new copy of the
+    /// binding on the thread's first call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the binding cannot be copied.
+    pub fn client(&self) -> Result<Arc<C>> {
+        let thread = thread::current().id();
+        if let Some(client) = self.clients.lock().unwrap().get(&thread) {
+            return Ok(client.clone());
+        }
+
+        // Copied without holding the lock, no other call creates this thread's client
+        let client = Arc::new(C::new(self.binding.try_clone()?));
+        self.clients.lock().unwrap().insert(thread, client.clone());
+        Ok(client)
+    }
+
+    /// Returns the binding the clients' bindings are copied from.
+    pub fn binding(&self) -> &ClientBinding {
+        &self.binding
+    }
+
+    /// Returns the number of clients created so far.
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    /// Returns whether no client was created yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the pool's clients, e.g. after their threads exited. Clients still in use
+    /// are freed once their last `Arc` is dropped.
+    pub fn clear(&self) {
+        self.clients.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,276 @@
+//! Retrying client calls that fail transiently.
+//!
+//! A busy or restarting server rejects calls with statuses like
+//! `RPC_S_SERVER_TOO_BUSY` or `RPC_S_SERVER_UNAVAILABLE`. A [`RetryPolicy`] decides
+//! which statuses are worth retrying and how long to wait between attempts, and a
+//! [`ResilientClient`] applies it to the calls of a generated client. A
+//! [`RobustClient`] also recreates the binding when the server is unavailable, so calls
+//! reach a server process that restarted and recreated its endpoint.
+//!
+//! Only retry statuses that mean the server did not execute the call: retrying a call
+//! that timed out or was canceled may run it twice.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::retry::{ResilientClient, RetryPolicy};
+//! use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! # fn main() -> Result<(), windows_rpc::RpcError> {
+//! let binding = ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")?;
+//! let client = ResilientClient::new(CalculatorClient::new(binding), RetryPolicy::default());
+//! let sum = client.call(|client| client.add(1, 2))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+
+use windows::Win32::System::Rpc::{RPC_S_SERVER_TOO_BUSY, RPC_S_SERVER_UNAVAILABLE, RPC_STATUS};
+
+use crate::RpcError;
+use crate::client_binding::ClientBinding;
+use crate::interface::RpcClient;
+
+/// How often and when to retry a failed call.
+///
+/// The wait before retry `n` (counting from 0) is `initial_backoff * multiplier^n`,
+/// capped at `max_backoff`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use windows_rpc::retry::RetryPolicy;
+///
+/// let policy = RetryPolicy {
+///     attempts: 5,
+///     initial_backoff: Duration::from_millis(50),
+///     ..RetryPolicy::default()
+/// };
+/// assert_eq!(policy.backoff(2), Duration::from_millis(200));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts made in total, including the first one. Defaults to 3; 0 and 1 make a
+    /// single attempt.
+    pub attempts: u32,
+    /// Wait before the first retry. Defaults to 100 ms.
+    pub initial_backoff: Duration,
+    /// Factor the wait grows by after each retry. Defaults to 2.
+    pub multiplier: u32,
+    /// Longest wait between attempts. Defaults to 5 s.
+    pub max_backoff: Duration,
+    /// Statuses that are retried; any other error is returned right away. Defaults to
+    /// `RPC_S_SERVER_TOO_BUSY` and `RPC_S_SERVER_UNAVAILABLE`.
+    pub retryable: Vec<RPC_STATUS>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            multiplier: 2,
+            max_backoff: Duration::from_secs(5),
+            retryable: vec![RPC_S_SERVER_TOO_BUSY, RPC_S_SERVER_UNAVAILABLE],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt.
+    pub fn never() -> Self {
+        Self {
+            attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Returns whether `error` is worth retrying.
+    pub fn is_retryable(&self, error: &RpcError) -> bool {
+        self.retryable.contains(&error.status())
+    }
+
+    /// Returns the wait before retry `retry`, counting from 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(retry);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Runs `call` until it succeeds, fails with a status that is not retryable, or
+    /// runs out of attempts, sleeping between attempts.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last attempt.
+    pub fn run<T>(&self, mut call: impl FnMut() -> Result<T, RpcError>) -> Result<T, RpcError> {
+        let mut retry = 0;
+        loop {
+            match call() {
+                Err(error) if retry + 1 < self.attempts && self.is_retryable(&error) => {
+                    thread::sleep(self.backoff(retry));
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// A generated client whose calls are retried according to a [`RetryPolicy`].
+///
+/// Calls go through [`call()`](Self::call), which passes the client to a closure making
+/// the actual call, so every method of the client can be retried.
+pub struct ResilientClient<C> {
+    client: C,
+    policy: RetryPolicy,
+}
+
+impl<C> ResilientClient<C> {
+    /// Wraps `client`, retrying its calls according to `policy`.
+    pub fn new(client: C, policy: RetryPolicy) -> Self {
+        Self { client, policy }
+    }
+
+    /// Makes the call of `call` on the client, retrying it according to the policy.
+    ///
+    /// `call` runs once per attempt, so it must not consume anything it cannot make
+    /// again.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last attempt.
+    pub fn call<T>(&self, mut call: impl FnMut(&C) -> Result<T, RpcError>) -> Result<T, RpcError> {
+        self.policy.run(|| call(&self.client))
+    }
+
+    /// Returns the wrapped client, for calls that must not be retried.
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    /// Returns the retry policy.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Replaces the retry policy.
+    pub fn set_policy(&mut self, policy: RetryPolicy) {
+        self.policy = policy;
+    }
+
+    /// Unwraps the client.
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+}
+
+/// Creates the bindings of a [`RobustClient`].
+type Connect = Box<dyn Fn() -> windows::core::Result<ClientBinding> + Send + Sync>;
+
+/// A generated client that reconnects to a restarted server.
+///
+/// When a call fails with `RPC_S_SERVER_UNAVAILABLE`, the client is recreated with a new
+/// binding from `connect` before the call is retried, as the old binding may still refer
+/// to the association with the server process that went away. Calls are retried
+/// according to a [`RetryPolicy`], which should leave the server enough time to come
+/// back.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use windows_rpc::retry::{RetryPolicy, RobustClient};
+/// use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+///
+/// #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+/// trait Calculator {
+///     fn add(a: i32, b: i32) -> i32;
+/// }
+///
+/// # fn main() -> Result<(), windows_rpc::RpcError> {
+/// let policy = RetryPolicy {
+///     attempts: 10,
+///     max_backoff: Duration::from_secs(2),
+///     ..RetryPolicy::default()
+/// };
+/// let client = RobustClient::<CalculatorClient>::new(policy, || {
+///     ClientBinding::new(ProtocolSequence::Alpc, "calculator_endpoint")
+/// })?;
+/// let sum = client.call(|client| client.add(1, 2))?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RobustClient<C: RpcClient> {
+    client: RwLock<C>,
+    connect: Connect,
+    policy: RetryPolicy,
+}
+
+impl<C: RpcClient> RobustClient<C> {
+    /// Creates a client over a binding from `connect`, which is called again to
+    /// reconnect.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `connect`.
+    pub fn new(
+        policy: RetryPolicy,
+        connect: impl Fn() -> windows::core::Result<ClientBinding> + Send + Sync + 'static,
+    ) -> windows::core::Result<Self> {
+        let client = C::new(connect()?);
+        Ok(Self {
+            client: RwLock::new(client),
+            connect: Box::new(connect),
+            policy,
+        })
+    }
+
+    /// Makes the call of `call` on the client, reconnecting and retrying it according
+    /// to the policy.
+    ///
+    /// `call` runs once per attempt, so it must not consume anything it cannot make
+    /// again.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last attempt, which is the error of `connect` if the
+    /// client could not be recreated.
+    pub fn call<T>(&self, mut call: impl FnMut(&C) -> Result<T, RpcError>) -> Result<T, RpcError> {
+        let mut reconnect = false;
+        self.policy.run(|| {
+            if reconnect {
+                self.reconnect()?;
+            }
+            let result = call(&self.client.read().unwrap());
+            reconnect = matches!(&result, Err(error) if error.status() == RPC_S_SERVER_UNAVAILABLE);
+            result
+        })
+    }
+
+    /// Recreates the client with a new binding from `connect`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `connect`, keeping the current client.
+    pub fn reconnect(&self) -> windows::core::Result<()> {
+        let client = C::new((self.connect)()?);
+        *self.client.write().unwrap() = client;
+        Ok(())
+    }
+
+    /// Returns the retry policy.
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+}
@@ -0,0 +1,228 @@
+//! Access control for server interfaces.
+//!
+//! A [`SecurityDescriptor`] attached to a server with `set_security_descriptor()` is
+//! passed to `RpcServerRegisterIf3`, so the RPC runtime checks callers against it before
+//! dispatching calls to the interface. Unlike a security descriptor on the endpoint, it
+//! applies to the interface alone, which matters when several interfaces share one
+//! endpoint. A descriptor attached with `set_endpoint_security_descriptor()` is passed to
+//! `RpcServerUseProtseqEpW` instead, and restricts who may connect to the endpoints.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//! use windows_rpc::security::SecurityDescriptor;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! struct CalculatorImpl;
+//! impl CalculatorServerImpl for CalculatorImpl {
+//!     fn add(a: i32, b: i32) -> i32 {
+//!         a + b
+//!     }
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let mut server = CalculatorServer::<CalculatorImpl>::new();
+//! // Only allow SYSTEM and administrators to call the interface
+//! server.set_security_descriptor(Some(SecurityDescriptor::from_sddl("D:(A;;GA;;;SY)(A;;GA;;;BA)")?));
+//! server.register("calculator_endpoint")?;
+//! server.listen_async()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Security callbacks
+//!
+//! For decisions a descriptor cannot express, `set_security_callback()` installs a
+//! [`SecurityCallback`] that the RPC runtime consults before each call is dispatched. It
+//! receives the interface UUID and the call's binding handle, which can be passed to
+//! functions like `RpcBindingInqAuthClientW`, and rejects the call by returning an error:
+//!
+//! ```rust,no_run
+//! # use windows_rpc::rpc_interface;
+//! # #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! # trait Calculator {
+//! #     fn add(a: i32, b: i32) -> i32;
+//! # }
+//! # struct CalculatorImpl;
+//! # impl CalculatorServerImpl for CalculatorImpl {
+//! #     fn add(a: i32, b: i32) -> i32 { a + b }
+//! # }
+//! use windows::Win32::System::Rpc::RPC_S_ACCESS_DENIED;
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let mut server = CalculatorServer::<CalculatorImpl>::new();
+//! server.set_security_callback(|_interface: u128, _binding: *mut std::ffi::c_void| {
+//!     if maintenance_mode() {
+//!         return Err(RPC_S_ACCESS_DENIED);
+//!     }
+//!     Ok(())
+//! });
+//! server.register("calculator_endpoint")?;
+//! # Ok(())
+//! # }
+//! # fn maintenance_mode() -> bool { false }
+//! ```
+//!
+//! # Per-method authorization
+//!
+//! `set_method_authorizer()` installs a [`MethodAuthorizer`], which sees which method is
+//! called and by whom, so access can be granted method by method without checking it in
+//! every handler:
+//!
+//! ```rust,no_run
+//! # use windows_rpc::rpc_interface;
+//! # #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! # trait Calculator {
+//! #     fn add(a: i32, b: i32) -> i32;
+//! #     fn reset();
+//! # }
+//! # struct CalculatorImpl;
+//! # impl CalculatorServerImpl for CalculatorImpl {
+//! #     fn add(a: i32, b: i32) -> i32 { a + b }
+//! #     fn reset() {}
+//! # }
+//! use windows_rpc::server::CallContext;
+//! use windows_rpc::security::Authorization;
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let mut server = CalculatorServer::<CalculatorImpl>::new();
+//! server.set_method_authorizer(|opnum: u32, context: &CallContext| {
+//!     // Anyone may add, only SYSTEM may reset
+//!     if opnum == 0 || context.client_sid.as_deref() == Some("S-1-5-18") {
+//!         Authorization::Allow
+//!     } else {
+//!         Authorization::Deny
+//!     }
+//! });
+//! server.register("calculator_endpoint")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ffi::c_void;
+use std::sync::Arc;
+
+use windows::Win32::Foundation::{HLOCAL, LocalFree};
+use windows::Win32::Security::Authorization::{
+    ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+};
+use windows::Win32::Security::PSECURITY_DESCRIPTOR;
+use windows::Win32::System::Rpc::RPC_STATUS;
+use windows::core::{HSTRING, Result};
+
+use crate::server::CallContext;
+
+/// Decides whether calls to a server interface are accepted, before they are dispatched.
+///
+/// Implemented for closures taking the interface UUID and the call's binding handle.
+/// A callback that panics rejects the call with `RPC_S_ACCESS_DENIED`.
+pub trait SecurityCallback: Send + Sync {
+    /// Returns `Ok(())` to accept the call, or the status the client receives, e.g.
+    /// `RPC_S_ACCESS_DENIED`, to reject it.
+    fn check(&self, interface: u128, binding: *mut c_void) -> std::result::Result<(), RPC_STATUS>;
+}
+
+impl<F> SecurityCallback for F
+where
+    F: Fn(u128, *mut c_void) -> std::result::Result<(), RPC_STATUS> + Send + Sync,
+{
+    fn check(&self, interface: u128, binding: *mut c_void) -> std::result::Result<(), RPC_STATUS> {
+        self(interface, binding)
+    }
+}
+
+/// The decision of a [`MethodAuthorizer`] on a call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Authorization {
+    /// Dispatches the call to its handler
+    Allow,
+    /// Faults the call with `RPC_S_ACCESS_DENIED` without running its handler
+    Deny,
+}
+
+/// Decides whether a call to a method of a server interface is dispatched.
+///
+/// Unlike a [`SecurityCallback`], it runs for every call, after the call is accepted by
+/// the runtime and before its parameters are unmarshalled, and receives the procedure
+/// number of the method, which is its position in the interface trait, along with the
+/// [`CallContext`] of the caller. Implemented for closures taking both.
+///
+/// An authorizer that panics, or a call whose context cannot be queried, is denied.
+pub trait MethodAuthorizer: Send + Sync {
+    /// Returns whether the call to the method at `opnum` is dispatched.
+    fn authorize(&self, opnum: u32, context: &CallContext) -> Authorization;
+}
+
+impl<F> MethodAuthorizer for F
+where
+    F: Fn(u32, &CallContext) -> Authorization + Send + Sync,
+{
+    fn authorize(&self, opnum: u32, context: &CallContext) -> Authorization {
+        self(opnum, context)
+    }
+}
+
+/// A self-relative security descriptor.
+///
+/// Cloning is cheap: clones share the same descriptor, which is freed when the last of
+/// them is dropped.
+#[derive(Clone)]
+pub struct SecurityDescriptor {
+    inner: Arc<OwnedDescriptor>,
+}
+
+/// Descriptor allocated by `ConvertStringSecurityDescriptorToSecurityDescriptorW`
+struct OwnedDescriptor(PSECURITY_DESCRIPTOR);
+
+// The descriptor is never modified after it is created
+unsafe impl Send for OwnedDescriptor {}
+unsafe impl Sync for OwnedDescriptor {}
+
+impl Drop for OwnedDescriptor {
+    fn drop(&mut self) {
+        unsafe {
+            LocalFree(Some(HLOCAL(self.0.0)));
+        }
+    }
+}
+
+impl SecurityDescriptor {
+    /// Creates a security descriptor from its Security Descriptor Definition Language
+    /// string, e.g. `D:(A;;GA;;;AU)` to allow all authenticated users.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ERROR_INVALID_PARAMETER` if `sddl` is not a valid SDDL string.
+    pub fn from_sddl(sddl: &str) -> Result<Self> {
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+        unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                &HSTRING::from(sddl),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                None,
+            )?;
+        }
+        Ok(Self {
+            inner: Arc::new(OwnedDescriptor(descriptor)),
+        })
+    }
+
+    /// Returns a pointer to the descriptor, valid for as long as `self` is alive.
+    pub fn as_ptr(&self) -> *const c_void {
+        self.inner.0.0
+    }
+}
+
+impl std::fmt::Debug for SecurityDescriptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SecurityDescriptor")
+            .field(&self.as_ptr())
+            .finish()
+    }
+}
@@ -0,0 +1,243 @@
+//! Helpers for server handlers.
+//!
+//! Handlers are static trait methods, so they reach per-server data through the
+//! functions of this module, which refer to the server whose call is being handled
+//! on the current thread.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use std::sync::atomic::{AtomicU32, Ordering};
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Counter {
+//!     fn increment() -> u32;
+//! }
+//!
+//! #[derive(Default)]
+//! struct CounterImpl {
+//!     count: AtomicU32,
+//! }
+//!
+//! impl CounterServerImpl for CounterImpl {
+//!     fn increment() -> u32 {
+//!         let this = windows_rpc::server::instance::<CounterImpl>().unwrap();
+//!         this.count.fetch_add(1, Ordering::Relaxed) + 1
+//!     }
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let counter = Arc::new(CounterImpl::default());
+//! // The application keeps its own reference to the implementation
+//! let mut server = CounterServer::with_instance(counter.clone());
+//! server.register("counter_endpoint")?;
+//! server.listen_async()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Impersonation
+//!
+//! [`impersonate_client()`] runs a closure under the caller's token, so that work like
+//! opening files is checked against the client's access rights rather than the server's:
+//!
+//! ```rust,no_run
+//! fn read_report(path: &str) -> String {
+//!     match windows_rpc::server::impersonate_client(|| std::fs::read_to_string(path)) {
+//!         Ok(Ok(report)) => report,
+//!         _ => String::new(),
+//!     }
+//! }
+//! ```
+//!
+//! # Caller identity
+//!
+//! [`call_context()`] describes the client of the current call, e.g. to log who made it
+//! or to authorize it by SID:
+//!
+//! ```rust,no_run
+//! fn delete_all() -> u32 {
+//!     let Ok(context) = windows_rpc::server::call_context() else {
+//!         return 0;
+//!     };
+//!     // Only local SYSTEM callers
+//!     if context.is_local && context.client_sid.as_deref() == Some("S-1-5-18") {
+//!         // ...
+//!     }
+//!     0
+//! }
+//! ```
+
+use std::ffi::c_void;
+use std::sync::Arc;
+
+use windows::Win32::Foundation::{CloseHandle, HANDLE, HLOCAL, LocalFree};
+use windows::Win32::Security::Authorization::ConvertSidToStringSidW;
+use windows::Win32::Security::{GetTokenInformation, TOKEN_QUERY, TOKEN_USER, TokenUser};
+use windows::Win32::System::Rpc::{
+    RPC_CALL_ATTRIBUTES_V2_W, RPC_CALL_ATTRIBUTES_VERSION, RPC_QUERY_CLIENT_PID,
+    RPC_QUERY_CLIENT_PRINCIPAL_NAME, RPC_QUERY_IS_CLIENT_LOCAL, RPC_QUERY_NO_AUTH_REQUIRED,
+    RPC_S_NO_CALL_ACTIVE, RPC_STATUS, RpcImpersonateClient, RpcRevertToSelfEx,
+    RpcServerInqCallAttributesW, rcclLocal,
+};
+use windows::Win32::System::Threading::{GetCurrentThread, OpenThreadToken};
+use windows::core::{Error, PWSTR, Result};
+
+use crate::{TransferSyntax, dispatch};
+
+/// Returns the implementation object the server handling the current call was created
+/// with by `{Interface}Server::with_instance()`.
+///
+/// Returns `None` outside of a call, if the server was created without an instance, or
+/// if the instance is not a `T`.
+pub fn instance<T: Send + Sync + 'static>() -> Option<Arc<T>> {
+    dispatch::current()?.instance()
+}
+
+/// Returns the transfer syntax the call being handled on this thread was encoded with,
+/// as negotiated by the RPC runtime for the client's association.
+///
+/// Returns `None` outside of a call. Calls of different clients may use different
+/// syntaxes at the same time.
+pub fn transfer_syntax() -> Option<TransferSyntax> {
+    dispatch::current_syntax()
+}
+
+/// Runs `f` while impersonating the client of the call being handled on this thread,
+/// reverting to the server's own token when `f` returns or panics.
+///
+/// Works from handlers running on an executor as well. Whether the server can act as
+/// the client depends on the impersonation level the client allows.
+///
+/// # Errors
+///
+/// Returns `RPC_S_NO_CALL_ACTIVE` outside of a call, or the error of
+/// `RpcImpersonateClient`, e.g. for unauthenticated clients.
+pub fn impersonate_client<R>(f: impl FnOnce() -> R) -> Result<R> {
+    let call = dispatch::current_call()
+        .ok_or_else(|| Error::from_hresult(RPC_S_NO_CALL_ACTIVE.to_hresult()))?;
+    unsafe { RpcImpersonateClient(Some(call.cast_const())) }.ok()?;
+    let _impersonation = Impersonation(call);
+    Ok(f())
+}
+
+/// Reverts the impersonation of the client of `.0` when dropped
+struct Impersonation(*mut c_void);
+
+impl Drop for Impersonation {
+    fn drop(&mut self) {
+        // Best effort: the call is still in progress, so this only fails if the
+        // thread was not impersonating
+        let _ = unsafe { RpcRevertToSelfEx(Some(self.0.cast_const())) };
+    }
+}
+
+/// Identity of the client of a call, returned by [`call_context()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallContext {
+    /// The SID of the client's user, e.g. `S-1-5-18`, if the client can be impersonated.
+    pub client_sid: Option<String>,
+    /// The client's principal name, e.g. `CONTOSO\alice`, for authenticated calls.
+    pub user_name: Option<String>,
+    /// The authentication level, one of the `RPC_C_AUTHN_LEVEL_*` constants.
+    pub authentication_level: u32,
+    /// The authentication service, one of the `RPC_C_AUTHN_*` constants.
+    pub authentication_service: u32,
+    /// Whether the client runs on the same machine.
+    pub is_local: bool,
+    /// The process ID of the client, for local calls such as over ALPC.
+    pub client_pid: Option<u32>,
+}
+
+/// Describes the client of the call being handled on this thread, with
+/// `RpcServerInqCallAttributesW`.
+///
+/// # Errors
+///
+/// Returns `RPC_S_NO_CALL_ACTIVE` outside of a call, or the error of
+/// `RpcServerInqCallAttributesW`.
+pub fn call_context() -> Result<CallContext> {
+    let call = dispatch::current_call()
+        .ok_or_else(|| Error::from_hresult(RPC_S_NO_CALL_ACTIVE.to_hresult()))?;
+
+    let mut attributes = RPC_CALL_ATTRIBUTES_V2_W {
+        Version: RPC_CALL_ATTRIBUTES_VERSION,
+        // Unauthenticated calls are described as well
+        Flags: RPC_QUERY_CLIENT_PID | RPC_QUERY_IS_CLIENT_LOCAL | RPC_QUERY_NO_AUTH_REQUIRED,
+        ..Default::default()
+    };
+    unsafe { RpcServerInqCallAttributesW(Some(call.cast_const()), (&raw mut attributes).cast()) }
+        .ok()?;
+
+    Ok(CallContext {
+        client_sid: impersonate_client(thread_user_sid).ok().flatten(),
+        user_name: client_principal_name(call),
+        authentication_level: attributes.AuthenticationLevel,
+        authentication_service: attributes.AuthenticationService,
+        is_local: attributes.IsClientLocal == rcclLocal,
+        client_pid: Some(attributes.ClientPID.0 as usize as u32).filter(|&pid| pid != 0),
+    })
+}
+
+/// Returns the principal name of the client of `call`, for authenticated calls
+fn client_principal_name(call: *mut c_void) -> Option<String> {
+    const ERROR_MORE_DATA: RPC_STATUS = RPC_STATUS(234);
+
+    let mut name = vec![0u16; 256];
+    loop {
+        let mut attributes = RPC_CALL_ATTRIBUTES_V2_W {
+            Version: RPC_CALL_ATTRIBUTES_VERSION,
+            Flags: RPC_QUERY_CLIENT_PRINCIPAL_NAME,
+            ClientPrincipalNameBufferLength: (name.len() * 2) as u32,
+            ClientPrincipalName: name.as_mut_ptr(),
+            ..Default::default()
+        };
+        let status = unsafe {
+            RpcServerInqCallAttributesW(Some(call.cast_const()), (&raw mut attributes).cast())
+        };
+        match status {
+            // The length is the required size in bytes
+            ERROR_MORE_DATA => {
+                name.resize(attributes.ClientPrincipalNameBufferLength as usize / 2, 0)
+            }
+            status => {
+                status.ok().ok()?;
+                let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+                return Some(String::from_utf16_lossy(&name[..len]))
+                    .filter(|name| !name.is_empty());
+            }
+        }
+    }
+}
+
+/// Returns the SID of the user of the thread's token, which is the client's while
+/// impersonating
+fn thread_user_sid() -> Option<String> {
+    let mut token = HANDLE::default();
+    unsafe { OpenThreadToken(GetCurrentThread(), TOKEN_QUERY, true, &raw mut token) }.ok()?;
+
+    let mut len = 0;
+    let _ = unsafe { GetTokenInformation(token, TokenUser, None, 0, &raw mut len) };
+    // `u64` keeps the buffer aligned for `TOKEN_USER`
+    let mut buffer = vec![0u64; (len as usize).div_ceil(8)];
+    let result = unsafe {
+        GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr().cast()),
+            len,
+            &raw mut len,
+        )
+    };
+    let _ = unsafe { CloseHandle(token) };
+    result.ok()?;
+
+    let user = unsafe { &*buffer.as_ptr().cast::<TOKEN_USER>() };
+    let mut sid = PWSTR::null();
+    unsafe { ConvertSidToStringSidW(user.User.Sid, &raw mut sid) }.ok()?;
+    let result = unsafe { sid.to_string() }.ok();
+    unsafe { LocalFree(Some(HLOCAL(sid.0.cast()))) };
+    result
+}
@@ -3,14 +3,26 @@
 //! This module provides types for creating and managing RPC server bindings,
 //! which control the server lifecycle: registration, listening, and shutdown.
 
+use std::collections::{HashMap, HashSet};
 use std::ffi::c_void;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
 use windows::Win32::System::Rpc::{
-    RPC_C_LISTEN_MAX_CALLS_DEFAULT, RpcMgmtStopServerListening, RpcServerListen,
-    RpcServerRegisterIf3, RpcServerUnregisterIf, RpcServerUseProtseqEpW,
+    RPC_C_LISTEN_MAX_CALLS_DEFAULT, RPC_IF_ALLOW_CALLBACKS_WITH_NO_AUTH, RPC_IF_AUTOLISTEN,
+    RPC_IF_SEC_NO_CACHE, RPC_S_ACCESS_DENIED, RPC_S_ALREADY_LISTENING, RPC_S_DUPLICATE_ENDPOINT,
+    RPC_S_NO_ENDPOINT_FOUND, RPC_S_NOT_LISTENING, RPC_S_OK, RPC_SERVER_INTERFACE, RPC_STATUS,
+    RpcBindingFree, RpcBindingInqAuthClientW, RpcBindingInqObject, RpcBindingServerFromClient,
+    RpcBindingToStringBindingW, RpcMgmtStopServerListening, RpcMgmtWaitServerListen,
+    RpcObjectInqType, RpcObjectSetType, RpcServerListen, RpcServerRegisterIf3,
+    RpcServerUnregisterIf, RpcServerUseProtseqEpW, RpcStringBindingParseW, RpcStringFreeW,
 };
-use windows::core::{Error, HSTRING, PCWSTR};
+use windows::core::{Error, GUID, HSTRING, PCWSTR, PWSTR};
+use windows_sys::Win32::System::Com::RPC_C_AUTHN_LEVEL_PKT_PRIVACY;
 
 use crate::ProtocolSequence;
+use crate::executor::CallFuture;
+use crate::security::{SecurityCallback, SecurityDescriptor};
 
 /// Manages the lifecycle of an RPC server.
 ///
@@ -50,7 +62,30 @@ pub struct ServerBinding {
     protocol: ProtocolSequence,
     endpoint: String,
     interface_handle: *const c_void,
-    registered: bool,
+    registered: AtomicBool,
+    /// Whether the binding holds one of the process's `LISTENERS`
+    listening: AtomicBool,
+    /// Whether `stop()` unregistered the interface, which `listen_async()` registers again
+    stopped: AtomicBool,
+    autolisten: bool,
+    security_descriptor: Option<SecurityDescriptor>,
+    security_callback: Option<Arc<dyn SecurityCallback>>,
+    require_encryption: bool,
+    options: ServerOptions,
+    /// `RPC_IF_*` flags added to the crate's own
+    registration_flags: u32,
+    /// Descriptor the endpoints are registered with
+    endpoint_security: Option<SecurityDescriptor>,
+    mode: EndpointMode,
+    owns_endpoint: bool,
+    /// Endpoints the interface is served on, starting with `endpoint` until it is removed
+    endpoints: Vec<String>,
+    /// Whether calls are checked against `endpoints`, once they were changed
+    filtered: bool,
+    /// Type UUID the manager is registered under, nil for the default manager
+    manager_type: u128,
+    /// Entry-point vector of the manager, null to use the interface's dispatch table
+    manager_epv: *const c_void,
 }
 
 impl ServerBinding {
@@ -66,36 +101,157 @@ impl ServerBinding {
     /// * `endpoint` - The endpoint name clients will connect to
     /// * `interface_handle` - Pointer to the RPC interface specification
     ///
+    /// The endpoint must not be registered yet; use
+    /// [`with_endpoint_mode()`](Self::with_endpoint_mode) to share an endpoint with
+    /// other servers in the process.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the protocol sequence and endpoint cannot be registered.
+    /// Returns `RPC_S_DUPLICATE_ENDPOINT` if the process already registered the endpoint,
+    /// or another error if the protocol sequence and endpoint cannot be registered.
     pub fn new(
         protocol: ProtocolSequence,
         endpoint: impl Into<String>,
         interface_handle: *const c_void,
     ) -> Result<Self, Error> {
-        let endpoint = endpoint.into();
-        let endpoint_hstring = HSTRING::from(&endpoint);
+        Self::with_endpoint_mode(
+            protocol,
+            endpoint,
+            interface_handle,
+            EndpointMode::Exclusive,
+        )
+    }
 
-        // Register the protocol sequence and endpoint
-        unsafe {
-            RpcServerUseProtseqEpW(
-                protocol.to_pcwstr(),
-                RPC_C_LISTEN_MAX_CALLS_DEFAULT,
-                PCWSTR::from_raw(endpoint_hstring.as_ptr()),
-                None, // No security descriptor
-            )
-            .ok()?;
-        }
+    /// Creates a new server binding, handling an endpoint that is already registered
+    /// according to `mode`.
+    ///
+    /// See [`EndpointMode`] for what a binding that reuses an endpoint is responsible for.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_DUPLICATE_ENDPOINT` if the endpoint is already registered and
+    /// `mode` is [`EndpointMode::Exclusive`], or another error if the protocol sequence
+    /// and endpoint cannot be registered.
+    pub fn with_endpoint_mode(
+        protocol: ProtocolSequence,
+        endpoint: impl Into<String>,
+        interface_handle: *const c_void,
+        mode: EndpointMode,
+    ) -> Result<Self, Error> {
+        Self::with_endpoint_security(protocol, endpoint, interface_handle, mode, None)
+    }
+
+    /// Creates a new server binding whose endpoints are registered with
+    /// `endpoint_security`, which restricts who may connect to them at all.
+    ///
+    /// Unlike the interface's descriptor, the endpoint's applies to every interface served
+    /// on it, and is checked when a client connects rather than on each call. The RPC
+    /// runtime only applies it to `ncalrpc` and `ncacn_np` endpoints, and only when they
+    /// are created: an endpoint reused according to [`EndpointMode::ReuseExisting`] keeps
+    /// the descriptor it was registered with.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_DUPLICATE_ENDPOINT` if the endpoint is already registered and
+    /// `mode` is [`EndpointMode::Exclusive`], or another error if the protocol sequence
+    /// and endpoint cannot be registered.
+    pub fn with_endpoint_security(
+        protocol: ProtocolSequence,
+        endpoint: impl Into<String>,
+        interface_handle: *const c_void,
+        mode: EndpointMode,
+        endpoint_security: Option<SecurityDescriptor>,
+    ) -> Result<Self, Error> {
+        let endpoint = endpoint.into();
+        let owns_endpoint = use_endpoint(protocol, &endpoint, mode, endpoint_security.as_ref())?;
 
         Ok(ServerBinding {
             protocol,
+            endpoints: vec![endpoint.clone()],
             endpoint,
             interface_handle,
-            registered: false,
+            registered: AtomicBool::new(false),
+            listening: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            autolisten: false,
+            security_descriptor: None,
+            security_callback: None,
+            require_encryption: false,
+            options: ServerOptions::default(),
+            registration_flags: 0,
+            endpoint_security,
+            mode,
+            filtered: false,
+            owns_endpoint,
+            manager_type: 0,
+            manager_epv: std::ptr::null(),
         })
     }
 
+    /// Registers the interface with `RPC_IF_AUTOLISTEN`.
+    ///
+    /// An autolisten interface accepts calls as soon as it is registered, independently
+    /// of `RpcServerListen`. [`listen()`](Self::listen) and
+    /// [`listen_async()`](Self::listen_async) then do nothing, and [`stop()`](Self::stop)
+    /// unregisters the interface. Must be set before [`register()`](Self::register).
+    pub fn set_autolisten(&mut self, autolisten: bool) {
+        self.autolisten = autolisten;
+    }
+
+    /// Restricts the callers of the interface to those granted access by
+    /// `security_descriptor`.
+    ///
+    /// The descriptor applies to this interface only, unlike the endpoint's, so
+    /// interfaces sharing an endpoint can have different access control. Must be set
+    /// before [`register()`](Self::register).
+    pub fn set_security_descriptor(&mut self, security_descriptor: Option<SecurityDescriptor>) {
+        self.security_descriptor = security_descriptor;
+    }
+
+    /// Consults `security_callback` before dispatching each call to the interface, which
+    /// rejects the calls it returns an error for. Must be set before
+    /// [`register()`](Self::register).
+    pub fn set_security_callback(&mut self, security_callback: Option<Arc<dyn SecurityCallback>>) {
+        self.security_callback = security_callback;
+    }
+
+    /// Rejects calls below the `RPC_C_AUTHN_LEVEL_PKT_PRIVACY` authentication level, so
+    /// that every call to the interface is authenticated and encrypted. Must be set
+    /// before [`register()`](Self::register).
+    pub fn set_require_encryption(&mut self, require_encryption: bool) {
+        self.require_encryption = require_encryption;
+    }
+
+    /// Sets the thread and call limits the interface is registered and listened with.
+    /// Must be set before [`register()`](Self::register).
+    pub fn set_options(&mut self, options: ServerOptions) {
+        self.options = options;
+    }
+
+    /// Adds `RPC_IF_*` flags, e.g. `RPC_IF_ALLOW_LOCAL_ONLY` or `RPC_IF_ALLOW_SECURE_ONLY`,
+    /// to those the interface is registered with. Must be set before
+    /// [`register()`](Self::register).
+    ///
+    /// The binding always registers with `RPC_IF_ALLOW_CALLBACKS_WITH_NO_AUTH` and
+    /// `RPC_IF_SEC_NO_CACHE` for its security callback, and adds `RPC_IF_AUTOLISTEN` as
+    /// set with [`set_autolisten()`](Self::set_autolisten).
+    pub fn set_registration_flags(&mut self, flags: u32) {
+        self.registration_flags = flags;
+    }
+
+    /// Registers `epv` as the manager of the interface for objects of type
+    /// `manager_type`, so that several implementations of an interface can be served
+    /// together. Calls to objects mapped to the type with [`set_object_type()`] are
+    /// dispatched to the routines of `epv`, and calls to other objects to the manager
+    /// of the nil type. Must be set before [`register()`](Self::register).
+    ///
+    /// The runtime keeps the interface specification of the first registration for
+    /// every manager, so the binding registered first must be unregistered last.
+    pub fn set_manager(&mut self, manager_type: u128, epv: *const c_void) {
+        self.manager_type = manager_type;
+        self.manager_epv = epv;
+    }
+
     /// Registers the RPC interface with the runtime.
     ///
     /// After registration, the server can begin accepting calls. This method
@@ -105,33 +261,65 @@ impl ServerBinding {
     ///
     /// Returns an error if the interface cannot be registered.
     pub fn register(&mut self) -> Result<(), Error> {
-        if self.registered {
+        self.register_interface()
+    }
+
+    fn register_interface(&self) -> Result<(), Error> {
+        if self.registered.load(Ordering::Relaxed) {
             return Ok(());
         }
 
+        let autolisten = if self.autolisten {
+            RPC_IF_AUTOLISTEN
+        } else {
+            0
+        };
+        // The callback checks every call, including unauthenticated ones
+        let flags = self.registration_flags
+            | autolisten
+            | RPC_IF_ALLOW_CALLBACKS_WITH_NO_AUTH
+            | RPC_IF_SEC_NO_CACHE;
+        let manager_type = GUID::from_u128(self.manager_type);
         unsafe {
             RpcServerRegisterIf3(
                 self.interface_handle,
-                None, // Interface UUID (use from handle)
-                None, // Manager EPV
-                0,    // Flags
-                RPC_C_LISTEN_MAX_CALLS_DEFAULT,
-                u32::MAX, // Max RPC size
-                None,     // Security callback
-                None,     // Security descriptor
+                Some(&raw const manager_type),
+                Some(self.manager_epv).filter(|epv| !epv.is_null()),
+                flags,
+                self.options.max_calls,
+                self.options.max_rpc_size,
+                Some(security_callback),
+                self.security_descriptor
+                    .as_ref()
+                    .map(SecurityDescriptor::as_ptr),
             )
             .ok()?;
         }
 
-        self.registered = true;
+        self.registered.store(true, Ordering::Relaxed);
+        self.stopped.store(false, Ordering::Relaxed);
+        self.update_filter();
+        if let Some(callback) = &self.security_callback {
+            SECURITY_CALLBACKS
+                .write()
+                .unwrap()
+                .insert(self.manager_key(), callback.clone());
+        }
+        if self.require_encryption {
+            ENCRYPTED_INTERFACES
+                .write()
+                .unwrap()
+                .insert(self.manager_key());
+        }
         Ok(())
     }
 
     /// Starts listening for RPC calls (blocking).
     ///
-    /// This method blocks the current thread until [`stop()`](Self::stop) is called
-    /// from another thread. Use [`listen_async()`](Self::listen_async) for non-blocking
-    /// operation.
+    /// This method blocks the current thread until the process stops listening, i.e.
+    /// until [`stop()`](Self::stop) is called from another thread on the last listening
+    /// binding, or a management call stops the server. Use
+    /// [`listen_async()`](Self::listen_async) for non-blocking operation.
     ///
     /// # Errors
     ///
@@ -139,19 +327,14 @@ impl ServerBinding {
     /// - The interface has not been registered
     /// - The RPC runtime fails to start listening
     pub fn listen(&self) -> Result<(), Error> {
-        if !self.registered {
-            return Err(Error::from_hresult(windows::core::HRESULT(-1)));
+        self.listen_async()?;
+        if self.autolisten {
+            return Ok(());
         }
 
         unsafe {
-            RpcServerListen(
-                1, // MinimumCallThreads
-                RPC_C_LISTEN_MAX_CALLS_DEFAULT,
-                0, // DontWait = false (blocking)
-            )
-            .ok()?;
+            RpcMgmtWaitServerListen().ok()?;
         }
-
         Ok(())
     }
 
@@ -161,6 +344,12 @@ impl ServerBinding {
     /// managed by the Windows RPC runtime. Call [`stop()`](Self::stop) to shut
     /// down the server.
     ///
+    /// Listening is process-wide and reference counted: the first binding to listen
+    /// starts it, with the thread and call limits of its [`ServerOptions`], and it keeps
+    /// going until every listening binding is stopped. Calling this again on a listening
+    /// binding takes no further reference, and calling it on a stopped binding registers
+    /// its interface again.
+    ///
     /// This is the recommended mode for most applications as it allows the main
     /// thread to continue other work or wait for a shutdown signal.
     ///
@@ -170,38 +359,93 @@ impl ServerBinding {
     /// - The interface has not been registered
     /// - The RPC runtime fails to start listening
     pub fn listen_async(&self) -> Result<(), Error> {
-        if !self.registered {
+        if self.stopped.load(Ordering::Relaxed) {
+            self.register_interface()?;
+        }
+        if !self.registered.load(Ordering::Relaxed) {
             return Err(Error::from_hresult(windows::core::HRESULT(-1)));
         }
+        if self.autolisten {
+            return Ok(());
+        }
 
-        unsafe {
+        let mut listeners = LISTENERS.lock().unwrap();
+        // Listening again also restarts it after a management call stopped it
+        let status = unsafe {
             RpcServerListen(
-                1, // MinimumCallThreads
-                RPC_C_LISTEN_MAX_CALLS_DEFAULT,
+                self.options.min_threads,
+                self.options.max_calls,
                 1, // DontWait = true (non-blocking)
             )
-            .ok()?;
+        };
+        if status != RPC_S_ALREADY_LISTENING {
+            status.ok()?;
+        }
+        if !self.listening.swap(true, Ordering::Relaxed) {
+            *listeners += 1;
         }
 
         Ok(())
     }
 
+    /// Blocks until the server stops listening.
+    ///
+    /// After [`listen_async()`](Self::listen_async), this parks the calling thread until
+    /// the process stops listening, because the last listening binding was stopped or a
+    /// remote management call stopped the server, and all calls in progress complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_NOT_LISTENING` if the server is not listening, which includes
+    /// autolisten interfaces.
+    pub fn wait_until_stopped(&self) -> Result<(), Error> {
+        wait_server_listen(self.autolisten)
+    }
+
+    /// Completes when the server is stopped, like
+    /// [`wait_until_stopped()`](Self::wait_until_stopped), for async applications. The
+    /// wait runs on tokio's blocking pool when called within a tokio runtime (with the
+    /// `tokio` feature), and on the shared `ThreadPool` of client calls otherwise.
+    pub fn wait_until_stopped_async(&self) -> CallFuture<Result<(), Error>> {
+        let autolisten = self.autolisten;
+        crate::executor::spawn(None, move || wait_server_listen(autolisten))
+    }
+
     /// Stops the server from accepting new RPC calls.
     ///
-    /// Outstanding calls may still complete. For a blocking server, this will
-    /// cause [`listen()`](Self::listen) to return.
+    /// Outstanding calls may still complete. The last listening binding in the process
+    /// stops listening, which makes a blocking [`listen()`](Self::listen) return; while
+    /// other bindings are still listening, the interface is unregistered instead, so
+    /// they keep serving their calls.
+    ///
+    /// A binding that never listened, e.g. one that reused another server's endpoint,
+    /// only unregisters its interface. Either way, [`listen_async()`](Self::listen_async)
+    /// serves the interface again.
     ///
     /// # Errors
     ///
     /// Returns an error if the RPC runtime fails to stop.
     pub fn stop(&self) -> Result<(), Error> {
-        unsafe {
-            RpcMgmtStopServerListening(None).ok()?;
+        if !self.autolisten && self.listening.swap(false, Ordering::Relaxed) {
+            let mut listeners = LISTENERS.lock().unwrap();
+            *listeners -= 1;
+            if *listeners == 0 {
+                unsafe {
+                    RpcMgmtStopServerListening(None).ok()?;
+                }
+                return Ok(());
+            }
         }
+
+        let registered = self.registered.load(Ordering::Relaxed);
+        self.unregister_interface()?;
+        self.stopped.store(registered, Ordering::Relaxed);
         Ok(())
     }
 
-    /// Unregisters the RPC interface.
+    /// Unregisters the RPC interface, and gives back the binding's reference to the
+    /// process's listening without stopping it, so the other listening bindings can still
+    /// stop it.
     ///
     /// This is called automatically when the `ServerBinding` is dropped.
     ///
@@ -209,27 +453,415 @@ impl ServerBinding {
     ///
     /// Returns an error if the interface cannot be unregistered.
     pub fn unregister(&mut self) -> Result<(), Error> {
-        if !self.registered {
+        self.release_listener();
+        self.stopped.store(false, Ordering::Relaxed);
+        self.unregister_interface()
+    }
+
+    /// Gives back the binding's reference to the process's listening, if it holds one
+    fn release_listener(&self) {
+        if self.listening.swap(false, Ordering::Relaxed) {
+            *LISTENERS.lock().unwrap() -= 1;
+        }
+    }
+
+    fn unregister_interface(&self) -> Result<(), Error> {
+        if !self.registered.load(Ordering::Relaxed) {
             return Ok(());
         }
 
+        // Only this binding's manager, those of other implementations stay registered
+        let manager_type = GUID::from_u128(self.manager_type);
         unsafe {
-            RpcServerUnregisterIf(Some(self.interface_handle), None, 1).ok()?;
+            RpcServerUnregisterIf(
+                Some(self.interface_handle),
+                Some(&raw const manager_type),
+                1,
+            )
+            .ok()?;
         }
+        let key = self.manager_key();
+        ENDPOINT_FILTERS.write().unwrap().remove(&key);
+        SECURITY_CALLBACKS.write().unwrap().remove(&key);
+        ENCRYPTED_INTERFACES.write().unwrap().remove(&key);
 
-        self.registered = false;
+        self.registered.store(false, Ordering::Relaxed);
         Ok(())
     }
 
-    /// Returns the endpoint name.
+    /// Returns the name of the endpoint the binding was created with.
     pub fn endpoint(&self) -> &str {
         &self.endpoint
     }
 
-    /// Returns the protocol sequence.
+    /// Serves the interface on another endpoint as well, e.g. a versioned name next to a
+    /// stable one. The endpoint is registered according to the binding's
+    /// [`EndpointMode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_DUPLICATE_ENDPOINT` if the endpoint is already registered and the
+    /// binding is [`EndpointMode::Exclusive`], or another error if it cannot be registered.
+    pub fn add_endpoint(&mut self, endpoint: impl Into<String>) -> Result<(), Error> {
+        self.add_protocol_endpoint(self.protocol, endpoint)
+    }
+
+    /// Serves the interface on an endpoint of another protocol sequence as well, e.g. a
+    /// named pipe next to an ALPC endpoint, so that it is reachable over both at the same
+    /// time. The endpoint is registered according to the binding's [`EndpointMode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_DUPLICATE_ENDPOINT` if the endpoint is already registered and the
+    /// binding is [`EndpointMode::Exclusive`], or another error if it cannot be registered.
+    pub fn add_protocol_endpoint(
+        &mut self,
+        protocol: ProtocolSequence,
+        endpoint: impl Into<String>,
+    ) -> Result<(), Error> {
+        let endpoint = endpoint.into();
+        if self.serves(&endpoint) {
+            return Ok(());
+        }
+        use_endpoint(
+            protocol,
+            &endpoint,
+            self.mode,
+            self.endpoint_security.as_ref(),
+        )?;
+        self.endpoints.push(endpoint);
+        self.filtered = true;
+        self.update_filter();
+        Ok(())
+    }
+
+    /// Stops serving the interface on `endpoint`; calls arriving on it are rejected with
+    /// `RPC_S_ACCESS_DENIED`, while the other endpoints keep being served.
+    ///
+    /// The RPC runtime cannot unregister endpoints, so the endpoint itself stays
+    /// registered in the process and other interfaces may still be served on it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `RPC_S_NO_ENDPOINT_FOUND` if the interface is not served on `endpoint`.
+    pub fn remove_endpoint(&mut self, endpoint: &str) -> Result<(), Error> {
+        if !self.serves(endpoint) {
+            return Err(Error::from_hresult(RPC_S_NO_ENDPOINT_FOUND.to_hresult()));
+        }
+        self.endpoints
+            .retain(|served| !served.eq_ignore_ascii_case(endpoint));
+        self.filtered = true;
+        self.update_filter();
+        Ok(())
+    }
+
+    /// Returns the endpoints the interface is served on.
+    pub fn endpoints(&self) -> &[String] {
+        &self.endpoints
+    }
+
+    fn serves(&self, endpoint: &str) -> bool {
+        self.endpoints
+            .iter()
+            .any(|served| served.eq_ignore_ascii_case(endpoint))
+    }
+
+    /// Publishes the served endpoints to [`security_callback`] once they were changed
+    fn update_filter(&self) {
+        if self.filtered && self.registered.load(Ordering::Relaxed) {
+            ENDPOINT_FILTERS
+                .write()
+                .unwrap()
+                .insert(self.manager_key(), self.endpoints.clone());
+        }
+    }
+
+    fn manager_key(&self) -> ManagerKey {
+        manager_key(self.interface_handle, self.manager_type)
+    }
+
+    /// Returns the protocol sequence the binding was created with.
     pub fn protocol(&self) -> ProtocolSequence {
         self.protocol
     }
+
+    /// Returns whether this binding registered the endpoint, rather than reusing an
+    /// endpoint already registered in the process.
+    pub fn owns_endpoint(&self) -> bool {
+        self.owns_endpoint
+    }
+}
+
+/// Maps the object UUID `object` to the type UUID `manager_type`, so that calls clients
+/// make to the object are dispatched to the manager registered for that type with
+/// [`ServerBinding::set_manager()`]. `None` removes the mapping.
+///
+/// The mapping is process-wide and applies to every interface.
+///
+/// # Errors
+///
+/// Returns an error if the runtime rejects the mapping, e.g. for a nil object UUID.
+pub fn set_object_type(object: u128, manager_type: Option<u128>) -> Result<(), Error> {
+    let object = GUID::from_u128(object);
+    let manager_type = manager_type.map(GUID::from_u128);
+    unsafe {
+        RpcObjectSetType(
+            &object,
+            manager_type
+                .as_ref()
+                .map(|manager_type| manager_type as *const GUID),
+        )
+    }
+    .ok()
+}
+
+/// Waits until the process stops listening
+fn wait_server_listen(autolisten: bool) -> Result<(), Error> {
+    if autolisten {
+        return Err(Error::from_hresult(RPC_S_NOT_LISTENING.to_hresult()));
+    }
+
+    unsafe {
+        RpcMgmtWaitServerListen().ok()?;
+    }
+    Ok(())
+}
+
+/// Number of bindings that are listening. The process stops listening when the last of
+/// them is stopped.
+static LISTENERS: Mutex<usize> = Mutex::new(0);
+
+/// Identifies the manager of an interface: the interface UUID and version, and the
+/// manager type UUID. The runtime passes the `RPC_SERVER_INTERFACE` of the first
+/// registration to the security callback of every manager, so its address doesn't tell
+/// the managers apart.
+type ManagerKey = (u128, u16, u16, u128);
+
+fn manager_key(interface: *const c_void, manager_type: u128) -> ManagerKey {
+    let id = unsafe { &(*interface.cast::<RPC_SERVER_INTERFACE>()).InterfaceId };
+    (
+        id.SyntaxGUID.to_u128(),
+        id.SyntaxVersion.MajorVersion,
+        id.SyntaxVersion.MinorVersion,
+        manager_type,
+    )
+}
+
+/// Returns the type of the object a call is made to, which selects the manager serving
+/// it. Objects without a type are served by the manager of the nil type.
+fn call_manager_type(call: *const c_void) -> u128 {
+    let mut object = GUID::zeroed();
+    if unsafe { RpcBindingInqObject(call, &raw mut object) } != RPC_S_OK {
+        return 0;
+    }
+    let mut manager_type = GUID::zeroed();
+    match unsafe { RpcObjectInqType(&raw const object, Some(&raw mut manager_type)) } {
+        RPC_S_OK => manager_type.to_u128(),
+        _ => 0,
+    }
+}
+
+/// Endpoints served by each manager whose endpoints were changed. Managers without an
+/// entry are served on every endpoint.
+static ENDPOINT_FILTERS: LazyLock<RwLock<HashMap<ManagerKey, Vec<String>>>> =
+    LazyLock::new(Default::default);
+
+/// Callbacks set with `set_security_callback()`, keyed like [`ENDPOINT_FILTERS`].
+static SECURITY_CALLBACKS: LazyLock<RwLock<HashMap<ManagerKey, Arc<dyn SecurityCallback>>>> =
+    LazyLock::new(Default::default);
+
+/// Managers that require `RPC_C_AUTHN_LEVEL_PKT_PRIVACY`, keyed like [`ENDPOINT_FILTERS`].
+static ENCRYPTED_INTERFACES: LazyLock<RwLock<HashSet<ManagerKey>>> =
+    LazyLock::new(Default::default);
+
+/// Registers `endpoint`, returning whether it was registered by this call rather than
+/// already registered in the process
+fn use_endpoint(
+    protocol: ProtocolSequence,
+    endpoint: &str,
+    mode: EndpointMode,
+    security_descriptor: Option<&SecurityDescriptor>,
+) -> Result<bool, Error> {
+    let endpoint_hstring = HSTRING::from(endpoint);
+    let status = unsafe {
+        RpcServerUseProtseqEpW(
+            protocol.to_pcwstr(),
+            RPC_C_LISTEN_MAX_CALLS_DEFAULT,
+            PCWSTR::from_raw(endpoint_hstring.as_ptr()),
+            security_descriptor.map(SecurityDescriptor::as_ptr),
+        )
+    };
+    match status {
+        RPC_S_DUPLICATE_ENDPOINT if mode == EndpointMode::ReuseExisting => Ok(false),
+        status => {
+            status.ok()?;
+            Ok(true)
+        }
+    }
+}
+
+/// Security callback of every interface. Rejects calls that arrive on endpoints the
+/// manager was removed from, as the RPC runtime serves every registered interface on
+/// every endpoint of the process, and unencrypted calls to managers that require
+/// encryption, then consults the manager's [`SecurityCallback`].
+unsafe extern "system" fn security_callback(
+    interface: *const c_void,
+    context: *const c_void,
+) -> RPC_STATUS {
+    let key = manager_key(interface, call_manager_type(context));
+    let status = endpoint_filter(&key, context);
+    if status != RPC_S_OK {
+        return status;
+    }
+    if ENCRYPTED_INTERFACES.read().unwrap().contains(&key) && !is_encrypted(context) {
+        return RPC_S_ACCESS_DENIED;
+    }
+
+    let Some(callback) = SECURITY_CALLBACKS.read().unwrap().get(&key).cloned() else {
+        return RPC_S_OK;
+    };
+    let check = || callback.check(key.0, context.cast_mut());
+    match catch_unwind(AssertUnwindSafe(check)) {
+        Ok(Ok(())) => RPC_S_OK,
+        Ok(Err(status)) => status,
+        Err(_) => RPC_S_ACCESS_DENIED,
+    }
+}
+
+/// Rejects calls whose endpoint is not served by the manager, once its endpoints were
+/// changed
+fn endpoint_filter(key: &ManagerKey, context: *const c_void) -> RPC_STATUS {
+    let filters = ENDPOINT_FILTERS.read().unwrap();
+    let Some(endpoints) = filters.get(key) else {
+        return RPC_S_OK;
+    };
+    match call_endpoint(context) {
+        Some(endpoint) if endpoints.iter().any(|e| e.eq_ignore_ascii_case(&endpoint)) => RPC_S_OK,
+        _ => RPC_S_ACCESS_DENIED,
+    }
+}
+
+/// Returns whether the call is authenticated at the packet privacy level
+fn is_encrypted(call: *const c_void) -> bool {
+    let mut privileges: *mut c_void = std::ptr::null_mut();
+    let mut level = 0;
+    // Fails for unauthenticated calls
+    let status = unsafe {
+        RpcBindingInqAuthClientW(
+            Some(call),
+            &raw mut privileges,
+            None,
+            Some(&raw mut level),
+            None,
+            None,
+        )
+    };
+    status == RPC_S_OK && level >= RPC_C_AUTHN_LEVEL_PKT_PRIVACY
+}
+
+/// Returns the endpoint a call arrived on, from its server binding
+fn call_endpoint(call: *const c_void) -> Option<String> {
+    let mut server_binding: *mut c_void = std::ptr::null_mut();
+    unsafe { RpcBindingServerFromClient(Some(call), &raw mut server_binding) }
+        .ok()
+        .ok()?;
+
+    let mut string_binding = PWSTR::null();
+    let status = unsafe { RpcBindingToStringBindingW(server_binding, &raw mut string_binding) };
+    let _ = unsafe { RpcBindingFree(&raw mut server_binding) };
+    status.ok().ok()?;
+
+    let mut endpoint = PWSTR::null();
+    let status = unsafe {
+        RpcStringBindingParseW(
+            PCWSTR::from_raw(string_binding.as_ptr()),
+            None,
+            None,
+            None,
+            Some(&raw mut endpoint),
+            None,
+        )
+    };
+    let _ = unsafe { RpcStringFreeW(&raw mut string_binding) };
+    status.ok().ok()?;
+
+    let result = unsafe { endpoint.to_string() }.ok();
+    let _ = unsafe { RpcStringFreeW(&raw mut endpoint) };
+    result
+}
+
+/// Thread and call limits of a server, set with `set_server_options()`.
+///
+/// `max_calls` and `max_rpc_size` apply to the interface when it is registered. The
+/// RPC runtime has a single pool of call threads for the process, so `min_threads`,
+/// and `max_calls` as a process-wide limit, only take effect for the server that starts
+/// listening.
+///
+/// # Example
+///
+/// ```rust
+/// use windows_rpc::server_binding::ServerOptions;
+///
+/// let options = ServerOptions {
+///     max_calls: 64,
+///     max_rpc_size: 1024 * 1024,
+///     ..ServerOptions::default()
+/// };
+/// assert_eq!(options.min_threads, 1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServerOptions {
+    /// Call threads the runtime keeps ready for incoming calls. Defaults to 1.
+    pub min_threads: u32,
+    /// Concurrent calls accepted before further calls are rejected. Defaults to
+    /// `RPC_C_LISTEN_MAX_CALLS_DEFAULT`, which lets the runtime decide.
+    pub max_calls: u32,
+    /// Largest incoming data block, in bytes; calls sending larger ones are rejected.
+    /// Not enforced for `ncalrpc`. Defaults to `u32::MAX`, no limit.
+    pub max_rpc_size: u32,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            min_threads: 1,
+            max_calls: RPC_C_LISTEN_MAX_CALLS_DEFAULT,
+            max_rpc_size: u32::MAX,
+        }
+    }
+}
+
+/// What dropping a generated `{Interface}Server` does, set with `set_on_drop()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnDrop {
+    /// Stops listening like `stop()`, then unregisters the interface once its
+    /// outstanding calls complete. Stopping is process-wide for servers that own their
+    /// endpoint, see [`EndpointMode`].
+    #[default]
+    StopAndWait,
+    /// Only unregisters the interface once its outstanding calls complete, leaving the
+    /// other servers of the process listening. A listening server still gives back its
+    /// listener, so the last of the others stops the process.
+    Unregister,
+    /// Leaves the runtime untouched, for servers shut down explicitly. A server that is
+    /// still registered keeps serving calls, and its metadata is leaked.
+    Nothing,
+}
+
+/// How a [`ServerBinding`] handles an endpoint that is already registered in the process,
+/// e.g. by another component or another interface of the same server.
+///
+/// The RPC runtime cannot unregister endpoints, so an endpoint stays registered until the
+/// process exits, even after the binding that registered it is dropped. Listening is
+/// process-wide and reference counted, so bindings sharing an endpoint may each listen
+/// and stop independently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EndpointMode {
+    /// Fail with `RPC_S_DUPLICATE_ENDPOINT` if the endpoint is already registered.
+    #[default]
+    Exclusive,
+    /// Register the interface on the existing endpoint.
+    ReuseExisting,
 }
 
 impl Drop for ServerBinding {
@@ -0,0 +1,160 @@
+//! Helpers for testing generated interfaces.
+//!
+//! [`pair()`] starts a server for an implementation on a unique ALPC endpoint, waits
+//! until its interface is actually reachable and returns it together with a connected
+//! client, avoiding sleep-based races in tests.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Calculator {
+//!     fn add(a: i32, b: i32) -> i32;
+//! }
+//!
+//! struct CalculatorImpl;
+//! impl CalculatorServerImpl for CalculatorImpl {
+//!     fn add(a: i32, b: i32) -> i32 {
+//!         a + b
+//!     }
+//! }
+//!
+//! # fn main() -> windows::core::Result<()> {
+//! let (_server, client) = windows_rpc::testing::pair::<CalculatorServer<_>, _>(CalculatorImpl)?;
+//! assert_eq!(client.add(1, 2)?, 3);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use windows::Win32::System::Rpc::{RPC_S_ALREADY_LISTENING, RPC_S_UNKNOWN_IF};
+use windows::core::Result;
+
+use crate::ProtocolSequence;
+use crate::client_binding::ClientBinding;
+use crate::interface::{RpcClient, RpcServer};
+use crate::mgmt::inq_if_ids;
+
+/// How long [`pair()`] waits for the server to become reachable.
+pub const READY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A running server that is stopped when dropped.
+pub struct ServerGuard<S: RpcServer> {
+    server: S,
+    endpoint: String,
+}
+
+impl<S: RpcServer> ServerGuard<S> {
+    /// Returns the endpoint the server is registered on.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Creates another client connected to the server.
+    pub fn client(&self) -> Result<S::Client> {
+        Ok(S::Client::new(ClientBinding::new(
+            ProtocolSequence::Alpc,
+            &self.endpoint,
+        )?))
+    }
+}
+
+impl<S: RpcServer> Deref for ServerGuard<S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.server
+    }
+}
+
+impl<S: RpcServer> DerefMut for ServerGuard<S> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.server
+    }
+}
+
+impl<S: RpcServer> Drop for ServerGuard<S> {
+    fn drop(&mut self) {
+        // Best effort cleanup
+        let _ = self.server.stop();
+    }
+}
+
+/// Returns an endpoint name that is unique within the machine.
+pub fn unique_endpoint() -> String {
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    format!(
+        "windows_rpc_test_{}_{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Starts a server for `instance` on a unique endpoint and returns it with a connected
+/// client.
+///
+/// The server is created with `with_instance()`, so handlers reach `instance` through
+/// [`server::instance()`](crate::server::instance). Pass an `Arc` to keep a handle on the
+/// state the handlers change.
+///
+/// Returns once the server's interface is registered on the endpoint and the endpoint
+/// listening, or fails after [`READY_TIMEOUT`]. Other servers of the process listening
+/// don't make it return earlier.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be registered or started, the client
+/// binding cannot be created, or the interface does not become reachable in time.
+pub fn pair<S, I>(instance: I) -> Result<(ServerGuard<S>, S::Client)>
+where
+    S: RpcServer,
+    S::Impl: Send + Sync + 'static,
+    I: Into<Arc<S::Impl>>,
+{
+    let endpoint = unique_endpoint();
+
+    let mut server = S::with_instance(instance.into());
+    server.register(&endpoint)?;
+    match server.listen_async() {
+        // Another server in this process already started listening
+        Err(e) if e.code() == RPC_S_ALREADY_LISTENING.to_hresult() => {}
+        result => result?,
+    }
+
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, &endpoint)?;
+    wait_until_reachable::<S::Client>(&binding, READY_TIMEOUT)?;
+
+    Ok((ServerGuard { server, endpoint }, S::Client::new(binding)))
+}
+
+/// Waits until the server behind `binding` lists an interface version `C` can call
+fn wait_until_reachable<C: RpcClient>(binding: &ClientBinding, timeout: Duration) -> Result<()> {
+    let (major, minor) = C::VERSION;
+    let deadline = Instant::now() + timeout;
+    loop {
+        // Fails until the endpoint is listening
+        let registered = inq_if_ids(binding).is_ok_and(|available| {
+            available
+                .iter()
+                .any(|&(guid, (server_major, server_minor))| {
+                    guid == C::GUID && server_major == major && server_minor >= minor
+                })
+        });
+        if registered {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return RPC_S_UNKNOWN_IF.ok();
+        }
+        thread::sleep(READY_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,679 @@
+//! Wide (UTF-16) string parameters without per-call conversion overhead.
+//!
+//! String parameters cross the RPC boundary as null-terminated UTF-16 strings. By default
+//! the generated code converts them from and to Rust strings on every call. This module
+//! provides the fast paths around that:
+//!
+//! - Client methods accept any [`AsWideStr`], so pre-encoded strings ([`U16CStr`],
+//!   [`HSTRING`]) are passed without conversion. Rust strings are encoded into
+//!   per-thread buffers that are reused across calls.
+//! - Server handlers decode `&str` parameters into reused per-thread buffers, and hand
+//!   the decoded buffer over to handlers taking a `String` parameter.
+//! - Declaring a parameter as `&U16CStr` instead of `&str` lets the handler borrow the
+//!   string from the RPC buffer as-is, skipping the UTF-8 round trip entirely.
+//! - Parameters declared as `&OsStr`, `&Path`, `OsString` or `PathBuf` are converted
+//!   without loss, so file names that aren't valid UTF-16 survive the call.
+//!
+//! [`HSTRING`] (`&HSTRING` parameters, `HSTRING` return values) and [`U16CString`]
+//! (return values) are also supported as string types in interface definitions, for
+//! applications that already hold their strings as UTF-16.
+//!
+//! With the `widestring` feature, [`U16CStr`] and [`U16CString`] convert to and from
+//! their `widestring` crate counterparts without copying, and client methods accept the
+//! `widestring` types directly.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use windows_rpc::rpc_interface;
+//! use windows_rpc::wstr::U16CStr;
+//!
+//! #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+//! trait Files {
+//!     fn exists(path: &U16CStr) -> u8;
+//! }
+//!
+//! struct FilesImpl;
+//! impl FilesServerImpl for FilesImpl {
+//!     fn exists(path: &U16CStr) -> u8 {
+//!         // Pass the path to a wide Win32 API without converting it
+//!         let _path = path.as_pcwstr();
+//!         1
+//!     }
+//! }
+//! ```
+
+use std::borrow::{Borrow, Cow};
+use std::cell::RefCell;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::ops::Deref;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::string::FromUtf16Error;
+
+use windows::Win32::Globalization::{
+    CP_ACP, MULTI_BYTE_TO_WIDE_CHAR_FLAGS, MultiByteToWideChar, WC_NO_BEST_FIT_CHARS,
+    WideCharToMultiByte,
+};
+use windows::core::{HSTRING, PCSTR, PCWSTR};
+
+/// Maximum number of buffers kept per thread.
+const MAX_POOLED_BUFFERS: usize = 8;
+/// Buffers that grew beyond this many elements are not kept.
+const MAX_POOLED_CAPACITY: usize = 4096;
+
+/// A borrowed null-terminated UTF-16 string.
+///
+/// This is the wide equivalent of [`std::ffi::CStr`]. The terminator is part of the
+/// underlying slice but excluded from [`as_slice()`](Self::as_slice).
+#[repr(transparent)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U16CStr {
+    // Always ends with the null terminator, which is the only zero in the slice
+    inner: [u16],
+}
+
+impl U16CStr {
+    /// Wraps a null-terminated slice.
+    ///
+    /// Returns `None` if `slice` doesn't end with a zero or contains another zero.
+    pub fn from_slice_with_nul(slice: &[u16]) -> Option<&Self> {
+        match slice.iter().position(|&c| c == 0) {
+            Some(end) if end == slice.len() - 1 => {
+                Some(unsafe { Self::from_slice_with_nul_unchecked(slice) })
+            }
+            _ => None,
+        }
+    }
+
+    /// Wraps a null-terminated slice without checking it.
+    ///
+    /// # Safety
+    ///
+    /// `slice` must end with a zero and contain no other zeros.
+    pub unsafe fn from_slice_with_nul_unchecked(slice: &[u16]) -> &Self {
+        unsafe { &*(slice as *const [u16] as *const Self) }
+    }
+
+    /// Wraps a null-terminated string, scanning it for its terminator.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null and point to a null-terminated string that stays valid and
+    /// unmodified for `'a`.
+    pub unsafe fn from_ptr<'a>(ptr: *const u16) -> &'a Self {
+        unsafe {
+            let mut len = 0;
+            while *ptr.add(len) != 0 {
+                len += 1;
+            }
+            Self::from_slice_with_nul_unchecked(std::slice::from_raw_parts(ptr, len + 1))
+        }
+    }
+
+    /// Returns the string without its terminator.
+    pub fn as_slice(&self) -> &[u16] {
+        &self.inner[..self.inner.len() - 1]
+    }
+
+    /// Returns the string including its terminator.
+    pub fn as_slice_with_nul(&self) -> &[u16] {
+        &self.inner
+    }
+
+    /// Returns a pointer to the string, for passing it to wide Win32 APIs.
+    pub fn as_pcwstr(&self) -> PCWSTR {
+        PCWSTR(self.inner.as_ptr())
+    }
+
+    /// Returns the length in UTF-16 code units, excluding the terminator.
+    pub fn len(&self) -> usize {
+        self.inner.len() - 1
+    }
+
+    /// Returns `true` if the string has no characters.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decodes the string, failing on invalid UTF-16.
+    pub fn to_string(&self) -> Result<String, FromUtf16Error> {
+        String::from_utf16(self.as_slice())
+    }
+
+    /// Decodes the string, replacing invalid UTF-16 with `U+FFFD`.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_slice())
+    }
+
+    /// Converts the string to an OS string, keeping invalid UTF-16 such as unpaired
+    /// surrogates, which Windows allows in file names.
+    pub fn to_os_string(&self) -> OsString {
+        OsString::from_wide(self.as_slice())
+    }
+}
+
+impl fmt::Debug for U16CStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl AsRef<[u16]> for U16CStr {
+    fn as_ref(&self) -> &[u16] {
+        self.as_slice()
+    }
+}
+
+impl ToOwned for U16CStr {
+    type Owned = U16CString;
+
+    fn to_owned(&self) -> U16CString {
+        U16CString {
+            inner: self.inner.to_vec(),
+        }
+    }
+}
+
+/// An owned null-terminated UTF-16 string.
+///
+/// This is the wide equivalent of [`std::ffi::CString`], and dereferences to [`U16CStr`].
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U16CString {
+    // Always ends with the null terminator, which is the only zero in the vector
+    inner: Vec<u16>,
+}
+
+impl U16CString {
+    /// Creates a string from UTF-16 code units, without a terminator.
+    ///
+    /// Returns `None` if `wide` contains a zero.
+    pub fn from_vec(mut wide: Vec<u16>) -> Option<Self> {
+        if wide.contains(&0) {
+            return None;
+        }
+        wide.push(0);
+        Some(Self { inner: wide })
+    }
+
+    /// Creates a string from UTF-16 code units, truncating it at the first zero.
+    pub fn from_slice_truncate(wide: &[u16]) -> Self {
+        let end = wide.iter().position(|&c| c == 0).unwrap_or(wide.len());
+        let mut inner = Vec::with_capacity(end + 1);
+        inner.extend_from_slice(&wide[..end]);
+        inner.push(0);
+        Self { inner }
+    }
+
+    /// Encodes a Rust string, truncating it at the first null character.
+    pub fn from_str_truncate(s: &str) -> Self {
+        let mut inner: Vec<u16> = s.encode_utf16().take_while(|&c| c != 0).collect();
+        inner.push(0);
+        Self { inner }
+    }
+
+    /// Returns the code units, without the terminator.
+    pub fn into_vec(mut self) -> Vec<u16> {
+        self.inner.pop();
+        self.inner
+    }
+}
+
+impl Default for U16CString {
+    fn default() -> Self {
+        Self { inner: vec![0] }
+    }
+}
+
+impl Deref for U16CString {
+    type Target = U16CStr;
+
+    fn deref(&self) -> &U16CStr {
+        unsafe { U16CStr::from_slice_with_nul_unchecked(&self.inner) }
+    }
+}
+
+impl Borrow<U16CStr> for U16CString {
+    fn borrow(&self) -> &U16CStr {
+        self
+    }
+}
+
+impl AsRef<U16CStr> for U16CString {
+    fn as_ref(&self) -> &U16CStr {
+        self
+    }
+}
+
+impl From<&U16CStr> for U16CString {
+    fn from(s: &U16CStr) -> Self {
+        s.to_owned()
+    }
+}
+
+impl fmt::Debug for U16CString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+thread_local! {
+    static WIDE_BUFFERS: RefCell<Vec<Vec<u16>>> = const { RefCell::new(Vec::new()) };
+    static STRING_BUFFERS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn take_buffer<T: Default>(pool: &'static std::thread::LocalKey<RefCell<Vec<T>>>) -> T {
+    pool.try_with(|pool| pool.try_borrow_mut().ok()?.pop())
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+fn return_buffer<T>(pool: &'static std::thread::LocalKey<RefCell<Vec<T>>>, buffer: T) {
+    let _ = pool.try_with(|pool| {
+        if let Ok(mut pool) = pool.try_borrow_mut()
+            && pool.len() < MAX_POOLED_BUFFERS
+        {
+            pool.push(buffer);
+        }
+    });
+}
+
+/// A string encoded for a call, either borrowed or held in a reused buffer.
+pub struct WideArg<'a> {
+    inner: WideArgInner<'a>,
+}
+
+enum WideArgInner<'a> {
+    Borrowed(&'a U16CStr),
+    Pooled(Vec<u16>),
+}
+
+impl WideArg<'_> {
+    fn encode(s: &str) -> Self {
+        Self::encode_wide(s.encode_utf16())
+    }
+
+    fn encode_wide(wide: impl Iterator<Item = u16>) -> Self {
+        let mut buffer: Vec<u16> = take_buffer(&WIDE_BUFFERS);
+        buffer.clear();
+        buffer.extend(wide);
+        buffer.push(0);
+        Self {
+            inner: WideArgInner::Pooled(buffer),
+        }
+    }
+
+    /// Returns a pointer to the null-terminated string, valid while `self` is alive.
+    pub fn as_pcwstr(&self) -> PCWSTR {
+        match &self.inner {
+            WideArgInner::Borrowed(s) => s.as_pcwstr(),
+            WideArgInner::Pooled(buffer) => PCWSTR(buffer.as_ptr()),
+        }
+    }
+}
+
+impl Drop for WideArg<'_> {
+    fn drop(&mut self) {
+        if let WideArgInner::Pooled(buffer) = &mut self.inner
+            && buffer.capacity() <= MAX_POOLED_CAPACITY
+        {
+            return_buffer(&WIDE_BUFFERS, std::mem::take(buffer));
+        }
+    }
+}
+
+/// Strings that can be passed as string parameters of client methods.
+pub trait AsWideStr {
+    /// Encodes the string for a call.
+    fn to_wide_arg(&self) -> WideArg<'_>;
+
+    /// Encodes the string into an owned buffer, e.g. for a call made on another thread.
+    fn to_wide_string(&self) -> U16CString {
+        let arg = self.to_wide_arg();
+        // SAFETY: Encoded arguments are null-terminated and live until the end of the scope
+        let wide = unsafe { U16CStr::from_ptr(arg.as_pcwstr().0) };
+        U16CString::from_slice_truncate(wide.as_slice())
+    }
+}
+
+impl AsWideStr for str {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        WideArg::encode(self)
+    }
+}
+
+impl AsWideStr for String {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        WideArg::encode(self)
+    }
+}
+
+impl AsWideStr for Cow<'_, str> {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        WideArg::encode(self)
+    }
+}
+
+// OS strings and paths are encoded losslessly, including unpaired surrogates
+impl AsWideStr for OsStr {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        WideArg::encode_wide(self.encode_wide())
+    }
+}
+
+impl AsWideStr for OsString {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        self.as_os_str().to_wide_arg()
+    }
+}
+
+impl AsWideStr for Path {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        self.as_os_str().to_wide_arg()
+    }
+}
+
+impl AsWideStr for PathBuf {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        self.as_os_str().to_wide_arg()
+    }
+}
+
+impl<T: AsWideStr + ?Sized> AsWideStr for &T {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        (**self).to_wide_arg()
+    }
+}
+
+impl AsWideStr for U16CStr {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        WideArg {
+            inner: WideArgInner::Borrowed(self),
+        }
+    }
+}
+
+impl AsWideStr for U16CString {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        (**self).to_wide_arg()
+    }
+}
+
+impl AsWideStr for HSTRING {
+    fn to_wide_arg(&self) -> WideArg<'_> {
+        // HSTRING is always null-terminated and has no interior nulls in practice.
+        // Interior nulls would only truncate the string on the wire.
+        WideArg {
+            inner: WideArgInner::Borrowed(unsafe { U16CStr::from_ptr(self.as_ptr()) }),
+        }
+    }
+}
+
+/// A string parameter decoded on the server into a reused buffer.
+#[doc(hidden)]
+pub struct DecodedStr {
+    buffer: String,
+}
+
+impl DecodedStr {
+    /// Decodes a null-terminated string received by a server wrapper.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid null-terminated string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string isn't valid UTF-16.
+    pub unsafe fn decode(ptr: PCWSTR) -> Self {
+        let wide = unsafe { U16CStr::from_ptr(ptr.0) };
+        let mut buffer: String = take_buffer(&STRING_BUFFERS);
+        buffer.clear();
+        for c in char::decode_utf16(wide.as_slice().iter().copied()) {
+            buffer.push(c.expect("string parameter is not valid UTF-16"));
+        }
+        Self { buffer }
+    }
+
+    /// Returns the decoded string, for handlers taking a `&String` parameter.
+    pub fn as_string(&self) -> &String {
+        &self.buffer
+    }
+
+    /// Takes the decoded string, for handlers taking a `String` parameter.
+    pub fn into_string(mut self) -> String {
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+impl Deref for DecodedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.buffer
+    }
+}
+
+impl Drop for DecodedStr {
+    fn drop(&mut self) {
+        if self.buffer.capacity() <= MAX_POOLED_CAPACITY {
+            return_buffer(&STRING_BUFFERS, std::mem::take(&mut self.buffer));
+        }
+    }
+}
+
+/// Converts a string parameter marked `#[rpc(ansi)]` to the ANSI code page of the
+/// system, null-terminated. Characters missing from the code page are replaced by its
+/// default character rather than by lookalikes (`WC_NO_BEST_FIT_CHARS`).
+#[doc(hidden)]
+pub fn to_ansi(s: &str) -> Vec<u8> {
+    let wide: Vec<u16> = s.encode_utf16().collect();
+    if wide.is_empty() {
+        return vec![0];
+    }
+    let len = unsafe {
+        WideCharToMultiByte(
+            CP_ACP,
+            WC_NO_BEST_FIT_CHARS,
+            &wide,
+            None,
+            PCSTR::null(),
+            None,
+        )
+    };
+    let mut ansi = vec![0; len as usize + 1];
+    let written = unsafe {
+        WideCharToMultiByte(
+            CP_ACP,
+            WC_NO_BEST_FIT_CHARS,
+            &wide,
+            Some(&mut ansi[..len as usize]),
+            PCSTR::null(),
+            None,
+        )
+    };
+    ansi.truncate(written as usize);
+    ansi.push(0);
+    ansi
+}
+
+/// Decodes a null-terminated string in the ANSI code page of the system, received by a
+/// server wrapper for a parameter marked `#[rpc(ansi)]`.
+///
+/// # Safety
+///
+/// `ptr` must point to a valid null-terminated string.
+#[doc(hidden)]
+pub unsafe fn decode_ansi(ptr: PCSTR) -> String {
+    let ansi = unsafe { ptr.as_bytes() };
+    if ansi.is_empty() {
+        return String::new();
+    }
+    let flags = MULTI_BYTE_TO_WIDE_CHAR_FLAGS(0);
+    let len = unsafe { MultiByteToWideChar(CP_ACP, flags, ansi, None) };
+    let mut wide = vec![0; len as usize];
+    let written = unsafe { MultiByteToWideChar(CP_ACP, flags, ansi, Some(&mut wide)) };
+    String::from_utf16_lossy(&wide[..written as usize])
+}
+
+/// String types that can be returned from interface methods.
+#[doc(hidden)]
+pub trait ReturnString: Sized {
+    /// Returns the string as UTF-16, without a terminator.
+    fn to_wide(&self) -> Cow<'_, [u16]>;
+
+    /// Creates the string from UTF-16 without a terminator or interior zeros.
+    fn from_wide(wide: &[u16]) -> Self;
+}
+
+impl ReturnString for String {
+    fn to_wide(&self) -> Cow<'_, [u16]> {
+        Cow::Owned(self.encode_utf16().collect())
+    }
+
+    fn from_wide(wide: &[u16]) -> Self {
+        String::from_utf16_lossy(wide)
+    }
+}
+
+impl ReturnString for HSTRING {
+    fn to_wide(&self) -> Cow<'_, [u16]> {
+        Cow::Borrowed(self)
+    }
+
+    fn from_wide(wide: &[u16]) -> Self {
+        HSTRING::from_wide(wide)
+    }
+}
+
+impl ReturnString for U16CString {
+    fn to_wide(&self) -> Cow<'_, [u16]> {
+        Cow::Borrowed(self.as_slice())
+    }
+
+    fn from_wide(wide: &[u16]) -> Self {
+        U16CString::from_slice_truncate(wide)
+    }
+}
+
+/// Copies a returned string into memory allocated with `midl_user_allocate`.
+///
+/// Returns null if the allocation fails.
+#[doc(hidden)]
+pub fn to_midl_string(value: &impl ReturnString) -> *mut u16 {
+    let wide = value.to_wide();
+    let ptr = crate::alloc::midl_alloc((wide.len() + 1) * std::mem::size_of::<u16>()) as *mut u16;
+    if !ptr.is_null() {
+        unsafe {
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), ptr, wide.len());
+            *ptr.add(wide.len()) = 0;
+        }
+    }
+    ptr
+}
+
+/// Converts a string returned by a server and frees it with `midl_user_free`.
+///
+/// # Safety
+///
+/// `ptr` must be null or a null-terminated string allocated by `midl_user_allocate`.
+#[doc(hidden)]
+pub unsafe fn from_midl_string<T: ReturnString>(ptr: *mut u16) -> T {
+    if ptr.is_null() {
+        return T::from_wide(&[]);
+    }
+
+    let result = T::from_wide(unsafe { U16CStr::from_ptr(ptr) }.as_slice());
+    crate::alloc::midl_free(ptr as *mut core::ffi::c_void);
+    result
+}
+
+/// Copies returned strings into memory allocated with `midl_user_allocate`, each string
+/// and the array of their pointers, returning its length and address.
+///
+/// Returns an empty null array if the vector is empty or the allocation of the array
+/// fails; strings that fail to allocate are null.
+#[doc(hidden)]
+pub fn to_midl_string_array(values: &[impl ReturnString]) -> (u32, *mut *mut u16) {
+    let Ok(len) = u32::try_from(values.len()) else {
+        return (0, std::ptr::null_mut());
+    };
+    if len == 0 {
+        return (0, std::ptr::null_mut());
+    }
+    let ptr =
+        crate::alloc::midl_alloc(values.len() * std::mem::size_of::<*mut u16>()) as *mut *mut u16;
+    if ptr.is_null() {
+        return (0, std::ptr::null_mut());
+    }
+    for (index, value) in values.iter().enumerate() {
+        unsafe { ptr.add(index).write(to_midl_string(value)) };
+    }
+    (len, ptr)
+}
+
+/// Converts strings returned by a server, and frees them and their array with
+/// `midl_user_free`.
+///
+/// # Safety
+///
+/// `ptr` must be null or an array of `len` pointers allocated by `midl_user_allocate`,
+/// each of which [`from_midl_string()`] accepts.
+#[doc(hidden)]
+pub unsafe fn from_midl_string_array<T: ReturnString>(ptr: *mut *mut u16, len: u32) -> Vec<T> {
+    if ptr.is_null() {
+        return Vec::new();
+    }
+
+    let result = unsafe { std::slice::from_raw_parts(ptr, len as usize) }
+        .iter()
+        .map(|&string| unsafe { from_midl_string(string) })
+        .collect();
+    crate::alloc::midl_free(ptr as *mut core::ffi::c_void);
+    result
+}
+
+#[cfg(feature = "widestring")]
+mod widestring_support {
+    use super::{AsWideStr, U16CStr, U16CString, WideArg};
+
+    impl AsRef<widestring::U16CStr> for U16CStr {
+        fn as_ref(&self) -> &widestring::U16CStr {
+            unsafe { widestring::U16CStr::from_slice_unchecked(self.as_slice_with_nul()) }
+        }
+    }
+
+    impl<'a> From<&'a widestring::U16CStr> for &'a U16CStr {
+        fn from(s: &'a widestring::U16CStr) -> Self {
+            unsafe { U16CStr::from_slice_with_nul_unchecked(s.as_slice_with_nul()) }
+        }
+    }
+
+    impl From<U16CString> for widestring::U16CString {
+        fn from(s: U16CString) -> Self {
+            unsafe { widestring::U16CString::from_vec_unchecked(s.inner) }
+        }
+    }
+
+    impl From<widestring::U16CString> for U16CString {
+        fn from(s: widestring::U16CString) -> Self {
+            Self {
+                inner: s.into_vec_with_nul(),
+            }
+        }
+    }
+
+    impl AsWideStr for widestring::U16CStr {
+        fn to_wide_arg(&self) -> WideArg<'_> {
+            <&U16CStr>::from(self).to_wide_arg()
+        }
+    }
+
+    impl AsWideStr for widestring::U16CString {
+        fn to_wide_arg(&self) -> WideArg<'_> {
+            <&U16CStr>::from(&**self).to_wide_arg()
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+// Only version 1.0 of this interface is served
+#[rpc_interface(guid(0x4d7a2c91_b36e_4f08_9e15_a8c3f60d72b4), version(1.0))]
+trait Search {
+    fn search(query: &str) -> String;
+    fn count(a: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x4d7a2c91_b36e_4f08_9e15_a8c3f60d72b4), version(1.2))]
+trait SearchV12 {
+    fn search(
+        query: &str,
+        #[since(1.1, default = 10)] limit: u32,
+        #[since(1.2, default = "")] scope: &str,
+    ) -> String;
+    fn count(a: i32) -> i32;
+}
+
+// Version 1.2 of this interface is served, which added trailing parameters to `lookup`
+#[rpc_interface(guid(0xa61f08d3_2c7b_4e95_b4a0_6d9e13f2c857), version(1.0))]
+trait Lookup {
+    fn lookup(query: &str) -> String;
+    fn count(a: i32) -> i32;
+}
+
+#[rpc_interface(guid(0xa61f08d3_2c7b_4e95_b4a0_6d9e13f2c857), version(1.2))]
+trait LookupV12 {
+    fn lookup(
+        query: &str,
+        #[since(1.1, default = 10)] limit: u32,
+        #[since(1.2, default = "")] scope: &str,
+    ) -> String;
+    fn count(a: i32) -> i32;
+}
+
+struct OldImpl;
+impl SearchServerImpl for OldImpl {
+    fn search(query: &str) -> String {
+        format!("{query} (1.0)")
+    }
+
+    fn count(a: i32) -> i32 {
+        a + 1
+    }
+}
+
+struct NewImpl;
+impl LookupV12ServerImpl for NewImpl {
+    fn lookup(query: &str, limit: u32, scope: &str) -> String {
+        format!("{query} limit={limit} scope={scope:?}")
+    }
+
+    fn count(a: i32) -> i32 {
+        a + 2
+    }
+}
+
+fn binding(endpoint: &str) -> ClientBinding {
+    ClientBinding::new(ProtocolSequence::Alpc, endpoint).expect("Failed to create client binding")
+}
+
+#[test]
+fn test_new_client_old_server() {
+    let (server, _) = windows_rpc::testing::pair::<SearchServer<_>, _>(OldImpl)
+        .expect("Failed to create client/server pair");
+
+    let client = SearchV12Client::connect(binding(server.endpoint())).expect("Failed to connect");
+    assert_eq!(client.call_version(), (1, 0));
+    // The added parameters are omitted
+    assert_eq!(client.search("rust", 5, "docs").unwrap(), "rust (1.0)");
+    assert_eq!(client.count(1).unwrap(), 2);
+}
+
+#[test]
+fn test_new_server() {
+    let (server, _) = windows_rpc::testing::pair::<LookupV12Server<_>, _>(NewImpl)
+        .expect("Failed to create client/server pair");
+    let endpoint = server.endpoint();
+
+    let client = LookupV12Client::connect(binding(endpoint)).expect("Failed to connect");
+    assert_eq!(client.call_version(), (1, 2));
+    assert_eq!(
+        client.lookup("rust", 5, "docs").unwrap(),
+        "rust limit=5 scope=\"docs\""
+    );
+
+    // A 1.1 call carries the parameters added up to 1.1, the server fills in the rest
+    let client = LookupV12Client::with_server_version(binding(endpoint), (1, 1))
+        .expect("Failed to create client");
+    assert_eq!(client.call_version(), (1, 1));
+    assert_eq!(
+        client.lookup("rust", 5, "docs").unwrap(),
+        "rust limit=5 scope=\"\""
+    );
+
+    // Clients built against 1.0 are served with the defaults
+    let client = LookupClient::new(binding(endpoint));
+    assert_eq!(client.lookup("rust").unwrap(), "rust limit=10 scope=\"\"");
+    assert_eq!(client.count(1).unwrap(), 3);
+}
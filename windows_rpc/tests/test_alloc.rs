@@ -0,0 +1,62 @@
+use windows_rpc::alloc::{midl_alloc, midl_free, set_pooling};
+
+#[test]
+fn test_alloc_alignment_and_reuse() {
+    for size in [0, 1, 7, 32, 33, 500, 4096, 1 << 20] {
+        let ptr = midl_alloc(size);
+        assert!(!ptr.is_null(), "allocation of {size} bytes failed");
+        assert_eq!(
+            ptr as usize % 8,
+            0,
+            "allocation of {size} bytes is unaligned"
+        );
+        unsafe { std::ptr::write_bytes(ptr as *mut u8, 0xAB, size) };
+        midl_free(ptr);
+    }
+
+    // A freed small block is handed out again by the pool
+    let first = midl_alloc(40);
+    midl_free(first);
+    let second = midl_alloc(50);
+    assert_eq!(first, second, "small blocks should be reused");
+    midl_free(second);
+
+    // Blocks allocated with pooling enabled can be freed after disabling it
+    let pooled = midl_alloc(16);
+    set_pooling(false);
+    let unpooled = midl_alloc(16);
+    midl_free(pooled);
+    midl_free(unpooled);
+    set_pooling(true);
+
+    midl_free(std::ptr::null_mut());
+}
+
+#[test]
+fn test_alloc_quota() {
+    windows_rpc::alloc::with_quota(Some(1024), || {
+        let first = midl_alloc(600);
+        assert!(!first.is_null(), "allocation within the quota failed");
+        // Freeing does not restore the quota
+        midl_free(first);
+        assert!(
+            midl_alloc(600).is_null(),
+            "allocation beyond the quota should fail"
+        );
+
+        let small = midl_alloc(400);
+        assert!(!small.is_null(), "the rest of the quota should be usable");
+        midl_free(small);
+
+        windows_rpc::alloc::with_quota(None, || {
+            let large = midl_alloc(1 << 20);
+            assert!(!large.is_null(), "nested scopes replace the quota");
+            midl_free(large);
+        });
+        assert!(midl_alloc(64).is_null(), "the outer quota is restored");
+    });
+
+    let ptr = midl_alloc(1 << 20);
+    assert!(!ptr.is_null(), "allocations outside a quota are unlimited");
+    midl_free(ptr);
+}
@@ -0,0 +1,66 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x5d3a9c07_e81f_4b26_a4d9_07c2e6b18f53), version(1.0))]
+trait Legacy {
+    fn length(#[rpc(ansi)] text: &str) -> u32;
+    fn join(#[rpc(ansi)] a: &str, b: &str) -> String;
+    fn label(#[rpc(ansi)] name: Option<&str>) -> String;
+}
+
+struct LegacyImpl;
+impl LegacyServerImpl for LegacyImpl {
+    fn length(text: &str) -> u32 {
+        text.len() as u32
+    }
+
+    fn join(a: &str, b: &str) -> String {
+        format!("{a}{b}")
+    }
+
+    fn label(name: Option<&str>) -> String {
+        name.map_or_else(|| "(none)".to_string(), |name| format!("[{name}]"))
+    }
+}
+
+fn check_legacy(client: &LegacyClient) {
+    assert_eq!(client.length("narrow").unwrap(), 6);
+    assert_eq!(client.length("").unwrap(), 0);
+    assert_eq!(
+        client.join("narrow ", "and wide").unwrap(),
+        "narrow and wide"
+    );
+    // An empty string is not `None`
+    assert_eq!(client.label(Some("tag")).unwrap(), "[tag]");
+    assert_eq!(client.label(Some("")).unwrap(), "[]");
+    assert_eq!(client.label(None).unwrap(), "(none)");
+}
+
+#[test]
+fn test_ansi_strings() {
+    let (server, client) = windows_rpc::testing::pair::<LegacyServer<_>, _>(LegacyImpl)
+        .expect("Failed to create client/server pair");
+    check_legacy(&client);
+
+    let client = LegacyClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_legacy(&client);
+}
+
+#[test]
+fn test_ansi_string_metadata() {
+    let parameters = LegacyClient::METADATA.methods[1].parameters;
+    assert_eq!(parameters[0].r#type.rust_type, "&str");
+    assert_eq!(parameters[0].r#type.idl_type, "[string] char*");
+    assert_eq!(parameters[1].r#type.idl_type, "[string] wchar_t*");
+    assert!(LegacyClient::IDL.contains(
+        "void join([in] handle_t binding, [in, string] const char* a, \
+         [in, string] const wchar_t* b, [out, string] wchar_t** return_value);"
+    ));
+    let parameter = &LegacyClient::METADATA.methods[2].parameters[0];
+    assert_eq!(parameter.r#type.rust_type, "Option<&str>");
+    assert_eq!(parameter.r#type.idl_type, "[unique, string] char*");
+}
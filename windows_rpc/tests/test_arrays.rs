@@ -0,0 +1,82 @@
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x3b9f6c42_e071_4d58_8a2c_91d4f7e06b3a), version(1.0))]
+trait ArrayRpc {
+    fn sum(values: &[i32]) -> i64;
+    fn max(values: &[u64]) -> u64;
+    fn bytes(count: u32) -> Vec<u8>;
+    fn squares(values: &[i32]) -> Vec<i32>;
+}
+
+struct ArrayRpcImpl;
+impl ArrayRpcServerImpl for ArrayRpcImpl {
+    fn sum(values: &[i32]) -> i64 {
+        values.iter().map(|&v| v as i64).sum()
+    }
+
+    fn max(values: &[u64]) -> u64 {
+        values.iter().copied().max().unwrap_or_default()
+    }
+
+    fn bytes(count: u32) -> Vec<u8> {
+        (0..count).map(|i| i as u8).collect()
+    }
+
+    fn squares(values: &[i32]) -> Vec<i32> {
+        values.iter().map(|v| v * v).collect()
+    }
+}
+
+#[test]
+fn test_array_params() {
+    let (_server, client) = windows_rpc::testing::pair::<ArrayRpcServer<_>, _>(ArrayRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(
+        client.sum(&[1, -2, i32::MAX, i32::MAX]).unwrap(),
+        2 * i32::MAX as i64 - 1
+    );
+    assert_eq!(client.sum(&[]).unwrap(), 0);
+    assert_eq!(client.max(&[3, u64::MAX, 5]).unwrap(), u64::MAX);
+}
+
+#[test]
+fn test_vec_return() {
+    let (_server, client) = windows_rpc::testing::pair::<ArrayRpcServer<_>, _>(ArrayRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.bytes(3).unwrap(), [0, 1, 2]);
+    assert_eq!(
+        client.bytes(0).unwrap(),
+        [0u8; 0],
+        "empty vectors should be supported"
+    );
+    let large = client.bytes(1 << 16).unwrap();
+    assert_eq!(large.len(), 1 << 16);
+    assert_eq!(large[0x1ff], 0xff);
+    assert_eq!(client.squares(&[-3, 0, 4]).unwrap(), [9, 0, 16]);
+}
+
+#[test]
+fn test_slice_length_overflow() {
+    // Slices too long for the 32-bit hidden length fail the call instead of panicking
+    assert_eq!(windows_rpc::ndr::slice_length(3).unwrap(), 3);
+    let error = windows_rpc::ndr::slice_length(u32::MAX as usize + 1).unwrap_err();
+    assert_eq!(
+        error.status().0,
+        windows::Win32::Foundation::RPC_X_BAD_STUB_DATA
+    );
+}
+
+#[test]
+fn test_array_metadata() {
+    let methods = ArrayRpcClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.rust_type, "&[i32]");
+    assert_eq!(
+        methods[0].parameters[0].r#type.idl_type,
+        "[size_is(length)] long*"
+    );
+    let return_type = methods[2].return_type.unwrap();
+    assert_eq!(return_type.rust_type, "Vec<u8>");
+    assert_eq!(return_type.idl_type, "[size_is(, *length)] byte**");
+}
@@ -0,0 +1,109 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use windows_rpc::executor::SingleThreadExecutor;
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x47b0e2c9_85d3_4a16_9e7f_c2a14d68b3f0), version(1.0))]
+trait AsyncRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn greet(name: &str) -> String;
+    fn sum(values: &[u32]) -> u32;
+    fn sleep(millis: u32);
+}
+
+struct AsyncRpcImpl;
+impl AsyncRpcServerImpl for AsyncRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn greet(name: &str) -> String {
+        format!("Hello, {name}!")
+    }
+
+    fn sum(values: &[u32]) -> u32 {
+        values.iter().sum()
+    }
+
+    fn sleep(millis: u32) {
+        thread::sleep(std::time::Duration::from_millis(millis.into()));
+    }
+}
+
+/// Wakes the thread blocked in `block_on`
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[test]
+fn test_async_calls() {
+    let (_server, client) = windows_rpc::testing::pair::<AsyncRpcServer<_>, _>(AsyncRpcImpl)
+        .expect("Failed to create client/server pair");
+    let client = Arc::new(client);
+
+    assert_eq!(block_on(client.add_async(1, 2)).unwrap(), 3);
+    let name = String::from("async");
+    let greeting = client.greet_async(&name);
+    // The call does not borrow its parameters
+    drop(name);
+    assert_eq!(block_on(greeting).unwrap(), "Hello, async!");
+    assert_eq!(block_on(client.sum_async(&[1, 2, 3])).unwrap(), 6);
+
+    // Calls in flight at the same time
+    let slow = client.sleep_async(200);
+    let fast = client.add_async(2, 3);
+    assert_eq!(block_on(fast).unwrap(), 5);
+    block_on(slow).unwrap();
+}
+
+#[test]
+fn test_async_calls_on_executor() {
+    let (_server, client) = windows_rpc::testing::pair::<AsyncRpcServer<_>, _>(AsyncRpcImpl)
+        .expect("Failed to create client/server pair");
+    let mut client = client;
+    client.set_executor(SingleThreadExecutor::new());
+    let client = Arc::new(client);
+
+    assert_eq!(block_on(client.add_async(20, 22)).unwrap(), 42);
+    assert_eq!(
+        block_on(client.greet_async("executor")).unwrap(),
+        "Hello, executor!"
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_async_calls_on_tokio() {
+    let (_server, client) = windows_rpc::testing::pair::<AsyncRpcServer<_>, _>(AsyncRpcImpl)
+        .expect("Failed to create client/server pair");
+    let client = Arc::new(client);
+
+    // Without an executor, calls run on the runtime's blocking pool
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Failed to create runtime");
+    assert_eq!(runtime.block_on(client.add_async(4, 5)).unwrap(), 9);
+    assert_eq!(
+        runtime.block_on(client.greet_async("tokio")).unwrap(),
+        "Hello, tokio!"
+    );
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use windows::Win32::System::Rpc::RPC_S_SERVER_UNAVAILABLE;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0x1e7b3d95_c48a_4f26_b3d1_8a5e20c7f946), version(1.0))]
+trait BindRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct BindRpcImpl;
+impl BindRpcServerImpl for BindRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_bind_to_server() {
+    let (server, _) = windows_rpc::testing::pair::<BindRpcServer<_>, _>(BindRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+        .expect("Failed to create client binding");
+    binding
+        .bind(Duration::from_secs(5))
+        .expect("Failed to bind to a listening server");
+
+    let client = BindRpcClient::new(binding);
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+}
+
+#[test]
+fn test_bind_to_missing_server() {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "test_bind_no_such_endpoint")
+        .expect("Failed to create client binding");
+
+    let error = binding
+        .bind(Duration::from_secs(1))
+        .expect_err("binding to a missing endpoint should fail");
+    assert_eq!(error.status(), RPC_S_SERVER_UNAVAILABLE);
+}
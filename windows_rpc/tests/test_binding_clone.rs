@@ -0,0 +1,46 @@
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0x9d2c6e81_4a7f_4b13_b5e9_07f3a8c14d2e), version(1.0))]
+trait CloneRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct CloneRpcImpl;
+impl CloneRpcServerImpl for CloneRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_clone_outlives_original() {
+    let (server, _) = windows_rpc::testing::pair::<CloneRpcServer<_>, _>(CloneRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+        .expect("Failed to create client binding");
+    let copy = binding.clone();
+    assert_ne!(copy.handle(), binding.handle());
+    assert_eq!(copy.string_binding(), binding.string_binding());
+
+    let client = CloneRpcClient::new(binding);
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    // Freeing the original handle leaves the copy usable
+    drop(client);
+
+    let client = CloneRpcClient::new(copy);
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+}
+
+#[test]
+fn test_bindings_are_freed() {
+    let (server, _) = windows_rpc::testing::pair::<CloneRpcServer<_>, _>(CloneRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    for i in 0..1000 {
+        let binding = ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding");
+        let client = CloneRpcClient::new(binding.clone());
+        assert_eq!(client.add(i, 1).unwrap(), i + 1);
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+use std::thread;
+
+use windows_rpc::pool::BindingPool;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0x4b8e2f17_a3c6_4d95_8e1b_5c7a09d36f42), version(1.0))]
+trait PoolRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct PoolRpcImpl;
+impl PoolRpcServerImpl for PoolRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_binding_pool() {
+    let (server, _) = windows_rpc::testing::pair::<PoolRpcServer<_>, _>(PoolRpcImpl)
+        .expect("Failed to create client/server pair");
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+        .expect("Failed to create client binding");
+    let pool = BindingPool::<PoolRpcClient>::new(binding);
+    assert!(pool.is_empty());
+
+    let clients = thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let pool = &pool;
+                scope.spawn(move || {
+                    let client = pool.client().expect("Failed to get a client");
+                    for j in 0..100 {
+                        assert_eq!(client.add(i, j).unwrap(), i + j);
+                    }
+                    // A thread keeps its client
+                    let again = pool.client().expect("Failed to get a client");
+                    assert!(Arc::ptr_eq(&client, &again));
+                    client
+                })
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().expect("a calling thread panicked"))
+            .collect::<Vec<_>>()
+    });
+
+    // Each thread got its own client
+    assert_eq!(pool.len(), 4);
+    for (i, client) in clients.iter().enumerate() {
+        assert!(
+            clients[i + 1..]
+                .iter()
+                .all(|other| !Arc::ptr_eq(client, other))
+        );
+    }
+
+    pool.clear();
+    assert!(pool.is_empty());
+    assert_eq!(clients[0].add(1, 2).unwrap(), 3);
+}
@@ -0,0 +1,55 @@
+use std::ffi::c_void;
+use std::ptr;
+
+use windows::Win32::System::Rpc::RpcBindingFromStringBindingW;
+use windows::core::HSTRING;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0x1c5f8a39_6e24_4d07_9a3b_e82d47f6c915), version(1.0))]
+trait RawRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct RawRpcImpl;
+impl RawRpcServerImpl for RawRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_into_raw_and_back() {
+    let (server, _) = windows_rpc::testing::pair::<RawRpcServer<_>, _>(RawRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+        .expect("Failed to create client binding");
+    let handle = binding.into_raw();
+    assert!(!handle.is_null());
+
+    let binding = unsafe { ClientBinding::from_raw(handle) };
+    assert_eq!(binding.handle(), handle);
+    assert_eq!(
+        binding.string_binding(),
+        format!("ncalrpc:[{}]", server.endpoint())
+    );
+
+    let client = RawRpcClient::new(binding);
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+}
+
+#[test]
+fn test_adopt_foreign_handle() {
+    let (server, _) = windows_rpc::testing::pair::<RawRpcServer<_>, _>(RawRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    // A handle created without the crate, as another library would
+    let string_binding = format!("ncalrpc:[{}]", server.endpoint());
+    let mut handle: *mut c_void = ptr::null_mut();
+    unsafe { RpcBindingFromStringBindingW(&HSTRING::from(&string_binding), &raw mut handle) }
+        .ok()
+        .expect("Failed to create binding handle");
+
+    let client = RawRpcClient::new(unsafe { ClientBinding::from_raw(handle) });
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+}
@@ -0,0 +1,39 @@
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x6d4e8b21_93a5_4c7f_b1e2_58a0f3c9d714), version(1.0))]
+trait BufferRpc {
+    fn checksum(data: &[u8]) -> u32;
+    fn describe(tag: &str, data: &[u8], extra: i32) -> String;
+}
+
+struct BufferRpcImpl;
+impl BufferRpcServerImpl for BufferRpcImpl {
+    fn checksum(data: &[u8]) -> u32 {
+        data.iter().map(|&b| b as u32).sum()
+    }
+
+    fn describe(tag: &str, data: &[u8], extra: i32) -> String {
+        format!("{tag}: {} bytes, extra {extra}", data.len())
+    }
+}
+
+#[test]
+fn test_byte_slice() {
+    let (_server, client) = windows_rpc::testing::pair::<BufferRpcServer<_>, _>(BufferRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.checksum(&[1, 2, 3, 4]).unwrap(), 10);
+    assert_eq!(
+        client.checksum(&[]).unwrap(),
+        0,
+        "empty slices should be supported"
+    );
+
+    let large = vec![1u8; 1 << 20];
+    assert_eq!(client.checksum(&large).unwrap(), 1 << 20);
+
+    assert_eq!(
+        client.describe("blob", &[0; 16], 7).unwrap(),
+        "blob: 16 bytes, extra 7"
+    );
+}
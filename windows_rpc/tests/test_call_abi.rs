@@ -0,0 +1,93 @@
+//! Calls whose arguments exercise the variadic `NdrClientCall3` convention: x64 passes the
+//! first four arguments in registers and ARM64 the first eight, all in integer registers,
+//! so later ones are read from the stack by both the client stub and the server routine.
+
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x9e37b2d4_5a18_4c6f_b093_7d1e4a2c8f65), version(1.0))]
+trait CallAbi {
+    fn narrow(a: u8, b: i8, c: u16, d: i16) -> i64;
+    fn echo_narrow(value: i8) -> i8;
+    fn many(a: i32, b: i64, c: u8, d: u32, e: i16, f: u64, g: i32, h: i64, i: u16, j: i32) -> i64;
+    fn straddle(a: i32, b: i32, c: i32, d: i32, e: i32, f: i32, values: &[u32]) -> u64;
+    fn late_floats(a: i64, b: i64, c: i64, d: i64, e: i64, f: i64, g: i64, h: f64, i: f32) -> f64;
+}
+
+struct CallAbiImpl;
+impl CallAbiServerImpl for CallAbiImpl {
+    fn narrow(a: u8, b: i8, c: u16, d: i16) -> i64 {
+        i64::from(a) + i64::from(b) + i64::from(c) + i64::from(d)
+    }
+
+    fn echo_narrow(value: i8) -> i8 {
+        value
+    }
+
+    fn many(a: i32, b: i64, c: u8, d: u32, e: i16, f: u64, g: i32, h: i64, i: u16, j: i32) -> i64 {
+        i64::from(a)
+            + b
+            + i64::from(c)
+            + i64::from(d)
+            + i64::from(e)
+            + f as i64
+            + i64::from(g)
+            + h
+            + i64::from(i)
+            + i64::from(j)
+    }
+
+    fn straddle(a: i32, b: i32, c: i32, d: i32, e: i32, f: i32, values: &[u32]) -> u64 {
+        let sum: u64 = values.iter().map(|&value| u64::from(value)).sum();
+        sum + [a, b, c, d, e, f]
+            .iter()
+            .map(|&value| value as u64)
+            .sum::<u64>()
+    }
+
+    fn late_floats(a: i64, b: i64, c: i64, d: i64, e: i64, f: i64, g: i64, h: f64, i: f32) -> f64 {
+        (a + b + c + d + e + f + g) as f64 + h + f64::from(i)
+    }
+}
+
+#[test]
+fn test_narrow_integers() {
+    let (_server, client) = windows_rpc::testing::pair::<CallAbiServer<_>, _>(CallAbiImpl)
+        .expect("Failed to create client/server pair");
+
+    // Promoted to `int` by the call, and sign-extended from their own width
+    assert_eq!(
+        client.narrow(u8::MAX, -1, u16::MAX, -2).unwrap(),
+        255 - 1 + 65535 - 2
+    );
+    assert_eq!(client.echo_narrow(i8::MIN).unwrap(), i8::MIN);
+}
+
+#[test]
+fn test_stack_arguments() {
+    let (_server, client) = windows_rpc::testing::pair::<CallAbiServer<_>, _>(CallAbiImpl)
+        .expect("Failed to create client/server pair");
+
+    // 64-bit values in both register and stack slots
+    assert_eq!(
+        client
+            .many(1, 1 << 40, 3, 4, -5, 1 << 33, 7, -(1 << 36), 9, 10)
+            .unwrap(),
+        1 + (1 << 40) + 3 + 4 - 5 + (1 << 33) + 7 - (1 << 36) + 9 + 10
+    );
+    // On ARM64 servers, the length of the slice is the last register argument and its
+    // pointer the first stack argument
+    assert_eq!(client.straddle(1, 2, 3, 4, 5, 6, &[100, 200]).unwrap(), 321);
+}
+
+#[test]
+fn test_late_float_arguments() {
+    let (_server, client) = windows_rpc::testing::pair::<CallAbiServer<_>, _>(CallAbiImpl)
+        .expect("Failed to create client/server pair");
+
+    // Past the slots of the FloatDoubleMask, so the server routine receives them like
+    // integers, on the stack of x64 and ARM64 alike
+    assert_eq!(
+        client.late_floats(1, 2, 3, 4, 5, 6, 7, 0.5, 0.25).unwrap(),
+        28.75
+    );
+}
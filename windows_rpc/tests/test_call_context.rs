@@ -0,0 +1,42 @@
+use windows::Win32::System::Rpc::RPC_S_NO_CALL_ACTIVE;
+use windows_rpc::rpc_interface;
+use windows_rpc::server::call_context;
+
+#[rpc_interface(guid(0x0b6e3f92_a4d7_4c18_8e5b_f2c709a3d16e), version(1.0))]
+trait ContextRpc {
+    fn caller_pid() -> u32;
+    fn caller_sid() -> String;
+    fn is_local() -> u8;
+}
+
+struct ContextRpcImpl;
+impl ContextRpcServerImpl for ContextRpcImpl {
+    fn caller_pid() -> u32 {
+        call_context().unwrap().client_pid.unwrap_or(0)
+    }
+
+    fn caller_sid() -> String {
+        call_context().unwrap().client_sid.unwrap_or_default()
+    }
+
+    fn is_local() -> u8 {
+        u8::from(call_context().unwrap().is_local)
+    }
+}
+
+#[test]
+fn test_call_context() {
+    let (_server, client) = windows_rpc::testing::pair::<ContextRpcServer<_>, _>(ContextRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    // The client is this process
+    assert_eq!(client.caller_pid().unwrap(), std::process::id());
+    assert!(client.caller_sid().unwrap().starts_with("S-1-"));
+    assert_eq!(client.is_local().unwrap(), 1);
+}
+
+#[test]
+fn test_call_context_outside_of_call() {
+    let error = call_context().expect_err("no call is being handled");
+    assert_eq!(error.code(), RPC_S_NO_CALL_ACTIVE.to_hresult());
+}
@@ -0,0 +1,166 @@
+use std::sync::Mutex;
+
+use windows::Win32::System::Rpc::{RPC_S_NO_CALL_ACTIVE, RPC_S_PROCNUM_OUT_OF_RANGE};
+use windows_rpc::{TransferSyntax, rpc_interface};
+
+#[rpc_interface(guid(0x8e2c47d1_6b3a_4f90_a5e8_d17c03b9f624), version(1.0))]
+trait Copier {
+    fn copy(files: u32) -> u32;
+    fn describe(name: &str) -> String;
+    fn sum_steps(count: u32) -> Result<u32, u32>;
+    #[rpc(callback)]
+    fn progress(copied: u32, total: u32) -> u32;
+    #[rpc(callback)]
+    fn label(name: &str) -> String;
+    #[rpc(callback)]
+    fn step(index: u32, values: &[u32]) -> Result<u32, u32>;
+}
+
+struct CopierImpl;
+impl CopierServerImpl for CopierImpl {
+    fn copy(files: u32) -> u32 {
+        let client = CopierCallbacksClient::current().unwrap();
+        for copied in 1..=files {
+            // The client cancels the copy by returning 0
+            if client.progress(copied, files).unwrap_or(0) == 0 {
+                return copied;
+            }
+        }
+        files
+    }
+
+    fn describe(name: &str) -> String {
+        let client = CopierCallbacksClient::current().unwrap();
+        match client.label(name) {
+            Ok(label) => format!("[{label}]"),
+            Err(error) => format!("error {}", error.status().0),
+        }
+    }
+
+    fn sum_steps(count: u32) -> Result<u32, u32> {
+        let client = CopierCallbacksClient::current().unwrap();
+        let values: Vec<u32> = (0..count).collect();
+        let mut sum = 0;
+        for index in 0..count {
+            sum += match client.step(index, &values) {
+                Ok(value) => value,
+                Err(windows_rpc::ServiceError::Service(code)) => return Err(code),
+                Err(windows_rpc::ServiceError::Rpc(error)) => return Err(error.status().0 as u32),
+            };
+        }
+        Ok(sum)
+    }
+}
+
+/// Records the progress reported by the server, cancelling the copy at `cancel_at`
+struct Progress {
+    reports: Mutex<Vec<(u32, u32)>>,
+    cancel_at: u32,
+}
+
+impl CopierCallbacks for Progress {
+    fn progress(&self, copied: u32, total: u32) -> u32 {
+        self.reports.lock().unwrap().push((copied, total));
+        u32::from(copied < self.cancel_at)
+    }
+
+    fn label(&self, name: &str) -> String {
+        name.to_uppercase()
+    }
+
+    fn step(&self, index: u32, values: &[u32]) -> Result<u32, u32> {
+        if index == 3 {
+            return Err(33);
+        }
+        Ok(values[index as usize] * 10)
+    }
+}
+
+fn progress(cancel_at: u32) -> Progress {
+    Progress {
+        reports: Mutex::new(vec![]),
+        cancel_at,
+    }
+}
+
+#[test]
+fn test_callbacks() {
+    let (_server, mut client) = windows_rpc::testing::pair::<CopierServer<_>, _>(CopierImpl)
+        .expect("Failed to create client/server pair");
+    client.set_callbacks(progress(u32::MAX));
+
+    assert_eq!(client.copy(3).unwrap(), 3);
+    assert_eq!(client.describe("report").unwrap(), "[REPORT]");
+    assert_eq!(client.sum_steps(3).unwrap(), 30);
+    // The error the callback returns reaches the server
+    assert!(matches!(
+        client.sum_steps(5),
+        Err(windows_rpc::ServiceError::Service(33))
+    ));
+}
+
+#[test]
+fn test_cancelled_by_callback() {
+    let (_server, mut client) = windows_rpc::testing::pair::<CopierServer<_>, _>(CopierImpl)
+        .expect("Failed to create client/server pair");
+    let progress = std::sync::Arc::new(progress(2));
+    client.set_callbacks(Shared(progress.clone()));
+
+    assert_eq!(client.copy(10).unwrap(), 2);
+    assert_eq!(*progress.reports.lock().unwrap(), [(1, 10), (2, 10)]);
+}
+
+#[test]
+fn test_callback_syntaxes() {
+    for syntax in [TransferSyntax::Ndr20, TransferSyntax::Ndr64] {
+        let (server, _client) = windows_rpc::testing::pair::<CopierServer<_>, _>(CopierImpl)
+            .expect("Failed to create client/server pair");
+        let mut client = CopierClient::with_transfer_syntax(
+            windows_rpc::client_binding::ClientBinding::new(
+                windows_rpc::ProtocolSequence::Alpc,
+                server.endpoint(),
+            )
+            .expect("Failed to create client binding"),
+            syntax,
+        );
+        client.set_callbacks(progress(u32::MAX));
+        assert_eq!(client.describe("syntax").unwrap(), "[SYNTAX]");
+        assert_eq!(client.transfer_syntax(), Some(syntax));
+    }
+}
+
+#[test]
+fn test_without_callbacks() {
+    let (_server, client) = windows_rpc::testing::pair::<CopierServer<_>, _>(CopierImpl)
+        .expect("Failed to create client/server pair");
+
+    // The callbacks fail without a handler, and the call goes on
+    assert_eq!(client.copy(5).unwrap(), 1);
+    assert_eq!(
+        client.describe("none").unwrap(),
+        format!("error {}", RPC_S_PROCNUM_OUT_OF_RANGE.0)
+    );
+}
+
+#[test]
+fn test_outside_of_call() {
+    let error = CopierCallbacksClient::current().err().unwrap();
+    assert_eq!(error.status(), RPC_S_NO_CALL_ACTIVE);
+}
+
+/// Handles the callbacks with a handler the test keeps a reference to
+struct Shared(std::sync::Arc<Progress>);
+
+impl CopierCallbacks for Shared {
+    fn progress(&self, copied: u32, total: u32) -> u32 {
+        self.0.progress(copied, total)
+    }
+
+    fn label(&self, name: &str) -> String {
+        self.0.label(name)
+    }
+
+    fn step(&self, index: u32, values: &[u32]) -> Result<u32, u32> {
+        self.0.step(index, values)
+    }
+}
@@ -41,10 +41,18 @@ fn test_client_server_integration() {
     );
 
     // Test the methods
-    assert_eq!(client.add(10, 20), 30, "add(10, 20) should return 30");
-    assert_eq!(client.multiply(5, 6), 30, "multiply(5, 6) should return 30");
     assert_eq!(
-        client.strlen("hello"),
+        client.add(10, 20).unwrap(),
+        30,
+        "add(10, 20) should return 30"
+    );
+    assert_eq!(
+        client.multiply(5, 6).unwrap(),
+        30,
+        "multiply(5, 6) should return 30"
+    );
+    assert_eq!(
+        client.strlen("hello").unwrap(),
         "hello".len() as u64,
         "strlen() should return len of param"
     );
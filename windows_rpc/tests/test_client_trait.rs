@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+
+use windows_rpc::{RpcError, rpc_interface};
+
+#[rpc_interface(guid(0x3e96c0d2_7a41_4b58_9f13_e52d08b7c6a4), version(1.0))]
+trait Accounts {
+    fn balance(account: &str) -> u64;
+    fn deposit(account: &str, amount: u64, new_balance: &mut u64);
+}
+
+struct AccountsImpl;
+impl AccountsServerImpl for AccountsImpl {
+    fn balance(account: &str) -> u64 {
+        account.len() as u64 * 100
+    }
+
+    fn deposit(account: &str, amount: u64, new_balance: &mut u64) {
+        *new_balance = Self::balance(account) + amount;
+    }
+}
+
+/// Records the calls instead of making them
+#[derive(Default)]
+struct MockAccounts {
+    calls: RefCell<Vec<String>>,
+}
+
+impl Accounts for MockAccounts {
+    fn balance(&self, account: &str) -> Result<u64, RpcError> {
+        self.calls.borrow_mut().push(format!("balance({account})"));
+        Ok(7)
+    }
+
+    fn deposit(&self, account: &str, amount: u64, new_balance: &mut u64) -> Result<(), RpcError> {
+        self.calls
+            .borrow_mut()
+            .push(format!("deposit({account}, {amount})"));
+        *new_balance = 7 + amount;
+        Ok(())
+    }
+}
+
+/// Application code, unaware of where the accounts are kept
+fn top_up(accounts: &dyn Accounts, account: &str) -> Result<u64, RpcError> {
+    let balance = accounts.balance(account)?;
+    let mut new_balance = 0;
+    accounts.deposit(account, 1000 - balance.min(1000), &mut new_balance)?;
+    Ok(new_balance)
+}
+
+#[test]
+fn test_client_trait() {
+    let (_server, client) = windows_rpc::testing::pair::<AccountsServer<_>, _>(AccountsImpl)
+        .expect("Failed to create client/server pair");
+    assert_eq!(top_up(&client, "alice").unwrap(), 1000);
+
+    let mock = MockAccounts::default();
+    assert_eq!(top_up(&mock, "bob").unwrap(), 1000);
+    assert_eq!(*mock.calls.borrow(), ["balance(bob)", "deposit(bob, 993)"]);
+}
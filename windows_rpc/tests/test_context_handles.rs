@@ -0,0 +1,138 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::RPC_X_SS_IN_NULL_CONTEXT;
+use windows::Win32::System::Rpc::RPC_STATUS;
+use windows_rpc::context::{ContextHandle, ServerContext};
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x5b93e0c2_8a1f_4d6e_b047_c2d91f3a6e58), version(1.0))]
+trait Sessions {
+    fn open(name: &str) -> ContextHandle;
+    fn name(session: &ContextHandle) -> String;
+    fn calls(session: &ContextHandle) -> u32;
+    fn rename(#[in_out] session: &mut ContextHandle, name: &str);
+    fn close(#[in_out] session: &mut ContextHandle);
+}
+
+struct Session {
+    name: String,
+    calls: AtomicU32,
+}
+
+/// Names of the sessions run down, whose clients went away without closing them
+static RUN_DOWN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn session(name: &str) -> ServerContext {
+    let session = Session {
+        name: name.to_string(),
+        calls: AtomicU32::new(0),
+    };
+    ServerContext::with_rundown(session, |session: Session| {
+        RUN_DOWN.lock().unwrap().push(session.name);
+    })
+}
+
+struct SessionsImpl;
+impl SessionsServerImpl for SessionsImpl {
+    fn open(name: &str) -> ServerContext {
+        session(name)
+    }
+
+    fn name(session: &ServerContext) -> String {
+        session.get::<Session>().unwrap().name.clone()
+    }
+
+    fn calls(session: &ServerContext) -> u32 {
+        session
+            .get::<Session>()
+            .unwrap()
+            .calls
+            .fetch_add(1, Ordering::Relaxed)
+            + 1
+    }
+
+    fn rename(session: &mut ServerContext, name: &str) {
+        *session = self::session(name);
+    }
+
+    fn close(session: &mut ServerContext) {
+        session.close();
+    }
+}
+
+fn wait_for_rundown(name: &str) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if RUN_DOWN
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|run_down| run_down == name)
+        {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+#[test]
+fn test_context_handles() {
+    let (_server, client) = windows_rpc::testing::pair::<SessionsServer<_>, _>(SessionsImpl)
+        .expect("Failed to create client/server pair");
+
+    let mut first = client.open("first").unwrap();
+    let second = client.open("second").unwrap();
+    assert!(!first.is_closed());
+    assert_eq!(client.name(&first).unwrap(), "first");
+    assert_eq!(client.name(&second).unwrap(), "second");
+
+    // Each context keeps its own state between calls
+    assert_eq!(client.calls(&first).unwrap(), 1);
+    assert_eq!(client.calls(&first).unwrap(), 2);
+    assert_eq!(client.calls(&second).unwrap(), 1);
+
+    // The handler replaces the context behind the same handle
+    client.rename(&mut first, "renamed").unwrap();
+    assert_eq!(client.name(&first).unwrap(), "renamed");
+    assert_eq!(client.calls(&first).unwrap(), 1);
+
+    // Closing the context closes the handle, which then fails the calls
+    client.close(&mut first).unwrap();
+    assert!(first.is_closed());
+    let error = client.name(&first).unwrap_err();
+    assert_eq!(error.status(), RPC_STATUS(RPC_X_SS_IN_NULL_CONTEXT));
+    let error = client.name(&ContextHandle::default()).unwrap_err();
+    assert_eq!(error.status(), RPC_STATUS(RPC_X_SS_IN_NULL_CONTEXT));
+
+    assert_eq!(client.name(&second).unwrap(), "second");
+}
+
+#[test]
+fn test_rundown() {
+    let (server, client) = windows_rpc::testing::pair::<SessionsServer<_>, _>(SessionsImpl)
+        .expect("Failed to create client/server pair");
+
+    let abandoned = client.open("abandoned").unwrap();
+    let mut closed = client.open("closed").unwrap();
+    client.close(&mut closed).unwrap();
+
+    // The server runs the context down once the client's connection closes
+    drop(abandoned);
+    drop(client);
+    assert!(wait_for_rundown("abandoned"));
+    // Closed contexts aren't run down
+    assert!(!RUN_DOWN.lock().unwrap().iter().any(|name| name == "closed"));
+
+    // The server keeps serving other clients
+    let client = SessionsClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+    );
+    let session = client.open("later").unwrap();
+    assert_eq!(client.name(&session).unwrap(), "later");
+}
@@ -0,0 +1,61 @@
+use windows::Win32::System::Rpc::RPC_S_DUPLICATE_ENDPOINT;
+use windows_rpc::rpc_interface;
+use windows_rpc::server_binding::EndpointMode;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x2f9c61a4_8d07_4e3b_b6f5_d048e7a91c52), version(1.0))]
+trait Owner {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x93e0b5d8_4c1a_4f72_8a69_1bd7f03e64a5), version(1.0))]
+trait Guest {
+    fn greet(name: &str) -> String;
+}
+
+struct OwnerImpl;
+impl OwnerServerImpl for OwnerImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+struct GuestImpl;
+impl GuestServerImpl for GuestImpl {
+    fn greet(name: &str) -> String {
+        format!("Hello, {name}!")
+    }
+}
+
+fn binding(endpoint: &str) -> ClientBinding {
+    ClientBinding::new(ProtocolSequence::Alpc, endpoint).expect("Failed to create client binding")
+}
+
+#[test]
+fn test_endpoint_reuse() {
+    let (owner, owner_client) = windows_rpc::testing::pair::<OwnerServer<_>, _>(OwnerImpl)
+        .expect("Failed to create client/server pair");
+    let endpoint = owner.endpoint();
+
+    // By default the endpoint belongs to the server that registered it
+    let mut guest = GuestServer::<GuestImpl>::new();
+    let error = guest
+        .register(endpoint)
+        .expect_err("registering an existing endpoint should fail");
+    assert_eq!(error.code(), RPC_S_DUPLICATE_ENDPOINT.to_hresult());
+
+    let mut guest = GuestServer::<GuestImpl>::new();
+    guest.set_endpoint_mode(EndpointMode::ReuseExisting);
+    guest
+        .register(endpoint)
+        .expect("Failed to register on the existing endpoint");
+    // The owner is already listening
+    guest.listen_async().expect("Failed to start listening");
+
+    let guest_client = GuestClient::new(binding(endpoint));
+    assert_eq!(guest_client.greet("guest").unwrap(), "Hello, guest!");
+
+    // Stopping the guest leaves the owner serving calls
+    guest.stop().expect("Failed to stop guest");
+    assert_eq!(owner_client.add(2, 3).unwrap(), 5);
+}
@@ -0,0 +1,69 @@
+use windows::Win32::System::Rpc::RPC_S_ACCESS_DENIED;
+use windows_rpc::rpc_interface;
+use windows_rpc::security::SecurityDescriptor;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x8e2b47d1_c05a_4f93_a6e8_3d71f9c2b054), version(1.0))]
+trait OpenRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x4a9c13f7_e26b_4d85_b30f_97c5e8a1d26b), version(1.0))]
+trait ClosedRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct RpcImpl;
+impl OpenRpcServerImpl for RpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+impl ClosedRpcServerImpl for RpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_endpoint_security_descriptor() {
+    let open_endpoint = windows_rpc::testing::unique_endpoint();
+    let closed_endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut open = OpenRpcServer::<RpcImpl>::new();
+    // Allow everyone
+    open.set_endpoint_security_descriptor(Some(
+        SecurityDescriptor::from_sddl("D:(A;;GA;;;WD)").expect("Failed to parse SDDL"),
+    ));
+    open.register(&open_endpoint)
+        .expect("Failed to register server");
+
+    let mut closed = ClosedRpcServer::<RpcImpl>::new();
+    // Deny everyone
+    closed.set_endpoint_security_descriptor(Some(
+        SecurityDescriptor::from_sddl("D:(D;;GA;;;WD)").expect("Failed to parse SDDL"),
+    ));
+    closed
+        .register(&closed_endpoint)
+        .expect("Failed to register server");
+
+    // Listening is process-wide
+    open.listen_async().expect("Failed to start listening");
+
+    let client = OpenRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &open_endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+
+    let client = ClosedRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &closed_endpoint)
+            .expect("Failed to create client binding"),
+    );
+    let error = client
+        .add(2, 3)
+        .expect_err("the endpoint should reject the connection");
+    assert_eq!(error.status(), RPC_S_ACCESS_DENIED);
+
+    open.stop().expect("Failed to stop server");
+}
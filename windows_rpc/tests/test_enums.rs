@@ -0,0 +1,92 @@
+use windows_rpc::{NdrEnum, rpc_interface};
+
+#[derive(NdrEnum, Clone, Copy, Debug, PartialEq)]
+#[repr(u16)]
+pub enum Suit {
+    Clubs = 1,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+#[derive(NdrEnum, Clone, Copy, Debug, PartialEq)]
+#[repr(u32)]
+pub enum Status {
+    Ok = 0,
+    Busy = 0x10000,
+    #[ndr(fallback)]
+    Unknown = 0xffff_ffff,
+}
+
+#[rpc_interface(
+    guid(0x7a4e1c93_2f58_4b06_9d3e_c81f05a6b274),
+    version(1.0),
+    enums(Suit, Status)
+)]
+trait EnumRpc {
+    fn next(suit: Suit) -> Suit;
+    fn is_red(suit: Suit) -> u8;
+    fn status(code: u32) -> Status;
+    fn echo_status(status: Status, code: &mut u32) -> Status;
+}
+
+struct EnumRpcImpl;
+impl EnumRpcServerImpl for EnumRpcImpl {
+    fn next(suit: Suit) -> Suit {
+        match suit {
+            Suit::Clubs => Suit::Diamonds,
+            Suit::Diamonds => Suit::Hearts,
+            Suit::Hearts => Suit::Spades,
+            Suit::Spades => Suit::Clubs,
+        }
+    }
+
+    fn is_red(suit: Suit) -> u8 {
+        matches!(suit, Suit::Diamonds | Suit::Hearts) as u8
+    }
+
+    fn status(code: u32) -> Status {
+        Status::from_wire(code).unwrap()
+    }
+
+    fn echo_status(status: Status, code: &mut u32) -> Status {
+        *code = status.to_wire();
+        status
+    }
+}
+
+#[test]
+fn test_enum_params() {
+    let (_server, client) = windows_rpc::testing::pair::<EnumRpcServer<_>, _>(EnumRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.next(Suit::Clubs).unwrap(), Suit::Diamonds);
+    assert_eq!(client.next(Suit::Spades).unwrap(), Suit::Clubs);
+    assert_eq!(client.is_red(Suit::Hearts).unwrap(), 1);
+    assert_eq!(client.is_red(Suit::Spades).unwrap(), 0);
+
+    let mut code = 0;
+    assert_eq!(
+        client.echo_status(Status::Busy, &mut code).unwrap(),
+        Status::Busy
+    );
+    assert_eq!(code, 0x10000, "32-bit enums should keep their upper bits");
+}
+
+#[test]
+fn test_enum_fallback() {
+    let (_server, client) = windows_rpc::testing::pair::<EnumRpcServer<_>, _>(EnumRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.status(0).unwrap(), Status::Ok);
+    assert_eq!(client.status(42).unwrap(), Status::Unknown);
+    assert_eq!(Status::from_wire(42), Some(Status::Unknown));
+    assert_eq!(Suit::from_wire(42), None);
+}
+
+#[test]
+fn test_enum_metadata() {
+    let methods = EnumRpcClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.rust_type, "Suit");
+    assert_eq!(methods[0].parameters[0].r#type.idl_type, "enum Suit");
+}
@@ -0,0 +1,192 @@
+use windows::Win32::Foundation::{
+    E_ACCESSDENIED, E_INVALIDARG, ERROR_ACCESS_DENIED, ERROR_FILE_NOT_FOUND,
+};
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+use windows_rpc::{ServiceError, rpc_interface};
+
+#[rpc_interface(guid(0x7a3c51e8_2d94_4b60_8f17_c6e90b4d2a53), version(1.0))]
+trait Files {
+    #[rpc(error_status)]
+    fn size(name: &str) -> Result<u64, u32>;
+    #[rpc(error_status)]
+    fn read(name: &str) -> Result<String, u32>;
+    #[rpc(error_status)]
+    fn list(count: u32) -> Result<Vec<u32>, u32>;
+    #[rpc(error_status)]
+    fn delete(name: &str) -> Result<(), u32>;
+}
+
+#[rpc_interface(guid(0x5e81c2a7_94d3_4f06_b1e8_2a7d603c9f14), version(1.0))]
+trait Settings {
+    #[rpc(hresult)]
+    fn get(key: &str) -> Result<i64, i32>;
+    #[rpc(hresult)]
+    fn describe(key: &str) -> Result<String, i32>;
+    #[rpc(hresult)]
+    fn reset(key: &str) -> Result<(), i32>;
+}
+
+struct FilesImpl;
+impl FilesServerImpl for FilesImpl {
+    fn size(name: &str) -> Result<u64, u32> {
+        match name {
+            "big" => Ok(u64::MAX),
+            _ => Err(ERROR_FILE_NOT_FOUND.0),
+        }
+    }
+
+    fn read(name: &str) -> Result<String, u32> {
+        match name {
+            "readme" => Ok("hello".to_string()),
+            _ => Err(ERROR_ACCESS_DENIED.0),
+        }
+    }
+
+    fn list(count: u32) -> Result<Vec<u32>, u32> {
+        if count > 3 {
+            return Err(ERROR_ACCESS_DENIED.0);
+        }
+        Ok((0..count).collect())
+    }
+
+    fn delete(name: &str) -> Result<(), u32> {
+        if name.is_empty() {
+            return Err(ERROR_FILE_NOT_FOUND.0);
+        }
+        Ok(())
+    }
+}
+
+struct SettingsImpl;
+impl SettingsServerImpl for SettingsImpl {
+    fn get(key: &str) -> Result<i64, i32> {
+        match key {
+            "min" => Ok(i64::MIN),
+            _ => Err(E_INVALIDARG.0),
+        }
+    }
+
+    fn describe(key: &str) -> Result<String, i32> {
+        match key {
+            "min" => Ok("lowest".to_string()),
+            _ => Err(E_ACCESSDENIED.0),
+        }
+    }
+
+    fn reset(key: &str) -> Result<(), i32> {
+        if key.is_empty() {
+            return Err(E_INVALIDARG.0);
+        }
+        Ok(())
+    }
+}
+
+fn check_files(client: &FilesClient) {
+    assert_eq!(client.size("big").unwrap(), u64::MAX);
+    assert_eq!(
+        client.size("missing").unwrap_err(),
+        ServiceError::Service(ERROR_FILE_NOT_FOUND.0)
+    );
+
+    assert_eq!(client.read("readme").unwrap(), "hello");
+    assert_eq!(
+        client.read("secret").unwrap_err(),
+        ServiceError::Service(ERROR_ACCESS_DENIED.0)
+    );
+
+    assert_eq!(client.list(3).unwrap(), [0, 1, 2]);
+    assert_eq!(
+        client.list(4).unwrap_err(),
+        ServiceError::Service(ERROR_ACCESS_DENIED.0)
+    );
+
+    client.delete("file").unwrap();
+    assert_eq!(
+        client.delete("").unwrap_err(),
+        ServiceError::Service(ERROR_FILE_NOT_FOUND.0)
+    );
+}
+
+#[test]
+fn test_error_status() {
+    let (server, client) = windows_rpc::testing::pair::<FilesServer<_>, _>(FilesImpl)
+        .expect("Failed to create client/server pair");
+    check_files(&client);
+
+    let client = FilesClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_files(&client);
+}
+
+#[test]
+fn test_hresult() {
+    let (_server, client) = windows_rpc::testing::pair::<SettingsServer<_>, _>(SettingsImpl)
+        .expect("Failed to create client/server pair");
+    assert_eq!(client.get("min").unwrap(), i64::MIN);
+    assert_eq!(
+        client.get("max").unwrap_err(),
+        ServiceError::Service(E_INVALIDARG.0)
+    );
+
+    assert_eq!(client.describe("min").unwrap(), "lowest");
+    assert_eq!(
+        client.describe("max").unwrap_err(),
+        ServiceError::Service(E_ACCESSDENIED.0)
+    );
+
+    client.reset("min").unwrap();
+    assert_eq!(
+        client.reset("").unwrap_err(),
+        ServiceError::Service(E_INVALIDARG.0)
+    );
+}
+
+#[test]
+fn test_failed_calls() {
+    // Failures of the call itself aren't mistaken for the returned status
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "test_error_status_no_endpoint")
+        .expect("Failed to create client binding");
+    let error = FilesClient::new(binding).delete("file").unwrap_err();
+    let ServiceError::Rpc(rpc_error) = &error else {
+        panic!("calling a missing endpoint should fail the call");
+    };
+    assert_eq!(
+        rpc_error.status(),
+        windows::Win32::System::Rpc::RPC_S_SERVER_UNAVAILABLE
+    );
+}
+
+#[test]
+fn test_error_status_metadata() {
+    let error_type = FilesClient::METADATA.methods[0].error_type.unwrap();
+    assert_eq!(error_type.rust_type, "u32");
+    assert_eq!(error_type.idl_type, "error_status_t");
+
+    assert!(FilesClient::IDL.contains(
+        "error_status_t size([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out] unsigned hyper* return_value);"
+    ));
+    assert!(FilesClient::IDL.contains(
+        "error_status_t delete([in] handle_t binding, [in, string] const wchar_t* name);"
+    ));
+}
+
+#[test]
+fn test_hresult_metadata() {
+    let error_type = SettingsClient::METADATA.methods[0].error_type.unwrap();
+    assert_eq!(error_type.rust_type, "i32");
+    assert_eq!(error_type.idl_type, "HRESULT");
+
+    assert!(SettingsClient::IDL.contains("import \"wtypes.idl\";"));
+    assert!(SettingsClient::IDL.contains(
+        "HRESULT get([in] handle_t binding, [in, string] const wchar_t* key, \
+         [out] hyper* return_value);"
+    ));
+    assert!(
+        SettingsClient::IDL
+            .contains("HRESULT reset([in] handle_t binding, [in, string] const wchar_t* key);")
+    );
+}
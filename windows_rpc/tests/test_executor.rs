@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use std::time::Duration;
+
+use windows_rpc::executor::{CallExecutor, Job, ThreadPool};
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x5a1e7c3b_8d2f_4e61_9b0a_6f4c2d8e1a73), version(1.0))]
+trait TestRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn greet(name: &str) -> String;
+}
+
+struct TestRpcImpl;
+impl TestRpcServerImpl for TestRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn greet(name: &str) -> String {
+        format!("Hello, {name}!")
+    }
+}
+
+static EXECUTED: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn test_handlers_run_on_executor() {
+    let endpoint = "test_endpoint_executor";
+
+    let mut server = TestRpcServer::<TestRpcImpl>::new();
+    server.set_executor(|job: Job| {
+        EXECUTED.fetch_add(1, Ordering::SeqCst);
+        std::thread::spawn(job);
+    });
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = TestRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+    );
+
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.greet("executor").unwrap(), "Hello, executor!");
+    assert_eq!(
+        EXECUTED.load(Ordering::SeqCst),
+        2,
+        "Both calls should go through the executor"
+    );
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_thread_pool_bounds_jobs() {
+    let pool = ThreadPool::new(2);
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+
+    for _ in 0..8 {
+        let (running, peak, sender) = (running.clone(), peak.clone(), sender.clone());
+        pool.execute(Box::new(move || {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            running.fetch_sub(1, Ordering::SeqCst);
+            sender.send(()).unwrap();
+        }));
+    }
+
+    for _ in 0..8 {
+        receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("Every queued job should run");
+    }
+    assert!(
+        peak.load(Ordering::SeqCst) <= 2,
+        "No more jobs than threads should run at the same time"
+    );
+}
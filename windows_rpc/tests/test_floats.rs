@@ -0,0 +1,105 @@
+use windows_rpc::{NdrStruct, rpc_interface};
+
+#[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Sample {
+    pub id: u8,
+    pub value: f64,
+    pub weight: f32,
+}
+
+#[rpc_interface(guid(0x5c1e8a47_92d3_4f60_b8e1_0a7d3c49f215), version(1.0))]
+trait FloatRpc {
+    fn scale(value: f32, factor: f64) -> f64;
+    fn half(value: f32) -> f32;
+    fn mixed(a: i32, b: f32, c: u64, d: f64, e: f32) -> f64;
+    fn split(value: f64, whole: &mut i64, fraction: &mut f64);
+    fn average(values: &[f32]) -> f32;
+    fn ramp(count: u32) -> Vec<f64>;
+    fn weigh(sample: &Sample) -> Sample;
+}
+
+struct FloatRpcImpl;
+impl FloatRpcServerImpl for FloatRpcImpl {
+    fn scale(value: f32, factor: f64) -> f64 {
+        value as f64 * factor
+    }
+
+    fn half(value: f32) -> f32 {
+        value / 2.0
+    }
+
+    fn mixed(a: i32, b: f32, c: u64, d: f64, e: f32) -> f64 {
+        a as f64 + b as f64 + c as f64 + d + e as f64
+    }
+
+    fn split(value: f64, whole: &mut i64, fraction: &mut f64) {
+        *whole = value.trunc() as i64;
+        *fraction = value.fract();
+    }
+
+    fn average(values: &[f32]) -> f32 {
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+
+    fn ramp(count: u32) -> Vec<f64> {
+        (0..count).map(|i| i as f64 * 0.5).collect()
+    }
+
+    fn weigh(sample: &Sample) -> Sample {
+        Sample {
+            value: sample.value * sample.weight as f64,
+            ..*sample
+        }
+    }
+}
+
+#[test]
+fn test_float_params() {
+    let (_server, client) = windows_rpc::testing::pair::<FloatRpcServer<_>, _>(FloatRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.scale(1.5, 4.0).unwrap(), 6.0);
+    assert_eq!(client.half(-3.0).unwrap(), -1.5);
+    assert!(client.half(f32::NAN).unwrap().is_nan());
+    // Floats mixed with integers, in both register and stack slots
+    assert_eq!(client.mixed(1, 0.5, 2, 0.25, 0.125).unwrap(), 3.875);
+}
+
+#[test]
+fn test_float_out_params() {
+    let (_server, client) = windows_rpc::testing::pair::<FloatRpcServer<_>, _>(FloatRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let (mut whole, mut fraction) = (0, 0.0);
+    client.split(-7.75, &mut whole, &mut fraction).unwrap();
+    assert_eq!((whole, fraction), (-7, -0.75));
+}
+
+#[test]
+fn test_float_arrays_and_structs() {
+    let (_server, client) = windows_rpc::testing::pair::<FloatRpcServer<_>, _>(FloatRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.average(&[1.0, 2.0, 4.5]).unwrap(), 2.5);
+    assert_eq!(client.ramp(3).unwrap(), [0.0, 0.5, 1.0]);
+    let sample = Sample {
+        id: 7,
+        value: 3.0,
+        weight: 0.5,
+    };
+    assert_eq!(
+        client.weigh(&sample).unwrap(),
+        Sample {
+            value: 1.5,
+            ..sample
+        }
+    );
+}
+
+#[test]
+fn test_float_metadata() {
+    let methods = FloatRpcClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.idl_type, "float");
+    assert_eq!(methods[0].return_type.unwrap().idl_type, "double");
+}
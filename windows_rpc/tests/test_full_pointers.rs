@@ -0,0 +1,90 @@
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+use windows_rpc::{NdrStruct, rpc_interface};
+
+#[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Node {
+    pub id: u32,
+    pub weight: i64,
+}
+
+#[rpc_interface(guid(0x2b8e5f17_c94a_4d36_8e1b_7a0d3c6f5e29), version(1.0))]
+trait Graph {
+    fn same(#[rpc(ptr)] a: &Node, #[rpc(ptr)] b: &Node) -> u32;
+    fn weight(#[rpc(ptr)] a: &Node, b: &Node) -> i64;
+    fn pick(#[rpc(ptr)] id: Option<u32>, #[rpc(ptr)] name: Option<&str>) -> u32;
+}
+
+struct GraphImpl;
+impl GraphServerImpl for GraphImpl {
+    // 2 when both parameters are the same node, 1 when equal ones, 0 otherwise
+    fn same(a: &Node, b: &Node) -> u32 {
+        if std::ptr::eq(a, b) {
+            2
+        } else {
+            u32::from(a == b)
+        }
+    }
+
+    fn weight(a: &Node, b: &Node) -> i64 {
+        a.weight + b.weight
+    }
+
+    fn pick(id: Option<u32>, name: Option<&str>) -> u32 {
+        id.unwrap_or(0) + name.map_or(0, |name| name.len() as u32)
+    }
+}
+
+fn check_graph(client: &GraphClient) {
+    let a = Node { id: 1, weight: 10 };
+    let copy = a;
+    let b = Node { id: 2, weight: -3 };
+
+    // An aliased node is sent once, and the server receives it aliased
+    assert_eq!(client.same(&a, &a).unwrap(), 2);
+    assert_eq!(client.same(&a, &copy).unwrap(), 1);
+    assert_eq!(client.same(&a, &b).unwrap(), 0);
+
+    // Full pointers mix with reference pointers to the same node
+    assert_eq!(client.weight(&a, &a).unwrap(), 20);
+    assert_eq!(client.weight(&a, &b).unwrap(), 7);
+
+    assert_eq!(client.pick(Some(3), Some("four")).unwrap(), 7);
+    assert_eq!(client.pick(None, Some("four")).unwrap(), 4);
+    assert_eq!(client.pick(Some(3), None).unwrap(), 3);
+    assert_eq!(client.pick(None, None).unwrap(), 0);
+}
+
+#[test]
+fn test_full_pointers() {
+    let (server, client) = windows_rpc::testing::pair::<GraphServer<_>, _>(GraphImpl)
+        .expect("Failed to create client/server pair");
+    check_graph(&client);
+
+    // NDR 2.0 keeps its own pointer tables
+    let client = GraphClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_graph(&client);
+}
+
+#[test]
+fn test_full_pointer_metadata() {
+    let methods = GraphClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.rust_type, "&Node");
+    assert_eq!(methods[0].parameters[0].r#type.idl_type, "[ptr] Node*");
+    assert_eq!(methods[1].parameters[1].r#type.idl_type, "Node*");
+    assert_eq!(
+        methods[2].parameters[0].r#type.idl_type,
+        "[ptr] unsigned long*"
+    );
+    assert_eq!(
+        methods[2].parameters[1].r#type.idl_type,
+        "[ptr, string] wchar_t*"
+    );
+    assert!(GraphClient::IDL.contains(
+        "unsigned long same([in] handle_t binding, [in, ptr] Node* a, [in, ptr] Node* b);"
+    ));
+}
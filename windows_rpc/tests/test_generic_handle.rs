@@ -0,0 +1,71 @@
+use std::cell::Cell;
+
+use windows_rpc::client_binding::{ClientBinding, GenericHandle};
+use windows_rpc::{ProtocolSequence, rpc_interface};
+
+struct Tenant {
+    endpoint: String,
+    binds: Cell<u32>,
+    unbinds: Cell<u32>,
+}
+
+impl GenericHandle for Tenant {
+    fn bind(&self) -> windows::core::Result<ClientBinding> {
+        self.binds.set(self.binds.get() + 1);
+        ClientBinding::new(ProtocolSequence::Alpc, &self.endpoint)
+    }
+
+    fn unbind(&self, binding: ClientBinding) {
+        self.unbinds.set(self.unbinds.get() + 1);
+        drop(binding);
+    }
+}
+
+#[rpc_interface(
+    guid(0x7c3e9a15_48d2_4b6f_a1e0_c5d82f9b3e46),
+    version(1.0),
+    generic_handle(Tenant)
+)]
+trait TenantRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn greet(name: &str) -> String;
+}
+
+struct TenantRpcImpl;
+impl TenantRpcServerImpl for TenantRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn greet(name: &str) -> String {
+        format!("Hello, {name}!")
+    }
+}
+
+#[test]
+fn test_generic_handle() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut server = TenantRpcServer::<TenantRpcImpl>::new();
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let tenant = Tenant {
+        endpoint: endpoint.clone(),
+        binds: Cell::new(0),
+        unbinds: Cell::new(0),
+    };
+
+    // Creating the client does not bind
+    let client = TenantRpcClient::new();
+    assert_eq!(tenant.binds.get(), 0);
+
+    assert_eq!(client.add(&tenant, 2, 3).unwrap(), 5);
+    assert_eq!(client.greet(&tenant, "tenant").unwrap(), "Hello, tenant!");
+    assert_eq!(tenant.binds.get(), 2, "each call should bind");
+    assert_eq!(tenant.unbinds.get(), 2, "each call should unbind");
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,44 @@
+use windows_rpc::client_binding::{ClientBinding, HttpOptions};
+use windows_rpc::{ProtocolSequence, rpc_interface};
+
+#[rpc_interface(guid(0x94c1f7e2_3a5d_4b08_8e6f_d2b7a1c09e35), version(1.0))]
+trait HttpRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct HttpRpcImpl;
+impl HttpRpcServerImpl for HttpRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_http_server() {
+    assert_eq!(ProtocolSequence::Http.name(), "ncacn_http");
+    if !ProtocolSequence::Http
+        .is_supported()
+        .expect("Failed to check the protocol sequence")
+    {
+        return;
+    }
+
+    // Calls need an RPC proxy, so only the registration on the port is checked
+    let mut server = HttpRpcServer::<HttpRpcImpl>::new();
+    server.set_protocol_sequence(ProtocolSequence::Http);
+    server.register("49593").expect("Failed to register server");
+    assert_eq!(server.endpoints(), ["49593"]);
+}
+
+#[test]
+fn test_http_binding() {
+    ClientBinding::with_http("localhost", 593, &HttpOptions::default())
+        .expect("Failed to create binding without proxies");
+
+    let options = HttpOptions {
+        rpc_proxy: Some("gateway.contoso.com:443".to_string()),
+        http_proxy: Some("proxy.contoso.com:8080".to_string()),
+    };
+    ClientBinding::with_http("calculator.corp.contoso.com", 593, &options)
+        .expect("Failed to create binding through proxies");
+}
@@ -0,0 +1,44 @@
+use windows::core::GUID;
+use windows_rpc::client_binding::ClientBinding;
+use windows_rpc::{ProtocolSequence, rpc_interface};
+
+const SERVICE_ID: u128 = 0x5d9e3b27_c6f1_4a84_9e02_b8a4d7c16f39;
+
+#[rpc_interface(guid(0x1f6c8a3d_72e5_4b90_a4d1_e3c95b07f2a8), version(1.0))]
+trait HvRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct HvRpcImpl;
+impl HvRpcServerImpl for HvRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_hvsocket_loopback() {
+    assert_eq!(ProtocolSequence::HyperVSocket.name(), "ncacn_hvsocket");
+    // Hyper-V sockets are only available where Hyper-V is
+    if !ProtocolSequence::HyperVSocket
+        .is_supported()
+        .expect("Failed to check the protocol sequence")
+    {
+        return;
+    }
+
+    let mut server = HvRpcServer::<HvRpcImpl>::new();
+    server.set_protocol_sequence(ProtocolSequence::HyperVSocket);
+    server
+        .register(&format!("{:?}", GUID::from_u128(SERVICE_ID)))
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = HvRpcClient::new(
+        ClientBinding::with_vm(ClientBinding::VM_ID_LOOPBACK, SERVICE_ID)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    server.stop().expect("Failed to stop server");
+}
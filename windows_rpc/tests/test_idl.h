@@ -0,0 +1,33 @@
+// Generated by windows-rpc from the `Catalog` trait
+#ifndef __Catalog_h__
+#define __Catalog_h__
+
+#include <rpc.h>
+#include <rpcndr.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* {6d3a8f21-94c7-4e0b-b258-1f7e0c9a4d63} */
+static const GUID Catalog_GUID = { 0x6d3a8f21, 0x94c7, 0x4e0b, { 0xb2, 0x58, 0x1f, 0x7e, 0x0c, 0x9a, 0x4d, 0x63 } };
+#define Catalog_VERSION_MAJOR 1
+#define Catalog_VERSION_MINOR 2
+
+unsigned long count(/* [in] */ handle_t binding);
+void find(/* [in] */ handle_t binding, /* [in, string] */ const wchar_t* name, /* [in, unique] */ unsigned short* limit, /* [out, string] */ wchar_t** return_value);
+void prices(/* [in] */ handle_t binding, /* [in] */ unsigned long ids_length, /* [in, size_is(ids_length)] */ unsigned long* ids, /* [out] */ unsigned long* return_value_length, /* [out, size_is(, *return_value_length)] */ double** return_value);
+void split(/* [in] */ handle_t binding, /* [in] */ unsigned hyper value, /* [out] */ unsigned long* high, /* [in, out] */ unsigned long* low, /* [out, string] */ wchar_t** return_value);
+/* [idempotent] */ void touch(/* [in] */ handle_t binding, /* [in] */ unsigned long id);
+
+extern RPC_IF_HANDLE Catalog_v1_2_c_ifspec;
+extern RPC_IF_HANDLE Catalog_v1_2_s_ifspec;
+
+void* __RPC_USER MIDL_user_allocate(size_t size);
+void __RPC_USER MIDL_user_free(void* pointer);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
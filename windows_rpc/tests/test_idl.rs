@@ -0,0 +1,93 @@
+use windows_rpc::rpc_interface;
+
+// The IDL and C header are also written to tests/test_idl.idl and tests/test_idl.h, for
+// compiling C/C++ peers with MIDL
+#[rpc_interface(
+    guid(0x6d3a8f21_94c7_4e0b_b258_1f7e0c9a4d63),
+    version(1.2),
+    idl("tests/test_idl.idl"),
+    header("tests/test_idl.h")
+)]
+trait Catalog {
+    fn count() -> u32;
+    fn find(name: &str, limit: Option<u16>) -> Option<String>;
+    fn prices(ids: &[u32]) -> Vec<f64>;
+    fn split(value: u64, high: &mut u32, #[in_out] low: &mut u32) -> String;
+    #[rpc(idempotent)]
+    fn touch(id: u32);
+}
+
+struct CatalogImpl;
+impl CatalogServerImpl for CatalogImpl {
+    fn count() -> u32 {
+        0
+    }
+
+    fn find(_name: &str, _limit: Option<u16>) -> Option<String> {
+        None
+    }
+
+    fn prices(ids: &[u32]) -> Vec<f64> {
+        ids.iter().map(|&id| id as f64).collect()
+    }
+
+    fn split(value: u64, high: &mut u32, low: &mut u32) -> String {
+        *high = (value >> 32) as u32;
+        *low = value as u32;
+        String::new()
+    }
+
+    fn touch(_id: u32) {}
+}
+
+const EXPECTED: &str = "\
+// Generated by windows-rpc from the `Catalog` trait
+[
+    uuid(6d3a8f21-94c7-4e0b-b258-1f7e0c9a4d63),
+    version(1.2),
+    pointer_default(unique)
+]
+interface Catalog
+{
+    unsigned long count([in] handle_t binding);
+    void find([in] handle_t binding, [in, string] const wchar_t* name, [in, unique] unsigned short* limit, [out, string] wchar_t** return_value);
+    void prices([in] handle_t binding, [in] unsigned long ids_length, [in, size_is(ids_length)] unsigned long* ids, [out] unsigned long* return_value_length, [out, size_is(, *return_value_length)] double** return_value);
+    void split([in] handle_t binding, [in] unsigned hyper value, [out] unsigned long* high, [in, out] unsigned long* low, [out, string] wchar_t** return_value);
+    [idempotent] void touch([in] handle_t binding, [in] unsigned long id);
+}
+";
+
+#[test]
+fn test_idl() {
+    assert_eq!(CatalogClient::IDL, EXPECTED);
+    assert_eq!(CatalogServer::<CatalogImpl>::IDL, EXPECTED);
+}
+
+#[test]
+fn test_idl_file() {
+    let written =
+        std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test_idl.idl"))
+            .expect("Failed to read the written IDL");
+    assert_eq!(written, EXPECTED);
+}
+
+#[test]
+fn test_header() {
+    let header = CatalogClient::HEADER;
+    assert_eq!(CatalogServer::<CatalogImpl>::HEADER, header);
+    assert!(header.contains(
+        "static const GUID Catalog_GUID = { 0x6d3a8f21, 0x94c7, 0x4e0b, \
+         { 0xb2, 0x58, 0x1f, 0x7e, 0x0c, 0x9a, 0x4d, 0x63 } };\n"
+    ));
+    assert!(header.contains("#define Catalog_VERSION_MAJOR 1\n#define Catalog_VERSION_MINOR 2\n"));
+    assert!(header.contains("\nunsigned long count(/* [in] */ handle_t binding);\n"));
+    assert!(header.contains(
+        "\n/* [idempotent] */ void touch(/* [in] */ handle_t binding, \
+         /* [in] */ unsigned long id);\n"
+    ));
+    assert!(header.contains("extern RPC_IF_HANDLE Catalog_v1_2_c_ifspec;\n"));
+
+    let written = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test_idl.h"))
+        .expect("Failed to read the written header");
+    assert_eq!(written, header);
+}
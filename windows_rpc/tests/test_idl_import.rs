@@ -0,0 +1,113 @@
+use windows::Win32::System::Rpc::RPC_S_INVALID_BOUND;
+use windows_rpc::{NdrEnum, NdrStruct, rpc_interface_from_idl};
+
+#[derive(NdrEnum, Clone, Copy, Debug, PartialEq)]
+#[repr(u32)]
+pub enum EntryKind {
+    File = 1,
+    Folder = 2,
+}
+
+#[derive(NdrStruct, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+// Generates `DirectoryServiceClient` and `DirectoryServiceServer`
+rpc_interface_from_idl!("tests/test_idl_import.idl");
+
+// The IDL generated for `Catalog` by test_idl.rs
+rpc_interface_from_idl!("tests/test_idl.idl");
+
+struct DirectoryServiceImpl;
+impl DirectoryServiceServerImpl for DirectoryServiceImpl {
+    fn get_count() -> u32 {
+        3
+    }
+
+    fn add_entry(psz_name: &str, kind: EntryKind, pdw_quota: Option<u32>) -> i32 {
+        match kind {
+            EntryKind::File => psz_name.len() as i32 + pdw_quota.unwrap_or(0) as i32,
+            EntryKind::Folder => -1,
+        }
+    }
+
+    fn lookup(psz_name: &str) -> String {
+        format!("value of {psz_name}")
+    }
+
+    fn sum_sizes(p_sizes: &[u64], p_total: &mut u64) {
+        *p_total = p_sizes.iter().sum();
+    }
+
+    fn list_ids() -> Vec<u32> {
+        vec![4, 8, 15]
+    }
+
+    fn move_(p_from: &Position, p_to: &mut Position) {
+        p_to.x += p_from.x;
+        p_to.y += p_from.y;
+    }
+
+    fn touch(_dw_id: u32) {}
+
+    fn read_entry(dw_id: u32, pb_buffer: &mut [u8], _cb_buffer: u32) {
+        pb_buffer.fill(dw_id as u8);
+    }
+
+    fn distance(p_from: &Position, p_to: &Position) -> u32 {
+        p_from.x.abs_diff(p_to.x) + p_from.y.abs_diff(p_to.y)
+    }
+}
+
+#[test]
+fn test_idl_import() {
+    let (_server, client) =
+        windows_rpc::testing::pair::<DirectoryServiceServer<_>, _>(DirectoryServiceImpl)
+            .expect("Failed to create client/server pair");
+
+    assert_eq!(
+        DirectoryServiceClient::INTERFACE_ID,
+        "4b9e1c37-82d5-4f0a-a6c3-5e7d29b10f84:2.1"
+    );
+    assert_eq!(client.get_count().unwrap(), 3);
+    assert_eq!(
+        client.add_entry("abc", EntryKind::File, Some(10)).unwrap(),
+        13
+    );
+    assert_eq!(
+        client.add_entry("abc", EntryKind::Folder, None).unwrap(),
+        -1
+    );
+    assert_eq!(client.lookup("key").unwrap(), "value of key");
+    let mut total = 0;
+    client.sum_sizes(&[1, 2, 3], &mut total).unwrap();
+    assert_eq!(total, 6);
+    // The range of the length is kept on the slice
+    let error = client.sum_sizes(&[1; 17], &mut total).unwrap_err();
+    assert_eq!(error.status(), RPC_S_INVALID_BOUND);
+    assert_eq!(client.list_ids().unwrap(), [4, 8, 15]);
+    let mut to = Position { x: 1, y: 1 };
+    client.move_(&Position { x: 2, y: 3 }, &mut to).unwrap();
+    assert_eq!(to, Position { x: 3, y: 4 });
+    client.touch(1).unwrap();
+    // The out array is sized by a length that doesn't precede it
+    let mut buffer = [0; 4];
+    client.read_entry(7, &mut buffer, 3).unwrap();
+    assert_eq!(buffer, [7, 7, 7, 0]);
+    // `[ptr]` parameters are full pointers, which may alias each other
+    let origin = Position::default();
+    assert_eq!(
+        client.distance(&origin, &Position { x: 3, y: 4 }).unwrap(),
+        7
+    );
+    assert_eq!(client.distance(&origin, &origin).unwrap(), 0);
+}
+
+#[test]
+fn test_idl_round_trip() {
+    // Importing generated IDL gives back the same interface
+    assert_eq!(CatalogClient::IDL, include_str!("test_idl.idl"));
+}
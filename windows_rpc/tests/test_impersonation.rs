@@ -0,0 +1,52 @@
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Security::TOKEN_QUERY;
+use windows::Win32::System::Rpc::RPC_S_NO_CALL_ACTIVE;
+use windows::Win32::System::Threading::{GetCurrentThread, OpenThreadToken};
+use windows_rpc::executor::SingleThreadExecutor;
+use windows_rpc::rpc_interface;
+use windows_rpc::server::impersonate_client;
+
+#[rpc_interface(guid(0xd7a2e5c3_1b84_4f6e_92a0_5c3e8b1f7d49), version(1.0))]
+trait ImpersonateRpc {
+    // Whether the thread had a token while impersonating, and after reverting
+    fn check() -> u32;
+}
+
+/// Returns whether the current thread is impersonating
+fn has_thread_token() -> bool {
+    let mut token = HANDLE::default();
+    let result = unsafe { OpenThreadToken(GetCurrentThread(), TOKEN_QUERY, true, &mut token) };
+    if result.is_ok() {
+        let _ = unsafe { CloseHandle(token) };
+    }
+    result.is_ok()
+}
+
+struct ImpersonateRpcImpl;
+impl ImpersonateRpcServerImpl for ImpersonateRpcImpl {
+    fn check() -> u32 {
+        let during = impersonate_client(has_thread_token).unwrap_or(false);
+        let after = has_thread_token();
+        u32::from(during) | u32::from(after) << 1
+    }
+}
+
+#[test]
+fn test_impersonate_client() {
+    let (mut server, client) =
+        windows_rpc::testing::pair::<ImpersonateRpcServer<_>, _>(ImpersonateRpcImpl)
+            .expect("Failed to create client/server pair");
+
+    // Impersonating during the call only
+    assert_eq!(client.check().unwrap(), 0b01);
+
+    // Handlers on an executor impersonate the client as well
+    server.set_executor(SingleThreadExecutor::new());
+    assert_eq!(client.check().unwrap(), 0b01);
+}
+
+#[test]
+fn test_impersonate_outside_of_call() {
+    let error = impersonate_client(|| ()).expect_err("no call is being handled");
+    assert_eq!(error.code(), RPC_S_NO_CALL_ACTIVE.to_hresult());
+}
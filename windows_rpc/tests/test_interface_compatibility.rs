@@ -0,0 +1,142 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{
+    InterfaceCompatibility, ProtocolSequence, TransferSyntax, client_binding::ClientBinding,
+};
+
+#[rpc_interface(guid(0x4f2a7c19_d6e3_4b85_a0c2_91e8b5d7f362), version(1.0))]
+trait CompatRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn echo(text: &str) -> String;
+    fn syntax() -> u32;
+}
+
+struct CompatRpcImpl;
+impl CompatRpcServerImpl for CompatRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn echo(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn syntax() -> u32 {
+        match windows_rpc::server::transfer_syntax() {
+            Some(TransferSyntax::Ndr20) => 20,
+            Some(TransferSyntax::Ndr64) => 64,
+            None => 0,
+        }
+    }
+}
+
+#[test]
+fn test_legacy_compatibility() {
+    let endpoint = "test_interface_compat_legacy";
+
+    let mut server = CompatRpcServer::<CompatRpcImpl>::new();
+    server.set_compatibility(InterfaceCompatibility::Legacy);
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = CompatRpcClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+    assert_eq!(client.echo("legacy").unwrap(), "legacy");
+    assert_eq!(client.transfer_syntax(), Some(TransferSyntax::Ndr20));
+    assert_eq!(
+        client.syntax().unwrap(),
+        20,
+        "a legacy interface should only offer NDR 2.0"
+    );
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_autolisten_compatibility() {
+    let endpoint = "test_interface_compat_autolisten";
+
+    // No listen call: an autolisten interface accepts calls once registered
+    let mut server = CompatRpcServer::<CompatRpcImpl>::new();
+    server.set_compatibility(InterfaceCompatibility::AutoListen);
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+
+    let client = CompatRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+    );
+
+    assert_eq!(client.add(20, 22).unwrap(), 42);
+    assert_eq!(client.echo("autolisten").unwrap(), "autolisten");
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_legacy_autolisten() {
+    let endpoint = "test_interface_compat_legacy_autolisten";
+
+    // Autolisten combines with any preset
+    let mut server = CompatRpcServer::<CompatRpcImpl>::new();
+    server.set_compatibility(InterfaceCompatibility::Legacy);
+    server.set_autolisten(true);
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+
+    let client = CompatRpcClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    assert_eq!(client.add(1, 1).unwrap(), 2);
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_clients_share_stub_info() {
+    let endpoint = "test_interface_compat_shared";
+
+    let mut server = CompatRpcServer::<CompatRpcImpl>::new();
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    // Clients of both presets, created from several threads at once, each build or reuse
+    // the stub info of their preset
+    let threads: Vec<_> = (0..8)
+        .map(|i| {
+            std::thread::spawn(move || {
+                let compatibility = if i % 2 == 0 {
+                    InterfaceCompatibility::Ndr64
+                } else {
+                    InterfaceCompatibility::Legacy
+                };
+                for j in 0..4 {
+                    let client = CompatRpcClient::with_compatibility(
+                        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+                            .expect("Failed to create client binding"),
+                        compatibility,
+                    );
+                    assert_eq!(client.add(i, j).unwrap(), i + j);
+                    assert_eq!(client.echo("shared").unwrap(), "shared");
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().expect("Client thread panicked");
+    }
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,63 @@
+use windows_rpc::rpc_interface;
+
+#[rpc_interface]
+trait ConstRpc {
+    const GUID: u128 = 0x4c81a7e3_5f2d_4b96_8e0a_d3b6f19c2e74;
+    const VERSION: (u16, u16) = (2, 3);
+
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x1d2e3f40_5a6b_4c7d_8e9f_a0b1c2d3e4f5), version(1.5))]
+trait AttrRpc {
+    fn noop();
+}
+
+struct ConstRpcImpl;
+impl ConstRpcServerImpl for ConstRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_interface_consts() {
+    assert_eq!(ConstRpcClient::GUID, 0x4c81a7e3_5f2d_4b96_8e0a_d3b6f19c2e74);
+    assert_eq!(ConstRpcClient::VERSION, (2, 3));
+    assert_eq!(ConstRpcServer::<ConstRpcImpl>::GUID, ConstRpcClient::GUID);
+    assert_eq!(ConstRpcServer::<ConstRpcImpl>::VERSION, (2, 3));
+
+    assert_eq!(AttrRpcClient::GUID, 0x1d2e3f40_5a6b_4c7d_8e9f_a0b1c2d3e4f5);
+    assert_eq!(AttrRpcClient::VERSION, (1, 5));
+}
+
+#[test]
+fn test_interface_id() {
+    assert_eq!(
+        ConstRpcClient::INTERFACE_ID,
+        "4c81a7e3-5f2d-4b96-8e0a-d3b6f19c2e74:2.3"
+    );
+    assert_eq!(
+        ConstRpcServer::<ConstRpcImpl>::INTERFACE_ID,
+        ConstRpcClient::INTERFACE_ID
+    );
+    assert_eq!(
+        <AttrRpcClient as windows_rpc::interface::RpcClient>::INTERFACE_ID,
+        "1d2e3f40-5a6b-4c7d-8e9f-a0b1c2d3e4f5:1.5"
+    );
+
+    let (guid, major, minor) = ConstRpcServer::<ConstRpcImpl>::interface_id();
+    assert_eq!(
+        guid,
+        windows::core::GUID::from_u128(0x4c81a7e3_5f2d_4b96_8e0a_d3b6f19c2e74)
+    );
+    assert_eq!((major, minor), (2, 3));
+    assert_eq!(ConstRpcClient::interface_id(), (guid, major, minor));
+}
+
+#[test]
+fn test_interface_consts_call() {
+    let (_server, client) = windows_rpc::testing::pair::<ConstRpcServer<_>, _>(ConstRpcImpl)
+        .expect("Failed to create client/server pair");
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+}
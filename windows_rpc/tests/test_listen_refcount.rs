@@ -0,0 +1,60 @@
+use windows::Win32::System::Rpc::{RPC_S_NOT_LISTENING, RpcMgmtIsServerListening};
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0xc4e81a3f_6d29_4b70_95e2_a07d3b6f18c5), version(1.0))]
+trait FirstRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x2d97f0b6_e3a1_4c58_b84d_5f16c29ae073), version(1.0))]
+trait SecondRpc {
+    fn multiply(a: i32, b: i32) -> i32;
+}
+
+struct FirstRpcImpl;
+impl FirstRpcServerImpl for FirstRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+struct SecondRpcImpl;
+impl SecondRpcServerImpl for SecondRpcImpl {
+    fn multiply(a: i32, b: i32) -> i32 {
+        a * b
+    }
+}
+
+#[test]
+fn test_listen_refcount() {
+    let (first, first_client) = windows_rpc::testing::pair::<FirstRpcServer<_>, _>(FirstRpcImpl)
+        .expect("Failed to create client/server pair");
+    let (second, second_client) =
+        windows_rpc::testing::pair::<SecondRpcServer<_>, _>(SecondRpcImpl)
+            .expect("Failed to create client/server pair");
+    assert_eq!(first_client.add(1, 2).unwrap(), 3);
+    assert_eq!(second_client.multiply(2, 3).unwrap(), 6);
+
+    // Stopping one server leaves the other one serving
+    first.stop().expect("Failed to stop server");
+    assert!(first_client.add(1, 2).is_err());
+    assert_eq!(second_client.multiply(3, 4).unwrap(), 12);
+    assert!(unsafe { RpcMgmtIsServerListening(None) }.is_ok());
+
+    // A stopped server serves its interface again once it listens
+    first.listen_async().expect("Failed to restart server");
+    assert_eq!(first_client.add(2, 3).unwrap(), 5);
+    second.stop().expect("Failed to stop server");
+    assert!(second_client.multiply(2, 3).is_err());
+    assert_eq!(first_client.add(3, 4).unwrap(), 7);
+    second.listen_async().expect("Failed to restart server");
+    assert_eq!(second_client.multiply(4, 5).unwrap(), 20);
+    first.stop().expect("Failed to stop server");
+
+    // The last server stops listening
+    second.stop().expect("Failed to stop server");
+    assert_eq!(
+        unsafe { RpcMgmtIsServerListening(None) },
+        RPC_S_NOT_LISTENING
+    );
+}
@@ -0,0 +1,34 @@
+use windows_rpc::rpc_interface;
+
+// The wire contracts are recorded in tests/test_lockfile.lock; changing one of them
+// fails the build
+#[rpc_interface(
+    guid(0x2f8e5b17_d04a_4c69_93b2_7a1c6e4d58f0),
+    version(1.1),
+    lockfile("tests/test_lockfile.lock")
+)]
+trait LockedCalculator {
+    fn add(a: i32, b: i32) -> i32;
+    fn describe(value: i32, #[since(1.1, default = "")] prefix: &str) -> String;
+}
+
+struct LockedCalculatorImpl;
+impl LockedCalculatorServerImpl for LockedCalculatorImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn describe(value: i32, prefix: &str) -> String {
+        format!("{prefix}{value}")
+    }
+}
+
+#[test]
+fn test_locked_interface() {
+    let (_server, client) =
+        windows_rpc::testing::pair::<LockedCalculatorServer<_>, _>(LockedCalculatorImpl)
+            .expect("Failed to create client/server pair");
+
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+    assert_eq!(client.describe(7, "value: ").unwrap(), "value: 7");
+}
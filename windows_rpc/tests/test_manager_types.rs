@@ -0,0 +1,116 @@
+use windows::Win32::System::Rpc::RPC_S_ACCESS_DENIED;
+use windows_rpc::rpc_interface;
+use windows_rpc::server_binding::{EndpointMode, set_object_type};
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x5b8e2d17_c4a9_4f63_9e0d_72a1f6c83b54), version(1.0))]
+trait Combine {
+    fn combine(a: i32, b: i32) -> i32;
+}
+
+struct Sum;
+impl CombineServerImpl for Sum {
+    fn combine(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+struct Product;
+impl CombineServerImpl for Product {
+    fn combine(a: i32, b: i32) -> i32 {
+        a * b
+    }
+}
+
+const PRODUCT_TYPE: u128 = 0x0e4c97a3_5f28_4b1d_a6e7_c93d08f2b145;
+const PRODUCT_OBJECT: u128 = 0x7a61d3f0_2b95_4e8c_81a4_5dc7e09b36f2;
+const GUARDED_TYPE: u128 = 0x3f0d7b92_e6a4_4c15_9b38_d2851ae7c064;
+const GUARDED_OBJECT: u128 = 0xc85e2a16_47d3_4b9f_a0e1_69fb3d5c2807;
+
+fn client(endpoint: &str, object: Option<u128>) -> CombineClient {
+    let mut binding = ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+        .expect("Failed to create client binding");
+    if let Some(object) = object {
+        binding.set_object(object).expect("Failed to set object");
+        assert_eq!(binding.object().unwrap(), object);
+    }
+    CombineClient::new(binding)
+}
+
+#[test]
+fn test_manager_types() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+    let other_endpoint = windows_rpc::testing::unique_endpoint();
+    set_object_type(PRODUCT_OBJECT, Some(PRODUCT_TYPE)).expect("Failed to set object type");
+    set_object_type(GUARDED_OBJECT, Some(GUARDED_TYPE)).expect("Failed to set object type");
+
+    // The default implementation, for objects of no other type
+    let mut sum = CombineServer::<Sum>::new();
+    sum.register(&endpoint).expect("Failed to register server");
+    sum.listen_async().expect("Failed to start listening");
+
+    let mut product = CombineServer::<Product>::new();
+    product.set_endpoint_mode(EndpointMode::ReuseExisting);
+    product.set_manager_type(PRODUCT_TYPE);
+    product
+        .register(&endpoint)
+        .expect("Failed to register the second implementation");
+    product.listen_async().expect("Failed to start listening");
+
+    assert_eq!(client(&endpoint, None).combine(3, 4).unwrap(), 7);
+    assert_eq!(
+        client(&endpoint, Some(PRODUCT_OBJECT))
+            .combine(3, 4)
+            .unwrap(),
+        12
+    );
+
+    // Security configuration only applies to the calls of its own manager
+    let mut guarded = CombineServer::<Product>::new();
+    guarded.set_endpoint_mode(EndpointMode::ReuseExisting);
+    guarded.set_manager_type(GUARDED_TYPE);
+    guarded.set_security_callback(|_, _| Err(RPC_S_ACCESS_DENIED));
+    guarded
+        .register(&endpoint)
+        .expect("Failed to register the guarded implementation");
+    guarded.listen_async().expect("Failed to start listening");
+    let error = client(&endpoint, Some(GUARDED_OBJECT))
+        .combine(3, 4)
+        .unwrap_err();
+    assert_eq!(error.code(), RPC_S_ACCESS_DENIED.to_hresult());
+    assert_eq!(client(&endpoint, None).combine(3, 4).unwrap(), 7);
+
+    // Endpoints removed from one manager are still served by the others
+    guarded
+        .add_endpoint(&other_endpoint)
+        .expect("Failed to add endpoint");
+    product
+        .add_endpoint(&other_endpoint)
+        .expect("Failed to add endpoint");
+    product
+        .remove_endpoint(&endpoint)
+        .expect("Failed to remove endpoint");
+    assert!(
+        client(&endpoint, Some(PRODUCT_OBJECT))
+            .combine(3, 4)
+            .is_err()
+    );
+    assert_eq!(
+        client(&other_endpoint, Some(PRODUCT_OBJECT))
+            .combine(3, 4)
+            .unwrap(),
+        12
+    );
+    assert_eq!(client(&endpoint, None).combine(5, 6).unwrap(), 11);
+    guarded.stop().expect("Failed to stop server");
+    drop(guarded);
+
+    // Unregistering an implementation leaves the other registered
+    product.stop().expect("Failed to stop server");
+    drop(product);
+    assert_eq!(client(&endpoint, None).combine(5, 6).unwrap(), 11);
+
+    sum.stop().expect("Failed to stop server");
+    set_object_type(PRODUCT_OBJECT, None).expect("Failed to remove object type");
+    set_object_type(GUARDED_OBJECT, None).expect("Failed to remove object type");
+}
@@ -0,0 +1,32 @@
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0xb57e2d91_36c4_4a0f_9d82_e1c6f4a30b78), version(1.0))]
+trait QuotaRpc {
+    fn repeat(text: &str, count: u32) -> String;
+}
+
+struct QuotaRpcImpl;
+impl QuotaRpcServerImpl for QuotaRpcImpl {
+    fn repeat(text: &str, count: u32) -> String {
+        text.repeat(count as usize)
+    }
+}
+
+#[test]
+fn test_calls_within_quota() {
+    let (mut server, client) = windows_rpc::testing::pair::<QuotaRpcServer<_>, _>(QuotaRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    // Returned strings are allocated by the stubs and count against the quota
+    server.set_memory_quota(Some(64 * 1024));
+    assert_eq!(client.repeat("ab", 3).unwrap(), "ababab");
+    assert_eq!(client.repeat("x", 1000).unwrap().len(), 1000);
+
+    // The quota applies per call, not to the server's lifetime
+    for _ in 0..100 {
+        assert_eq!(client.repeat("y", 10_000).unwrap().len(), 10_000);
+    }
+
+    server.set_memory_quota(None);
+    assert_eq!(client.repeat("z", 100_000).unwrap().len(), 100_000);
+}
@@ -0,0 +1,104 @@
+use windows_rpc::metadata::TypeMetadata;
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x0a1b2c3d_4e5f_4a6b_8c7d_9e0f1a2b3c4d), version(2.1))]
+trait MetadataRpc {
+    fn add(a: i32, b: i64) -> i32;
+    fn greet(name: &str) -> String;
+    fn upload(data: &[u8]);
+    fn ping();
+}
+
+struct MetadataRpcImpl;
+impl MetadataRpcServerImpl for MetadataRpcImpl {
+    fn add(a: i32, b: i64) -> i32 {
+        a + b as i32
+    }
+
+    fn greet(name: &str) -> String {
+        name.to_string()
+    }
+
+    fn upload(_data: &[u8]) {}
+
+    fn ping() {}
+}
+
+#[test]
+fn test_metadata() {
+    let metadata = MetadataRpcClient::METADATA;
+    assert_eq!(metadata, MetadataRpcServer::<MetadataRpcImpl>::METADATA);
+
+    assert_eq!(metadata.name, "MetadataRpc");
+    assert_eq!(metadata.guid, MetadataRpcClient::GUID);
+    assert_eq!(
+        metadata.guid_string(),
+        "0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d"
+    );
+    assert_eq!(metadata.version, (2, 1));
+
+    let names: Vec<_> = metadata.methods.iter().map(|m| (m.opnum, m.name)).collect();
+    assert_eq!(
+        names,
+        [(0, "add"), (1, "greet"), (2, "upload"), (3, "ping")]
+    );
+
+    let greet = &metadata.methods[1];
+    assert_eq!(greet.parameters[0].name, "name");
+    assert_eq!(greet.parameters[0].r#type.rust_type, "&str");
+    assert_eq!(
+        greet.return_type,
+        Some(TypeMetadata {
+            rust_type: "String",
+            idl_type: "[string] wchar_t*",
+        })
+    );
+    assert_eq!(metadata.methods[3].return_type, None);
+}
+
+#[test]
+fn test_metadata_json() {
+    let expected = r#"{
+  "name": "MetadataRpc",
+  "guid": "0a1b2c3d-4e5f-4a6b-8c7d-9e0f1a2b3c4d",
+  "version": { "major": 2, "minor": 1 },
+  "methods": [
+    {
+      "name": "add",
+      "opnum": 0,
+      "parameters": [
+        { "name": "a", "rust_type": "i32", "idl_type": "long", "direction": "in" },
+        { "name": "b", "rust_type": "i64", "idl_type": "hyper", "direction": "in" }
+      ],
+      "return": { "rust_type": "i32", "idl_type": "long" },
+      "error": null
+    },
+    {
+      "name": "greet",
+      "opnum": 1,
+      "parameters": [
+        { "name": "name", "rust_type": "&str", "idl_type": "[string] wchar_t*", "direction": "in" }
+      ],
+      "return": { "rust_type": "String", "idl_type": "[string] wchar_t*" },
+      "error": null
+    },
+    {
+      "name": "upload",
+      "opnum": 2,
+      "parameters": [
+        { "name": "data", "rust_type": "&[u8]", "idl_type": "[size_is(length)] byte*", "direction": "in" }
+      ],
+      "return": null,
+      "error": null
+    },
+    {
+      "name": "ping",
+      "opnum": 3,
+      "parameters": [],
+      "return": null,
+      "error": null
+    }
+  ]
+}"#;
+    assert_eq!(MetadataRpcClient::METADATA.to_json(), expected);
+}
@@ -0,0 +1,69 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x7e2a4c96_13b8_4f5d_a6c1_9b0d58e3f274), version(1.0))]
+trait Notifier {
+    #[rpc(maybe)]
+    fn notify(event: &str, value: u32);
+    #[rpc(idempotent)]
+    fn count() -> u32;
+    #[rpc(maybe, idempotent)]
+    fn reset();
+}
+
+static EVENTS: Mutex<Vec<(String, u32)>> = Mutex::new(Vec::new());
+
+struct NotifierImpl;
+impl NotifierServerImpl for NotifierImpl {
+    fn notify(event: &str, value: u32) {
+        EVENTS.lock().unwrap().push((event.to_string(), value));
+    }
+
+    fn count() -> u32 {
+        EVENTS.lock().unwrap().len() as u32
+    }
+
+    fn reset() {
+        EVENTS.lock().unwrap().clear();
+    }
+}
+
+#[test]
+fn test_method_attributes() {
+    let (_server, client) = windows_rpc::testing::pair::<NotifierServer<_>, _>(NotifierImpl)
+        .expect("Failed to create client/server pair");
+
+    for value in 0..3 {
+        client
+            .notify("tick", value)
+            .expect("Failed to send notification");
+    }
+
+    // The calls return without waiting for the server, which handles them eventually
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while client.count().unwrap() < 3 {
+        assert!(
+            Instant::now() < deadline,
+            "notifications were not delivered"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let mut events = EVENTS.lock().unwrap().clone();
+    events.sort_by_key(|(_, value)| *value);
+    assert_eq!(
+        events,
+        [0, 1, 2].map(|value| ("tick".to_string(), value)).to_vec()
+    );
+
+    // Combined attributes, and the idempotent `count()` above, work like any other call
+    client.reset().expect("Failed to reset");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while client.count().unwrap() != 0 {
+        assert!(Instant::now() < deadline, "the reset was not delivered");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,53 @@
+use windows::Win32::System::Rpc::RPC_S_ACCESS_DENIED;
+use windows_rpc::rpc_interface;
+use windows_rpc::security::Authorization;
+use windows_rpc::server::CallContext;
+
+#[rpc_interface(guid(0x9a4e2c17_b5d8_4f63_a0c1_7e3b58d2f904), version(1.0))]
+trait AclRpc {
+    fn read() -> i32;
+    fn write(value: i32) -> i32;
+    fn panic_check() -> i32;
+}
+
+struct AclRpcImpl;
+impl AclRpcServerImpl for AclRpcImpl {
+    fn read() -> i32 {
+        1
+    }
+
+    fn write(value: i32) -> i32 {
+        value
+    }
+
+    fn panic_check() -> i32 {
+        3
+    }
+}
+
+#[test]
+fn test_method_authorizer() {
+    let (mut server, client) = windows_rpc::testing::pair::<AclRpcServer<_>, _>(AclRpcImpl)
+        .expect("Failed to create client/server pair");
+    server.set_method_authorizer(|opnum: u32, context: &CallContext| {
+        assert_eq!(context.client_pid, Some(std::process::id()));
+        match opnum {
+            0 => Authorization::Allow,
+            1 => Authorization::Deny,
+            _ => panic!("unexpected method"),
+        }
+    });
+
+    assert_eq!(client.read().unwrap(), 1);
+    let error = client
+        .write(2)
+        .expect_err("the authorizer should deny the call");
+    assert_eq!(error.status(), RPC_S_ACCESS_DENIED);
+    let error = client
+        .panic_check()
+        .expect_err("a panicking authorizer should deny the call");
+    assert_eq!(error.status(), RPC_S_ACCESS_DENIED);
+
+    // The server keeps serving allowed methods
+    assert_eq!(client.read().unwrap(), 1);
+}
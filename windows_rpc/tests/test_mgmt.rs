@@ -0,0 +1,34 @@
+use windows::Win32::System::Rpc::RPC_S_SERVER_UNAVAILABLE;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, mgmt, rpc_interface};
+
+#[rpc_interface(guid(0x4f7d2a91_b36c_4e58_8a04_c1e95d3b7f62), version(1.2))]
+trait Inventory {
+    fn count() -> u32;
+}
+
+struct InventoryImpl;
+impl InventoryServerImpl for InventoryImpl {
+    fn count() -> u32 {
+        3
+    }
+}
+
+#[test]
+fn test_inq_if_ids() {
+    let (server, client) = windows_rpc::testing::pair::<InventoryServer<_>, _>(InventoryImpl)
+        .expect("Failed to create client/server pair");
+    assert_eq!(client.count().unwrap(), 3);
+
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+        .expect("Failed to create client binding");
+    let interfaces = mgmt::inq_if_ids(&binding).expect("Failed to query the server");
+    assert!(interfaces.contains(&(InventoryClient::GUID, InventoryClient::VERSION)));
+}
+
+#[test]
+fn test_inq_if_ids_missing_server() {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "test_mgmt_no_such_endpoint")
+        .expect("Failed to create client binding");
+    let error = mgmt::inq_if_ids(&binding).expect_err("querying a missing endpoint should fail");
+    assert_eq!(error.status(), RPC_S_SERVER_UNAVAILABLE);
+}
@@ -0,0 +1,116 @@
+use windows::Win32::System::Rpc::{RPC_S_ALREADY_LISTENING, RPC_S_INVALID_VERS_OPTION};
+use windows_rpc::host::RpcServerHost;
+use windows_rpc::negotiate::{Negotiate, Negotiated};
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0xc7a2e95b_1d40_4f83_b6e2_09f5d3a71c68), version(1.0))]
+trait Inventory {
+    fn count(item: u32) -> u32;
+}
+
+#[rpc_interface(guid(0xc7a2e95b_1d40_4f83_b6e2_09f5d3a71c68), version(1.1))]
+trait InventoryV11 {
+    fn count(item: u32) -> u32;
+    fn reserve(item: u32, amount: u32) -> u32;
+}
+
+#[rpc_interface(guid(0xc7a2e95b_1d40_4f83_b6e2_09f5d3a71c68), version(2.0))]
+trait InventoryV2 {
+    fn count_all() -> u32;
+}
+
+struct LegacyInventory;
+impl InventoryServerImpl for LegacyInventory {
+    fn count(item: u32) -> u32 {
+        item
+    }
+}
+
+struct CurrentInventory;
+impl InventoryV11ServerImpl for CurrentInventory {
+    fn count(item: u32) -> u32 {
+        item * 10
+    }
+
+    fn reserve(item: u32, amount: u32) -> u32 {
+        item * 10 - amount
+    }
+}
+
+impl InventoryV2ServerImpl for CurrentInventory {
+    fn count_all() -> u32 {
+        100
+    }
+}
+
+fn binding(endpoint: &str) -> ClientBinding {
+    ClientBinding::new(ProtocolSequence::Alpc, endpoint).expect("Failed to create client binding")
+}
+
+#[test]
+fn test_multi_version() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut host = RpcServerHost::new(ProtocolSequence::Alpc, &endpoint);
+    host.add(InventoryServer::<LegacyInventory>::new())
+        .expect("Failed to add the 1.0 server");
+    host.add(InventoryV11Server::<CurrentInventory>::new())
+        .expect("Failed to add the 1.1 server");
+    host.add(InventoryV2Server::<CurrentInventory>::new())
+        .expect("Failed to add the 2.0 server");
+    assert_eq!(
+        host.versions(InventoryClient::GUID),
+        [(1, 0), (1, 1), (2, 0)]
+    );
+    match host.listen_async() {
+        // Another test in this process already started listening
+        Err(e) if e.code() == RPC_S_ALREADY_LISTENING.to_hresult() => {}
+        result => result.expect("Failed to start listening"),
+    }
+
+    // Each version's clients are served by the implementation of their version
+    assert_eq!(
+        InventoryClient::new(binding(&endpoint)).count(3).unwrap(),
+        3
+    );
+    let client = InventoryV11Client::new(binding(&endpoint));
+    assert_eq!(client.count(3).unwrap(), 30);
+    assert_eq!(client.reserve(3, 5).unwrap(), 25);
+    assert_eq!(
+        InventoryV2Client::new(binding(&endpoint))
+            .count_all()
+            .unwrap(),
+        100
+    );
+
+    let client = Negotiated::<InventoryV11Client, InventoryClient>::connect(binding(&endpoint))
+        .expect("Failed to create the clients");
+    assert_eq!(
+        client
+            .call(|client| client.count(3), |client| client.count(3))
+            .unwrap(),
+        30
+    );
+    assert_eq!(client.version(), Some((1, 1)));
+
+    host.stop().expect("Failed to stop host");
+}
+
+#[test]
+fn test_multi_version_order() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut host = RpcServerHost::new(ProtocolSequence::Alpc, &endpoint);
+    host.add(InventoryV11Server::<CurrentInventory>::new())
+        .expect("Failed to add the 1.1 server");
+    // The 1.1 server would take the calls of 1.0 clients
+    let error = host
+        .add(InventoryServer::<LegacyInventory>::new())
+        .expect_err("adding an older minor version last should fail");
+    assert_eq!(error.code(), RPC_S_INVALID_VERS_OPTION.to_hresult());
+    // Other major versions are independent
+    host.add(InventoryV2Server::<CurrentInventory>::new())
+        .expect("Failed to add the 2.0 server");
+    assert_eq!(host.len(), 2);
+}
@@ -0,0 +1,52 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0xc5a8e132_6f4d_4b97_8e20_3d9b71f5a6c4), version(1.0))]
+trait MultiRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct MultiRpcImpl;
+impl MultiRpcServerImpl for MultiRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+fn client(endpoint: &str) -> MultiRpcClient {
+    MultiRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+    )
+}
+
+#[test]
+fn test_multiple_endpoints() {
+    let stable = "test_multiple_endpoints";
+    let versioned = "test_multiple_endpoints_v1";
+
+    let mut server = MultiRpcServer::<MultiRpcImpl>::new();
+    assert!(
+        server.add_endpoint(versioned).is_err(),
+        "not registered yet"
+    );
+    server.register(stable).expect("Failed to register server");
+    server
+        .add_endpoint(versioned)
+        .expect("Failed to add endpoint");
+    server.listen_async().expect("Failed to start listening");
+    assert_eq!(server.endpoints(), [stable, versioned]);
+
+    assert_eq!(client(stable).add(1, 2).unwrap(), 3);
+    assert_eq!(client(versioned).add(2, 3).unwrap(), 5);
+
+    // The other endpoint keeps being served
+    server
+        .remove_endpoint(stable)
+        .expect("Failed to remove endpoint");
+    assert_eq!(server.endpoints(), [versioned]);
+    assert_eq!(client(versioned).add(3, 4).unwrap(), 7);
+    assert!(server.remove_endpoint(stable).is_err(), "already removed");
+
+    server.stop().expect("Failed to stop server");
+}
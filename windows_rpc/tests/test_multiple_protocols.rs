@@ -0,0 +1,58 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x6b4d2f8e_91a7_4c35_b0e2_8f5c3a71d9e4), version(1.0))]
+trait DualRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct DualRpcImpl;
+impl DualRpcServerImpl for DualRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+fn client(protocol: ProtocolSequence, endpoint: &str) -> DualRpcClient {
+    DualRpcClient::new(
+        ClientBinding::new(protocol, endpoint).expect("Failed to create client binding"),
+    )
+}
+
+#[test]
+fn test_multiple_protocol_sequences() {
+    let alpc = windows_rpc::testing::unique_endpoint();
+    let pipe = format!(r"\pipe\{alpc}");
+
+    let mut server = DualRpcServer::<DualRpcImpl>::new();
+    assert!(server.register_endpoints(&[]).is_err(), "no endpoints");
+    server
+        .register_endpoints(&[
+            (ProtocolSequence::Alpc, alpc.as_str()),
+            (ProtocolSequence::NamedPipe, pipe.as_str()),
+        ])
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+    assert_eq!(server.endpoints(), [alpc.as_str(), pipe.as_str()]);
+
+    assert_eq!(client(ProtocolSequence::Alpc, &alpc).add(1, 2).unwrap(), 3);
+    assert_eq!(
+        client(ProtocolSequence::NamedPipe, &pipe)
+            .add(2, 3)
+            .unwrap(),
+        5
+    );
+
+    // Each protocol sequence's endpoint can be removed on its own
+    server
+        .remove_endpoint(&alpc)
+        .expect("Failed to remove endpoint");
+    assert_eq!(
+        client(ProtocolSequence::NamedPipe, &pipe)
+            .add(3, 4)
+            .unwrap(),
+        7
+    );
+
+    server.stop().expect("Failed to stop server");
+}
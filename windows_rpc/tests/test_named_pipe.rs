@@ -0,0 +1,48 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x2e7b5c91_d4a3_4f86_9b1e_6a0c38f2d751), version(1.0))]
+trait PipeRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn greet(name: &str) -> String;
+}
+
+struct PipeRpcImpl;
+impl PipeRpcServerImpl for PipeRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn greet(name: &str) -> String {
+        format!("Hello, {name}!")
+    }
+}
+
+#[test]
+fn test_named_pipe() {
+    assert_eq!(ProtocolSequence::NamedPipe.name(), "ncacn_np");
+    let endpoint = format!(r"\pipe\{}", windows_rpc::testing::unique_endpoint());
+
+    let mut server = PipeRpcServer::<PipeRpcImpl>::new();
+    server.set_protocol_sequence(ProtocolSequence::NamedPipe);
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = PipeRpcClient::new(
+        ClientBinding::new(ProtocolSequence::NamedPipe, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.greet("pipe").unwrap(), "Hello, pipe!");
+
+    // The local machine addressed by name, like a remote server
+    let remote = PipeRpcClient::new(
+        ClientBinding::with_server(ProtocolSequence::NamedPipe, "localhost", &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(remote.add(2, 3).unwrap(), 5);
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,196 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, mpsc};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use windows::Win32::System::Rpc::RPC_S_CALL_FAILED;
+use windows_rpc::executor::{Job, SingleThreadExecutor};
+use windows_rpc::{ProtocolSequence, ServiceError, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0x3d6f92a4_0b7e_4c58_a1d3_e8f25c96b047), version(1.0))]
+trait Jobs {
+    #[rpc(async)]
+    fn add(a: i32, b: i32) -> i32;
+    #[rpc(async)]
+    fn greet(name: &str) -> String;
+    #[rpc(async)]
+    fn squares(count: u32) -> Vec<u32>;
+    #[rpc(async)]
+    fn sleep(millis: u32);
+    #[rpc(async)]
+    fn check(value: u32) -> Result<u32, u32>;
+    #[rpc(async)]
+    fn split(value: u32, high: &mut u16) -> u16;
+    // Not asynchronous, alongside the asynchronous procedures
+    fn sync_add(a: i32, b: i32) -> i32;
+}
+
+struct JobsImpl;
+impl JobsServerImpl for JobsImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn greet(name: &str) -> String {
+        format!("Hello, {name}!")
+    }
+
+    fn squares(count: u32) -> Vec<u32> {
+        (0..count).map(|i| i * i).collect()
+    }
+
+    fn sleep(millis: u32) {
+        thread::sleep(Duration::from_millis(millis.into()));
+    }
+
+    fn check(value: u32) -> Result<u32, u32> {
+        if value == 0 {
+            return Err(87);
+        }
+        Ok(value * 2)
+    }
+
+    fn split(value: u32, high: &mut u16) -> u16 {
+        *high = (value >> 16) as u16;
+        value as u16
+    }
+
+    fn sync_add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+/// Wakes the thread blocked in `block_on`
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+#[test]
+fn test_blocking_calls() {
+    let (_server, client) = windows_rpc::testing::pair::<JobsServer<_>, _>(JobsImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.greet("async").unwrap(), "Hello, async!");
+    assert_eq!(client.squares(4).unwrap(), vec![0, 1, 4, 9]);
+    client.sleep(10).unwrap();
+    assert_eq!(client.check(21).unwrap(), 42);
+    assert!(matches!(client.check(0), Err(ServiceError::Service(87))));
+    let mut high = 0;
+    assert_eq!(client.split(0x0001_0002, &mut high).unwrap(), 2);
+    assert_eq!(high, 1);
+    assert_eq!(client.sync_add(2, 3).unwrap(), 5);
+}
+
+#[test]
+fn test_awaited_calls() {
+    let (_server, client) = windows_rpc::testing::pair::<JobsServer<_>, _>(JobsImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(block_on(client.add_async(20, 22)).unwrap(), 42);
+    let name = String::from("future");
+    let greeting = client.greet_async(&name);
+    // The request is sent once the method returns
+    drop(name);
+    assert_eq!(block_on(greeting).unwrap(), "Hello, future!");
+    assert_eq!(block_on(client.squares_async(3)).unwrap(), vec![0, 1, 4]);
+    assert!(matches!(
+        block_on(client.check_async(0)),
+        Err(ServiceError::Service(87))
+    ));
+
+    // Calls in flight at the same time, without a thread waiting for each
+    let slow = client.sleep_async(200);
+    let fast = client.add_async(2, 3);
+    assert_eq!(block_on(fast).unwrap(), 5);
+    block_on(slow).unwrap();
+}
+
+#[test]
+fn test_cancelled_call() {
+    let (_server, client) = windows_rpc::testing::pair::<JobsServer<_>, _>(JobsImpl)
+        .expect("Failed to create client/server pair");
+
+    // Dropping the future cancels the call, leaving the client usable
+    drop(client.sleep_async(500));
+    assert_eq!(client.add(1, 1).unwrap(), 2);
+}
+
+#[test]
+fn test_server_executor() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut server = JobsServer::<JobsImpl>::new();
+    server.set_executor(SingleThreadExecutor::new());
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = JobsClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+
+    assert_eq!(client.add(4, 5).unwrap(), 9);
+    assert_eq!(
+        block_on(client.greet_async("executor")).unwrap(),
+        "Hello, executor!"
+    );
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_completed_by_executor() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    // Holds the jobs, which complete their calls whenever they run
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let sender = Mutex::new(sender);
+    let mut server = JobsServer::<JobsImpl>::new();
+    server.set_executor(move |job: Job| {
+        sender.lock().unwrap().send(job).unwrap();
+    });
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = JobsClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+
+    // The RPC thread returned with the call pending on the job
+    let sum = client.add_async(6, 7);
+    let job = receiver.recv().unwrap();
+    thread::sleep(Duration::from_millis(50));
+    job();
+    assert_eq!(block_on(sum).unwrap(), 13);
+
+    // A dropped job aborts its call like a panicking handler
+    let sum = client.add_async(1, 2);
+    drop(receiver.recv().unwrap());
+    let error = block_on(sum).unwrap_err();
+    assert_eq!(error.status(), RPC_S_CALL_FAILED);
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,84 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{
+    InterfaceCompatibility, ProtocolSequence, TransferSyntax, client_binding::ClientBinding,
+};
+
+#[rpc_interface(
+    guid(0x8d4c2f61_b7e9_4a53_9c1d_6e2a5f80b347),
+    version(1.0),
+    transfer_syntax(ndr20)
+)]
+trait Ndr20Only {
+    fn add(a: i32, b: i32) -> i32;
+    fn return_string(param: &str) -> String;
+    fn syntax() -> u32;
+}
+
+// The same interface with both transfer syntaxes
+#[rpc_interface(guid(0x8d4c2f61_b7e9_4a53_9c1d_6e2a5f80b347), version(1.0))]
+trait AllSyntaxes {
+    fn add(a: i32, b: i32) -> i32;
+    fn return_string(param: &str) -> String;
+    fn syntax() -> u32;
+}
+
+struct Ndr20OnlyImpl;
+impl Ndr20OnlyServerImpl for Ndr20OnlyImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn return_string(param: &str) -> String {
+        format!("Got {param}")
+    }
+
+    fn syntax() -> u32 {
+        call_syntax()
+    }
+}
+
+/// Reports the transfer syntax of the call, as its number
+fn call_syntax() -> u32 {
+    match windows_rpc::server::transfer_syntax() {
+        Some(TransferSyntax::Ndr20) => 20,
+        Some(TransferSyntax::Ndr64) => 64,
+        None => 0,
+    }
+}
+
+#[test]
+fn test_ndr20_only() {
+    let (server, client) = windows_rpc::testing::pair::<Ndr20OnlyServer<_>, _>(Ndr20OnlyImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.return_string("hello").unwrap(), "Got hello");
+    assert_eq!(client.syntax().unwrap(), 20);
+    assert_eq!(client.transfer_syntax(), Some(TransferSyntax::Ndr20));
+
+    // Clients offering both syntaxes negotiate NDR 2.0
+    let client = AllSyntaxesClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+    assert_eq!(client.return_string("world").unwrap(), "Got world");
+    assert_eq!(client.syntax().unwrap(), 20);
+
+    // Clients restricted to NDR64 can't call it
+    let client = AllSyntaxesClient::with_transfer_syntax(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        TransferSyntax::Ndr64,
+    );
+    assert!(client.add(2, 3).is_err());
+
+    // The preset doesn't add NDR64
+    let client = Ndr20OnlyClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Ndr64,
+    );
+    assert_eq!(client.add(3, 4).unwrap(), 7);
+    assert_eq!(client.syntax().unwrap(), 20);
+}
@@ -0,0 +1,73 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, TransferSyntax, client_binding::ClientBinding};
+
+#[rpc_interface(
+    guid(0x3b8e51c7_d2a4_4f96_8e07_b5c1a94f2d63),
+    version(1.0),
+    transfer_syntax(ndr64)
+)]
+trait Ndr64Only {
+    fn add(a: i32, b: i32) -> i32;
+    fn return_string(param: &str) -> String;
+    fn syntax() -> u32;
+}
+
+// The same interface with both transfer syntaxes
+#[rpc_interface(guid(0x3b8e51c7_d2a4_4f96_8e07_b5c1a94f2d63), version(1.0))]
+trait AllSyntaxes {
+    fn add(a: i32, b: i32) -> i32;
+    fn return_string(param: &str) -> String;
+    fn syntax() -> u32;
+}
+
+struct Ndr64OnlyImpl;
+impl Ndr64OnlyServerImpl for Ndr64OnlyImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn return_string(param: &str) -> String {
+        format!("Got {param}")
+    }
+
+    fn syntax() -> u32 {
+        call_syntax()
+    }
+}
+
+/// Reports the transfer syntax of the call, as its number
+fn call_syntax() -> u32 {
+    match windows_rpc::server::transfer_syntax() {
+        Some(TransferSyntax::Ndr20) => 20,
+        Some(TransferSyntax::Ndr64) => 64,
+        None => 0,
+    }
+}
+
+#[test]
+fn test_ndr64_only() {
+    let (server, client) = windows_rpc::testing::pair::<Ndr64OnlyServer<_>, _>(Ndr64OnlyImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.return_string("hello").unwrap(), "Got hello");
+    assert_eq!(client.syntax().unwrap(), 64);
+    assert_eq!(client.transfer_syntax(), Some(TransferSyntax::Ndr64));
+
+    // Clients offering both syntaxes negotiate NDR64
+    let client = AllSyntaxesClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+    assert_eq!(client.return_string("world").unwrap(), "Got world");
+    assert_eq!(client.syntax().unwrap(), 64);
+
+    // Clients restricted to NDR 2.0 can't call it
+    let client = AllSyntaxesClient::with_transfer_syntax(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        TransferSyntax::Ndr20,
+    );
+    assert!(client.add(2, 3).is_err());
+}
@@ -0,0 +1,134 @@
+use windows::Win32::System::Rpc::RPC_S_UNKNOWN_IF;
+use windows_rpc::negotiate::{Negotiate, Negotiated};
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+// Only the 1.0 version of this interface is served
+#[rpc_interface(guid(0x93c1d7e4_5a28_4f6b_b0d9_2e7a4c8f1b53), version(1.0))]
+trait OldOnly {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x93c1d7e4_5a28_4f6b_b0d9_2e7a4c8f1b53), version(1.1))]
+trait OldOnlyV11 {
+    fn add(a: i32, b: i32) -> i32;
+    fn negate(a: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x93c1d7e4_5a28_4f6b_b0d9_2e7a4c8f1b53), version(2.0))]
+trait OldOnlyV2 {
+    fn sub(a: i32, b: i32) -> i32;
+}
+
+// The 1.1 version of this interface is served, which also accepts 1.0 clients
+#[rpc_interface(guid(0x1e8b6f20_c94d_4a37_85e1_d3f9a0b27c64), version(1.0))]
+trait Current {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x1e8b6f20_c94d_4a37_85e1_d3f9a0b27c64), version(1.1))]
+trait CurrentV11 {
+    fn add(a: i32, b: i32) -> i32;
+    fn negate(a: i32) -> i32;
+}
+
+struct Impl;
+impl OldOnlyServerImpl for Impl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+impl CurrentV11ServerImpl for Impl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn negate(a: i32) -> i32 {
+        -a
+    }
+}
+
+fn binding(endpoint: &str) -> ClientBinding {
+    ClientBinding::new(ProtocolSequence::Alpc, endpoint).expect("Failed to create client binding")
+}
+
+#[test]
+fn test_negotiate_fallback() {
+    let (server, _) = windows_rpc::testing::pair::<OldOnlyServer<_>, _>(Impl)
+        .expect("Failed to create client/server pair");
+    let endpoint = server.endpoint();
+
+    let client = Negotiated::<OldOnlyV11Client, OldOnlyClient>::connect(binding(endpoint))
+        .expect("Failed to create the clients");
+    assert_eq!(client.version(), None);
+    assert!(!client.supports((1, 0)));
+
+    // The server rejects the 1.1 call, so it is made again with 1.0
+    assert_eq!(
+        client
+            .call(|client| client.add(2, 3), |client| client.add(2, 3))
+            .unwrap(),
+        5
+    );
+    assert_eq!(client.version(), Some((1, 0)));
+    assert!(client.supports((1, 0)));
+    assert!(!client.supports((1, 1)));
+
+    // Later calls go straight to 1.0
+    let sum = client
+        .call(
+            |_| panic!("the 1.1 version was rejected"),
+            |client| client.add(1, 1),
+        )
+        .unwrap();
+    assert_eq!(sum, 2);
+
+    let unknown = Negotiated::<OldOnlyV2Client, OldOnlyV11Client>::connect(binding(endpoint))
+        .expect("Failed to create the clients");
+    let error = unknown
+        .call(|client| client.sub(3, 2), |client| client.negate(1))
+        .unwrap_err();
+    assert_eq!(error.status(), RPC_S_UNKNOWN_IF);
+    assert_eq!(unknown.version(), None);
+}
+
+#[test]
+fn test_negotiate_newest() {
+    let (server, _) = windows_rpc::testing::pair::<CurrentV11Server<_>, _>(Impl)
+        .expect("Failed to create client/server pair");
+    let endpoint = server.endpoint();
+
+    let client = Negotiated::<CurrentV11Client, CurrentClient>::connect(binding(endpoint))
+        .expect("Failed to create the clients");
+    let negated = client
+        .call(|client| client.negate(7).map(Some), |_| Ok(None))
+        .unwrap();
+    assert_eq!(negated, Some(-7));
+    assert_eq!(client.version(), Some((1, 1)));
+    assert!(client.supports((1, 0)));
+
+    // Older clients are still accepted by the newer server
+    assert_eq!(CurrentClient::new(binding(endpoint)).add(1, 1).unwrap(), 2);
+}
+
+#[test]
+fn test_negotiate_chain() {
+    let (server, _) = windows_rpc::testing::pair::<OldOnlyServer<_>, _>(Impl)
+        .expect("Failed to create client/server pair");
+
+    // Falls back twice, through the nested chain
+    let client =
+        Negotiated::<OldOnlyV2Client, Negotiated<OldOnlyV11Client, OldOnlyClient>>::connect(
+            binding(server.endpoint()),
+        )
+        .expect("Failed to create the clients");
+    let sum = client
+        .call(
+            |client| client.sub(5, -1),
+            |chain| chain.call(|client| client.add(5, 1), |client| client.add(5, 1)),
+        )
+        .unwrap();
+    assert_eq!(sum, 6);
+    assert_eq!(client.version(), Some((1, 0)));
+}
@@ -0,0 +1,84 @@
+use windows::Win32::System::Rpc::{RPC_S_NOT_LISTENING, RpcMgmtIsServerListening};
+use windows_rpc::rpc_interface;
+use windows_rpc::server_binding::OnDrop;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x4e81c7a3_05bd_4f2e_a97c_62d1e8b0f354), version(1.0))]
+trait Lasting {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0xb3f6a0d9_72e1_4c58_8d4b_a15e9c2f7068), version(1.0))]
+trait Dropped {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0x61d9e2b7_c843_4a0f_b5e6_0f7a3c8d92e1), version(1.0))]
+trait Leaked {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct Impl;
+impl LastingServerImpl for Impl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+impl DroppedServerImpl for Impl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+impl LeakedServerImpl for Impl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+fn binding(endpoint: &str) -> ClientBinding {
+    ClientBinding::new(ProtocolSequence::Alpc, endpoint).expect("Failed to create client binding")
+}
+
+#[test]
+fn test_on_drop() {
+    let (lasting, lasting_client) = windows_rpc::testing::pair::<LastingServer<_>, _>(Impl)
+        .expect("Failed to create client/server pair");
+
+    // Unregistering leaves the other servers listening
+    let mut dropped = DroppedServer::<Impl>::new();
+    dropped.set_on_drop(OnDrop::Unregister);
+    dropped
+        .register("test_on_drop_unregister")
+        .expect("Failed to register server");
+    dropped.listen_async().expect("Failed to start listening");
+    assert_eq!(
+        DroppedClient::new(binding("test_on_drop_unregister"))
+            .add(1, 2)
+            .unwrap(),
+        3
+    );
+    drop(dropped);
+    assert_eq!(lasting_client.add(2, 3).unwrap(), 5);
+
+    // A server that is left registered keeps serving after it is dropped
+    let mut leaked = LeakedServer::<Impl>::new();
+    leaked.set_on_drop(OnDrop::Nothing);
+    leaked
+        .register("test_on_drop_nothing")
+        .expect("Failed to register server");
+    drop(leaked);
+    assert_eq!(
+        LeakedClient::new(binding("test_on_drop_nothing"))
+            .add(3, 4)
+            .unwrap(),
+        7
+    );
+    assert_eq!(lasting_client.add(4, 5).unwrap(), 9);
+
+    // The unregistered server gave back its listener, so the remaining one is the last
+    lasting.stop().expect("Failed to stop server");
+    assert_eq!(
+        unsafe { RpcMgmtIsServerListening(None) },
+        RPC_S_NOT_LISTENING
+    );
+}
@@ -0,0 +1,68 @@
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x6f1d3a84_2c7b_4e95_a8d6_0b4e7c2f9a13), version(1.0))]
+trait OptionRpc {
+    fn add(a: Option<i32>, b: Option<i32>) -> Option<i32>;
+    fn scale(value: Option<f64>, factor: f32) -> f64;
+    fn greet(name: Option<&str>) -> String;
+    fn lookup(id: u32) -> Option<String>;
+}
+
+struct OptionRpcImpl;
+impl OptionRpcServerImpl for OptionRpcImpl {
+    fn add(a: Option<i32>, b: Option<i32>) -> Option<i32> {
+        Some(a? + b?)
+    }
+
+    fn scale(value: Option<f64>, factor: f32) -> f64 {
+        value.unwrap_or(1.0) * f64::from(factor)
+    }
+
+    fn greet(name: Option<&str>) -> String {
+        format!("Hello, {}!", name.unwrap_or("stranger"))
+    }
+
+    fn lookup(id: u32) -> Option<String> {
+        match id {
+            0 => Some(String::new()),
+            1 => Some("one".to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_optional_numbers() {
+    let (_server, client) = windows_rpc::testing::pair::<OptionRpcServer<_>, _>(OptionRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.add(Some(2), Some(40)).unwrap(), Some(42));
+    assert_eq!(client.add(Some(2), None).unwrap(), None);
+    assert_eq!(client.add(None, None).unwrap(), None);
+
+    assert_eq!(client.scale(Some(2.5), 2.0).unwrap(), 5.0);
+    assert_eq!(client.scale(None, 3.0).unwrap(), 3.0);
+}
+
+#[test]
+fn test_optional_strings() {
+    let (_server, client) = windows_rpc::testing::pair::<OptionRpcServer<_>, _>(OptionRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.greet(Some("world")).unwrap(), "Hello, world!");
+    assert_eq!(client.greet(None).unwrap(), "Hello, stranger!");
+
+    // An empty string is not `None`
+    assert_eq!(client.lookup(0).unwrap().as_deref(), Some(""));
+    assert_eq!(client.lookup(1).unwrap().as_deref(), Some("one"));
+    assert_eq!(client.lookup(2).unwrap(), None);
+}
+
+#[test]
+fn test_optional_metadata() {
+    let methods = &OptionRpcClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.rust_type, "Option<i32>");
+    assert_eq!(methods[0].parameters[0].r#type.idl_type, "[unique] long*");
+    assert_eq!(methods[2].parameters[0].r#type.rust_type, "Option<&str>");
+    assert_eq!(methods[3].return_type.unwrap().rust_type, "Option<String>");
+}
@@ -0,0 +1,74 @@
+use std::ffi::{OsStr, OsString};
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x9a4e1f73_28c6_4d0b_b5e9_71d3c08a6f2e), version(1.0))]
+trait Paths {
+    fn wide_len(name: &OsStr) -> u32;
+    fn file_name(path: &Path) -> String;
+    fn extension_len(path: PathBuf) -> u32;
+    fn first_unit(name: OsString) -> u32;
+}
+
+struct PathsImpl;
+impl PathsServerImpl for PathsImpl {
+    fn wide_len(name: &OsStr) -> u32 {
+        name.encode_wide().count() as u32
+    }
+
+    fn file_name(path: &Path) -> String {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    fn extension_len(mut path: PathBuf) -> u32 {
+        path.set_extension("");
+        path.as_os_str().encode_wide().count() as u32
+    }
+
+    fn first_unit(name: OsString) -> u32 {
+        name.encode_wide().next().map_or(0, u32::from)
+    }
+}
+
+#[test]
+fn test_os_strings() {
+    let (_server, client) = windows_rpc::testing::pair::<PathsServer<_>, _>(PathsImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.wide_len(OsStr::new("name")).unwrap(), 4);
+    assert_eq!(client.wide_len("from str").unwrap(), 8);
+    assert_eq!(
+        client
+            .file_name(Path::new(r"C:\Windows\notepad.exe"))
+            .unwrap(),
+        "notepad.exe"
+    );
+    assert_eq!(
+        client.extension_len(PathBuf::from(r"C:\data.txt")).unwrap(),
+        7
+    );
+    assert_eq!(client.first_unit(OsString::from("A")).unwrap(), 0x41);
+
+    // An unpaired surrogate, valid in Windows file names but not in Rust strings, survives
+    // the call
+    let unpaired = OsString::from_wide(&[0xd800, 0x41]);
+    assert_eq!(client.wide_len(&unpaired).unwrap(), 2);
+    assert_eq!(client.first_unit(unpaired).unwrap(), 0xd800);
+}
+
+#[test]
+fn test_os_string_metadata() {
+    let methods = PathsClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.rust_type, "&OsStr");
+    assert_eq!(methods[1].parameters[0].r#type.rust_type, "&Path");
+    assert_eq!(methods[2].parameters[0].r#type.rust_type, "PathBuf");
+    assert_eq!(methods[3].parameters[0].r#type.rust_type, "OsString");
+    assert_eq!(
+        methods[3].parameters[0].r#type.idl_type,
+        "[string] wchar_t*"
+    );
+}
@@ -0,0 +1,92 @@
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x2c7f94e1_5b38_4a0d_9e61_d4b2a8f0c357), version(1.0))]
+trait OutParamsRpc {
+    fn div_mod(a: i32, b: i32, quotient: &mut i32, remainder: &mut i32);
+    fn split(value: u64, high: &mut u32, low: &mut u32) -> u8;
+    fn widths(small: &mut i8, short: &mut u16, long: &mut i64) -> String;
+    fn accumulate(#[in_out] total: &mut u64, amount: u64, #[in_out] count: &mut u32);
+}
+
+struct OutParamsRpcImpl;
+impl OutParamsRpcServerImpl for OutParamsRpcImpl {
+    fn div_mod(a: i32, b: i32, quotient: &mut i32, remainder: &mut i32) {
+        *quotient = a / b;
+        *remainder = a % b;
+    }
+
+    fn split(value: u64, high: &mut u32, low: &mut u32) -> u8 {
+        *high = (value >> 32) as u32;
+        *low = value as u32;
+        2
+    }
+
+    fn widths(small: &mut i8, short: &mut u16, long: &mut i64) -> String {
+        *small = i8::MIN;
+        *short = u16::MAX;
+        *long = i64::MIN;
+        "written".to_string()
+    }
+
+    fn accumulate(total: &mut u64, amount: u64, count: &mut u32) {
+        *total += amount;
+        *count += 1;
+    }
+}
+
+#[test]
+fn test_out_params() {
+    let (_server, client) =
+        windows_rpc::testing::pair::<OutParamsRpcServer<_>, _>(OutParamsRpcImpl)
+            .expect("Failed to create client/server pair");
+
+    let (mut quotient, mut remainder) = (0, 0);
+    client
+        .div_mod(17, 5, &mut quotient, &mut remainder)
+        .unwrap();
+    assert_eq!((quotient, remainder), (3, 2));
+
+    // Initial values are overwritten, not sent
+    let (mut high, mut low) = (u32::MAX, u32::MAX);
+    assert_eq!(
+        client
+            .split(0x1234_5678_9abc_def0, &mut high, &mut low)
+            .unwrap(),
+        2
+    );
+    assert_eq!((high, low), (0x1234_5678, 0x9abc_def0));
+
+    let (mut small, mut short, mut long) = (0, 0, 0);
+    assert_eq!(
+        client.widths(&mut small, &mut short, &mut long).unwrap(),
+        "written"
+    );
+    assert_eq!((small, short, long), (i8::MIN, u16::MAX, i64::MIN));
+}
+
+#[test]
+fn test_in_out_params() {
+    let (_server, client) =
+        windows_rpc::testing::pair::<OutParamsRpcServer<_>, _>(OutParamsRpcImpl)
+            .expect("Failed to create client/server pair");
+
+    // The server receives the current values and the client sees the updated ones
+    let (mut total, mut count) = (100, 0);
+    client.accumulate(&mut total, 20, &mut count).unwrap();
+    client.accumulate(&mut total, 3, &mut count).unwrap();
+    assert_eq!((total, count), (123, 2));
+}
+
+#[test]
+fn test_out_params_metadata() {
+    let method = &OutParamsRpcClient::METADATA.methods[0];
+    let quotient = &method.parameters[2];
+    assert!(!quotient.is_in);
+    assert!(quotient.is_out);
+    assert_eq!(quotient.r#type.rust_type, "&mut i32");
+    assert_eq!(quotient.r#type.idl_type, "long*");
+
+    let total = &OutParamsRpcClient::METADATA.methods[3].parameters[0];
+    assert!(total.is_in);
+    assert!(total.is_out);
+}
@@ -32,7 +32,7 @@ fn test_client_server_integration() {
 
     // Test the methods
     assert_eq!(
-        client.return_string("t e s t"),
+        client.return_string("t e s t").unwrap(),
         "Got t e s t",
         "return_string() should return 'Got t e s t'"
     );
@@ -0,0 +1,76 @@
+// `&String` parameters are what is being tested
+#![allow(clippy::ptr_arg)]
+
+use std::borrow::Cow;
+
+use windows_rpc::rpc_interface;
+use windows_rpc::wstr::U16CString;
+
+#[rpc_interface(guid(0x3c8f5e21_9b4d_4a76_8e03_f6d12a7c4b95), version(1.0))]
+trait Names {
+    fn shout(name: String) -> String;
+    fn count(name: &String) -> u32;
+    fn trim(name: Cow<'_, str>) -> String;
+}
+
+struct NamesImpl;
+impl NamesServerImpl for NamesImpl {
+    fn shout(mut name: String) -> String {
+        name.make_ascii_uppercase();
+        name.push('!');
+        name
+    }
+
+    fn count(name: &String) -> u32 {
+        name.chars().count() as u32
+    }
+
+    fn trim(name: Cow<'_, str>) -> String {
+        // Borrowed from the decoded string
+        assert!(matches!(name, Cow::Borrowed(_)));
+        name.trim().to_string()
+    }
+}
+
+#[test]
+fn test_owned_strings() {
+    let (_server, client) = windows_rpc::testing::pair::<NamesServer<_>, _>(NamesImpl)
+        .expect("Failed to create client/server pair");
+
+    // Taken by value, or by reference like `&str` parameters
+    assert_eq!(client.shout("hello".to_string()).unwrap(), "HELLO!");
+    assert_eq!(client.shout("hello").unwrap(), "HELLO!");
+    assert_eq!(
+        client.shout(U16CString::from_str_truncate("wide")).unwrap(),
+        "WIDE!"
+    );
+    assert_eq!(client.count(&"héllo".to_string()).unwrap(), 5);
+    assert_eq!(client.count("").unwrap(), 0);
+    assert_eq!(client.trim(Cow::Borrowed("  padded ")).unwrap(), "padded");
+    assert_eq!(
+        client.trim(Cow::Owned(" owned".to_string())).unwrap(),
+        "owned"
+    );
+
+    // The client trait takes the declared types
+    let names: &dyn Names = &client;
+    assert_eq!(names.shout("trait".to_string()).unwrap(), "TRAIT!");
+    assert_eq!(names.count(&"trait".to_string()).unwrap(), 5);
+    assert_eq!(names.trim(Cow::Borrowed(" trait ")).unwrap(), "trait");
+}
+
+#[test]
+fn test_owned_string_metadata() {
+    let methods = NamesClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.rust_type, "String");
+    assert_eq!(methods[1].parameters[0].r#type.rust_type, "&String");
+    assert_eq!(methods[2].parameters[0].r#type.rust_type, "Cow<'_, str>");
+    assert_eq!(
+        methods[2].parameters[0].r#type.idl_type,
+        "[string] wchar_t*"
+    );
+    assert!(NamesClient::IDL.contains(
+        "void shout([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out, string] wchar_t** return_value);"
+    ));
+}
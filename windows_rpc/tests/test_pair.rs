@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x3f6b1d8a_24c7_4e95_a0b3_7c1e9d52f6a8), version(1.0))]
+trait TestRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn return_string(param: &str) -> String;
+}
+
+struct TestRpcImpl;
+impl TestRpcServerImpl for TestRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn return_string(param: &str) -> String {
+        format!("Got {param}")
+    }
+}
+
+#[rpc_interface(guid(0x9c2f47e1_5b3a_4d86_b1e0_3a7d6c58f914), version(1.0))]
+trait Visits {
+    fn visit() -> u32;
+}
+
+#[derive(Default)]
+struct VisitsImpl {
+    count: AtomicU32,
+}
+
+impl VisitsServerImpl for VisitsImpl {
+    fn visit() -> u32 {
+        let this = windows_rpc::server::instance::<VisitsImpl>().expect("No instance");
+        this.count.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+#[test]
+fn test_pair() {
+    let (server, client) = windows_rpc::testing::pair::<TestRpcServer<_>, _>(TestRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(
+        client.add(10, 20).unwrap(),
+        30,
+        "add(10, 20) should return 30"
+    );
+    assert_eq!(client.return_string("pair").unwrap(), "Got pair");
+
+    let second = server.client().expect("Failed to create second client");
+    assert_eq!(second.add(1, 2).unwrap(), 3, "add(1, 2) should return 3");
+}
+
+#[test]
+fn test_pair_instance() {
+    let visits = Arc::new(VisitsImpl::default());
+    let (_server, client) =
+        windows_rpc::testing::pair::<VisitsServer<VisitsImpl>, _>(visits.clone())
+            .expect("Failed to create client/server pair");
+
+    assert_eq!(client.visit().unwrap(), 1);
+    assert_eq!(client.visit().unwrap(), 2);
+
+    // The handlers changed the instance the test passed in
+    assert_eq!(visits.count.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_pair_while_listening() {
+    // The process is already listening, so only the new interface's registration tells
+    // that it is ready
+    let (_first, _) = windows_rpc::testing::pair::<TestRpcServer<_>, _>(TestRpcImpl)
+        .expect("Failed to create client/server pair");
+    let (_second, client) = windows_rpc::testing::pair::<VisitsServer<_>, _>(VisitsImpl::default())
+        .expect("Failed to create client/server pair");
+    assert_eq!(client.visit().unwrap(), 1);
+}
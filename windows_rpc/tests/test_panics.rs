@@ -0,0 +1,65 @@
+use windows::Win32::System::Rpc::{RPC_S_ACCESS_DENIED, RPC_S_CALL_FAILED};
+use windows_rpc::executor::SingleThreadExecutor;
+use windows_rpc::rpc_interface;
+
+#[rpc_interface(guid(0x8d2f6a14_c37b_4e59_a0e8_5b19c7d43f62), version(1.0))]
+trait PanicRpc {
+    fn divide(a: i32, b: i32) -> i32;
+    fn greet(name: &str) -> String;
+}
+
+struct PanicRpcImpl;
+impl PanicRpcServerImpl for PanicRpcImpl {
+    fn divide(a: i32, b: i32) -> i32 {
+        a / b
+    }
+
+    fn greet(name: &str) -> String {
+        assert!(!name.is_empty(), "empty name");
+        format!("Hello, {name}!")
+    }
+}
+
+#[test]
+fn test_panic_faults_call() {
+    let (_server, client) = windows_rpc::testing::pair::<PanicRpcServer<_>, _>(PanicRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let error = client
+        .divide(1, 0)
+        .expect_err("a panic should fail the call");
+    assert_eq!(error.status(), RPC_S_CALL_FAILED);
+    let error = client.greet("").expect_err("a panic should fail the call");
+    assert_eq!(error.status(), RPC_S_CALL_FAILED);
+
+    // The server keeps serving
+    assert_eq!(client.divide(6, 3).unwrap(), 2);
+    assert_eq!(client.greet("world").unwrap(), "Hello, world!");
+}
+
+#[test]
+fn test_panic_fault_status() {
+    let (mut server, client) = windows_rpc::testing::pair::<PanicRpcServer<_>, _>(PanicRpcImpl)
+        .expect("Failed to create client/server pair");
+    server.set_panic_fault(RPC_S_ACCESS_DENIED);
+
+    let error = client
+        .divide(1, 0)
+        .expect_err("a panic should fail the call");
+    assert_eq!(error.status(), RPC_S_ACCESS_DENIED);
+}
+
+#[test]
+fn test_panic_on_executor() {
+    let (mut server, client) = windows_rpc::testing::pair::<PanicRpcServer<_>, _>(PanicRpcImpl)
+        .expect("Failed to create client/server pair");
+    server.set_executor(SingleThreadExecutor::new());
+
+    let error = client
+        .divide(1, 0)
+        .expect_err("a panic should fail the call");
+    assert_eq!(error.status(), RPC_S_CALL_FAILED);
+
+    // The executor's thread survives the panic
+    assert_eq!(client.divide(6, 3).unwrap(), 2);
+}
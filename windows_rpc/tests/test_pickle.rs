@@ -0,0 +1,82 @@
+use windows::Win32::Foundation::RPC_X_BAD_STUB_DATA;
+use windows::Win32::System::Rpc::RPC_STATUS;
+use windows_rpc::{NdrStruct, NdrUnion, pickle};
+
+#[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Has padding after `tag` and at the end
+#[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Record {
+    pub tag: u8,
+    pub id: u64,
+    pub flags: u16,
+}
+
+#[derive(NdrUnion, Clone, Copy, Debug, PartialEq)]
+#[repr(C, u32)]
+pub enum Value {
+    Empty,
+    Int(i32),
+    Real(f64),
+}
+
+#[test]
+fn test_pickle_struct() {
+    let bytes = pickle::encode(&Point { x: 1, y: -2 }).unwrap();
+    // The common header (version 1, little-endian, length 8, filler), the private
+    // header with the length of the data, then the data
+    assert_eq!(
+        bytes,
+        [
+            0x01, 0x10, 0x08, 0x00, 0xcc, 0xcc, 0xcc, 0xcc, //
+            0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, //
+            0x01, 0x00, 0x00, 0x00, 0xfe, 0xff, 0xff, 0xff,
+        ]
+    );
+    assert_eq!(
+        pickle::decode::<Point>(&bytes).unwrap(),
+        Point { x: 1, y: -2 }
+    );
+
+    let record = Record {
+        tag: 7,
+        id: u64::MAX - 1,
+        flags: 0x8001,
+    };
+    let bytes = pickle::encode(&record).unwrap();
+    assert_eq!(pickle::decode::<Record>(&bytes).unwrap(), record);
+}
+
+#[test]
+fn test_pickle_union() {
+    for value in [Value::Empty, Value::Int(-42), Value::Real(1.5)] {
+        let bytes = pickle::encode(&value).unwrap();
+        assert_eq!(pickle::decode::<Value>(&bytes).unwrap(), value);
+    }
+}
+
+#[test]
+fn test_decode_malformed() {
+    let bytes = pickle::encode(&Point { x: 1, y: 2 }).unwrap();
+
+    let error = pickle::decode::<Point>(&[]).unwrap_err();
+    assert_eq!(error.code(), RPC_STATUS(RPC_X_BAD_STUB_DATA).to_hresult());
+    let error = pickle::decode::<Point>(&bytes[..bytes.len() - 4]).unwrap_err();
+    assert_eq!(error.code(), RPC_STATUS(RPC_X_BAD_STUB_DATA).to_hresult());
+
+    let mut big_endian = bytes.clone();
+    big_endian[1] = 0x00;
+    assert!(pickle::decode::<Point>(&big_endian).is_err());
+
+    // A discriminant without an arm
+    let mut unknown = pickle::encode(&Value::Int(3)).unwrap();
+    unknown[16] = 9;
+    let error = pickle::decode::<Value>(&unknown).unwrap_err();
+    assert_eq!(error.code(), RPC_STATUS(RPC_X_BAD_STUB_DATA).to_hresult());
+}
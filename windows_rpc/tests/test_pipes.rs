@@ -0,0 +1,147 @@
+use std::io::{Read, Write};
+
+use windows_rpc::pipe::{PipeReader, PipeSink, PipeWriter};
+use windows_rpc::{TransferSyntax, rpc_interface};
+
+#[rpc_interface(guid(0x3f6d1a92_c45e_4b07_9e21_a8c05d7b3e46), version(1.0))]
+trait Storage {
+    fn sum(values: InPipe<u32>) -> u64;
+    fn count(start: i64, count: u32, values: OutPipe<i64>);
+    fn reverse(data: InPipe<u8>, reversed: OutPipe<u8>) -> u32;
+    fn skip(data: InPipe<f64>, skip: u32) -> f64;
+}
+
+struct StorageImpl;
+impl StorageServerImpl for StorageImpl {
+    fn sum(values: &mut PipeReader<'_, u32>) -> u64 {
+        values.flatten().map(u64::from).sum()
+    }
+
+    fn count(start: i64, count: u32, values: &mut PipeWriter<'_, i64>) {
+        // In chunks of various sizes
+        let values_to_send: Vec<i64> = (start..start + i64::from(count)).collect();
+        for (index, chunk) in values_to_send.chunks(1000).enumerate() {
+            values.push(&chunk[..chunk.len().min(index + 1)]);
+            values.push(&chunk[chunk.len().min(index + 1)..]);
+        }
+    }
+
+    fn reverse(data: &mut PipeReader<'_, u8>, reversed: &mut PipeWriter<'_, u8>) -> u32 {
+        let mut received = vec![];
+        data.read_to_end(&mut received).unwrap();
+        received.reverse();
+        reversed.write_all(&received).unwrap();
+        received.len() as u32
+    }
+
+    fn skip(data: &mut PipeReader<'_, f64>, skip: u32) -> f64 {
+        // The stub receives what the handler leaves of the pipe
+        let mut first = [0.0; 1];
+        for _ in 0..skip {
+            data.pull(&mut first);
+        }
+        first[0]
+    }
+}
+
+/// Counts the chunks the client receives
+struct Chunks {
+    values: Vec<i64>,
+    chunks: usize,
+}
+
+impl PipeSink<i64> for Chunks {
+    fn push(&mut self, chunk: &[i64]) {
+        self.values.extend_from_slice(chunk);
+        self.chunks += 1;
+    }
+}
+
+#[test]
+fn test_in_pipe() {
+    let (_server, client) = windows_rpc::testing::pair::<StorageServer<_>, _>(StorageImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.sum(&mut (1..=100_000u32)).unwrap(), 5_000_050_000);
+    assert_eq!(client.sum(&mut std::iter::empty()).unwrap(), 0);
+    let values = [3, 4, 5];
+    assert_eq!(client.sum(&mut values.into_iter()).unwrap(), 12);
+}
+
+#[test]
+fn test_out_pipe() {
+    let (_server, client) = windows_rpc::testing::pair::<StorageServer<_>, _>(StorageImpl)
+        .expect("Failed to create client/server pair");
+
+    let mut values: Vec<i64> = vec![];
+    client.count(-5, 100_000, &mut values).unwrap();
+    assert_eq!(values, (-5..99_995).collect::<Vec<_>>());
+
+    let mut chunks = Chunks {
+        values: vec![],
+        chunks: 0,
+    };
+    client.count(0, 0, &mut chunks).unwrap();
+    assert!(chunks.values.is_empty());
+    client.count(0, 10, &mut chunks).unwrap();
+    assert_eq!(chunks.values, (0..10).collect::<Vec<_>>());
+    assert!(chunks.chunks > 0);
+}
+
+#[test]
+fn test_in_and_out_pipes() {
+    let (_server, client) = windows_rpc::testing::pair::<StorageServer<_>, _>(StorageImpl)
+        .expect("Failed to create client/server pair");
+
+    let data: Vec<u8> = (0..=255).cycle().take(300_000).collect();
+    let mut reversed: Vec<u8> = vec![];
+    let length = client
+        .reverse(&mut data.iter().copied(), &mut reversed)
+        .unwrap();
+    assert_eq!(length, 300_000);
+    assert!(reversed.iter().eq(data.iter().rev()));
+}
+
+#[test]
+fn test_partly_read_pipe() {
+    let (_server, client) = windows_rpc::testing::pair::<StorageServer<_>, _>(StorageImpl)
+        .expect("Failed to create client/server pair");
+
+    let mut data = (0..50_000).map(f64::from);
+    assert_eq!(client.skip(&mut data, 1).unwrap(), 0.0);
+    // The whole pipe was sent, and the next call goes on
+    assert_eq!(data.next(), None);
+    assert_eq!(client.skip(&mut [1.5, 2.5].into_iter(), 0).unwrap(), 0.0);
+}
+
+#[test]
+#[should_panic(expected = "source failed")]
+fn test_source_panic() {
+    let (_server, client) = windows_rpc::testing::pair::<StorageServer<_>, _>(StorageImpl)
+        .expect("Failed to create client/server pair");
+
+    // The panic aborts the call, and is resumed once it returns
+    let mut values = (0..10u32).inspect(|value| assert!(*value < 5, "source failed"));
+    let _ = client.sum(&mut values);
+}
+
+#[test]
+fn test_pipe_syntaxes() {
+    for syntax in [TransferSyntax::Ndr20, TransferSyntax::Ndr64] {
+        let (server, _client) = windows_rpc::testing::pair::<StorageServer<_>, _>(StorageImpl)
+            .expect("Failed to create client/server pair");
+        let client = StorageClient::with_transfer_syntax(
+            windows_rpc::client_binding::ClientBinding::new(
+                windows_rpc::ProtocolSequence::Alpc,
+                server.endpoint(),
+            )
+            .expect("Failed to create client binding"),
+            syntax,
+        );
+        assert_eq!(client.sum(&mut (1..=1000u32)).unwrap(), 500_500);
+        let mut values: Vec<i64> = vec![];
+        client.count(7, 3, &mut values).unwrap();
+        assert_eq!(values, [7, 8, 9]);
+        assert_eq!(client.transfer_syntax(), Some(syntax));
+    }
+}
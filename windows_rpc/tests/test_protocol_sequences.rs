@@ -0,0 +1,23 @@
+use windows_rpc::ProtocolSequence;
+
+#[test]
+fn test_alpc_is_supported() {
+    assert_eq!(ProtocolSequence::Alpc.name(), "ncalrpc");
+    assert!(
+        ProtocolSequence::Alpc
+            .is_supported()
+            .expect("Failed to check the protocol sequence")
+    );
+}
+
+#[test]
+fn test_supported_protocol_sequences() {
+    let protseqs = windows_rpc::supported_protocol_sequences()
+        .expect("Failed to enumerate protocol sequences");
+    for name in &protseqs {
+        assert!(
+            name.starts_with("nca"),
+            "unexpected protocol sequence {name}"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use windows::Win32::System::Rpc::RPC_S_INVALID_BOUND;
+use windows_rpc::rpc_interface;
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x3b7e5d92_c41a_4f68_8e2d_a951c06f3b47), version(1.0))]
+trait Bounded {
+    fn scale(#[rpc(range(1, 10))] factor: u8, value: i32) -> i32;
+    fn offset(#[rpc(range(-100, 100))] delta: i16) -> i32;
+    fn sum(#[rpc(range(0, 4))] values: &[u32], extra: &[u16]) -> u32;
+    fn repeat(#[rpc(range(1, 3))] count: u32, value: u32) -> Vec<u32>;
+}
+
+/// Calls that reached the handlers
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+struct BoundedImpl;
+impl BoundedServerImpl for BoundedImpl {
+    fn scale(factor: u8, value: i32) -> i32 {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        i32::from(factor) * value
+    }
+
+    fn offset(delta: i16) -> i32 {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        1000 + i32::from(delta)
+    }
+
+    fn sum(values: &[u32], extra: &[u16]) -> u32 {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        values.iter().sum::<u32>() + extra.iter().map(|&e| u32::from(e)).sum::<u32>()
+    }
+
+    fn repeat(count: u32, value: u32) -> Vec<u32> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        vec![value; count as usize]
+    }
+}
+
+fn check_ranges(client: &BoundedClient) {
+    let calls = CALLS.load(Ordering::SeqCst);
+
+    // Values within the ranges, bounds included, reach the handlers
+    assert_eq!(client.scale(1, 7).unwrap(), 7);
+    assert_eq!(client.scale(10, 7).unwrap(), 70);
+    assert_eq!(client.offset(-100).unwrap(), 900);
+    assert_eq!(client.offset(100).unwrap(), 1100);
+    assert_eq!(client.sum(&[], &[1]).unwrap(), 1);
+    assert_eq!(client.sum(&[1, 2, 3, 4], &[5; 10]).unwrap(), 60);
+    assert_eq!(client.repeat(3, 9).unwrap(), [9, 9, 9]);
+    assert_eq!(CALLS.load(Ordering::SeqCst), calls + 7);
+
+    // Values outside of them are rejected by the stubs
+    for error in [
+        client.scale(0, 7).unwrap_err(),
+        client.scale(11, 7).unwrap_err(),
+        client.offset(-101).unwrap_err(),
+        client.offset(i16::MAX).unwrap_err(),
+        client.sum(&[1; 5], &[]).unwrap_err(),
+        client.repeat(0, 9).unwrap_err(),
+    ] {
+        assert_eq!(error.status(), RPC_S_INVALID_BOUND);
+    }
+    assert_eq!(
+        CALLS.load(Ordering::SeqCst),
+        calls + 7,
+        "out of range calls should not reach the handlers"
+    );
+}
+
+#[test]
+fn test_range() {
+    let (server, client) = windows_rpc::testing::pair::<BoundedServer<_>, _>(BoundedImpl)
+        .expect("Failed to create client/server pair");
+    check_ranges(&client);
+
+    // NDR 2.0 checks ranges with its own descriptors
+    let client = BoundedClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_ranges(&client);
+}
@@ -0,0 +1,53 @@
+use windows::Win32::System::Rpc::{RPC_IF_ALLOW_LOCAL_ONLY, RPC_IF_ALLOW_SECURE_ONLY};
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0xa7c25e90_1b4d_4f38_8d6a_e05f93b2c71d), version(1.0))]
+trait FlaggedRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct FlaggedRpcImpl;
+impl FlaggedRpcServerImpl for FlaggedRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_registration_flags() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut server = FlaggedRpcServer::<FlaggedRpcImpl>::new();
+    server.set_registration_flags(RPC_IF_ALLOW_LOCAL_ONLY);
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    // Local callers are allowed
+    let client = FlaggedRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    server.stop().expect("Failed to stop server");
+
+    // Unauthenticated callers are not
+    let endpoint = windows_rpc::testing::unique_endpoint();
+    let mut server = FlaggedRpcServer::<FlaggedRpcImpl>::new();
+    server.set_registration_flags(RPC_IF_ALLOW_LOCAL_ONLY | RPC_IF_ALLOW_SECURE_ONLY);
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = FlaggedRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert!(client.add(1, 2).is_err());
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,39 @@
+use windows::Win32::System::Rpc::RPC_S_ACCESS_DENIED;
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x5c2f8b06_d93e_4a71_b4c8_0e6a2d9f31b7), version(1.0))]
+trait PrivateRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct PrivateRpcImpl;
+impl PrivateRpcServerImpl for PrivateRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_unencrypted_calls_rejected() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut server = PrivateRpcServer::<PrivateRpcImpl>::new();
+    server.set_require_encryption(true);
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    // The binding carries no authentication information
+    let client = PrivateRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    let error = client
+        .add(1, 2)
+        .expect_err("an unencrypted call should be rejected");
+    assert_eq!(error.status(), RPC_S_ACCESS_DENIED);
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use windows::Win32::System::Rpc::{
+    RPC_S_CALL_FAILED, RPC_S_INVALID_STRING_BINDING, RPC_S_SERVER_UNAVAILABLE,
+};
+use windows_rpc::retry::{ResilientClient, RetryPolicy, RobustClient};
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0x6f3a9c24_85d1_4e7b_a2c8_1d94e05b7f36), version(1.0))]
+trait RetryRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn fail() -> i32;
+}
+
+static FAILED_CALLS: AtomicU32 = AtomicU32::new(0);
+
+struct RetryRpcImpl;
+impl RetryRpcServerImpl for RetryRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn fail() -> i32 {
+        FAILED_CALLS.fetch_add(1, Ordering::SeqCst);
+        panic!("the handler fails");
+    }
+}
+
+fn policy(attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        attempts,
+        initial_backoff: Duration::from_millis(50),
+        multiplier: 1,
+        ..RetryPolicy::default()
+    }
+}
+
+fn client(endpoint: &str, attempts: u32) -> ResilientClient<RetryRpcClient> {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+        .expect("Failed to create client binding");
+    ResilientClient::new(RetryRpcClient::new(binding), policy(attempts))
+}
+
+#[test]
+fn test_retry_until_server_listens() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+    let client = client(&endpoint, 100);
+
+    // The first attempts find no server
+    let call = thread::spawn(move || client.call(|client| client.add(1, 2)));
+    thread::sleep(Duration::from_millis(200));
+
+    let mut server = RetryRpcServer::<RetryRpcImpl>::new();
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let result = call.join().expect("the calling thread panicked");
+    assert_eq!(result.expect("the call should succeed once retried"), 3);
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_retry_gives_up() {
+    let mut attempts = 0;
+    let client = client("test_retry_no_such_endpoint", 3);
+
+    let error = client
+        .call(|client| {
+            attempts += 1;
+            client.add(1, 2)
+        })
+        .expect_err("calling a missing endpoint should fail");
+    assert_eq!(error.status(), RPC_S_SERVER_UNAVAILABLE);
+    assert_eq!(attempts, 3);
+
+    assert_eq!(RetryPolicy::never().attempts, 1);
+    assert_eq!(policy(3).backoff(5), Duration::from_millis(50));
+}
+
+#[test]
+fn test_faults_are_not_retried() {
+    let (server, _) = windows_rpc::testing::pair::<RetryRpcServer<_>, _>(RetryRpcImpl)
+        .expect("Failed to create client/server pair");
+    let client = client(server.endpoint(), 3);
+
+    let error = client
+        .call(|client| client.fail())
+        .expect_err("a faulting call should fail");
+    assert_eq!(error.status(), RPC_S_CALL_FAILED);
+    assert_eq!(FAILED_CALLS.load(Ordering::SeqCst), 1);
+}
+
+fn robust_client(
+    endpoint: &str,
+    attempts: u32,
+    connects: &Arc<AtomicU32>,
+) -> RobustClient<RetryRpcClient> {
+    let endpoint = endpoint.to_string();
+    let connects = connects.clone();
+    RobustClient::new(policy(attempts), move || {
+        connects.fetch_add(1, Ordering::SeqCst);
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+    })
+    .expect("Failed to create client")
+}
+
+#[test]
+fn test_reconnect_until_server_listens() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+    let connects = Arc::new(AtomicU32::new(0));
+    let client = robust_client(&endpoint, 100, &connects);
+
+    let call = thread::spawn(move || client.call(|client| client.add(2, 3)));
+    thread::sleep(Duration::from_millis(200));
+
+    let mut server = RetryRpcServer::<RetryRpcImpl>::new();
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let result = call.join().expect("the calling thread panicked");
+    assert_eq!(result.expect("the call should succeed once reconnected"), 5);
+    // Every failed attempt was followed by a new binding
+    assert!(connects.load(Ordering::SeqCst) > 1);
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_reconnect_gives_up() {
+    let connects = Arc::new(AtomicU32::new(0));
+    let client = robust_client("test_retry_no_such_endpoint", 3, &connects);
+
+    let error = client
+        .call(|client| client.add(1, 2))
+        .expect_err("calling a missing endpoint should fail");
+    assert_eq!(error.status(), RPC_S_SERVER_UNAVAILABLE);
+    assert_eq!(connects.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_reconnect_failure() {
+    let connects = Arc::new(AtomicU32::new(0));
+    let counter = connects.clone();
+    let client = RobustClient::<RetryRpcClient>::new(policy(3), move || {
+        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+            ClientBinding::new(ProtocolSequence::Alpc, "test_retry_no_such_endpoint")
+        } else {
+            ClientBinding::from_string_binding("not a string binding")
+        }
+    })
+    .expect("Failed to create client");
+
+    let error = client
+        .call(|client| client.add(1, 2))
+        .expect_err("reconnecting should fail");
+    assert_eq!(error.status(), RPC_S_INVALID_STRING_BINDING);
+    assert_eq!(connects.load(Ordering::SeqCst), 2);
+}
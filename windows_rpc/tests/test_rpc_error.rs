@@ -0,0 +1,97 @@
+use windows::Win32::Foundation::{E_ACCESSDENIED, ERROR_ACCESS_DENIED};
+use windows::Win32::System::Rpc::{RPC_S_SERVER_UNAVAILABLE, RPC_S_UNKNOWN_IF, RPC_STATUS};
+use windows_rpc::negotiate::Negotiated;
+use windows_rpc::{ProtocolSequence, RpcError, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0xd2f84a61_7b3e_4c09_95a1_6e0c3b8f2d17), version(1.0))]
+trait Unreachable {
+    fn ping();
+}
+
+#[rpc_interface(guid(0x5c3e91a7_28d4_4b6f_9e05_a7b1d84c6f29), version(1.0))]
+trait Reachable {
+    fn ping();
+}
+
+struct ReachableImpl;
+impl ReachableServerImpl for ReachableImpl {
+    fn ping() {}
+}
+
+#[test]
+fn test_rpc_error_on_missing_server() {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "test_rpc_error_no_such_endpoint")
+        .expect("Failed to create client binding");
+
+    let client = Negotiated::<UnreachableClient, UnreachableClient>::connect(binding)
+        .expect("Failed to create the clients");
+    let error = client
+        .call(|client| client.ping(), |client| client.ping())
+        .expect_err("negotiating with a missing endpoint should fail");
+    assert_eq!(error.status(), RPC_S_SERVER_UNAVAILABLE);
+    assert_eq!(error.code(), RPC_S_SERVER_UNAVAILABLE.to_hresult());
+
+    // Every record of the chain is rendered after the status
+    let message = error.to_string();
+    assert!(message.contains(&RPC_S_SERVER_UNAVAILABLE.0.to_string()));
+    assert_eq!(message.lines().count(), 1 + error.extended_info().len());
+
+    let converted: windows::core::Error = error.into();
+    assert_eq!(converted.code(), RPC_S_SERVER_UNAVAILABLE.to_hresult());
+}
+
+#[test]
+fn test_call_fails_on_missing_server() {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "test_rpc_error_no_such_endpoint")
+        .expect("Failed to create client binding");
+
+    let error = UnreachableClient::new(binding)
+        .ping()
+        .expect_err("calling a missing endpoint should fail");
+    assert_eq!(error.status(), RPC_S_SERVER_UNAVAILABLE);
+
+    // The runtime records extended error information for local RPC by default
+    assert!(!error.extended_info().is_empty());
+    let origin = error.origin().unwrap();
+    assert_eq!(origin, error.extended_info().last().unwrap());
+    assert_eq!(origin.process_id, std::process::id());
+    assert!(origin.computer_name.is_none());
+}
+
+#[test]
+fn test_stale_extended_info() {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "test_rpc_error_no_such_endpoint")
+        .expect("Failed to create client binding");
+    let error = UnreachableClient::new(binding)
+        .ping()
+        .expect_err("calling a missing endpoint should fail");
+    assert!(!error.extended_info().is_empty());
+
+    // Errors unrelated to the failed call don't carry its extended error information
+    let unrelated = RpcError::from(windows::core::Error::from_hresult(E_ACCESSDENIED));
+    assert_eq!(unrelated.status(), RPC_STATUS(ERROR_ACCESS_DENIED.0 as i32));
+    assert!(unrelated.extended_info().is_empty());
+    // and it was cleared from the thread when the call's error captured it
+    assert!(
+        RpcError::from_status(RPC_S_SERVER_UNAVAILABLE)
+            .extended_info()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_call_fails_on_unknown_interface() {
+    let (server, client) = windows_rpc::testing::pair::<ReachableServer<_>, _>(ReachableImpl)
+        .expect("Failed to create client/server pair");
+    client
+        .ping()
+        .expect("the served interface should be reachable");
+
+    // The endpoint is listening, but does not serve this interface
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+        .expect("Failed to create client binding");
+    let error = UnreachableClient::new(binding)
+        .ping()
+        .expect_err("calling an unknown interface should fail");
+    assert_eq!(error.status(), RPC_S_UNKNOWN_IF);
+}
@@ -0,0 +1,63 @@
+use std::ffi::c_void;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use windows::Win32::System::Rpc::RPC_S_ACCESS_DENIED;
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x3f8a1d6c_57e2_4b09_9c34_a1e7d05b82f6), version(1.0))]
+trait GuardedRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct GuardedRpcImpl;
+impl GuardedRpcServerImpl for GuardedRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_security_callback() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+    let checks = Arc::new(AtomicU32::new(0));
+    let locked = Arc::new(AtomicBool::new(false));
+
+    let mut server = GuardedRpcServer::<GuardedRpcImpl>::new();
+    server.set_security_callback({
+        let checks = checks.clone();
+        let locked = locked.clone();
+        move |interface: u128, binding: *mut c_void| {
+            assert_eq!(interface, GuardedRpcServer::<GuardedRpcImpl>::GUID);
+            assert!(!binding.is_null());
+            checks.fetch_add(1, Ordering::Relaxed);
+            if locked.load(Ordering::Relaxed) {
+                return Err(RPC_S_ACCESS_DENIED);
+            }
+            Ok(())
+        }
+    });
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = GuardedRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert!(checks.load(Ordering::Relaxed) > 0);
+
+    locked.store(true, Ordering::Relaxed);
+    let error = client
+        .add(1, 2)
+        .expect_err("the callback should reject the call");
+    assert_eq!(error.status(), RPC_S_ACCESS_DENIED);
+
+    locked.store(false, Ordering::Relaxed);
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,43 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::security::SecurityDescriptor;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x6c1d94e2_7a3f_4b58_9d06_e2f8a15c3b79), version(1.0))]
+trait SecuredRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct SecuredRpcImpl;
+impl SecuredRpcServerImpl for SecuredRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_interface_security_descriptor() {
+    let endpoint = "test_security_descriptor";
+
+    let mut server = SecuredRpcServer::<SecuredRpcImpl>::new();
+    // Allow everyone
+    server.set_security_descriptor(Some(
+        SecurityDescriptor::from_sddl("D:(A;;GA;;;WD)").expect("Failed to parse SDDL"),
+    ));
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = SecuredRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(2, 3).unwrap(), 5);
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_invalid_sddl() {
+    assert!(SecurityDescriptor::from_sddl("not a security descriptor").is_err());
+}
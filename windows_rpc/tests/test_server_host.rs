@@ -0,0 +1,74 @@
+use windows::Win32::System::Rpc::{RPC_S_ALREADY_LISTENING, RPC_S_NO_INTERFACES};
+use windows_rpc::host::RpcServerHost;
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x61d3b8e4_2a7c_4f95_b0e6_d84c17a39f52), version(1.0))]
+trait HostedCalculator {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+#[rpc_interface(guid(0xe2a95c08_7b14_4d3e_9f61_3c8b06d7a4e1), version(1.0))]
+trait HostedGreeter {
+    fn greet(name: &str) -> String;
+}
+
+struct HostedCalculatorImpl;
+impl HostedCalculatorServerImpl for HostedCalculatorImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+struct HostedGreeterImpl;
+impl HostedGreeterServerImpl for HostedGreeterImpl {
+    fn greet(name: &str) -> String {
+        format!("Hello, {name}!")
+    }
+}
+
+#[test]
+fn test_server_host() {
+    let endpoint = windows_rpc::testing::unique_endpoint();
+
+    let mut host = RpcServerHost::new(ProtocolSequence::Alpc, &endpoint);
+    host.add(HostedCalculatorServer::<HostedCalculatorImpl>::new())
+        .expect("Failed to add server");
+    host.add(HostedGreeterServer::<HostedGreeterImpl>::new())
+        .expect("Failed to add server");
+    assert_eq!(host.len(), 2);
+    assert_eq!(host.endpoint(), endpoint);
+    match host.listen_async() {
+        // Another test in this process already started listening
+        Err(e) if e.code() == RPC_S_ALREADY_LISTENING.to_hresult() => {}
+        result => result.expect("Failed to start listening"),
+    }
+
+    let calculator = HostedCalculatorClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    let greeter = HostedGreeterClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(calculator.add(1, 2).unwrap(), 3);
+    assert_eq!(greeter.greet("host").unwrap(), "Hello, host!");
+
+    let server = host
+        .get::<HostedGreeterServer<HostedGreeterImpl>>()
+        .expect("The greeter should be hosted");
+    assert_eq!(server.endpoints(), std::slice::from_ref(&endpoint));
+
+    host.stop().expect("Failed to stop host");
+}
+
+#[test]
+fn test_empty_host() {
+    let host = RpcServerHost::new(ProtocolSequence::Alpc, "test_empty_host");
+    assert!(host.is_empty());
+    let error = host
+        .listen_async()
+        .expect_err("an empty host has nothing to serve");
+    assert_eq!(error.code(), RPC_S_NO_INTERFACES.to_hresult());
+}
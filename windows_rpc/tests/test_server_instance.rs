@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use windows::Win32::System::Rpc::RPC_S_ALREADY_LISTENING;
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x7d2e9b41_a6c3_4f85_9b10_e4c8f2a736d5), version(1.0))]
+trait Counter {
+    fn increment() -> u32;
+}
+
+#[derive(Default)]
+struct CounterImpl {
+    count: AtomicU32,
+}
+
+impl CounterServerImpl for CounterImpl {
+    fn increment() -> u32 {
+        let this = windows_rpc::server::instance::<CounterImpl>().expect("No instance");
+        this.count.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+#[rpc_interface(guid(0x4b8c1e27_93d5_4a6f_8e02_c7a5d9f1b364), version(1.0))]
+trait Tally {
+    fn add(value: u32) -> u32;
+}
+
+#[derive(Default)]
+struct TallyImpl {
+    total: AtomicU32,
+}
+
+impl TallyServerImpl for TallyImpl {
+    fn add(value: u32) -> u32 {
+        let this = windows_rpc::server::instance::<TallyImpl>().expect("No instance");
+        this.total.fetch_add(value, Ordering::Relaxed) + value
+    }
+}
+
+#[test]
+fn test_shared_instance() {
+    let endpoint = "test_server_instance";
+    let counter = Arc::new(CounterImpl::default());
+
+    let mut server = CounterServer::with_instance(counter.clone());
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = CounterClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.increment().unwrap(), 1);
+    assert_eq!(client.increment().unwrap(), 2);
+
+    // The application sees the state the handlers changed
+    assert_eq!(counter.count.load(Ordering::Relaxed), 2);
+    assert_eq!(Arc::strong_count(&counter), 2);
+
+    server.stop().expect("Failed to stop server");
+}
+
+#[test]
+fn test_concurrent_servers() {
+    let counter = Arc::new(CounterImpl::default());
+    let tally = Arc::new(TallyImpl::default());
+    let counter_endpoint = windows_rpc::testing::unique_endpoint();
+    let tally_endpoint = windows_rpc::testing::unique_endpoint();
+
+    // Each server dispatches to its own instance, whatever else the process serves
+    let mut counter_server = CounterServer::with_instance(counter.clone());
+    counter_server
+        .register(&counter_endpoint)
+        .expect("Failed to register server");
+    let mut tally_server = TallyServer::with_instance(tally.clone());
+    tally_server
+        .register(&tally_endpoint)
+        .expect("Failed to register server");
+    for result in [counter_server.listen_async(), tally_server.listen_async()] {
+        match result {
+            // Listening is process-wide
+            Err(e) if e.code() == RPC_S_ALREADY_LISTENING.to_hresult() => {}
+            result => result.expect("Failed to start listening"),
+        }
+    }
+
+    let counter_client = CounterClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &counter_endpoint)
+            .expect("Failed to create client binding"),
+    );
+    let tally_client = TallyClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, &tally_endpoint)
+            .expect("Failed to create client binding"),
+    );
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..50 {
+                counter_client.increment().unwrap();
+            }
+        });
+        scope.spawn(|| {
+            for _ in 0..50 {
+                tally_client.add(2).unwrap();
+            }
+        });
+    });
+    assert_eq!(counter.count.load(Ordering::Relaxed), 50);
+    assert_eq!(tally.total.load(Ordering::Relaxed), 100);
+}
+
+#[test]
+fn test_no_instance_outside_call() {
+    assert!(windows_rpc::server::instance::<CounterImpl>().is_none());
+}
@@ -0,0 +1,47 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::server_binding::ServerOptions;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x8e36b1d9_f4a2_47c0_a5b3_19d6e8c07f24), version(1.0))]
+trait LimitedRpc {
+    fn length(text: &str) -> u32;
+}
+
+struct LimitedRpcImpl;
+impl LimitedRpcServerImpl for LimitedRpcImpl {
+    fn length(text: &str) -> u32 {
+        text.len() as u32
+    }
+}
+
+#[test]
+fn test_server_options() {
+    assert_eq!(ServerOptions::default().min_threads, 1);
+    assert_eq!(ServerOptions::default().max_rpc_size, u32::MAX);
+
+    let endpoint = format!(r"\pipe\{}", windows_rpc::testing::unique_endpoint());
+    let mut server = LimitedRpcServer::<LimitedRpcImpl>::new();
+    server.set_protocol_sequence(ProtocolSequence::NamedPipe);
+    server.set_server_options(ServerOptions {
+        min_threads: 2,
+        max_calls: 4,
+        max_rpc_size: 4096,
+    });
+    server
+        .register(&endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = LimitedRpcClient::new(
+        ClientBinding::new(ProtocolSequence::NamedPipe, &endpoint)
+            .expect("Failed to create client binding"),
+    );
+    assert_eq!(client.length("small").unwrap(), 5);
+
+    // Requests over the size limit are rejected
+    let large = "x".repeat(64 * 1024);
+    assert!(client.length(&large).is_err());
+    assert_eq!(client.length("small again").unwrap(), 11);
+
+    server.stop().expect("Failed to stop server");
+}
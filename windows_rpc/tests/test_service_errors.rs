@@ -0,0 +1,142 @@
+use windows::Win32::Foundation::{ERROR_ACCESS_DENIED, ERROR_FILE_NOT_FOUND};
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+use windows_rpc::{NdrEnum, ServiceError, rpc_interface};
+
+#[derive(NdrEnum, Clone, Copy, Debug, PartialEq)]
+#[repr(u32)]
+pub enum LookupError {
+    NotFound = 1,
+    Denied,
+}
+
+#[rpc_interface(
+    guid(0x2e6b9d41_7c03_4f85_a91e_5d48c07b3f26),
+    version(1.0),
+    enums(LookupError)
+)]
+trait Files {
+    fn size(name: &str) -> Result<u64, u32>;
+    fn read(name: &str) -> Result<String, LookupError>;
+    fn list(count: u32) -> Result<Vec<u32>, u32>;
+    fn delete(name: &str) -> Result<(), u32>;
+}
+
+struct FilesImpl;
+impl FilesServerImpl for FilesImpl {
+    fn size(name: &str) -> Result<u64, u32> {
+        match name {
+            "big" => Ok(u64::MAX),
+            _ => Err(ERROR_FILE_NOT_FOUND.0),
+        }
+    }
+
+    fn read(name: &str) -> Result<String, LookupError> {
+        match name {
+            "readme" => Ok("hello".to_string()),
+            "secret" => Err(LookupError::Denied),
+            _ => Err(LookupError::NotFound),
+        }
+    }
+
+    fn list(count: u32) -> Result<Vec<u32>, u32> {
+        if count > 3 {
+            return Err(ERROR_ACCESS_DENIED.0);
+        }
+        Ok((0..count).collect())
+    }
+
+    fn delete(name: &str) -> Result<(), u32> {
+        if name.is_empty() {
+            return Err(ERROR_FILE_NOT_FOUND.0);
+        }
+        Ok(())
+    }
+}
+
+fn check_files(client: &FilesClient) {
+    assert_eq!(client.size("big").unwrap(), u64::MAX);
+    assert_eq!(
+        client.size("missing").unwrap_err(),
+        ServiceError::Service(ERROR_FILE_NOT_FOUND.0)
+    );
+
+    // Enums are sent as the values of their variants
+    assert_eq!(client.read("readme").unwrap(), "hello");
+    assert_eq!(
+        client.read("secret").unwrap_err(),
+        ServiceError::Service(LookupError::Denied)
+    );
+    assert_eq!(
+        client.read("other").unwrap_err().service_error(),
+        Some(&LookupError::NotFound)
+    );
+
+    assert_eq!(client.list(3).unwrap(), [0, 1, 2]);
+    assert_eq!(
+        client.list(4).unwrap_err(),
+        ServiceError::Service(ERROR_ACCESS_DENIED.0)
+    );
+
+    client.delete("file").unwrap();
+    assert_eq!(
+        client.delete("").unwrap_err(),
+        ServiceError::Service(ERROR_FILE_NOT_FOUND.0)
+    );
+}
+
+#[test]
+fn test_service_errors() {
+    let (server, client) = windows_rpc::testing::pair::<FilesServer<_>, _>(FilesImpl)
+        .expect("Failed to create client/server pair");
+    check_files(&client);
+
+    let client = FilesClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_files(&client);
+
+    // The client trait returns the same errors
+    let files: &dyn Files = &client;
+    assert_eq!(
+        files.size("missing").unwrap_err(),
+        ServiceError::Service(ERROR_FILE_NOT_FOUND.0)
+    );
+}
+
+#[test]
+fn test_failed_calls() {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "test_service_errors_no_endpoint")
+        .expect("Failed to create client binding");
+    let error = FilesClient::new(binding).size("big").unwrap_err();
+    let ServiceError::Rpc(rpc_error) = &error else {
+        panic!("calling a missing endpoint should fail the call");
+    };
+    assert_eq!(
+        rpc_error.status(),
+        windows::Win32::System::Rpc::RPC_S_SERVER_UNAVAILABLE
+    );
+    assert_eq!(error.service_error(), None);
+    assert!(std::error::Error::source(&error).is_some());
+}
+
+#[test]
+fn test_service_error_metadata() {
+    let method = &FilesClient::METADATA.methods[1];
+    assert_eq!(method.return_type.unwrap().rust_type, "String");
+    let error_type = method.error_type.unwrap();
+    assert_eq!(error_type.rust_type, "LookupError");
+    assert_eq!(error_type.idl_type, "unsigned long");
+    assert!(FilesClient::METADATA.methods[0].error_type.is_some());
+
+    // The error code precedes the return value
+    assert!(FilesClient::IDL.contains(
+        "hyper size([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out] unsigned long* service_error);"
+    ));
+    assert!(FilesClient::IDL.contains(
+        "void read([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out] unsigned long* service_error, [out, string] wchar_t** return_value);"
+    ));
+}
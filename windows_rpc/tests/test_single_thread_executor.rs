@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use windows_rpc::executor::SingleThreadExecutor;
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x0c9d4b2e_71a5_4f38_8e16_d3b7a2c95f04), version(1.0))]
+trait ThreadRpc {
+    fn thread_id() -> u64;
+}
+
+struct ThreadRpcImpl;
+impl ThreadRpcServerImpl for ThreadRpcImpl {
+    fn thread_id() -> u64 {
+        thread_local! {
+            static ID: u64 = NEXT_ID.fetch_add(1, Ordering::SeqCst) as u64;
+        }
+        ID.with(|id| *id)
+    }
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[test]
+fn test_single_thread_executor() {
+    let endpoint = "test_endpoint_single_thread";
+
+    let mut server = ThreadRpcServer::<ThreadRpcImpl>::new();
+    server.set_executor(SingleThreadExecutor::new());
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let first = ThreadRpcClient::new(
+        ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+            .expect("Failed to create client binding"),
+    );
+    let expected = first.thread_id().unwrap();
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            std::thread::spawn(move || {
+                let client = ThreadRpcClient::new(
+                    ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+                        .expect("Failed to create client binding"),
+                );
+                client.thread_id().unwrap()
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(
+            handle.join().unwrap(),
+            expected,
+            "All calls should run on the same worker thread"
+        );
+    }
+
+    server.stop().expect("Failed to stop server");
+}
@@ -0,0 +1,127 @@
+use windows::Win32::System::Rpc::RPC_S_INVALID_BOUND;
+use windows_rpc::rpc_interface;
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x6d2f8a41_93c7_4e05_b1a8_3c5e07d4f962), version(1.0))]
+trait Buffers {
+    fn read(first: u8, #[rpc(size_is(length))] buffer: &mut [u8], length: u32) -> u32;
+    fn write(count: u32, #[rpc(size_is(count))] values: &[u16]) -> u32;
+    fn fill(value: i32, values: &mut [i32]);
+    fn twice(
+        #[in_out]
+        #[rpc(size_is(count))]
+        values: &mut [f64],
+        count: u32,
+    );
+    fn bounded(#[rpc(size_is(count))] values: &mut [u32], #[rpc(range(0, 4))] count: u32);
+}
+
+struct BuffersImpl;
+impl BuffersServerImpl for BuffersImpl {
+    fn read(first: u8, buffer: &mut [u8], length: u32) -> u32 {
+        assert_eq!(buffer.len(), length as usize);
+        assert!(
+            buffer.iter().all(|&b| b == 0),
+            "out buffers should be zeroed"
+        );
+        for (i, byte) in buffer.iter_mut().enumerate() {
+            *byte = first + i as u8;
+        }
+        length
+    }
+
+    fn write(count: u32, values: &[u16]) -> u32 {
+        assert_eq!(values.len(), count as usize);
+        values.iter().map(|&v| u32::from(v)).sum()
+    }
+
+    fn fill(value: i32, values: &mut [i32]) {
+        values.fill(value);
+    }
+
+    fn twice(values: &mut [f64], count: u32) {
+        assert_eq!(values.len(), count as usize);
+        for value in values {
+            *value *= 2.0;
+        }
+    }
+
+    fn bounded(values: &mut [u32], _count: u32) {
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = i as u32;
+        }
+    }
+}
+
+fn check_buffers(client: &BuffersClient) {
+    // Only the elements the length gives are written
+    let mut buffer = [0xff; 8];
+    assert_eq!(client.read(10, &mut buffer, 4).unwrap(), 4);
+    assert_eq!(buffer, [10, 11, 12, 13, 0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(client.read(10, &mut buffer, 0).unwrap(), 0);
+    assert_eq!(client.read(0, &mut buffer, 8).unwrap(), 8);
+    assert_eq!(buffer, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    // Likewise, only that many are sent
+    assert_eq!(client.write(2, &[1, 2, 3]).unwrap(), 3);
+    assert_eq!(client.write(0, &[]).unwrap(), 0);
+
+    // Slices without a `size_is` are sized by their hidden length
+    let mut values = [0; 3];
+    client.fill(7, &mut values).unwrap();
+    assert_eq!(values, [7, 7, 7]);
+    client.fill(7, &mut []).unwrap();
+
+    let mut values = [1.5, 2.0, 3.0];
+    client.twice(&mut values, 2).unwrap();
+    assert_eq!(values, [3.0, 4.0, 3.0]);
+
+    let mut values = [9; 8];
+    client.bounded(&mut values, 4).unwrap();
+    assert_eq!(values, [0, 1, 2, 3, 9, 9, 9, 9]);
+
+    // Lengths beyond the slice are rejected by the client, and out of their range by the
+    // server
+    let mut buffer = [0xff; 8];
+    for error in [
+        client.read(10, &mut buffer, 9).unwrap_err(),
+        client.write(4, &[1, 2, 3]).unwrap_err(),
+        client.twice(&mut [1.0], 2).unwrap_err(),
+        client.bounded(&mut [0; 8], 5).unwrap_err(),
+    ] {
+        assert_eq!(error.status(), RPC_S_INVALID_BOUND);
+    }
+    assert_eq!(buffer, [0xff; 8]);
+}
+
+#[test]
+fn test_sized_buffers() {
+    let (server, client) = windows_rpc::testing::pair::<BuffersServer<_>, _>(BuffersImpl)
+        .expect("Failed to create client/server pair");
+    check_buffers(&client);
+
+    // NDR 2.0 correlates the arrays with their lengths in its own descriptors
+    let client = BuffersClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_buffers(&client);
+}
+
+#[test]
+fn test_sized_buffer_metadata() {
+    let methods = BuffersClient::METADATA.methods;
+    let buffer = &methods[0].parameters[1];
+    assert!(buffer.is_out && !buffer.is_in);
+    assert_eq!(buffer.r#type.rust_type, "&mut [u8]");
+    assert_eq!(buffer.r#type.idl_type, "[size_is(length)] byte*");
+    assert_eq!(
+        methods[2].parameters[1].r#type.idl_type,
+        "[size_is(length)] long*"
+    );
+    assert!(BuffersClient::IDL.contains(
+        "void twice([in] handle_t binding, [in, out, size_is(count)] double* values, \
+         [in] unsigned long count);"
+    ));
+}
@@ -0,0 +1,70 @@
+use windows::Win32::System::Rpc::RPC_S_INVALID_STRING_BINDING;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0xa3e7c05b_6d19_4f42_8b7a_2c9e51f0d846), version(1.0))]
+trait ParseRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct ParseRpcImpl;
+impl ParseRpcServerImpl for ParseRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+#[test]
+fn test_composed_string_binding() {
+    let binding = ClientBinding::new(ProtocolSequence::Alpc, "calculator")
+        .expect("Failed to create client binding");
+    assert_eq!(binding.string_binding(), "ncalrpc:[calculator]");
+    let copy = binding.try_clone().expect("Failed to copy binding");
+    assert_eq!(copy.string_binding(), "ncalrpc:[calculator]");
+
+    let binding =
+        ClientBinding::with_server(ProtocolSequence::NamedPipe, "10.0.0.5", r"\pipe\calculator")
+            .expect("Failed to create client binding");
+    assert!(
+        binding.string_binding().starts_with("ncacn_np:10.0.0.5["),
+        "unexpected string binding {}",
+        binding.string_binding()
+    );
+
+    let binding = ClientBinding::with_server(
+        ProtocolSequence::NamedPipe,
+        "fileserver",
+        r"\pipe\calculator",
+    )
+    .expect("Failed to create client binding");
+    assert!(binding.string_binding().starts_with("ncacn_np:fileserver["));
+}
+
+#[test]
+fn test_from_string_binding() {
+    let (server, _client) = windows_rpc::testing::pair::<ParseRpcServer<_>, _>(ParseRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let string_binding = format!("ncalrpc:[{}]", server.endpoint());
+    let binding =
+        ClientBinding::from_string_binding(&string_binding).expect("Failed to parse binding");
+    assert_eq!(binding.string_binding(), string_binding);
+
+    let rendered = binding
+        .to_string_binding()
+        .expect("Failed to render binding");
+    assert_eq!(rendered, string_binding);
+
+    // The rendered form round-trips
+    let client = ParseRpcClient::new(
+        ClientBinding::from_string_binding(&rendered).expect("Failed to parse binding"),
+    );
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+}
+
+#[test]
+fn test_invalid_string_binding() {
+    let error = ClientBinding::from_string_binding("not a binding")
+        .err()
+        .expect("a malformed string binding should be rejected");
+    assert_eq!(error.code(), RPC_S_INVALID_STRING_BINDING.to_hresult());
+}
@@ -0,0 +1,121 @@
+use windows_rpc::rpc_interface;
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x8d2e4a17_5b6c_4f93_a1e8_3c7f90b2d564), version(1.0))]
+trait Directory {
+    fn names(count: u32) -> Vec<String>;
+    fn split(text: &str) -> Vec<String>;
+    fn partition(text: &str, shorter: &mut Vec<String>, longer: &mut Vec<String>) -> u32;
+    fn find(prefix: &str, names: &mut Vec<String>) -> Result<(), u32>;
+}
+
+struct DirectoryImpl;
+impl DirectoryServerImpl for DirectoryImpl {
+    fn names(count: u32) -> Vec<String> {
+        (0..count).map(|i| format!("name{i}")).collect()
+    }
+
+    fn split(text: &str) -> Vec<String> {
+        text.split(',').map(str::to_string).collect()
+    }
+
+    fn partition(text: &str, shorter: &mut Vec<String>, longer: &mut Vec<String>) -> u32 {
+        for word in text.split(',') {
+            if word.chars().count() < 4 {
+                shorter.push(word.to_string());
+            } else {
+                longer.push(word.to_string());
+            }
+        }
+        (shorter.len() + longer.len()) as u32
+    }
+
+    fn find(prefix: &str, names: &mut Vec<String>) -> Result<(), u32> {
+        names.extend(["alpha", "beta"].map(str::to_string));
+        // The names written before the error aren't sent
+        if prefix.is_empty() {
+            return Err(87);
+        }
+        names.retain(|name| name.starts_with(prefix));
+        Ok(())
+    }
+}
+
+fn check_directory(client: &DirectoryClient) {
+    assert_eq!(client.names(3).unwrap(), ["name0", "name1", "name2"]);
+    assert_eq!(
+        client.names(0).unwrap(),
+        [""; 0],
+        "empty vectors should be supported"
+    );
+    let many = client.names(1000).unwrap();
+    assert_eq!(many.len(), 1000);
+    assert_eq!(many[999], "name999");
+
+    // Empty and non-ASCII strings
+    assert_eq!(
+        client.split("a,,gädget,日本").unwrap(),
+        ["a", "", "gädget", "日本"]
+    );
+    assert_eq!(client.split("").unwrap(), [""]);
+
+    // Out parameters replace the caller's vectors
+    let mut shorter = vec!["stale".to_string()];
+    let mut longer = vec![];
+    assert_eq!(
+        client
+            .partition("a,gädget,日本,longer", &mut shorter, &mut longer)
+            .unwrap(),
+        4
+    );
+    assert_eq!(shorter, ["a", "日本"]);
+    assert_eq!(longer, ["gädget", "longer"]);
+    assert_eq!(
+        client.partition("word", &mut shorter, &mut longer).unwrap(),
+        1
+    );
+    assert!(shorter.is_empty(), "empty vectors should be supported");
+    assert_eq!(longer, ["word"]);
+
+    let mut names = vec![];
+    client.find("b", &mut names).unwrap();
+    assert_eq!(names, ["beta"]);
+    assert!(matches!(
+        client.find("", &mut names),
+        Err(windows_rpc::ServiceError::Service(87))
+    ));
+    assert!(names.is_empty());
+}
+
+#[test]
+fn test_string_vec() {
+    let (server, client) = windows_rpc::testing::pair::<DirectoryServer<_>, _>(DirectoryImpl)
+        .expect("Failed to create client/server pair");
+    check_directory(&client);
+
+    let client = DirectoryClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_directory(&client);
+}
+
+#[test]
+fn test_string_vec_metadata() {
+    let return_type = DirectoryClient::METADATA.methods[0].return_type.unwrap();
+    assert_eq!(return_type.rust_type, "Vec<String>");
+    assert_eq!(return_type.idl_type, "[size_is(, *length)] LPWSTR**");
+    assert!(DirectoryClient::IDL.contains(
+        "void names([in] handle_t binding, [in] unsigned long count, \
+         [out] unsigned long* return_value_length, \
+         [out, size_is(, *return_value_length)] LPWSTR** return_value);"
+    ));
+
+    let parameter = &DirectoryClient::METADATA.methods[2].parameters[1];
+    assert_eq!(parameter.r#type.rust_type, "&mut Vec<String>");
+    assert_eq!(parameter.r#type.idl_type, "[size_is(, *length)] LPWSTR**");
+    assert!(DirectoryClient::IDL.contains(
+        "[out] unsigned long* shorter_length, [out, size_is(, *shorter_length)] LPWSTR** shorter, "
+    ));
+}
@@ -0,0 +1,213 @@
+use windows::core::{PCSTR, PCWSTR, s, w};
+use windows_rpc::ndr::NdrPointee;
+use windows_rpc::wstr::U16CString;
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+use windows_rpc::{NdrStruct, RpcTransmittable, rpc_interface};
+
+/// Has a wide string, an array sized by `count` and an ANSI string
+#[derive(NdrStruct, Clone, Copy, Debug)]
+#[repr(C)]
+pub struct Entry {
+    pub id: u32,
+    pub name: PCWSTR,
+    pub count: u32,
+    #[ndr(size_is(count))]
+    pub values: *const u16,
+    pub label: PCSTR,
+}
+
+/// Owns its strings and array, sent as `RecordWire`
+#[derive(NdrStruct, Clone, Debug, PartialEq)]
+pub struct Record {
+    pub id: u32,
+    #[ndr(string)]
+    pub name: Vec<u16>,
+    pub count: u32,
+    #[ndr(size_is(count))]
+    pub values: Vec<u16>,
+    #[ndr(string)]
+    pub label: Vec<u8>,
+}
+
+#[rpc_interface(
+    guid(0x6f1b2d84_3c97_4e5a_b02f_8d4a71c9e316),
+    version(1.0),
+    transmit_as(Record = RecordWire)
+)]
+trait Catalog {
+    fn describe(entry: &Entry) -> String;
+    fn total(entry: &Entry, scale: u32) -> u32;
+    fn describe_record(record: Record) -> String;
+}
+
+/// Borrows the array of an entry received by the server
+fn values(entry: &Entry) -> &[u16] {
+    if entry.values.is_null() {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(entry.values, entry.count as usize) }
+    }
+}
+
+struct CatalogImpl;
+impl CatalogServerImpl for CatalogImpl {
+    fn describe(entry: &Entry) -> String {
+        let name = if entry.name.is_null() {
+            "(none)".to_string()
+        } else {
+            unsafe { entry.name.to_string() }.unwrap()
+        };
+        let label = if entry.label.is_null() {
+            "(none)".to_string()
+        } else {
+            unsafe { entry.label.to_string() }.unwrap()
+        };
+        format!("{}: {name} [{label}] {:?}", entry.id, values(entry))
+    }
+
+    fn total(entry: &Entry, scale: u32) -> u32 {
+        values(entry)
+            .iter()
+            .map(|&value| u32::from(value))
+            .sum::<u32>()
+            * scale
+    }
+
+    fn describe_record(record: Record) -> String {
+        let name = String::from_utf16_lossy(&record.name);
+        let label = String::from_utf8_lossy(&record.label);
+        format!(
+            "{}: {name:?} [{label:?}] {:?} of {}",
+            record.id, record.values, record.count
+        )
+    }
+}
+
+fn check_catalog(client: &CatalogClient) {
+    let values = [1u16, 2, 3, 400];
+    let entry = Entry {
+        id: 7,
+        name: w!("widget"),
+        count: values.len() as u32,
+        values: values.as_ptr(),
+        label: s!("tag"),
+    };
+    assert_eq!(
+        client.describe(&entry).unwrap(),
+        "7: widget [tag] [1, 2, 3, 400]"
+    );
+    assert_eq!(client.total(&entry, 2).unwrap(), 812);
+
+    // Strings built at runtime, and only part of the array
+    let name = U16CString::from_str_truncate("gädget");
+    let entry = Entry {
+        name: name.as_pcwstr(),
+        count: 2,
+        ..entry
+    };
+    assert_eq!(client.describe(&entry).unwrap(), "7: gädget [tag] [1, 2]");
+
+    // The pointers are unique pointers, which may be null
+    let entry = Entry {
+        id: 8,
+        name: PCWSTR::null(),
+        count: 0,
+        values: std::ptr::null(),
+        label: PCSTR::null(),
+    };
+    assert_eq!(client.describe(&entry).unwrap(), "8: (none) [(none)] []");
+    assert_eq!(client.total(&entry, 2).unwrap(), 0);
+
+    // Strings arrive with their NUL, and the count is taken from the array
+    let record = Record {
+        id: 9,
+        name: "gädget\0".encode_utf16().collect(),
+        count: 0,
+        values: vec![5, 6, 700],
+        label: b"tag\0".to_vec(),
+    };
+    assert_eq!(
+        client.describe_record(record).unwrap(),
+        "9: \"gädget\\0\" [\"tag\\0\"] [5, 6, 700] of 3"
+    );
+    // Empty strings are sent as null pointers, and empty arrays with no elements
+    let record = Record {
+        id: 10,
+        name: vec![],
+        count: 0,
+        values: vec![],
+        label: vec![],
+    };
+    assert_eq!(
+        client.describe_record(record).unwrap(),
+        "10: \"\" [\"\"] [] of 0"
+    );
+}
+
+#[test]
+fn test_struct_pointers() {
+    let (server, client) = windows_rpc::testing::pair::<CatalogServer<_>, _>(CatalogImpl)
+        .expect("Failed to create client/server pair");
+    check_catalog(&client);
+
+    let client = CatalogClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_catalog(&client);
+}
+
+#[test]
+fn test_struct_pointer_formats() {
+    // FC_BOGUS_STRUCT, alignment - 1, memory size, no conformant array, offset to the
+    // pointer layout, then the members with FC_POINTER for the pointers
+    assert_eq!(
+        Entry::NDR_FORMAT[..16],
+        [
+            0x1a, 7, 40, 0, 0, 0, 10, 0, 0x08, 0x40, 0x36, 0x08, 0x40, 0x36, 0x36, 0x5b
+        ]
+    );
+    // FC64_PSTRUCT, with the pointer layout
+    assert_eq!(Entry::NDR64_FORMAT.FormatCode, 0x31);
+    assert_eq!(Entry::NDR64_FORMAT.MemorySize, 40);
+    assert_eq!(
+        Entry::NDR64_POINTERS,
+        [
+            (8, NdrPointee::WideString),
+            (
+                24,
+                NdrPointee::Array {
+                    format_char: 0x04,
+                    element_size: 2,
+                    size_offset: 16,
+                }
+            ),
+            (32, NdrPointee::AnsiString),
+        ]
+    );
+
+    // The wire struct of `Record` is laid out like `Entry`
+    assert_eq!(RecordWire::NDR_FORMAT, Entry::NDR_FORMAT);
+    assert_eq!(RecordWire::NDR64_POINTERS, Entry::NDR64_POINTERS);
+    let record = Record {
+        id: 1,
+        name: vec![0x61, 0],
+        count: 0,
+        values: vec![2, 3],
+        label: vec![0],
+    };
+    let wire = record.to_wire();
+    assert_eq!(wire.count, 2);
+    assert_eq!(
+        Record::from_wire(wire),
+        Record {
+            count: 2,
+            ..record.clone()
+        }
+    );
+
+    let method = &CatalogClient::METADATA.methods[0];
+    assert_eq!(method.parameters[0].r#type.rust_type, "&Entry");
+    assert_eq!(method.parameters[0].r#type.idl_type, "Entry*");
+}
@@ -0,0 +1,124 @@
+use windows_rpc::{NdrStruct, rpc_interface};
+
+#[derive(NdrStruct, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Has padding after `tag` and at the end
+#[derive(NdrStruct, Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Record {
+    pub tag: u8,
+    pub id: u64,
+    pub flags: u16,
+}
+
+#[rpc_interface(guid(0x8e2d5a17_c4f3_4b69_a0d8_3f71e6b92c45), version(1.0))]
+trait StructRpc {
+    fn translate(point: &Point, dx: i32, dy: i32) -> Point;
+    fn bounds(a: &Point, b: &Point, min: &mut Point, max: &mut Point);
+    fn bump(#[in_out] record: &mut Record) -> u64;
+    fn describe(record: &Record, label: &str) -> String;
+}
+
+struct StructRpcImpl;
+impl StructRpcServerImpl for StructRpcImpl {
+    fn translate(point: &Point, dx: i32, dy: i32) -> Point {
+        Point {
+            x: point.x + dx,
+            y: point.y + dy,
+        }
+    }
+
+    fn bounds(a: &Point, b: &Point, min: &mut Point, max: &mut Point) {
+        *min = Point {
+            x: a.x.min(b.x),
+            y: a.y.min(b.y),
+        };
+        *max = Point {
+            x: a.x.max(b.x),
+            y: a.y.max(b.y),
+        };
+    }
+
+    fn bump(record: &mut Record) -> u64 {
+        record.id += 1;
+        record.flags |= 0x8000;
+        record.id
+    }
+
+    fn describe(record: &Record, label: &str) -> String {
+        format!("{label}: {} {} {:#x}", record.tag, record.id, record.flags)
+    }
+}
+
+#[test]
+fn test_struct_params() {
+    let (_server, client) = windows_rpc::testing::pair::<StructRpcServer<_>, _>(StructRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(
+        client.translate(&Point { x: 1, y: 2 }, 10, -20).unwrap(),
+        Point { x: 11, y: -18 }
+    );
+
+    let (mut min, mut max) = Default::default();
+    client
+        .bounds(
+            &Point { x: 5, y: -1 },
+            &Point { x: -3, y: 8 },
+            &mut min,
+            &mut max,
+        )
+        .unwrap();
+    assert_eq!(min, Point { x: -3, y: -1 });
+    assert_eq!(max, Point { x: 5, y: 8 });
+}
+
+#[test]
+fn test_padded_struct() {
+    let (_server, client) = windows_rpc::testing::pair::<StructRpcServer<_>, _>(StructRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let mut record = Record {
+        tag: 7,
+        id: u64::MAX - 1,
+        flags: 1,
+    };
+    assert_eq!(client.bump(&mut record).unwrap(), u64::MAX);
+    assert_eq!(
+        record,
+        Record {
+            tag: 7,
+            id: u64::MAX,
+            flags: 0x8001,
+        }
+    );
+
+    assert_eq!(
+        client.describe(&record, "rec").unwrap(),
+        "rec: 7 18446744073709551615 0x8001"
+    );
+}
+
+#[test]
+fn test_struct_formats() {
+    // FC_STRUCT, alignment - 1, memory size, FC_ULONG, FC_ULONG, FC_PAD, FC_END, as
+    // `i32`s are described like parameters
+    assert_eq!(Point::NDR_FORMAT, [0x15, 3, 8, 0, 0x09, 0x09, 0x5c, 0x5b]);
+    // FC_BYTE, FC_STRUCTPAD7, FC_HYPER, FC_USHORT, FC_STRUCTPAD6, FC_END
+    assert_eq!(
+        Record::NDR_FORMAT,
+        [0x15, 7, 24, 0, 0x01, 0x43, 0x0b, 0x06, 0x42, 0x5b]
+    );
+    assert_eq!(Record::NDR64_FORMAT.MemorySize, 24);
+    assert_eq!(Record::NDR64_FORMAT.Alignment, 7);
+
+    let method = &StructRpcClient::METADATA.methods[0];
+    assert_eq!(method.parameters[0].r#type.rust_type, "&Point");
+    assert_eq!(method.parameters[0].r#type.idl_type, "Point*");
+    assert_eq!(method.return_type.unwrap().rust_type, "Point");
+}
@@ -0,0 +1,79 @@
+use std::os::windows::io::{AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle};
+
+use windows::Win32::Foundation::{HANDLE, WAIT_OBJECT_0};
+use windows::Win32::System::Threading::{CreateEventW, SetEvent, WaitForSingleObject};
+use windows_rpc::rpc_interface;
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x8e2f4a17_c35b_4d90_b6e1_3a7c9d05f2b8), version(1.0))]
+trait Events {
+    // EVENT_MODIFY_STATE is all the server needs to signal the event
+    fn signal(#[rpc(system_handle(event, 0x0002))] event: BorrowedHandle<'_>);
+    #[rpc(system_handle(event))]
+    fn create(signaled: u32) -> OwnedHandle;
+}
+
+fn create_event(signaled: bool) -> OwnedHandle {
+    let event =
+        unsafe { CreateEventW(None, true, signaled, None) }.expect("Failed to create event");
+    unsafe { OwnedHandle::from_raw_handle(event.0) }
+}
+
+fn is_signaled(event: &impl AsRawHandle) -> bool {
+    let status = unsafe { WaitForSingleObject(HANDLE(event.as_raw_handle()), 0) };
+    status == WAIT_OBJECT_0
+}
+
+struct EventsImpl;
+impl EventsServerImpl for EventsImpl {
+    fn signal(event: BorrowedHandle<'_>) {
+        unsafe { SetEvent(HANDLE(event.as_raw_handle())) }.expect("Failed to signal event");
+    }
+
+    fn create(signaled: u32) -> OwnedHandle {
+        create_event(signaled != 0)
+    }
+}
+
+fn check_events(client: &EventsClient) {
+    let event = create_event(false);
+    client.signal(event.as_handle()).unwrap();
+    // The server signaled its duplicate of the event, and closed it
+    assert!(is_signaled(&event));
+
+    let event = client.create(1).unwrap();
+    assert!(is_signaled(&event));
+    assert!(!is_signaled(&client.create(0).unwrap()));
+}
+
+#[test]
+fn test_system_handles() {
+    let (server, client) = windows_rpc::testing::pair::<EventsServer<_>, _>(EventsImpl)
+        .expect("Failed to create client/server pair");
+    check_events(&client);
+
+    let client = EventsClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_events(&client);
+}
+
+#[test]
+fn test_system_handle_metadata() {
+    let methods = EventsClient::METADATA.methods;
+    assert_eq!(
+        methods[0].parameters[0].r#type.rust_type,
+        "BorrowedHandle<'_>"
+    );
+    assert_eq!(
+        methods[0].parameters[0].r#type.idl_type,
+        "[system_handle(sh_event, 0x2)] HANDLE"
+    );
+    assert_eq!(methods[1].return_type.unwrap().rust_type, "OwnedHandle");
+    assert!(EventsClient::IDL.contains(
+        "void create([in] handle_t binding, [in] unsigned long signaled, \
+         [out, system_handle(sh_event)] HANDLE* return_value);"
+    ));
+}
@@ -0,0 +1,82 @@
+use windows_rpc::{ProtocolSequence, TransferSyntax, client_binding::ClientBinding, rpc_interface};
+
+#[rpc_interface(guid(0x9e2c4a71_5b3d_4f80_8c6e_1a7d3b9f2e54), version(1.0))]
+trait TestRpc {
+    fn add(a: i32, b: i32) -> i32;
+    fn return_string(param: &str) -> String;
+    fn syntax() -> u32;
+}
+
+struct TestRpcImpl;
+impl TestRpcServerImpl for TestRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn return_string(param: &str) -> String {
+        format!("Got {param}")
+    }
+
+    fn syntax() -> u32 {
+        match windows_rpc::server::transfer_syntax() {
+            Some(TransferSyntax::Ndr20) => 20,
+            Some(TransferSyntax::Ndr64) => 64,
+            None => 0,
+        }
+    }
+}
+
+#[test]
+fn test_negotiated_transfer_syntax() {
+    let (_server, client) = windows_rpc::testing::pair::<TestRpcServer<_>, _>(TestRpcImpl)
+        .expect("Failed to create client/server pair");
+    assert_eq!(windows_rpc::server::transfer_syntax(), None);
+    assert_eq!(client.transfer_syntax(), None);
+
+    let expected = if cfg!(target_pointer_width = "64") {
+        64
+    } else {
+        20
+    };
+    assert_eq!(client.syntax().unwrap(), expected);
+
+    // The client records the syntax the runtime negotiated for its calls
+    let negotiated = if cfg!(target_pointer_width = "64") {
+        TransferSyntax::Ndr64
+    } else {
+        TransferSyntax::Ndr20
+    };
+    assert_eq!(client.transfer_syntax(), Some(negotiated));
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+    assert_eq!(client.transfer_syntax(), Some(negotiated));
+}
+
+#[test]
+fn test_chosen_transfer_syntax() {
+    let (server, _client) = windows_rpc::testing::pair::<TestRpcServer<_>, _>(TestRpcImpl)
+        .expect("Failed to create client/server pair");
+    let client = |syntax| {
+        TestRpcClient::with_transfer_syntax(
+            ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+                .expect("Failed to create client binding"),
+            syntax,
+        )
+    };
+    let ndr20 = client(TransferSyntax::Ndr20);
+    let ndr64 = client(TransferSyntax::Ndr64);
+    assert_eq!(ndr20.transfer_syntax(), Some(TransferSyntax::Ndr20));
+    assert_eq!(ndr64.transfer_syntax(), Some(TransferSyntax::Ndr64));
+
+    // Calls of both syntaxes at the same time each see their own
+    std::thread::scope(|scope| {
+        for (client, expected) in [(&ndr20, 20), (&ndr64, 64)] {
+            scope.spawn(move || {
+                for i in 0..100 {
+                    assert_eq!(client.syntax().unwrap(), expected);
+                    assert_eq!(client.add(i, 1).unwrap(), i + 1);
+                    assert_eq!(client.return_string("x").unwrap(), "Got x");
+                }
+            });
+        }
+    });
+}
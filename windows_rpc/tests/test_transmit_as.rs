@@ -0,0 +1,114 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+use windows_rpc::{NdrStruct, RpcTransmittable, rpc_interface};
+
+#[derive(NdrStruct, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct Bounds {
+    pub start: u32,
+    pub end: u32,
+}
+
+/// A type that isn't `Copy`, sent as `Bounds`
+#[derive(Debug, PartialEq)]
+pub struct Interval(Range<u32>);
+
+impl RpcTransmittable<Bounds> for Interval {
+    fn to_wire(&self) -> Bounds {
+        Bounds {
+            start: self.0.start,
+            end: self.0.end,
+        }
+    }
+
+    fn from_wire(wire: Bounds) -> Self {
+        Interval(wire.start..wire.end)
+    }
+}
+
+#[rpc_interface(
+    guid(0x5d3a9c81_2f6e_4b17_a8d4_0e9b7c2f1a63),
+    version(1.0),
+    transmit_as(Duration = u64, Interval = Bounds)
+)]
+trait Scheduler {
+    fn delay(timeout: Duration, factor: u32) -> Duration;
+    fn widen(interval: Interval, by: u32) -> Interval;
+    fn length(interval: Interval, scale: &Bounds) -> u32;
+}
+
+struct SchedulerImpl;
+impl SchedulerServerImpl for SchedulerImpl {
+    fn delay(timeout: Duration, factor: u32) -> Duration {
+        timeout * factor
+    }
+
+    fn widen(interval: Interval, by: u32) -> Interval {
+        Interval(interval.0.start - by..interval.0.end + by)
+    }
+
+    fn length(interval: Interval, scale: &Bounds) -> u32 {
+        interval.0.len() as u32 * (scale.end - scale.start)
+    }
+}
+
+fn check_scheduler(client: &SchedulerClient) {
+    assert_eq!(
+        client.delay(Duration::from_millis(1500), 3).unwrap(),
+        Duration::from_millis(4500)
+    );
+    // Durations are sent in 100-nanosecond intervals
+    assert_eq!(
+        client.delay(Duration::new(2, 250), 2).unwrap(),
+        Duration::new(4, 400)
+    );
+    assert_eq!(client.widen(Interval(10..20), 5).unwrap(), Interval(5..25));
+    assert_eq!(
+        client
+            .length(Interval(3..7), &Bounds { start: 1, end: 3 })
+            .unwrap(),
+        8
+    );
+}
+
+#[test]
+fn test_transmit_as() {
+    let (server, client) = windows_rpc::testing::pair::<SchedulerServer<_>, _>(SchedulerImpl)
+        .expect("Failed to create client/server pair");
+    check_scheduler(&client);
+
+    let client = SchedulerClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_scheduler(&client);
+}
+
+#[test]
+fn test_duration_wire() {
+    assert_eq!(Duration::from_secs(1).to_wire(), 10_000_000);
+    assert_eq!(Duration::from_nanos(199).to_wire(), 1);
+    assert_eq!(Duration::MAX.to_wire(), u64::MAX);
+    assert_eq!(
+        Duration::from_wire(12_345_678),
+        Duration::new(1, 234_567_800)
+    );
+}
+
+#[test]
+fn test_transmit_as_metadata() {
+    let methods = SchedulerClient::METADATA.methods;
+    assert_eq!(methods[0].parameters[0].r#type.rust_type, "Duration");
+    assert_eq!(methods[0].parameters[0].r#type.idl_type, "unsigned hyper");
+    assert_eq!(methods[0].return_type.unwrap().rust_type, "Duration");
+    assert_eq!(methods[1].parameters[0].r#type.rust_type, "Interval");
+    assert_eq!(methods[1].parameters[0].r#type.idl_type, "Bounds*");
+    assert_eq!(methods[1].return_type.unwrap().idl_type, "Bounds");
+    assert!(SchedulerClient::IDL.contains(
+        "unsigned hyper delay([in] handle_t binding, [in] unsigned hyper timeout, \
+         [in] unsigned long factor);"
+    ));
+}
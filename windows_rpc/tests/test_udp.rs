@@ -0,0 +1,85 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use windows::Win32::System::Rpc::RPC_S_PROTSEQ_NOT_SUPPORTED;
+use windows_rpc::client_binding::ClientBinding;
+use windows_rpc::{ProtocolSequence, rpc_interface};
+
+#[rpc_interface(guid(0x3a7d1e94_b25c_4f68_8c03_d6e91f5a2b47), version(1.0))]
+trait Discovery {
+    fn add(a: i32, b: i32) -> i32;
+    #[rpc(broadcast)]
+    fn announce() -> u32;
+}
+
+/// Whether this process's server answered a broadcast
+static ANNOUNCED: AtomicBool = AtomicBool::new(false);
+
+struct DiscoveryImpl;
+impl DiscoveryServerImpl for DiscoveryImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    fn announce() -> u32 {
+        ANNOUNCED.store(true, Ordering::SeqCst);
+        std::process::id()
+    }
+}
+
+/// A UDP port no other socket of the machine is bound to
+fn free_port() -> String {
+    let socket = std::net::UdpSocket::bind("0.0.0.0:0").expect("Failed to bind a UDP socket");
+    socket
+        .local_addr()
+        .expect("Failed to get the socket address")
+        .port()
+        .to_string()
+}
+
+#[test]
+fn test_udp_support() {
+    assert_eq!(ProtocolSequence::Udp.name(), "ncadg_ip_udp");
+
+    // Servers can only register the protocol sequence where the runtime supports it
+    let supported = ProtocolSequence::Udp
+        .is_supported()
+        .expect("Failed to check the protocol sequence");
+    let mut server = DiscoveryServer::<DiscoveryImpl>::new();
+    server.set_protocol_sequence(ProtocolSequence::Udp);
+    match server.register(&free_port()) {
+        Ok(()) => assert!(supported, "registered an unsupported protocol sequence"),
+        Err(error) => {
+            assert!(!supported, "failed to register over UDP: {error}");
+            assert_eq!(error.code(), RPC_S_PROTSEQ_NOT_SUPPORTED.to_hresult());
+        }
+    }
+}
+
+#[test]
+#[ignore = "ncadg_ip_udp is not supported since Windows Vista, run where datagram RPC is"]
+fn test_udp() {
+    let port = free_port();
+    let mut server = DiscoveryServer::<DiscoveryImpl>::new();
+    server.set_protocol_sequence(ProtocolSequence::Udp);
+    server.register(&port).expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    let client = DiscoveryClient::new(
+        ClientBinding::new(ProtocolSequence::Udp, &port).expect("Failed to create client binding"),
+    );
+    assert_eq!(client.add(1, 2).unwrap(), 3);
+
+    // Other servers of the network may answer first, but this one receives the broadcast
+    client.announce().expect("Failed to broadcast");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !ANNOUNCED.load(Ordering::SeqCst) {
+        assert!(
+            Instant::now() < deadline,
+            "the local server didn't receive the broadcast"
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    server.stop().expect("Failed to stop server");
+}
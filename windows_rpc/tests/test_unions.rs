@@ -0,0 +1,93 @@
+use windows_rpc::{NdrUnion, rpc_interface};
+
+#[derive(NdrUnion, Clone, Copy, Debug, PartialEq)]
+#[repr(C, u32)]
+pub enum Value {
+    Empty,
+    Int(i32),
+    Real(f64),
+}
+
+/// Has explicit discriminants and a payload narrower than the discriminant
+#[derive(NdrUnion, Clone, Copy, Debug, PartialEq)]
+#[repr(C, u16)]
+pub enum Small {
+    Byte(u8) = 5,
+    Short(i16),
+    Nothing = 100,
+}
+
+#[rpc_interface(guid(0x4b7e9c12_5d3a_4f86_b1e0_92c6a8d47f35), version(1.0))]
+trait UnionRpc {
+    fn negate(value: &Value) -> Value;
+    fn widen(small: &Small) -> Value;
+    fn describe(value: &Value, label: &str) -> String;
+}
+
+struct UnionRpcImpl;
+impl UnionRpcServerImpl for UnionRpcImpl {
+    fn negate(value: &Value) -> Value {
+        match *value {
+            Value::Empty => Value::Empty,
+            Value::Int(int) => Value::Int(-int),
+            Value::Real(real) => Value::Real(-real),
+        }
+    }
+
+    fn widen(small: &Small) -> Value {
+        match *small {
+            Small::Byte(byte) => Value::Int(byte.into()),
+            Small::Short(short) => Value::Int(short.into()),
+            Small::Nothing => Value::Empty,
+        }
+    }
+
+    fn describe(value: &Value, label: &str) -> String {
+        format!("{label}: {value:?}")
+    }
+}
+
+#[test]
+fn test_union_params() {
+    let (_server, client) = windows_rpc::testing::pair::<UnionRpcServer<_>, _>(UnionRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    assert_eq!(client.negate(&Value::Empty).unwrap(), Value::Empty);
+    assert_eq!(client.negate(&Value::Int(42)).unwrap(), Value::Int(-42));
+    assert_eq!(client.negate(&Value::Real(1.5)).unwrap(), Value::Real(-1.5));
+
+    assert_eq!(client.widen(&Small::Byte(200)).unwrap(), Value::Int(200));
+    assert_eq!(client.widen(&Small::Short(-7)).unwrap(), Value::Int(-7));
+    assert_eq!(client.widen(&Small::Nothing).unwrap(), Value::Empty);
+
+    assert_eq!(
+        client.describe(&Value::Int(3), "value").unwrap(),
+        "value: Int(3)"
+    );
+}
+
+#[test]
+fn test_union_formats() {
+    // FC_ENCAPSULATED_UNION, increment 8 and `u32` discriminant, memory size, 3 arms,
+    // then each discriminant and arm (empty, `i32` and `f64`), and no default
+    assert_eq!(
+        Value::NDR_FORMAT,
+        [
+            0x2a, 0x88, 8, 0, 3, 0, //
+            0, 0, 0, 0, 0, 0, //
+            1, 0, 0, 0, 0x09, 0x80, //
+            2, 0, 0, 0, 0x0c, 0x80, //
+            0xff, 0xff,
+        ]
+    );
+    // The arms start right after the 16-bit discriminant
+    assert_eq!(Small::NDR_FORMAT[1], 0x26);
+    assert_eq!(&Small::NDR_FORMAT[2..4], [2, 0]);
+    assert_eq!(Value::NDR64_FORMAT.MemoryOffset, 8);
+    assert_eq!(Value::NDR64_FORMAT.MemorySize, 16);
+    assert_eq!(Value::NDR64_FORMAT.Alignment, 7);
+    assert_eq!(
+        Small::NDR64_ARMS,
+        [(5, Some(0x10)), (6, Some(0x04)), (100, None)]
+    );
+}
@@ -0,0 +1,103 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use windows::Win32::System::Rpc::RpcMgmtStopServerListening;
+use windows_rpc::rpc_interface;
+use windows_rpc::{ProtocolSequence, client_binding::ClientBinding};
+
+#[rpc_interface(guid(0x8a4f26d1_3e9b_4c70_a5d8_f21b67e94c03), version(1.0))]
+trait WaitRpc {
+    fn add(a: i32, b: i32) -> i32;
+}
+
+struct WaitRpcImpl;
+impl WaitRpcServerImpl for WaitRpcImpl {
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+}
+
+/// Serializes the tests that start and stop the process's listening
+static LISTENING: Mutex<()> = Mutex::new(());
+
+/// Wakes the thread blocked in `block_on`
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+/// Makes a call to the server on `endpoint` from another thread, then stops the server
+fn stop_after_call(endpoint: &'static str) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let client = WaitRpcClient::new(
+            ClientBinding::new(ProtocolSequence::Alpc, endpoint)
+                .expect("Failed to create client binding"),
+        );
+        assert_eq!(client.add(2, 3).unwrap(), 5);
+        unsafe { RpcMgmtStopServerListening(None) }
+            .ok()
+            .expect("Failed to stop server");
+    })
+}
+
+#[test]
+fn test_wait_until_stopped() {
+    let _listening = LISTENING.lock().unwrap();
+    let endpoint = "test_wait_until_stopped";
+
+    let mut server = WaitRpcServer::<WaitRpcImpl>::new();
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    // Another component makes a call, then stops the server
+    let stopper = stop_after_call(endpoint);
+
+    server
+        .wait_until_stopped()
+        .expect("Failed to wait for the server");
+    stopper.join().expect("Client thread panicked");
+}
+
+#[test]
+fn test_wait_until_stopped_async() {
+    let _listening = LISTENING.lock().unwrap();
+    let endpoint = "test_wait_until_stopped_async";
+
+    let mut server = WaitRpcServer::<WaitRpcImpl>::new();
+    server
+        .register(endpoint)
+        .expect("Failed to register server");
+    server.listen_async().expect("Failed to start listening");
+
+    // The wait runs off the awaiting thread, which only parks until it is woken
+    let stopped = server.wait_until_stopped_async();
+    let stopper = stop_after_call(endpoint);
+    block_on(stopped).expect("Failed to wait for the server");
+    stopper.join().expect("Client thread panicked");
+}
+
+#[test]
+fn test_wait_before_register() {
+    let server = WaitRpcServer::<WaitRpcImpl>::new();
+    assert!(server.wait_until_stopped().is_err());
+    assert!(block_on(server.wait_until_stopped_async()).is_err());
+}
@@ -0,0 +1,93 @@
+use windows::core::HSTRING;
+use windows_rpc::rpc_interface;
+use windows_rpc::wstr::{U16CStr, U16CString};
+
+#[rpc_interface(guid(0x27b9e5c4_0d61_4f3a_9c8e_b4a7126f0d53), version(1.0))]
+trait WideRpc {
+    fn wide_len(text: &U16CStr) -> u32;
+    fn echo(text: &str) -> String;
+    fn join(a: &str, b: &U16CStr) -> String;
+    fn upper_hstring(text: &HSTRING) -> HSTRING;
+    fn reverse_wide(text: &U16CStr) -> U16CString;
+}
+
+struct WideRpcImpl;
+impl WideRpcServerImpl for WideRpcImpl {
+    fn wide_len(text: &U16CStr) -> u32 {
+        text.len() as u32
+    }
+
+    fn echo(text: &str) -> String {
+        text.to_string()
+    }
+
+    fn join(a: &str, b: &U16CStr) -> String {
+        format!("{a}{}", b.to_string_lossy())
+    }
+
+    fn upper_hstring(text: &HSTRING) -> HSTRING {
+        HSTRING::from(text.to_string_lossy().to_uppercase())
+    }
+
+    fn reverse_wide(text: &U16CStr) -> U16CString {
+        let mut wide = text.as_slice().to_vec();
+        wide.reverse();
+        U16CString::from_vec(wide).unwrap()
+    }
+}
+
+#[test]
+fn test_wide_string() {
+    let (_server, client) = windows_rpc::testing::pair::<WideRpcServer<_>, _>(WideRpcImpl)
+        .expect("Failed to create client/server pair");
+
+    let encoded: Vec<u16> = "wide".encode_utf16().chain([0]).collect();
+    let wide = U16CStr::from_slice_with_nul(&encoded).expect("valid wide string");
+
+    assert_eq!(client.wide_len(wide).unwrap(), 4);
+    assert_eq!(client.wide_len("from str").unwrap(), 8);
+    assert_eq!(client.wide_len("").unwrap(), 0);
+
+    assert_eq!(
+        client.echo(wide).unwrap(),
+        "wide",
+        "pre-encoded strings are accepted"
+    );
+    assert_eq!(client.echo(&HSTRING::from("hstring")).unwrap(), "hstring");
+    assert_eq!(client.echo(&String::from("owned")).unwrap(), "owned");
+
+    // Buffers are reused across calls, make sure shorter strings aren't polluted
+    assert_eq!(client.echo("a longer string").unwrap(), "a longer string");
+    assert_eq!(client.echo("short").unwrap(), "short");
+
+    assert_eq!(client.join("a", wide).unwrap(), "awide");
+
+    assert_eq!(
+        client.upper_hstring("hstring").unwrap(),
+        HSTRING::from("HSTRING")
+    );
+    assert_eq!(client.upper_hstring("").unwrap(), HSTRING::new());
+
+    let reversed = client.reverse_wide(wide).unwrap();
+    assert_eq!(reversed.to_string_lossy(), "ediw");
+    assert!(client.reverse_wide("").unwrap().is_empty());
+}
+
+#[test]
+fn test_u16cstr_from_slice() {
+    assert!(U16CStr::from_slice_with_nul(&[]).is_none());
+    assert!(U16CStr::from_slice_with_nul(&[0x61]).is_none());
+    assert!(U16CStr::from_slice_with_nul(&[0x61, 0, 0x62, 0]).is_none());
+
+    let s = U16CStr::from_slice_with_nul(&[0x61, 0x62, 0]).unwrap();
+    assert_eq!(s.as_slice(), &[0x61, 0x62]);
+    assert_eq!(s.as_slice_with_nul(), &[0x61, 0x62, 0]);
+    assert_eq!(s.to_string().unwrap(), "ab");
+    assert!(!s.is_empty());
+
+    let owned = U16CString::from_str_truncate("ab\0cd");
+    assert_eq!(&*owned, s);
+    assert_eq!(owned.into_vec(), vec![0x61, 0x62]);
+    assert!(U16CString::from_vec(vec![0x61, 0]).is_none());
+    assert!(U16CString::default().is_empty());
+}
@@ -0,0 +1,36 @@
+#![cfg(feature = "widestring")]
+
+use windows_rpc::rpc_interface;
+use windows_rpc::wstr::{U16CStr, U16CString};
+
+#[rpc_interface(guid(0x8a35f0d2_6b19_4e07_a5c4_19d7e2b83f60), version(1.0))]
+trait WidestringRpc {
+    fn echo(text: &U16CStr) -> U16CString;
+}
+
+struct WidestringRpcImpl;
+impl WidestringRpcServerImpl for WidestringRpcImpl {
+    fn echo(text: &U16CStr) -> U16CString {
+        text.to_owned()
+    }
+}
+
+#[test]
+fn test_widestring() {
+    let (_server, client) =
+        windows_rpc::testing::pair::<WidestringRpcServer<_>, _>(WidestringRpcImpl)
+            .expect("Failed to create client/server pair");
+
+    let text = widestring::U16CString::from_str("widestring").unwrap();
+    assert_eq!(
+        client.echo(text.as_ucstr()).unwrap().to_string_lossy(),
+        "widestring"
+    );
+
+    let echoed: widestring::U16CString = client.echo(&text).unwrap().into();
+    assert_eq!(echoed, text);
+
+    let borrowed: &U16CStr = text.as_ucstr().into();
+    let back: &widestring::U16CStr = borrowed.as_ref();
+    assert_eq!(back, text.as_ucstr());
+}
@@ -0,0 +1,101 @@
+use windows::Win32::Foundation::{FILETIME, SYSTEMTIME};
+use windows_rpc::{InterfaceCompatibility, ProtocolSequence, client_binding::ClientBinding};
+use windows_rpc::{LARGE_INTEGER, rpc_interface};
+
+#[rpc_interface(guid(0x2b7e4c19_a05d_4f83_9c62_d18e3f5a7b04), version(1.0))]
+trait Clock {
+    fn add(time: &FILETIME, ticks: &LARGE_INTEGER) -> FILETIME;
+    fn elapsed(since: &FILETIME, until: &FILETIME, ticks: &mut LARGE_INTEGER);
+    fn midnight(time: &SYSTEMTIME) -> SYSTEMTIME;
+}
+
+fn to_ticks(time: &FILETIME) -> i64 {
+    (u64::from(time.dwHighDateTime) << 32 | u64::from(time.dwLowDateTime)) as i64
+}
+
+fn from_ticks(ticks: i64) -> FILETIME {
+    FILETIME {
+        dwLowDateTime: ticks as u32,
+        dwHighDateTime: (ticks >> 32) as u32,
+    }
+}
+
+struct ClockImpl;
+impl ClockServerImpl for ClockImpl {
+    fn add(time: &FILETIME, ticks: &LARGE_INTEGER) -> FILETIME {
+        from_ticks(to_ticks(time) + ticks.QuadPart)
+    }
+
+    fn elapsed(since: &FILETIME, until: &FILETIME, ticks: &mut LARGE_INTEGER) {
+        *ticks = (to_ticks(until) - to_ticks(since)).into();
+    }
+
+    fn midnight(time: &SYSTEMTIME) -> SYSTEMTIME {
+        SYSTEMTIME {
+            wHour: 0,
+            wMinute: 0,
+            wSecond: 0,
+            wMilliseconds: 0,
+            ..*time
+        }
+    }
+}
+
+fn check_clock(client: &ClockClient) {
+    // Crosses into the high DWORD
+    let time = from_ticks(0xffff_fff0);
+    let later = client.add(&time, &LARGE_INTEGER::from(0x20)).unwrap();
+    assert_eq!(later, from_ticks(0x1_0000_0010));
+
+    let mut ticks = LARGE_INTEGER::default();
+    client.elapsed(&later, &time, &mut ticks).unwrap();
+    assert_eq!(i64::from(ticks), -0x20);
+
+    let time = SYSTEMTIME {
+        wYear: 2024,
+        wMonth: 2,
+        wDayOfWeek: 4,
+        wDay: 29,
+        wHour: 23,
+        wMinute: 59,
+        wSecond: 58,
+        wMilliseconds: 999,
+    };
+    assert_eq!(
+        client.midnight(&time).unwrap(),
+        SYSTEMTIME {
+            wYear: 2024,
+            wMonth: 2,
+            wDayOfWeek: 4,
+            wDay: 29,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_win32_structs() {
+    let (server, client) = windows_rpc::testing::pair::<ClockServer<_>, _>(ClockImpl)
+        .expect("Failed to create client/server pair");
+    check_clock(&client);
+
+    let client = ClockClient::with_compatibility(
+        ClientBinding::new(ProtocolSequence::Alpc, server.endpoint())
+            .expect("Failed to create client binding"),
+        InterfaceCompatibility::Legacy,
+    );
+    check_clock(&client);
+}
+
+#[test]
+fn test_win32_structs_idl() {
+    assert!(ClockClient::IDL.contains("import \"wtypes.idl\";"));
+    assert!(ClockClient::IDL.contains(
+        "void add([in] handle_t binding, [in] FILETIME* time, [in] LARGE_INTEGER* ticks, \
+         [out] FILETIME* return_value);"
+    ));
+    assert!(ClockClient::IDL.contains(
+        "void midnight([in] handle_t binding, [in] SYSTEMTIME* time, \
+         [out] SYSTEMTIME* return_value);"
+    ));
+}
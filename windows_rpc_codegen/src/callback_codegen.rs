@@ -0,0 +1,470 @@
+//! Callbacks, generated for interfaces with `#[rpc(callback)]` methods.
+//!
+//! The callbacks are compiled as an interface of their own, `{Interface}Callbacks`, with the
+//! GUID and version of the interface and opnums of their own. Servers call them with a
+//! client of it bound to the call being handled, and clients serve them with the server
+//! metadata their `RPC_CLIENT_INTERFACE` points to, dispatching them to the
+//! `{Interface}Callbacks` implementation given to `set_callbacks()`.
+
+use quote::{format_ident, quote};
+
+use crate::client_codegen::{generate_method, generate_stub_info};
+use crate::constants::{
+    MIDL_STUB_DESC_CHECK_BOUNDS, MIDL_STUB_DESC_MIDL_VERSION, MIDL_STUB_DESC_VERSION,
+    RPC_TRANSFER_SYNTAX_NDR_GUID, RPC_TRANSFER_SYNTAX_NDR64_GUID,
+};
+use crate::ndr::{generate_proc_header, generate_type_fixups, generate_type_format_string};
+use crate::ndr64::{generate_ndr64_proc_buffer_code, generate_ndr64_type_format};
+use crate::server_codegen::{
+    generate_dispatch_table_init, generate_server_method_signature, generate_server_routine_table,
+    generate_wrapper_functions,
+};
+use crate::types::{Interface, TransferSyntaxes};
+
+/// Name of the server metadata of the clients, dispatching the callbacks they receive
+fn receiver_name(interface: &Interface) -> syn::Ident {
+    format_ident!("__{}CallbacksServerInfo", interface.name)
+}
+
+/// Generates the field of the client stub info holding the server metadata of the callbacks
+pub fn generate_receiver_field(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    let receiver_name = receiver_name(interface);
+    quote! {
+        callbacks: #receiver_name,
+    }
+}
+
+/// Generates the initialization of the server metadata of the callbacks, which the client
+/// interface points the runtime to
+pub fn generate_receiver_init(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    let receiver_name = receiver_name(interface);
+    quote! {
+        let callbacks = #receiver_name::new(compatibility, ndr64_only, &raw mut *client_interface as _);
+        client_interface.DispatchTable = callbacks.dispatch_table as _;
+        client_interface.InterpreterInfo = &raw const *callbacks.server_info as _;
+        client_interface.Flags |= windows::Win32::System::Rpc::RPCFLG_HAS_CALLBACK;
+    }
+}
+
+/// Generates the field initializer of the server metadata of the callbacks
+pub fn generate_receiver_self_init(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    quote! { callbacks, }
+}
+
+/// Generates the client field holding the handler of the callbacks
+pub fn generate_callbacks_field(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    let trait_name = format_ident!("{}Callbacks", interface.name);
+    quote! {
+        callbacks: std::option::Option<std::sync::Arc<dyn #trait_name>>,
+    }
+}
+
+/// Generates the initializer of the client field holding the handler of the callbacks
+pub fn generate_callbacks_field_init(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    quote! { callbacks: std::option::Option::None, }
+}
+
+/// Generates the client method setting the handler of the callbacks
+pub fn generate_set_callbacks(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    let trait_name = format_ident!("{}Callbacks", interface.name);
+    quote! {
+        /// Handles the callbacks the server makes during the client's calls with
+        /// `callbacks`, on the thread making the call. Without a handler, the callbacks
+        /// fail with `RPC_S_PROCNUM_OUT_OF_RANGE`, as do those made during the calls of
+        /// `async` methods.
+        pub fn set_callbacks(&mut self, callbacks: impl #trait_name + 'static) {
+            self.callbacks = std::option::Option::Some(std::sync::Arc::new(callbacks));
+        }
+    }
+}
+
+/// Generates the statement making the client's handler current for the callbacks of a call
+pub fn generate_callback_scope(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    let interface_guid = interface.uuid;
+    quote! {
+        let __callbacks = windows_rpc::callback::enter(#interface_guid, self.callbacks.as_ref());
+    }
+}
+
+/// Generates the `{Interface}Callbacks` trait clients implement, the
+/// `{Interface}CallbacksClient` servers call it through, and the server metadata
+/// dispatching the callbacks to the clients' handlers
+pub fn compile_callbacks(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.callbacks.is_empty() {
+        return quote! {};
+    }
+    let callbacks = interface.callback_interface();
+    let trait_name = format_ident!("{}", callbacks.name);
+    let client_name = format_ident!("{}Client", callbacks.name);
+    let stub_info_name = format_ident!("__{}ClientStubInfo", callbacks.name);
+    let interface_name = &interface.name;
+
+    let signatures = callbacks
+        .methods
+        .iter()
+        .map(|method| generate_server_method_signature(method, true));
+    let methods = callbacks
+        .methods
+        .iter()
+        .enumerate()
+        .map(|method| generate_method(&callbacks, method));
+    let stub_info = generate_stub_info(&callbacks);
+    let receiver = generate_receiver(&callbacks, &receiver_name(interface));
+
+    quote! {
+        #[doc = concat!("The callbacks of the `", #interface_name, "` interface, which the server makes")]
+        /// during a client's calls, handled by the implementation given to the client's
+        /// `set_callbacks()`.
+        #[allow(clippy::too_many_arguments)]
+        pub trait #trait_name: std::marker::Send + std::marker::Sync {
+            #(#signatures;)*
+        }
+
+        #stub_info
+
+        #[doc = concat!("Makes the callbacks of the `", #interface_name, "` interface on the client")]
+        /// whose call is being handled.
+        pub struct #client_name {
+            binding: windows_rpc::callback::CallBinding,
+            stub_info: &'static #stub_info_name,
+            negotiated_syntax: windows_rpc::client_binding::NegotiatedSyntax,
+        }
+
+        // The methods take as many parameters as the callbacks declare
+        #[allow(clippy::too_many_arguments)]
+        impl #client_name {
+            /// Returns the client of the callbacks of the call being handled on this thread,
+            /// which encodes them with the transfer syntax of the call. Callbacks are made
+            /// from the thread of the call, while the handler runs.
+            ///
+            /// # Errors
+            ///
+            /// Returns `RPC_S_NO_CALL_ACTIVE` outside of a call.
+            pub fn current() -> std::result::Result<Self, windows_rpc::RpcError> {
+                let binding = windows_rpc::callback::CallBinding::current()?;
+                std::result::Result::Ok(Self {
+                    stub_info: #stub_info_name::for_syntax(binding.transfer_syntax()),
+                    binding,
+                    negotiated_syntax: windows_rpc::client_binding::NegotiatedSyntax::new(),
+                })
+            }
+
+            #(#methods)*
+        }
+
+        #receiver
+    }
+}
+
+/// Generates the server metadata of the clients of an interface, dispatching the callbacks
+/// of `callbacks` to the handler of the client whose call is in progress. It offers the
+/// syntaxes of the client stub info it belongs to.
+fn generate_receiver(
+    callbacks: &Interface,
+    receiver_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let method_count = callbacks.methods.len();
+    let syntaxes = callbacks.transfer_syntaxes;
+    let syntax_info_len = usize::from(syntaxes.has_ndr20()) + usize::from(syntaxes.has_ndr64());
+
+    let (type_format, type_offsets) = generate_type_format_string(callbacks);
+    let (proc_header, format_offsets, type_fixups) = generate_proc_header(callbacks, &type_offsets);
+    let type_fixups = generate_type_fixups(&type_fixups);
+    let proc_header_len = proc_header.len();
+    let format_offsets_len = format_offsets.len();
+    let ndr64_type_format = generate_ndr64_type_format(callbacks);
+    let ndr64_type_format_len = ndr64_type_format.len();
+    let ndr64_proc_buffer_construction = generate_ndr64_proc_buffer_code(callbacks);
+    let proc_table_indices: Vec<_> = (0..method_count).collect();
+
+    let wrapper_functions = generate_wrapper_functions(callbacks, true);
+    let dispatch_table_init = generate_dispatch_table_init(callbacks);
+    let server_routine_table = generate_server_routine_table(callbacks);
+
+    let (ndr_fields, ndr_init, ndr_self_init, ndr_syntax_info) = if !syntaxes.has_ndr20() {
+        (quote! {}, quote! {}, quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                dispatch_table_ndr: std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE>,
+                dispatch_functions_ndr: std::boxed::Box<[windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION; #method_count]>,
+                type_format: std::boxed::Box<[u8]>,
+                proc_header: std::boxed::Box<[u8; #proc_header_len]>,
+                format_offsets: std::boxed::Box<[u16; #format_offsets_len]>,
+            },
+            quote! {
+                let mut type_format: std::boxed::Box<[u8]> = std::boxed::Box::new([#(#type_format),*]);
+                let mut proc_header: std::boxed::Box<[u8; #proc_header_len]> = std::boxed::Box::new([#(#proc_header),*]);
+                #type_fixups
+                let format_offsets: std::boxed::Box<[u16; #format_offsets_len]> = std::boxed::Box::new([#(#format_offsets),*]);
+                let mut dispatch_table_ndr = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE {
+                    DispatchTableCount: #method_count as u32,
+                    DispatchTable: std::option::Option::Some(unsafe {
+                        std::mem::transmute::<*const windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION, _>(
+                            dispatch_functions_ndr.as_ptr()
+                        )
+                    }),
+                    Reserved: 0,
+                });
+            },
+            quote! {
+                dispatch_table_ndr,
+                dispatch_functions_ndr,
+                type_format,
+                proc_header,
+                format_offsets,
+            },
+            quote! {
+                windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO {
+                    TransferSyntax: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR_GUID),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: 2,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: &raw mut *dispatch_table_ndr,
+                    ProcString: proc_header.as_mut_ptr(),
+                    FmtStringOffset: format_offsets.as_ptr(),
+                    TypeString: type_format.as_mut_ptr(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    pMethodProperties: std::ptr::null(),
+                    pReserved2: 0,
+                },
+            },
+        )
+    };
+    let (ndr64_fields, ndr64_init, ndr64_self_init, ndr64_syntax_info) = if !syntaxes.has_ndr64() {
+        (quote! {}, quote! {}, quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                dispatch_table_ndr64: std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE>,
+                dispatch_functions_ndr64: std::boxed::Box<[windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION; #method_count]>,
+                ndr64_type_format: std::boxed::Box<[u8; #ndr64_type_format_len]>,
+                ndr64_proc_buffer: std::boxed::Box<std::vec::Vec<u8>>,
+                ndr64_proc_table: std::boxed::Box<[*const u8; #method_count]>,
+            },
+            quote! {
+                let ndr64_type_format: std::boxed::Box<[u8; #ndr64_type_format_len]> =
+                    std::boxed::Box::new([#(#ndr64_type_format),*]);
+                let (ndr64_proc_buffer_data, proc_table_offsets) = #ndr64_proc_buffer_construction;
+                let ndr64_proc_buffer = std::boxed::Box::new(ndr64_proc_buffer_data);
+                let ndr64_proc_table: std::boxed::Box<[*const u8; #method_count]> = {
+                    let base_ptr = ndr64_proc_buffer.as_ptr();
+                    std::boxed::Box::new([
+                        #(unsafe { base_ptr.add(proc_table_offsets[#proc_table_indices]) }),*
+                    ])
+                };
+                let mut dispatch_table_ndr64 = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE {
+                    DispatchTableCount: #method_count as u32,
+                    DispatchTable: std::option::Option::Some(unsafe {
+                        std::mem::transmute::<*const windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION, _>(
+                            dispatch_functions_ndr64.as_ptr()
+                        )
+                    }),
+                    Reserved: 0,
+                });
+            },
+            quote! {
+                dispatch_table_ndr64,
+                dispatch_functions_ndr64,
+                ndr64_type_format,
+                ndr64_proc_buffer,
+                ndr64_proc_table,
+            },
+            quote! {
+                windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO {
+                    TransferSyntax: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR64_GUID),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: 1,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: &raw mut *dispatch_table_ndr64,
+                    ProcString: std::ptr::null_mut(),
+                    FmtStringOffset: ndr64_proc_table.as_ptr() as *const u16,
+                    TypeString: std::ptr::null_mut(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    pMethodProperties: std::ptr::null(),
+                    pReserved2: 0,
+                },
+            },
+        )
+    };
+
+    // Offers the syntaxes the client stub info does, picking NDR64 at run time for
+    // dual-syntax interfaces whose clients are restricted to it
+    let select = |ndr20: proc_macro2::TokenStream, ndr64: proc_macro2::TokenStream| match syntaxes {
+        TransferSyntaxes::All => quote! { if ndr64_only { #ndr64 } else { #ndr20 } },
+        TransferSyntaxes::Ndr20 => ndr20,
+        TransferSyntaxes::Ndr64 => ndr64,
+    };
+    let format_types = select(
+        quote! { type_format.as_ptr() },
+        quote! { ndr64_type_format.as_ptr() },
+    );
+    let proc_string = select(
+        quote! { proc_header.as_mut_ptr() },
+        quote! { std::ptr::null_mut() },
+    );
+    let fmt_string_offset = select(
+        quote! { format_offsets.as_ptr() },
+        quote! { ndr64_proc_table.as_ptr() as *const u16 },
+    );
+    let transfer_syntax = select(
+        quote! { &raw const *rpc_transfer_syntax_ndr },
+        quote! { &raw const *rpc_transfer_syntax_ndr64 },
+    );
+    let dispatch_table = select(
+        quote! { &raw mut *dispatch_table_ndr },
+        quote! { &raw mut *dispatch_table_ndr64 },
+    );
+    // NDR64 is the last syntax info of dual-syntax interfaces
+    let syntax_info = select(
+        quote! { syntax_info_array.as_mut_ptr() },
+        quote! { unsafe { syntax_info_array.as_mut_ptr().add(#syntax_info_len - 1) } },
+    );
+    let (syntax_count, flags_compatibility) = match syntaxes {
+        TransferSyntaxes::All => (
+            quote! { if ndr64_only { 1 } else { compatibility.syntax_count() } },
+            quote! { compatibility },
+        ),
+        TransferSyntaxes::Ndr20 => (
+            quote! { 1 },
+            quote! { windows_rpc::InterfaceCompatibility::Legacy },
+        ),
+        TransferSyntaxes::Ndr64 => (quote! { 1 }, quote! { compatibility }),
+    };
+    let unused = (syntaxes != TransferSyntaxes::All).then(|| quote! { let _ = ndr64_only; });
+
+    quote! {
+        // The server metadata of the clients of one preset, which the runtime dispatches
+        // the callbacks with
+        struct #receiver_name {
+            server_info: std::boxed::Box<windows_sys::Win32::System::Rpc::MIDL_SERVER_INFO>,
+            stub_desc: std::boxed::Box<windows_sys::Win32::System::Rpc::MIDL_STUB_DESC>,
+            server_routines: std::boxed::Box<[windows_sys::Win32::System::Rpc::SERVER_ROUTINE; #method_count]>,
+            syntax_info_array: std::boxed::Box<[windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO; #syntax_info_len]>,
+            rpc_transfer_syntax_ndr: std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER>,
+            rpc_transfer_syntax_ndr64: std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER>,
+            // The dispatch table of the syntax offered first
+            dispatch_table: *mut windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE,
+            #ndr_fields
+            #ndr64_fields
+            auto_bind_handle: std::boxed::Box<*mut std::ffi::c_void>,
+        }
+
+        // The callbacks take as many parameters as they declare
+        #[allow(clippy::too_many_arguments)]
+        impl #receiver_name {
+            #wrapper_functions
+
+            fn new(
+                compatibility: windows_rpc::InterfaceCompatibility,
+                ndr64_only: bool,
+                client_interface: *mut std::ffi::c_void,
+            ) -> Self {
+                #unused
+                let mut auto_bind_handle = std::boxed::Box::new(std::ptr::null_mut());
+                let rpc_transfer_syntax_ndr = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                    SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR_GUID),
+                    SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                        MajorVersion: 2,
+                        MinorVersion: 0,
+                    },
+                });
+                let rpc_transfer_syntax_ndr64 = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                    SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR64_GUID),
+                    SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                        MajorVersion: 1,
+                        MinorVersion: 0,
+                    },
+                });
+
+                #dispatch_table_init
+                #server_routine_table
+                #ndr_init
+                #ndr64_init
+
+                let mut syntax_info_array = std::boxed::Box::new([
+                    #ndr_syntax_info
+                    #ndr64_syntax_info
+                ]);
+
+                let mut stub_desc = std::boxed::Box::new(windows_sys::Win32::System::Rpc::MIDL_STUB_DESC {
+                    RpcInterfaceInformation: client_interface,
+                    pfnAllocate: std::option::Option::Some(windows_rpc::alloc::midl_alloc),
+                    pfnFree: std::option::Option::Some(windows_rpc::alloc::midl_free),
+                    IMPLICIT_HANDLE_INFO: windows_sys::Win32::System::Rpc::MIDL_STUB_DESC_0 {
+                        pAutoHandle: &raw mut *auto_bind_handle,
+                    },
+                    apfnNdrRundownRoutines: std::ptr::null(),
+                    aGenericBindingRoutinePairs: std::ptr::null(),
+                    apfnExprEval: std::ptr::null(),
+                    aXmitQuintuple: std::ptr::null(),
+                    pFormatTypes: #format_types,
+                    fCheckBounds: #MIDL_STUB_DESC_CHECK_BOUNDS as _,
+                    Version: #MIDL_STUB_DESC_VERSION as _,
+                    pMallocFreeStruct: std::ptr::null_mut(),
+                    MIDLVersion: #MIDL_STUB_DESC_MIDL_VERSION as _,
+                    CommFaultOffsets: std::ptr::null(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    NotifyRoutineTable: std::ptr::null(),
+                    mFlags: #flags_compatibility.stub_desc_flags() as _,
+                    CsRoutineTables: std::ptr::null(),
+                    // Will be filled later
+                    ProxyServerInfo: std::ptr::null_mut(),
+                    pExprInfo: std::ptr::null(),
+                });
+
+                let mut server_info = std::boxed::Box::new(windows_sys::Win32::System::Rpc::MIDL_SERVER_INFO {
+                    pStubDesc: &raw mut *stub_desc,
+                    DispatchTable: server_routines.as_ptr() as _,
+                    ProcString: #proc_string,
+                    FmtStringOffset: #fmt_string_offset,
+                    ThunkTable: std::ptr::null(),
+                    pTransferSyntax: #transfer_syntax as *mut _,
+                    nCount: #syntax_count as _,
+                    pSyntaxInfo: #syntax_info,
+                });
+                stub_desc.ProxyServerInfo = &raw mut *server_info as _;
+
+                Self {
+                    dispatch_table: #dispatch_table,
+                    server_info,
+                    stub_desc,
+                    server_routines,
+                    syntax_info_array,
+                    rpc_transfer_syntax_ndr,
+                    rpc_transfer_syntax_ndr64,
+                    #ndr_self_init
+                    #ndr64_self_init
+                    auto_bind_handle,
+                }
+            }
+        }
+    }
+}
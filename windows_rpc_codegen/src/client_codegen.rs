@@ -0,0 +1,1459 @@
+use quote::{ToTokens, format_ident, quote};
+
+use crate::callback_codegen::{
+    generate_callback_scope, generate_callbacks_field, generate_callbacks_field_init,
+    generate_receiver_field, generate_receiver_init, generate_receiver_self_init,
+    generate_set_callbacks,
+};
+use crate::compat_codegen::{
+    generate_client_constructors, generate_client_dispatch, generate_client_field,
+    generate_client_field_init,
+};
+use crate::constants::{
+    MIDL_STUB_DESC_CHECK_BOUNDS, MIDL_STUB_DESC_MIDL_VERSION, MIDL_STUB_DESC_VERSION,
+    RPC_TRANSFER_SYNTAX_NDR_GUID, RPC_TRANSFER_SYNTAX_NDR64_GUID,
+};
+use crate::header::generate_header;
+use crate::idl::generate_idl;
+use crate::ndr::{generate_proc_header, generate_type_fixups, generate_type_format_string};
+use crate::ndr64::{generate_ndr64_proc_buffer_code, generate_ndr64_type_format};
+use crate::types::{BaseType, Interface, Method, Parameter, TransferSyntaxes, Type};
+
+fn generate_parameter(param: &Parameter) -> proc_macro2::TokenStream {
+    let param_name = format_ident!("{}", param.name);
+    // Strings accept anything that can be passed as a wide string, including pre-encoded ones,
+    // by value when declared as `String` or `Cow<'_, str>`
+    let param_type = if param.r#type.is_string() && param.string_form.is_owned() {
+        quote! { impl windows_rpc::wstr::AsWideStr }
+    } else if param.r#type.is_string() {
+        quote! { &(impl windows_rpc::wstr::AsWideStr + ?Sized) }
+    } else {
+        param.to_rust_type()
+    };
+    quote! {
+        #param_name: #param_type
+    }
+}
+
+pub(crate) fn generate_method(
+    interface: &Interface,
+    method: (usize, &Method),
+) -> proc_macro2::TokenStream {
+    let (method_index, method) = method;
+    let generic_handle = interface.generic_handle.as_ref();
+    // Clients created for older servers call through their version instead
+    let compat_dispatch = generate_client_dispatch(interface, method_index, "");
+    let method_index = method_index as u32;
+    let method_name = format_ident!("{}", method.name);
+
+    // With a generic handle, the binding is produced from the first parameter for each call
+    let (handle_parameter, bind, binding) = match generic_handle {
+        Some(handle_type) => (
+            Some(quote! { binding_handle: &#handle_type }),
+            quote! { let __binding = windows_rpc::client_binding::GenericBinding::bind(binding_handle)?; },
+            quote! { __binding.handle() },
+        ),
+        None => (None, quote! {}, quote! { self.binding.handle() }),
+    };
+    let parameters = handle_parameter
+        .into_iter()
+        .chain(method.parameters.iter().map(generate_parameter));
+
+    // Encode string parameters, reusing per-thread buffers
+    let string_conversions: Vec<_> = method
+        .parameters
+        .iter()
+        .filter_map(|param| {
+            let param_name = format_ident!("{}", param.name);
+            let wide_name = format_ident!("__{}_wide", param.name);
+            if param.r#type.is_string() && param.string_form.is_owned() {
+                Some(quote! {
+                    let #wide_name = windows_rpc::wstr::AsWideStr::to_wide_arg(&#param_name);
+                })
+            } else if param.r#type.is_string() {
+                Some(quote! {
+                    let #wide_name = windows_rpc::wstr::AsWideStr::to_wide_arg(#param_name);
+                })
+            } else if param.r#type == Type::OptionalString {
+                Some(quote! {
+                    let #wide_name = #param_name.map(windows_rpc::wstr::AsWideStr::to_wide_arg);
+                })
+            } else if param.r#type == Type::AnsiString {
+                let ansi_name = format_ident!("__{}_ansi", param.name);
+                Some(quote! {
+                    let #ansi_name = windows_rpc::wstr::to_ansi(#param_name);
+                })
+            } else if param.r#type == Type::OptionalAnsiString {
+                let ansi_name = format_ident!("__{}_ansi", param.name);
+                Some(quote! {
+                    let #ansi_name = #param_name.map(windows_rpc::wstr::to_ansi);
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // Convert `transmit_as(...)` parameters to the types they are sent as
+    let wire_conversions: Vec<_> = method
+        .parameters
+        .iter()
+        .filter_map(|param| {
+            let transmittable = param.r#type.transmittable(param.presented.as_ref()?);
+            let param_name = format_ident!("{}", param.name);
+            let wire_name = format_ident!("__{}_wire", param.name);
+            Some(quote! {
+                let #wire_name = #transmittable::to_wire(&#param_name);
+            })
+        })
+        .collect();
+
+    // The stubs read or write as many elements as the `size_is` parameter says, which the
+    // slice must hold
+    let size_checks: Vec<_> = method
+        .parameters
+        .iter()
+        .filter_map(|param| {
+            let param_name = format_ident!("{}", param.name);
+            if param.has_hidden_length() {
+                // The hidden length passed before the array
+                let length = format_ident!("__{}_length", param.name);
+                return Some(quote! {
+                    let #length = windows_rpc::ndr::slice_length(#param_name.len())?;
+                });
+            }
+            let length = format_ident!("{}", param.size_is.as_ref()?);
+            Some(quote! {
+                windows_rpc::ndr::check_size_is(#length, #param_name.len())?;
+            })
+        })
+        .collect();
+
+    // String vector out parameters are received into a length and an array, which are
+    // converted into the vector once the call succeeded, freeing the memory allocated by
+    // the server
+    let (vec_outputs, vec_results): (Vec<_>, Vec<_>) = method
+        .parameters
+        .iter()
+        .filter(|param| param.is_out_string_vec())
+        .map(|param| {
+            let param_name = format_ident!("{}", param.name);
+            let length = format_ident!("__{}_length", param.name);
+            let array = format_ident!("__{}_array", param.name);
+            (
+                quote! {
+                    let mut #length: u32 = 0;
+                    let mut #array: *mut *mut u16 = std::ptr::null_mut();
+                },
+                quote! {
+                    *#param_name = unsafe { windows_rpc::wstr::from_midl_string_array(#array, #length) };
+                },
+            )
+        })
+        .unzip();
+
+    // `[in]` context handles must be open, which the client stub would otherwise raise
+    let context_checks: Vec<_> = method
+        .parameters
+        .iter()
+        .filter(|param| param.r#type == Type::ContextHandle && !param.is_out)
+        .map(|param| {
+            let param_name = format_ident!("{}", param.name);
+            quote! { #param_name.check_open()?; }
+        })
+        .collect();
+
+    // Pipes are streamed through pipe structures the client stub calls back during the
+    // call, which keep a panic of the source or sink to resume once it returns
+    let (pipe_setups, pipe_finishes): (Vec<_>, Vec<_>) = method
+        .parameters
+        .iter()
+        .filter(|param| matches!(param.r#type, Type::Pipe(_)))
+        .map(|param| {
+            let param_name = format_ident!("{}", param.name);
+            let pipe_name = format_ident!("__{}_pipe", param.name);
+            let end = if param.is_out {
+                quote! { sink }
+            } else {
+                quote! { source }
+            };
+            (
+                quote! { let #pipe_name = windows_rpc::pipe::ClientPipe::#end(#param_name); },
+                quote! { #pipe_name.finish(); },
+            )
+        })
+        .unzip();
+
+    // Generate parameter propagation, using the encoded variables for strings
+    let parameters_propagation: Vec<_> = method
+        .parameters
+        .iter()
+        .map(|param| {
+            if param.r#type.is_string() {
+                let wide_name = format_ident!("__{}_wide", param.name);
+                quote! { #wide_name.as_pcwstr().0 }
+            } else if param.r#type == Type::OptionalString {
+                // `None` is passed as a null pointer
+                let wide_name = format_ident!("__{}_wide", param.name);
+                quote! { #wide_name.as_ref().map_or(std::ptr::null(), |wide| wide.as_pcwstr().0) }
+            } else if param.r#type == Type::AnsiString {
+                let ansi_name = format_ident!("__{}_ansi", param.name);
+                quote! { #ansi_name.as_ptr() }
+            } else if param.r#type == Type::OptionalAnsiString {
+                let ansi_name = format_ident!("__{}_ansi", param.name);
+                quote! { #ansi_name.as_ref().map_or(std::ptr::null(), |ansi| ansi.as_ptr()) }
+            } else if param.presented.is_some() {
+                let wire_name = format_ident!("__{}_wire", param.name);
+                match param.r#type {
+                    Type::Struct(_) => quote! { &raw const #wire_name },
+                    _ => param.r#type.rust_type_to_abi(wire_name),
+                }
+            } else if let (Type::Slice(_), Some(_)) = (&param.r#type, &param.size_is) {
+                // Sized by another parameter, checked against the slice before the call
+                let param_name = format_ident!("{}", param.name);
+                if param.is_out {
+                    quote! { #param_name.as_mut_ptr() }
+                } else {
+                    quote! { #param_name.as_ptr() }
+                }
+            } else if let (Type::Slice(_), true) = (&param.r#type, param.is_out) {
+                // The client stub writes the array received from the server into the slice
+                let param_name = format_ident!("{}", param.name);
+                let length = format_ident!("__{}_length", param.name);
+                quote! { #length, #param_name.as_mut_ptr() }
+            } else if param.is_out_string_vec() {
+                let length = format_ident!("__{}_length", param.name);
+                let array = format_ident!("__{}_array", param.name);
+                quote! { &raw mut #length, &raw mut #array }
+            } else if let Type::Pipe(_) = param.r#type {
+                let pipe_name = format_ident!("__{}_pipe", param.name);
+                quote! { #pipe_name.as_raw() }
+            } else if param.is_out {
+                // The client stub writes the value received from the server through it
+                let param_name = format_ident!("{}", param.name);
+                quote! { &raw mut *#param_name }
+            } else {
+                param
+                    .r#type
+                    .rust_type_to_abi(format_ident!("{}", param.name))
+            }
+        })
+        .collect();
+
+    // The variables the client stub writes the outputs to, as their name, type and initial
+    // value. Asynchronous calls write them once the reply arrives, after the client stub
+    // returned, so they are boxed for the future awaiting it to own them.
+    let output = |name: &str, r#type, init| (format_ident!("{}", name), r#type, init);
+    let output_arg = |name: &str| {
+        let name = format_ident!("{}", name);
+        if method.r#async {
+            quote! { &raw mut *#name }
+        } else {
+            quote! { &raw mut #name }
+        }
+    };
+
+    // Each return type is passed back differently: `outputs` declares the variables the
+    // client stub writes it to, `output_args` passes them after the parameters, and
+    // `result` converts them once the call succeeded
+    let (rtype, outputs, output_args, result) = match &method.return_type {
+        Some(Type::Simple(base_type)) => {
+            let rtype = Type::Simple(*base_type).to_rust_type();
+            // The return value is copied into the register-sized result, so floating point
+            // values are read back from its bits
+            let result = match base_type {
+                BaseType::F32 => quote! { f32::from_bits(unsafe { __result.Simple } as u32) },
+                BaseType::F64 => quote! { f64::from_bits(unsafe { __result.Simple } as u64) },
+                _ => quote! { unsafe { __result.Simple } as #rtype },
+            };
+            // The return value has a stack slot, which precedes the status
+            (rtype, vec![], vec![quote! { 0usize }], result)
+        }
+        Some(enum_type @ Type::Enum(_)) => {
+            // Enums are returned as 32-bit integers, which fail the call if they match no variant
+            let rtype = enum_type.to_rust_return_type();
+            let result = quote! { windows_rpc::ndr::enum_from_return::<#rtype>(unsafe { __result.Simple } as u32)? };
+            (rtype, vec![], vec![quote! { 0usize }], result)
+        }
+        Some(return_type @ (Type::String | Type::WideString | Type::HString)) => {
+            // String return: we need to pass an out parameter pointer
+            let rtype = return_type.to_rust_return_type();
+            (
+                rtype.clone(),
+                vec![output(
+                    "__out_string",
+                    quote! { *mut u16 },
+                    quote! { std::ptr::null_mut() },
+                )],
+                vec![output_arg("__out_string")],
+                // Convert the wide string and free the memory allocated by the server
+                quote! { unsafe { windows_rpc::wstr::from_midl_string::<#rtype>(__out_string) } },
+            )
+        }
+        Some(Type::OptionalString) => {
+            // Optional string return: like string returns, but a null string is `None`
+            (
+                quote! { std::option::Option<String> },
+                vec![output(
+                    "__out_string",
+                    quote! { *mut u16 },
+                    quote! { std::ptr::null_mut() },
+                )],
+                vec![output_arg("__out_string")],
+                quote! {
+                    (!__out_string.is_null())
+                        .then(|| unsafe { windows_rpc::wstr::from_midl_string::<String>(__out_string) })
+                },
+            )
+        }
+        Some(optional_type @ Type::Optional(base_type)) => {
+            // Optional return: the server writes a pointer to the value, or null for `None`
+            let value_type = base_type.to_rust_type();
+            (
+                optional_type.to_rust_return_type(),
+                vec![output(
+                    "__out_value",
+                    quote! { *mut #value_type },
+                    quote! { std::ptr::null_mut() },
+                )],
+                vec![output_arg("__out_value")],
+                // Copy the value and free the memory allocated by the client stub
+                quote! { unsafe { windows_rpc::ndr::from_midl_value(__out_value) } },
+            )
+        }
+        Some(struct_type @ Type::Struct(_)) => {
+            // Struct return: the server writes it through an out parameter pointer
+            let rtype = struct_type.to_rust_return_type();
+            (
+                rtype.clone(),
+                // Written by the client stub, unless the call fails
+                vec![output(
+                    "__out_struct",
+                    quote! { std::mem::MaybeUninit<#rtype> },
+                    quote! { std::mem::MaybeUninit::uninit() },
+                )],
+                vec![output_arg("__out_struct")],
+                quote! { unsafe { __out_struct.assume_init() } },
+            )
+        }
+        Some(handle_type @ Type::SystemHandle { .. }) => {
+            // Handle return: the client stub duplicates the server's handle into this process
+            (
+                handle_type.to_rust_return_type(),
+                vec![output(
+                    "__out_handle",
+                    quote! { *mut std::ffi::c_void },
+                    quote! { std::ptr::null_mut() },
+                )],
+                vec![output_arg("__out_handle")],
+                quote! { unsafe { windows_rpc::ndr::system_handle_from_return(__out_handle)? } },
+            )
+        }
+        Some(Type::ContextHandle) => {
+            // Context handle return: the client stub opens the context it writes here
+            (
+                Type::ContextHandle.to_rust_return_type(),
+                vec![output(
+                    "__out_context",
+                    quote! { windows_rpc::context::ContextHandle },
+                    quote! { std::default::Default::default() },
+                )],
+                vec![output_arg("__out_context")],
+                quote! { __out_context },
+            )
+        }
+        Some(slice_type @ Type::Slice(element)) => {
+            // Vector return: the server writes its length and array through out parameters
+            let element = element.to_rust_type();
+            (
+                slice_type.to_rust_return_type(),
+                vec![
+                    output("__out_len", quote! { u32 }, quote! { 0 }),
+                    output(
+                        "__out_array",
+                        quote! { *mut #element },
+                        quote! { std::ptr::null_mut() },
+                    ),
+                ],
+                vec![output_arg("__out_len"), output_arg("__out_array")],
+                // Copy the array and free the memory allocated by the server
+                quote! { unsafe { windows_rpc::ndr::from_midl_array(__out_array, __out_len) } },
+            )
+        }
+        Some(Type::StringVec) => {
+            // String vector return: like vector returns, the array holding string pointers
+            (
+                quote! { std::vec::Vec<String> },
+                vec![
+                    output("__out_len", quote! { u32 }, quote! { 0 }),
+                    output(
+                        "__out_array",
+                        quote! { *mut *mut u16 },
+                        quote! { std::ptr::null_mut() },
+                    ),
+                ],
+                vec![output_arg("__out_len"), output_arg("__out_array")],
+                // Convert the strings and free the strings and array allocated by the server
+                quote! { unsafe { windows_rpc::wstr::from_midl_string_array(__out_array, __out_len) } },
+            )
+        }
+        Some(Type::AnsiString | Type::OptionalAnsiString) => {
+            unreachable!("ANSI strings are only input parameters")
+        }
+        Some(Type::Pipe(_)) => unreachable!("pipes are only parameters"),
+        None => (quote! { () }, vec![], vec![], quote! { () }),
+    };
+    // `error_status` and `hresult` methods return the status by value instead, so a base
+    // type return value is written through a hidden out parameter
+    let (mut outputs, output_args, result) = match &method.return_type {
+        Some(Type::Simple(base_type)) if method.returns_status() => {
+            let value_type = Type::Simple(*base_type).to_rust_type();
+            (
+                vec![output(
+                    "__return_value",
+                    value_type,
+                    quote! { std::default::Default::default() },
+                )],
+                vec![output_arg("__return_value"), quote! { 0usize }],
+                quote! { __return_value },
+            )
+        }
+        _ if method.returns_status() => {
+            // The status has a stack slot after the hidden out parameters
+            let output_args = output_args.into_iter().chain([quote! { 0usize }]).collect();
+            (outputs, output_args, result)
+        }
+        _ => (outputs, output_args, result),
+    };
+    // Convert a `transmit_as(...)` return value from the type it is sent as
+    let (rtype, result) = match (&method.presented_return, &method.return_type) {
+        (Some(presented), Some(return_type)) => {
+            let transmittable = return_type.transmittable(presented);
+            (
+                presented
+                    .parse()
+                    .expect("presented paths are parsed from tokens"),
+                quote! { #transmittable::from_wire(#result) },
+            )
+        }
+        _ => (rtype, result),
+    };
+    let error_type = generate_error_type(method);
+    // The error code is written through the hidden parameter preceding the return value,
+    // which isn't converted when the call returns an error
+    let (error_arg, error_check) = match &method.error {
+        Some(_) if method.error_status => (
+            vec![],
+            // Returned as the error status of the call
+            quote! {
+                let __service_error = unsafe { __result.Simple } as u32;
+                if __service_error != 0 {
+                    return std::result::Result::Err(windows_rpc::ServiceError::Service(__service_error));
+                }
+            },
+        ),
+        Some(_) if method.hresult => (
+            vec![],
+            // Returned as the `HRESULT` of the call, failed when negative
+            quote! {
+                let __service_error = unsafe { __result.Simple } as i32;
+                if __service_error < 0 {
+                    return std::result::Result::Err(windows_rpc::ServiceError::Service(__service_error));
+                }
+            },
+        ),
+        Some(error) => {
+            let error = match error {
+                Type::Enum(_) => {
+                    let path = error.struct_path();
+                    quote! { windows_rpc::ndr::enum_from_return::<#path>(__service_error)? }
+                }
+                _ => quote! { __service_error },
+            };
+            outputs.insert(0, output("__service_error", quote! { u32 }, quote! { 0 }));
+            (
+                vec![output_arg("__service_error")],
+                quote! {
+                    if __service_error != 0 {
+                        return std::result::Result::Err(windows_rpc::ServiceError::Service(#error));
+                    }
+                },
+            )
+        }
+        None => (vec![], quote! {}),
+    };
+    // Written by the client stub when the call fails, instead of raising an exception
+    outputs.push(output("__status", quote! { u32 }, quote! { 0 }));
+    let status_arg = output_arg("__status");
+
+    let declarations: Vec<_> = outputs
+        .iter()
+        .map(|(name, r#type, init)| {
+            if method.r#async {
+                quote! { let mut #name: std::boxed::Box<#r#type> = std::boxed::Box::new(#init); }
+            } else {
+                quote! { let mut #name: #r#type = #init; }
+            }
+        })
+        .collect();
+    // Moved out of their boxes once the reply is received
+    let unboxing: Vec<_> = outputs
+        .iter()
+        .filter(|_| method.r#async)
+        .map(|(name, _, _)| quote! { let #name = *#name; })
+        .collect();
+    let prologue = quote! {
+        #(#context_checks)*
+        #(#size_checks)*
+        #bind
+        #(#string_conversions)*
+        #(#wire_conversions)*
+        #(#vec_outputs)*
+        #(#pipe_setups)*
+        #(#declarations)*
+    };
+    let record_syntax = quote! {
+        if self.stub_info.syntax.is_none() {
+            self.negotiated_syntax.record(#binding, &raw const *self.stub_info.client_interface as _);
+        }
+    };
+    let epilogue = quote! {
+        #(#vec_results)*
+        #error_check
+        // Converted first, as enum return values fail the call with `?` if unknown
+        let __value = #result;
+        std::result::Result::Ok(__value)
+    };
+
+    if !method.r#async {
+        let callback_scope = generate_callback_scope(interface);
+        return quote! {
+            pub fn #method_name(&self, #(#parameters),*) -> std::result::Result<#rtype, #error_type> {
+                #compat_dispatch
+                #prologue
+                #callback_scope
+                let __result = unsafe {
+                    windows_sys::Win32::System::Rpc::NdrClientCall3(
+                        &raw const *self.stub_info.proxy_info as _,
+                        #method_index,
+                        std::ptr::null_mut(),
+                        #binding,
+                        #(#parameters_propagation,)*
+                        #(#error_arg,)*
+                        #(#output_args,)*
+                        #status_arg
+                    )
+                };
+                #(#pipe_finishes)*
+                windows_rpc::ndr::call_result(__status)?;
+                #record_syntax
+                #epilogue
+            }
+        };
+    }
+
+    // Asynchronous calls send the request, which the client stub marshals before returning,
+    // and receive the reply with `RpcAsyncCompleteCall` once it arrives
+    let start = quote! {
+        #prologue
+        let __call = windows_rpc::async_call::AsyncCall::new()?;
+        unsafe {
+            windows_sys::Win32::System::Rpc::Ndr64AsyncClientCall(
+                &raw const *self.stub_info.proxy_info as _,
+                #method_index,
+                std::ptr::null_mut(),
+                __call.state(),
+                #binding,
+                #(#parameters_propagation,)*
+                #(#error_arg,)*
+                #(#output_args,)*
+                #status_arg
+            )
+        };
+        __call.start(*__status)?;
+        #record_syntax
+    };
+    let blocking = quote! {
+        pub fn #method_name(&self, #(#parameters),*) -> std::result::Result<#rtype, #error_type> {
+            #compat_dispatch
+            #start
+            let __result = __call.wait()?;
+            #(#unboxing)*
+            windows_rpc::ndr::call_result(__status)?;
+            #epilogue
+        }
+    };
+    // The future owns the outputs, so its call cannot write through the caller's out
+    // parameters, nor borrow a generic handle
+    if method.parameters.iter().any(|param| param.is_out)
+        || method.has_system_handle_param()
+        || generic_handle.is_some()
+    {
+        return blocking;
+    }
+    let async_name = format_ident!("{}_async", method.name);
+    let async_parameters = method.parameters.iter().map(generate_parameter);
+    let async_dispatch = generate_client_dispatch(interface, method_index as usize, "_async");
+    quote! {
+        #blocking
+
+        /// Sends the request and returns a future that completes with the reply, without
+        /// occupying a thread while the call runs. Dropping the future cancels the call.
+        pub fn #async_name(
+            &self,
+            #(#async_parameters),*
+        ) -> windows_rpc::executor::CallFuture<std::result::Result<#rtype, #error_type>> {
+            #async_dispatch
+            windows_rpc::async_call::begin(|| {
+                #start
+                let __finish = move |__reply: std::result::Result<windows_sys::Win32::System::Rpc::CLIENT_CALL_RETURN, windows_rpc::RpcError>| {
+                    let __result = __reply?;
+                    #(#unboxing)*
+                    windows_rpc::ndr::call_result(__status)?;
+                    #epilogue
+                };
+                std::result::Result::Ok(unsafe { __call.into_future(__finish) })
+            })
+        }
+    }
+}
+
+/// Returns the type of a client method's successful result.
+fn generate_return_type(method: &Method) -> proc_macro2::TokenStream {
+    if let Some(presented) = &method.presented_return {
+        return presented
+            .parse()
+            .expect("presented paths are parsed from tokens");
+    }
+    match &method.return_type {
+        Some(Type::Simple(base_type)) => Type::Simple(*base_type).to_rust_type(),
+        Some(Type::OptionalString) => quote! { std::option::Option<String> },
+        Some(return_type) => return_type.to_rust_return_type(),
+        None => quote! { () },
+    }
+}
+
+/// Returns the error type of a client method: the `ServiceError` of methods returning
+/// `Result<T, E>`, or `RpcError`.
+fn generate_error_type(method: &Method) -> proc_macro2::TokenStream {
+    match &method.error {
+        Some(error @ Type::Enum(_)) => {
+            let path = error.struct_path();
+            quote! { windows_rpc::ServiceError<#path> }
+        }
+        Some(_) if method.hresult => quote! { windows_rpc::ServiceError<i32> },
+        Some(_) => quote! { windows_rpc::ServiceError },
+        None => quote! { windows_rpc::RpcError },
+    }
+}
+
+/// Generates the object-safe trait named after the interface, whose methods take `&self`
+/// and return the result of the call, and implements it for the client. Application code
+/// generic over the trait can use the client, a local implementation or a mock.
+pub fn generate_client_trait(interface: &Interface) -> proc_macro2::TokenStream {
+    let trait_name = format_ident!("{}", interface.name);
+    let rpc_client_name = format_ident!("{}Client", interface.name);
+    let handle_parameter = interface
+        .generic_handle
+        .as_ref()
+        .map(|handle_type| quote! { binding_handle: &#handle_type });
+    let handle_argument = handle_parameter.as_ref().map(|_| quote! { binding_handle });
+
+    let (signatures, calls): (Vec<_>, Vec<_>) = interface
+        .methods
+        .iter()
+        .map(|method| {
+            let method_name = format_ident!("{}", method.name);
+            let parameters = handle_parameter
+                .iter()
+                .cloned()
+                .chain(method.parameters.iter().map(|param| {
+                    let name = format_ident!("{}", param.name);
+                    let rust_type = param.to_rust_type();
+                    quote! { #name: #rust_type }
+                }));
+            let arguments = handle_argument.iter().cloned().chain(
+                method
+                    .parameters
+                    .iter()
+                    .map(|param| format_ident!("{}", param.name).into_token_stream()),
+            );
+            let rtype = generate_return_type(method);
+            let error_type = generate_error_type(method);
+            let signature = quote! {
+                fn #method_name(&self, #(#parameters),*) -> std::result::Result<#rtype, #error_type>
+            };
+            let call = quote! {
+                #signature {
+                    #rpc_client_name::#method_name(self, #(#arguments),*)
+                }
+            };
+            (signature, call)
+        })
+        .unzip();
+
+    quote! {
+        /// The methods of the interface, implemented by the generated client, for code
+        /// that also works with local implementations or mocks. String parameters take
+        /// the declared types, rather than anything the client's methods accept.
+        #[allow(clippy::too_many_arguments)]
+        pub trait #trait_name {
+            #(#signatures;)*
+        }
+
+        impl #trait_name for #rpc_client_name {
+            #(#calls)*
+        }
+    }
+}
+
+/// Generates the `{method}_async` variant of a client method, which makes the call on the
+/// client's executor with owned copies of the parameters. Methods with output parameters,
+/// handles, context handles or pipes, which the caller would have to keep borrowed across
+/// the call, have none.
+fn generate_async_method(method: &Method) -> Option<proc_macro2::TokenStream> {
+    // Asynchronous procedures have their own, which awaits the reply without a thread
+    if method.r#async
+        || method.parameters.iter().any(|param| param.is_out)
+        || method.has_system_handle_param()
+        || method.has_context_handle_param()
+        || method.has_pipe_param()
+    {
+        return None;
+    }
+    let method_name = format_ident!("{}", method.name);
+    let async_name = format_ident!("{}_async", method.name);
+    let parameters = method.parameters.iter().map(generate_parameter);
+    let rtype = generate_return_type(method);
+    let error_type = generate_error_type(method);
+
+    // Copy borrowed parameters, so that the call can outlive the caller's borrows
+    let (owned, arguments): (Vec<_>, Vec<_>) = method
+        .parameters
+        .iter()
+        .map(|param| {
+            let name = format_ident!("{}", param.name);
+            match &param.r#type {
+                // Taken by value
+                _ if param.presented.is_some() => (quote! {}, quote! { #name }),
+                r#type if r#type.is_string() && param.string_form.is_owned() => (
+                    quote! { let #name = windows_rpc::wstr::AsWideStr::to_wide_string(&#name); },
+                    quote! { &#name },
+                ),
+                r#type if r#type.is_string() => (
+                    quote! { let #name = windows_rpc::wstr::AsWideStr::to_wide_string(#name); },
+                    quote! { &#name },
+                ),
+                Type::OptionalString | Type::OptionalAnsiString => (
+                    quote! { let #name = #name.map(str::to_owned); },
+                    quote! { #name.as_deref() },
+                ),
+                Type::AnsiString => (quote! { let #name = #name.to_owned(); }, quote! { &#name }),
+                Type::Slice(_) => (quote! { let #name = #name.to_vec(); }, quote! { &#name }),
+                Type::Struct(_) => (quote! { let #name = *#name; }, quote! { &#name }),
+                _ => (quote! {}, quote! { #name }),
+            }
+        })
+        .unzip();
+
+    // Structs with pointer fields aren't `Send`, and would outlive the caller's borrows of
+    // the pointees, which leaves the method uncallable for them. The bound is higher-ranked
+    // so that it is only checked by callers.
+    let struct_bounds = method
+        .parameters
+        .iter()
+        .filter(|param| param.presented.is_none() && matches!(param.r#type, Type::Struct(_)))
+        .map(|param| {
+            let path = param.r#type.struct_path();
+            quote! { for<'__a> #path: std::marker::Send }
+        })
+        .collect::<Vec<_>>();
+    let where_clause = (!struct_bounds.is_empty()).then(|| quote! { where #(#struct_bounds),* });
+
+    Some(quote! {
+        /// Makes the call on the client's executor, see
+        /// [`set_executor()`](Self::set_executor), and completes with its result.
+        pub fn #async_name(
+            self: &std::sync::Arc<Self>,
+            #(#parameters),*
+        ) -> windows_rpc::executor::CallFuture<std::result::Result<#rtype, #error_type>>
+        #where_clause
+        {
+            #(#owned)*
+            let __client = std::sync::Arc::clone(self);
+            windows_rpc::executor::spawn(self.executor.as_deref(), move || {
+                __client.#method_name(#(#arguments),*)
+            })
+        }
+    })
+}
+
+fn generate_metadata(interface: &Interface) -> proc_macro2::TokenStream {
+    let name = &interface.name;
+    let guid = interface.uuid;
+    let major = interface.version.major;
+    let minor = interface.version.minor;
+    let methods = interface.methods.iter().enumerate().map(|(opnum, method)| {
+        let name = &method.name;
+        let opnum = opnum as u32;
+        let parameters = method.parameters.iter().map(|param| {
+            let name = &param.name;
+            let (rust_type, idl_type) = param.metadata_names();
+            let is_in = param.is_in;
+            let is_out = param.is_out;
+            quote! {
+                windows_rpc::metadata::ParameterMetadata {
+                    name: #name,
+                    r#type: windows_rpc::metadata::TypeMetadata {
+                        rust_type: #rust_type,
+                        idl_type: #idl_type,
+                    },
+                    is_in: #is_in,
+                    is_out: #is_out,
+                }
+            }
+        });
+        let return_type = match &method.return_type {
+            Some(return_type) => {
+                let (rust_type, idl_type) = return_type.metadata_names(true);
+                let rust_type = method.presented_return.clone().unwrap_or(rust_type);
+                quote! {
+                    std::option::Option::Some(windows_rpc::metadata::TypeMetadata {
+                        rust_type: #rust_type,
+                        idl_type: #idl_type,
+                    })
+                }
+            }
+            None => quote! { std::option::Option::None },
+        };
+        // Error codes are sent as `unsigned long`s, whatever their Rust type, unless they are
+        // the `error_status_t` or `HRESULT` of the call
+        let error_type = match &method.error {
+            Some(error) => {
+                let (rust_type, _) = error.metadata_names(true);
+                let idl_type = if method.error_status {
+                    "error_status_t"
+                } else if method.hresult {
+                    "HRESULT"
+                } else {
+                    "unsigned long"
+                };
+                quote! {
+                    std::option::Option::Some(windows_rpc::metadata::TypeMetadata {
+                        rust_type: #rust_type,
+                        idl_type: #idl_type,
+                    })
+                }
+            }
+            None => quote! { std::option::Option::None },
+        };
+        quote! {
+            windows_rpc::metadata::MethodMetadata {
+                name: #name,
+                opnum: #opnum,
+                parameters: &[#(#parameters),*],
+                return_type: #return_type,
+                error_type: #error_type,
+            }
+        }
+    });
+
+    quote! {
+        windows_rpc::metadata::InterfaceMetadata {
+            name: #name,
+            guid: #guid,
+            version: (#major, #minor),
+            methods: &[#(#methods),*],
+        }
+    }
+}
+
+/// Generates the `__{Name}ClientStubInfo` struct holding the stub descriptor and format
+/// strings of the clients of `interface`, with the metadata dispatching its callbacks
+pub(crate) fn generate_stub_info(interface: &Interface) -> proc_macro2::TokenStream {
+    let stub_info_name = format_ident!("__{}ClientStubInfo", interface.name);
+    let interface_guid = interface.uuid;
+    let interface_version_major = interface.version.major;
+    let interface_version_minor = interface.version.minor;
+
+    // Generate NDR format strings
+    let (type_format, type_offsets) = generate_type_format_string(interface);
+
+    // Generate proc header with type offsets
+    let (proc_header, format_offsets, type_fixups) = generate_proc_header(interface, &type_offsets);
+    let type_fixups = generate_type_fixups(&type_fixups);
+    let proc_header_len = proc_header.len();
+    let format_offsets_len = format_offsets.len();
+
+    // Interfaces offering a single transfer syntax omit the other's format strings and
+    // syntax info
+    let syntaxes = interface.transfer_syntaxes;
+    let syntax_info_len = usize::from(syntaxes.has_ndr20()) + usize::from(syntaxes.has_ndr64());
+    let (ndr_fields, ndr_init, ndr_self_init, ndr_syntax_info) = if !syntaxes.has_ndr20() {
+        (quote! {}, quote! {}, quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                type_format: std::boxed::Box<[u8]>,
+                proc_header: std::boxed::Box<[u8; #proc_header_len]>,
+                format_offsets: std::boxed::Box<[u16; #format_offsets_len]>,
+            },
+            quote! {
+                let mut type_format: std::boxed::Box<[u8]> = std::boxed::Box::new([#(#type_format),*]);
+                let mut proc_header: std::boxed::Box<[u8; #proc_header_len]> = std::boxed::Box::new([#(#proc_header),*]);
+                #type_fixups
+                let mut format_offsets: std::boxed::Box<[u16; #format_offsets_len]> = std::boxed::Box::new([#(#format_offsets),*]);
+            },
+            quote! {
+                format_offsets,
+                proc_header,
+                type_format,
+            },
+            quote! {
+                // NDR 2.0 syntax info (index 0)
+                windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO {
+                    TransferSyntax: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR_GUID),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: 2,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: std::ptr::null_mut(),
+                    ProcString: proc_header.as_mut_ptr(),
+                    FmtStringOffset: format_offsets.as_ptr(),
+                    TypeString: type_format.as_mut_ptr(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    pMethodProperties: std::ptr::null(),
+                    pReserved2: 0,
+                },
+            },
+        )
+    };
+
+    // Generate NDR64 format structures
+    let ndr64_type_format = generate_ndr64_type_format(interface);
+    let ndr64_type_format_len = ndr64_type_format.len();
+
+    // Generate code to build proc buffer at runtime
+    let ndr64_proc_buffer_construction = generate_ndr64_proc_buffer_code(interface);
+    let ndr64_proc_table_len = interface.methods.len();
+    let proc_table_indices: Vec<_> = (0..ndr64_proc_table_len).collect();
+
+    let (ndr64_fields, ndr64_init, ndr64_self_init, ndr64_syntax_info) = if !syntaxes.has_ndr64() {
+        (quote! {}, quote! {}, quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                // NDR64 format data (contiguous memory)
+                ndr64_type_format: std::boxed::Box<[u8; #ndr64_type_format_len]>,
+                ndr64_proc_buffer: std::boxed::Box<std::vec::Vec<u8>>,  // Built at runtime, variable size
+                ndr64_proc_table: std::boxed::Box<[*const u8; #ndr64_proc_table_len]>,
+            },
+            quote! {
+                // Initialize NDR64 data structures
+                let ndr64_type_format: std::boxed::Box<[u8; #ndr64_type_format_len]> =
+                    std::boxed::Box::new([#(#ndr64_type_format),*]);
+
+                // Build proc buffer at runtime (so pointers to ndr64_type_format are valid)
+                let (ndr64_proc_buffer_data, proc_table_offsets) = #ndr64_proc_buffer_construction;
+
+                let ndr64_proc_buffer = std::boxed::Box::new(ndr64_proc_buffer_data);
+
+                // Build Ndr64ProcTable - array of pointers into proc_buffer
+                let ndr64_proc_table: std::boxed::Box<[*const u8; #ndr64_proc_table_len]> = {
+                    let base_ptr = ndr64_proc_buffer.as_ptr();
+                    std::boxed::Box::new([
+                        #(unsafe { base_ptr.add(proc_table_offsets[#proc_table_indices]) }),*
+                    ])
+                };
+            },
+            quote! {
+                ndr64_type_format,
+                ndr64_proc_buffer,
+                ndr64_proc_table,
+            },
+            quote! {
+                // NDR64 1.0 syntax info
+                windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO {
+                    TransferSyntax: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR64_GUID),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: 1,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: std::ptr::null_mut(),
+                    ProcString: std::ptr::null_mut(),
+                    FmtStringOffset: ndr64_proc_table.as_ptr() as *const u16,
+                    TypeString: std::ptr::null_mut(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    pMethodProperties: std::ptr::null(),
+                    pReserved2: 0,
+                },
+            },
+        )
+    };
+
+    // Picks the NDR 2.0 or the NDR64 value, at run time for dual-syntax interfaces whose
+    // clients may be restricted to NDR64
+    let select = |ndr20: proc_macro2::TokenStream, ndr64: proc_macro2::TokenStream| match syntaxes {
+        TransferSyntaxes::All => quote! { if ndr64_only { #ndr64 } else { #ndr20 } },
+        TransferSyntaxes::Ndr20 => ndr20,
+        TransferSyntaxes::Ndr64 => ndr64,
+    };
+    let format_types = select(
+        quote! { type_format.as_ptr() },
+        quote! { ndr64_type_format.as_ptr() },
+    );
+    let proc_format_string = select(
+        quote! { proc_header.as_mut_ptr() },
+        quote! { std::ptr::null_mut() },
+    );
+    let format_string_offset = select(
+        quote! { format_offsets.as_mut_ptr() },
+        quote! { ndr64_proc_table.as_ptr() as *mut u16 },
+    );
+    let transfer_syntax = select(
+        quote! { &raw const *rpc_transfer_syntax_ndr },
+        quote! { &raw const *rpc_transfer_syntax_ndr64 },
+    );
+    // NDR64 is the last syntax info of dual-syntax interfaces
+    let syntax_info = select(
+        quote! { syntax_info_array.as_mut_ptr() },
+        quote! { unsafe { syntax_info_array.as_mut_ptr().add(#syntax_info_len - 1) } },
+    );
+    let client_syntax_guid = select(
+        quote! { windows::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR_GUID) },
+        quote! { windows::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR64_GUID) },
+    );
+    let client_syntax_major = select(quote! { 2 }, quote! { 1 });
+    // Single-syntax interfaces offer their syntax whatever the preset, and NDR 2.0-only ones
+    // have the interface flags of the `Legacy` preset, like MIDL's `/protocol dce`
+    let (syntax_count, offered_syntax) = match syntaxes {
+        TransferSyntaxes::All => (
+            quote! {
+                if ndr64_only { 1 } else { compatibility.syntax_count() }
+            },
+            quote! {
+                if ndr64_only {
+                    std::option::Option::Some(windows_rpc::TransferSyntax::Ndr64)
+                } else if compatibility.syntax_count() == 1 {
+                    std::option::Option::Some(windows_rpc::TransferSyntax::Ndr20)
+                } else {
+                    std::option::Option::None
+                }
+            },
+        ),
+        TransferSyntaxes::Ndr20 => (
+            quote! { 1 },
+            quote! { std::option::Option::Some(windows_rpc::TransferSyntax::Ndr20) },
+        ),
+        TransferSyntaxes::Ndr64 => (
+            quote! { 1 },
+            quote! { std::option::Option::Some(windows_rpc::TransferSyntax::Ndr64) },
+        ),
+    };
+    let flags_compatibility = if syntaxes == TransferSyntaxes::Ndr20 {
+        quote! { windows_rpc::InterfaceCompatibility::Legacy }
+    } else {
+        quote! { compatibility }
+    };
+    // The runtime only sets up the pipes of interfaces that declare them
+    let pipe_flags = if interface.has_pipes() {
+        quote! { | windows::Win32::System::Rpc::RPC_INTERFACE_HAS_PIPES }
+    } else {
+        quote! {}
+    };
+    let compatibility_check = match syntaxes {
+        TransferSyntaxes::All => quote! {},
+        TransferSyntaxes::Ndr20 => quote! {
+            let _ = (compatibility, ndr64_only);
+        },
+        TransferSyntaxes::Ndr64 => quote! {
+            let _ = ndr64_only;
+            assert!(
+                compatibility != windows_rpc::InterfaceCompatibility::Legacy,
+                "NDR64-only interfaces cannot use the Legacy preset",
+            );
+        },
+    };
+    // Clients restricted to a syntax use the `Legacy` stub info for NDR 2.0, which offers
+    // only NDR 2.0, and an NDR64-only one for NDR64
+    let (ndr64_stub_info, syntax_stub_info) = match syntaxes {
+        TransferSyntaxes::All => (
+            quote! {
+                fn ndr64() -> &'static Self {
+                    static STUB_INFO: std::sync::OnceLock<#stub_info_name> = std::sync::OnceLock::new();
+                    STUB_INFO.get_or_init(|| Self::new(windows_rpc::InterfaceCompatibility::Ndr64, true))
+                }
+            },
+            quote! {
+                match syntax {
+                    windows_rpc::TransferSyntax::Ndr20 => #stub_info_name::get(windows_rpc::InterfaceCompatibility::Legacy),
+                    windows_rpc::TransferSyntax::Ndr64 => #stub_info_name::ndr64(),
+                }
+            },
+        ),
+        TransferSyntaxes::Ndr20 => (
+            quote! {},
+            quote! {{
+                assert!(
+                    syntax == windows_rpc::TransferSyntax::Ndr20,
+                    "NDR 2.0-only interfaces cannot use NDR64",
+                );
+                #stub_info_name::get(windows_rpc::InterfaceCompatibility::default())
+            }},
+        ),
+        TransferSyntaxes::Ndr64 => (
+            quote! {},
+            quote! {{
+                assert!(
+                    syntax == windows_rpc::TransferSyntax::Ndr64,
+                    "NDR64-only interfaces cannot use NDR 2.0",
+                );
+                #stub_info_name::get(windows_rpc::InterfaceCompatibility::default())
+            }},
+        ),
+    };
+
+    // The stack offset of each method's status parameter, for both the comm and fault status
+    let status_offsets = interface
+        .methods
+        .iter()
+        .map(|method| method.status_stack_offset(std::mem::size_of::<usize>()) as i16)
+        .collect::<Vec<_>>();
+
+    let receiver_field = generate_receiver_field(interface);
+    let receiver_init = generate_receiver_init(interface);
+    let receiver_self_init = generate_receiver_self_init(interface);
+
+    quote! {
+        // The stub descriptor and format strings of the clients of one preset, built on
+        // first use and shared by them
+        struct #stub_info_name {
+            proxy_info: std::boxed::Box<windows_sys::Win32::System::Rpc::MIDL_STUBLESS_PROXY_INFO>,
+            stub_desc: std::boxed::Box<windows_sys::Win32::System::Rpc::MIDL_STUB_DESC>,
+            syntax_info_array: std::boxed::Box<[windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO; #syntax_info_len]>,
+            // The only syntax offered, `None` if the runtime negotiates it
+            syntax: std::option::Option<windows_rpc::TransferSyntax>,
+            client_interface: std::boxed::Box<windows::Win32::System::Rpc::RPC_CLIENT_INTERFACE>,
+            iface_handle: std::boxed::Box<*mut windows::Win32::System::Rpc::RPC_CLIENT_INTERFACE>,
+            rpc_transfer_syntax_ndr: std::boxed::Box<windows::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER>,
+            rpc_transfer_syntax_ndr64: std::boxed::Box<windows::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER>,
+            #ndr_fields
+            #ndr64_fields
+            auto_bind_handle: std::boxed::Box<*mut std::ffi::c_void>,
+            comm_fault_offsets: std::boxed::Box<[windows_sys::Win32::System::Rpc::COMM_FAULT_OFFSETS; #ndr64_proc_table_len]>,
+            #receiver_field
+        }
+
+        // Only read, by the RPC runtime, once built
+        unsafe impl std::marker::Send for #stub_info_name {}
+        unsafe impl std::marker::Sync for #stub_info_name {}
+
+        impl #stub_info_name {
+            fn get(compatibility: windows_rpc::InterfaceCompatibility) -> &'static Self {
+                static STUB_INFOS: windows_rpc::PerPreset<#stub_info_name> = windows_rpc::PerPreset::new();
+                STUB_INFOS.get_or_init(compatibility, || Self::new(compatibility, false))
+            }
+
+            #ndr64_stub_info
+
+            /// The stub info of the clients offering only `syntax`
+            fn for_syntax(syntax: windows_rpc::TransferSyntax) -> &'static Self {
+                #syntax_stub_info
+            }
+
+            fn new(compatibility: windows_rpc::InterfaceCompatibility, ndr64_only: bool) -> Self {
+                #compatibility_check
+                let mut auto_bind_handle = std::boxed::Box::new(std::ptr::null_mut());
+                #ndr_init
+                #ndr64_init
+
+                let rpc_transfer_syntax_ndr = std::boxed::Box::new(windows::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                    SyntaxGUID: windows::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR_GUID),
+                    SyntaxVersion: windows::Win32::System::Rpc::RPC_VERSION {
+                        MajorVersion: 2,
+                        MinorVersion: 0,
+                    },
+                });
+
+                // Create NDR64 transfer syntax
+                let rpc_transfer_syntax_ndr64 = std::boxed::Box::new(windows::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                    SyntaxGUID: windows::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR64_GUID),
+                    SyntaxVersion: windows::Win32::System::Rpc::RPC_VERSION {
+                        MajorVersion: 1,
+                        MinorVersion: 0,
+                    },
+                });
+
+                let mut iface_handle = std::boxed::Box::new(std::ptr::null_mut());
+
+                let comm_fault_offsets = std::boxed::Box::new([
+                    #(windows_sys::Win32::System::Rpc::COMM_FAULT_OFFSETS {
+                        CommOffset: #status_offsets,
+                        FaultOffset: #status_offsets,
+                    }),*
+                ]);
+
+                // Create the syntax infos
+                let mut syntax_info_array = std::boxed::Box::new([
+                    #ndr_syntax_info
+                    #ndr64_syntax_info
+                ]);
+                let mut stub_desc = std::boxed::Box::new(windows_sys::Win32::System::Rpc::MIDL_STUB_DESC {
+                    // Will be filled later
+                    RpcInterfaceInformation: std::ptr::null_mut(),
+                    pfnAllocate: std::option::Option::Some(windows_rpc::alloc::midl_alloc),
+                    pfnFree: std::option::Option::Some(windows_rpc::alloc::midl_free),
+                    IMPLICIT_HANDLE_INFO: windows_sys::Win32::System::Rpc::MIDL_STUB_DESC_0 {
+                        pAutoHandle: &raw mut *auto_bind_handle,
+                    },
+                    apfnNdrRundownRoutines: std::ptr::null(),
+                    aGenericBindingRoutinePairs: std::ptr::null(),
+                    apfnExprEval: std::ptr::null(),
+                    aXmitQuintuple: std::ptr::null(),
+                    pFormatTypes: #format_types,
+                    fCheckBounds: #MIDL_STUB_DESC_CHECK_BOUNDS as _,
+                    Version: #MIDL_STUB_DESC_VERSION as _,
+                    pMallocFreeStruct: std::ptr::null_mut(),
+                    MIDLVersion: #MIDL_STUB_DESC_MIDL_VERSION as _,
+                    CommFaultOffsets: comm_fault_offsets.as_ptr(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    NotifyRoutineTable: std::ptr::null(),
+                    mFlags: #flags_compatibility.stub_desc_flags() as _,
+                    CsRoutineTables: std::ptr::null(),
+                    // Will be filled later
+                    ProxyServerInfo: std::ptr::null_mut(),
+                    pExprInfo: std::ptr::null(),
+                });
+                // Update proxy info to point to dual syntax array
+                let mut proxy_info = std::boxed::Box::new(windows_sys::Win32::System::Rpc::MIDL_STUBLESS_PROXY_INFO {
+                    pStubDesc: &raw mut *stub_desc,
+                    ProcFormatString: #proc_format_string,
+                    FormatStringOffset: #format_string_offset,
+                    pTransferSyntax: #transfer_syntax as *mut _,
+                    nCount: #syntax_count as _,
+                    pSyntaxInfo: #syntax_info,
+                });
+                // Circular dependency fixup
+                stub_desc.ProxyServerInfo = &raw mut *proxy_info as _;
+
+                let mut client_interface= std::boxed::Box::new(windows::Win32::System::Rpc::RPC_CLIENT_INTERFACE {
+                    Length: std::mem::size_of::<windows::Win32::System::Rpc::RPC_CLIENT_INTERFACE>() as u32,
+                    InterfaceId: windows::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows::core::GUID::from_u128(#interface_guid),
+                        SyntaxVersion: windows::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: #interface_version_major,
+                            MinorVersion: #interface_version_minor,
+                        },
+                    },
+                    TransferSyntax: windows::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: #client_syntax_guid,
+                        SyntaxVersion: windows::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: #client_syntax_major,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: std::ptr::null_mut(),
+                    RpcProtseqEndpointCount: 0,
+                    RpcProtseqEndpoint: std::ptr::null_mut(),
+                    Reserved: 0,
+                    InterpreterInfo: &raw const *proxy_info as _,
+                    Flags: #flags_compatibility.client_interface_flags() #pipe_flags,
+                });
+                *iface_handle = &raw mut *client_interface;
+                stub_desc.RpcInterfaceInformation = &raw mut *client_interface as _;
+                #receiver_init
+
+                Self {
+                    proxy_info,
+                    client_interface,
+                    stub_desc,
+                    syntax_info_array,
+                    syntax: #offered_syntax,
+                    iface_handle,
+                    rpc_transfer_syntax_ndr,
+                    rpc_transfer_syntax_ndr64,
+                    #ndr_self_init
+                    #ndr64_self_init
+                    auto_bind_handle,
+                    comm_fault_offsets,
+                    #receiver_self_init
+                }
+            }
+        }
+    }
+}
+
+pub fn compile_client(interface: &Interface) -> proc_macro2::TokenStream {
+    let rpc_client_name = format_ident!("{}Client", interface.name);
+    let stub_info_name = format_ident!("__{}ClientStubInfo", interface.name);
+    let interface_guid_name = format_ident!("{}_GUID", interface.name.to_uppercase());
+    let interface_guid = interface.uuid;
+    let interface_id = interface.id_string();
+    let interface_version_major = interface.version.major;
+    let interface_version_minor = interface.version.minor;
+    let generic_handle = interface.generic_handle.as_ref();
+    let methods = interface
+        .methods
+        .iter()
+        .enumerate()
+        .map(|method| generate_method(interface, method));
+
+    let stub_info = generate_stub_info(interface);
+    let metadata = generate_metadata(interface);
+    let idl = generate_idl(interface);
+    let header = generate_header(interface);
+    let callbacks_field = generate_callbacks_field(interface);
+    let callbacks_field_init = generate_callbacks_field_init(interface);
+    let set_callbacks = generate_set_callbacks(interface);
+    let call_path_field = generate_client_field(interface);
+    let call_path_init = generate_client_field_init(interface);
+    let compat_constructors = generate_client_constructors(interface);
+
+    let async_methods = if generic_handle.is_some() {
+        quote! {}
+    } else {
+        let methods = interface.methods.iter().filter_map(generate_async_method);
+        quote! {
+            /// Runs the calls of the `_async` methods on `executor` instead of tokio's
+            /// blocking pool or the shared `ThreadPool`.
+            pub fn set_executor(&mut self, executor: impl windows_rpc::executor::CallExecutor + 'static) {
+                self.executor = std::option::Option::Some(std::sync::Arc::new(executor));
+            }
+
+            #(#methods)*
+        }
+    };
+
+    // Generic handle clients bind per call instead of owning a binding
+    // and have no async variants, whose calls cannot borrow the handle
+    let (binding_field, binding_param, binding_arg, binding_init, rpc_client_impl) =
+        if generic_handle.is_some() {
+            (quote! {}, quote! {}, quote! {}, quote! {}, quote! {})
+        } else {
+            (
+                quote! {
+                    binding: windows_rpc::client_binding::ClientBinding,
+                    executor: std::option::Option<std::sync::Arc<dyn windows_rpc::executor::CallExecutor>>,
+                },
+                quote! { binding: windows_rpc::client_binding::ClientBinding, },
+                quote! { binding, },
+                quote! {
+                    binding,
+                    executor: std::option::Option::None,
+                },
+                quote! {
+                    impl windows_rpc::interface::RpcClient for #rpc_client_name {
+                        const GUID: u128 = #interface_guid;
+                        const VERSION: (u16, u16) = (#interface_version_major, #interface_version_minor);
+                        const INTERFACE_ID: &'static str = #interface_id;
+
+                        fn new(binding: windows_rpc::client_binding::ClientBinding) -> Self {
+                            Self::new(binding)
+                        }
+                    }
+                },
+            )
+        };
+
+    quote! {
+        const #interface_guid_name: windows::core::GUID = windows::core::GUID::from_u128(#interface_guid);
+
+        #stub_info
+
+
+        pub struct #rpc_client_name {
+            #binding_field
+            stub_info: &'static #stub_info_name,
+            negotiated_syntax: windows_rpc::client_binding::NegotiatedSyntax,
+            #callbacks_field
+            #call_path_field
+        }
+
+        // The methods take as many parameters as the interface declares
+        #[allow(clippy::too_many_arguments)]
+        impl #rpc_client_name {
+            /// The interface identifier.
+            pub const GUID: u128 = #interface_guid;
+            /// The interface version, as `(major, minor)`.
+            pub const VERSION: (u16, u16) = (#interface_version_major, #interface_version_minor);
+            /// The definition of the interface, for external tooling.
+            pub const METADATA: windows_rpc::metadata::InterfaceMetadata = #metadata;
+            /// The MIDL definition of the interface, for compiling matching C/C++ stubs.
+            pub const IDL: &'static str = #idl;
+            /// The C header of the interface, declaring the procedures of the client stubs MIDL
+            /// generates from [`Self::IDL`].
+            pub const HEADER: &'static str = #header;
+            /// Stable textual identifier of the interface, the GUID followed by the version,
+            /// for logs and metrics labels.
+            pub const INTERFACE_ID: &'static str = #interface_id;
+
+            /// Returns the interface identifier and version, as `(guid, major, minor)`.
+            pub fn interface_id() -> (windows::core::GUID, u16, u16) {
+                (
+                    windows::core::GUID::from_u128(Self::GUID),
+                    Self::VERSION.0,
+                    Self::VERSION.1,
+                )
+            }
+
+            pub fn new(#binding_param) -> Self {
+                Self::with_compatibility(#binding_arg windows_rpc::InterfaceCompatibility::default())
+            }
+
+            /// Creates a client whose interface flags follow the given preset.
+            pub fn with_compatibility(
+                #binding_param
+                compatibility: windows_rpc::InterfaceCompatibility,
+            ) -> Self {
+                Self {
+                    #binding_init
+                    stub_info: #stub_info_name::get(compatibility),
+                    negotiated_syntax: windows_rpc::client_binding::NegotiatedSyntax::new(),
+                    #callbacks_field_init
+                    #call_path_init
+                }
+            }
+
+            /// Creates a client offering only `syntax`, so that its calls are encoded with
+            /// it, or fail if the server doesn't support it.
+            pub fn with_transfer_syntax(
+                #binding_param
+                syntax: windows_rpc::TransferSyntax,
+            ) -> Self {
+                Self {
+                    #binding_init
+                    stub_info: #stub_info_name::for_syntax(syntax),
+                    negotiated_syntax: windows_rpc::client_binding::NegotiatedSyntax::new(),
+                    #callbacks_field_init
+                    #call_path_init
+                }
+            }
+
+            /// Returns the transfer syntax of the client's calls: the only one it offers,
+            /// or the one the RPC runtime negotiated with the server for its first
+            /// successful call. `None` if it offers both and made no call yet.
+            pub fn transfer_syntax(&self) -> std::option::Option<windows_rpc::TransferSyntax> {
+                self.stub_info.syntax.or_else(|| self.negotiated_syntax.get())
+            }
+
+            #compat_constructors
+
+            #set_callbacks
+
+            #(#methods)*
+
+            #async_methods
+        }
+
+        // The metadata is never modified after the client is created, and binding handles
+        // may be used by several threads at once
+        unsafe impl std::marker::Send for #rpc_client_name {}
+        unsafe impl std::marker::Sync for #rpc_client_name {}
+
+        #rpc_client_impl
+    }
+}
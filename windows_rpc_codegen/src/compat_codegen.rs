@@ -0,0 +1,290 @@
+//! Call paths for older interface versions, generated for interfaces with `#[since]`
+//! parameters.
+//!
+//! Each older version is compiled as its own hidden interface, without the parameters
+//! added after it. Clients call through it when the server implements that version,
+//! and servers register it alongside the newest version to serve older clients,
+//! filling the added parameters with their defaults.
+
+use quote::{format_ident, quote};
+
+use crate::client_codegen::compile_client;
+use crate::server_codegen::{compile_server, generate_server_method_signature};
+use crate::types::{Interface, InterfaceVersion};
+
+/// Name of the hidden module holding the older versions of the interface
+pub fn compat_module(interface: &Interface) -> syn::Ident {
+    format_ident!("__{}Compat", interface.name)
+}
+
+/// Name of the `CallPath` variant calling through an older version
+fn call_path_variant(version: InterfaceVersion) -> syn::Ident {
+    format_ident!("V{}x{}", version.major, version.minor)
+}
+
+/// Name of the server field registering an older version
+fn server_field(version: InterfaceVersion) -> syn::Ident {
+    format_ident!("compat_{}x{}", version.major, version.minor)
+}
+
+/// Generates the hidden module with a client and server for each older version
+pub fn compile_compat(interface: &Interface) -> proc_macro2::TokenStream {
+    let versions = interface.compat_versions();
+    if versions.is_empty() {
+        return quote! {};
+    }
+
+    let module = compat_module(interface);
+    let trait_name = format_ident!("{}ServerImpl", interface.name);
+
+    let compat_interfaces: Vec<_> = versions.iter().map(|v| interface.at_version(*v)).collect();
+    let clients = compat_interfaces.iter().map(compile_client);
+    let servers = compat_interfaces.iter().map(compile_server);
+
+    // Servers of older versions forward to the newest implementation, passing the
+    // defaults of the parameters added since
+    let adapters = compat_interfaces.iter().map(|compat| {
+        let compat_trait_name = format_ident!("{}ServerImpl", compat.name);
+        let methods = compat
+            .methods
+            .iter()
+            .zip(&interface.methods)
+            .map(|(old, new)| {
+                let signature = generate_server_method_signature(old, false);
+                let method_name = format_ident!("{}", new.name);
+                let arguments =
+                    new.parameters
+                        .iter()
+                        .enumerate()
+                        .map(|(i, param)| match &param.default {
+                            Some(default) if i >= old.parameters.len() => {
+                                let default: proc_macro2::TokenStream = default.parse().unwrap();
+                                quote! { #default }
+                            }
+                            _ => {
+                                let name = format_ident!("{}", param.name);
+                                quote! { #name }
+                            }
+                        });
+                quote! {
+                    #signature {
+                        <T as super::#trait_name>::#method_name(#(#arguments),*)
+                    }
+                }
+            });
+        quote! {
+            impl<T: super::#trait_name> #compat_trait_name for Adapter<T> {
+                #(#methods)*
+            }
+        }
+    });
+
+    let variants = versions
+        .iter()
+        .zip(&compat_interfaces)
+        .map(|(version, compat)| {
+            let variant = call_path_variant(*version);
+            let client = format_ident!("{}Client", compat.name);
+            quote! { #variant(#client) }
+        });
+
+    quote! {
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        pub mod #module {
+            #(#clients)*
+            #(#servers)*
+
+            /// Implements the servers of older versions with the newest implementation `T`.
+            pub struct Adapter<T>(std::marker::PhantomData<T>);
+
+            #(#adapters)*
+
+            /// The older version a client calls through.
+            pub enum CallPath {
+                #(#variants),*
+            }
+        }
+    }
+}
+
+/// Generates the client field holding the older version calls go through, if any
+pub fn generate_client_field(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.compat_versions().is_empty() {
+        return quote! {};
+    }
+    let module = compat_module(interface);
+    quote! {
+        call_path: std::option::Option<#module::CallPath>,
+    }
+}
+
+/// Generates the initialization of the client field, calling the newest version
+pub fn generate_client_field_init(interface: &Interface) -> proc_macro2::TokenStream {
+    if interface.compat_versions().is_empty() {
+        return quote! {};
+    }
+    quote! {
+        call_path: std::option::Option::None,
+    }
+}
+
+/// Generates the start of a client method, or of its variant named with `suffix`, calling
+/// through the older version the client was created for without the parameters added
+/// after it
+pub fn generate_client_dispatch(
+    interface: &Interface,
+    method_index: usize,
+    suffix: &str,
+) -> proc_macro2::TokenStream {
+    let versions = interface.compat_versions();
+    if versions.is_empty() {
+        return quote! {};
+    }
+    let module = compat_module(interface);
+    let method_name = format_ident!("{}{}", interface.methods[method_index].name, suffix);
+    let arms = versions.iter().map(|version| {
+        let variant = call_path_variant(*version);
+        let arguments = interface.at_version(*version).methods[method_index]
+            .parameters
+            .iter()
+            .map(|param| format_ident!("{}", param.name))
+            .collect::<Vec<_>>();
+        quote! {
+            #module::CallPath::#variant(client) => client.#method_name(#(#arguments),*),
+        }
+    });
+    quote! {
+        if let std::option::Option::Some(call_path) = &self.call_path {
+            return match call_path {
+                #(#arms)*
+            };
+        }
+    }
+}
+
+/// Generates the client constructors choosing the call path from the server's version
+pub fn generate_client_constructors(interface: &Interface) -> proc_macro2::TokenStream {
+    let versions = interface.compat_versions();
+    if versions.is_empty() {
+        return quote! {};
+    }
+    let module = compat_module(interface);
+    let major = interface.version.major;
+    let minor = interface.version.minor;
+
+    // Nested from the newest older version down to the base version, which always matches
+    let mut selection = None;
+    for version in &versions {
+        let variant = call_path_variant(*version);
+        let client = format_ident!("{}Client", interface.at_version(*version).name);
+        let version_minor = version.minor;
+        let call_path = quote! {
+            std::option::Option::Some(#module::CallPath::#variant(#module::#client::new(binding.try_clone()?)))
+        };
+        selection = Some(match selection {
+            None => call_path,
+            Some(older) => quote! {
+                if version.1 >= #version_minor {
+                    #call_path
+                } else {
+                    #older
+                }
+            },
+        });
+    }
+
+    let version_arms = versions.iter().map(|version| {
+        let variant = call_path_variant(*version);
+        let version_minor = version.minor;
+        quote! {
+            std::option::Option::Some(#module::CallPath::#variant(_)) => (#major, #version_minor),
+        }
+    });
+
+    quote! {
+        /// Creates a client for a server implementing `version` of the interface.
+        ///
+        /// Calls to servers older than the interface omit the parameters added after
+        /// their version.
+        pub fn with_server_version(
+            binding: windows_rpc::client_binding::ClientBinding,
+            version: (u16, u16),
+        ) -> windows::core::Result<Self> {
+            let call_path = if version.0 != #major || version.1 >= #minor {
+                std::option::Option::None
+            } else {
+                #selection
+            };
+            let mut client = Self::new(binding);
+            client.call_path = call_path;
+            std::result::Result::Ok(client)
+        }
+
+        /// Queries the server behind `binding` for the newest version of the interface
+        /// it implements, and creates a client for it.
+        ///
+        /// # Errors
+        ///
+        /// Returns `RPC_S_UNKNOWN_IF` if the server does not implement the interface's
+        /// major version, or the error from `RpcMgmtInqIfIds` if it cannot be queried.
+        pub fn connect(
+            binding: windows_rpc::client_binding::ClientBinding,
+        ) -> std::result::Result<Self, windows_rpc::RpcError> {
+            let version = windows_rpc::negotiate::server_version(&binding, Self::GUID, #major)?;
+            std::result::Result::Ok(Self::with_server_version(binding, version)?)
+        }
+
+        /// The interface version calls are made with, as `(major, minor)`.
+        pub fn call_version(&self) -> (u16, u16) {
+            match &self.call_path {
+                std::option::Option::None => Self::VERSION,
+                #(#version_arms)*
+            }
+        }
+    }
+}
+
+/// Generates the server fields registering the older versions
+pub fn generate_server_fields(interface: &Interface) -> proc_macro2::TokenStream {
+    let module = compat_module(interface);
+    let fields = interface.compat_versions().into_iter().map(|version| {
+        let field = server_field(version);
+        let server = format_ident!("{}Server", interface.at_version(version).name);
+        quote! {
+            #field: #module::#server<#module::Adapter<T>>,
+        }
+    });
+    quote! { #(#fields)* }
+}
+
+/// Generates the initialization of the server fields, sharing the server's `state` and
+/// reusing its endpoint
+pub fn generate_server_fields_init(interface: &Interface) -> proc_macro2::TokenStream {
+    let module = compat_module(interface);
+    let fields = interface.compat_versions().into_iter().map(|version| {
+        let field = server_field(version);
+        let server = format_ident!("{}Server", interface.at_version(version).name);
+        quote! {
+            #field: {
+                let mut server = #module::#server::new();
+                server.share_state(state.clone());
+                server.set_endpoint_mode(windows_rpc::server_binding::EndpointMode::ReuseExisting);
+                server
+            },
+        }
+    });
+    quote! { #(#fields)* }
+}
+
+/// Generates a statement calling `method` on each older version's server
+pub fn generate_server_forward(
+    interface: &Interface,
+    forward: impl Fn(proc_macro2::TokenStream) -> proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let statements = interface.compat_versions().into_iter().map(|version| {
+        let field = server_field(version);
+        forward(quote! { self.#field })
+    });
+    quote! { #(#statements)* }
+}
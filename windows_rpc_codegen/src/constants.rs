@@ -0,0 +1,149 @@
+// NDR format flags and constants
+#[allow(non_upper_case_globals)]
+pub const Oi_FULL_PTR_USED: u8 = 0x01; // The stubs set up a full pointer table
+#[allow(non_upper_case_globals)]
+pub const Oi_HAS_RPCFLAGS: u8 = 8;
+#[allow(non_upper_case_globals)]
+pub const Oi_USE_NEW_INIT_ROUTINES: u8 = 0x40;
+#[allow(non_upper_case_globals)]
+pub const Oi_HAS_COMM_OR_FAULT: u8 = 0x20; // Errors are stored at the CommFaultOffsets
+pub const FC_BIND_PRIMITIVE: u8 = 0x32;
+// rpc_flags of the proc header
+pub const RPC_NCA_FLAGS_IDEMPOTENT: u32 = 0x1; // May be executed more than once
+pub const RPC_NCA_FLAGS_BROADCAST: u32 = 0x2; // Sent to every server on the network
+pub const RPC_NCA_FLAGS_MAYBE: u32 = 0x4; // No reply is sent
+pub const INTERPRETER_OPT_FLAGS2_NEW_CORRELATION_DESCRIPTOR: u8 = 1;
+pub const INTERPRETER_OPT_FLAGS2_CLIENT_CORR_CHECK: u8 = 2;
+pub const INTERPRETER_OPT_FLAGS2_SERVER_CORR_CHECK: u8 = 4;
+pub const INTERPRETER_OPT_FLAGS2_RANGE_ON_CONFORMANCE: u8 = 0x40;
+pub const PARAM_ATTRIBUTES_MUST_SIZE: u16 = 0x1;
+pub const PARAM_ATTRIBUTES_MUST_FREE: u16 = 0x2;
+pub const PARAM_ATTRIBUTES_IS_PIPE: u16 = 0x4;
+pub const PARAM_ATTRIBUTES_IS_IN: u16 = 0x8;
+pub const PARAM_ATTRIBUTES_IS_OUT: u16 = 0x10;
+pub const PARAM_ATTRIBUTES_IS_RETURN: u16 = 0x20;
+pub const PARAM_ATTRIBUTES_IS_BASE_TYPE: u16 = 0x40;
+pub const PARAM_ATTRIBUTES_IS_BY_VALUE: u16 = 0x80;
+pub const PARAM_ATTRIBUTES_IS_SIMPLE_REF: u16 = 0x100;
+// Following consts can be mixed to create 8 + 16 + 32 = 56 bytes.
+pub const PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8: u16 = 0x2000;
+pub const PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_16: u16 = 0x4000;
+pub const PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_32: u16 = 0x8000;
+// Type format string constants
+pub const FC_RP: u8 = 0x11; // Reference pointer
+pub const FC_UP: u8 = 0x12; // Unique pointer
+pub const FC_FP: u8 = 0x14; // Full pointer, which may alias others
+pub const FC_C_CSTRING: u8 = 0x22; // Conformant character string
+pub const FC_C_WSTRING: u8 = 0x25; // Conformant wide character string (unicode)
+pub const FC_PAD: u8 = 0x5c; // Padding
+pub const FC_SIMPLE_POINTER: u8 = 0x8; // Simple pointer flag
+pub const FC_CARRAY: u8 = 0x1b; // Conformant array
+pub const FC_BOGUS_ARRAY: u8 = 0x21; // Complex array, e.g. of pointers
+pub const FC_END: u8 = 0x5b; // End of a compound type description
+pub const FC_BYTE: u8 = 0x01;
+pub const FC_SMALL: u8 = 0x03;
+pub const FC_SHORT: u8 = 0x06;
+pub const FC_USHORT: u8 = 0x07;
+pub const FC_LONG: u8 = 0x08;
+pub const FC_ULONG: u8 = 0x09;
+pub const FC_FLOAT: u8 = 0x0a;
+pub const FC_DOUBLE: u8 = 0x0c;
+pub const FC_ENUM16: u8 = 0x0d; // Enum transmitted as 16 bits
+pub const FC_ENUM32: u8 = 0x0e; // Enum transmitted as 32 bits
+pub const FC_ERROR_STATUS_T: u8 = 0x10; // Status code returned by a procedure
+pub const FC_STRUCT: u8 = 0x15; // Simple structure, copied as a block
+pub const FC_STRUCTPAD1: u8 = 0x3d; // Padding in a structure, up to FC_STRUCTPAD7 for 7 bytes
+pub const FC_BOGUS_STRUCT: u8 = 0x1a; // Complex structure, e.g. with pointers
+pub const FC_POINTER: u8 = 0x36; // Pointer member of a complex structure
+pub const FC_ENCAPSULATED_UNION: u8 = 0x2a; // Union preceded by its discriminant
+pub const FC_UNION_ARM_SIMPLE: u16 = 0x8000; // Union arm of a base type, in the low byte
+pub const FC_UNION_NO_DEFAULT: u16 = 0xffff; // Unknown discriminants are rejected
+pub const FC_RANGE: u8 = 0xb7; // Base type value within bounds
+pub const FC_SYSTEM_HANDLE: u8 = 0x3c; // Kernel object handle, duplicated over ALPC
+pub const FC_BIND_CONTEXT: u8 = 0x30; // Context handle
+pub const FC_PIPE: u8 = 0xb5; // Pipe, streaming its elements in chunks
+// Context handle flags, shared by NDR64
+pub const NDR_CONTEXT_HANDLE_CANNOT_BE_NULL: u8 = 0x01;
+pub const NDR_CONTEXT_HANDLE_IS_OUT: u8 = 0x20;
+pub const NDR_CONTEXT_HANDLE_IS_IN: u8 = 0x40;
+pub const NDR_CONTEXT_HANDLE_IS_VIA_PTR: u8 = 0x80;
+
+// Correlation descriptor types
+pub const FC_POINTER_CONFORMANCE: u8 = 0x10; // Conformance taken from the embedding struct
+pub const FC_TOP_LEVEL_CONFORMANCE: u8 = 0x20; // Conformance taken from a parameter
+pub const FC_CORR_FLAGS_EARLY: u16 = 0x01; // The correlated parameter precedes the array
+pub const FC_NO_CORRELATION: u32 = 0xffffffff; // In place of a descriptor, e.g. no variance
+pub const FC_DEREFERENCE: u8 = 0x54; // The correlated parameter is a pointer to the value
+// Range following a correlation descriptor in procedures with RANGE_ON_CONFORMANCE
+pub const FC_CORR_RANGE_NONE: u8 = 0; // The conformance isn't checked
+pub const FC_CORR_RANGE: u8 = 1; // The conformance must be within the bounds that follow
+
+// Procedure flags
+pub const OI2_CLIENT_MUST_SIZE: u8 = 0x02;
+pub const OI2_HAS_PIPES: u8 = 0x08; // Pipe parameters are streamed after the others
+pub const OI2_HAS_ASYNC_HANDLE: u8 = 0x80; // The RPC_ASYNC_STATE precedes the binding handle
+
+// Transfer Syntax GUIDs
+pub const RPC_TRANSFER_SYNTAX_NDR_GUID: u128 = 0x8A885D04_1CEB_11C9_9FE8_08002B104860;
+pub const RPC_TRANSFER_SYNTAX_NDR64_GUID: u128 = 0x71710533_beba_4937_8319_b5dbef9ccc36;
+
+// NDR64 Format Codes (for base types)
+pub const NDR64_FC_INT8: u8 = 0x10;
+pub const NDR64_FC_INT16: u8 = 0x04;
+pub const NDR64_FC_INT32: u8 = 0x05;
+pub const NDR64_FC_INT64: u8 = 0x07;
+pub const NDR64_FC_FLOAT32: u8 = 0x0b;
+pub const NDR64_FC_FLOAT64: u8 = 0x0c;
+// Signedness matters to range checks only
+pub const NDR64_FC_UINT8: u8 = 0x01;
+pub const NDR64_FC_SIGNED_INT8: u8 = 0x02; // FC64_INT8, where NDR64_FC_INT8 is FC64_CHAR
+pub const NDR64_FC_UINT16: u8 = 0x03;
+pub const NDR64_FC_UINT32: u8 = 0x06;
+pub const NDR64_FC_RANGE: u8 = 0xa4; // Base type value within bounds
+pub const NDR64_FC_SYSTEM_HANDLE: u8 = 0x3c; // Kernel object handle, duplicated over ALPC
+pub const NDR64_FC_BIND_CONTEXT: u8 = 0x70; // Context handle
+pub const NDR64_FC_PIPE: u8 = 0xa3; // Pipe, streaming its elements in chunks
+pub const NDR64_FC_UP: u8 = 0x21; // Unique pointer
+pub const NDR64_FC_FP: u8 = 0x23; // Full pointer, which may alias others
+pub const NDR64_FC_CONF_CHAR_STRING: u8 = 0x63; // Conformant character string
+pub const NDR64_FC_CONF_WCHAR_STRING: u8 = 0x64; // Conformant wide character string
+pub const NDR64_FC_CONF_ARRAY: u8 = 0x41; // Conformant array
+pub const NDR64_FC_BOGUS_ARRAY: u8 = 0x47; // Complex array, e.g. of pointers
+pub const NDR64_ARRAY_HAS_POINTER_INFO: u8 = 0x01; // The elements hold pointers
+pub const NDR64_FC_EXPR_VAR: u8 = 0x03; // Expression reading a stack variable
+pub const NDR64_FC_EXPR_OPER: u8 = 0x04; // Expression applying an operator
+pub const NDR64_OP_UNARY_INDIRECTION: u8 = 0x05; // Dereferences its operand
+pub const NDR64_FC_STRUCT: u8 = 0x30; // Simple structure, copied as a block
+pub const NDR64_FC_PSTRUCT: u8 = 0x31; // Structure with pointers, followed by their layout
+pub const NDR64_STRUCTURE_HAS_POINTER_INFO: u8 = 0x01; // The pointer layout follows
+pub const NDR64_FC_ENCAPSULATED_UNION: u8 = 0x50; // Union preceded by its discriminant
+
+// NDR64 Parameter Attributes
+pub const NDR64_IS_IN: u16 = 0x0008;
+pub const NDR64_IS_OUT: u16 = 0x0010;
+pub const NDR64_IS_RETURN: u16 = 0x0020;
+pub const NDR64_IS_BASE_TYPE: u16 = 0x0040;
+pub const NDR64_IS_BY_VALUE: u16 = 0x0080;
+pub const NDR64_MUST_SIZE: u16 = 0x0001;
+pub const NDR64_MUST_FREE: u16 = 0x0002;
+pub const NDR64_IS_PIPE: u16 = 0x0004;
+pub const NDR64_IS_SIMPLE_REF: u16 = 0x0100;
+pub const NDR64_USE_CACHE: u16 = 0x8000; // Allocated by the server stub
+
+// NDR64 Procedure flags
+pub const NDR64_PROC_CLIENT_MUST_SIZE: u32 = 0x00040000;
+pub const NDR64_PROC_SERVER_MUST_SIZE: u32 = 0x01000000;
+pub const NDR64_PROC_SERVER_HAS_CORRELATION: u32 = 0x00200000;
+pub const NDR64_PROC_CLIENT_HAS_CORRELATION: u32 = 0x00400000;
+pub const NDR64_PROC_USES_FULL_PTR_PACKAGE: u32 = 0x00001000;
+pub const NDR64_PROC_USES_PIPES: u32 = 0x00004000; // Pipe parameters are streamed after the others
+pub const NDR64_PROC_HANDLES_EXCEPTIONS: u32 = 0x00018000; // Comm and fault statuses
+pub const NDR64_PROC_IS_ASYNC: u32 = 0x00000200; // The RPC_ASYNC_STATE precedes the binding handle
+
+// OI2 flags (INTERPRETER_OPT_FLAGS)
+pub const OI2_SERVER_MUST_SIZE: u8 = 0x01;
+
+// MIDL_STUB_DESC values
+pub const MIDL_STUB_DESC_CHECK_BOUNDS: i32 = 1;
+pub const MIDL_STUB_DESC_VERSION: u32 = 0x60001;
+pub const MIDL_STUB_DESC_MIDL_VERSION: u32 = 0x8010274;
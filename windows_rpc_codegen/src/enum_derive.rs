@@ -0,0 +1,126 @@
+//! `#[derive(NdrEnum)]`, describing fieldless enums to NDR.
+//!
+//! `#[repr(u16)]` enums are `FC_ENUM16` and `#[repr(u32)]` ones `FC_ENUM32` in NDR 2.0.
+//! Stubs hold enums as 32-bit integers in memory whatever their width on the wire, so the
+//! generated code passes them as `u32` and converts them from and to the enum.
+//!
+//! A value received from a peer may match no variant. It is rejected, unless a variant
+//! is marked `#[ndr(fallback)]`, which then stands for all unknown values.
+
+use quote::quote;
+use syn::{Data, DeriveInput, Fields};
+
+use crate::constants::{FC_ENUM16, FC_ENUM32};
+
+pub fn derive_ndr_enum(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrEnum can only be derived for enums",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "NdrEnum cannot be derived for generic enums",
+        ));
+    }
+    let Some(format_char) = repr_format_char(&input) else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrEnum requires `#[repr(u16)]` or `#[repr(u32)]`",
+        ));
+    };
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrEnum cannot be derived for enums without variants",
+        ));
+    }
+
+    let mut variants = vec![];
+    let mut fallback = None;
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                &variant.fields,
+                "NdrEnum can only be derived for enums without fields",
+            ));
+        }
+        if is_fallback(variant)? {
+            if fallback.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &variant.ident,
+                    "Only one variant can be marked `#[ndr(fallback)]`",
+                ));
+            }
+            fallback = Some(&variant.ident);
+        }
+        variants.push(&variant.ident);
+    }
+
+    let unknown = match fallback {
+        Some(variant) => quote! { std::option::Option::Some(Self::#variant) },
+        None => quote! { std::option::Option::None },
+    };
+    let name = &input.ident;
+
+    Ok(quote! {
+        impl windows_rpc::ndr::NdrEnum for #name {
+            const NDR_FORMAT_CHAR: u8 = #format_char;
+
+            fn to_wire(self) -> u32 {
+                self as u32
+            }
+
+            fn from_wire(value: u32) -> std::option::Option<Self> {
+                #(
+                    if value == Self::#variants as u32 {
+                        return std::option::Option::Some(Self::#variants);
+                    }
+                )*
+                #unknown
+            }
+        }
+    })
+}
+
+/// Returns the NDR 2.0 format character matching the `#[repr]` of the enum
+fn repr_format_char(input: &DeriveInput) -> Option<u8> {
+    let mut format_char = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("u16") {
+                format_char = Some(FC_ENUM16);
+            } else if meta.path.is_ident("u32") {
+                format_char = Some(FC_ENUM32);
+            }
+            Ok(())
+        });
+    }
+    format_char
+}
+
+/// Whether the variant is marked `#[ndr(fallback)]`
+fn is_fallback(variant: &syn::Variant) -> syn::Result<bool> {
+    let mut fallback = false;
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ndr"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("fallback") {
+                fallback = true;
+                Ok(())
+            } else {
+                Err(meta.error("Expected `fallback`"))
+            }
+        })?;
+    }
+    Ok(fallback)
+}
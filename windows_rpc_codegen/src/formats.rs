@@ -0,0 +1,685 @@
+//! NDR formats of an interface, and annotated listings of them.
+//!
+//! [`formats()`] returns the format strings the generated client and server embed. The
+//! `annotate_*` functions list format strings one field per line, in the layout of the
+//! format strings of MIDL's stub files (`/* offset */ bytes, /* description */`), so that
+//! they can be read and compared with MIDL's. The listings are decoded from the bytes
+//! alone, so they also apply to format strings MIDL generated; bytes that can't be decoded
+//! are listed without a description.
+//!
+//! The fragments of structs and unions are appended to the type format string when the
+//! generated code runs, and their offsets and the format characters of enums written into
+//! the proc format string; [`Formats::fixups`] lists those positions, which hold zeros, as
+//! [`Formats::type_fixups`] lists the offsets of full pointers to them in the type format
+//! string.
+
+use std::fmt::Write;
+
+use crate::constants::*;
+use crate::ndr::{generate_proc_header, generate_type_format_string};
+use crate::ndr64::{generate_ndr64_proc_buffer_code, generate_ndr64_type_format};
+use crate::types::{Interface, SystemHandleKind, Type};
+
+/// The NDR formats of an interface
+pub struct Formats {
+    /// NDR 2.0 proc format string: a header per method, followed by its parameters
+    pub proc_format: Vec<u8>,
+    /// Offset of the header of each method in `proc_format`, by opnum
+    pub proc_offsets: Vec<u16>,
+    /// NDR 2.0 type format string, without the fragments of structs and unions
+    pub type_format: Vec<u8>,
+    /// Positions in `proc_format` of the type offsets of structs and unions and of the
+    /// format characters of enums, filled when the generated code runs
+    pub fixups: Vec<(usize, Type)>,
+    /// Positions in `type_format` of the offsets of full pointers to structs and unions,
+    /// filled when the generated code runs
+    pub type_fixups: Vec<(usize, Type)>,
+    /// NDR64 formats of the base types and strings the procedures refer to
+    pub ndr64_type_format: Vec<u8>,
+    /// Block building the NDR64 procedure descriptors when the generated code runs, as
+    /// they point to each other and to `ndr64_type_format`
+    pub ndr64_descriptors: proc_macro2::TokenStream,
+}
+
+/// Returns the NDR formats of `interface`
+pub fn formats(interface: &Interface) -> Formats {
+    let (type_format, type_offsets) = generate_type_format_string(interface);
+    let (proc_format, proc_offsets, fixups) = generate_proc_header(interface, &type_offsets);
+    let (type_fixups, fixups): (Vec<_>, Vec<_>) =
+        fixups.into_iter().partition(|fixup| fixup.in_type_format);
+    Formats {
+        proc_format,
+        proc_offsets,
+        type_format,
+        fixups: fixups
+            .into_iter()
+            .map(|fixup| (fixup.position, fixup.r#type))
+            .collect(),
+        type_fixups: type_fixups
+            .into_iter()
+            .map(|fixup| (fixup.position, fixup.r#type))
+            .collect(),
+        ndr64_type_format: generate_ndr64_type_format(interface),
+        ndr64_descriptors: generate_ndr64_proc_buffer_code(interface),
+    }
+}
+
+/// Lists an NDR 2.0 proc format string of `Oicf` procedure headers, which start at
+/// `proc_offsets`. Descriptors between the parameters a header counts and the next
+/// procedure are listed as uncounted parameters.
+pub fn annotate_proc_format(format: &[u8], proc_offsets: &[u16]) -> String {
+    let mut listing = Listing::new(format);
+    for (index, &offset) in proc_offsets.iter().enumerate() {
+        let end = proc_offsets
+            .get(index + 1)
+            .map_or(format.len(), |&next| usize::from(next));
+        listing.raw_until(usize::from(offset));
+        if annotate_procedure(&mut listing).is_none() {
+            continue;
+        }
+        while listing.position + PARAM_DESCRIPTOR_SIZE <= end {
+            annotate_parameter(&mut listing, "Uncounted parameter");
+        }
+    }
+    listing.finish()
+}
+
+/// Lists an NDR 2.0 type format string
+pub fn annotate_type_format(format: &[u8]) -> String {
+    let mut listing = Listing::new(format);
+    // Type format strings start with a short of padding
+    if listing.peek_short(0) == Some(0) {
+        listing.short(|value| value.to_string());
+    }
+    while listing.remaining() > 1 && annotate_type(&mut listing).is_some() {}
+    listing.finish()
+}
+
+/// Lists an NDR64 type format of base types and conformant strings
+pub fn annotate_ndr64_type_format(format: &[u8]) -> String {
+    let mut listing = Listing::new(format);
+    while let Some(format_char) = listing.peek(0) {
+        if format_char == NDR64_FC_CONF_WCHAR_STRING || format_char == NDR64_FC_CONF_CHAR_STRING {
+            if listing.remaining() < 4 {
+                break;
+            }
+            listing.byte(|format_char| match format_char {
+                NDR64_FC_CONF_CHAR_STRING => "FC64_CONF_CHAR_STRING".to_string(),
+                _ => "FC64_CONF_WCHAR_STRING".to_string(),
+            });
+            listing.byte(|flags| format!("Flags = {flags:#x}"));
+            listing.short(|size| format!("Element size = {size}"));
+        } else if let Some(name) = ndr64_base_type_name(format_char) {
+            listing.byte(|_| name.to_string());
+        } else {
+            break;
+        }
+    }
+    listing.finish()
+}
+
+/// Lists a procedure header and its parameters, or returns `None` if the format ends or
+/// holds something else
+fn annotate_procedure(listing: &mut Listing) -> Option<()> {
+    let handle_type = listing.peek(0)?;
+    let oi_flags = listing.peek(1)?;
+    let has_rpc_flags = oi_flags & Oi_HAS_RPCFLAGS != 0;
+    let opnum = listing.peek_short(if has_rpc_flags { 6 } else { 2 })?;
+    listing.heading(&format!("Procedure {opnum}"));
+
+    listing.byte(|handle_type| match handle_type {
+        0 => "Explicit handle".to_string(),
+        _ => format_char_name(handle_type),
+    })?;
+    listing.byte(|flags| flag_names("Oi flags:", flags.into(), OI_FLAG_NAMES))?;
+    if has_rpc_flags {
+        listing.long(|flags| flag_names("Rpc flags:", flags, RPC_FLAG_NAMES))?;
+    }
+    listing.short(|opnum| format!("Opnum = {opnum}"))?;
+    listing.short(|size| format!("Stack size = {size}"))?;
+    if handle_type == 0 {
+        annotate_explicit_handle(listing)?;
+    }
+    listing.short(|size| format!("Client buffer size = {size}"))?;
+    listing.short(|size| format!("Server buffer size = {size}"))?;
+    let oi2_flags = listing.byte(|flags| flag_names("Oi2 flags:", flags.into(), OI2_FLAG_NAMES))?;
+    let param_count = listing.byte(|count| format!("{count} parameters"))?;
+
+    if oi2_flags & OI2_HAS_EXTENSIONS != 0 {
+        let size = listing.byte(|size| format!("Extension size = {size}"))?;
+        listing.byte(|flags| flag_names("Ext flags:", flags.into(), EXT_FLAG_NAMES))?;
+        listing.short(|hint| format!("Client corr hint = {hint}"))?;
+        listing.short(|hint| format!("Server corr hint = {hint}"))?;
+        listing.short(|index| format!("Notify index = {index}"))?;
+        // The float/double mask is only present for 64-bit targets
+        if size >= 10 {
+            listing.short(|mask| format!("Float/double mask = {mask:#x}"))?;
+        }
+        for _ in 10..size {
+            listing.byte(|byte| byte.to_string())?;
+        }
+    }
+
+    for _ in 0..param_count {
+        let attributes = listing.peek_short(0)?;
+        if attributes & PARAM_ATTRIBUTES_IS_RETURN != 0 {
+            annotate_parameter(listing, "Return value")?;
+        } else {
+            annotate_parameter(listing, "Parameter")?;
+        }
+    }
+    Some(())
+}
+
+/// Lists a parameter descriptor under `heading`
+fn annotate_parameter(listing: &mut Listing, heading: &str) -> Option<()> {
+    let attributes = listing.peek_short(0)?;
+    listing.heading(heading);
+    listing.short(|flags| flag_names("Flags:", flags.into(), PARAM_FLAG_NAMES))?;
+    listing.short(|offset| format!("Stack offset = {offset}"))?;
+    if attributes & PARAM_ATTRIBUTES_IS_BASE_TYPE != 0 {
+        listing.byte(format_char_name)?;
+        listing.byte(|byte| byte.to_string())?;
+    } else {
+        listing.short(|offset| format!("Type offset = {offset}"))?;
+    }
+    Some(())
+}
+
+/// Lists the explicit handle description of a procedure header
+fn annotate_explicit_handle(listing: &mut Listing) -> Option<()> {
+    let handle_type = listing.peek(0)?;
+    listing.byte(format_char_name)?;
+    listing.byte(|flags| format!("Flags = {flags:#x}"))?;
+    listing.short(|offset| format!("Stack offset = {offset}"))?;
+    match handle_type {
+        FC_BIND_GENERIC => {
+            listing.byte(|index| format!("Binding routine pair index = {index}"))?;
+            listing.byte(format_char_name)?;
+        }
+        FC_BIND_CONTEXT => {
+            listing.byte(|index| format!("Context rundown routine index = {index}"))?;
+            listing.byte(|param| format!("Parameter number = {param}"))?;
+        }
+        _ => {}
+    }
+    Some(())
+}
+
+/// Lists a type description, or returns `None` if it isn't one the listing decodes
+fn annotate_type(listing: &mut Listing) -> Option<()> {
+    let format_char = listing.peek(0)?;
+    match format_char {
+        FC_RP | FC_UP | FC_OP | FC_FP => {
+            let flags = listing.peek(1)?;
+            listing.bytes(2, pointer_name(format_char, flags))?;
+            if flags & FC_SIMPLE_POINTER != 0 {
+                listing.byte(format_char_name)?;
+                listing.byte(format_char_name)?;
+            } else {
+                listing.offset()?;
+            }
+        }
+        FC_C_CSTRING | FC_C_WSTRING => {
+            listing.byte(format_char_name)?;
+            listing.byte(format_char_name)?;
+        }
+        FC_RANGE => {
+            listing.byte(format_char_name)?;
+            listing.byte(|base_type| format_char_name(base_type & 0x0f))?;
+            listing.long(|min| format!("Min = {}", min as i32))?;
+            listing.long(|max| format!("Max = {}", max as i32))?;
+        }
+        FC_SYSTEM_HANDLE => {
+            listing.byte(format_char_name)?;
+            listing.byte(|kind| match SystemHandleKind::from_fc_value(kind) {
+                Some(kind) => format!("sh_{}", kind.name()),
+                None => format!("Handle type = {kind}"),
+            })?;
+            listing.long(|access| format!("Desired access = {access:#x}"))?;
+        }
+        FC_BIND_CONTEXT => {
+            listing.byte(format_char_name)?;
+            listing.byte(|flags| flag_names("Ctxt flags:", flags.into(), CONTEXT_FLAG_NAMES))?;
+            listing.byte(|index| format!("Rundown routine index = {index}"))?;
+            listing.byte(|num| format!("Param num = {num}"))?;
+        }
+        FC_CARRAY => {
+            let alignment = listing.peek(1)?;
+            listing.bytes(2, format!("FC_CARRAY {alignment}"))?;
+            listing.short(|size| format!("Element size = {size}"))?;
+            annotate_correlation(listing)?;
+            annotate_members(listing)?;
+        }
+        FC_BOGUS_ARRAY => {
+            let alignment = listing.peek(1)?;
+            listing.bytes(2, format!("FC_BOGUS_ARRAY {alignment}"))?;
+            listing.short(|count| format!("Number of elements = {count}"))?;
+            annotate_correlation(listing)?;
+            if listing.peek_short(0)? == 0xffff && listing.peek_short(2)? == 0xffff {
+                listing.long(|_| "No variance".to_string())?;
+                listing.short(|flags| flag_names("Corr flags:", flags.into(), CORR_FLAG_NAMES))?;
+                annotate_correlation_range(listing)?;
+            } else {
+                annotate_correlation(listing)?;
+            }
+            // The element, described inline, e.g. a pointer
+            if matches!(listing.peek(0)?, FC_RP | FC_UP | FC_OP | FC_FP) {
+                annotate_type(listing)?;
+            }
+            annotate_members(listing)?;
+        }
+        FC_STRUCT => {
+            let alignment = listing.peek(1)?;
+            listing.bytes(2, format!("FC_STRUCT {alignment}"))?;
+            listing.short(|size| format!("Memory size = {size}"))?;
+            annotate_members(listing)?;
+        }
+        FC_BOGUS_STRUCT => {
+            let alignment = listing.peek(1)?;
+            listing.bytes(2, format!("FC_BOGUS_STRUCT {alignment}"))?;
+            listing.short(|size| format!("Memory size = {size}"))?;
+            if listing.peek_short(0)? == 0 {
+                listing.short(|_| "No conformant array".to_string())?;
+            } else {
+                listing.offset()?;
+            }
+            // The pointer layout, descriptions of the `FC_POINTER` members, follows
+            listing.offset()?;
+            annotate_members(listing)?;
+        }
+        FC_ENCAPSULATED_UNION => {
+            let switch = listing.peek(1)?;
+            listing.bytes(
+                2,
+                format!(
+                    "FC_ENCAPSULATED_UNION, increment {}, {}",
+                    switch >> 4,
+                    format_char_name(switch & 0x0f)
+                ),
+            )?;
+            listing.short(|size| format!("Memory size = {size}"))?;
+            let arm_count = listing.short(|arms| format!("{} arms", arms & 0x0fff))? & 0x0fff;
+            for _ in 0..arm_count {
+                listing.long(|case| format!("Case = {case}"))?;
+                listing.short(|arm| match arm {
+                    0 => "Empty arm".to_string(),
+                    _ if arm & 0xff00 == FC_UNION_ARM_SIMPLE => format_char_name(arm as u8),
+                    _ => format!("Arm offset = {arm}"),
+                })?;
+            }
+            listing.short(|default| match default {
+                FC_UNION_NO_DEFAULT => "No default".to_string(),
+                _ => format!("Default arm = {default}"),
+            })?;
+        }
+        FC_PIPE => {
+            listing.byte(format_char_name)?;
+            listing.byte(|alignment| format!("Alignment = {alignment}"))?;
+            listing.offset()?;
+            listing.short(|size| format!("Memory size = {size}"))?;
+            listing.short(|size| format!("Buffer size = {size}"))?;
+        }
+        // The element type of a pipe, which its FC_PIPE follows
+        FC_BYTE..=FC_DOUBLE if listing.peek(1)? == FC_PAD => {
+            listing.byte(format_char_name)?;
+            listing.byte(format_char_name)?;
+        }
+        0 => {
+            listing.byte(|byte| byte.to_string())?;
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Lists the correlation descriptor of a conformant array, in the new format
+fn annotate_correlation(listing: &mut Listing) -> Option<()> {
+    listing.byte(|kind| {
+        let source = match kind & 0xf0 {
+            FC_TOP_LEVEL_CONFORMANCE => "parameter",
+            FC_POINTER_CONFORMANCE => "field pointer",
+            0 => "field",
+            _ => "other",
+        };
+        format!("Corr desc: {source}, {}", format_char_name(kind & 0x0f))
+    })?;
+    listing.byte(|operator| match operator {
+        0 => "No operator".to_string(),
+        _ => format_char_name(operator),
+    })?;
+    listing.short(|offset| format!("Offset = {offset}"))?;
+    listing.short(|flags| flag_names("Corr flags:", flags.into(), CORR_FLAG_NAMES))?;
+    annotate_correlation_range(listing)
+}
+
+/// Lists the range following a correlation descriptor, if any
+fn annotate_correlation_range(listing: &mut Listing) -> Option<()> {
+    // The range of procedures with ranges on conformance, told apart from the element
+    // types that follow descriptors without one, which are never followed by a zero
+    if matches!(listing.peek(0)?, FC_CORR_RANGE_NONE | FC_CORR_RANGE) && listing.peek(1)? == 0 {
+        listing.bytes(
+            2,
+            match listing.peek(0)? {
+                FC_CORR_RANGE => "Corr range".to_string(),
+                _ => "No corr range".to_string(),
+            },
+        )?;
+        listing.long(|min| format!("Min = {min}"))?;
+        listing.long(|max| format!("Max = {max}"))?;
+    }
+    Some(())
+}
+
+/// Lists the member or element format characters of a compound type, up to `FC_END`
+fn annotate_members(listing: &mut Listing) -> Option<()> {
+    while listing.byte(format_char_name)? != FC_END {}
+    Some(())
+}
+
+/// An annotated listing of a format string being built
+struct Listing<'a> {
+    format: &'a [u8],
+    position: usize,
+    text: String,
+}
+
+impl<'a> Listing<'a> {
+    fn new(format: &'a [u8]) -> Self {
+        Listing {
+            format,
+            position: 0,
+            text: String::new(),
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.format.len() - self.position
+    }
+
+    /// The byte at `offset` from the current position
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.format.get(self.position + offset).copied()
+    }
+
+    /// The little-endian short at `offset` from the current position
+    fn peek_short(&self, offset: usize) -> Option<u16> {
+        Some(u16::from_le_bytes([
+            self.peek(offset)?,
+            self.peek(offset + 1)?,
+        ]))
+    }
+
+    /// Starts a section, like MIDL's `/* Procedure name */`
+    fn heading(&mut self, title: &str) {
+        writeln!(self.text, "\n\t/* {title} */\n").unwrap();
+    }
+
+    fn line(&mut self, value: &str, description: &str) {
+        writeln!(
+            self.text,
+            "/* {:>4} */\t{value},\t/* {description} */",
+            self.position
+        )
+        .unwrap();
+    }
+
+    /// Lists a byte
+    fn byte(&mut self, describe: impl FnOnce(u8) -> String) -> Option<u8> {
+        let byte = self.peek(0)?;
+        self.line(&format!("{byte:#x}"), &describe(byte));
+        self.position += 1;
+        Some(byte)
+    }
+
+    /// Lists `count` bytes on one line
+    fn bytes(&mut self, count: usize, description: String) -> Option<()> {
+        let bytes = self.format.get(self.position..self.position + count)?;
+        let value = bytes
+            .iter()
+            .map(|byte| format!("{byte:#x}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.line(&value, &description);
+        self.position += count;
+        Some(())
+    }
+
+    /// Lists a short, like MIDL's `NdrFcShort`
+    fn short(&mut self, describe: impl FnOnce(u16) -> String) -> Option<u16> {
+        let value = self.peek_short(0)?;
+        self.line(&format!("NdrFcShort( {value:#x} )"), &describe(value));
+        self.position += 2;
+        Some(value)
+    }
+
+    /// Lists a long, like MIDL's `NdrFcLong`
+    fn long(&mut self, describe: impl FnOnce(u32) -> String) -> Option<u32> {
+        let bytes = self.format.get(self.position..self.position + 4)?;
+        let value = u32::from_le_bytes(bytes.try_into().unwrap());
+        self.line(&format!("NdrFcLong( {value:#x} )"), &describe(value));
+        self.position += 4;
+        Some(value)
+    }
+
+    /// Lists an offset relative to its own position, with the position it points to
+    fn offset(&mut self) -> Option<()> {
+        let position = self.position;
+        self.short(|offset| {
+            let target = position as i64 + i64::from(offset as i16);
+            format!("Offset = {} ({target})", offset as i16)
+        })?;
+        Some(())
+    }
+
+    /// Lists the bytes up to `position` without decoding them
+    fn raw_until(&mut self, position: usize) {
+        while self.position < position && self.byte(|byte| byte.to_string()).is_some() {}
+    }
+
+    /// Lists the bytes left undecoded, and returns the listing
+    fn finish(mut self) -> String {
+        self.raw_until(self.format.len());
+        self.text
+    }
+}
+
+const FC_OP: u8 = 0x13;
+const FC_BIND_CONTEXT: u8 = 0x30;
+const FC_BIND_GENERIC: u8 = 0x31;
+const OI2_HAS_EXTENSIONS: u8 = 0x40;
+/// Attributes, stack offset, and format character or type offset
+const PARAM_DESCRIPTOR_SIZE: usize = 6;
+
+const OI_FLAG_NAMES: &[(u32, &str)] = &[
+    (Oi_FULL_PTR_USED as u32, "full ptr"),
+    (0x02, "rpcss alloc"),
+    (0x04, "object proc"),
+    (Oi_HAS_RPCFLAGS as u32, "has rpc flags"),
+    (Oi_HAS_COMM_OR_FAULT as u32, "has comm or fault"),
+    (Oi_USE_NEW_INIT_ROUTINES as u32, "new init routines"),
+];
+
+const RPC_FLAG_NAMES: &[(u32, &str)] = &[
+    (RPC_NCA_FLAGS_IDEMPOTENT, "idempotent"),
+    (RPC_NCA_FLAGS_BROADCAST, "broadcast"),
+    (RPC_NCA_FLAGS_MAYBE, "maybe"),
+];
+
+const OI2_FLAG_NAMES: &[(u32, &str)] = &[
+    (OI2_SERVER_MUST_SIZE as u32, "srv must size"),
+    (OI2_CLIENT_MUST_SIZE as u32, "clt must size"),
+    (0x04, "has return"),
+    (OI2_HAS_PIPES as u32, "has pipes"),
+    (0x20, "has async uuid"),
+    (OI2_HAS_EXTENSIONS as u32, "has ext"),
+    (OI2_HAS_ASYNC_HANDLE as u32, "has async handle"),
+];
+
+const EXT_FLAG_NAMES: &[(u32, &str)] = &[
+    (
+        INTERPRETER_OPT_FLAGS2_NEW_CORRELATION_DESCRIPTOR as u32,
+        "new corr desc",
+    ),
+    (
+        INTERPRETER_OPT_FLAGS2_CLIENT_CORR_CHECK as u32,
+        "clt corr check",
+    ),
+    (
+        INTERPRETER_OPT_FLAGS2_SERVER_CORR_CHECK as u32,
+        "srv corr check",
+    ),
+    (0x08, "has notify"),
+    (0x10, "has notify2"),
+    (
+        INTERPRETER_OPT_FLAGS2_RANGE_ON_CONFORMANCE as u32,
+        "range on conformance",
+    ),
+];
+
+const PARAM_FLAG_NAMES: &[(u32, &str)] = &[
+    (PARAM_ATTRIBUTES_MUST_SIZE as u32, "must size"),
+    (PARAM_ATTRIBUTES_MUST_FREE as u32, "must free"),
+    (PARAM_ATTRIBUTES_IS_PIPE as u32, "pipe"),
+    (PARAM_ATTRIBUTES_IS_IN as u32, "in"),
+    (PARAM_ATTRIBUTES_IS_OUT as u32, "out"),
+    (PARAM_ATTRIBUTES_IS_RETURN as u32, "return"),
+    (PARAM_ATTRIBUTES_IS_BASE_TYPE as u32, "base type"),
+    (PARAM_ATTRIBUTES_IS_BY_VALUE as u32, "by val"),
+    (PARAM_ATTRIBUTES_IS_SIMPLE_REF as u32, "simple ref"),
+    (0x200, "dont call freeinst"),
+    (0x400, "save for async finish"),
+    (
+        PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8 as u32,
+        "srv alloc size=8",
+    ),
+    (
+        PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_16 as u32,
+        "srv alloc size=16",
+    ),
+    (
+        PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_32 as u32,
+        "srv alloc size=32",
+    ),
+];
+
+const CORR_FLAG_NAMES: &[(u32, &str)] = &[
+    (FC_CORR_FLAGS_EARLY as u32, "early"),
+    (0x02, "split"),
+    (0x04, "is iid is"),
+    (0x08, "dont check"),
+];
+
+const CONTEXT_FLAG_NAMES: &[(u32, &str)] = &[
+    (NDR_CONTEXT_HANDLE_CANNOT_BE_NULL as u32, "cannot be null"),
+    (NDR_CONTEXT_HANDLE_IS_OUT as u32, "out"),
+    (NDR_CONTEXT_HANDLE_IS_IN as u32, "in"),
+    (NDR_CONTEXT_HANDLE_IS_VIA_PTR as u32, "via ptr"),
+];
+
+const POINTER_FLAG_NAMES: &[(u8, &str)] = &[
+    (0x01, "[all_nodes]"),
+    (0x02, "[dont_free]"),
+    (0x04, "[alloced_on_stack]"),
+    (FC_SIMPLE_POINTER, "[simple_pointer]"),
+    (0x10, "[pointer_deref]"),
+];
+
+/// Describes flags MIDL's way, e.g. `Flags:  in, base type,`, with unknown bits in hex
+fn flag_names(prefix: &str, flags: u32, names: &[(u32, &str)]) -> String {
+    let mut description = format!("{prefix} ");
+    let mut unknown = flags;
+    for &(flag, name) in names {
+        if flags & flag != 0 {
+            write!(description, " {name},").unwrap();
+            unknown &= !flag;
+        }
+    }
+    if unknown != 0 {
+        write!(description, " {unknown:#x},").unwrap();
+    }
+    description
+}
+
+/// Describes a pointer, e.g. `FC_RP [alloced_on_stack] [pointer_deref]`
+fn pointer_name(format_char: u8, flags: u8) -> String {
+    let mut description = format_char_name(format_char);
+    for &(flag, name) in POINTER_FLAG_NAMES {
+        if flags & flag != 0 {
+            write!(description, " {name}").unwrap();
+        }
+    }
+    description
+}
+
+/// Name of an NDR 2.0 format character, or its value if unknown
+fn format_char_name(format_char: u8) -> String {
+    let name = match format_char {
+        0x01 => "FC_BYTE",
+        0x02 => "FC_CHAR",
+        0x03 => "FC_SMALL",
+        0x04 => "FC_USMALL",
+        0x05 => "FC_WCHAR",
+        0x06 => "FC_SHORT",
+        0x07 => "FC_USHORT",
+        0x08 => "FC_LONG",
+        0x09 => "FC_ULONG",
+        0x0a => "FC_FLOAT",
+        0x0b => "FC_HYPER",
+        0x0c => "FC_DOUBLE",
+        0x0d => "FC_ENUM16",
+        0x0e => "FC_ENUM32",
+        0x10 => "FC_ERROR_STATUS_T",
+        0x11 => "FC_RP",
+        0x12 => "FC_UP",
+        0x13 => "FC_OP",
+        0x14 => "FC_FP",
+        0x15 => "FC_STRUCT",
+        0x16 => "FC_PSTRUCT",
+        0x17 => "FC_CSTRUCT",
+        0x1a => "FC_BOGUS_STRUCT",
+        0x1b => "FC_CARRAY",
+        0x1c => "FC_CVARRAY",
+        0x1d => "FC_SMFARRAY",
+        0x21 => "FC_BOGUS_ARRAY",
+        0x22 => "FC_C_CSTRING",
+        0x25 => "FC_C_WSTRING",
+        0x2a => "FC_ENCAPSULATED_UNION",
+        0x2b => "FC_NON_ENCAPSULATED_UNION",
+        0x30 => "FC_BIND_CONTEXT",
+        0x31 => "FC_BIND_GENERIC",
+        0x32 => "FC_BIND_PRIMITIVE",
+        0x36 => "FC_POINTER",
+        0x3c => "FC_SYSTEM_HANDLE",
+        0x3d => "FC_STRUCTPAD1",
+        0x3e => "FC_STRUCTPAD2",
+        0x3f => "FC_STRUCTPAD3",
+        0x40 => "FC_STRUCTPAD4",
+        0x41 => "FC_STRUCTPAD5",
+        0x42 => "FC_STRUCTPAD6",
+        0x43 => "FC_STRUCTPAD7",
+        0x4c => "FC_EMBEDDED_COMPLEX",
+        0x54 => "FC_DEREFERENCE",
+        0x5b => "FC_END",
+        0x5c => "FC_PAD",
+        0xb5 => "FC_PIPE",
+        0xb7 => "FC_RANGE",
+        0xb9 => "FC_INT3264",
+        0xba => "FC_UINT3264",
+        _ => return format!("{format_char:#x}"),
+    };
+    name.to_string()
+}
+
+/// Name of an NDR64 base type format character
+fn ndr64_base_type_name(format_char: u8) -> Option<&'static str> {
+    Some(match format_char {
+        NDR64_FC_INT8 => "FC64_INT8",
+        NDR64_FC_INT16 => "FC64_INT16",
+        NDR64_FC_INT32 => "FC64_INT32",
+        NDR64_FC_INT64 => "FC64_INT64",
+        NDR64_FC_FLOAT32 => "FC64_FLOAT32",
+        NDR64_FC_FLOAT64 => "FC64_FLOAT64",
+        _ => return None,
+    })
+}
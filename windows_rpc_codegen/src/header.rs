@@ -0,0 +1,113 @@
+//! C header of a trait, exposed as the `HEADER` const of the generated types and
+//! optionally written to a file with `header("path")`.
+//!
+//! The header declares what C callers of the interface need, in the layout of the
+//! headers MIDL generates: the interface GUID and version, a prototype per procedure
+//! with the parameters of [`crate::idl`], the client and server interface handles, and
+//! `MIDL_user_allocate`/`MIDL_user_free`. The prototypes are implemented by the client
+//! stubs MIDL generates from the `IDL` const, so C callers don't write IDL themselves.
+//! Structs, unions and enums are referred to by name and have to be declared before
+//! the header is included.
+
+use std::fmt::Write;
+
+use crate::idl::{guid_string, pipe_elements, signature};
+use crate::types::Interface;
+
+/// Generates the C header of `interface`
+pub fn generate_header(interface: &Interface) -> String {
+    let name = &interface.name;
+    let guid = interface.uuid;
+    let (major, minor) = (interface.version.major, interface.version.minor);
+    let data4 = (0..8)
+        .map(|i| format!("0x{:02x}", (guid >> (56 - 8 * i)) & 0xff))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut header = format!(
+        "// Generated by windows-rpc from the `{name}` trait\n\
+         #ifndef __{name}_h__\n\
+         #define __{name}_h__\n\
+         \n\
+         #include <rpc.h>\n\
+         #include <rpcndr.h>\n\
+         \n\
+         #ifdef __cplusplus\n\
+         extern \"C\" {{\n\
+         #endif\n\
+         \n\
+         /* {{{}}} */\n\
+         static const GUID {name}_GUID = {{ 0x{:08x}, 0x{:04x}, 0x{:04x}, {{ {data4} }} }};\n\
+         #define {name}_VERSION_MAJOR {major}\n\
+         #define {name}_VERSION_MINOR {minor}\n\
+         \n",
+        guid_string(guid),
+        guid >> 96,
+        (guid >> 80) & 0xffff,
+        (guid >> 64) & 0xffff,
+    );
+    // Pipe types are structures of the procedures streaming their elements, as MIDL
+    // declares them
+    for element in pipe_elements(interface) {
+        let (c_type, pipe) = (element.idl_name(), element.idl_pipe_name());
+        writeln!(
+            header,
+            "typedef struct pipe_{pipe}\n\
+             {{\n    \
+                 void (__RPC_USER * pull) (char * state, {c_type} * buf, unsigned long esize, unsigned long * ecount);\n    \
+                 void (__RPC_USER * push) (char * state, {c_type} * buf, unsigned long ecount);\n    \
+                 void (__RPC_USER * alloc) (char * state, unsigned long bsize, {c_type} * * buf, unsigned long * bcount);\n    \
+                 char * state;\n\
+             }} {pipe};\n"
+        )
+        .unwrap();
+    }
+    // Callbacks are implemented by the client, and called by the server stubs
+    let callbacks = interface.callbacks.iter().map(|method| (method, true));
+    for (method, callback) in interface
+        .methods
+        .iter()
+        .map(|method| (method, false))
+        .chain(callbacks)
+    {
+        let mut signature = signature(method);
+        if callback {
+            signature.attributes.insert(0, "callback");
+        }
+        let attributes = if signature.attributes.is_empty() {
+            String::new()
+        } else {
+            format!("/* [{}] */ ", signature.attributes.join(", "))
+        };
+        let parameters = signature
+            .parameters
+            .iter()
+            .map(|p| format!("/* [{}] */ {} {}", p.attributes, p.c_type, p.name))
+            .collect::<Vec<_>>();
+        writeln!(
+            header,
+            "{attributes}{} {}({});",
+            signature.return_type,
+            method.name,
+            parameters.join(", ")
+        )
+        .unwrap();
+    }
+    write!(
+        header,
+        "\n\
+         extern RPC_IF_HANDLE {name}_v{major}_{minor}_c_ifspec;\n\
+         extern RPC_IF_HANDLE {name}_v{major}_{minor}_s_ifspec;\n\
+         \n\
+         void* __RPC_USER MIDL_user_allocate(size_t size);\n\
+         void __RPC_USER MIDL_user_free(void* pointer);\n\
+         \n\
+         #ifdef __cplusplus\n\
+         }}\n\
+         #endif\n\
+         \n\
+         #endif\n"
+    )
+    .unwrap();
+    header
+}
@@ -0,0 +1,388 @@
+//! MIDL interface definition of a trait, exposed as the `IDL` const of the generated
+//! types and optionally written to a file with `idl("path")`.
+//!
+//! The definition describes the same wire format as the generated stubs, so C and C++
+//! peers compiled from it with MIDL interoperate with the Rust ones. Parameters the
+//! stubs add on the wire are spelled out: the explicit binding handle, the lengths of
+//! slices, and the `[out]` pointers that return values other than base types and enums
+//! travel through. Structs, unions and enums are referred to by name; their typedefs
+//! have to be provided to MIDL separately, except for the Win32 structs of
+//! [`WTYPES_STRUCTS`], which are imported from `wtypes.idl`.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::types::{BaseType, Interface, Method, Parameter, Type};
+
+/// Win32 structs declared by MIDL's `wtypes.idl`, by their IDL name, with the Rust type
+/// implementing `NdrStruct` for them
+pub const WTYPES_STRUCTS: [(&str, &str); 3] = [
+    ("FILETIME", "windows::Win32::Foundation::FILETIME"),
+    ("SYSTEMTIME", "windows::Win32::Foundation::SYSTEMTIME"),
+    ("LARGE_INTEGER", "windows_rpc::LARGE_INTEGER"),
+];
+
+/// Name of the hidden `[out]` parameter of return values that aren't returned directly
+pub const RETURN_NAME: &str = "return_value";
+
+/// Name of the hidden `[out]` parameter of the error codes of `Result<T, E>` return values
+pub const ERROR_NAME: &str = "service_error";
+
+/// A parameter of a procedure, as declared in IDL
+pub struct Declaration {
+    /// MIDL attributes, e.g. `in, string`
+    pub attributes: String,
+    /// C type, e.g. `const wchar_t*`
+    pub c_type: String,
+    pub name: String,
+}
+
+impl Declaration {
+    fn new(attributes: impl Into<String>, c_type: impl Into<String>, name: &str) -> Self {
+        Declaration {
+            attributes: attributes.into(),
+            c_type: c_type.into(),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// A procedure of the interface, as declared in IDL
+pub struct Signature {
+    /// Procedure attributes, e.g. `idempotent`
+    pub attributes: Vec<&'static str>,
+    /// C return type, `void` for methods returning through an `[out]` parameter
+    pub return_type: String,
+    pub parameters: Vec<Declaration>,
+}
+
+/// Generates the IDL of `interface`
+pub fn generate_idl(interface: &Interface) -> String {
+    let mut idl = format!(
+        "// Generated by windows-rpc from the `{}` trait\n{}\
+         [\n    \
+             uuid({}),\n    \
+             version({}.{}),\n    \
+             pointer_default(unique)\n\
+         ]\n\
+         interface {}\n{{\n",
+        interface.name,
+        if uses_wtypes_structs(interface) {
+            "import \"wtypes.idl\";\n\n"
+        } else {
+            ""
+        },
+        guid_string(interface.uuid),
+        interface.version.major,
+        interface.version.minor,
+        interface.name,
+    );
+    let pipe_elements = pipe_elements(interface);
+    for element in &pipe_elements {
+        writeln!(
+            idl,
+            "    typedef pipe {} {};",
+            element.idl_name(),
+            element.idl_pipe_name()
+        )
+        .unwrap();
+    }
+    if !pipe_elements.is_empty() {
+        idl.push('\n');
+    }
+    // Callbacks follow the methods, numbered on their own
+    let callbacks = interface.callbacks.iter().map(|method| (method, true));
+    for (method, callback) in interface
+        .methods
+        .iter()
+        .map(|method| (method, false))
+        .chain(callbacks)
+    {
+        let mut signature = signature(method);
+        if callback {
+            signature.attributes.insert(0, "callback");
+        }
+        let attributes = if signature.attributes.is_empty() {
+            String::new()
+        } else {
+            format!("[{}] ", signature.attributes.join(", "))
+        };
+        let parameters = signature
+            .parameters
+            .iter()
+            .map(|p| format!("[{}] {} {}", p.attributes, p.c_type, p.name))
+            .collect::<Vec<_>>();
+        writeln!(
+            idl,
+            "    {attributes}{} {}({});",
+            signature.return_type,
+            method.name,
+            parameters.join(", ")
+        )
+        .unwrap();
+    }
+    idl.push_str("}\n");
+    idl
+}
+
+/// Whether any method of `interface` passes one of the [`WTYPES_STRUCTS`], returns
+/// strings as the `LPWSTR`s of `wtypes.idl`, or returns its `HRESULT`
+fn uses_wtypes_structs(interface: &Interface) -> bool {
+    interface
+        .methods
+        .iter()
+        .chain(&interface.callbacks)
+        .any(|method| {
+            method.hresult
+                || method
+                    .parameters
+                    .iter()
+                    .map(|p| &p.r#type)
+                    .chain(&method.return_type)
+                    .any(|t| {
+                        *t == Type::StringVec
+                            || matches!(t, Type::Struct(path)
+                        if WTYPES_STRUCTS.iter().any(|(name, _)| *name == type_name(path)))
+                    })
+        })
+}
+
+/// Returns the element types of the pipes of `interface`, whose pipe types are declared
+/// before the procedures
+pub fn pipe_elements(interface: &Interface) -> Vec<BaseType> {
+    let mut elements = vec![];
+    for param in interface
+        .methods
+        .iter()
+        .flat_map(|method| &method.parameters)
+    {
+        if let Type::Pipe(element) = param.r#type
+            && !elements.contains(&element)
+        {
+            elements.push(element);
+        }
+    }
+    elements
+}
+
+/// Writes generated `contents` to `path`, relative to the manifest directory of the
+/// crate being compiled, if they changed.
+pub fn write_generated(path: &syn::LitStr, contents: &str) -> syn::Result<()> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let full_path = PathBuf::from(manifest_dir).join(path.value());
+    if std::fs::read_to_string(&full_path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    std::fs::write(&full_path, contents).map_err(|e| {
+        syn::Error::new_spanned(
+            path,
+            format!("Failed to write {}: {e}", full_path.display()),
+        )
+    })
+}
+
+/// Returns the canonical form of a GUID, e.g. `12345678-1234-1234-1234-123456789abc`
+pub fn guid_string(guid: u128) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        guid >> 96,
+        (guid >> 80) & 0xffff,
+        (guid >> 64) & 0xffff,
+        (guid >> 48) & 0xffff,
+        guid & 0xffff_ffff_ffff,
+    )
+}
+
+/// Declares a method as a procedure taking an explicit binding handle
+pub fn signature(method: &Method) -> Signature {
+    let mut attributes = vec![];
+    if method.maybe {
+        attributes.push("maybe");
+    }
+    if method.idempotent {
+        attributes.push("idempotent");
+    }
+    if method.broadcast {
+        attributes.push("broadcast");
+    }
+
+    let mut parameters = vec![Declaration::new("in", "handle_t", "binding")];
+    parameters.extend(
+        method
+            .wire_parameters()
+            .iter()
+            .flat_map(parameter_declarations),
+    );
+    let status = if method.hresult {
+        "HRESULT"
+    } else {
+        "error_status_t"
+    };
+    let return_type = match &method.return_type {
+        // The value is already declared as the hidden parameter following the others
+        Some(Type::Simple(_)) if method.returns_status() => status.to_string(),
+        Some(return_type) if method.returns_status() => {
+            parameters.extend(out_declarations(return_type, "out", RETURN_NAME));
+            status.to_string()
+        }
+        None if method.returns_status() => status.to_string(),
+        None => "void".to_string(),
+        Some(Type::Simple(base_type)) => base_type.idl_name().to_string(),
+        Some(Type::Enum(path)) => format!("enum {}", type_name(path)),
+        Some(return_type) => {
+            parameters.extend(out_declarations(return_type, "out", RETURN_NAME));
+            "void".to_string()
+        }
+    };
+
+    Signature {
+        attributes,
+        return_type,
+        parameters,
+    }
+}
+
+/// Declares a parameter, preceded by its hidden length for slices
+fn parameter_declarations(parameter: &Parameter) -> Vec<Declaration> {
+    let name = &parameter.name;
+    // The range of a slice bounds its hidden length
+    let direction = match parameter.range {
+        Some((min, max)) => format!("in, range({min}, {max})"),
+        None => "in".to_string(),
+    };
+    if let Type::Slice(base_type) = &parameter.r#type {
+        return slice_declarations(parameter, *base_type, direction);
+    }
+    if parameter.is_out {
+        let direction = if parameter.is_in { "in, out" } else { "out" };
+        return out_declarations(&parameter.r#type, direction, name);
+    }
+
+    // Options are unique pointers, and structs reference pointers, unless full pointers
+    let (option, reference) = if parameter.full_pointer {
+        ("in, ptr", "in, ptr")
+    } else {
+        ("in, unique", "in")
+    };
+    let declaration = match &parameter.r#type {
+        Type::Simple(base_type) => Declaration::new(direction, base_type.idl_name(), name),
+        Type::String | Type::WideString | Type::HString => {
+            Declaration::new("in, string", "const wchar_t*", name)
+        }
+        Type::AnsiString => Declaration::new("in, string", "const char*", name),
+        Type::OptionalString => {
+            Declaration::new(format!("{option}, string"), "const wchar_t*", name)
+        }
+        Type::OptionalAnsiString => {
+            Declaration::new(format!("{option}, string"), "const char*", name)
+        }
+        Type::Optional(base_type) => {
+            Declaration::new(option, format!("{}*", base_type.idl_name()), name)
+        }
+        Type::Struct(path) => Declaration::new(reference, format!("{}*", type_name(path)), name),
+        Type::Enum(path) => Declaration::new("in", format!("enum {}", type_name(path)), name),
+        Type::SystemHandle { kind, access } => Declaration::new(
+            format!("in, {}", kind.idl_attribute(*access)),
+            "HANDLE",
+            name,
+        ),
+        Type::ContextHandle => Declaration::new("in, context_handle", "void*", name),
+        // Passed by value, as MIDL passes `[in]` pipes
+        Type::Pipe(element) => Declaration::new("in", element.idl_pipe_name(), name),
+        Type::Slice(_) => unreachable!("slices are declared by `slice_declarations()`"),
+        Type::StringVec => unreachable!("string vectors are only return values"),
+    };
+    vec![declaration]
+}
+
+/// Declares the array of a slice parameter, sized by its `size_is` parameter or preceded
+/// by its hidden length, declared as `length_direction`
+fn slice_declarations(
+    parameter: &Parameter,
+    base_type: BaseType,
+    length_direction: String,
+) -> Vec<Declaration> {
+    let name = &parameter.name;
+    let direction = match (parameter.is_in, parameter.is_out) {
+        (true, true) => "in, out",
+        (false, true) => "out",
+        _ => "in",
+    };
+    let array = |length: &str| {
+        Declaration::new(
+            format!("{direction}, size_is({length})"),
+            format!("{}*", base_type.idl_name()),
+            name,
+        )
+    };
+    if let Some(length) = &parameter.size_is {
+        return vec![array(length)];
+    }
+
+    let length = format!("{name}_length");
+    vec![
+        Declaration::new(length_direction, "unsigned long", &length),
+        array(&length),
+    ]
+}
+
+/// Declares the `[out]` pointers a value of `type` is returned through
+fn out_declarations(r#type: &Type, direction: &str, name: &str) -> Vec<Declaration> {
+    let declaration = match r#type {
+        Type::Simple(base_type) => {
+            Declaration::new(direction, format!("{}*", base_type.idl_name()), name)
+        }
+        Type::Enum(path) => Declaration::new(direction, format!("enum {}*", type_name(path)), name),
+        Type::String | Type::WideString | Type::HString | Type::OptionalString => {
+            Declaration::new(format!("{direction}, string"), "wchar_t**", name)
+        }
+        Type::Optional(base_type) => {
+            Declaration::new(direction, format!("{}**", base_type.idl_name()), name)
+        }
+        Type::Struct(path) => Declaration::new(direction, format!("{}*", type_name(path)), name),
+        Type::AnsiString | Type::OptionalAnsiString => {
+            unreachable!("ANSI strings are only input parameters")
+        }
+        Type::ContextHandle => {
+            Declaration::new(format!("{direction}, context_handle"), "void**", name)
+        }
+        Type::Pipe(element) => {
+            Declaration::new(direction, format!("{}*", element.idl_pipe_name()), name)
+        }
+        Type::SystemHandle { kind, access } => Declaration::new(
+            format!("{direction}, {}", kind.idl_attribute(*access)),
+            "HANDLE*",
+            name,
+        ),
+        Type::Slice(base_type) => {
+            let length = format!("{name}_length");
+            return vec![
+                Declaration::new(direction, "unsigned long*", &length),
+                Declaration::new(
+                    format!("{direction}, size_is(, *{length})"),
+                    format!("{}**", base_type.idl_name()),
+                    name,
+                ),
+            ];
+        }
+        // The strings are `LPWSTR`s of `wtypes.idl`, as `[string]` can't apply to the
+        // elements of the array
+        Type::StringVec => {
+            let length = format!("{name}_length");
+            return vec![
+                Declaration::new(direction, "unsigned long*", &length),
+                Declaration::new(
+                    format!("{direction}, size_is(, *{length})"),
+                    "LPWSTR**",
+                    name,
+                ),
+            ];
+        }
+    };
+    vec![declaration]
+}
+
+/// Name of a struct or enum in the IDL, the last segment of its Rust path
+fn type_name(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path).trim()
+}
@@ -0,0 +1,989 @@
+//! Import of MIDL interface definitions, for `rpc_interface_from_idl!`.
+//!
+//! A subset of MIDL is translated into the equivalent trait, which is then compiled like
+//! one written by hand. The subset is the inverse of what [`crate::idl`] generates:
+//! procedures of integers, floats, enums, structs, strings (`char` strings as
+//! `#[rpc(ansi)]` ones) and conformant arrays (keeping the `range` of integers and array
+//! lengths), with the explicit binding handle dropped, an `unsigned long` preceding a
+//! `size_is` array folded into a slice (other `unsigned long` lengths are named by
+//! `#[rpc(size_is(...))]`, and `[out]` arrays are `&mut [T]`), `[ptr]` pointers marked
+//! `#[rpc(ptr)]`, and a trailing `[out]` string, `T**` or sized array pointer of a `void`
+//! procedure turned into its return value. Imports and `cpp_quote` are skipped;
+//! typedefs, preprocessor directives and object interfaces are rejected. Structs and
+//! enums are referred to by name, and have to be defined in Rust, except for the Win32
+//! structs of `wtypes.idl` that the runtime supports, `FILETIME`, `SYSTEMTIME` and
+//! `LARGE_INTEGER`.
+
+use std::path::PathBuf;
+
+use quote::quote;
+use syn::{Token, parse::Parse};
+
+/// Arguments of `rpc_interface_from_idl!`: the path of the IDL file, followed by
+/// `rpc_interface` arguments applied to every interface in it
+pub struct IdlImport {
+    pub path: syn::LitStr,
+    pub attributes: proc_macro2::TokenStream,
+}
+
+impl Parse for IdlImport {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+        Ok(IdlImport {
+            path,
+            attributes: input.parse()?,
+        })
+    }
+}
+
+/// An interface translated from IDL, as the arguments and item of `rpc_interface`
+pub struct ImportedInterface {
+    pub attributes: proc_macro2::TokenStream,
+    pub item: proc_macro2::TokenStream,
+}
+
+/// Reads and translates the interfaces of the IDL file at `import.path`, relative to the
+/// manifest directory of the crate being compiled.
+///
+/// Also returns tokens making the crate rebuild when the file changes.
+pub fn import_idl(
+    import: &IdlImport,
+) -> syn::Result<(Vec<ImportedInterface>, proc_macro2::TokenStream)> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let full_path = PathBuf::from(manifest_dir).join(import.path.value());
+    let error = |message: String| syn::Error::new_spanned(&import.path, message);
+
+    let source = std::fs::read_to_string(&full_path)
+        .map_err(|e| error(format!("Failed to read {}: {e}", full_path.display())))?;
+    let interfaces = Parser::new(&source)
+        .and_then(|mut parser| parser.parse_file())
+        .map_err(|e| error(format!("{}:{}: {}", import.path.value(), e.line, e.message)))?;
+    if interfaces.is_empty() {
+        return Err(error(format!(
+            "{} doesn't define an interface",
+            import.path.value()
+        )));
+    }
+
+    let extra_attributes = &import.attributes;
+    let interfaces = interfaces
+        .into_iter()
+        .map(|interface| {
+            let parse = |source: String| {
+                source
+                    .parse::<proc_macro2::TokenStream>()
+                    .map_err(|e| error(format!("Failed to translate `{}`: {e}", interface.name)))
+            };
+            let mut attributes = parse(interface.attributes())?;
+            if !extra_attributes.is_empty() {
+                attributes.extend(quote! { , #extra_attributes });
+            }
+            Ok(ImportedInterface {
+                attributes,
+                item: parse(interface.item())?,
+            })
+        })
+        .collect::<syn::Result<_>>()?;
+
+    let full_path = full_path.to_string_lossy();
+    Ok((
+        interfaces,
+        quote! {
+            const _: &[u8] = include_bytes!(#full_path);
+        },
+    ))
+}
+
+/// An error in the IDL file, at a line number
+struct ParseError {
+    line: usize,
+    message: String,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Punct(char),
+}
+
+/// A `name` or `name(argument)` attribute in square brackets
+struct Attribute {
+    name: String,
+    argument: Option<String>,
+    line: usize,
+}
+
+fn has_attribute(attributes: &[Attribute], name: &str) -> bool {
+    attributes.iter().any(|attribute| attribute.name == name)
+}
+
+#[derive(Clone, PartialEq)]
+enum BaseKind {
+    Void,
+    Handle,
+    /// The `error_status_t` returned by a procedure
+    ErrorStatus,
+    /// The `HRESULT` returned by a procedure
+    HResult,
+    WChar,
+    /// Integer or floating point type, by its Rust name
+    Base(&'static str),
+    Enum(String),
+    /// Struct or union, by its name
+    Named(String),
+}
+
+#[derive(Clone)]
+struct IdlType {
+    kind: BaseKind,
+    pointers: usize,
+    /// Whether the type is one of the `[string]` pointer typedefs of `wtypes.idl`, e.g.
+    /// `LPWSTR`
+    string: bool,
+}
+
+struct IdlParameter {
+    attributes: Vec<Attribute>,
+    r#type: IdlType,
+    name: String,
+    line: usize,
+}
+
+impl IdlParameter {
+    fn is_in(&self) -> bool {
+        has_attribute(&self.attributes, "in")
+    }
+
+    fn is_out(&self) -> bool {
+        has_attribute(&self.attributes, "out")
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> ParseResult<T> {
+        Err(ParseError {
+            line: self.line,
+            message: format!("`{}`: {}", self.name, message.into()),
+        })
+    }
+}
+
+struct TranslatedInterface {
+    name: String,
+    uuid: u128,
+    version: (u16, u16),
+    enums: Vec<String>,
+    /// Rust declarations of the methods
+    methods: Vec<String>,
+}
+
+impl TranslatedInterface {
+    fn attributes(&self) -> String {
+        let mut attributes = format!(
+            "guid(0x{:032x}), version({}.{})",
+            self.uuid, self.version.0, self.version.1
+        );
+        if !self.enums.is_empty() {
+            attributes.push_str(&format!(", enums({})", self.enums.join(", ")));
+        }
+        attributes
+    }
+
+    fn item(&self) -> String {
+        format!("trait {} {{ {} }}", self.name, self.methods.join(" "))
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> ParseResult<Self> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+        })
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> ParseResult<T> {
+        Err(ParseError {
+            line: self.line(),
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> ParseResult<Token> {
+        match self.tokens.get(self.position) {
+            Some((token, _)) => {
+                self.position += 1;
+                Ok(token.clone())
+            }
+            None => self.error("Unexpected end of file"),
+        }
+    }
+
+    fn eat_punct(&mut self, punct: char) -> bool {
+        let found = self.peek() == Some(&Token::Punct(punct));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(w)) if w == word);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, punct: char) -> ParseResult<()> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            self.error(format!("Expected `{punct}`"))
+        }
+    }
+
+    fn word(&mut self) -> ParseResult<String> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let word = word.clone();
+                self.position += 1;
+                Ok(word)
+            }
+            _ => self.error("Expected an identifier"),
+        }
+    }
+
+    /// Skips tokens up to and including `punct`
+    fn skip_past(&mut self, punct: char) -> ParseResult<()> {
+        while self.next()? != Token::Punct(punct) {}
+        Ok(())
+    }
+
+    fn parse_file(&mut self) -> ParseResult<Vec<TranslatedInterface>> {
+        let mut interfaces = vec![];
+        while self.peek().is_some() {
+            if self.eat_word("import") {
+                self.skip_past(';')?;
+            } else if self.eat_word("cpp_quote") {
+                self.skip_past(')')?;
+            } else {
+                let attributes = self.parse_attributes()?;
+                if !self.eat_word("interface") {
+                    return self.error(
+                        "Expected an interface; typedefs and other declarations are not \
+                         supported, define the types in Rust instead",
+                    );
+                }
+                interfaces.push(self.parse_interface(attributes)?);
+            }
+        }
+        Ok(interfaces)
+    }
+
+    /// Parses the attributes in square brackets, if any
+    fn parse_attributes(&mut self) -> ParseResult<Vec<Attribute>> {
+        let mut attributes = vec![];
+        if !self.eat_punct('[') {
+            return Ok(attributes);
+        }
+        loop {
+            let line = self.line();
+            let name = self.word()?;
+            let argument = if self.eat_punct('(') {
+                Some(self.parse_argument()?)
+            } else {
+                None
+            };
+            attributes.push(Attribute {
+                name,
+                argument,
+                line,
+            });
+            if self.eat_punct(']') {
+                return Ok(attributes);
+            }
+            self.expect_punct(',')?;
+        }
+    }
+
+    /// Returns the text of an attribute argument, up to the closing parenthesis
+    fn parse_argument(&mut self) -> ParseResult<String> {
+        let mut text = String::new();
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Token::Punct(')') if depth == 0 => return Ok(text),
+                Token::Punct(punct) => {
+                    match punct {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    text.push(punct);
+                }
+                Token::Word(word) => {
+                    if text.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                        text.push(' ');
+                    }
+                    text.push_str(&word);
+                }
+                Token::Str(string) => text.push_str(&format!("{string:?}")),
+            }
+        }
+    }
+
+    fn parse_interface(&mut self, attributes: Vec<Attribute>) -> ParseResult<TranslatedInterface> {
+        let name = self.word()?;
+        if self.eat_punct(':') {
+            return self.error(format!(
+                "`{name}` is an object (COM) interface, which is not supported"
+            ));
+        }
+
+        let mut uuid = None;
+        let mut version = (0, 0);
+        let mut unique_default = true;
+        for attribute in &attributes {
+            let argument = attribute.argument.as_deref().unwrap_or_default();
+            let error = |message: String| ParseError {
+                line: attribute.line,
+                message,
+            };
+            match attribute.name.as_str() {
+                "uuid" => {
+                    let hex = argument.replace('-', "");
+                    uuid = Some(
+                        u128::from_str_radix(&hex, 16)
+                            .ok()
+                            .filter(|_| hex.len() == 32)
+                            .ok_or_else(|| error(format!("Invalid uuid `{argument}`")))?,
+                    );
+                }
+                "version" => {
+                    let (major, minor) = argument.split_once('.').unwrap_or((argument, "0"));
+                    version = major
+                        .parse()
+                        .ok()
+                        .zip(minor.parse().ok())
+                        .ok_or_else(|| error(format!("Invalid version `{argument}`")))?;
+                }
+                "pointer_default" => unique_default = argument == "unique",
+                "endpoint" | "helpstring" | "helpcontext" => {}
+                other => {
+                    return Err(error(format!("Unsupported interface attribute `{other}`")));
+                }
+            }
+        }
+        let Some(uuid) = uuid else {
+            return self.error(format!("`{name}` has no uuid"));
+        };
+
+        let mut interface = TranslatedInterface {
+            name: to_camel_case(&name),
+            uuid,
+            version,
+            enums: vec![],
+            methods: vec![],
+        };
+        self.expect_punct('{')?;
+        while !self.eat_punct('}') {
+            if self.eat_word("typedef") || self.eat_word("const") {
+                return self.error(
+                    "Declarations other than procedures are not supported, define the types \
+                     in Rust instead",
+                );
+            }
+            let method = self.parse_method(&mut interface.enums, unique_default)?;
+            interface.methods.push(method);
+        }
+        self.eat_punct(';');
+        Ok(interface)
+    }
+
+    fn parse_type(&mut self) -> ParseResult<IdlType> {
+        self.eat_word("const");
+        let unsigned = self.eat_word("unsigned");
+        let signed = !unsigned && self.eat_word("signed");
+        let word = self.word()?;
+        let mut pointers = 0;
+        let mut string = false;
+        let kind = match word.as_str() {
+            "enum" => BaseKind::Enum(self.word()?),
+            "struct" | "union" => BaseKind::Named(self.word()?),
+            "void" => BaseKind::Void,
+            "handle_t" => BaseKind::Handle,
+            "error_status_t" => BaseKind::ErrorStatus,
+            "HRESULT" => BaseKind::HResult,
+            "wchar_t" | "WCHAR" => BaseKind::WChar,
+            "LPWSTR" | "LPCWSTR" | "PWSTR" | "PCWSTR" => {
+                pointers += 1;
+                string = true;
+                BaseKind::WChar
+            }
+            "LPSTR" | "LPCSTR" | "PSTR" | "PCSTR" => {
+                pointers += 1;
+                string = true;
+                BaseKind::Base("u8")
+            }
+            word => match base_type(word, unsigned, signed) {
+                Some(rust_type) => {
+                    if matches!(word, "short" | "long") {
+                        self.eat_word("int");
+                    }
+                    BaseKind::Base(rust_type)
+                }
+                None if unsigned || signed => {
+                    return self.error(format!("Unsupported type `{word}`"));
+                }
+                None => BaseKind::Named(
+                    crate::idl::WTYPES_STRUCTS
+                        .iter()
+                        .find(|(name, _)| *name == word)
+                        .map_or(word, |(_, path)| path)
+                        .to_string(),
+                ),
+            },
+        };
+        self.eat_word("const");
+        while self.eat_punct('*') {
+            pointers += 1;
+            self.eat_word("const");
+        }
+        Ok(IdlType {
+            kind,
+            pointers,
+            string,
+        })
+    }
+
+    fn parse_method(
+        &mut self,
+        enums: &mut Vec<String>,
+        unique_default: bool,
+    ) -> ParseResult<String> {
+        let attributes = self.parse_attributes()?;
+        let mut rpc_attributes = vec![];
+        for attribute in &attributes {
+            match attribute.name.as_str() {
+                "maybe" | "idempotent" | "broadcast" | "callback" => {
+                    rpc_attributes.push(attribute.name.clone())
+                }
+                other => {
+                    return Err(ParseError {
+                        line: attribute.line,
+                        message: format!("Unsupported procedure attribute `{other}`"),
+                    });
+                }
+            }
+        }
+
+        let line = self.line();
+        let return_type = self.parse_type()?;
+        let name = self.word()?;
+        self.expect_punct('(')?;
+        let mut parameters = vec![];
+        let empty = self.position + 1 < self.tokens.len()
+            && self.tokens[self.position].0 == Token::Word("void".to_string())
+            && self.tokens[self.position + 1].0 == Token::Punct(')');
+        if empty {
+            self.position += 1;
+        }
+        while !self.eat_punct(')') {
+            if !parameters.is_empty() {
+                self.expect_punct(',')?;
+            }
+            let attributes = self.parse_attributes()?;
+            let line = self.line();
+            let r#type = self.parse_type()?;
+            let name = self.word()?;
+            parameters.push(IdlParameter {
+                attributes,
+                r#type,
+                name,
+                line,
+            });
+        }
+        self.expect_punct(';')?;
+
+        let method_error = |message: String| ParseError {
+            line,
+            message: format!("`{name}`: {message}"),
+        };
+
+        // The binding handle isn't transmitted, the generated clients pass their own
+        if parameters
+            .first()
+            .is_some_and(|p| p.r#type.kind == BaseKind::Handle && p.r#type.pointers == 0)
+        {
+            parameters.remove(0);
+        }
+
+        // Procedures returning an `error_status_t` or an `HRESULT` return their value
+        // through a trailing `[out]` parameter, and fail with the status
+        let error_status = return_type.kind == BaseKind::ErrorStatus && return_type.pointers == 0;
+        let hresult = return_type.kind == BaseKind::HResult && return_type.pointers == 0;
+        if error_status {
+            rpc_attributes.push("error_status".to_string());
+        }
+        if hresult {
+            rpc_attributes.push("hresult".to_string());
+        }
+        let mut rust_return = match (&return_type.kind, return_type.pointers) {
+            (BaseKind::Void | BaseKind::ErrorStatus | BaseKind::HResult, 0) => None,
+            (BaseKind::Base(rust_type), 0) => Some(rust_type.to_string()),
+            (BaseKind::Enum(name), 0) => {
+                add_enum(enums, name);
+                Some(name.clone())
+            }
+            _ => return Err(method_error("Unsupported return type".to_string())),
+        };
+        if rust_return.is_none() {
+            rust_return =
+                take_return_parameter(&mut parameters, unique_default, error_status || hresult)?;
+        }
+        if error_status || hresult {
+            let value = rust_return.unwrap_or_else(|| "()".to_string());
+            let error = if hresult { "i32" } else { "u32" };
+            rust_return = Some(format!("Result<{value}, {error}>"));
+        }
+
+        // Rust parameters, with the IDL names of the `unsigned long` ones for `size_is`
+        let mut rust_parameters: Vec<(String, Option<String>)> = vec![];
+        for (index, parameter) in parameters.iter().enumerate() {
+            let rust_name = to_rust_name(&parameter.name);
+            let attributes = &parameter.attributes;
+            let kind = &parameter.r#type.kind;
+            let declaration = match (parameter.is_in(), parameter.is_out()) {
+                (false, false) => return parameter.error("Expected `[in]` or `[out]`"),
+                (true, false) => match (kind, parameter.r#type.pointers) {
+                    (BaseKind::Base(rust_type), 0) => {
+                        let length_name = (*rust_type == "u32").then(|| parameter.name.clone());
+                        let range = range_attribute(attributes);
+                        rust_parameters
+                            .push((format!("{range}{rust_name}: {rust_type}"), length_name));
+                        continue;
+                    }
+                    (BaseKind::Enum(name), 0) => {
+                        add_enum(enums, name);
+                        format!("{rust_name}: {name}")
+                    }
+                    (BaseKind::WChar, 1) if has_attribute(attributes, "string") => {
+                        if has_attribute(attributes, "ptr") {
+                            format!("#[rpc(ptr)] {rust_name}: Option<&str>")
+                        } else if has_attribute(attributes, "unique") {
+                            format!("{rust_name}: Option<&str>")
+                        } else {
+                            format!("{rust_name}: &str")
+                        }
+                    }
+                    // `char` strings are sent in the ANSI code page
+                    (BaseKind::Base("u8"), 1) if has_attribute(attributes, "string") => {
+                        if has_attribute(attributes, "ptr") {
+                            format!("#[rpc(ansi, ptr)] {rust_name}: Option<&str>")
+                        } else if has_attribute(attributes, "unique") {
+                            format!("#[rpc(ansi)] {rust_name}: Option<&str>")
+                        } else {
+                            format!("#[rpc(ansi)] {rust_name}: &str")
+                        }
+                    }
+                    (BaseKind::Base(rust_type), 1) => {
+                        if size_is(attributes).is_some() {
+                            slice_declaration(
+                                &parameters,
+                                parameter,
+                                &mut rust_parameters,
+                                format!("{rust_name}: &[{rust_type}]"),
+                            )?
+                        } else if has_attribute(attributes, "ptr") {
+                            format!("#[rpc(ptr)] {rust_name}: Option<{rust_type}>")
+                        } else if has_attribute(attributes, "unique") {
+                            format!("{rust_name}: Option<{rust_type}>")
+                        } else {
+                            return parameter.error(
+                                "Pointers to integers must be `[unique]`, `[ptr]` or `size_is` \
+                                 arrays",
+                            );
+                        }
+                    }
+                    (BaseKind::Named(name), 1) if has_attribute(attributes, "ptr") => {
+                        format!("#[rpc(ptr)] {rust_name}: &{name}")
+                    }
+                    (BaseKind::Named(name), 1) => format!("{rust_name}: &{name}"),
+                    _ => return parameter.error("Unsupported `[in]` parameter type"),
+                },
+                (is_in, true) => {
+                    let prefix = if is_in { "#[in_out] " } else { "" };
+                    match (kind, parameter.r#type.pointers) {
+                        (BaseKind::Base(name), 1) if size_is(attributes).is_some() => {
+                            let declaration = slice_declaration(
+                                &parameters,
+                                parameter,
+                                &mut rust_parameters,
+                                format!("{rust_name}: &mut [{name}]"),
+                            )?;
+                            format!("{prefix}{declaration}")
+                        }
+                        (BaseKind::Base(name), 1) => format!("{prefix}{rust_name}: &mut {name}"),
+                        // Arrays of `LPWSTR`s, with their `[out] unsigned long*` length
+                        // right before them
+                        (BaseKind::WChar, 3)
+                            if !is_in
+                                && parameter.r#type.string
+                                && size_is(attributes).is_some() =>
+                        {
+                            let length = size_is(attributes).unwrap_or_default();
+                            let length = length.trim_start_matches(',').trim_start_matches('*');
+                            let counted = index > 0 && {
+                                let length_parameter = &parameters[index - 1];
+                                length_parameter.name == length
+                                    && length_parameter.is_out()
+                                    && !length_parameter.is_in()
+                                    && length_parameter.r#type.kind == BaseKind::Base("u32")
+                                    && length_parameter.r#type.pointers == 1
+                            };
+                            if !counted {
+                                return parameter.error(
+                                    "`size_is(, *length)` must name an `[out] unsigned long*` \
+                                     parameter right before the array",
+                                );
+                            }
+                            if !unique_default {
+                                return parameter.error(
+                                    "String arrays are unique pointers, the interface needs \
+                                     `pointer_default(unique)`",
+                                );
+                            }
+                            rust_parameters.pop();
+                            format!("{rust_name}: &mut Vec<String>")
+                        }
+                        (BaseKind::Named(name), 1) => {
+                            format!("{prefix}{rust_name}: &mut {name}")
+                        }
+                        _ => {
+                            return parameter.error(
+                                "`[out]` parameters must be pointers to integers, floats or \
+                                 structs, `size_is` arrays, or a trailing return value",
+                            );
+                        }
+                    }
+                }
+            };
+            rust_parameters.push((declaration, None));
+        }
+
+        let attributes = if rpc_attributes.is_empty() {
+            String::new()
+        } else {
+            format!("#[rpc({})] ", rpc_attributes.join(", "))
+        };
+        let parameters = rust_parameters
+            .into_iter()
+            .map(|(declaration, _)| declaration)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let return_type = rust_return
+            .map(|rust_type| format!(" -> {rust_type}"))
+            .unwrap_or_default();
+        Ok(format!(
+            "{attributes}fn {}({parameters}){return_type};",
+            to_rust_name(&name)
+        ))
+    }
+}
+
+/// Removes the trailing `[out]` parameters that carry the return value of a `void`
+/// procedure, or of one returning a status, and returns its Rust type
+fn take_return_parameter(
+    parameters: &mut Vec<IdlParameter>,
+    unique_default: bool,
+    returns_status: bool,
+) -> ParseResult<Option<String>> {
+    let Some(last) = parameters.last().filter(|p| p.is_out() && !p.is_in()) else {
+        return Ok(None);
+    };
+    let pointers = last.r#type.pointers;
+    // Number of trailing parameters carrying the return value
+    let mut count = 1;
+    // Element type of a returned pointer or array: arrays of `LPWSTR`s, whose typedef is
+    // `[string]`, are returned as `Vec<String>`
+    let element = match (&last.r#type.kind, pointers) {
+        (BaseKind::Base(rust_type), 2) => Some(*rust_type),
+        (BaseKind::WChar, 3) if last.r#type.string && size_is(&last.attributes).is_some() => {
+            Some("String")
+        }
+        _ => None,
+    };
+    let rust_type = match (&last.r#type.kind, pointers, element) {
+        (BaseKind::WChar, 2, _) if has_attribute(&last.attributes, "string") => {
+            "String".to_string()
+        }
+        (_, _, Some(rust_type)) => match size_is(&last.attributes) {
+            None => format!("Option<{rust_type}>"),
+            Some(length) => {
+                let length = length.trim_start_matches(',').trim_start_matches('*');
+                let counted = parameters.len() >= 2 && {
+                    let length_parameter = &parameters[parameters.len() - 2];
+                    length_parameter.name == length
+                        && length_parameter.is_out()
+                        && !length_parameter.is_in()
+                        && length_parameter.r#type.kind == BaseKind::Base("u32")
+                        && length_parameter.r#type.pointers == 1
+                };
+                if !counted {
+                    return last.error(
+                        "`size_is(, *length)` must name an `[out] unsigned long*` parameter \
+                         right before the array",
+                    );
+                }
+                count = 2;
+                format!("Vec<{rust_type}>")
+            }
+        },
+        // Out-only structs are returned when they carry the return value, as in the IDL
+        // generated for traits
+        (BaseKind::Named(name), 1, _) if last.name == crate::idl::RETURN_NAME => name.clone(),
+        // Numbers are only returned this way by procedures returning a status
+        (BaseKind::Base(rust_type), 1, _)
+            if returns_status && last.name == crate::idl::RETURN_NAME =>
+        {
+            rust_type.to_string()
+        }
+        _ => return Ok(None),
+    };
+    if pointers >= 2 && !unique_default {
+        return last.error(
+            "Returned pointers must be unique, the interface needs `pointer_default(unique)`",
+        );
+    }
+    parameters.truncate(parameters.len() - count);
+    Ok(Some(rust_type))
+}
+
+/// Returns the `declaration` of the slice of a `size_is` array `parameter`. The `[in]
+/// unsigned long` the array is sized by becomes its hidden length when it comes right
+/// before the array, and no other array shares it, or is named by `#[rpc(size_is(...))]`.
+fn slice_declaration(
+    parameters: &[IdlParameter],
+    parameter: &IdlParameter,
+    rust_parameters: &mut Vec<(String, Option<String>)>,
+    declaration: String,
+) -> ParseResult<String> {
+    let length = size_is(&parameter.attributes).unwrap_or_default();
+    let shared = parameters
+        .iter()
+        .filter(|p| size_is(&p.attributes).as_ref() == Some(&length))
+        .count()
+        > 1;
+    if !shared && matches!(rust_parameters.last(), Some((_, Some(name))) if *name == length) {
+        rust_parameters.pop();
+        // The range of the length moves to the slice
+        let range = parameters
+            .iter()
+            .find(|p| p.name == length)
+            .map(|p| range_attribute(&p.attributes))
+            .unwrap_or_default();
+        return Ok(format!("{range}{declaration}"));
+    }
+
+    let is_length = |p: &IdlParameter| {
+        p.name == length
+            && p.is_in()
+            && !p.is_out()
+            && p.r#type.kind == BaseKind::Base("u32")
+            && p.r#type.pointers == 0
+    };
+    if !parameters.iter().any(is_length) {
+        return parameter.error("`size_is` must name an `[in] unsigned long` parameter");
+    }
+    Ok(format!(
+        "#[rpc(size_is({}))] {declaration}",
+        to_rust_name(&length)
+    ))
+}
+
+/// Returns the argument of the `size_is` attribute, if any
+fn size_is(attributes: &[Attribute]) -> Option<String> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name == "size_is")
+        .and_then(|attribute| attribute.argument.as_ref())
+        .map(|argument| argument.replace(' ', ""))
+}
+
+/// Returns the `#[rpc(range(...))]` attribute of a parameter with a `range`, followed by a
+/// space, or nothing
+fn range_attribute(attributes: &[Attribute]) -> String {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name == "range")
+        .and_then(|attribute| attribute.argument.as_ref())
+        .map(|argument| format!("#[rpc(range({argument}))] "))
+        .unwrap_or_default()
+}
+
+fn add_enum(enums: &mut Vec<String>, name: &str) {
+    if !enums.iter().any(|e| e == name) {
+        enums.push(name.to_string());
+    }
+}
+
+/// Returns the Rust type of a MIDL base type or Windows typedef of one
+fn base_type(word: &str, unsigned: bool, signed: bool) -> Option<&'static str> {
+    let rust_type = match (word, unsigned) {
+        ("small" | "char", true) => "u8",
+        ("short", true) => "u16",
+        ("long" | "int", true) => "u32",
+        ("hyper" | "__int64", true) => "u64",
+        (_, true) => return None,
+        ("char", false) if signed => "i8",
+        ("byte" | "char" | "BYTE" | "UCHAR" | "UINT8", false) => "u8",
+        ("small" | "INT8", false) => "i8",
+        ("short" | "SHORT" | "INT16", false) => "i16",
+        ("USHORT" | "WORD" | "UINT16", false) => "u16",
+        ("long" | "int" | "LONG" | "INT" | "INT32", false) => "i32",
+        ("ULONG" | "DWORD" | "UINT" | "UINT32", false) => "u32",
+        ("hyper" | "__int64" | "LONGLONG" | "INT64", false) => "i64",
+        ("ULONGLONG" | "DWORD64" | "UINT64", false) => "u64",
+        ("float", false) => "f32",
+        ("double", false) => "f64",
+        _ => return None,
+    };
+    Some(rust_type)
+}
+
+/// Converts a C identifier to snake case, escaping Rust keywords
+fn to_rust_name(name: &str) -> String {
+    let characters: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in characters.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = characters[i - 1];
+            let next_is_lower = characters.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    if syn::parse_str::<syn::Ident>(&snake).is_err() {
+        snake.push('_');
+    }
+    snake
+}
+
+/// Converts a C identifier to upper camel case, for the trait name
+fn to_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut characters = part.chars();
+            characters
+                .next()
+                .map(|first| first.to_uppercase().chain(characters).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Splits MIDL source into words, strings and punctuation, with their line numbers
+fn tokenize(source: &str) -> ParseResult<Vec<(Token, usize)>> {
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut at_line_start = true;
+    let mut characters = source.chars().peekable();
+    while let Some(c) = characters.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                at_line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            '#' if at_line_start => {
+                return Err(ParseError {
+                    line,
+                    message: "Preprocessor directives are not supported".to_string(),
+                });
+            }
+            '/' if characters.peek() == Some(&'/') => {
+                while characters.next_if(|&c| c != '\n').is_some() {}
+                continue;
+            }
+            '/' if characters.peek() == Some(&'*') => {
+                characters.next();
+                let mut previous = ' ';
+                loop {
+                    match characters.next() {
+                        Some('/') if previous == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            previous = c;
+                        }
+                        None => {
+                            return Err(ParseError {
+                                line,
+                                message: "Unterminated comment".to_string(),
+                            });
+                        }
+                    }
+                }
+                continue;
+            }
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match characters.next() {
+                        Some('"') => break,
+                        Some('\\') => string.extend(characters.next()),
+                        Some(c) => string.push(c),
+                        None => {
+                            return Err(ParseError {
+                                line,
+                                message: "Unterminated string".to_string(),
+                            });
+                        }
+                    }
+                }
+                tokens.push((Token::Str(string), line));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' => {
+                let mut word = c.to_string();
+                while let Some(c) =
+                    characters.next_if(|&c| c.is_alphanumeric() || c == '_' || c == '.')
+                {
+                    word.push(c);
+                }
+                tokens.push((Token::Word(word), line));
+            }
+            c => tokens.push((Token::Punct(c), line)),
+        }
+        at_line_start = false;
+    }
+    Ok(tokens)
+}
@@ -0,0 +1,1023 @@
+//! Code generation behind the `windows-rpc-macros` crate, usable outside of procedural
+//! macros.
+//!
+//! An interface is described by an [`Interface`] with its [`Method`]s and their
+//! [`Parameter`]s, the model `rpc_interface` builds from a trait. Tools can build it
+//! themselves, e.g. from a configuration file in a build script, and generate the same
+//! client and server the macro would with [`compile_interface()`], or the client alone
+//! with [`compile_client()`]. The generated code refers to the `windows_rpc` crate, which
+//! the crate including it depends on.
+//!
+//! ```rust
+//! use windows_rpc_codegen::{
+//!     BaseType, Interface, InterfaceVersion, Method, Parameter, Type, compile_interface,
+//! };
+//!
+//! let interface = Interface {
+//!     name: "Calculator".to_string(),
+//!     uuid: 0x12345678_1234_1234_1234_123456789abc,
+//!     version: InterfaceVersion { major: 1, minor: 0 },
+//!     methods: vec![Method {
+//!         name: "add".to_string(),
+//!         parameters: vec![
+//!             Parameter::new("a", Type::Simple(BaseType::I32)),
+//!             Parameter::new("b", Type::Simple(BaseType::I32)),
+//!         ],
+//!         return_type: Some(Type::Simple(BaseType::I32)),
+//!         ..Method::default()
+//!     }],
+//!     ..Interface::default()
+//! };
+//!
+//! // e.g. written to `OUT_DIR` by a build script, and brought in with `include!`
+//! let code = compile_interface(&interface).to_string();
+//! assert!(code.contains("CalculatorClient"));
+//! ```
+//!
+//! [`parse_interface()`] and [`parse_idl_file()`] build the model from a trait or a MIDL
+//! file instead. [`generate_idl()`] and [`generate_header()`] describe the interface to
+//! MIDL and to C callers, and [`formats`] exposes its NDR format strings. The
+//! [`expand_rpc_interface()`] and `derive_*` functions are the macros themselves, taking
+//! and returning tokens.
+
+mod callback_codegen;
+mod client_codegen;
+mod compat_codegen;
+#[allow(dead_code)]
+mod constants;
+mod enum_derive;
+pub mod formats;
+mod header;
+mod idl;
+mod idl_import;
+mod lockfile;
+mod ndr;
+mod ndr64;
+mod parse;
+mod server_codegen;
+mod struct_derive;
+mod types;
+mod union_derive;
+
+use quote::ToTokens;
+use syn::{FnArg, ReturnType, TraitItem};
+
+use callback_codegen::compile_callbacks;
+use client_codegen::generate_client_trait;
+use compat_codegen::compile_compat;
+use idl::write_generated;
+use idl_import::{IdlImport, import_idl};
+use lockfile::check_lockfile;
+use parse::{
+    InterfaceAttributes, InterfaceConsts, MethodAttributes, ParameterAttributes, SinceAttribute,
+};
+
+pub use client_codegen::compile_client;
+pub use enum_derive::derive_ndr_enum;
+pub use header::generate_header;
+pub use idl::generate_idl;
+pub use server_codegen::compile_server;
+pub use struct_derive::derive_ndr_struct;
+pub use types::{
+    BaseType, Interface, InterfaceVersion, Method, Parameter, StringForm, SystemHandleKind,
+    TransferSyntaxes, Type,
+};
+pub use union_derive::derive_ndr_union;
+
+/// Expands `#[rpc_interface(attr)]` on the trait `input`.
+pub fn expand_rpc_interface(
+    attr: proc_macro2::TokenStream,
+    input: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let (interface, files) = parse_trait(attr, input)?;
+
+    let lockfile_code = match &files.lockfile {
+        Some(path) => check_lockfile(&interface, path)?,
+        None => quote::quote! {},
+    };
+    if let Some(path) = &files.idl {
+        write_generated(path, &generate_idl(&interface))?;
+    }
+    if let Some(path) = &files.header {
+        write_generated(path, &generate_header(&interface))?;
+    }
+    let code = compile_interface(&interface);
+
+    Ok(quote::quote! {
+        #code
+        #lockfile_code
+    })
+}
+
+/// Parses the trait `input` with the `rpc_interface` arguments `attr` into its model, with
+/// the checks of the macro. The `lockfile`, `idl` and `header` arguments are ignored.
+pub fn parse_interface(
+    attr: proc_macro2::TokenStream,
+    input: proc_macro2::TokenStream,
+) -> syn::Result<Interface> {
+    parse_trait(attr, input).map(|(interface, _)| interface)
+}
+
+/// Files named by the `lockfile`, `idl` and `header` arguments
+struct GeneratedFiles {
+    lockfile: Option<syn::LitStr>,
+    idl: Option<syn::LitStr>,
+    header: Option<syn::LitStr>,
+}
+
+fn parse_trait(
+    attr: proc_macro2::TokenStream,
+    input: proc_macro2::TokenStream,
+) -> syn::Result<(Interface, GeneratedFiles)> {
+    // Parse interface attributes (guid and version)
+    let mut attrs: InterfaceAttributes = syn::parse2(attr)?;
+    let generic_handle = attrs.generic_handle.take();
+    let lockfile = attrs.lockfile.take();
+    let idl = attrs.idl.take();
+    let header = attrs.header.take();
+    let transfer_syntaxes = attrs.transfer_syntaxes;
+    let enums = std::mem::take(&mut attrs.enums);
+    // Types declared with `transmit_as(...)` are sent as numbers or structs
+    let mut transmit_as = vec![];
+    for (presented, wire) in std::mem::take(&mut attrs.transmit_as) {
+        match Type::try_from(wire.clone())?.resolve_enum(&enums) {
+            wire @ (Type::Simple(_) | Type::Struct(_)) => transmit_as.push((presented, wire)),
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    wire,
+                    "`transmit_as` types are sent as numbers or structs",
+                ));
+            }
+        }
+    }
+    // Returns the wire type and path of a type declared with `transmit_as(...)`
+    let transmitted = |r#type: &Type| match r#type {
+        Type::Struct(path) => transmit_as
+            .iter()
+            .find(|(presented, _)| presented == path)
+            .map(|(presented, wire)| (wire.clone(), presented.clone())),
+        _ => None,
+    };
+
+    let input_clone = input.clone();
+    let t: syn::ItemTrait = syn::parse2(input)?;
+
+    let mut consts = InterfaceConsts::default();
+    let mut methods = vec![];
+    let mut callbacks = vec![];
+    // Span of every parameter, to report invalid `#[since]` versions once the
+    // interface version is known
+    let mut since_spans = vec![];
+    for item in t.items {
+        if let TraitItem::Const(item) = &item {
+            consts.parse_const(item)?;
+            continue;
+        }
+        let TraitItem::Fn(func) = item else {
+            return Err(syn::Error::new_spanned(
+                input_clone,
+                "Only functions are allowed on this trait",
+            ));
+        };
+
+        let mut method_attrs = MethodAttributes::default();
+        let mut method_attr = None;
+        for attr in &func.attrs {
+            if attr.path().is_ident("rpc") {
+                method_attrs = attr.parse_args()?;
+                method_attr = Some(attr.clone());
+            }
+        }
+
+        // `Result<T, E>` returns the value `T`, or the error code `E` in its place
+        let mut error = None;
+        let mut output = func.sig.output;
+        if let ReturnType::Type(arrow, t) = &output
+            && let Some((value, error_type)) = result_types(t)
+        {
+            error = match Type::try_from(error_type.clone())?.resolve_enum(&enums) {
+                error @ (Type::Simple(BaseType::U32) | Type::Enum(_))
+                    if matches!(error_type, syn::Type::Path(_)) =>
+                {
+                    Some(error)
+                }
+                // `HRESULT`s are signed
+                error @ Type::Simple(BaseType::I32)
+                    if method_attrs.hresult.is_some()
+                        && matches!(error_type, syn::Type::Path(_)) =>
+                {
+                    Some(error)
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        error_type,
+                        "Errors are returned as `u32` codes or enums declared with `enums(...)`",
+                    ));
+                }
+            };
+            output = match value {
+                syn::Type::Tuple(tuple) if tuple.elems.is_empty() => ReturnType::Default,
+                value => ReturnType::Type(*arrow, Box::new(value)),
+            };
+        }
+
+        let mut presented_return = None;
+        let mut handle_return = method_attrs.system_handle.take();
+        let return_type = match output {
+            ReturnType::Default => None,
+            // Handles are returned as `OwnedHandle`, of the kind the method attribute gives
+            ReturnType::Type(_, t) if is_handle(&t) => {
+                if !is_handle_named(&t, "OwnedHandle") {
+                    return Err(syn::Error::new_spanned(
+                        t,
+                        "Handles are returned as `OwnedHandle`",
+                    ));
+                }
+                let Some(handle) = handle_return.take() else {
+                    return Err(syn::Error::new_spanned(
+                        t,
+                        "Returned handles need a `#[rpc(system_handle(kind))]` method attribute",
+                    ));
+                };
+                Some(Type::SystemHandle {
+                    kind: handle.kind,
+                    access: handle.access,
+                })
+            }
+            ReturnType::Type(_, t) if is_pipe(&t) => {
+                return Err(syn::Error::new_spanned(
+                    t,
+                    "Pipes are only supported as `InPipe<T>` and `OutPipe<T>` parameters",
+                ));
+            }
+            ReturnType::Type(_, t) => {
+                let mut return_type = Type::try_from((*t).clone())?.resolve_enum(&enums);
+                if let Some((wire, presented)) = transmitted(&return_type) {
+                    if matches!(*t, syn::Type::Reference(_)) {
+                        return Err(syn::Error::new_spanned(
+                            t,
+                            "`transmit_as` types are returned by value",
+                        ));
+                    }
+                    return_type = wire;
+                    presented_return = Some(presented);
+                }
+                if matches!(return_type, Type::Slice(_)) && matches!(*t, syn::Type::Reference(_)) {
+                    return Err(syn::Error::new_spanned(
+                        t,
+                        "Arrays are returned as `Vec<T>`",
+                    ));
+                }
+                if matches!(return_type, Type::Struct(_)) && matches!(*t, syn::Type::Reference(_)) {
+                    return Err(syn::Error::new_spanned(t, "Structs are returned by value"));
+                }
+                if matches!(return_type, Type::Enum(_)) && matches!(*t, syn::Type::Reference(_)) {
+                    return Err(syn::Error::new_spanned(t, "Enums are returned by value"));
+                }
+                if return_type == Type::ContextHandle && matches!(*t, syn::Type::Reference(_)) {
+                    return Err(syn::Error::new_spanned(
+                        t,
+                        "Context handles are returned by value, as `ContextHandle`",
+                    ));
+                }
+                if return_type == Type::OptionalString
+                    && matches!(&*t, syn::Type::Path(path) if is_option_of_reference(path))
+                {
+                    return Err(syn::Error::new_spanned(
+                        t,
+                        "Optional strings are returned as `Option<String>`",
+                    ));
+                }
+                Some(return_type)
+            }
+        };
+        if let Some(handle) = handle_return {
+            return Err(syn::Error::new_spanned(
+                handle.ident,
+                "`system_handle` is only supported on methods returning `OwnedHandle`",
+            ));
+        }
+        // Failed calls have no handle for the server stub to duplicate
+        if error.is_some() && matches!(return_type, Some(Type::SystemHandle { .. })) {
+            return Err(syn::Error::new_spanned(
+                &func.sig.ident,
+                "Methods returning handles cannot return errors",
+            ));
+        }
+
+        if let (Some(_), Some(ident)) = (&method_attrs.error_status, &method_attrs.hresult) {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`hresult` and `error_status` are exclusive",
+            ));
+        }
+        // The value goes through `[out]` pointers, which can't point to enums
+        if let Some(ident) = &method_attrs.error_status {
+            if error != Some(Type::Simple(BaseType::U32)) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`error_status` is only supported on methods returning `Result<T, u32>`",
+                ));
+            }
+            if matches!(return_type, Some(Type::Enum(_))) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`error_status` methods cannot return enums",
+                ));
+            }
+        }
+        if let Some(ident) = &method_attrs.hresult {
+            if error != Some(Type::Simple(BaseType::I32)) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`hresult` is only supported on methods returning `Result<T, i32>`",
+                ));
+            }
+            if matches!(return_type, Some(Type::Enum(_))) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`hresult` methods cannot return enums",
+                ));
+            }
+        }
+
+        let mut params = vec![];
+        // Index and `size_is` of every slice sized by another parameter, checked once all
+        // of them are known
+        let mut size_is_spans = vec![];
+        for param in func.sig.inputs {
+            let FnArg::Typed(typed) = param else {
+                return Err(syn::Error::new_spanned(
+                    input_clone,
+                    "Passing self is currently not supported",
+                ));
+            };
+
+            let syn::Pat::Ident(param_name) = *typed.pat else {
+                return Err(syn::Error::new_spanned(
+                    typed.pat.to_token_stream(),
+                    "Expected identifier",
+                ));
+            };
+
+            if generic_handle.is_some() && param_name.ident == "binding_handle" {
+                return Err(syn::Error::new_spanned(
+                    &param_name.ident,
+                    "`binding_handle` is reserved for the generic handle parameter",
+                ));
+            }
+
+            let mut since = None;
+            let mut in_out = None;
+            let mut range = None;
+            let mut size_is = None;
+            let mut ptr = None;
+            let mut system_handle = None;
+            let mut ansi = None;
+            let mut presented = None;
+            for attr in &typed.attrs {
+                if attr.path().is_ident("since") {
+                    let attr: SinceAttribute = attr.parse_args()?;
+                    since = Some((attr.version, attr.default));
+                } else if attr.path().is_ident("in_out") {
+                    attr.meta.require_path_only()?;
+                    in_out = Some(attr.clone());
+                } else if attr.path().is_ident("rpc") {
+                    let attributes: ParameterAttributes = attr.parse_args()?;
+                    range = attributes.range.map(|range| (range, attr.clone()));
+                    size_is = attributes.size_is;
+                    ptr = attributes.ptr;
+                    system_handle = attributes.system_handle;
+                    ansi = attributes.ansi;
+                }
+            }
+            if since.is_none() && params.iter().any(|p: &Parameter| p.since.is_some()) {
+                return Err(syn::Error::new_spanned(
+                    &param_name.ident,
+                    "Parameters following a `#[since]` parameter must also be marked `#[since]`",
+                ));
+            }
+
+            // `&mut T` parameters are `[out]` pointers to numbers or structs, and `&mut [T]`
+            // ones to arrays, or `[in, out]` ones when marked `#[in_out]`
+            let string_form = string_form(&typed.ty);
+            let (param_type, is_out) = match *typed.ty {
+                // Strings taken as another type than `&str`, see `StringForm`, are sent alike
+                _ if string_form != StringForm::Str => (Type::String, false),
+                // Pipes are taken by value, and their type gives their direction
+                ty if is_pipe(&ty) => {
+                    if matches!(ty, syn::Type::Reference(_)) {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            "Pipes are passed by value, as `InPipe<T>` or `OutPipe<T>` parameters",
+                        ));
+                    }
+                    let is_out = is_handle_named(&ty, "OutPipe");
+                    (Type::try_from(ty)?, is_out)
+                }
+                // Handles are lent as `BorrowedHandle<'_>`, of the kind the attribute gives
+                ty if is_handle(&ty) => {
+                    if !is_handle_named(&ty, "BorrowedHandle") {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            "Handles are passed as `BorrowedHandle<'_>` parameters",
+                        ));
+                    }
+                    let Some(handle) = system_handle.take() else {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            "Handle parameters need a `#[rpc(system_handle(kind))]` attribute",
+                        ));
+                    };
+                    let handle_type = Type::SystemHandle {
+                        kind: handle.kind,
+                        access: handle.access,
+                    };
+                    (handle_type, false)
+                }
+                syn::Type::Reference(reference) if reference.mutability.is_some() => {
+                    let param_type = if let syn::Type::Slice(_) = &*reference.elem {
+                        Type::try_from(syn::Type::Reference(syn::TypeReference {
+                            mutability: None,
+                            ..reference.clone()
+                        }))?
+                    } else {
+                        Type::try_from((*reference.elem).clone())?.resolve_enum(&enums)
+                    };
+                    if transmitted(&param_type).is_some() {
+                        return Err(syn::Error::new_spanned(
+                            reference,
+                            "`transmit_as` types are not supported as `&mut` out parameters",
+                        ));
+                    }
+                    if matches!(param_type, Type::Slice(_))
+                        && !matches!(*reference.elem, syn::Type::Slice(_))
+                    {
+                        return Err(syn::Error::new_spanned(
+                            reference,
+                            "Arrays are passed as `&mut [T]` out parameters",
+                        ));
+                    }
+                    // Context handles are closed by the server through `[in, out]` ones, which
+                    // only need the handle to be passed by pointer
+                    if param_type == Type::ContextHandle && in_out.is_none() {
+                        return Err(syn::Error::new_spanned(
+                            reference,
+                            "Context handles are opened as return values, `&mut ContextHandle` parameters must be `#[in_out]`",
+                        ));
+                    }
+                    if !matches!(
+                        param_type,
+                        Type::Simple(_)
+                            | Type::Struct(_)
+                            | Type::Slice(_)
+                            | Type::StringVec
+                            | Type::ContextHandle
+                    ) {
+                        return Err(syn::Error::new_spanned(
+                            reference,
+                            "Only numbers, structs, arrays and `Vec<String>` are supported as `&mut` out parameters",
+                        ));
+                    }
+                    if since.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            reference,
+                            "`#[since]` is not supported on out parameters",
+                        ));
+                    }
+                    (param_type, true)
+                }
+                syn::Type::Path(path) => {
+                    let param_type =
+                        Type::try_from(syn::Type::Path(path.clone()))?.resolve_enum(&enums);
+                    if let Some((wire, presented_path)) = transmitted(&param_type) {
+                        presented = Some(presented_path);
+                        (wire, false)
+                    } else {
+                        match param_type {
+                            Type::Struct(_) => {
+                                return Err(syn::Error::new_spanned(
+                                    path,
+                                    "Structs are passed by reference, as `&T` parameters",
+                                ));
+                            }
+                            Type::Slice(_) => {
+                                return Err(syn::Error::new_spanned(
+                                    path,
+                                    "Arrays are passed as `&[T]` parameters",
+                                ));
+                            }
+                            Type::StringVec => {
+                                return Err(syn::Error::new_spanned(
+                                    path,
+                                    "Arrays of strings are only supported as `Vec<String>` return values and `&mut Vec<String>` out parameters",
+                                ));
+                            }
+                            Type::ContextHandle => {
+                                return Err(syn::Error::new_spanned(
+                                    path,
+                                    "Context handles are passed as `&ContextHandle` or `#[in_out] &mut ContextHandle` parameters",
+                                ));
+                            }
+                            Type::OptionalString if !is_option_of_reference(&path) => {
+                                return Err(syn::Error::new_spanned(
+                                    path,
+                                    "Optional strings are passed as `Option<&str>` parameters",
+                                ));
+                            }
+                            param_type => (param_type, false),
+                        }
+                    }
+                }
+                ty => match Type::try_from(ty.clone())?.resolve_enum(&enums) {
+                    param_type if transmitted(&param_type).is_some() => {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            "`transmit_as` types are passed by value, as `T` parameters",
+                        ));
+                    }
+                    Type::Enum(_) => {
+                        return Err(syn::Error::new_spanned(
+                            ty,
+                            "Enums are passed by value, as `T` parameters",
+                        ));
+                    }
+                    param_type => (param_type, false),
+                },
+            };
+            if let Some(handle) = system_handle {
+                return Err(syn::Error::new_spanned(
+                    handle.ident,
+                    "`system_handle` is only supported on `BorrowedHandle<'_>` parameters",
+                ));
+            }
+            // Older clients would hold no context to pass
+            if param_type == Type::ContextHandle && since.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &param_name.ident,
+                    "`#[since]` is not supported on context handles",
+                ));
+            }
+            // Older clients would have no pipe for the server to pull or push
+            if matches!(param_type, Type::Pipe(_)) && since.is_some() {
+                return Err(syn::Error::new_spanned(
+                    &param_name.ident,
+                    "`#[since]` is not supported on pipes",
+                ));
+            }
+            // `[in, out]` pipes would be pulled from and pushed to in the same call
+            if let Some(attr) = &in_out
+                && matches!(param_type, Type::Pipe(_))
+            {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`#[in_out]` is not supported on pipes, take an `InPipe<T>` and an `OutPipe<T>`",
+                ));
+            }
+            if let Some(attr) = &in_out
+                && !is_out
+            {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`#[in_out]` is only supported on `&mut` parameters",
+                ));
+            }
+            // The server fills the vector, so the client's elements aren't sent
+            if let Some(attr) = &in_out
+                && param_type == Type::StringVec
+            {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`&mut Vec<String>` parameters are out only, `#[in_out]` is not supported",
+                ));
+            }
+
+            // Ranges bound `[in]` integers that NDR 2.0 range descriptors can hold, or the
+            // `u32` length of slices
+            if let Some(((min, max), attr)) = &range {
+                let bounds = match &param_type {
+                    Type::Simple(base_type) if !is_out => base_type.range_bounds(),
+                    Type::Slice(_) => BaseType::U32.range_bounds(),
+                    _ => None,
+                };
+                let Some((lowest, highest)) = bounds else {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "`range` is only supported on integers of up to 32 bits and slices",
+                    ));
+                };
+                if min > max || *min < lowest || *max > highest {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        format!(
+                            "Expected a range within {lowest}..={highest}, with the minimum first"
+                        ),
+                    ));
+                }
+            }
+
+            // The length of a `size_is` slice is another parameter, which may follow it
+            if let Some(length) = &size_is {
+                if !matches!(param_type, Type::Slice(_)) {
+                    return Err(syn::Error::new_spanned(
+                        length,
+                        "`size_is` is only supported on `&[T]` and `&mut [T]` parameters",
+                    ));
+                }
+                if let Some((_, attr)) = &range {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "The range of a `size_is` slice goes on its length parameter",
+                    ));
+                }
+                size_is_spans.push((params.len(), length.clone()));
+            }
+
+            // Full pointers replace the unique pointers of options, and the reference
+            // pointers of `[in]` structs, which may then alias each other
+            if let Some(ptr) = &ptr
+                && (is_out
+                    || presented.is_some()
+                    || !matches!(
+                        param_type,
+                        Type::Optional(_) | Type::OptionalString | Type::Struct(_)
+                    ))
+            {
+                return Err(syn::Error::new_spanned(
+                    ptr,
+                    "`ptr` is only supported on `Option<T>` and `&T` struct parameters",
+                ));
+            }
+
+            // ANSI strings replace the wide strings of `&str` parameters
+            let param_type = match (ansi, param_type) {
+                (Some(_), Type::String) if string_form == StringForm::Str => Type::AnsiString,
+                (Some(_), Type::OptionalString) => Type::OptionalAnsiString,
+                (Some(ansi), _) => {
+                    return Err(syn::Error::new_spanned(
+                        ansi,
+                        "`ansi` is only supported on `&str` and `Option<&str>` parameters",
+                    ));
+                }
+                (None, param_type) => param_type,
+            };
+
+            params.push(Parameter {
+                r#type: param_type,
+                name: param_name.ident.to_string(),
+                is_in: !is_out || in_out.is_some(),
+                is_out,
+                since: since.as_ref().map(|(version, _)| *version),
+                default: since.map(|(_, default)| default.to_token_stream().to_string()),
+                range: range.map(|(range, _)| range),
+                size_is: size_is.map(|length| length.to_string()),
+                full_pointer: ptr.is_some(),
+                presented,
+                string_form,
+            });
+            since_spans.push(param_name.ident.span());
+        }
+        for (index, length) in size_is_spans {
+            let slice = &params[index];
+            let Some(length_param) = params
+                .iter()
+                .find(|p| length == p.name && p.r#type == Type::Simple(BaseType::U32) && !p.is_out)
+            else {
+                return Err(syn::Error::new_spanned(
+                    length,
+                    "`size_is` must name a `u32` parameter of the method",
+                ));
+            };
+            // Calls from older clients would have the slice without its length
+            if length_param.since > slice.since {
+                return Err(syn::Error::new_spanned(
+                    length,
+                    "`size_is` cannot name a `#[since]` parameter added after the slice",
+                ));
+            }
+        }
+
+        // No reply carries a return value or out parameters back
+        if let Some(attr) = &method_attr
+            && method_attrs.maybe
+            && (return_type.is_some() || error.is_some() || params.iter().any(|p| p.is_out))
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`#[rpc(maybe)]` methods cannot return values or have out parameters",
+            ));
+        }
+        // The pipes are streamed within the call, which `maybe` and `broadcast` calls
+        // don't wait for
+        let has_pipes = params.iter().any(|p| matches!(p.r#type, Type::Pipe(_)));
+        if let Some(attr) = &method_attr
+            && has_pipes
+            && (method_attrs.maybe || method_attrs.broadcast)
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`maybe` and `broadcast` methods cannot have pipe parameters",
+            ));
+        }
+        // Calls without a reply have nothing to complete
+        if let Some(ident) = &method_attrs.r#async
+            && method_attrs.maybe
+        {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`async` methods cannot be `maybe`",
+            ));
+        }
+        if let Some(ident) = &method_attrs.r#async
+            && (return_type == Some(Type::ContextHandle)
+                || params.iter().any(|p| p.r#type == Type::ContextHandle))
+        {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`async` methods cannot take or return context handles",
+            ));
+        }
+        // Asynchronous pipes are driven through `RpcAsyncPull/PushPipe` notifications instead
+        if let Some(ident) = &method_attrs.r#async
+            && has_pipes
+        {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`async` methods cannot have pipe parameters",
+            ));
+        }
+
+        if let Some(ident) = &method_attrs.callback {
+            if method_attrs.r#async.is_some() {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`callback` methods cannot be `async`",
+                ));
+            }
+            if return_type == Some(Type::ContextHandle)
+                || params.iter().any(|p| p.r#type == Type::ContextHandle)
+            {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`callback` methods cannot take or return context handles",
+                ));
+            }
+            if has_pipes {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`callback` methods cannot have pipe parameters",
+                ));
+            }
+            // Callbacks have no call paths for older versions
+            if params.iter().any(|p| p.since.is_some()) {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "`callback` methods cannot have `#[since]` parameters",
+                ));
+            }
+            since_spans.truncate(since_spans.len() - params.len());
+        }
+
+        let method = Method {
+            return_type,
+            name: func.sig.ident.to_string(),
+            parameters: params,
+            maybe: method_attrs.maybe,
+            idempotent: method_attrs.idempotent,
+            broadcast: method_attrs.broadcast,
+            presented_return,
+            error,
+            error_status: method_attrs.error_status.is_some(),
+            hresult: method_attrs.hresult.is_some(),
+            r#async: method_attrs.r#async.is_some(),
+        };
+        if method_attrs.callback.is_some() {
+            callbacks.push(method);
+        } else {
+            methods.push(method);
+        }
+    }
+
+    let (uuid, version) = consts.resolve(attrs, t.ident.span())?;
+
+    let params = methods.iter().flat_map(|m: &Method| &m.parameters);
+    for (param, span) in params.zip(since_spans) {
+        let Some(since) = param.since else {
+            continue;
+        };
+        if since.major != version.major || since.minor == 0 || since > version {
+            return Err(syn::Error::new(
+                span,
+                "`#[since]` must name a minor version of the interface's major version, \
+                 up to the interface version",
+            ));
+        }
+        if generic_handle.is_some() {
+            return Err(syn::Error::new(
+                span,
+                "`#[since]` parameters are not supported with `generic_handle`",
+            ));
+        }
+        // The call paths of older versions don't dispatch callbacks
+        if !callbacks.is_empty() {
+            return Err(syn::Error::new(
+                span,
+                "`#[since]` parameters are not supported with callbacks",
+            ));
+        }
+    }
+    for method in &methods {
+        if !method.parameters.windows(2).all(|pair| {
+            pair[0]
+                .since
+                .is_none_or(|since| pair[1].since >= Some(since))
+        }) {
+            return Err(syn::Error::new(
+                t.ident.span(),
+                format!(
+                    "The `#[since]` parameters of `{}` must be in version order",
+                    method.name
+                ),
+            ));
+        }
+    }
+    let interface = Interface {
+        name: t.ident.to_string(),
+        uuid,
+        version,
+        methods,
+        callbacks,
+        generic_handle,
+        transfer_syntaxes,
+    };
+    let files = GeneratedFiles {
+        lockfile,
+        idl,
+        header,
+    };
+    Ok((interface, files))
+}
+
+/// Generates the code `rpc_interface` generates for `interface`: the client, its
+/// `{Interface}` trait, the server with its `{Interface}ServerImpl` trait, and the call
+/// paths of older versions.
+///
+/// The interface is expected to follow the rules `rpc_interface` checks, e.g. `#[since]`
+/// parameters are trailing and in version order and `maybe` methods return nothing.
+pub fn compile_interface(interface: &Interface) -> proc_macro2::TokenStream {
+    let client_code = compile_client(interface);
+    let client_trait = generate_client_trait(interface);
+    let server_code = compile_server(interface);
+    let compat_code = compile_compat(interface);
+    let callbacks_code = compile_callbacks(interface);
+    let struct_checks = ndr::generate_struct_checks(interface);
+    // The RPC runtime only implements NDR64 on 64-bit Windows
+    let target_check = (interface.transfer_syntaxes == TransferSyntaxes::Ndr64).then(|| {
+        let message = format!(
+            "`{}` is generated with `transfer_syntax(ndr64)`, which requires a 64-bit target",
+            interface.name
+        );
+        quote::quote! {
+            #[cfg(not(target_pointer_width = "64"))]
+            compile_error!(#message);
+        }
+    });
+
+    // Pipes are passed by value, as pointers to copies on 64-bit targets only, which the
+    // one-slot pipe parameters assume
+    let pipe_target_check = interface.has_pipes().then(|| {
+        let message = format!(
+            "`{}` has pipe parameters, which require a 64-bit target",
+            interface.name
+        );
+        quote::quote! {
+            #[cfg(not(target_pointer_width = "64"))]
+            compile_error!(#message);
+        }
+    });
+
+    quote::quote! {
+        #target_check
+        #pipe_target_check
+        #struct_checks
+        #client_code
+        #client_trait
+        #server_code
+        #compat_code
+        #callbacks_code
+    }
+}
+
+/// Expands `rpc_interface_from_idl!(input)`.
+pub fn expand_rpc_interface_from_idl(
+    input: proc_macro2::TokenStream,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let import: IdlImport = syn::parse2(input)?;
+    let (interfaces, mut code) = import_idl(&import)?;
+    for interface in interfaces {
+        code.extend(expand_rpc_interface(interface.attributes, interface.item)?);
+    }
+    Ok(code)
+}
+
+/// Parses the interfaces of the MIDL file at `path` into their models, as
+/// `rpc_interface_from_idl!` does. A relative path is relative to the current directory,
+/// or to `CARGO_MANIFEST_DIR` when it is set.
+pub fn parse_idl_file(path: &std::path::Path) -> syn::Result<Vec<Interface>> {
+    let import = IdlImport {
+        path: syn::LitStr::new(&path.to_string_lossy(), proc_macro2::Span::call_site()),
+        attributes: proc_macro2::TokenStream::new(),
+    };
+    let (interfaces, _) = import_idl(&import)?;
+    interfaces
+        .into_iter()
+        .map(|interface| parse_interface(interface.attributes, interface.item))
+        .collect()
+}
+
+/// Whether the type is a `BorrowedHandle` or an `OwnedHandle`, or a reference to one
+fn is_handle(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(reference) => is_handle(&reference.elem),
+        ty => is_handle_named(ty, "BorrowedHandle") || is_handle_named(ty, "OwnedHandle"),
+    }
+}
+
+/// Whether the type is an `InPipe` or an `OutPipe`, or a reference to one
+fn is_pipe(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(reference) => is_pipe(&reference.elem),
+        ty => is_handle_named(ty, "InPipe") || is_handle_named(ty, "OutPipe"),
+    }
+}
+
+/// Whether the type is the handle type `name`, e.g. `std::os::windows::io::OwnedHandle`
+fn is_handle_named(ty: &syn::Type, name: &str) -> bool {
+    matches!(ty, syn::Type::Path(path)
+        if path.path.segments.last().is_some_and(|segment| segment.ident == name))
+}
+
+/// Returns the Rust type a string parameter of type `ty` is taken as: `String`, `&String`,
+/// `Cow<'_, str>`, `&OsStr`, `OsString`, `&Path` or `PathBuf`, or `StringForm::Str` for
+/// `&str` and other types
+fn string_form(ty: &syn::Type) -> StringForm {
+    let (reference, ty) = match ty {
+        syn::Type::Reference(reference) if reference.mutability.is_none() => {
+            (true, &*reference.elem)
+        }
+        ty => (false, ty),
+    };
+    let syn::Type::Path(path) = ty else {
+        return StringForm::Str;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return StringForm::Str;
+    };
+    let is_str = |argument: &syn::GenericArgument| matches!(argument, syn::GenericArgument::Type(syn::Type::Path(path)) if path.path.is_ident("str"));
+    match (&segment.arguments, reference) {
+        (syn::PathArguments::None, false) if segment.ident == "String" => StringForm::String,
+        (syn::PathArguments::None, true) if segment.ident == "String" => StringForm::StringRef,
+        (syn::PathArguments::None, true) if segment.ident == "OsStr" => StringForm::OsStr,
+        (syn::PathArguments::None, false) if segment.ident == "OsString" => StringForm::OsString,
+        (syn::PathArguments::None, true) if segment.ident == "Path" => StringForm::Path,
+        (syn::PathArguments::None, false) if segment.ident == "PathBuf" => StringForm::PathBuf,
+        (syn::PathArguments::AngleBracketed(arguments), false)
+            if segment.ident == "Cow" && arguments.args.last().is_some_and(is_str) =>
+        {
+            StringForm::Cow
+        }
+        _ => StringForm::Str,
+    }
+}
+
+/// Returns the value and error types of a `Result<T, E>`
+fn result_types(ty: &syn::Type) -> Option<(syn::Type, syn::Type)> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.iter().collect::<Vec<_>>()[..] {
+        [
+            syn::GenericArgument::Type(value),
+            syn::GenericArgument::Type(error),
+        ] if segment.ident == "Result" => Some((value.clone(), error.clone())),
+        _ => None,
+    }
+}
+
+/// Whether the type is `Option<&T>`
+fn is_option_of_reference(path: &syn::TypePath) -> bool {
+    let Some(segment) = path.path.segments.last() else {
+        return false;
+    };
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return false;
+    };
+    matches!(
+        arguments.args.first(),
+        Some(syn::GenericArgument::Type(syn::Type::Reference(_)))
+    )
+}
@@ -0,0 +1,117 @@
+//! Wire-contract lockfile, opted into with `lockfile("path")`.
+//!
+//! The contract of an interface is what peers built from other revisions depend on: its
+//! GUID, version, and the NDR descriptors of its procedures in opnum order (including the
+//! older versions kept for `#[since]` parameters). Method and parameter names are not
+//! part of it. The contract is hashed and compared with the hash recorded for the
+//! interface in the lockfile, a text file of `Name = hash` lines, so that a trait edit
+//! that changes the wire format fails the build instead of breaking deployed peers.
+//!
+//! Setting `WINDOWS_RPC_UPDATE_LOCKFILE=1` while building records the current hashes.
+
+use std::path::{Path, PathBuf};
+
+use quote::quote;
+
+use crate::ndr::{generate_proc_header, generate_type_format_string};
+use crate::ndr64::generate_ndr64_type_format;
+use crate::types::Interface;
+
+/// Environment variable that makes the build record contract hashes instead of checking them
+const UPDATE_VARIABLE: &str = "WINDOWS_RPC_UPDATE_LOCKFILE";
+
+/// Checks the contract of `interface` against the lockfile at `path`, relative to the
+/// manifest directory of the crate being compiled.
+///
+/// Returns tokens making the crate rebuild when the lockfile changes.
+pub fn check_lockfile(
+    interface: &Interface,
+    path: &syn::LitStr,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let full_path = PathBuf::from(manifest_dir).join(path.value());
+    let hash = format!("{:016x}", contract_hash(interface));
+    let error = |message: String| syn::Error::new_spanned(path, message);
+
+    let contents = std::fs::read_to_string(&full_path).unwrap_or_default();
+    let recorded = contents.lines().find_map(|line| {
+        let (name, hash) = line.split_once('=')?;
+        (name.trim() == interface.name).then(|| hash.trim().to_string())
+    });
+
+    if recorded.as_deref() != Some(hash.as_str()) {
+        if std::env::var(UPDATE_VARIABLE).is_ok_and(|value| value == "1") {
+            update(&full_path, &contents, &interface.name, &hash)
+                .map_err(|e| error(format!("Failed to update {}: {e}", full_path.display())))?;
+        } else {
+            return Err(error(match recorded {
+                Some(recorded) => format!(
+                    "The wire contract of `{}` changed (locked {recorded}, now {hash}). \
+                     Peers built from the locked revision may no longer interoperate; if the \
+                     change is intended, rebuild with {UPDATE_VARIABLE}=1 to update the lockfile",
+                    interface.name
+                ),
+                None => format!(
+                    "`{}` is not in the lockfile; add `{} = {hash}` to it or rebuild with \
+                     {UPDATE_VARIABLE}=1",
+                    interface.name, interface.name
+                ),
+            }));
+        }
+    }
+
+    let full_path = full_path.to_string_lossy();
+    Ok(quote! {
+        const _: &[u8] = include_bytes!(#full_path);
+    })
+}
+
+/// Replaces or appends the line of `name` in the lockfile
+fn update(path: &Path, contents: &str, name: &str, hash: &str) -> std::io::Result<()> {
+    let entry = format!("{name} = {hash}");
+    let mut lines: Vec<String> = contents
+        .lines()
+        .filter(|line| line.split_once('=').is_none_or(|(n, _)| n.trim() != name))
+        .map(str::to_string)
+        .collect();
+    lines.push(entry);
+    lines.sort();
+    std::fs::write(path, lines.join("\n") + "\n")
+}
+
+/// Hashes the wire contract of the interface with FNV-1a, which is stable across
+/// compiler versions
+fn contract_hash(interface: &Interface) -> u64 {
+    let mut contract = vec![];
+    for interface in std::iter::once(interface.clone()).chain(
+        interface
+            .compat_versions()
+            .into_iter()
+            .map(|version| interface.at_version(version)),
+    ) {
+        contract.extend_from_slice(&interface.uuid.to_le_bytes());
+        contract.extend_from_slice(&interface.version.major.to_le_bytes());
+        contract.extend_from_slice(&interface.version.minor.to_le_bytes());
+        contract.extend_from_slice(&(interface.methods.len() as u32).to_le_bytes());
+
+        let (type_format, type_offsets) = generate_type_format_string(&interface);
+        let (proc_header, _, type_fixups) = generate_proc_header(&interface, &type_offsets);
+        for section in [
+            type_format,
+            proc_header,
+            generate_ndr64_type_format(&interface),
+        ] {
+            contract.extend_from_slice(&(section.len() as u32).to_le_bytes());
+            contract.extend_from_slice(&section);
+        }
+        // Struct layouts and enum widths are only known to the compiler, so they are
+        // identified by path
+        for fixup in type_fixups {
+            contract.extend_from_slice(fixup.r#type.struct_path().to_string().as_bytes());
+        }
+    }
+
+    contract.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,1088 @@
+use std::collections::HashMap;
+
+use quote::quote;
+
+use crate::constants::*;
+use crate::types::{BaseType, Interface, Method, Parameter, Type};
+
+pub fn ndr_fc_long(value: u32) -> [u8; 4] {
+    [
+        (value & 0xFF) as u8,
+        ((value >> 8) & 0xFF) as u8,
+        ((value >> 16) & 0xFF) as u8,
+        ((value >> 24) & 0xFF) as u8,
+    ]
+}
+
+pub fn ndr_fc_short(value: u16) -> [u8; 2] {
+    [(value & 0xFF) as u8, ((value >> 8) & 0xFF) as u8]
+}
+
+/// Buffer space reserved for each out base type: the largest type plus its alignment
+pub const OUT_BASE_TYPE_BUFFER_SIZE: usize = 16;
+
+/// Buffer space reserved for each context handle: its 20-byte wire form plus its alignment
+pub const CONTEXT_HANDLE_BUFFER_SIZE: usize = 24;
+
+/// Flags of the `FC_BIND_CONTEXT` of a returned context handle: an `[out]` pointer to it,
+/// which the server stub creates
+pub const RETURN_CONTEXT_FLAGS: u8 = NDR_CONTEXT_HANDLE_IS_VIA_PTR | NDR_CONTEXT_HANDLE_IS_OUT;
+
+/// Key for type offset lookup - can be a parameter or a return type marker
+#[derive(PartialEq, Eq, Hash, Clone)]
+pub enum TypeKey {
+    Parameter(Parameter),
+    ReturnString, // Out string for return value
+    // Out conformant array for a `Vec<T>` return value, sized by the hidden length
+    // parameter at `length_offset`
+    ReturnSlice {
+        element: BaseType,
+        length_offset: u16,
+        range: CorrelationRange,
+    },
+    // Conformant array sized by the `u32` parameter at `length_offset`: its hidden length,
+    // or the `size_is` parameter, which is `early` when it precedes the array
+    Slice {
+        element: BaseType,
+        length_offset: u16,
+        early: bool,
+        range: CorrelationRange,
+    },
+    // Out array of unique string pointers for a `Vec<String>` return value or out
+    // parameter, sized like `ReturnSlice`
+    ReturnStringVec {
+        length_offset: u16,
+        range: CorrelationRange,
+    },
+    // Out unique pointer for an `Option<T>` return value
+    ReturnOptional(BaseType),
+    // Full pointer to a struct, whose offset to the struct fragment is filled at runtime
+    StructFullPointer(Type),
+    // `FC_RANGE` of a ranged integer, or of the hidden length of a ranged slice
+    Range {
+        base_type: BaseType,
+        min: i64,
+        max: i64,
+    },
+    // `FC_SYSTEM_HANDLE` of a handle parameter or return value
+    SystemHandle(Type),
+    // `FC_BIND_CONTEXT` of a context handle parameter or return value, by its flags
+    ContextHandle(u8),
+    // `FC_PIPE` of `[in]` and `[out]` pipes of the element type
+    Pipe(BaseType),
+}
+
+/// Range following a correlation descriptor, which the descriptors of the procedures with
+/// `RANGE_ON_CONFORMANCE` have
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CorrelationRange {
+    /// The procedure has no ranges on conformance
+    Absent,
+    /// The conformance isn't checked
+    Unbounded,
+    /// The conformance must be within `min..=max`
+    Bounded(i64, i64),
+}
+
+impl CorrelationRange {
+    /// Returns the range of a correlation descriptor of `method`, for a slice of `range`
+    pub fn new(method: &Method, range: Option<(i64, i64)>) -> Self {
+        match range {
+            _ if !method.has_range_on_conformance() => CorrelationRange::Absent,
+            Some((min, max)) => CorrelationRange::Bounded(min, max),
+            None => CorrelationRange::Unbounded,
+        }
+    }
+
+    /// Appends the range to a correlation descriptor
+    fn push(self, type_format: &mut Vec<u8>) {
+        let (flags, min, max) = match self {
+            CorrelationRange::Absent => return,
+            CorrelationRange::Unbounded => (FC_CORR_RANGE_NONE, 0, 0),
+            CorrelationRange::Bounded(min, max) => (FC_CORR_RANGE, min, max),
+        };
+        type_format.push(flags);
+        type_format.push(0);
+        type_format.extend_from_slice(&ndr_fc_long(min as u32));
+        type_format.extend_from_slice(&ndr_fc_long(max as u32));
+    }
+}
+
+/// Returns the key of the conformant array of the slice parameter at `index` of `method`,
+/// given the stack offsets of its parameters
+fn slice_key(method: &Method, index: usize, element: BaseType, stack_offsets: &[u16]) -> TypeKey {
+    let param = &method.parameters[index];
+    let (length_offset, early) = match method.size_param_index(param) {
+        Some(length_index) => (stack_offsets[length_index], length_index < index),
+        None => (stack_offsets[index], true),
+    };
+    TypeKey::Slice {
+        element,
+        length_offset,
+        early,
+        range: CorrelationRange::new(method, param.length_range()),
+    }
+}
+
+/// Returns the key of the `FC_RANGE` of a ranged `base_type`
+fn range_key(base_type: BaseType, (min, max): (i64, i64)) -> TypeKey {
+    TypeKey::Range {
+        base_type,
+        min,
+        max,
+    }
+}
+
+pub fn generate_type_format_string(interface: &Interface) -> (Vec<u8>, HashMap<TypeKey, u16>) {
+    let mut type_format = vec![];
+    let mut type_offsets = HashMap::new();
+
+    // Start with padding short (always 0)
+    type_format.extend_from_slice(&ndr_fc_short(0));
+
+    // Collect all unique types that need descriptors
+    let mut types_to_process = Vec::new();
+    for method in &interface.methods {
+        let (stack_offsets, return_stack_offset) =
+            method.stack_offsets(std::mem::size_of::<usize>());
+        for (index, param) in method.parameters.iter().enumerate() {
+            let type_key = match param.r#type {
+                Type::Simple(base_type) => match param.value_range() {
+                    Some(range) => range_key(base_type, range),
+                    None => continue,
+                },
+                // Struct fragments are appended at runtime, see `generate_type_fixups()`
+                Type::Struct(_) if param.full_pointer => {
+                    TypeKey::StructFullPointer(param.r#type.clone())
+                }
+                Type::Struct(_) | Type::Enum(_) => continue,
+                Type::Slice(element) => {
+                    if let Some(range) = param.length_range() {
+                        let length_key = range_key(BaseType::U32, range);
+                        if !types_to_process.contains(&length_key) {
+                            types_to_process.push(length_key);
+                        }
+                    }
+                    slice_key(method, index, element, &stack_offsets)
+                }
+                Type::String
+                | Type::WideString
+                | Type::HString
+                | Type::AnsiString
+                | Type::Optional(_)
+                | Type::OptionalString
+                | Type::OptionalAnsiString => TypeKey::Parameter(param.clone()),
+                Type::SystemHandle { .. } => TypeKey::SystemHandle(param.r#type.clone()),
+                Type::ContextHandle => TypeKey::ContextHandle(param.context_flags()),
+                Type::Pipe(element) => TypeKey::Pipe(element),
+                // Sized by the length in the parameter's first slot
+                Type::StringVec => TypeKey::ReturnStringVec {
+                    length_offset: stack_offsets[index],
+                    range: CorrelationRange::new(method, None),
+                },
+            };
+            if !types_to_process.contains(&type_key) {
+                types_to_process.push(type_key);
+            }
+        }
+        // Check if method has a string return type, which may also be optional: string
+        // return values are unique pointers
+        if let Some(return_type) = &method.return_type
+            && (return_type.is_string() || *return_type == Type::OptionalString)
+            && !types_to_process.contains(&TypeKey::ReturnString)
+        {
+            types_to_process.push(TypeKey::ReturnString);
+        }
+        if let Some(Type::Slice(element)) = method.return_type {
+            let type_key = TypeKey::ReturnSlice {
+                element,
+                length_offset: return_stack_offset,
+                range: CorrelationRange::new(method, None),
+            };
+            if !types_to_process.contains(&type_key) {
+                types_to_process.push(type_key);
+            }
+        }
+        if let Some(Type::StringVec) = method.return_type {
+            let type_key = TypeKey::ReturnStringVec {
+                length_offset: return_stack_offset,
+                range: CorrelationRange::new(method, None),
+            };
+            if !types_to_process.contains(&type_key) {
+                types_to_process.push(type_key);
+            }
+        }
+        if let Some(Type::Optional(base_type)) = method.return_type
+            && !types_to_process.contains(&TypeKey::ReturnOptional(base_type))
+        {
+            types_to_process.push(TypeKey::ReturnOptional(base_type));
+        }
+        if let Some(handle_type @ Type::SystemHandle { .. }) = &method.return_type {
+            let type_key = TypeKey::SystemHandle(handle_type.clone());
+            if !types_to_process.contains(&type_key) {
+                types_to_process.push(type_key);
+            }
+        }
+        if method.return_type == Some(Type::ContextHandle) {
+            let type_key = TypeKey::ContextHandle(RETURN_CONTEXT_FLAGS);
+            if !types_to_process.contains(&type_key) {
+                types_to_process.push(type_key);
+            }
+        }
+    }
+
+    // Generate type descriptors
+    for type_key in types_to_process {
+        let current_offset = type_format.len() as u16;
+        type_offsets.insert(type_key.clone(), current_offset);
+
+        match &type_key {
+            TypeKey::Parameter(param) => match param.r#type {
+                Type::String | Type::WideString | Type::HString => {
+                    if param.is_in && !param.is_out {
+                        // Simple pointer to conformant string (for [in] parameters)
+                        // FC_RP [simple_pointer]
+                        type_format.push(FC_RP);
+                        type_format.push(FC_SIMPLE_POINTER);
+                        // FC_C_WSTRING (unicode wide string)
+                        type_format.push(FC_C_WSTRING);
+                        type_format.push(FC_PAD);
+                    } else if param.is_out {
+                        // Pointer to pointer to conformant string (for [out] parameters)
+                        // FC_RP [alloced_on_stack] [pointer_deref]
+                        type_format.push(FC_RP);
+                        type_format.push(0x14); // alloced_on_stack | pointer_deref
+                        // Offset to the next pointer descriptor
+                        type_format.extend_from_slice(&ndr_fc_short(2));
+
+                        // FC_UP [simple_pointer]
+                        type_format.push(FC_UP);
+                        type_format.push(FC_SIMPLE_POINTER);
+                        // FC_C_WSTRING (unicode wide string)
+                        type_format.push(FC_C_WSTRING);
+                        type_format.push(FC_PAD);
+                    }
+                }
+                Type::AnsiString => {
+                    // Simple pointer to conformant ANSI string ([in] parameters only)
+                    // FC_RP [simple_pointer]
+                    type_format.push(FC_RP);
+                    type_format.push(FC_SIMPLE_POINTER);
+                    // FC_C_CSTRING (char string)
+                    type_format.push(FC_C_CSTRING);
+                    type_format.push(FC_PAD);
+                }
+                Type::Optional(base_type) => {
+                    // Unique or full pointer to the value, null for `None`
+                    // FC_UP [simple_pointer]
+                    type_format.push(if param.full_pointer { FC_FP } else { FC_UP });
+                    type_format.push(FC_SIMPLE_POINTER);
+                    type_format.push(base_type.to_fc_value());
+                    type_format.push(FC_PAD);
+                }
+                Type::OptionalString => {
+                    // Unique or full pointer to conformant string, null for `None`
+                    // FC_UP [simple_pointer]
+                    type_format.push(if param.full_pointer { FC_FP } else { FC_UP });
+                    type_format.push(FC_SIMPLE_POINTER);
+                    type_format.push(FC_C_WSTRING);
+                    type_format.push(FC_PAD);
+                }
+                Type::OptionalAnsiString => {
+                    // Unique or full pointer to conformant ANSI string, null for `None`
+                    // FC_UP [simple_pointer]
+                    type_format.push(if param.full_pointer { FC_FP } else { FC_UP });
+                    type_format.push(FC_SIMPLE_POINTER);
+                    type_format.push(FC_C_CSTRING);
+                    type_format.push(FC_PAD);
+                }
+                Type::Simple(_)
+                | Type::Slice(_)
+                | Type::Struct(_)
+                | Type::Enum(_)
+                | Type::SystemHandle { .. }
+                | Type::ContextHandle
+                | Type::Pipe(_)
+                | Type::StringVec => {
+                    // Simple types don't need type descriptors, slices use TypeKey::Slice,
+                    // handles TypeKey::SystemHandle or TypeKey::ContextHandle and pipes
+                    // TypeKey::Pipe
+                }
+            },
+            TypeKey::Slice {
+                element,
+                length_offset,
+                early,
+                range,
+            } => {
+                // FC_RP to the array. The parameter is a simple ref, so it refers to the
+                // array directly and this pointer is only kept for parity with MIDL.
+                type_format.push(FC_RP);
+                type_format.push(0);
+                type_format.extend_from_slice(&ndr_fc_short(2));
+
+                type_offsets.insert(type_key.clone(), type_format.len() as u16);
+                // FC_CARRAY, alignment, element size
+                type_format.push(FC_CARRAY);
+                type_format.push((element.size() - 1) as u8);
+                type_format.extend_from_slice(&ndr_fc_short(element.size() as u16));
+                // Conformance descriptor: the u32 length parameter on the stack, checked
+                // once unmarshalled when it follows the array
+                type_format.push(FC_TOP_LEVEL_CONFORMANCE | FC_ULONG);
+                type_format.push(0); // No operator
+                type_format.extend_from_slice(&ndr_fc_short(*length_offset));
+                type_format.extend_from_slice(&ndr_fc_short(if *early {
+                    FC_CORR_FLAGS_EARLY
+                } else {
+                    0
+                }));
+                range.push(&mut type_format);
+                // Element type
+                type_format.push(element.to_fc_value());
+                type_format.push(FC_END);
+            }
+            TypeKey::StructFullPointer(_) => {
+                // FC_FP to the struct, whose offset is filled once it is appended
+                type_format.push(FC_FP);
+                type_format.push(0);
+                type_format.extend_from_slice(&ndr_fc_short(0));
+            }
+            TypeKey::Range {
+                base_type,
+                min,
+                max,
+            } => {
+                // FC_RANGE, the type compared with its signedness, then the bounds
+                type_format.push(FC_RANGE);
+                type_format.push(base_type.to_range_fc_value());
+                type_format.extend_from_slice(&ndr_fc_long(*min as u32));
+                type_format.extend_from_slice(&ndr_fc_long(*max as u32));
+            }
+            TypeKey::SystemHandle(handle_type) => {
+                let Type::SystemHandle { kind, access } = handle_type else {
+                    unreachable!("system handle keys hold system handle types");
+                };
+                // FC_SYSTEM_HANDLE, the kind of object, then the access of the duplicate
+                type_format.push(FC_SYSTEM_HANDLE);
+                type_format.push(kind.to_fc_value());
+                type_format.extend_from_slice(&ndr_fc_long(*access));
+            }
+            TypeKey::ContextHandle(flags) => {
+                // FC_BIND_CONTEXT, its flags, the index of the server's rundown routine,
+                // and the parameter number used for bindings through context handles,
+                // which procedures don't do as they take an explicit handle
+                type_format.push(FC_BIND_CONTEXT);
+                type_format.push(*flags);
+                type_format.push(0);
+                type_format.push(0);
+            }
+            TypeKey::Pipe(element) => {
+                // The element type, which the FC_PIPE that the parameters refer to follows
+                type_format.push(element.to_fc_value());
+                type_format.push(FC_PAD);
+                type_offsets.insert(type_key.clone(), type_format.len() as u16);
+                // FC_PIPE, the element alignment, the offset to the element type relative
+                // to the offset itself, then the memory and wire sizes of an element
+                type_format.push(FC_PIPE);
+                type_format.push((element.size() - 1) as u8);
+                type_format.extend_from_slice(&ndr_fc_short(-4i16 as u16));
+                type_format.extend_from_slice(&ndr_fc_short(element.size() as u16));
+                type_format.extend_from_slice(&ndr_fc_short(element.size() as u16));
+            }
+            TypeKey::ReturnString => {
+                // Out string return value: wchar_t**
+                // FC_RP [alloced_on_stack] [pointer_deref]
+                type_format.push(FC_RP);
+                type_format.push(0x14); // alloced_on_stack | pointer_deref
+                // Offset to the next pointer descriptor
+                type_format.extend_from_slice(&ndr_fc_short(2));
+
+                // FC_UP [simple_pointer]
+                type_format.push(FC_UP);
+                type_format.push(FC_SIMPLE_POINTER);
+                // FC_C_WSTRING (unicode wide string)
+                type_format.push(FC_C_WSTRING);
+                type_format.push(FC_PAD);
+            }
+            TypeKey::ReturnOptional(base_type) => {
+                // Out optional return value: T**
+                // FC_RP [alloced_on_stack] [pointer_deref]
+                type_format.push(FC_RP);
+                type_format.push(0x14); // alloced_on_stack | pointer_deref
+                type_format.extend_from_slice(&ndr_fc_short(2));
+
+                // FC_UP [simple_pointer], null for `None`
+                type_format.push(FC_UP);
+                type_format.push(FC_SIMPLE_POINTER);
+                type_format.push(base_type.to_fc_value());
+                type_format.push(FC_PAD);
+            }
+            TypeKey::ReturnSlice {
+                element,
+                length_offset,
+                range,
+            } => {
+                // Out array return value: T**
+                // FC_RP [alloced_on_stack] [pointer_deref]
+                type_format.push(FC_RP);
+                type_format.push(0x14); // alloced_on_stack | pointer_deref
+                type_format.extend_from_slice(&ndr_fc_short(2));
+
+                // FC_UP to the array, which may be null when it is empty
+                type_format.push(FC_UP);
+                type_format.push(0);
+                type_format.extend_from_slice(&ndr_fc_short(2));
+
+                // FC_CARRAY, alignment, element size
+                type_format.push(FC_CARRAY);
+                type_format.push((element.size() - 1) as u8);
+                type_format.extend_from_slice(&ndr_fc_short(element.size() as u16));
+                // Conformance descriptor: the u32 pointed to by the hidden length parameter
+                type_format.push(FC_TOP_LEVEL_CONFORMANCE | FC_ULONG);
+                type_format.push(FC_DEREFERENCE);
+                type_format.extend_from_slice(&ndr_fc_short(*length_offset));
+                type_format.extend_from_slice(&ndr_fc_short(FC_CORR_FLAGS_EARLY));
+                range.push(&mut type_format);
+                // Element type
+                type_format.push(element.to_fc_value());
+                type_format.push(FC_END);
+            }
+            TypeKey::ReturnStringVec {
+                length_offset,
+                range,
+            } => {
+                // Out string array return value: wchar_t***
+                // FC_RP [alloced_on_stack] [pointer_deref]
+                type_format.push(FC_RP);
+                type_format.push(0x14); // alloced_on_stack | pointer_deref
+                type_format.extend_from_slice(&ndr_fc_short(2));
+
+                // FC_UP to the array, which may be null when it is empty
+                type_format.push(FC_UP);
+                type_format.push(0);
+                type_format.extend_from_slice(&ndr_fc_short(2));
+
+                // FC_BOGUS_ARRAY of pointers, aligned as their 4-byte referent IDs, with no
+                // fixed number of elements
+                type_format.push(FC_BOGUS_ARRAY);
+                type_format.push(3);
+                type_format.extend_from_slice(&ndr_fc_short(0));
+                // Conformance descriptor: the u32 pointed to by the hidden length parameter
+                type_format.push(FC_TOP_LEVEL_CONFORMANCE | FC_ULONG);
+                type_format.push(FC_DEREFERENCE);
+                type_format.extend_from_slice(&ndr_fc_short(*length_offset));
+                type_format.extend_from_slice(&ndr_fc_short(FC_CORR_FLAGS_EARLY));
+                range.push(&mut type_format);
+                // Variance descriptor: the whole array is sent
+                type_format.extend_from_slice(&ndr_fc_long(FC_NO_CORRELATION));
+                type_format.extend_from_slice(&ndr_fc_short(0));
+                range.push(&mut type_format);
+                // Element type: FC_UP [simple_pointer] to a conformant string, null only if
+                // the server fails to allocate it
+                type_format.push(FC_UP);
+                type_format.push(FC_SIMPLE_POINTER);
+                type_format.push(FC_C_WSTRING);
+                type_format.push(FC_PAD);
+                type_format.push(FC_PAD);
+                type_format.push(FC_END);
+            }
+        }
+    }
+
+    // End marker
+    type_format.push(0);
+
+    (type_format, type_offsets)
+}
+
+/// Returns the parameter descriptors of a string vector, the return value or an out
+/// parameter: its length out parameter (u32*) at `stack_offset`, followed by the string
+/// array out parameter (wchar_t***) described at `type_offset` in the next slot
+fn string_vec_descriptors(type_offset: u16, stack_offset: u16) -> Vec<u8> {
+    let mut descriptors = vec![];
+    descriptors.extend_from_slice(&ndr_fc_short(
+        PARAM_ATTRIBUTES_IS_OUT
+            | PARAM_ATTRIBUTES_IS_BASE_TYPE
+            | PARAM_ATTRIBUTES_IS_SIMPLE_REF
+            | PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8,
+    ));
+    descriptors.extend_from_slice(&ndr_fc_short(stack_offset));
+    descriptors.extend_from_slice(&ndr_fc_short(BaseType::U32.to_fc_value() as u16));
+
+    descriptors.extend_from_slice(&ndr_fc_short(
+        PARAM_ATTRIBUTES_MUST_SIZE
+            | PARAM_ATTRIBUTES_MUST_FREE
+            | PARAM_ATTRIBUTES_IS_OUT
+            | PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8,
+    ));
+    descriptors.extend_from_slice(&ndr_fc_short(
+        stack_offset + std::mem::size_of::<usize>() as u16,
+    ));
+    descriptors.extend_from_slice(&ndr_fc_short(type_offset));
+    descriptors
+}
+
+/// Part of the formats describing a user-defined type, only known once the generated
+/// code is compiled: the type offset of a struct parameter or the format character of an
+/// enum parameter in the proc header, or the offset of a full pointer to a struct in the
+/// type format
+pub struct TypeFixup {
+    /// Position of the type offset or format character in the proc header, or of the
+    /// pointer's offset in the type format
+    pub position: usize,
+    /// The struct or enum type
+    pub r#type: Type,
+    /// Whether `position` is in the type format
+    pub in_type_format: bool,
+}
+
+// Returns proc header, procedure offsets and the parts to fix up for structs and enums
+pub fn generate_proc_header(
+    interface: &Interface,
+    type_offsets: &HashMap<TypeKey, u16>,
+) -> (Vec<u8>, Vec<u16>, Vec<TypeFixup>) {
+    let mut header = vec![];
+    let mut proc_offsets: Vec<u16> = vec![];
+    let mut type_fixups = vec![];
+
+    for (proc_index, proc) in interface.methods.iter().enumerate() {
+        proc_offsets.push(header.len().try_into().unwrap());
+
+        // Calculate stack size:
+        // - 8 bytes for the RPC_ASYNC_STATE of asynchronous procedures
+        // - 8 bytes for binding handle (first implicit param)
+        // - 8 bytes per parameter slot (slices take two: length and pointer)
+        // - 8 bytes per hidden out parameter of the return value (the string or struct
+        //   pointer, or the vector length and array pointer)
+        // - 8 bytes for the hidden status out parameter of the client
+        // - 8 bytes for the return value (if simple type)
+        let (param_stack_offsets, return_stack_offset) =
+            proc.stack_offsets(std::mem::size_of::<usize>());
+        let stack_size = proc.stack_size(std::mem::size_of::<usize>());
+
+        // Explicit handle
+        header.push(0);
+        // Oi_flags
+        // Failed calls are reported through the status parameter instead of raising
+        let full_pointer_flag = if proc.uses_full_pointers() {
+            Oi_FULL_PTR_USED
+        } else {
+            0
+        };
+        header.push(
+            Oi_HAS_RPCFLAGS | Oi_USE_NEW_INIT_ROUTINES | Oi_HAS_COMM_OR_FAULT | full_pointer_flag,
+        );
+        // rpc_flags
+        header.extend_from_slice(&ndr_fc_long(proc.rpc_flags()));
+        // proc_num
+        header.extend_from_slice(&ndr_fc_short(proc_index.try_into().unwrap()));
+        // Stack size - the total size of all parameters on the stack,
+        // including any this pointer and/or return value
+        header.extend_from_slice(&ndr_fc_short(stack_size));
+
+        // Explicit handle
+        // handle_type
+        header.push(FC_BIND_PRIMITIVE);
+        // IsPassByPointer
+        header.push(0);
+        // Offset from the beginning of the stack to the primitive handle: the first
+        // parameter, following the RPC_ASYNC_STATE of asynchronous procedures
+        header.extend_from_slice(&ndr_fc_short(
+            proc.binding_stack_offset(std::mem::size_of::<usize>()),
+        ));
+        // constant_client_buffer_size
+        // Only a partial size when the ClientMustSize flag triggers the sizing of the
+        // parameters of variable size.
+        header.extend_from_slice(&ndr_fc_short(
+            proc.constant_client_buffer_size().try_into().unwrap(),
+        ));
+        // constant_server_buffer_size
+        // Likewise partial when the ServerMustSize flag is set.
+        header.extend_from_slice(&ndr_fc_short(
+            proc.constant_server_buffer_size().try_into().unwrap(),
+        ));
+        // INTERPRETER_OPT_FLAGS (OI2 flags)
+        let has_slice_param = proc.has_slice_param();
+        // Returned vectors and out slices are sized by the server
+        let has_out_array = proc.has_slice_return() || proc.has_out_slice_param();
+        // Count params including hidden slice lengths and the out string, struct or vector
+        // return value (which becomes out params)
+        let param_count = proc.abi_param_count() + proc.out_return_param_count();
+        let oi2_flags = 0x40 // has ext
+            | if proc.has_base_type_return() { 0x04 } else { 0 } // has return (only for base types)
+            | if proc.client_must_size() { OI2_CLIENT_MUST_SIZE } else { 0 } // client must size
+            | if proc.server_must_size() { OI2_SERVER_MUST_SIZE } else { 0 } // server must size
+            | if proc.has_pipe_param() { OI2_HAS_PIPES } else { 0 } // has pipes
+            | if proc.r#async { OI2_HAS_ASYNC_HANDLE } else { 0 }; // has async handle
+        header.push(oi2_flags);
+        // Number of parameters (includes out string if returning string)
+        header.push(param_count.try_into().unwrap());
+
+        // Extension section
+        // extension_version (size of this section in bytes)
+        header.push(10);
+        // INTERPRETER_OPT_FLAGS2
+        // FIXME: when do we set ServerCorrCheck and ClientCorrCheck?
+        // https://learn.microsoft.com/en-us/windows/win32/rpc/the-header
+        header.push(
+            INTERPRETER_OPT_FLAGS2_NEW_CORRELATION_DESCRIPTOR
+            // The correlation descriptors of the procedure are followed by ranges, which
+            // the server checks array sizes against
+                | if proc.has_range_on_conformance() {
+                    INTERPRETER_OPT_FLAGS2_RANGE_ON_CONFORMANCE
+                } else {
+                    0
+                }
+            // The server must verify array sizes against their length parameters
+                | if has_slice_param {
+                    INTERPRETER_OPT_FLAGS2_SERVER_CORR_CHECK
+                } else {
+                    0
+                }
+            // The client must verify returned array sizes against their lengths
+                | if has_out_array {
+                    INTERPRETER_OPT_FLAGS2_CLIENT_CORR_CHECK
+                } else {
+                    0
+                },
+        );
+        // ClientCorrHint - some cache hint for the client
+        // FIXME: figure out
+        header.extend_from_slice(&ndr_fc_short(if has_out_array { 1 } else { 0 }));
+        // ServerCorrHint - some cache hint for the server
+        // FIXME: figure out
+        header.extend_from_slice(&ndr_fc_short(if has_slice_param { 1 } else { 0 }));
+        // Notify routine index, if one is used
+        header.extend_from_slice(&ndr_fc_short(0));
+        // FloatDoubleMask - relevant only for 64-bit, where it only has room for the
+        // first 8 stack slots. It follows the host, like the stack offsets, so that the
+        // extension matches its size when generating on another 64-bit platform.
+        #[cfg(target_pointer_width = "64")]
+        header.extend_from_slice(&ndr_fc_short(proc.float_double_mask()));
+
+        // Parameters, followed by the hidden error parameter
+        // The first parameter is the RPC handle, skip it.
+        for (index, (param, &param_stack_offset)) in proc
+            .wire_parameters()
+            .iter()
+            .zip(&param_stack_offsets)
+            .enumerate()
+        {
+            if let Type::Slice(element) = param.r#type
+                && param.size_is.is_some()
+            {
+                // The array alone, sized by another parameter
+                header.extend_from_slice(&ndr_fc_short(param.param_attributes()));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&slice_key(proc, index, element, &param_stack_offsets))
+                        .unwrap(),
+                ));
+                continue;
+            }
+            if param.is_out_string_vec() {
+                // Out length (u32*), followed by the string array (wchar_t***) in the next
+                // slot, as for string vector return values
+                header.extend_from_slice(&string_vec_descriptors(
+                    *type_offsets
+                        .get(&TypeKey::ReturnStringVec {
+                            length_offset: param_stack_offset,
+                            range: CorrelationRange::new(proc, None),
+                        })
+                        .unwrap(),
+                    param_stack_offset,
+                ));
+                continue;
+            }
+            if let Type::Slice(element) = param.r#type {
+                // Hidden length parameter, passed by value to its `FC_RANGE` when ranged
+                if let Some(range) = param.length_range() {
+                    header.extend_from_slice(&ndr_fc_short(
+                        PARAM_ATTRIBUTES_IS_IN | PARAM_ATTRIBUTES_IS_BY_VALUE,
+                    ));
+                    header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                    header.extend_from_slice(&ndr_fc_short(
+                        *type_offsets.get(&range_key(BaseType::U32, range)).unwrap(),
+                    ));
+                } else {
+                    header.extend_from_slice(&ndr_fc_short(
+                        PARAM_ATTRIBUTES_IS_IN | PARAM_ATTRIBUTES_IS_BASE_TYPE,
+                    ));
+                    header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                    header.extend_from_slice(&ndr_fc_short(BaseType::U32.to_fc_value() as u16));
+                }
+
+                // The array itself, in the next slot
+                header.extend_from_slice(&ndr_fc_short(param.param_attributes()));
+                header.extend_from_slice(&ndr_fc_short(
+                    param_stack_offset + std::mem::size_of::<usize>() as u16,
+                ));
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&slice_key(proc, index, element, &param_stack_offsets))
+                        .unwrap(),
+                ));
+                continue;
+            }
+
+            // PARAM_ATTRIBUTES
+            header.extend_from_slice(&ndr_fc_short(param.param_attributes()));
+            // stack_offset
+            header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+            // type_offset OR base type value for simple types
+            if let (Type::Simple(base_type), Some(range)) = (&param.r#type, param.value_range()) {
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets.get(&range_key(*base_type, range)).unwrap(),
+                ));
+            } else if let Type::Simple(base_type) = &param.r#type {
+                header.extend_from_slice(&ndr_fc_short(base_type.to_fc_value() as u16));
+            } else if let (Type::Struct(_), true) = (&param.r#type, param.full_pointer) {
+                let type_offset = *type_offsets
+                    .get(&TypeKey::StructFullPointer(param.r#type.clone()))
+                    .unwrap();
+                header.extend_from_slice(&ndr_fc_short(type_offset));
+                // The pointer is shared by the parameters of the struct's type
+                let position = usize::from(type_offset) + 2;
+                if !type_fixups
+                    .iter()
+                    .any(|fixup: &TypeFixup| fixup.in_type_format && fixup.position == position)
+                {
+                    type_fixups.push(TypeFixup {
+                        position,
+                        r#type: param.r#type.clone(),
+                        in_type_format: true,
+                    });
+                }
+            } else if let Type::SystemHandle { .. } = &param.r#type {
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&TypeKey::SystemHandle(param.r#type.clone()))
+                        .unwrap(),
+                ));
+            } else if param.r#type == Type::ContextHandle {
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&TypeKey::ContextHandle(param.context_flags()))
+                        .unwrap(),
+                ));
+            } else if let Type::Pipe(element) = param.r#type {
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets.get(&TypeKey::Pipe(element)).unwrap(),
+                ));
+            } else if let Type::Struct(_) | Type::Enum(_) = &param.r#type {
+                type_fixups.push(TypeFixup {
+                    position: header.len(),
+                    r#type: param.r#type.clone(),
+                    in_type_format: false,
+                });
+                header.extend_from_slice(&ndr_fc_short(0));
+            } else {
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&TypeKey::Parameter(param.clone()))
+                        .unwrap(),
+                ));
+            }
+        }
+        let param_stack_offset = return_stack_offset;
+
+        // Handle return type: values other than base types become hidden out parameters
+        match &proc.return_type {
+            Some(Type::String | Type::WideString | Type::HString | Type::OptionalString) => {
+                // String return value becomes an out parameter (wchar_t**)
+                // PARAM_ATTRIBUTES: 0x2013 = MUST_SIZE | MUST_FREE | IS_OUT | SERVER_ALLOC_SIZE_8
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_MUST_SIZE
+                        | PARAM_ATTRIBUTES_MUST_FREE
+                        | PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8,
+                ));
+                // stack_offset
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                // type_offset
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets.get(&TypeKey::ReturnString).unwrap(),
+                ));
+            }
+            Some(Type::Optional(base_type)) => {
+                // Optional return value becomes an out parameter (T**)
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_MUST_SIZE
+                        | PARAM_ATTRIBUTES_MUST_FREE
+                        | PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8,
+                ));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&TypeKey::ReturnOptional(*base_type))
+                        .unwrap(),
+                ));
+            }
+            Some(struct_type @ Type::Struct(_)) => {
+                // Struct return value becomes an out parameter (T*)
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_MUST_SIZE
+                        | PARAM_ATTRIBUTES_MUST_FREE
+                        | PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_IS_SIMPLE_REF,
+                ));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                type_fixups.push(TypeFixup {
+                    position: header.len(),
+                    r#type: struct_type.clone(),
+                    in_type_format: false,
+                });
+                header.extend_from_slice(&ndr_fc_short(0));
+            }
+            Some(handle_type @ Type::SystemHandle { .. }) => {
+                // Handle return value becomes an out parameter (HANDLE*), which the
+                // server stub allocates
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_MUST_SIZE
+                        | PARAM_ATTRIBUTES_MUST_FREE
+                        | PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_IS_SIMPLE_REF
+                        | PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8,
+                ));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&TypeKey::SystemHandle(handle_type.clone()))
+                        .unwrap(),
+                ));
+            }
+            Some(Type::ContextHandle) => {
+                // Context handle return value becomes an out parameter to the handle
+                // (void**), referring to its FC_BIND_CONTEXT directly
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_IS_OUT | PARAM_ATTRIBUTES_IS_SIMPLE_REF,
+                ));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&TypeKey::ContextHandle(RETURN_CONTEXT_FLAGS))
+                        .unwrap(),
+                ));
+            }
+            Some(Type::Slice(element)) => {
+                // Vector return value becomes a length out parameter (u32*)...
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_IS_BASE_TYPE
+                        | PARAM_ATTRIBUTES_IS_SIMPLE_REF
+                        | PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8,
+                ));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                header.extend_from_slice(&ndr_fc_short(BaseType::U32.to_fc_value() as u16));
+
+                // ...and an array out parameter (T**) in the next slot
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_MUST_SIZE
+                        | PARAM_ATTRIBUTES_MUST_FREE
+                        | PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8,
+                ));
+                header.extend_from_slice(&ndr_fc_short(
+                    param_stack_offset + std::mem::size_of::<usize>() as u16,
+                ));
+                header.extend_from_slice(&ndr_fc_short(
+                    *type_offsets
+                        .get(&TypeKey::ReturnSlice {
+                            element: *element,
+                            length_offset: param_stack_offset,
+                            range: CorrelationRange::new(proc, None),
+                        })
+                        .unwrap(),
+                ));
+            }
+            Some(Type::StringVec) => {
+                // String vector return value becomes a length out parameter (u32*) and a
+                // string array out parameter (wchar_t***)
+                header.extend_from_slice(&string_vec_descriptors(
+                    *type_offsets
+                        .get(&TypeKey::ReturnStringVec {
+                            length_offset: param_stack_offset,
+                            range: CorrelationRange::new(proc, None),
+                        })
+                        .unwrap(),
+                    param_stack_offset,
+                ));
+            }
+            Some(Type::AnsiString | Type::OptionalAnsiString) => {
+                unreachable!("ANSI strings are only input parameters")
+            }
+            Some(Type::Pipe(_)) => unreachable!("pipes are only parameters"),
+            // Returned by value below, or through the hidden value parameter of
+            // `error_status` and `hresult` methods
+            Some(Type::Simple(_) | Type::Enum(_)) | None => {}
+        }
+
+        // Base type return value, following the hidden status parameter
+        let param_stack_offset = proc.base_type_return_stack_offset(std::mem::size_of::<usize>());
+        match proc.base_type_return() {
+            Some(Type::Simple(_)) if proc.returns_status() => {
+                // The error status of the call, or its `HRESULT`, a signed long
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_IS_RETURN
+                        | PARAM_ATTRIBUTES_IS_BASE_TYPE,
+                ));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                let status = if proc.hresult {
+                    FC_LONG
+                } else {
+                    FC_ERROR_STATUS_T
+                };
+                header.extend_from_slice(&ndr_fc_short(status as u16));
+            }
+            Some(Type::Simple(return_type)) => {
+                // Simple type return value
+                // PARAM_ATTRIBUTES
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_IS_RETURN
+                        | PARAM_ATTRIBUTES_IS_BASE_TYPE,
+                ));
+                // stack_offset
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                // type_offset OR base type value for simple types
+                header.extend_from_slice(&ndr_fc_short(return_type.to_fc_value() as u16));
+            }
+            Some(enum_type @ Type::Enum(_)) => {
+                // Enum return value, whose format character is fixed up at runtime
+                header.extend_from_slice(&ndr_fc_short(
+                    PARAM_ATTRIBUTES_IS_OUT
+                        | PARAM_ATTRIBUTES_IS_RETURN
+                        | PARAM_ATTRIBUTES_IS_BASE_TYPE,
+                ));
+                header.extend_from_slice(&ndr_fc_short(param_stack_offset));
+                type_fixups.push(TypeFixup {
+                    position: header.len(),
+                    r#type: enum_type,
+                    in_type_format: false,
+                });
+                header.extend_from_slice(&ndr_fc_short(0));
+            }
+            _ => {}
+        }
+    }
+
+    // Zero marks the end of the header
+    header.push(0);
+    (header, proc_offsets, type_fixups)
+}
+
+/// Generates code appending the `FC_STRUCT` and `FC_ENCAPSULATED_UNION` fragments of the
+/// structs and unions used by the interface to `type_format`, and writing their offsets and the format characters of the
+/// enums it uses into `proc_header`, or into the full pointers to them in `type_format`.
+///
+/// `type_format` and `proc_header` must be mutable boxed slices in scope.
+pub fn generate_type_fixups(type_fixups: &[TypeFixup]) -> proc_macro2::TokenStream {
+    if type_fixups.is_empty() {
+        return quote! {};
+    }
+
+    let enum_patches = type_fixups
+        .iter()
+        .filter(|fixup| matches!(fixup.r#type, Type::Enum(_)))
+        .map(|fixup| {
+            let path = fixup.r#type.struct_path();
+            let position = fixup.position;
+            quote! {
+                proc_header[#position] = <#path as windows_rpc::ndr::NdrEnum>::NDR_FORMAT_CHAR;
+            }
+        });
+
+    let mut struct_types: Vec<&Type> = vec![];
+    for fixup in type_fixups {
+        if matches!(fixup.r#type, Type::Struct(_)) && !struct_types.contains(&&fixup.r#type) {
+            struct_types.push(&fixup.r#type);
+        }
+    }
+    if struct_types.is_empty() {
+        return quote! { #(#enum_patches)* };
+    }
+    let appends = struct_types.iter().map(|struct_type| {
+        let path = struct_type.struct_path();
+        let patches = type_fixups
+            .iter()
+            .filter(|fixup| &fixup.r#type == *struct_type)
+            .map(|fixup| {
+                let (start, end) = (fixup.position, fixup.position + 2);
+                if fixup.in_type_format {
+                    // Pointer offsets are relative to their own position
+                    let relative_to = start as u16;
+                    quote! {
+                        __type_format[#start..#end]
+                            .copy_from_slice(&(__offset - #relative_to).to_le_bytes());
+                    }
+                } else {
+                    quote! { proc_header[#start..#end].copy_from_slice(&__offset.to_le_bytes()); }
+                }
+            });
+        quote! {
+            let __offset = u16::try_from(__type_format.len()).expect("type format string is too long");
+            __type_format.extend_from_slice(<#path as windows_rpc::ndr::NdrType>::ndr_format());
+            #(#patches)*
+        }
+    });
+
+    quote! {
+        #(#enum_patches)*
+        let mut __type_format = type_format.into_vec();
+        #(#appends)*
+        __type_format.push(0);
+        type_format = __type_format.into_boxed_slice();
+    }
+}
+
+/// Generates compile-time checks of the structs with pointer fields used by the interface:
+/// they are only input parameters, as the stubs would allocate and free their pointees
+/// with `midl_user_allocate`, and those with arrays aren't parameters of procedures with
+/// ranged slices, whose correlation descriptors are followed by ranges that the struct
+/// fragments lack.
+pub fn generate_struct_checks(interface: &Interface) -> proc_macro2::TokenStream {
+    let mut checks: Vec<(String, &Type, &str)> = vec![];
+    for method in &interface.methods {
+        let outputs = method
+            .parameters
+            .iter()
+            .filter(|param| param.is_out)
+            .map(|param| &param.r#type)
+            .chain(&method.return_type);
+        for struct_type in outputs {
+            if let Type::Struct(path) = struct_type {
+                let message = format!(
+                    "`{path}` has pointer fields, so `{}` can only take it as an input parameter",
+                    method.name
+                );
+                checks.push((message, struct_type, "HAS_POINTERS"));
+            }
+        }
+        if method.has_range_on_conformance() {
+            for param in &method.parameters {
+                if let Type::Struct(path) = &param.r#type {
+                    let message = format!(
+                        "`{path}` has arrays sized by its fields, which `{}` can't take along with ranged slices",
+                        method.name
+                    );
+                    checks.push((message, &param.r#type, "HAS_CONFORMANCE"));
+                }
+            }
+        }
+    }
+
+    let checks = checks.iter().map(|(message, struct_type, property)| {
+        let path = struct_type.struct_path();
+        let property = quote::format_ident!("{property}");
+        quote! {
+            const _: () = assert!(
+                !<#path as windows_rpc::ndr::NdrType>::#property,
+                #message,
+            );
+        }
+    });
+    quote! { #(#checks)* }
+}
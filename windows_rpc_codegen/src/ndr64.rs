@@ -0,0 +1,1105 @@
+use quote::quote;
+
+use crate::constants::{
+    FC_SIMPLE_POINTER, NDR64_ARRAY_HAS_POINTER_INFO, NDR64_FC_BIND_CONTEXT, NDR64_FC_BOGUS_ARRAY,
+    NDR64_FC_CONF_ARRAY, NDR64_FC_CONF_CHAR_STRING, NDR64_FC_CONF_WCHAR_STRING, NDR64_FC_EXPR_OPER,
+    NDR64_FC_EXPR_VAR, NDR64_FC_FP, NDR64_FC_INT64, NDR64_FC_PIPE, NDR64_FC_RANGE,
+    NDR64_FC_SYSTEM_HANDLE, NDR64_FC_UP, NDR64_IS_BASE_TYPE, NDR64_IS_BY_VALUE, NDR64_IS_IN,
+    NDR64_IS_OUT, NDR64_IS_SIMPLE_REF, NDR64_MUST_FREE, NDR64_MUST_SIZE,
+    NDR64_OP_UNARY_INDIRECTION, NDR64_PROC_CLIENT_HAS_CORRELATION, NDR64_PROC_IS_ASYNC,
+    NDR64_PROC_SERVER_HAS_CORRELATION, NDR64_PROC_USES_FULL_PTR_PACKAGE, NDR64_PROC_USES_PIPES,
+    NDR64_USE_CACHE,
+};
+use crate::ndr::{CONTEXT_HANDLE_BUFFER_SIZE, OUT_BASE_TYPE_BUFFER_SIZE, RETURN_CONTEXT_FLAGS};
+use crate::types::{BaseType, Interface, Type};
+
+pub fn generate_ndr64_type_format(interface: &Interface) -> Vec<u8> {
+    // Type fragments must be contiguous in memory (not separately boxed)
+    // For NDR64, even base types need type descriptors that can be pointed to
+    // Collect all unique types and write them sequentially into one Vec<u8>
+    let mut type_format = vec![];
+
+    // Generate type formats for all unique types
+    for t in interface.unique_types() {
+        match t {
+            Type::String => {
+                // NDR64_CONFORMANT_STRING_FORMAT (4 bytes)
+                // This is used for input strings
+                type_format.push(NDR64_FC_CONF_WCHAR_STRING); // 0x64
+                type_format.push(0); // flags byte
+                type_format.extend_from_slice(&2u16.to_le_bytes()); // element size = 2 for wchar_t
+            }
+            Type::AnsiString => {
+                // NDR64_CONFORMANT_STRING_FORMAT of `char` strings (4 bytes)
+                type_format.push(NDR64_FC_CONF_CHAR_STRING); // 0x63
+                type_format.push(0); // flags byte
+                type_format.extend_from_slice(&1u16.to_le_bytes()); // element size = 1 for char
+            }
+            Type::Simple(bt) => {
+                type_format.push(bt.to_ndr64_fc_value());
+            }
+            Type::Slice(_)
+            | Type::WideString
+            | Type::HString
+            | Type::Struct(_)
+            | Type::Enum(_)
+            | Type::Optional(_)
+            | Type::OptionalString
+            | Type::OptionalAnsiString
+            | Type::SystemHandle { .. }
+            | Type::ContextHandle
+            | Type::Pipe(_)
+            | Type::StringVec => {
+                unreachable!(
+                    "slices, wide strings, structs, enums, options, handles and pipes are not listed in unique_types"
+                )
+            }
+        }
+    }
+
+    // If we have out strings, we need to add the pointer chain for them
+    // Note: The pointer structures for out strings are generated at runtime in proc buffer
+    // because they need actual pointers to other type format entries
+
+    type_format
+}
+
+/// Returns true if the interface has any string return types, optional or not
+pub fn has_string_return(interface: &Interface) -> bool {
+    interface.methods.iter().any(|m| {
+        m.return_type
+            .as_ref()
+            .is_some_and(|t| t.is_string() || *t == Type::OptionalString)
+    })
+}
+
+// Helper to compute type offset in the ndr64_type_format buffer
+// Note: Strings take 4 bytes, simple types take 1 byte
+pub fn compute_type_offset(interface: &Interface, target_type: &Type) -> usize {
+    // All string types share the descriptor of strings, and enums the one of 32-bit integers
+    let ndr64_enum = Type::ndr64_enum();
+    let target_type = match target_type {
+        Type::WideString | Type::HString => &Type::String,
+        Type::Enum(_) => &ndr64_enum,
+        _ => target_type,
+    };
+    let mut offset = 0;
+    for t in interface.unique_types() {
+        if &t == target_type {
+            return offset;
+        }
+        // Strings are 4 bytes (format code + flags + element size u16)
+        // Simple types are 1 byte
+        offset += match t {
+            Type::String | Type::AnsiString => 4,
+            Type::Simple(_) => 1,
+            Type::Slice(_)
+            | Type::WideString
+            | Type::HString
+            | Type::Struct(_)
+            | Type::Enum(_)
+            | Type::Optional(_)
+            | Type::OptionalString
+            | Type::OptionalAnsiString
+            | Type::SystemHandle { .. }
+            | Type::ContextHandle
+            | Type::Pipe(_)
+            | Type::StringVec => 0,
+        };
+    }
+    0 // Not found
+}
+
+pub fn generate_ndr64_proc_buffer_code(interface: &Interface) -> proc_macro2::TokenStream {
+    let mut proc_descriptors = vec![];
+    let needs_out_string_ptrs = has_string_return(interface);
+
+    for method in interface.methods.iter() {
+        // Slices take two parameters: the hidden length and the array
+        let param_count = method.abi_param_count();
+        let has_simple_return = method.has_base_type_return();
+        let has_string_return_val = method.has_out_return();
+        // For string and struct returns, we add an out param (two for vectors: the length
+        // and the array); for simple returns, it's a real return value
+        let total_params =
+            param_count + if has_simple_return { 1 } else { 0 } + method.out_return_param_count();
+        // The client passes a hidden status out parameter last, which is not described
+        let stack_size = u32::from(method.stack_size(8));
+        let float_double_mask = method.float_double_mask();
+        let rpc_flags = method.rpc_flags() as u16;
+
+        let has_slice_param = method.has_slice_param();
+        let has_string_param = method.client_must_size();
+
+        // Base flags: 0x01000040 = HasExtensions + some base flags needed for NDR64
+        // Note: 0x01000000 seems to be part of the base for NDR64 proc format
+        // Failed calls are reported through the status parameter instead of raising
+        let mut flags = 0x01000040u32 | crate::constants::NDR64_PROC_HANDLES_EXCEPTIONS;
+        if has_simple_return {
+            flags |= 0x00080000; // HasReturn flag (only for simple types)
+        }
+        if has_string_param {
+            flags |= crate::constants::NDR64_PROC_CLIENT_MUST_SIZE; // 0x00040000
+        }
+        if has_slice_param {
+            // The server checks array sizes against their length parameters
+            flags |= NDR64_PROC_SERVER_HAS_CORRELATION;
+        }
+        if method.has_slice_return() || method.has_out_slice_param() {
+            // The client checks returned array sizes against their lengths
+            flags |= NDR64_PROC_CLIENT_HAS_CORRELATION;
+        }
+        if method.uses_full_pointers() {
+            // The stubs set up the full pointer table of the call
+            flags |= NDR64_PROC_USES_FULL_PTR_PACKAGE;
+        }
+        if method.has_pipe_param() {
+            // The stubs stream the pipes once the other parameters are marshalled
+            flags |= NDR64_PROC_USES_PIPES;
+        }
+        if has_string_return_val {
+            // For string returns, we need IsInterpreted (0x20000) flag
+            flags |= 0x00020000; // IsInterpreted
+        }
+        if method.server_must_size() {
+            flags |= crate::constants::NDR64_PROC_SERVER_MUST_SIZE; // 0x01000000 (already in base, but be explicit)
+        }
+        if method.r#async {
+            // The stubs take the RPC_ASYNC_STATE before the binding handle
+            flags |= NDR64_PROC_IS_ASYNC;
+        }
+
+        // Parameters of variable size are sized on top of the constant part
+        let constant_client_buffer_size = method.constant_client_buffer_size() as u32;
+
+        // Server buffer size: for string returns, server must size; otherwise compute constant.
+        // Out base types (including the length of a returned vector) and context handles are
+        // never sized, so they are always counted.
+        let out_base_types = method.out_base_type_count() + usize::from(method.has_slice_return());
+        let out_buffer_size = (out_base_types * OUT_BASE_TYPE_BUFFER_SIZE
+            + method.out_context_handle_count() * CONTEXT_HANDLE_BUFFER_SIZE)
+            as u32;
+        let constant_server_buffer_size = out_buffer_size
+            + if method.server_must_size() {
+                0u32
+            } else {
+                std::mem::size_of::<usize>() as u32 + if has_simple_return { 8u32 } else { 0u32 }
+            };
+
+        // Generate proc format struct
+        let proc_format = quote! {
+            windows::Win32::System::Rpc::NDR64_PROC_FORMAT {
+                Flags: #flags,
+                StackSize: #stack_size,
+                ConstantClientBufferSize: #constant_client_buffer_size,
+                ConstantServerBufferSize: #constant_server_buffer_size,
+                RpcFlags: #rpc_flags,
+                FloatDoubleMask: #float_double_mask,
+                NumberOfParams: #total_params as u16,
+                ExtensionSize: 8,
+            }
+        };
+
+        // Generate bind extension
+        let binding_stack_offset = method.binding_stack_offset(8);
+        let bind_extension = quote! {
+            windows::Win32::System::Rpc::NDR64_BIND_AND_NOTIFY_EXTENSION {
+                Binding: windows::Win32::System::Rpc::NDR64_BIND_CONTEXT {
+                    HandleType: 0x72, // FC64_BIND_PRIMITIVE
+                    Flags: 0,
+                    StackOffset: #binding_stack_offset,
+                    RoutineIndex: 0,
+                    Ordinal: 0,
+                },
+                NotifyIndex: 0,
+            }
+        };
+
+        // Generate parameter descriptors
+        let mut param_descriptors = vec![];
+        let (param_stack_offsets, return_stack_offset) = method.stack_offsets(8);
+
+        // The parameters, followed by the hidden error parameter
+        for (param, &stack_offset) in method.wire_parameters().iter().zip(&param_stack_offsets) {
+            let stack_offset = stack_offset as u32;
+            if let (Type::Slice(element), Some(length_index)) =
+                (&param.r#type, method.size_param_index(param))
+            {
+                // The array alone, sized by another parameter
+                let conf_array = generate_conf_array_format(
+                    interface,
+                    *element,
+                    param_stack_offsets[length_index].into(),
+                );
+                let attributes = param.ndr64_param_attributes();
+                param_descriptors.push(quote! {
+                    [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                        Type: #conf_array,
+                        Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                            _bitfield: #attributes,
+                        },
+                        Reserved: 0,
+                        StackOffset: #stack_offset,
+                    }]
+                });
+                continue;
+            }
+            if param.is_out_string_vec() {
+                param_descriptors.push(generate_string_vec_descriptors(interface, stack_offset));
+                continue;
+            }
+            if let Type::Slice(element) = param.r#type {
+                param_descriptors.push(generate_slice_param_descriptors(
+                    interface,
+                    element,
+                    param.ndr64_param_attributes(),
+                    stack_offset,
+                    param.length_range(),
+                ));
+                continue;
+            }
+
+            if let (Type::Simple(base_type), Some(range)) = (&param.r#type, param.value_range()) {
+                let range_format = generate_range_format(*base_type, range);
+                let attributes = param.ndr64_param_attributes();
+                param_descriptors.push(quote! {
+                    [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                        Type: #range_format,
+                        Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                            _bitfield: #attributes,
+                        },
+                        Reserved: 0,
+                        StackOffset: #stack_offset,
+                    }]
+                });
+                continue;
+            }
+
+            if let Type::Optional(_) | Type::OptionalString | Type::OptionalAnsiString =
+                param.r#type
+            {
+                param_descriptors.push(generate_unique_param_descriptor(
+                    interface,
+                    &param.r#type,
+                    if param.full_pointer {
+                        NDR64_FC_FP
+                    } else {
+                        NDR64_FC_UP
+                    },
+                    param.ndr64_param_attributes(),
+                    stack_offset,
+                ));
+                continue;
+            }
+
+            if let (Type::Struct(_), true) = (&param.r#type, param.full_pointer) {
+                param_descriptors.push(generate_struct_full_pointer_descriptor(
+                    &param.r#type,
+                    param.ndr64_param_attributes(),
+                    stack_offset,
+                ));
+                continue;
+            }
+
+            if let Type::SystemHandle { .. } = param.r#type {
+                param_descriptors.push(generate_system_handle_descriptor(
+                    &param.r#type,
+                    param.ndr64_param_attributes(),
+                    stack_offset,
+                ));
+                continue;
+            }
+
+            if param.r#type == Type::ContextHandle {
+                param_descriptors.push(generate_context_handle_descriptor(
+                    param.context_flags(),
+                    param.ndr64_param_attributes(),
+                    stack_offset,
+                ));
+                continue;
+            }
+
+            if let Type::Pipe(element) = param.r#type {
+                param_descriptors.push(generate_pipe_descriptor(
+                    interface,
+                    element,
+                    param.ndr64_param_attributes(),
+                    stack_offset,
+                ));
+                continue;
+            }
+
+            if let Type::Struct(_) = param.r#type {
+                param_descriptors.push(generate_struct_param_descriptor(
+                    &param.r#type,
+                    param.ndr64_param_attributes(),
+                    stack_offset,
+                ));
+                continue;
+            }
+
+            let type_offset = compute_type_offset(interface, &param.r#type);
+            let attributes = param.ndr64_param_attributes();
+
+            param_descriptors.push(quote! {
+                [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: unsafe { ndr64_type_format.as_ptr().add(#type_offset) as *mut core::ffi::c_void },
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: #attributes,
+                    },
+                    Reserved: 0,
+                    StackOffset: #stack_offset,
+                }]
+            });
+        }
+
+        // Generate return value descriptor if present: values other than base types become
+        // hidden out parameters
+        let stack_offset = return_stack_offset as u32;
+        if let Some(ref return_type) = method.return_type {
+            match return_type {
+                // Returned by value below, or through the hidden value parameter of
+                // `error_status` and `hresult` methods
+                Type::Simple(_) | Type::Enum(_) => {}
+                Type::String | Type::WideString | Type::HString | Type::OptionalString => {
+                    // String return value: points to the out_string_rp_ptr structure
+                    // Attributes: MustSize(0x01) | MustFree(0x02) | IsOut(0x10) | UseCache(0x8000) = 0x8013
+                    let out_string_attrs: u16 = 0x8013;
+                    param_descriptors.push(quote! {
+                        [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                            Type: out_string_rp_ptr as *mut core::ffi::c_void,
+                            Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                                _bitfield: #out_string_attrs,
+                            },
+                            Reserved: 0,
+                            StackOffset: #stack_offset,
+                        }]
+                    });
+                }
+                Type::Optional(base_type) => {
+                    // Optional return value becomes an out parameter (T**)
+                    param_descriptors.push(generate_optional_return_descriptor(
+                        interface,
+                        *base_type,
+                        stack_offset,
+                    ));
+                }
+                Type::Struct(_) => {
+                    // Struct return value becomes an out parameter (T*)
+                    param_descriptors.push(generate_struct_param_descriptor(
+                        return_type,
+                        NDR64_MUST_SIZE | NDR64_MUST_FREE | NDR64_IS_OUT | NDR64_IS_SIMPLE_REF,
+                        stack_offset,
+                    ));
+                }
+                Type::SystemHandle { .. } => {
+                    // Handle return value becomes an out parameter (HANDLE*)
+                    param_descriptors.push(generate_system_handle_descriptor(
+                        return_type,
+                        NDR64_MUST_SIZE
+                            | NDR64_MUST_FREE
+                            | NDR64_IS_OUT
+                            | NDR64_IS_SIMPLE_REF
+                            | NDR64_USE_CACHE,
+                        stack_offset,
+                    ));
+                }
+                Type::ContextHandle => {
+                    // Context handle return value becomes an out parameter (void**)
+                    param_descriptors.push(generate_context_handle_descriptor(
+                        RETURN_CONTEXT_FLAGS,
+                        NDR64_IS_OUT | NDR64_IS_SIMPLE_REF,
+                        stack_offset,
+                    ));
+                }
+                Type::Slice(element) => {
+                    // Vector return value becomes length and array out parameters
+                    param_descriptors.push(generate_slice_return_descriptors(
+                        interface,
+                        *element,
+                        stack_offset,
+                    ));
+                }
+                Type::StringVec => {
+                    // String vector return value becomes length and string array out
+                    // parameters
+                    param_descriptors
+                        .push(generate_string_vec_descriptors(interface, stack_offset));
+                }
+                Type::AnsiString | Type::OptionalAnsiString => {
+                    unreachable!("ANSI strings are only input parameters")
+                }
+                Type::Pipe(_) => unreachable!("pipes are only parameters"),
+            }
+        }
+
+        // Base type return value (the status of `error_status` and `hresult` methods, which
+        // NDR64 describes as a 32-bit integer), following the hidden status parameter
+        if let Some(return_type) = method.base_type_return() {
+            let type_offset = compute_type_offset(interface, &return_type);
+            let stack_offset = u32::from(method.base_type_return_stack_offset(8));
+            param_descriptors.push(quote! {
+                [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: unsafe { ndr64_type_format.as_ptr().add(#type_offset) as *mut core::ffi::c_void },
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: 0x00f0, // IS_OUT | IS_RETURN | IS_BASE_TYPE | IS_BY_VALUE
+                    },
+                    Reserved: 0,
+                    StackOffset: #stack_offset,
+                }]
+            });
+        }
+
+        proc_descriptors.push(quote! {
+            {
+                let proc_format = #proc_format;
+                proc_buffer.extend_from_slice(unsafe {
+                    std::slice::from_raw_parts(
+                        &proc_format as *const _ as *const u8,
+                        std::mem::size_of::<windows::Win32::System::Rpc::NDR64_PROC_FORMAT>(),
+                    )
+                });
+
+                let bind_extension = #bind_extension;
+                proc_buffer.extend_from_slice(unsafe {
+                    std::slice::from_raw_parts(
+                        &bind_extension as *const _ as *const u8,
+                        std::mem::size_of::<windows::Win32::System::Rpc::NDR64_BIND_AND_NOTIFY_EXTENSION>(),
+                    )
+                });
+
+                #(
+                    for param_format in #param_descriptors {
+                        proc_buffer.extend_from_slice(unsafe {
+                            std::slice::from_raw_parts(
+                                &param_format as *const _ as *const u8,
+                                std::mem::size_of::<windows::Win32::System::Rpc::NDR64_PARAM_FORMAT>(),
+                            )
+                        });
+                    }
+                )*
+            }
+        });
+    }
+
+    // Generate the out string pointer chain if needed
+    let out_string_ptr_setup = if needs_out_string_ptrs {
+        // Get the offset for the base string type (FC64_CONF_WCHAR_STRING)
+        let string_type_offset = compute_type_offset(interface, &Type::String);
+        quote! {
+            // Build the NDR64 pointer chain for out strings at runtime
+            // This creates: FC64_RP -> FC64_UP -> FC64_CONF_WCHAR_STRING
+
+            // First, get a pointer to the conformant string type
+            let conf_string_ptr = unsafe { ndr64_type_format.as_ptr().add(#string_type_offset) };
+
+            // NDR64_POINTER_FORMAT for FC64_UP (unique pointer to string)
+            #[repr(C)]
+            struct Ndr64PointerFormat {
+                format_code: u8,
+                flags: u8,
+                reserved: u16,
+                pointee: *const u8,
+            }
+
+            // Create the FC64_UP pointer (points to the conformant string)
+            let out_string_up = std::boxed::Box::new(Ndr64PointerFormat {
+                format_code: 0x21, // FC64_UP
+                flags: 0,
+                reserved: 0,
+                pointee: conf_string_ptr,
+            });
+            let out_string_up_ptr = std::boxed::Box::into_raw(out_string_up);
+
+            // Create the FC64_RP pointer (points to the UP)
+            // Flags: 0x14 = alloced_on_stack | pointer_deref
+            let out_string_rp = std::boxed::Box::new(Ndr64PointerFormat {
+                format_code: 0x20, // FC64_RP
+                flags: 0x14, // alloced_on_stack | pointer_deref
+                reserved: 0,
+                pointee: out_string_up_ptr as *const u8,
+            });
+            let out_string_rp_ptr = std::boxed::Box::into_raw(out_string_rp);
+        }
+    } else {
+        quote! {
+            // No out string pointers needed
+            let out_string_rp_ptr: *const u8 = std::ptr::null();
+        }
+    };
+
+    quote! {
+        {
+            let mut proc_buffer: Vec<u8> = Vec::new();
+            let mut proc_table_offsets: Vec<usize> = Vec::new();
+
+            #out_string_ptr_setup
+
+            #(
+                proc_table_offsets.push(proc_buffer.len());
+                #proc_descriptors
+            )*
+
+            (proc_buffer, proc_table_offsets)
+        }
+    }
+}
+
+/// Generates an expression of the `NDR64_RANGE_FORMAT` of a ranged value, built (and leaked)
+/// at runtime as the type format only holds byte-aligned fragments
+fn generate_range_format(base_type: BaseType, (min, max): (i64, i64)) -> proc_macro2::TokenStream {
+    let range_type = base_type.to_ndr64_range_fc_value();
+    quote! {
+        std::boxed::Box::into_raw(std::boxed::Box::new(
+            windows::Win32::System::Rpc::NDR64_RANGE_FORMAT {
+                FormatCode: #NDR64_FC_RANGE,
+                RangeType: #range_type,
+                Reserved: 0,
+                MinValue: #min,
+                MaxValue: #max,
+            },
+        )) as *mut core::ffi::c_void
+    }
+}
+
+/// Generates the descriptor of a handle parameter or return value, pointing to its
+/// `NDR64_SYSTEM_HANDLE_FORMAT`, built (and leaked) at runtime
+fn generate_system_handle_descriptor(
+    handle_type: &Type,
+    attributes: u16,
+    stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let Type::SystemHandle { kind, access } = handle_type else {
+        unreachable!("not a system handle type");
+    };
+    let kind = kind.to_fc_value();
+    quote! {
+        [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+            Type: std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_SYSTEM_HANDLE_FORMAT {
+                    FormatCode: #NDR64_FC_SYSTEM_HANDLE,
+                    HandleType: #kind,
+                    DesiredAccess: #access,
+                },
+            )) as *mut core::ffi::c_void,
+            Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                _bitfield: #attributes,
+            },
+            Reserved: 0,
+            StackOffset: #stack_offset,
+        }]
+    }
+}
+
+/// Generates the descriptor of a context handle parameter or return value, pointing to its
+/// `NDR64_CONTEXT_HANDLE_FORMAT` with the context `flags`, built (and leaked) at runtime
+fn generate_context_handle_descriptor(
+    flags: u8,
+    attributes: u16,
+    stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    quote! {
+        [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+            Type: std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_CONTEXT_HANDLE_FORMAT {
+                    FormatCode: #NDR64_FC_BIND_CONTEXT,
+                    ContextFlags: #flags,
+                    // The server's only rundown routine
+                    RundownRoutineIndex: 0,
+                    Ordinal: 0,
+                },
+            )) as *mut core::ffi::c_void,
+            Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                _bitfield: #attributes,
+            },
+            Reserved: 0,
+            StackOffset: #stack_offset,
+        }]
+    }
+}
+
+/// Generates the descriptor of a pipe parameter, pointing to its `NDR64_PIPE_FORMAT` of
+/// `element`, built (and leaked) at runtime as it points to the element's format
+fn generate_pipe_descriptor(
+    interface: &Interface,
+    element: BaseType,
+    attributes: u16,
+    stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let element_offset = compute_type_offset(interface, &Type::Simple(element));
+    let alignment = (element.size() - 1) as u8;
+    let size = element.size() as u32;
+    quote! {
+        [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+            Type: std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_PIPE_FORMAT {
+                    FormatCode: #NDR64_FC_PIPE,
+                    // The elements are marshalled one by one, without a range
+                    Flags: 0,
+                    Alignment: #alignment,
+                    Reserved: 0,
+                    Type: unsafe { ndr64_type_format.as_ptr().add(#element_offset) as *mut core::ffi::c_void },
+                    MemorySize: #size,
+                    BufferSize: #size,
+                },
+            )) as *mut core::ffi::c_void,
+            Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                _bitfield: #attributes,
+            },
+            Reserved: 0,
+            StackOffset: #stack_offset,
+        }]
+    }
+}
+
+/// Generates the descriptor of a struct or union parameter, pointing to the format of its
+/// `NdrStruct` or `NdrUnion` implementation
+fn generate_struct_param_descriptor(
+    struct_type: &Type,
+    attributes: u16,
+    stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let path = struct_type.struct_path();
+    quote! {
+        [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+            Type: <#path as windows_rpc::ndr::NdrType>::ndr64_format(),
+            Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                _bitfield: #attributes,
+            },
+            Reserved: 0,
+            StackOffset: #stack_offset,
+        }]
+    }
+}
+
+/// Generates the descriptor of a struct or union parameter passed through a full pointer,
+/// an `FC64_FP` to the format of its `NdrStruct` or `NdrUnion` implementation, built (and
+/// leaked) at runtime
+fn generate_struct_full_pointer_descriptor(
+    struct_type: &Type,
+    attributes: u16,
+    stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let path = struct_type.struct_path();
+    quote! {
+        {
+            let full = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_POINTER_FORMAT {
+                    FormatCode: #NDR64_FC_FP,
+                    Flags: 0,
+                    Reserved: 0,
+                    Pointee: <#path as windows_rpc::ndr::NdrType>::ndr64_format(),
+                },
+            ));
+            [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                Type: full as *mut core::ffi::c_void,
+                Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                    _bitfield: #attributes,
+                },
+                Reserved: 0,
+                StackOffset: #stack_offset,
+            }]
+        }
+    }
+}
+
+/// Generates the descriptor of an optional parameter, pointing to an `FC64_UP` (or, for
+/// full pointers, `FC64_FP`) to the value or string, built (and leaked) at runtime
+fn generate_unique_param_descriptor(
+    interface: &Interface,
+    param_type: &Type,
+    format_code: u8,
+    attributes: u16,
+    stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let (pointee_type, flags) = match param_type {
+        // Base types are simple pointees
+        Type::Optional(base_type) => (Type::Simple(*base_type), FC_SIMPLE_POINTER),
+        Type::OptionalAnsiString => (Type::AnsiString, 0),
+        _ => (Type::String, 0),
+    };
+    let pointee_offset = compute_type_offset(interface, &pointee_type);
+
+    quote! {
+        {
+            let unique = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_POINTER_FORMAT {
+                    FormatCode: #format_code,
+                    Flags: #flags,
+                    Reserved: 0,
+                    Pointee: unsafe { ndr64_type_format.as_ptr().add(#pointee_offset) as *mut core::ffi::c_void },
+                },
+            ));
+            [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                Type: unique as *mut core::ffi::c_void,
+                Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                    _bitfield: #attributes,
+                },
+                Reserved: 0,
+                StackOffset: #stack_offset,
+            }]
+        }
+    }
+}
+
+/// Generates the descriptor of an optional return value: an out pointer to a unique
+/// pointer to the value (`FC64_RP` -> `FC64_UP` -> base type), built at runtime
+fn generate_optional_return_descriptor(
+    interface: &Interface,
+    base_type: BaseType,
+    stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let value_offset = compute_type_offset(interface, &Type::Simple(base_type));
+    // MustSize | MustFree | IsOut | UseCache, like out strings
+    let attributes = NDR64_MUST_SIZE | NDR64_MUST_FREE | NDR64_IS_OUT | NDR64_USE_CACHE;
+
+    quote! {
+        {
+            let unique = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_POINTER_FORMAT {
+                    FormatCode: 0x21, // FC64_UP
+                    Flags: #FC_SIMPLE_POINTER,
+                    Reserved: 0,
+                    Pointee: unsafe { ndr64_type_format.as_ptr().add(#value_offset) as *mut core::ffi::c_void },
+                },
+            ));
+            let reference = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_POINTER_FORMAT {
+                    FormatCode: 0x20, // FC64_RP
+                    Flags: 0x14, // alloced_on_stack | pointer_deref
+                    Reserved: 0,
+                    Pointee: unique as *mut core::ffi::c_void,
+                },
+            ));
+            [windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                Type: reference as *mut core::ffi::c_void,
+                Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                    _bitfield: #attributes,
+                },
+                Reserved: 0,
+                StackOffset: #stack_offset,
+            }]
+        }
+    }
+}
+
+/// Generates the descriptors of a slice parameter: its hidden `u32` length followed by
+/// the conformant array sized by it. A ranged length is passed by value to its range.
+fn generate_slice_param_descriptors(
+    interface: &Interface,
+    element: BaseType,
+    attributes: u16,
+    length_stack_offset: u32,
+    length_range: Option<(i64, i64)>,
+) -> proc_macro2::TokenStream {
+    let (length_type, length_attributes) = match length_range {
+        Some(range) => (
+            generate_range_format(BaseType::U32, range),
+            NDR64_IS_IN | NDR64_IS_BY_VALUE,
+        ),
+        None => {
+            let length_type_offset = compute_type_offset(interface, &Type::Simple(BaseType::U32));
+            (
+                quote! {
+                    unsafe { ndr64_type_format.as_ptr().add(#length_type_offset) as *mut core::ffi::c_void }
+                },
+                NDR64_IS_IN | NDR64_IS_BASE_TYPE | NDR64_IS_BY_VALUE,
+            )
+        }
+    };
+    let conf_array = generate_conf_array_format(interface, element, length_stack_offset);
+    let array_stack_offset = length_stack_offset + 8;
+
+    quote! {
+        {
+            let conf_array = #conf_array;
+
+            [
+                windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: #length_type,
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: #length_attributes,
+                    },
+                    Reserved: 0,
+                    StackOffset: #length_stack_offset,
+                },
+                windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: conf_array,
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: #attributes,
+                    },
+                    Reserved: 0,
+                    StackOffset: #array_stack_offset,
+                },
+            ]
+        }
+    }
+}
+
+/// Generates an expression of the conformant array format of a slice, sized by the `u32`
+/// parameter at `length_stack_offset`: its hidden length or its `size_is` parameter.
+///
+/// Like the out string pointer chain, the array format refers to other format fragments
+/// by address, so it is built (and leaked) at runtime.
+fn generate_conf_array_format(
+    interface: &Interface,
+    element: BaseType,
+    length_stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let length_fc = BaseType::U32.to_ndr64_fc_value();
+    let element_type_offset = compute_type_offset(interface, &Type::Simple(element));
+    let element_size = element.size() as u32;
+    let alignment = (element.size() - 1) as u8;
+
+    quote! {
+        {
+            // NDR64_CONF_ARRAY_HEADER_FORMAT followed by its NDR64_ARRAY_ELEMENT_INFO
+            #[repr(C)]
+            struct Ndr64ConfArrayFormat {
+                header: windows::Win32::System::Rpc::NDR64_CONF_ARRAY_HEADER_FORMAT,
+                element: windows::Win32::System::Rpc::NDR64_ARRAY_ELEMENT_INFO,
+            }
+
+            // The array size is read from the length parameter on the stack
+            let length_expr = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_EXPR_VAR {
+                    ExprType: #NDR64_FC_EXPR_VAR,
+                    VarType: #length_fc,
+                    Reserved: 0,
+                    Offset: #length_stack_offset,
+                },
+            ));
+            std::boxed::Box::into_raw(std::boxed::Box::new(Ndr64ConfArrayFormat {
+                header: windows::Win32::System::Rpc::NDR64_CONF_ARRAY_HEADER_FORMAT {
+                    FormatCode: #NDR64_FC_CONF_ARRAY,
+                    Alignment: #alignment,
+                    Flags: windows::Win32::System::Rpc::NDR64_ARRAY_FLAGS { _bitfield: 0 },
+                    Reserved: 0,
+                    ElementSize: #element_size,
+                    ConfDescriptor: length_expr as *mut core::ffi::c_void,
+                },
+                element: windows::Win32::System::Rpc::NDR64_ARRAY_ELEMENT_INFO {
+                    ElementMemSize: #element_size,
+                    Element: unsafe { ndr64_type_format.as_ptr().add(#element_type_offset) as *mut core::ffi::c_void },
+                },
+            })) as *mut core::ffi::c_void
+        }
+    }
+}
+
+/// Generates the descriptors of a vector return value: a hidden `u32` length out parameter
+/// followed by an out pointer to the conformant array sized by it.
+///
+/// The array format chain (`FC64_RP` -> `FC64_UP` -> conformant array) is built at runtime,
+/// like the one of slice parameters.
+fn generate_slice_return_descriptors(
+    interface: &Interface,
+    element: BaseType,
+    length_stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let length_type_offset = compute_type_offset(interface, &Type::Simple(BaseType::U32));
+    let length_attributes =
+        NDR64_IS_OUT | NDR64_IS_BASE_TYPE | NDR64_IS_SIMPLE_REF | NDR64_USE_CACHE;
+    let length_fc = BaseType::U32.to_ndr64_fc_value();
+    // MustSize | MustFree | IsOut | UseCache, like out strings
+    let array_attributes = NDR64_MUST_SIZE | NDR64_MUST_FREE | NDR64_IS_OUT | NDR64_USE_CACHE;
+    let element_type_offset = compute_type_offset(interface, &Type::Simple(element));
+    let element_size = element.size() as u32;
+    let alignment = (element.size() - 1) as u8;
+    let array_stack_offset = length_stack_offset + 8;
+
+    quote! {
+        {
+            #[repr(C)]
+            struct Ndr64ConfArrayFormat {
+                header: windows::Win32::System::Rpc::NDR64_CONF_ARRAY_HEADER_FORMAT,
+                element: windows::Win32::System::Rpc::NDR64_ARRAY_ELEMENT_INFO,
+            }
+
+            // The array size is read through the length pointer on the stack: *length
+            #[repr(C)]
+            struct Ndr64DereferenceExpr {
+                operator: windows::Win32::System::Rpc::NDR64_EXPR_OPERATOR,
+                variable: windows::Win32::System::Rpc::NDR64_EXPR_VAR,
+            }
+
+            #[repr(C)]
+            struct Ndr64PointerFormat {
+                format_code: u8,
+                flags: u8,
+                reserved: u16,
+                pointee: *const u8,
+            }
+
+            let length_expr = std::boxed::Box::into_raw(std::boxed::Box::new(Ndr64DereferenceExpr {
+                operator: windows::Win32::System::Rpc::NDR64_EXPR_OPERATOR {
+                    ExprType: #NDR64_FC_EXPR_OPER,
+                    Operator: #NDR64_OP_UNARY_INDIRECTION,
+                    CastType: #length_fc,
+                    Reserved: 0,
+                },
+                variable: windows::Win32::System::Rpc::NDR64_EXPR_VAR {
+                    ExprType: #NDR64_FC_EXPR_VAR,
+                    VarType: #NDR64_FC_INT64,
+                    Reserved: 0,
+                    Offset: #length_stack_offset,
+                },
+            }));
+            let conf_array = std::boxed::Box::into_raw(std::boxed::Box::new(Ndr64ConfArrayFormat {
+                header: windows::Win32::System::Rpc::NDR64_CONF_ARRAY_HEADER_FORMAT {
+                    FormatCode: #NDR64_FC_CONF_ARRAY,
+                    Alignment: #alignment,
+                    Flags: windows::Win32::System::Rpc::NDR64_ARRAY_FLAGS { _bitfield: 0 },
+                    Reserved: 0,
+                    ElementSize: #element_size,
+                    ConfDescriptor: length_expr as *mut core::ffi::c_void,
+                },
+                element: windows::Win32::System::Rpc::NDR64_ARRAY_ELEMENT_INFO {
+                    ElementMemSize: #element_size,
+                    Element: unsafe { ndr64_type_format.as_ptr().add(#element_type_offset) as *mut core::ffi::c_void },
+                },
+            }));
+            // FC64_UP to the array, which is null when it is empty
+            let array_up = std::boxed::Box::into_raw(std::boxed::Box::new(Ndr64PointerFormat {
+                format_code: 0x21, // FC64_UP
+                flags: 0,
+                reserved: 0,
+                pointee: conf_array as *const u8,
+            }));
+            let array_rp = std::boxed::Box::into_raw(std::boxed::Box::new(Ndr64PointerFormat {
+                format_code: 0x20, // FC64_RP
+                flags: 0x14, // alloced_on_stack | pointer_deref
+                reserved: 0,
+                pointee: array_up as *const u8,
+            }));
+
+            [
+                windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: unsafe { ndr64_type_format.as_ptr().add(#length_type_offset) as *mut core::ffi::c_void },
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: #length_attributes,
+                    },
+                    Reserved: 0,
+                    StackOffset: #length_stack_offset,
+                },
+                windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: array_rp as *mut core::ffi::c_void,
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: #array_attributes,
+                    },
+                    Reserved: 0,
+                    StackOffset: #array_stack_offset,
+                },
+            ]
+        }
+    }
+}
+
+/// Generates the descriptors of a string vector return value or out parameter: a `u32`
+/// length out parameter followed by an out pointer to the array of unique pointers to the
+/// strings.
+///
+/// The array is an `FC64_BOGUS_ARRAY`, as its elements are pointers, whose format chain
+/// (`FC64_RP` -> `FC64_UP` -> array -> `FC64_UP` -> string) is built at runtime like the one
+/// of vector return values.
+fn generate_string_vec_descriptors(
+    interface: &Interface,
+    length_stack_offset: u32,
+) -> proc_macro2::TokenStream {
+    let length_type_offset = compute_type_offset(interface, &Type::Simple(BaseType::U32));
+    let string_type_offset = compute_type_offset(interface, &Type::String);
+    let length_attributes =
+        NDR64_IS_OUT | NDR64_IS_BASE_TYPE | NDR64_IS_SIMPLE_REF | NDR64_USE_CACHE;
+    let length_fc = BaseType::U32.to_ndr64_fc_value();
+    let array_attributes = NDR64_MUST_SIZE | NDR64_MUST_FREE | NDR64_IS_OUT | NDR64_USE_CACHE;
+    let array_stack_offset = length_stack_offset + 8;
+
+    quote! {
+        {
+            // The array size is read through the length pointer on the stack: *length
+            #[repr(C)]
+            struct Ndr64DereferenceExpr {
+                operator: windows::Win32::System::Rpc::NDR64_EXPR_OPERATOR,
+                variable: windows::Win32::System::Rpc::NDR64_EXPR_VAR,
+            }
+
+            let length_expr = std::boxed::Box::into_raw(std::boxed::Box::new(Ndr64DereferenceExpr {
+                operator: windows::Win32::System::Rpc::NDR64_EXPR_OPERATOR {
+                    ExprType: #NDR64_FC_EXPR_OPER,
+                    Operator: #NDR64_OP_UNARY_INDIRECTION,
+                    CastType: #length_fc,
+                    Reserved: 0,
+                },
+                variable: windows::Win32::System::Rpc::NDR64_EXPR_VAR {
+                    ExprType: #NDR64_FC_EXPR_VAR,
+                    VarType: #NDR64_FC_INT64,
+                    Reserved: 0,
+                    Offset: #length_stack_offset,
+                },
+            }));
+            // FC64_UP to each string, as declared by `pointer_default(unique)`
+            let string_up = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_POINTER_FORMAT {
+                    FormatCode: #NDR64_FC_UP,
+                    Flags: 0,
+                    Reserved: 0,
+                    Pointee: unsafe { ndr64_type_format.as_ptr().add(#string_type_offset) as *mut core::ffi::c_void },
+                },
+            ));
+            // Conformant only, so without variance and offset descriptors
+            let bogus_array = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_CONF_VAR_BOGUS_ARRAY_HEADER_FORMAT {
+                    FixedArrayFormat: windows::Win32::System::Rpc::NDR64_BOGUS_ARRAY_HEADER_FORMAT {
+                        FormatCode: #NDR64_FC_BOGUS_ARRAY,
+                        Alignment: 7,
+                        Flags: windows::Win32::System::Rpc::NDR64_ARRAY_FLAGS {
+                            _bitfield: #NDR64_ARRAY_HAS_POINTER_INFO,
+                        },
+                        NumberDims: 0,
+                        NumberElements: 0,
+                        Element: string_up as *mut core::ffi::c_void,
+                    },
+                    ConfDescription: length_expr as *mut core::ffi::c_void,
+                    VarDescription: std::ptr::null_mut(),
+                    OffsetDescription: std::ptr::null_mut(),
+                },
+            ));
+            // FC64_UP to the array, which is null when it is empty
+            let array_up = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_POINTER_FORMAT {
+                    FormatCode: #NDR64_FC_UP,
+                    Flags: 0,
+                    Reserved: 0,
+                    Pointee: bogus_array as *mut core::ffi::c_void,
+                },
+            ));
+            let array_rp = std::boxed::Box::into_raw(std::boxed::Box::new(
+                windows::Win32::System::Rpc::NDR64_POINTER_FORMAT {
+                    FormatCode: 0x20, // FC64_RP
+                    Flags: 0x14, // alloced_on_stack | pointer_deref
+                    Reserved: 0,
+                    Pointee: array_up as *mut core::ffi::c_void,
+                },
+            ));
+
+            [
+                windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: unsafe { ndr64_type_format.as_ptr().add(#length_type_offset) as *mut core::ffi::c_void },
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: #length_attributes,
+                    },
+                    Reserved: 0,
+                    StackOffset: #length_stack_offset,
+                },
+                windows::Win32::System::Rpc::NDR64_PARAM_FORMAT {
+                    Type: array_rp as *mut core::ffi::c_void,
+                    Attributes: windows::Win32::System::Rpc::NDR64_PARAM_FLAGS {
+                        _bitfield: #array_attributes,
+                    },
+                    Reserved: 0,
+                    StackOffset: #array_stack_offset,
+                },
+            ]
+        }
+    }
+}
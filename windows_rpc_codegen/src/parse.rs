@@ -0,0 +1,467 @@
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::{Expr, Ident, Lit, LitFloat, LitInt, Token, TraitItemConst, parse::Parse};
+
+use crate::types::{InterfaceVersion, SystemHandleKind, TransferSyntaxes};
+
+/// Parsed attributes for the rpc_interface macro
+///
+/// Both can alternatively be declared as associated consts of the trait, see
+/// [`InterfaceConsts`].
+#[derive(Default)]
+pub struct InterfaceAttributes {
+    pub guid: Option<u128>,
+    pub version: Option<InterfaceVersion>,
+    /// User-defined binding handle type, taken by every client method
+    pub generic_handle: Option<syn::Type>,
+    /// Transfer syntaxes to generate, from `transfer_syntax(ndr20 | ndr64 | all)`
+    pub transfer_syntaxes: TransferSyntaxes,
+    /// Path of the wire-contract lockfile, relative to the crate's manifest directory
+    pub lockfile: Option<syn::LitStr>,
+    /// Path the IDL of the interface is written to, relative to the crate's manifest directory
+    pub idl: Option<syn::LitStr>,
+    /// Path the C header of the interface is written to, relative to the crate's manifest
+    /// directory
+    pub header: Option<syn::LitStr>,
+    /// Paths of the types that are enums deriving `NdrEnum`, from `enums(...)`
+    pub enums: Vec<String>,
+    /// Paths of the types implementing `RpcTransmittable`, with the types they are sent as,
+    /// from `transmit_as(T = Wire, ...)`
+    pub transmit_as: Vec<(String, syn::Type)>,
+}
+
+impl Parse for InterfaceAttributes {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut guid: Option<u128> = None;
+        let mut version: Option<InterfaceVersion> = None;
+        let mut generic_handle: Option<syn::Type> = None;
+        let mut lockfile: Option<syn::LitStr> = None;
+        let mut idl: Option<syn::LitStr> = None;
+        let mut header: Option<syn::LitStr> = None;
+        let mut transfer_syntaxes = TransferSyntaxes::All;
+        let mut enums = vec![];
+        let mut transmit_as = vec![];
+
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            let content;
+            syn::parenthesized!(content in input);
+
+            match ident.to_string().as_str() {
+                "guid" => {
+                    let lit: LitInt = content.parse()?;
+                    guid = Some(lit.base10_parse::<u128>().map_err(|_| {
+                        syn::Error::new_spanned(&lit, "Expected a u128 hex literal for guid")
+                    })?);
+                }
+                "version" => {
+                    version = Some(parse_version(&content)?);
+                }
+                "generic_handle" => {
+                    generic_handle = Some(content.parse()?);
+                }
+                "transfer_syntax" => {
+                    let syntax: Ident = content.parse()?;
+                    match syntax.to_string().as_str() {
+                        "ndr20" => transfer_syntaxes = TransferSyntaxes::Ndr20,
+                        "ndr64" => transfer_syntaxes = TransferSyntaxes::Ndr64,
+                        "all" => transfer_syntaxes = TransferSyntaxes::All,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                syntax,
+                                "Expected `ndr20`, `ndr64` or `all` transfer syntax",
+                            ));
+                        }
+                    }
+                }
+                "lockfile" => {
+                    lockfile = Some(content.parse()?);
+                }
+                "idl" => {
+                    idl = Some(content.parse()?);
+                }
+                "header" => {
+                    header = Some(content.parse()?);
+                }
+                "enums" => {
+                    let paths = content.parse_terminated(syn::Path::parse, Token![,])?;
+                    enums.extend(
+                        paths
+                            .iter()
+                            .map(|path| path.to_token_stream().to_string().replace(' ', "")),
+                    );
+                }
+                "transmit_as" => {
+                    let pairs = content.parse_terminated(TransmitAs::parse, Token![,])?;
+                    transmit_as.extend(pairs.into_iter().map(|pair| {
+                        let path = pair
+                            .presented
+                            .to_token_stream()
+                            .to_string()
+                            .replace(' ', "");
+                        (path, pair.wire)
+                    }));
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        &ident,
+                        format!("Unknown attribute: {other}"),
+                    ));
+                }
+            }
+
+            // Consume optional comma
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(InterfaceAttributes {
+            guid,
+            version,
+            generic_handle,
+            transfer_syntaxes,
+            lockfile,
+            idl,
+            header,
+            enums,
+            transmit_as,
+        })
+    }
+}
+
+/// `T = Wire` pair of `transmit_as(...)`
+struct TransmitAs {
+    presented: syn::Path,
+    wire: syn::Type,
+}
+
+impl Parse for TransmitAs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let presented = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let wire = input.parse()?;
+        Ok(TransmitAs { presented, wire })
+    }
+}
+
+/// Parses a version as either a `major.minor` float literal or a single integer
+/// meaning `major.0`.
+fn parse_version(content: syn::parse::ParseStream) -> syn::Result<InterfaceVersion> {
+    if content.peek(LitFloat) {
+        let lit: LitFloat = content.parse()?;
+        let version_str = lit.to_string();
+        let parts: Vec<&str> = version_str.split('.').collect();
+        if parts.len() != 2 {
+            return Err(syn::Error::new_spanned(
+                &lit,
+                "Expected version format: major.minor",
+            ));
+        }
+        let major: u16 = parts[0]
+            .parse()
+            .map_err(|_| syn::Error::new_spanned(&lit, "Invalid major version number"))?;
+        let minor: u16 = parts[1]
+            .parse()
+            .map_err(|_| syn::Error::new_spanned(&lit, "Invalid minor version number"))?;
+        Ok(InterfaceVersion { major, minor })
+    } else if content.peek(LitInt) {
+        // Handle case like version(1) meaning 1.0
+        let major_lit: LitInt = content.parse()?;
+        let major: u16 = major_lit.base10_parse()?;
+        let minor = if content.peek(Token![.]) {
+            content.parse::<Token![.]>()?;
+            let minor_lit: LitInt = content.parse()?;
+            minor_lit.base10_parse()?
+        } else {
+            0
+        };
+        Ok(InterfaceVersion { major, minor })
+    } else {
+        Err(syn::Error::new(content.span(), "Expected version number"))
+    }
+}
+
+/// Parsed `#[since(major.minor, default = expr)]` parameter attribute, marking a
+/// trailing parameter added in a later version of the interface
+pub struct SinceAttribute {
+    pub version: InterfaceVersion,
+    /// Value servers use when called by clients of older versions
+    pub default: Expr,
+}
+
+impl Parse for SinceAttribute {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let version = parse_version(input)?;
+        input.parse::<Token![,]>()?;
+        let ident: Ident = input.parse()?;
+        if ident != "default" {
+            return Err(syn::Error::new_spanned(ident, "Expected `default = ...`"));
+        }
+        input.parse::<Token![=]>()?;
+        let default = input.parse()?;
+        Ok(SinceAttribute { version, default })
+    }
+}
+
+/// Parsed `#[rpc(...)]` method attribute, setting flags of the method's procedure
+#[derive(Default)]
+pub struct MethodAttributes {
+    /// `maybe`: the client does not wait for a reply
+    pub maybe: bool,
+    /// `idempotent`: the runtime may execute the call more than once
+    pub idempotent: bool,
+    /// `broadcast`: the call goes to every server on the local network
+    pub broadcast: bool,
+    /// `system_handle(kind, access)`: the returned `OwnedHandle`
+    pub system_handle: Option<SystemHandleAttribute>,
+    /// `error_status`: the error code is the `error_status_t` return value, by the keyword,
+    /// which errors point to
+    pub error_status: Option<Ident>,
+    /// `hresult`: the error code is the `HRESULT` return value, by the keyword, which
+    /// errors point to
+    pub hresult: Option<Ident>,
+    /// `async`: the call completes asynchronously, as with MIDL's ACF `[async]`, by the
+    /// keyword, which errors point to
+    pub r#async: Option<Ident>,
+    /// `callback`: the server calls the procedure on the client, as with MIDL's
+    /// `[callback]`, by the keyword, which errors point to
+    pub callback: Option<Ident>,
+}
+
+impl Parse for MethodAttributes {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut attributes = MethodAttributes::default();
+        while !input.is_empty() {
+            // `async` is a keyword
+            let ident = Ident::parse_any(input)?;
+            match ident.to_string().as_str() {
+                "maybe" => attributes.maybe = true,
+                "idempotent" => attributes.idempotent = true,
+                "broadcast" => attributes.broadcast = true,
+                "error_status" => attributes.error_status = Some(ident.clone()),
+                "hresult" => attributes.hresult = Some(ident.clone()),
+                "async" => attributes.r#async = Some(ident.clone()),
+                "callback" => attributes.callback = Some(ident.clone()),
+                "system_handle" => {
+                    attributes.system_handle = Some(SystemHandleAttribute::parse(ident, input)?);
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        &ident,
+                        format!("Unknown method attribute: {other}"),
+                    ));
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+/// Parsed `system_handle(kind)` or `system_handle(kind, access)` attribute of a handle
+/// parameter or method returning a handle
+pub struct SystemHandleAttribute {
+    pub kind: SystemHandleKind,
+    /// Access mask requested for the duplicated handle, 0 for the access of the sent one
+    pub access: u32,
+    /// The `system_handle` keyword, which errors point to
+    pub ident: Ident,
+}
+
+impl SystemHandleAttribute {
+    /// Parses the arguments following the `system_handle` keyword `ident`
+    fn parse(ident: Ident, input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::parenthesized!(content in input);
+        let kind: Ident = content.parse()?;
+        let kind = SystemHandleKind::from_name(&kind.to_string()).ok_or_else(|| {
+            syn::Error::new_spanned(
+                &kind,
+                "Expected a handle kind: file, semaphore, event, mutex, process, token, \
+                 section, reg_key, thread, composition, socket, job or pipe",
+            )
+        })?;
+        let access = if content.is_empty() {
+            0
+        } else {
+            content.parse::<Token![,]>()?;
+            content.parse::<LitInt>()?.base10_parse()?
+        };
+        if !content.is_empty() {
+            return Err(content.error("Expected `system_handle(kind, access)`"));
+        }
+        Ok(SystemHandleAttribute {
+            kind,
+            access,
+            ident,
+        })
+    }
+}
+
+/// Parsed `#[rpc(...)]` parameter attribute
+#[derive(Default)]
+pub struct ParameterAttributes {
+    /// `range(min, max)`: inclusive bounds of the value, or of the length of a slice
+    pub range: Option<(i64, i64)>,
+    /// `size_is(length)`: the parameter holding the number of elements of a slice
+    pub size_is: Option<Ident>,
+    /// `ptr`: the parameter is a full pointer, marshalled once however many pointers alias
+    /// it
+    pub ptr: Option<Ident>,
+    /// `system_handle(kind, access)`: the `BorrowedHandle<'_>` the parameter is
+    pub system_handle: Option<SystemHandleAttribute>,
+    /// `ansi`: the string is sent in the ANSI code page, as a `char` string
+    pub ansi: Option<Ident>,
+}
+
+impl Parse for ParameterAttributes {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut attributes = ParameterAttributes::default();
+        while !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            match ident.to_string().as_str() {
+                "range" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let min = parse_bound(&content)?;
+                    content.parse::<Token![,]>()?;
+                    let max = parse_bound(&content)?;
+                    if !content.is_empty() {
+                        return Err(content.error("Expected `range(min, max)`"));
+                    }
+                    attributes.range = Some((min, max));
+                }
+                "size_is" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    attributes.size_is = Some(content.parse()?);
+                    if !content.is_empty() {
+                        return Err(content.error("Expected `size_is(length)`"));
+                    }
+                }
+                "ptr" => attributes.ptr = Some(ident),
+                "ansi" => attributes.ansi = Some(ident),
+                "system_handle" => {
+                    attributes.system_handle = Some(SystemHandleAttribute::parse(ident, input)?);
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        &ident,
+                        format!("Unknown parameter attribute: {other}"),
+                    ));
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(attributes)
+    }
+}
+
+/// Parses an integer literal, which may be negative
+fn parse_bound(input: syn::parse::ParseStream) -> syn::Result<i64> {
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let lit: LitInt = input.parse()?;
+    let value: i64 = lit.base10_parse()?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Interface identity declared as associated consts of the trait:
+/// `const GUID: u128 = ...;` and `const VERSION: (u16, u16) = (major, minor);`
+#[derive(Default)]
+pub struct InterfaceConsts {
+    pub guid: Option<u128>,
+    pub version: Option<InterfaceVersion>,
+}
+
+impl InterfaceConsts {
+    /// Records an associated const of the trait, failing on unknown consts.
+    pub fn parse_const(&mut self, item: &TraitItemConst) -> syn::Result<()> {
+        let Some((_, value)) = &item.default else {
+            return Err(syn::Error::new_spanned(
+                item,
+                "Expected a value for the const",
+            ));
+        };
+
+        match item.ident.to_string().as_str() {
+            "GUID" if self.guid.is_none() => {
+                let lit = expect_int_literal(value)?;
+                self.guid = Some(lit.base10_parse::<u128>().map_err(|_| {
+                    syn::Error::new_spanned(lit, "Expected a u128 hex literal for GUID")
+                })?);
+            }
+            "VERSION" if self.version.is_none() => {
+                let Expr::Tuple(tuple) = value else {
+                    return Err(syn::Error::new_spanned(
+                        value,
+                        "Expected VERSION as a (major, minor) tuple",
+                    ));
+                };
+                let [major, minor] = [0, 1].map(|i| tuple.elems.get(i));
+                let (Some(major), Some(minor), 2) = (major, minor, tuple.elems.len()) else {
+                    return Err(syn::Error::new_spanned(
+                        tuple,
+                        "Expected VERSION as a (major, minor) tuple",
+                    ));
+                };
+                self.version = Some(InterfaceVersion {
+                    major: expect_int_literal(major)?.base10_parse()?,
+                    minor: expect_int_literal(minor)?.base10_parse()?,
+                });
+            }
+            "GUID" | "VERSION" => {
+                return Err(syn::Error::new_spanned(&item.ident, "Duplicate const"));
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &item.ident,
+                    "Only the GUID and VERSION consts are allowed on this trait",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Combines the consts with the macro attributes into the interface identity.
+    pub fn resolve(
+        self,
+        attrs: InterfaceAttributes,
+        span: proc_macro2::Span,
+    ) -> syn::Result<(u128, InterfaceVersion)> {
+        if attrs.guid.is_some() && self.guid.is_some() {
+            return Err(syn::Error::new(
+                span,
+                "The guid is specified both as an attribute and as a const",
+            ));
+        }
+        if attrs.version.is_some() && self.version.is_some() {
+            return Err(syn::Error::new(
+                span,
+                "The version is specified both as an attribute and as a const",
+            ));
+        }
+
+        let guid = attrs.guid.or(self.guid).ok_or_else(|| {
+            syn::Error::new(
+                span,
+                "Missing required 'guid' attribute or `const GUID: u128` item",
+            )
+        })?;
+        let version = attrs.version.or(self.version).unwrap_or_default();
+        Ok((guid, version))
+    }
+}
+
+fn expect_int_literal(expr: &Expr) -> syn::Result<&LitInt> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => Ok(lit),
+        _ => Err(syn::Error::new_spanned(expr, "Expected an integer literal")),
+    }
+}
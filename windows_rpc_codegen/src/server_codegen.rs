@@ -0,0 +1,1726 @@
+use quote::{format_ident, quote};
+
+use crate::compat_codegen::{
+    generate_server_fields, generate_server_fields_init, generate_server_forward,
+};
+use crate::constants::{
+    MIDL_STUB_DESC_CHECK_BOUNDS, MIDL_STUB_DESC_MIDL_VERSION, MIDL_STUB_DESC_VERSION,
+    RPC_TRANSFER_SYNTAX_NDR_GUID, RPC_TRANSFER_SYNTAX_NDR64_GUID,
+};
+use crate::ndr::{generate_proc_header, generate_type_fixups, generate_type_format_string};
+use crate::ndr64::{generate_ndr64_proc_buffer_code, generate_ndr64_type_format};
+use crate::types::{BaseType, Interface, Method, StringForm, TransferSyntaxes};
+
+use crate::types::Type;
+
+/// Generate the signature of a server trait method, without the trailing semicolon, taking
+/// `&self` first with `receiver`, as the methods of callback traits do
+pub(crate) fn generate_server_method_signature(
+    method: &Method,
+    receiver: bool,
+) -> proc_macro2::TokenStream {
+    let method_name = format_ident!("{}", method.name);
+    let receiver = receiver.then(|| quote! { &self, });
+    let params: Vec<_> = method
+        .parameters
+        .iter()
+        .map(|param| {
+            let param_name = format_ident!("{}", param.name);
+            let param_type = param.to_server_rust_type();
+            quote! { #param_name: #param_type }
+        })
+        .collect();
+
+    let return_type = if let Some(presented) = &method.presented_return {
+        let rtype_tokens: proc_macro2::TokenStream = presented
+            .parse()
+            .expect("presented paths are parsed from tokens");
+        Some(rtype_tokens)
+    } else {
+        // Use to_rust_return_type for return values (String instead of &str), and the
+        // server's context for opened context handles
+        method.return_type.as_ref().map(|rtype| match rtype {
+            Type::ContextHandle => quote! { windows_rpc::context::ServerContext },
+            rtype => rtype.to_rust_return_type(),
+        })
+    };
+    let return_type = match (&method.error, return_type) {
+        (Some(error), rtype) => {
+            let rtype = rtype.unwrap_or_else(|| quote! { () });
+            let error = error.to_rust_return_type();
+            quote! { -> std::result::Result<#rtype, #error> }
+        }
+        (None, Some(rtype)) => quote! { -> #rtype },
+        (None, None) => quote! {},
+    };
+
+    quote! {
+        fn #method_name(#receiver #(#params),*) #return_type
+    }
+}
+
+/// Generate the server implementation trait that users will implement
+fn generate_server_trait(interface: &Interface) -> proc_macro2::TokenStream {
+    let trait_name = format_ident!("{}ServerImpl", interface.name);
+
+    let methods = interface
+        .methods
+        .iter()
+        .map(|method| generate_server_method_signature(method, false));
+
+    quote! {
+        #[allow(clippy::too_many_arguments)]
+        pub trait #trait_name {
+            #(#methods;)*
+        }
+    }
+}
+
+/// Generate extern "C-unwind" wrapper functions for each method, which may raise faults
+/// through the NDR engine
+/// These are now generated as part of the impl block and call T::method_name directly, or
+/// the methods of the client's handler for `callbacks`
+pub(crate) fn generate_wrapper_functions(
+    interface: &Interface,
+    callbacks: bool,
+) -> proc_macro2::TokenStream {
+    let _trait_name = format_ident!("{}ServerImpl", interface.name);
+    let callbacks_trait = format_ident!("{}", interface.name);
+    let interface_guid = interface.uuid;
+
+    let wrappers: Vec<_> = interface
+        .methods
+        .iter()
+        .map(|method| {
+            let wrapper_name = format_ident!("__{}__{}_wrapper", interface.name, method.name);
+            let method_name = format_ident!("{}", method.name);
+            let has_string_return = method.return_type.as_ref().is_some_and(Type::is_string);
+            let struct_return = method
+                .return_type
+                .as_ref()
+                .filter(|t| matches!(t, Type::Struct(_)));
+
+            let unmasked_floats = method.unmasked_float_params();
+            // Generate FFI parameter types (PCWSTR for strings, native types for others)
+            let mut ffi_params: Vec<_> = method
+                .parameters
+                .iter()
+                .map(|param| {
+                    let param_name = format_ident!("{}", param.name);
+                    match param.r#type {
+                        Type::String | Type::WideString | Type::HString => {
+                            quote! { #param_name: windows::core::PCWSTR }
+                        }
+                        Type::AnsiString => quote! { #param_name: windows::core::PCSTR },
+                        // Slices arrive as their hidden length followed by the array pointer,
+                        // or as the pointer alone when sized by another parameter
+                        Type::Slice(element) => {
+                            let len_name = format_ident!("__{}_len", param.name);
+                            let element = element.to_rust_type();
+                            let pointer = if param.is_out {
+                                quote! { *mut #element }
+                            } else {
+                                quote! { *const #element }
+                            };
+                            if param.size_is.is_some() {
+                                quote! { #param_name: #pointer }
+                            } else {
+                                quote! { #len_name: u32, #param_name: #pointer }
+                            }
+                        }
+                        // Out parameters point to a value owned by the server stub
+                        Type::Simple(_) if param.is_out => {
+                            let param_type = param.r#type.to_rust_type();
+                            quote! { #param_name: *mut #param_type }
+                        }
+                        // Floats the `FloatDoubleMask` has no room for arrive as integers
+                        Type::Simple(_) if unmasked_floats.contains(&param.name.as_str()) => {
+                            quote! { #param_name: u64 }
+                        }
+                        Type::Simple(_) => {
+                            let param_type = param.r#type.to_rust_type();
+                            quote! { #param_name: #param_type }
+                        }
+                        Type::Struct(_) if param.is_out => {
+                            let path = param.r#type.struct_path();
+                            quote! { #param_name: *mut #path }
+                        }
+                        Type::Struct(_) => {
+                            let path = param.r#type.struct_path();
+                            quote! { #param_name: *const #path }
+                        }
+                        // Enums arrive as 32-bit integers
+                        Type::Enum(_) => quote! { #param_name: u32 },
+                        // Options arrive as pointers, null for `None`
+                        Type::Optional(base_type) => {
+                            let value_type = base_type.to_rust_type();
+                            quote! { #param_name: *const #value_type }
+                        }
+                        Type::OptionalString => quote! { #param_name: windows::core::PCWSTR },
+                        Type::OptionalAnsiString => quote! { #param_name: windows::core::PCSTR },
+                        Type::SystemHandle { .. } => {
+                            quote! { #param_name: *mut std::ffi::c_void }
+                        }
+                        // Context handles arrive as the server's context, or a pointer to it
+                        // when the handler may replace it
+                        Type::ContextHandle if param.is_out => {
+                            quote! { #param_name: *mut *mut std::ffi::c_void }
+                        }
+                        Type::ContextHandle => quote! { #param_name: *mut std::ffi::c_void },
+                        // Pipes arrive as the pipe structure of the server stub
+                        Type::Pipe(_) => quote! { #param_name: *mut std::ffi::c_void },
+                        // Out string vectors point to their length and array
+                        Type::StringVec => {
+                            let len_name = format_ident!("__{}_len", param.name);
+                            quote! { #len_name: *mut u32, #param_name: *mut *mut *mut u16 }
+                        }
+                    }
+                })
+                .collect();
+
+            // Add the error code parameter, preceding the return value, if function returns
+            // a `Result`, or the value parameter if it returns the status instead
+            if method.error.is_some() && !method.returns_status() {
+                ffi_params.push(quote! { __service_error: *mut u32 });
+            }
+            if let Some(value_param) = method.value_parameter() {
+                let value_type = value_param.r#type.to_rust_type();
+                ffi_params.push(quote! { __return_value: *mut #value_type });
+            }
+            // Add out string parameter if function returns String
+            if has_string_return {
+                ffi_params.push(quote! { __out_string: *mut *mut u16 });
+            }
+            // Add out string or value pointer parameter if function returns an option
+            match method.return_type {
+                Some(Type::OptionalString) => {
+                    ffi_params.push(quote! { __out_string: *mut *mut u16 });
+                }
+                Some(Type::Optional(base_type)) => {
+                    let value_type = base_type.to_rust_type();
+                    ffi_params.push(quote! { __out_value: *mut *mut #value_type });
+                }
+                // Add out handle parameter if function returns a handle
+                Some(Type::SystemHandle { .. }) => {
+                    ffi_params.push(quote! { __out_handle: *mut *mut std::ffi::c_void });
+                }
+                // Add out context parameter if function opens a context handle
+                Some(Type::ContextHandle) => {
+                    ffi_params.push(quote! { __out_context: *mut *mut std::ffi::c_void });
+                }
+                _ => {}
+            }
+            // Add out struct parameter if function returns a struct
+            if let Some(struct_type) = struct_return {
+                let path = struct_type.struct_path();
+                ffi_params.push(quote! { __out_struct: *mut #path });
+            }
+            // Add out length and array parameters if function returns a vector
+            if let Some(Type::Slice(element)) = method.return_type {
+                let element = element.to_rust_type();
+                ffi_params.push(quote! { __out_len: *mut u32, __out_array: *mut *mut #element });
+            }
+            if let Some(Type::StringVec) = method.return_type {
+                ffi_params.push(quote! { __out_len: *mut u32, __out_array: *mut *mut *mut u16 });
+            }
+
+            // Generate conversions for string and slice parameters, after restoring the
+            // floats received as integers, whose low 32 bits hold a `float`
+            let float_conversions = method.parameters.iter().filter_map(|param| {
+                if !unmasked_floats.contains(&param.name.as_str()) {
+                    return None;
+                }
+                let param_name = format_ident!("{}", param.name);
+                Some(match param.r#type {
+                    Type::Simple(BaseType::F32) => {
+                        quote! { let #param_name = f32::from_bits(#param_name as u32); }
+                    }
+                    _ => quote! { let #param_name = f64::from_bits(#param_name); },
+                })
+            });
+            let string_conversions: Vec<_> = float_conversions
+                .chain(method.parameters.iter().filter_map(|param| {
+                    let param_name = format_ident!("{}", param.name);
+                    let converted_name = format_ident!("__{}_converted", param.name);
+                    // The length of a slice: its hidden length, or its `size_is` parameter
+                    let len_name = match &param.size_is {
+                        Some(length) => format_ident!("{}", length),
+                        None => format_ident!("__{}_len", param.name),
+                    };
+                    // Converted from the number or struct it is sent as
+                    if let Some(presented) = &param.presented {
+                        let transmittable = param.r#type.transmittable(presented);
+                        let wire = match param.r#type {
+                            Type::Struct(_) => quote! { unsafe { *#param_name } },
+                            _ => quote! { #param_name },
+                        };
+                        return Some(quote! {
+                            let #converted_name = #transmittable::from_wire(#wire);
+                        });
+                    }
+                    match param.r#type {
+                        // Converted without loss, invalid UTF-16 included
+                        Type::String if param.string_form.is_os_string() => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::wstr::U16CStr::from_ptr(#param_name.0) }.to_os_string();
+                        }),
+                        // Handed over to handlers taking it by value
+                        Type::String if param.string_form == StringForm::String => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::wstr::DecodedStr::decode(#param_name) }.into_string();
+                        }),
+                        // Decoded into a reused per-thread buffer
+                        Type::String => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::wstr::DecodedStr::decode(#param_name) };
+                        }),
+                        // Converted from the ANSI code page
+                        Type::AnsiString => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::wstr::decode_ansi(#param_name) };
+                        }),
+                        // Borrowed from the RPC buffer without decoding
+                        Type::WideString => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::wstr::U16CStr::from_ptr(#param_name.0) };
+                        }),
+                        // Copied without decoding
+                        Type::HString => Some(quote! {
+                            let #converted_name = windows::core::HSTRING::from_wide(
+                                unsafe { windows_rpc::wstr::U16CStr::from_ptr(#param_name.0) }.as_slice(),
+                            );
+                        }),
+                        // Out arrays are allocated by the server stub
+                        Type::Slice(_) if param.is_out => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::ndr::out_slice(#param_name, #len_name) };
+                        }),
+                        // The array points into the RPC buffer, which outlives the call
+                        Type::Slice(_) => Some(quote! {
+                            let #converted_name = if #len_name == 0 || #param_name.is_null() {
+                                &[]
+                            } else {
+                                unsafe { std::slice::from_raw_parts(#param_name, #len_name as usize) }
+                            };
+                        }),
+                        Type::Simple(_) if param.is_out => Some(quote! {
+                            let #converted_name = unsafe { &mut *#param_name };
+                        }),
+                        // Only zeroed structs are valid out parameters, not unions
+                        Type::Struct(_) if param.is_out => {
+                            let path = param.r#type.struct_path();
+                            Some(quote! {
+                                let #converted_name = unsafe { windows_rpc::ndr::out_struct::<#path>(#param_name) };
+                            })
+                        }
+                        // Full pointers from other clients may be null
+                        Type::Struct(_) if param.full_pointer => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::ndr::full_pointer_ref(#param_name) };
+                        }),
+                        // The struct points into the RPC buffer or stub memory
+                        Type::Struct(_) => Some(quote! {
+                            let #converted_name = unsafe { &*#param_name };
+                        }),
+                        // Values matching no variant fault the call
+                        Type::Enum(_) => {
+                            let path = param.r#type.struct_path();
+                            Some(quote! {
+                                let #converted_name = windows_rpc::ndr::enum_from_wire::<#path>(#param_name);
+                            })
+                        }
+                        // Copied out of the RPC buffer
+                        Type::Optional(_) => Some(quote! {
+                            let #converted_name = unsafe { #param_name.as_ref() }.copied();
+                        }),
+                        Type::OptionalString => Some(quote! {
+                            let #converted_name = (!#param_name.is_null())
+                                .then(|| unsafe { windows_rpc::wstr::DecodedStr::decode(#param_name) });
+                        }),
+                        Type::OptionalAnsiString => Some(quote! {
+                            let #converted_name = (!#param_name.is_null())
+                                .then(|| unsafe { windows_rpc::wstr::decode_ansi(#param_name) });
+                        }),
+                        // Duplicated by the server stub, which closes it once the call returns
+                        Type::SystemHandle { .. } => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::ndr::borrow_system_handle(#param_name) };
+                        }),
+                        // Owned by the runtime, which holds it until the context is closed or
+                        // run down
+                        Type::ContextHandle if param.is_out => Some(quote! {
+                            let mut #converted_name = unsafe { windows_rpc::context::ContextSlot::new(#param_name) };
+                        }),
+                        Type::ContextHandle => Some(quote! {
+                            let #converted_name = unsafe { windows_rpc::context::ServerContext::borrow_raw(#param_name) };
+                        }),
+                        // Streamed through the server stub while the handler runs
+                        Type::Pipe(element) if param.is_out => {
+                            let element = element.to_rust_type();
+                            Some(quote! {
+                                let mut #converted_name = unsafe { windows_rpc::pipe::PipeWriter::<#element>::new(#param_name) };
+                            })
+                        }
+                        Type::Pipe(element) => {
+                            let element = element.to_rust_type();
+                            Some(quote! {
+                                let mut #converted_name = unsafe { windows_rpc::pipe::PipeReader::<#element>::new(#param_name) };
+                            })
+                        }
+                        Type::Simple(_) => None,
+                        // Filled by the handler, and left empty for the stub if it fails
+                        Type::StringVec => Some(quote! {
+                            unsafe { (*#len_name, *#param_name) = (0, std::ptr::null_mut()) };
+                            let mut #converted_name = std::vec::Vec::<String>::new();
+                        }),
+                    }
+                }))
+                .collect();
+
+            // Generate parameter names for the trait method call (converted names for strings)
+            let param_names: Vec<_> = method
+                .parameters
+                .iter()
+                .map(|param| {
+                    let converted_name = format_ident!("__{}_converted", param.name);
+                    if param.r#type == Type::String && param.string_form != StringForm::Str {
+                        match param.string_form {
+                            StringForm::StringRef => quote! { #converted_name.as_string() },
+                            StringForm::Cow => quote! { std::borrow::Cow::Borrowed(&*#converted_name) },
+                            StringForm::OsStr => quote! { #converted_name.as_os_str() },
+                            StringForm::Path => quote! { std::path::Path::new(&#converted_name) },
+                            StringForm::PathBuf => quote! { std::path::PathBuf::from(#converted_name) },
+                            _ => quote! { #converted_name },
+                        }
+                    } else if matches!(param.r#type, Type::String | Type::AnsiString) {
+                        quote! { &*#converted_name }
+                    } else if matches!(param.r#type, Type::OptionalString | Type::OptionalAnsiString) {
+                        quote! { #converted_name.as_deref() }
+                    } else if matches!(param.r#type, Type::HString) {
+                        quote! { &#converted_name }
+                    } else if param.is_out_string_vec() {
+                        quote! { &mut #converted_name }
+                    } else if param.r#type == Type::ContextHandle && param.is_out {
+                        quote! { &mut *#converted_name }
+                    } else if param.r#type == Type::ContextHandle {
+                        quote! { &*#converted_name }
+                    } else if matches!(param.r#type, Type::Pipe(_)) {
+                        quote! { &mut #converted_name }
+                    } else if matches!(param.r#type, Type::WideString | Type::Slice(_) | Type::Struct(_) | Type::Enum(_) | Type::Optional(_) | Type::SystemHandle { .. })
+                        || param.is_out
+                        || param.presented.is_some()
+                    {
+                        quote! { #converted_name }
+                    } else {
+                        let param_name = format_ident!("{}", param.name);
+                        quote! { #param_name }
+                    }
+                })
+                .collect();
+
+            // Calls the implementation, converting a `transmit_as(...)` return value to the
+            // type it is sent as
+            let call = if callbacks {
+                quote! { __callbacks.#method_name(#(#param_names),*) }
+            } else {
+                quote! { T::#method_name(#(#param_names),*) }
+            };
+            let transmittable = match (&method.presented_return, &method.return_type) {
+                (Some(presented), Some(return_type)) => Some(return_type.transmittable(presented)),
+                _ => None,
+            };
+            // The pointer fields of struct parameters point into the call's buffer, which
+            // outlives the handler wherever it runs
+            let has_struct_param = method
+                .parameters
+                .iter()
+                .any(|param| matches!(param.r#type, Type::Struct(_)) && param.presented.is_none());
+            // Pipes left unfinished by the handler are finished once it returns, `[in]` ones
+            // first, as the server stub receives them before sending `[out]` ones
+            let mut pipes: Vec<_> = method
+                .parameters
+                .iter()
+                .filter(|param| matches!(param.r#type, Type::Pipe(_)))
+                .collect();
+            pipes.sort_by_key(|param| param.is_out);
+            let pipe_finishes: Vec<_> = pipes
+                .iter()
+                .map(|param| {
+                    let converted_name = format_ident!("__{}_converted", param.name);
+                    quote! { #converted_name.finish(); }
+                })
+                .collect();
+            // Asynchronous routines already run where `invoke_async()` calls them, and
+            // handlers streaming pipes on the thread whose server stub streams them
+            let invoke_handler = |body: proc_macro2::TokenStream| {
+                let invoke = if method.r#async {
+                    body
+                } else if method.has_pipe_param() {
+                    quote! {
+                        windows_rpc::dispatch::invoke_here(|| {
+                            let __value = #body;
+                            #(#pipe_finishes)*
+                            __value
+                        })
+                    }
+                } else if has_struct_param {
+                    quote! { windows_rpc::dispatch::invoke(unsafe { windows_rpc::dispatch::assume_send(|| #body) }) }
+                } else {
+                    quote! { windows_rpc::dispatch::invoke(|| #body) }
+                };
+                // Callbacks are handled by the client whose call is in progress on the thread
+                if callbacks {
+                    quote! {{
+                        let __callbacks = unsafe { windows_rpc::callback::handler::<dyn #callbacks_trait>(#interface_guid) };
+                        #invoke
+                    }}
+                } else {
+                    invoke
+                }
+            };
+            let invoke_call = invoke_handler(call.clone());
+            let invoke = match &method.error {
+                // The error code is returned as the status, and the outputs are left empty
+                // for the stub to send back
+                Some(_) if method.returns_status() => {
+                    let empty_outputs = generate_empty_outputs(method, quote! { __error });
+                    let to_wire = match &transmittable {
+                        Some(transmittable) => quote! { #transmittable::to_wire(&__value) },
+                        None => quote! { __value },
+                    };
+                    quote! {
+                        match #invoke_call {
+                            std::result::Result::Ok(__value) => #to_wire,
+                            std::result::Result::Err(__error) => {
+                                #empty_outputs
+                            }
+                        }
+                    }
+                }
+                // The error code is written in place of the value, and the outputs are left
+                // empty for the stub to send back
+                Some(error) => {
+                    let code = match error {
+                        Type::Enum(_) => quote! { windows_rpc::ndr::NdrEnum::to_wire(__error) },
+                        _ => quote! { __error },
+                    };
+                    let empty_outputs = generate_empty_outputs(method, quote! {});
+                    let to_wire = match &transmittable {
+                        Some(transmittable) => quote! { #transmittable::to_wire(&__value) },
+                        None => quote! { __value },
+                    };
+                    quote! {
+                        match #invoke_call {
+                            std::result::Result::Ok(__value) => #to_wire,
+                            std::result::Result::Err(__error) => {
+                                unsafe { *__service_error = #code };
+                                #empty_outputs
+                            }
+                        }
+                    }
+                }
+                None => match &transmittable {
+                    Some(transmittable) => invoke_handler(quote! { #transmittable::to_wire(&#call) }),
+                    None => invoke_call,
+                },
+            };
+
+            // String vector out parameters are copied to memory allocated with
+            // midl_user_allocate once the handler succeeded, as for string vector returns
+            let vec_writes: Vec<_> = method
+                .parameters
+                .iter()
+                .filter(|param| param.is_out_string_vec())
+                .map(|param| {
+                    let param_name = format_ident!("{}", param.name);
+                    let len_name = format_ident!("__{}_len", param.name);
+                    let converted_name = format_ident!("__{}_converted", param.name);
+                    quote! {
+                        unsafe { (*#len_name, *#param_name) = windows_rpc::wstr::to_midl_string_array(&#converted_name) };
+                    }
+                })
+                .collect();
+            let invoke = if vec_writes.is_empty() {
+                invoke
+            } else {
+                quote! {
+                    {
+                        let __value = #invoke;
+                        #(#vec_writes)*
+                        __value
+                    }
+                }
+            };
+
+            // `error_status` and `hresult` methods return a zero status, `S_OK`, once the
+            // outputs are written
+            let (status_type, status) = if method.error_status {
+                (quote! { -> u32 }, quote! { 0 })
+            } else if method.hresult {
+                (quote! { -> i32 }, quote! { 0 })
+            } else {
+                (quote! {}, quote! {})
+            };
+
+            // Generate the wrapper body based on return type
+            // Now calling T::method_name directly instead of using context
+            let (ret, body) = match &method.return_type {
+                Some(Type::Simple(_)) if method.returns_status() => {
+                    // The value is written to the out param
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+                        unsafe { *__return_value = __result };
+                        0
+                    })
+                }
+                Some(Type::Simple(_)) => {
+                    let rtype_tokens = method.return_type.as_ref().unwrap().to_rust_return_type();
+                    (quote! { -> #rtype_tokens }, quote! {
+                        #(#string_conversions)*
+                        #invoke
+                    })
+                }
+                Some(Type::Enum(_)) => {
+                    // Enums are returned as 32-bit integers
+                    (quote! { -> u32 }, quote! {
+                        #(#string_conversions)*
+                        windows_rpc::ndr::NdrEnum::to_wire(
+                            #invoke,
+                        )
+                    })
+                }
+                Some(Type::String | Type::WideString | Type::HString) => {
+                    // For string return, we don't return anything directly - we write to the out param
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+
+                        // Copy the string to memory allocated with midl_user_allocate and write the
+                        // pointer to the out parameter
+                        unsafe { *__out_string = windows_rpc::wstr::to_midl_string(&__result) };
+                        #status
+                    })
+                }
+                Some(Type::OptionalString) => {
+                    // For optional string return, `None` is written as a null string
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+                        unsafe {
+                            *__out_string = __result
+                                .map_or(std::ptr::null_mut(), |string| windows_rpc::wstr::to_midl_string(&string))
+                        };
+                        #status
+                    })
+                }
+                Some(Type::Optional(_)) => {
+                    // For optional return, the value is copied to memory allocated with
+                    // midl_user_allocate, and `None` is written as a null pointer
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+                        unsafe { *__out_value = windows_rpc::ndr::to_midl_value(__result) };
+                        #status
+                    })
+                }
+                Some(Type::Struct(_)) => {
+                    // For struct return, the value is written to the out param
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+                        unsafe { *__out_struct = __result };
+                        #status
+                    })
+                }
+                Some(Type::SystemHandle { .. }) => {
+                    // For handle return, the handle is written to the out param, and the
+                    // server stub closes it once it is duplicated into the client
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+                        unsafe { *__out_handle = std::os::windows::io::IntoRawHandle::into_raw_handle(__result) };
+                        #status
+                    })
+                }
+                Some(Type::ContextHandle) => {
+                    // For context handle return, the context is handed over to the runtime,
+                    // which opens a context handle for a non-null one
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+                        unsafe { *__out_context = windows_rpc::context::ServerContext::into_raw(__result) };
+                        #status
+                    })
+                }
+                Some(Type::Slice(_)) => {
+                    // For vector return, the length and array are written to the out params
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+
+                        // Copy the array to memory allocated with midl_user_allocate
+                        unsafe { (*__out_len, *__out_array) = windows_rpc::ndr::to_midl_array(&__result) };
+                        #status
+                    })
+                }
+                Some(Type::StringVec) => {
+                    // For string vector return, the strings and the array of their pointers
+                    // are copied to memory allocated with midl_user_allocate, which the
+                    // server stub frees once they are sent
+                    (status_type, quote! {
+                        #(#string_conversions)*
+                        let __result = #invoke;
+                        unsafe { (*__out_len, *__out_array) = windows_rpc::wstr::to_midl_string_array(&__result) };
+                        #status
+                    })
+                }
+                Some(Type::AnsiString | Type::OptionalAnsiString) => {
+                    unreachable!("ANSI strings are only input parameters")
+                }
+                Some(Type::Pipe(_)) => unreachable!("pipes are only parameters"),
+                None => (status_type, quote! {
+                    #(#string_conversions)*
+                    #invoke;
+                    #status
+                }),
+            };
+
+            if method.r#async {
+                // The server stub passes the call's `RPC_ASYNC_STATE` first, and the routine
+                // returns the reply for `invoke_async()` to complete the call with
+                quote! {
+                    extern "C-unwind" fn #wrapper_name(__async_state: *mut std::ffi::c_void, binding_handle: *const std::ffi::c_void, #(#ffi_params),*) {
+                        let __routine = unsafe { windows_rpc::dispatch::assume_send(move || #ret { #body }) };
+                        unsafe { windows_rpc::dispatch::invoke_async(__async_state, __routine) }
+                    }
+                }
+            } else {
+                quote! {
+                    extern "C-unwind" fn #wrapper_name(binding_handle: *const std::ffi::c_void, #(#ffi_params),*) #ret {
+                        #body
+                    }
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        #(#wrappers)*
+    }
+}
+
+/// Generates the return of a wrapper whose handler returned an error: a zero base type,
+/// or null pointers and zero lengths written to the hidden outputs, which the stub sends
+/// back as is. `status` is the status returned by `error_status` and `hresult` wrappers.
+fn generate_empty_outputs(
+    method: &Method,
+    status: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match &method.return_type {
+        // Written to the out param zeroed by the server stub
+        Some(Type::Simple(_)) if method.returns_status() => quote! { return #status },
+        Some(Type::Simple(_)) => quote! { return std::default::Default::default() },
+        Some(Type::Enum(_)) => quote! { return 0 },
+        Some(Type::String | Type::WideString | Type::HString | Type::OptionalString) => quote! {
+            unsafe { *__out_string = std::ptr::null_mut() };
+            return #status;
+        },
+        Some(Type::Optional(_)) => quote! {
+            unsafe { *__out_value = std::ptr::null_mut() };
+            return #status;
+        },
+        // Structs without pointer fields, for which zero is a valid value
+        Some(Type::Struct(_)) => quote! {
+            unsafe { __out_struct.write_bytes(0, 1) };
+            return #status;
+        },
+        Some(Type::Slice(_) | Type::StringVec) => quote! {
+            unsafe { (*__out_len, *__out_array) = (0, std::ptr::null_mut()) };
+            return #status;
+        },
+        // No context is opened
+        Some(Type::ContextHandle) => quote! {
+            unsafe { *__out_context = std::ptr::null_mut() };
+            return #status;
+        },
+        Some(Type::SystemHandle { .. }) => unreachable!("methods returning handles have no errors"),
+        Some(Type::AnsiString | Type::OptionalAnsiString) => {
+            unreachable!("ANSI strings are only input parameters")
+        }
+        Some(Type::Pipe(_)) => unreachable!("pipes are only parameters"),
+        None => quote! { return #status },
+    }
+}
+
+/// Generate the dispatch table initialization
+pub(crate) fn generate_dispatch_table_init(interface: &Interface) -> proc_macro2::TokenStream {
+    let method_count = interface.methods.len();
+
+    // For NDR 2.0, all entries forward to NdrServerCall2, or NdrAsyncServerCall for
+    // asynchronous procedures
+    let ndr_entries = interface.methods.iter().map(|method| {
+        let dispatch = if method.r#async {
+            quote! { windows_rpc::dispatch::ndr_async_dispatch }
+        } else {
+            quote! { windows_rpc::dispatch::ndr_dispatch }
+        };
+        quote! {
+            std::option::Option::Some(unsafe {
+                std::mem::transmute::<
+                    unsafe extern "system-unwind" fn(*mut windows_sys::Win32::System::Rpc::RPC_MESSAGE),
+                    unsafe extern "system" fn(*mut windows_sys::Win32::System::Rpc::RPC_MESSAGE),
+                >(#dispatch)
+            })
+        }
+    });
+
+    // For NDR64, all entries forward to NdrServerCallAll, or Ndr64AsyncServerCallAll for
+    // asynchronous procedures
+    let ndr64_entries = interface.methods.iter().map(|method| {
+        let dispatch = if method.r#async {
+            quote! { windows_rpc::dispatch::ndr64_async_dispatch }
+        } else {
+            quote! { windows_rpc::dispatch::ndr64_dispatch }
+        };
+        quote! {
+            std::option::Option::Some(unsafe {
+                std::mem::transmute::<
+                    unsafe extern "system-unwind" fn(*mut windows_sys::Win32::System::Rpc::RPC_MESSAGE),
+                    unsafe extern "system" fn(*mut windows_sys::Win32::System::Rpc::RPC_MESSAGE),
+                >(#dispatch)
+            })
+        }
+    });
+
+    let dispatch_functions_ndr = interface.transfer_syntaxes.has_ndr20().then(|| {
+        quote! {
+            let dispatch_functions_ndr: std::boxed::Box<[windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION; #method_count]> =
+                std::boxed::Box::new([#(#ndr_entries),*]);
+        }
+    });
+
+    let dispatch_functions_ndr64 = interface.transfer_syntaxes.has_ndr64().then(|| {
+        quote! {
+            let dispatch_functions_ndr64: std::boxed::Box<[windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION; #method_count]> =
+                std::boxed::Box::new([#(#ndr64_entries),*]);
+        }
+    });
+
+    quote! {
+        #dispatch_functions_ndr
+        #dispatch_functions_ndr64
+    }
+}
+
+/// Generate the server routine table initialization
+pub(crate) fn generate_server_routine_table(interface: &Interface) -> proc_macro2::TokenStream {
+    let method_count = interface.methods.len();
+
+    let wrapper_casts: Vec<_> = interface
+        .methods
+        .iter()
+        .map(|method| {
+            let wrapper_name = format_ident!("__{}__{}_wrapper", interface.name, method.name);
+            quote! {
+                unsafe {
+                    std::mem::transmute::<
+                        *const (),
+                        windows_sys::Win32::System::Rpc::SERVER_ROUTINE
+                    >(Self::#wrapper_name as *const ())
+                }
+            }
+        })
+        .collect();
+
+    quote! {
+        let server_routines: std::boxed::Box<[windows_sys::Win32::System::Rpc::SERVER_ROUTINE; #method_count]> =
+            std::boxed::Box::new([#(#wrapper_casts),*]);
+    }
+}
+
+pub fn compile_server(interface: &Interface) -> proc_macro2::TokenStream {
+    let rpc_server_name = format_ident!("{}Server", interface.name);
+    let rpc_client_name = format_ident!("{}Client", interface.name);
+    let trait_name = format_ident!("{}ServerImpl", interface.name);
+    let interface_guid_name = format_ident!("{}_GUID", interface.name.to_uppercase());
+    let interface_version_major = interface.version.major;
+    let interface_version_minor = interface.version.minor;
+    let interface_guid = interface.uuid;
+
+    // Generate format strings (reused from client)
+    let (type_format, type_offsets) = generate_type_format_string(interface);
+
+    let (proc_header, format_offsets, type_fixups) = generate_proc_header(interface, &type_offsets);
+    let type_fixups = generate_type_fixups(&type_fixups);
+    let proc_header_len = proc_header.len();
+    let format_offsets_len = format_offsets.len();
+
+    let ndr64_type_format = generate_ndr64_type_format(interface);
+    let ndr64_type_format_len = ndr64_type_format.len();
+
+    let ndr64_proc_buffer_construction = generate_ndr64_proc_buffer_code(interface);
+    let ndr64_proc_table_len = interface.methods.len();
+    let proc_table_indices: Vec<_> = (0..ndr64_proc_table_len).collect();
+
+    let method_count = interface.methods.len();
+
+    // Interfaces offering a single transfer syntax omit the other's format strings, syntax
+    // info and dispatch table
+    let syntaxes = interface.transfer_syntaxes;
+    let syntax_info_len = usize::from(syntaxes.has_ndr20()) + usize::from(syntaxes.has_ndr64());
+    let (ndr_fields, ndr_init, ndr_self_init, ndr_syntax_info, ndr_fixup) = if !syntaxes.has_ndr20()
+    {
+        (quote! {}, quote! {}, quote! {}, quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                dispatch_table_ndr: std::mem::ManuallyDrop<std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE>>,
+                dispatch_functions_ndr: std::mem::ManuallyDrop<std::boxed::Box<[windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION; #method_count]>>,
+                type_format: std::mem::ManuallyDrop<std::boxed::Box<[u8]>>,
+                proc_header: std::mem::ManuallyDrop<std::boxed::Box<[u8; #proc_header_len]>>,
+                format_offsets: std::mem::ManuallyDrop<std::boxed::Box<[u16; #format_offsets_len]>>,
+            },
+            quote! {
+                let mut type_format: std::boxed::Box<[u8]> = std::boxed::Box::new([#(#type_format),*]);
+                let mut proc_header: std::boxed::Box<[u8; #proc_header_len]> = std::boxed::Box::new([#(#proc_header),*]);
+                #type_fixups
+                let mut format_offsets: std::boxed::Box<[u16; #format_offsets_len]> = std::boxed::Box::new([#(#format_offsets),*]);
+
+                let mut dispatch_table_ndr = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE {
+                    DispatchTableCount: #method_count as u32,
+                    DispatchTable: std::option::Option::None,
+                    Reserved: 0,
+                });
+            },
+            quote! {
+                dispatch_table_ndr: std::mem::ManuallyDrop::new(dispatch_table_ndr),
+                dispatch_functions_ndr: std::mem::ManuallyDrop::new(dispatch_functions_ndr),
+                type_format: std::mem::ManuallyDrop::new(type_format),
+                proc_header: std::mem::ManuallyDrop::new(proc_header),
+                format_offsets: std::mem::ManuallyDrop::new(format_offsets),
+            },
+            quote! {
+                windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO {
+                    TransferSyntax: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR_GUID),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: 2,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: &raw mut *dispatch_table_ndr as *mut _,
+                    ProcString: proc_header.as_mut_ptr(),
+                    FmtStringOffset: format_offsets.as_ptr(),
+                    TypeString: type_format.as_mut_ptr(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    pMethodProperties: std::ptr::null(),
+                    pReserved2: 0,
+                },
+            },
+            quote! {
+                dispatch_table_ndr.DispatchTable = std::option::Option::Some(unsafe {
+                    std::mem::transmute::<*const windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION, _>(
+                        dispatch_functions_ndr.as_ptr()
+                    )
+                });
+            },
+        )
+    };
+    let (ndr64_fields, ndr64_init, ndr64_self_init, ndr64_syntax_info, ndr64_fixup) = if !syntaxes
+        .has_ndr64()
+    {
+        (quote! {}, quote! {}, quote! {}, quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                dispatch_table_ndr64: std::mem::ManuallyDrop<std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE>>,
+                dispatch_functions_ndr64: std::mem::ManuallyDrop<std::boxed::Box<[windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION; #method_count]>>,
+                ndr64_type_format: std::mem::ManuallyDrop<std::boxed::Box<[u8; #ndr64_type_format_len]>>,
+                ndr64_proc_buffer: std::mem::ManuallyDrop<std::boxed::Box<std::vec::Vec<u8>>>,
+                ndr64_proc_table: std::mem::ManuallyDrop<std::boxed::Box<[*const u8; #ndr64_proc_table_len]>>,
+            },
+            quote! {
+                let ndr64_type_format: std::boxed::Box<[u8; #ndr64_type_format_len]> =
+                    std::boxed::Box::new([#(#ndr64_type_format),*]);
+
+                let (ndr64_proc_buffer_data, proc_table_offsets) = #ndr64_proc_buffer_construction;
+                let ndr64_proc_buffer = std::boxed::Box::new(ndr64_proc_buffer_data);
+
+                let ndr64_proc_table: std::boxed::Box<[*const u8; #ndr64_proc_table_len]> = {
+                    let base_ptr = ndr64_proc_buffer.as_ptr();
+                    std::boxed::Box::new([
+                        #(unsafe { base_ptr.add(proc_table_offsets[#proc_table_indices]) }),*
+                    ])
+                };
+
+                let mut dispatch_table_ndr64 = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_DISPATCH_TABLE {
+                    DispatchTableCount: #method_count as u32,
+                    DispatchTable: std::option::Option::None,
+                    Reserved: 0,
+                });
+            },
+            quote! {
+                dispatch_table_ndr64: std::mem::ManuallyDrop::new(dispatch_table_ndr64),
+                dispatch_functions_ndr64: std::mem::ManuallyDrop::new(dispatch_functions_ndr64),
+                ndr64_type_format: std::mem::ManuallyDrop::new(ndr64_type_format),
+                ndr64_proc_buffer: std::mem::ManuallyDrop::new(ndr64_proc_buffer),
+                ndr64_proc_table: std::mem::ManuallyDrop::new(ndr64_proc_table),
+            },
+            quote! {
+                windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO {
+                    TransferSyntax: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR64_GUID),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: 1,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: &raw mut *dispatch_table_ndr64 as *mut _,
+                    ProcString: std::ptr::null_mut(),
+                    FmtStringOffset: ndr64_proc_table.as_ptr() as *const u16,
+                    TypeString: std::ptr::null_mut(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    pMethodProperties: std::ptr::null(),
+                    pReserved2: 0,
+                },
+            },
+            quote! {
+                dispatch_table_ndr64.DispatchTable = std::option::Option::Some(unsafe {
+                    std::mem::transmute::<*const windows_sys::Win32::System::Rpc::RPC_DISPATCH_FUNCTION, _>(
+                        dispatch_functions_ndr64.as_ptr()
+                    )
+                });
+            },
+        )
+    };
+    let (format_types, proc_string, fmt_string_offset, transfer_syntax, dispatch_table) =
+        if syntaxes == TransferSyntaxes::Ndr64 {
+            (
+                quote! { ndr64_type_format.as_ptr() },
+                quote! { std::ptr::null_mut() },
+                quote! { ndr64_proc_table.as_ptr() as *const u16 },
+                quote! { &raw const *rpc_transfer_syntax_ndr64 },
+                quote! { &raw mut *dispatch_table_ndr64 },
+            )
+        } else {
+            (
+                quote! { type_format.as_ptr() },
+                quote! { proc_header.as_mut_ptr() },
+                quote! { format_offsets.as_ptr() },
+                quote! { &raw const *rpc_transfer_syntax_ndr },
+                quote! { &raw mut *dispatch_table_ndr },
+            )
+        };
+    // Single-syntax interfaces offer their syntax whatever the preset, and NDR 2.0-only ones
+    // have the interface flags of the `Legacy` preset, like MIDL's `/protocol dce`
+    let syntax_count = if syntaxes == TransferSyntaxes::All {
+        quote! { compatibility.syntax_count() }
+    } else {
+        quote! { 1 }
+    };
+    let flags_compatibility = if syntaxes == TransferSyntaxes::Ndr20 {
+        quote! { windows_rpc::InterfaceCompatibility::Legacy }
+    } else {
+        quote! { compatibility }
+    };
+    // The runtime only sets up the pipes of interfaces that declare them
+    let pipe_flags = if interface.has_pipes() {
+        quote! { | windows::Win32::System::Rpc::RPC_INTERFACE_HAS_PIPES }
+    } else {
+        quote! {}
+    };
+    let (server_syntax_guid, server_syntax_major) = if syntaxes == TransferSyntaxes::Ndr64 {
+        (RPC_TRANSFER_SYNTAX_NDR64_GUID, 1u16)
+    } else {
+        (RPC_TRANSFER_SYNTAX_NDR_GUID, 2u16)
+    };
+    // Boxed metadata the runtime reads, which servers left registered when dropped leak
+    let mut metadata_fields = vec![
+        format_ident!("server_interface"),
+        format_ident!("server_info"),
+        format_ident!("stub_desc"),
+        format_ident!("server_routines"),
+        format_ident!("syntax_info_array"),
+        format_ident!("rpc_transfer_syntax_ndr"),
+        format_ident!("rpc_transfer_syntax_ndr64"),
+        format_ident!("auto_bind_handle"),
+    ];
+    if syntaxes.has_ndr20() {
+        metadata_fields.extend(
+            [
+                "dispatch_table_ndr",
+                "dispatch_functions_ndr",
+                "type_format",
+                "proc_header",
+                "format_offsets",
+            ]
+            .map(|field| format_ident!("{field}")),
+        );
+    }
+    if syntaxes.has_ndr64() {
+        metadata_fields.extend(
+            [
+                "dispatch_table_ndr64",
+                "dispatch_functions_ndr64",
+                "ndr64_type_format",
+                "ndr64_proc_buffer",
+                "ndr64_proc_table",
+            ]
+            .map(|field| format_ident!("{field}")),
+        );
+    }
+    let legacy_check = (syntaxes == TransferSyntaxes::Ndr64).then(|| {
+        quote! {
+            assert!(
+                compatibility != windows_rpc::InterfaceCompatibility::Legacy,
+                "NDR64-only interfaces cannot use the Legacy preset",
+            );
+        }
+    });
+
+    // Generate components
+    let server_trait = generate_server_trait(interface);
+    let wrapper_functions = generate_wrapper_functions(interface, false);
+    let dispatch_table_init = generate_dispatch_table_init(interface);
+    let server_routine_table = generate_server_routine_table(interface);
+
+    // Servers of older versions, registered alongside to serve older clients
+    let compat_fields = generate_server_fields(interface);
+    let compat_fields_init = generate_server_fields_init(interface);
+    let compat_set_compatibility = generate_server_forward(interface, |server| {
+        quote! { #server.set_compatibility(compatibility); }
+    });
+    let compat_set_security_descriptor = generate_server_forward(interface, |server| {
+        quote! { #server.set_security_descriptor(security_descriptor.clone()); }
+    });
+    let compat_share_security_callback = generate_server_forward(interface, |server| {
+        quote! { #server.share_security_callback(security_callback.clone()); }
+    });
+    let compat_set_require_encryption = generate_server_forward(interface, |server| {
+        quote! { #server.set_require_encryption(require_encryption); }
+    });
+    let compat_set_registration_flags = generate_server_forward(interface, |server| {
+        quote! { #server.set_registration_flags(flags); }
+    });
+    let compat_set_manager_type = generate_server_forward(interface, |server| {
+        quote! { #server.set_manager_type(manager_type); }
+    });
+    let compat_set_autolisten = generate_server_forward(interface, |server| {
+        quote! { #server.set_autolisten(autolisten); }
+    });
+    let compat_set_server_options = generate_server_forward(interface, |server| {
+        quote! { #server.set_server_options(options); }
+    });
+    let compat_set_protocol_sequence = generate_server_forward(interface, |server| {
+        quote! { #server.set_protocol_sequence(protocol); }
+    });
+    let compat_register = generate_server_forward(interface, |server| {
+        quote! { #server.register(endpoint)?; }
+    });
+    let compat_add_endpoint = generate_server_forward(interface, |server| {
+        quote! { #server.add_endpoint(endpoint)?; }
+    });
+    let compat_add_protocol_endpoint = generate_server_forward(interface, |server| {
+        quote! { #server.add_protocol_endpoint(protocol, endpoint)?; }
+    });
+    let compat_remove_endpoint = generate_server_forward(interface, |server| {
+        quote! { #server.remove_endpoint(endpoint)?; }
+    });
+    let compat_set_on_drop = generate_server_forward(interface, |server| {
+        quote! { #server.set_on_drop(on_drop); }
+    });
+    let compat_stop = generate_server_forward(interface, |server| {
+        quote! { let _ = #server.stop(); }
+    });
+
+    // `RpcServer` pairs the server with a client created from a binding, which generic
+    // handle clients are not
+    let rpc_server_impl = if interface.generic_handle.is_some() {
+        quote! {}
+    } else {
+        quote! {
+            impl<T: #trait_name> windows_rpc::interface::RpcServer for #rpc_server_name<T> {
+                type Client = #rpc_client_name;
+                type Impl = T;
+
+                fn new() -> Self {
+                    Self::new()
+                }
+
+                fn with_instance(instance: std::sync::Arc<T>) -> Self
+                where
+                    T: std::marker::Send + std::marker::Sync + 'static,
+                {
+                    Self::with_instance(instance)
+                }
+
+                fn set_protocol_sequence(&mut self, protocol: windows_rpc::ProtocolSequence) {
+                    Self::set_protocol_sequence(self, protocol)
+                }
+
+                fn set_endpoint_mode(&mut self, mode: windows_rpc::server_binding::EndpointMode) {
+                    Self::set_endpoint_mode(self, mode)
+                }
+
+                fn register(&mut self, endpoint: &str) -> std::result::Result<(), windows::core::Error> {
+                    Self::register(self, endpoint)
+                }
+
+                fn listen_async(&self) -> std::result::Result<(), windows::core::Error> {
+                    Self::listen_async(self)
+                }
+
+                fn stop(&self) -> std::result::Result<(), windows::core::Error> {
+                    Self::stop(self)
+                }
+            }
+        }
+    };
+
+    quote! {
+        #server_trait
+
+        pub struct #rpc_server_name<T: #trait_name> {
+            // RPC metadata structures
+            server_interface: std::mem::ManuallyDrop<std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_SERVER_INTERFACE>>,
+            server_info: std::mem::ManuallyDrop<std::boxed::Box<windows_sys::Win32::System::Rpc::MIDL_SERVER_INFO>>,
+            stub_desc: std::mem::ManuallyDrop<std::boxed::Box<windows_sys::Win32::System::Rpc::MIDL_STUB_DESC>>,
+            server_routines: std::mem::ManuallyDrop<std::boxed::Box<[windows_sys::Win32::System::Rpc::SERVER_ROUTINE; #method_count]>>,
+            syntax_info_array: std::mem::ManuallyDrop<std::boxed::Box<[windows_sys::Win32::System::Rpc::MIDL_SYNTAX_INFO; #syntax_info_len]>>,
+            rpc_transfer_syntax_ndr: std::mem::ManuallyDrop<std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER>>,
+            rpc_transfer_syntax_ndr64: std::mem::ManuallyDrop<std::boxed::Box<windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER>>,
+
+            // Format strings
+            #ndr_fields
+            #ndr64_fields
+            auto_bind_handle: std::mem::ManuallyDrop<std::boxed::Box<*mut std::ffi::c_void>>,
+
+            // Server state
+            state: std::sync::Arc<windows_rpc::dispatch::ServerState>,
+            binding: std::option::Option<windows_rpc::server_binding::ServerBinding>,
+            compatibility: windows_rpc::InterfaceCompatibility,
+            security_descriptor: std::option::Option<windows_rpc::security::SecurityDescriptor>,
+            security_callback: std::option::Option<std::sync::Arc<dyn windows_rpc::security::SecurityCallback>>,
+            endpoint_security: std::option::Option<windows_rpc::security::SecurityDescriptor>,
+            require_encryption: bool,
+            options: windows_rpc::server_binding::ServerOptions,
+            autolisten: bool,
+            registration_flags: u32,
+            manager_type: u128,
+            protocol: windows_rpc::ProtocolSequence,
+            endpoint_mode: windows_rpc::server_binding::EndpointMode,
+            on_drop: windows_rpc::server_binding::OnDrop,
+            #compat_fields
+            _phantom: std::marker::PhantomData<T>,
+        }
+
+        // The wrappers take as many parameters as the interface declares
+        #[allow(clippy::too_many_arguments)]
+        impl<T: #trait_name> #rpc_server_name<T> {
+            /// The interface identifier.
+            pub const GUID: u128 = #interface_guid;
+            /// The interface version, as `(major, minor)`.
+            pub const VERSION: (u16, u16) = (#interface_version_major, #interface_version_minor);
+            /// The definition of the interface, for external tooling.
+            pub const METADATA: windows_rpc::metadata::InterfaceMetadata = #rpc_client_name::METADATA;
+            /// The MIDL definition of the interface, for compiling matching C/C++ stubs.
+            pub const IDL: &'static str = #rpc_client_name::IDL;
+            /// The C header of the interface, declaring the procedures of the client stubs MIDL
+            /// generates from [`Self::IDL`].
+            pub const HEADER: &'static str = #rpc_client_name::HEADER;
+            /// Stable textual identifier of the interface, the GUID followed by the version,
+            /// for logs and metrics labels.
+            pub const INTERFACE_ID: &'static str = #rpc_client_name::INTERFACE_ID;
+
+            /// Returns the interface identifier and version, as `(guid, major, minor)`.
+            pub fn interface_id() -> (windows::core::GUID, u16, u16) {
+                #rpc_client_name::interface_id()
+            }
+
+            #wrapper_functions
+
+            pub fn new() -> Self {
+                let mut auto_bind_handle = std::boxed::Box::new(std::ptr::null_mut());
+
+                let compatibility = windows_rpc::InterfaceCompatibility::default();
+
+                // Create transfer syntax identifiers
+                let rpc_transfer_syntax_ndr = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                    SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR_GUID),
+                    SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                        MajorVersion: 2,
+                        MinorVersion: 0,
+                    },
+                });
+
+                let rpc_transfer_syntax_ndr64 = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                    SyntaxGUID: windows_sys::core::GUID::from_u128(#RPC_TRANSFER_SYNTAX_NDR64_GUID),
+                    SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                        MajorVersion: 1,
+                        MinorVersion: 0,
+                    },
+                });
+
+                // Create dispatch tables and routine tables
+                #dispatch_table_init
+                #server_routine_table
+
+                // Initialize format strings
+                #ndr_init
+                #ndr64_init
+
+                // Create syntax info array
+                let mut syntax_info_array = std::boxed::Box::new([
+                    #ndr_syntax_info
+                    #ndr64_syntax_info
+                ]);
+
+                // Create stub desc
+                let mut stub_desc = std::boxed::Box::new(windows_sys::Win32::System::Rpc::MIDL_STUB_DESC {
+                    // Will be filled later
+                    RpcInterfaceInformation: std::ptr::null_mut(),
+                    pfnAllocate: std::option::Option::Some(windows_rpc::alloc::midl_alloc),
+                    pfnFree: std::option::Option::Some(windows_rpc::alloc::midl_free),
+                    IMPLICIT_HANDLE_INFO: windows_sys::Win32::System::Rpc::MIDL_STUB_DESC_0 {
+                        pAutoHandle: &raw mut *auto_bind_handle,
+                    },
+                    apfnNdrRundownRoutines: windows_rpc::context::RUNDOWN_ROUTINES.as_ptr(),
+                    aGenericBindingRoutinePairs: std::ptr::null(),
+                    apfnExprEval: std::ptr::null(),
+                    aXmitQuintuple: std::ptr::null(),
+                    pFormatTypes: #format_types,
+                    fCheckBounds: #MIDL_STUB_DESC_CHECK_BOUNDS as _,
+                    Version: #MIDL_STUB_DESC_VERSION as _,
+                    pMallocFreeStruct: std::ptr::null_mut(),
+                    MIDLVersion: #MIDL_STUB_DESC_MIDL_VERSION as _,
+                    CommFaultOffsets: std::ptr::null(),
+                    aUserMarshalQuadruple: std::ptr::null(),
+                    NotifyRoutineTable: std::ptr::null(),
+                    mFlags: #flags_compatibility.stub_desc_flags() as _,
+                    CsRoutineTables: std::ptr::null(),
+                    // Will be filled later
+                    ProxyServerInfo: std::ptr::null_mut(),
+                    pExprInfo: std::ptr::null(),
+                });
+
+                // Create server info
+                let mut server_info = std::boxed::Box::new(windows_sys::Win32::System::Rpc::MIDL_SERVER_INFO {
+                    pStubDesc: &raw mut *stub_desc,
+                    DispatchTable: server_routines.as_ptr() as _,
+                    ProcString: #proc_string,
+                    FmtStringOffset: #fmt_string_offset,
+                    ThunkTable: std::ptr::null(),
+                    pTransferSyntax: #transfer_syntax as *mut _,
+                    nCount: #syntax_count as _,
+                    pSyntaxInfo: syntax_info_array.as_mut_ptr(),
+                });
+
+                // Create server interface
+                let interface_guid_u128 = #interface_guid_name.to_u128();
+                let mut server_interface = std::boxed::Box::new(windows_sys::Win32::System::Rpc::RPC_SERVER_INTERFACE {
+                    Length: std::mem::size_of::<windows_sys::Win32::System::Rpc::RPC_SERVER_INTERFACE>() as u32,
+                    InterfaceId: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(interface_guid_u128),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: #interface_version_major,
+                            MinorVersion: #interface_version_minor,
+                        },
+                    },
+                    TransferSyntax: windows_sys::Win32::System::Rpc::RPC_SYNTAX_IDENTIFIER {
+                        SyntaxGUID: windows_sys::core::GUID::from_u128(#server_syntax_guid),
+                        SyntaxVersion: windows_sys::Win32::System::Rpc::RPC_VERSION {
+                            MajorVersion: #server_syntax_major,
+                            MinorVersion: 0,
+                        },
+                    },
+                    DispatchTable: #dispatch_table,
+                    RpcProtseqEndpointCount: 0,
+                    RpcProtseqEndpoint: std::ptr::null_mut(),
+                    DefaultManagerEpv: std::ptr::null_mut(),
+                    InterpreterInfo: &raw const *server_info as *const _ as *const _,
+                    Flags: #flags_compatibility.server_interface_flags() #pipe_flags,
+                });
+
+                // Fixup circular references
+                #ndr_fixup
+                #ndr64_fixup
+                stub_desc.RpcInterfaceInformation = &raw mut *server_interface as *mut _ as *mut _;
+                stub_desc.ProxyServerInfo = &raw mut *server_info as _;
+
+                let state = std::sync::Arc::new(windows_rpc::dispatch::ServerState::default());
+                windows_rpc::dispatch::register_state(
+                    &raw const *server_interface as *const std::ffi::c_void,
+                    state.clone(),
+                );
+                // The routines are registered as the manager's entry-point vector
+                windows_rpc::dispatch::register_state(
+                    server_routines.as_ptr() as *const std::ffi::c_void,
+                    state.clone(),
+                );
+
+                Self {
+                    server_interface: std::mem::ManuallyDrop::new(server_interface),
+                    server_info: std::mem::ManuallyDrop::new(server_info),
+                    stub_desc: std::mem::ManuallyDrop::new(stub_desc),
+                    server_routines: std::mem::ManuallyDrop::new(server_routines),
+                    syntax_info_array: std::mem::ManuallyDrop::new(syntax_info_array),
+                    rpc_transfer_syntax_ndr: std::mem::ManuallyDrop::new(rpc_transfer_syntax_ndr),
+                    rpc_transfer_syntax_ndr64: std::mem::ManuallyDrop::new(rpc_transfer_syntax_ndr64),
+                    #ndr_self_init
+                    #ndr64_self_init
+                    auto_bind_handle: std::mem::ManuallyDrop::new(auto_bind_handle),
+                    // Before `state` is moved in
+                    #compat_fields_init
+                    state,
+                    binding: std::option::Option::None,
+                    compatibility,
+                    security_descriptor: std::option::Option::None,
+                    security_callback: std::option::Option::None,
+                    endpoint_security: std::option::Option::None,
+                    require_encryption: false,
+                    options: windows_rpc::server_binding::ServerOptions::default(),
+                    autolisten: false,
+                    registration_flags: 0,
+                    manager_type: 0,
+                    protocol: windows_rpc::ProtocolSequence::Alpc,
+                    endpoint_mode: windows_rpc::server_binding::EndpointMode::Exclusive,
+                    on_drop: windows_rpc::server_binding::OnDrop::StopAndWait,
+                    _phantom: std::marker::PhantomData,
+                }
+            }
+
+            /// Creates the server with a shared implementation object, which handlers get
+            /// with `windows_rpc::server::instance::<T>()` while the application keeps using it.
+            pub fn with_instance(instance: std::sync::Arc<T>) -> Self
+            where
+                T: std::marker::Send + std::marker::Sync + 'static,
+            {
+                let server = Self::new();
+                server.state.set_instance(std::option::Option::Some(instance));
+                server
+            }
+
+            /// Runs handler calls on `executor` instead of the RPC runtime's worker threads.
+            pub fn set_executor(&mut self, executor: impl windows_rpc::executor::CallExecutor + 'static) {
+                self.state.set_executor(std::option::Option::Some(std::sync::Arc::new(executor)));
+            }
+
+            /// Consults `authorizer` with the method's opnum and the caller's
+            /// [`CallContext`](windows_rpc::server::CallContext) before each call is dispatched,
+            /// faulting denied calls with `RPC_S_ACCESS_DENIED`.
+            pub fn set_method_authorizer(&mut self, authorizer: impl windows_rpc::security::MethodAuthorizer + 'static) {
+                self.state.set_authorizer(std::option::Option::Some(std::sync::Arc::new(authorizer)));
+            }
+
+            /// Faults calls that make the stubs allocate more than `quota` bytes, bounding the
+            /// memory a single call can consume. `None` removes the limit.
+            pub fn set_memory_quota(&mut self, quota: std::option::Option<usize>) {
+                self.state.set_memory_quota(quota);
+            }
+
+            /// Sets the status that faults calls whose handler panics, which the client
+            /// receives as the error of the call. Defaults to `RPC_S_CALL_FAILED`.
+            pub fn set_panic_fault(&mut self, status: windows::Win32::System::Rpc::RPC_STATUS) {
+                self.state.set_panic_fault(status);
+            }
+
+            /// Sets the interface flag preset. Takes effect on the next [`register()`](Self::register).
+            pub fn set_compatibility(&mut self, compatibility: windows_rpc::InterfaceCompatibility) {
+                #legacy_check
+                self.server_interface.Flags = #flags_compatibility.server_interface_flags() #pipe_flags;
+                self.server_info.nCount = #syntax_count as _;
+                self.stub_desc.mFlags = #flags_compatibility.stub_desc_flags() as _;
+                self.compatibility = compatibility;
+                #compat_set_compatibility
+            }
+
+            /// Restricts the callers of the interface to those granted access by
+            /// `security_descriptor`, independently of other interfaces on the endpoint.
+            /// Takes effect on the next [`register()`](Self::register).
+            pub fn set_security_descriptor(
+                &mut self,
+                security_descriptor: std::option::Option<windows_rpc::security::SecurityDescriptor>,
+            ) {
+                #compat_set_security_descriptor
+                self.security_descriptor = security_descriptor;
+            }
+
+            /// Registers the endpoints with `security_descriptor`, restricting who may connect
+            /// to them at all, for every interface served on them. Only applies to `ncalrpc`
+            /// and `ncacn_np` endpoints created by [`register()`](Self::register).
+            pub fn set_endpoint_security_descriptor(
+                &mut self,
+                security_descriptor: std::option::Option<windows_rpc::security::SecurityDescriptor>,
+            ) {
+                self.endpoint_security = security_descriptor;
+            }
+
+            /// Rejects calls that are not authenticated at the `RPC_C_AUTHN_LEVEL_PKT_PRIVACY`
+            /// level, i.e. not encrypted, with `RPC_S_ACCESS_DENIED`. Takes effect on the next
+            /// [`register()`](Self::register).
+            pub fn set_require_encryption(&mut self, require_encryption: bool) {
+                #compat_set_require_encryption
+                self.require_encryption = require_encryption;
+            }
+
+            /// Consults `callback` before dispatching each call, rejecting the calls it
+            /// returns an error for. Takes effect on the next [`register()`](Self::register).
+            pub fn set_security_callback(
+                &mut self,
+                callback: impl windows_rpc::security::SecurityCallback + 'static,
+            ) {
+                self.share_security_callback(std::option::Option::Some(std::sync::Arc::new(callback)));
+            }
+
+            /// Sets the security callback shared with the servers of older versions.
+            #[doc(hidden)]
+            pub fn share_security_callback(
+                &mut self,
+                security_callback: std::option::Option<std::sync::Arc<dyn windows_rpc::security::SecurityCallback>>,
+            ) {
+                #compat_share_security_callback
+                self.security_callback = security_callback;
+            }
+
+            /// Dispatches calls with `state` instead of the server's own, so that servers
+            /// registered together share their configuration.
+            #[doc(hidden)]
+            pub fn share_state(&mut self, state: std::sync::Arc<windows_rpc::dispatch::ServerState>) {
+                let interface = &raw const **self.server_interface as *const std::ffi::c_void;
+                windows_rpc::dispatch::register_state(interface, state.clone());
+                let epv = self.server_routines.as_ptr() as *const std::ffi::c_void;
+                windows_rpc::dispatch::register_state(epv, state.clone());
+                self.state = state;
+            }
+
+            /// Registers the interface with additional `RPC_IF_*` flags, e.g.
+            /// `RPC_IF_ALLOW_LOCAL_ONLY` to reject remote callers or `RPC_IF_ALLOW_SECURE_ONLY`
+            /// to reject unauthenticated ones. Takes effect on the next
+            /// [`register()`](Self::register).
+            pub fn set_registration_flags(&mut self, flags: u32) {
+                #compat_set_registration_flags
+                self.registration_flags = flags;
+            }
+
+            /// Registers the implementation as the manager of the interface for objects of
+            /// type `manager_type`, so that servers of several implementations can serve
+            /// the interface together on an endpoint. Clients select the implementation by
+            /// the object UUID of their binding, which
+            /// [`set_object_type()`](windows_rpc::server_binding::set_object_type) maps to
+            /// a type. Defaults to the nil type, which handles calls to objects of no other
+            /// registered type. Takes effect on the next [`register()`](Self::register).
+            pub fn set_manager_type(&mut self, manager_type: u128) {
+                #compat_set_manager_type
+                self.manager_type = manager_type;
+            }
+
+            /// Registers the interface with `RPC_IF_AUTOLISTEN`, so that it accepts calls as
+            /// soon as it is registered, without a process-wide listen call, whatever its
+            /// compatibility preset. Takes effect on the next [`register()`](Self::register).
+            pub fn set_autolisten(&mut self, autolisten: bool) {
+                #compat_set_autolisten
+                self.autolisten = autolisten;
+            }
+
+            /// Sets the thread and call limits of the server. Takes effect on the next
+            /// [`register()`](Self::register) and [`listen()`](Self::listen).
+            pub fn set_server_options(&mut self, options: windows_rpc::server_binding::ServerOptions) {
+                #compat_set_server_options
+                self.options = options;
+            }
+
+            /// Sets the protocol sequence [`register()`](Self::register) serves the interface
+            /// on, e.g. `ProtocolSequence::NamedPipe` with `\pipe\name` endpoints.
+            /// Defaults to `ProtocolSequence::Alpc`.
+            pub fn set_protocol_sequence(&mut self, protocol: windows_rpc::ProtocolSequence) {
+                #compat_set_protocol_sequence
+                self.protocol = protocol;
+            }
+
+            /// Sets how [`register()`](Self::register) handles an endpoint that is already
+            /// registered in the process. Defaults to `EndpointMode::Exclusive`.
+            pub fn set_endpoint_mode(&mut self, mode: windows_rpc::server_binding::EndpointMode) {
+                self.endpoint_mode = mode;
+            }
+
+            pub fn register(&mut self, endpoint: &str) -> std::result::Result<(), windows::core::Error> {
+                // The endpoint is registered by this server, which the older versions reuse
+                let mut binding = windows_rpc::server_binding::ServerBinding::with_endpoint_security(
+                    self.protocol,
+                    endpoint,
+                    &raw const **self.server_interface as *const _ as *const std::ffi::c_void,
+                    self.endpoint_mode,
+                    self.endpoint_security.clone(),
+                )?;
+                binding.set_autolisten(
+                    self.autolisten || self.compatibility == windows_rpc::InterfaceCompatibility::AutoListen,
+                );
+                binding.set_security_descriptor(self.security_descriptor.clone());
+                binding.set_security_callback(self.security_callback.clone());
+                binding.set_require_encryption(self.require_encryption);
+                binding.set_options(self.options);
+                binding.set_registration_flags(self.registration_flags);
+                binding.set_manager(
+                    self.manager_type,
+                    self.server_routines.as_ptr() as *const std::ffi::c_void,
+                );
+
+                // Older versions go first, as the runtime dispatches a call to the first
+                // registered version compatible with the client's
+                #compat_register
+
+                self.binding = std::option::Option::Some(binding);
+                self.binding.as_mut().unwrap().register()?;
+
+                std::result::Result::Ok(())
+            }
+
+            /// Serves the interface on another endpoint as well, after [`register()`](Self::register).
+            pub fn add_endpoint(&mut self, endpoint: &str) -> std::result::Result<(), windows::core::Error> {
+                let std::option::Option::Some(binding) = &mut self.binding else {
+                    return std::result::Result::Err(windows::core::Error::from_hresult(windows::core::HRESULT(-1)));
+                };
+                binding.add_endpoint(endpoint)?;
+                #compat_add_endpoint
+                std::result::Result::Ok(())
+            }
+
+            /// Serves the interface on an endpoint of another protocol sequence as well, after
+            /// [`register()`](Self::register).
+            pub fn add_protocol_endpoint(
+                &mut self,
+                protocol: windows_rpc::ProtocolSequence,
+                endpoint: &str,
+            ) -> std::result::Result<(), windows::core::Error> {
+                let std::option::Option::Some(binding) = &mut self.binding else {
+                    return std::result::Result::Err(windows::core::Error::from_hresult(windows::core::HRESULT(-1)));
+                };
+                binding.add_protocol_endpoint(protocol, endpoint)?;
+                #compat_add_protocol_endpoint
+                std::result::Result::Ok(())
+            }
+
+            /// Registers the interface on each `(protocol, endpoint)` pair, so that it is
+            /// reachable over several protocol sequences at the same time. The first pair
+            /// replaces the protocol sequence set with
+            /// [`set_protocol_sequence()`](Self::set_protocol_sequence).
+            pub fn register_endpoints(
+                &mut self,
+                endpoints: &[(windows_rpc::ProtocolSequence, &str)],
+            ) -> std::result::Result<(), windows::core::Error> {
+                let [(protocol, endpoint), rest @ ..] = endpoints else {
+                    return std::result::Result::Err(windows::core::Error::from_hresult(
+                        windows::Win32::System::Rpc::RPC_S_NO_ENDPOINT_FOUND.to_hresult(),
+                    ));
+                };
+                self.set_protocol_sequence(*protocol);
+                self.register(endpoint)?;
+                for (protocol, endpoint) in rest {
+                    self.add_protocol_endpoint(*protocol, endpoint)?;
+                }
+                std::result::Result::Ok(())
+            }
+
+            /// Stops serving the interface on `endpoint`, rejecting the calls that arrive on
+            /// it while the other endpoints keep being served.
+            pub fn remove_endpoint(&mut self, endpoint: &str) -> std::result::Result<(), windows::core::Error> {
+                let std::option::Option::Some(binding) = &mut self.binding else {
+                    return std::result::Result::Err(windows::core::Error::from_hresult(windows::core::HRESULT(-1)));
+                };
+                binding.remove_endpoint(endpoint)?;
+                #compat_remove_endpoint
+                std::result::Result::Ok(())
+            }
+
+            /// Returns the endpoints the interface is served on.
+            pub fn endpoints(&self) -> &[std::string::String] {
+                match &self.binding {
+                    std::option::Option::Some(binding) => binding.endpoints(),
+                    std::option::Option::None => &[],
+                }
+            }
+
+            pub fn listen(&self) -> std::result::Result<(), windows::core::Error> {
+                if let std::option::Option::Some(binding) = &self.binding {
+                    binding.listen()
+                } else {
+                    std::result::Result::Err(windows::core::Error::from_hresult(windows::core::HRESULT(-1)))
+                }
+            }
+
+            pub fn listen_async(&self) -> std::result::Result<(), windows::core::Error> {
+                if let std::option::Option::Some(binding) = &self.binding {
+                    binding.listen_async()
+                } else {
+                    std::result::Result::Err(windows::core::Error::from_hresult(windows::core::HRESULT(-1)))
+                }
+            }
+
+            /// Blocks until the server is stopped, by [`stop()`](Self::stop) from another
+            /// thread or by a management call, after [`listen_async()`](Self::listen_async).
+            pub fn wait_until_stopped(&self) -> std::result::Result<(), windows::core::Error> {
+                if let std::option::Option::Some(binding) = &self.binding {
+                    binding.wait_until_stopped()
+                } else {
+                    std::result::Result::Err(windows::core::Error::from_hresult(windows::core::HRESULT(-1)))
+                }
+            }
+
+            /// Completes when the server is stopped, like
+            /// [`wait_until_stopped()`](Self::wait_until_stopped), without blocking an async
+            /// runtime.
+            pub fn wait_until_stopped_async(
+                &self,
+            ) -> windows_rpc::executor::CallFuture<std::result::Result<(), windows::core::Error>> {
+                if let std::option::Option::Some(binding) = &self.binding {
+                    binding.wait_until_stopped_async()
+                } else {
+                    windows_rpc::executor::spawn(std::option::Option::None, || {
+                        std::result::Result::Err(windows::core::Error::from_hresult(windows::core::HRESULT(-1)))
+                    })
+                }
+            }
+
+            /// Sets what dropping the server does. Defaults to `OnDrop::StopAndWait`.
+            pub fn set_on_drop(&mut self, on_drop: windows_rpc::server_binding::OnDrop) {
+                #compat_set_on_drop
+                self.on_drop = on_drop;
+            }
+
+            pub fn stop(&self) -> std::result::Result<(), windows::core::Error> {
+                if let std::option::Option::Some(binding) = &self.binding {
+                    binding.stop()?;
+                }
+                #compat_stop
+                std::result::Result::Ok(())
+            }
+        }
+
+        #rpc_server_impl
+
+        impl<T: #trait_name> std::ops::Drop for #rpc_server_name<T> {
+            fn drop(&mut self) {
+                match self.on_drop {
+                    windows_rpc::server_binding::OnDrop::StopAndWait => {
+                        let _ = self.stop();
+                    }
+                    // Dropping the binding unregisters the interface and gives back its
+                    // listener, without stopping the other servers
+                    windows_rpc::server_binding::OnDrop::Unregister => {}
+                    windows_rpc::server_binding::OnDrop::Nothing if self.binding.is_some() => {
+                        // The runtime keeps using the binding, state and metadata of the
+                        // registered interface
+                        std::mem::forget(self.binding.take());
+                        return;
+                    }
+                    windows_rpc::server_binding::OnDrop::Nothing => {}
+                }
+                windows_rpc::dispatch::unregister_state(
+                    &raw const **self.server_interface as *const std::ffi::c_void,
+                );
+                windows_rpc::dispatch::unregister_state(
+                    self.server_routines.as_ptr() as *const std::ffi::c_void,
+                );
+
+                // The metadata is only freed once the interface is unregistered
+                drop(self.binding.take());
+                unsafe {
+                    #(std::mem::ManuallyDrop::drop(&mut self.#metadata_fields);)*
+                }
+            }
+        }
+    }
+}
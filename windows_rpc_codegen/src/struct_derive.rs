@@ -0,0 +1,614 @@
+//! `#[derive(NdrStruct)]`, describing plain-old-data structs to NDR.
+//!
+//! The struct layout is computed from the field types with the `#[repr(C)]` rules, which
+//! match the NDR alignment of base types, so NDR copies the struct as a block
+//! (`FC_STRUCT`/`FC64_STRUCT`). The layout is checked against the compiler's at compile
+//! time.
+//!
+//! Structs with pointer fields, to strings (`PCWSTR`, `PCSTR`) or to arrays sized by
+//! another field (`*const T` with `#[ndr(size_is(field))]`), can't be copied as a block
+//! by NDR 2.0, whose pointers are smaller on the wire than in memory: they are complex
+//! structs (`FC_BOGUS_STRUCT`), and structs with pointers (`FC64_PSTRUCT`) for NDR64.
+//! Both formats are followed by the pointer layout, describing what each pointer field
+//! points to. `*const u16` and `*const u8` fields are strings with `#[ndr(string)]`, and
+//! arrays with `#[ndr(size_is(field))]`, as the two are sent differently.
+//!
+//! Structs with `Vec<u16>` or `Vec<u8>` fields, which carry the same attributes, aren't
+//! `Copy`: they get a `{Name}Wire` struct with pointers in place of the vectors, which
+//! derives `NdrStruct`, and are converted to it with `RpcTransmittable`, so interfaces
+//! pass them through `transmit_as(Name = NameWire)`.
+
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields};
+
+use crate::constants::{
+    FC_BOGUS_STRUCT, FC_C_CSTRING, FC_C_WSTRING, FC_CARRAY, FC_CORR_FLAGS_EARLY, FC_END, FC_PAD,
+    FC_POINTER, FC_POINTER_CONFORMANCE, FC_SIMPLE_POINTER, FC_STRUCT, FC_STRUCTPAD1, FC_ULONG,
+    FC_UP, NDR64_FC_PSTRUCT, NDR64_FC_STRUCT, NDR64_STRUCTURE_HAS_POINTER_INFO,
+};
+use crate::ndr::ndr_fc_short;
+use crate::types::{BaseType, Type};
+
+pub fn derive_ndr_struct(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrStruct can only be derived for structs",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "NdrStruct cannot be derived for generic structs",
+        ));
+    }
+    let fields = match &data.fields {
+        Fields::Named(fields) => &fields.named,
+        Fields::Unnamed(fields) => &fields.unnamed,
+        Fields::Unit => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "NdrStruct cannot be derived for structs without fields",
+            ));
+        }
+    };
+    if fields.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrStruct cannot be derived for structs without fields",
+        ));
+    }
+    if fields.iter().any(|field| vec_element(&field.ty).is_some()) {
+        return derive_transmitted_struct(&input, fields);
+    }
+    if !has_repr_c(&input) {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrStruct requires `#[repr(C)]`",
+        ));
+    }
+
+    let mut field_types = vec![];
+    for field in fields {
+        let FieldAttributes { size_is, string } = field_attributes(field)?;
+        let field_type = match &field.ty {
+            syn::Type::Ptr(pointer) => {
+                let element = match Type::try_from((*pointer.elem).clone()) {
+                    Ok(Type::Simple(element)) if pointer.const_token.is_some() => element,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &field.ty,
+                            "Array fields must be `*const T` pointers to integers or floating point numbers",
+                        ));
+                    }
+                };
+                if string {
+                    if size_is.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            &field.ty,
+                            "`string` and `size_is` are exclusive, a field is either a string or an array",
+                        ));
+                    }
+                    let field_type = match element {
+                        BaseType::U16 => FieldType::WideString,
+                        BaseType::U8 => FieldType::AnsiString,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                &field.ty,
+                                "`string` is only supported on `*const u16` and `*const u8` fields",
+                            ));
+                        }
+                    };
+                    field_types.push(field_type);
+                    continue;
+                }
+                let Some(size_is) = size_is else {
+                    if matches!(element, BaseType::U16 | BaseType::U8) {
+                        return Err(syn::Error::new_spanned(
+                            &field.ty,
+                            "`*const u16` and `*const u8` fields need `#[ndr(string)]` for strings or `#[ndr(size_is(field))]` for arrays",
+                        ));
+                    }
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "Array fields need `#[ndr(size_is(field))]`, naming the `u32` field holding their length",
+                    ));
+                };
+                // The length is read from a `u32` field of the struct
+                let size_field = fields.iter().position(|field| {
+                    field.ident.as_ref() == Some(&size_is)
+                        && matches!(
+                            Type::try_from(field.ty.clone()),
+                            Ok(Type::Simple(BaseType::U32))
+                        )
+                });
+                let Some(size_field) = size_field else {
+                    return Err(syn::Error::new_spanned(
+                        &size_is,
+                        "`size_is` must name a `u32` field of the struct",
+                    ));
+                };
+                FieldType::Array {
+                    element,
+                    size_field,
+                }
+            }
+            _ if size_is.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "`size_is` is only supported on `*const T` fields",
+                ));
+            }
+            _ if string => {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "`string` is only supported on `*const u16` and `*const u8` fields",
+                ));
+            }
+            ty if is_named(ty, "PCWSTR") => FieldType::WideString,
+            ty if is_named(ty, "PCSTR") => FieldType::AnsiString,
+            ty => match Type::try_from(ty.clone()) {
+                Ok(Type::Simple(base_type)) => FieldType::Simple(base_type),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        &field.ty,
+                        "Only integer, floating point, string (`PCWSTR`, `PCSTR`) and array (`*const T`, `Vec<u16>`, `Vec<u8>`) fields are supported by NdrStruct",
+                    ));
+                }
+            },
+        };
+        field_types.push(field_type);
+    }
+
+    let layout = StructLayout::new(&field_types);
+    let Ok(memory_size) = u16::try_from(layout.size) else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "The struct is too large for NDR",
+        ));
+    };
+    let has_pointers = layout.fields.iter().any(|field| field.r#type.is_pointer());
+    let ndr_format = if has_pointers {
+        layout.bogus_ndr_format(memory_size)
+    } else {
+        layout.ndr_format(memory_size)
+    };
+    let size = layout.size;
+    let ndr64_alignment = (layout.alignment - 1) as u8;
+    let ndr64_size = layout.size as u32;
+    let (ndr64_format_code, ndr64_flags) = if has_pointers {
+        (NDR64_FC_PSTRUCT, NDR64_STRUCTURE_HAS_POINTER_INFO)
+    } else {
+        (NDR64_FC_STRUCT, 0)
+    };
+    let ndr64_pointers = has_pointers.then(|| {
+        let pointers = layout.ndr64_pointers();
+        quote! {
+            const NDR64_POINTERS: &'static [(u32, windows_rpc::ndr::NdrPointee)] = &[#(#pointers),*];
+        }
+    });
+    let name = &input.ident;
+
+    Ok(quote! {
+        const _: () = assert!(
+            std::mem::size_of::<#name>() == #size,
+            "the struct layout does not match its NdrStruct description",
+        );
+
+        unsafe impl windows_rpc::ndr::NdrStruct for #name {
+            const NDR_FORMAT: &'static [u8] = &[#(#ndr_format),*];
+            const NDR64_FORMAT: windows::Win32::System::Rpc::NDR64_STRUCTURE_HEADER_FORMAT =
+                windows::Win32::System::Rpc::NDR64_STRUCTURE_HEADER_FORMAT {
+                    FormatCode: #ndr64_format_code,
+                    Alignment: #ndr64_alignment,
+                    Flags: windows::Win32::System::Rpc::NDR64_STRUCTURE_FLAGS { _bitfield: #ndr64_flags },
+                    Reserve: 0,
+                    MemorySize: #ndr64_size,
+                };
+            #ndr64_pointers
+        }
+    })
+}
+
+fn has_repr_c(input: &DeriveInput) -> bool {
+    let mut repr_c = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        let _ = attr.parse_nested_meta(|meta| {
+            repr_c |= meta.path.is_ident("C");
+            Ok(())
+        });
+    }
+    repr_c
+}
+
+/// The `#[ndr(...)]` attributes of a field
+struct FieldAttributes {
+    /// The field named by `size_is(field)`, holding the length of an array field
+    size_is: Option<syn::Ident>,
+    /// Whether `string` marks the field as a NUL-terminated string
+    string: bool,
+}
+
+fn field_attributes(field: &syn::Field) -> syn::Result<FieldAttributes> {
+    let mut attributes = FieldAttributes {
+        size_is: None,
+        string: false,
+    };
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("ndr"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("size_is") {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                attributes.size_is = Some(content.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("string") {
+                attributes.string = true;
+                Ok(())
+            } else {
+                Err(meta.error("Expected `size_is(field)` or `string`"))
+            }
+        })?;
+    }
+    Ok(attributes)
+}
+
+/// Returns the element type of a `Vec<u16>` or `Vec<u8>` field
+fn vec_element(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Vec" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        syn::GenericArgument::Type(element)
+            if is_named(element, "u16") || is_named(element, "u8") =>
+        {
+            Some(element)
+        }
+        _ => None,
+    }
+}
+
+/// Derives the `{Name}Wire` struct of a struct with `Vec` fields, each sent as a pointer
+/// to its elements, and the `RpcTransmittable` conversion to it. The wire struct points
+/// into the vectors of the struct it was converted from, so it only lives for a call.
+fn derive_transmitted_struct(
+    input: &DeriveInput,
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let wire_name = format_ident!("{}Wire", name);
+    let vis = &input.vis;
+
+    let mut wire_fields = vec![];
+    // Size fields are sent as the length of the vector they size
+    let mut lengths = vec![];
+    let mut to_wire = vec![];
+    let mut from_wire = vec![];
+    for field in fields {
+        let Some(field_name) = &field.ident else {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "NdrStruct needs named fields for `Vec` fields",
+            ));
+        };
+        let Some(element) = vec_element(&field.ty) else {
+            wire_fields.push(field.clone());
+            from_wire.push(quote! { #field_name: wire.#field_name });
+            continue;
+        };
+
+        let FieldAttributes { size_is, string } = field_attributes(field)?;
+        let mut wire_field = field.clone();
+        wire_field.ty = syn::parse_quote! { *const #element };
+        wire_fields.push(wire_field);
+        match (size_is, string) {
+            (Some(size_is), false) => {
+                if lengths.iter().any(|(length, _)| length == &size_is) {
+                    return Err(syn::Error::new_spanned(
+                        &size_is,
+                        "Each `Vec` field needs its own `size_is` field",
+                    ));
+                }
+                lengths.push((size_is.clone(), field_name.clone()));
+                to_wire.push(quote! { #field_name: self.#field_name.as_ptr() });
+                from_wire.push(quote! {
+                    #field_name: unsafe { windows_rpc::ndr::array_field(wire.#field_name, wire.#size_is) }
+                });
+            }
+            (None, true) => {
+                to_wire.push(quote! {
+                    #field_name: windows_rpc::ndr::string_field(&self.#field_name)
+                });
+                from_wire.push(quote! {
+                    #field_name: unsafe { windows_rpc::ndr::string_from_field(wire.#field_name) }
+                });
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "`Vec` fields need either `#[ndr(string)]` for strings or `#[ndr(size_is(field))]` for arrays",
+                ));
+            }
+        }
+    }
+    for field in fields {
+        let field_name = field.ident.as_ref().expect("checked above");
+        if vec_element(&field.ty).is_some() {
+            continue;
+        }
+        match lengths.iter().find(|(length, _)| length == field_name) {
+            Some((_, vector)) => to_wire.push(quote! {
+                #field_name: u32::try_from(self.#vector.len())
+                    .expect("vectors are sent with a 32-bit length")
+            }),
+            None => to_wire.push(quote! { #field_name: self.#field_name }),
+        }
+    }
+
+    let wire_doc = format!(
+        "The form [`{name}`] is sent in, pointing into its vectors. Derived by `NdrStruct`."
+    );
+    let wire_struct: DeriveInput = syn::parse_quote! {
+        #[doc = #wire_doc]
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        #vis struct #wire_name {
+            #(#wire_fields),*
+        }
+    };
+    let wire_impl = derive_ndr_struct(wire_struct.clone())?;
+    // The emitted struct doesn't derive `NdrStruct`, which owns the `ndr` attribute
+    let mut wire_struct = wire_struct;
+    if let Data::Struct(data) = &mut wire_struct.data {
+        for field in data.fields.iter_mut() {
+            field.attrs.retain(|attr| !attr.path().is_ident("ndr"));
+        }
+    }
+
+    Ok(quote! {
+        #wire_struct
+
+        #wire_impl
+
+        impl windows_rpc::RpcTransmittable<#wire_name> for #name {
+            fn to_wire(&self) -> #wire_name {
+                #wire_name {
+                    #(#to_wire),*
+                }
+            }
+
+            /// Copies the vectors out of `wire`, whose pointers must point to as many
+            /// elements as its size fields say, or to NUL-terminated strings, as they do
+            /// when the server receives it.
+            fn from_wire(wire: #wire_name) -> Self {
+                Self {
+                    #(#from_wire),*
+                }
+            }
+        }
+    })
+}
+
+/// Whether `ty` is a path ending with `name`, like `windows::core::PCWSTR`
+fn is_named(ty: &syn::Type, name: &str) -> bool {
+    matches!(ty, syn::Type::Path(path)
+        if path.path.segments.last().is_some_and(|segment| segment.ident == name))
+}
+
+/// Type of a struct field
+#[derive(Clone, Copy, PartialEq)]
+enum FieldType {
+    Simple(BaseType),
+    /// `PCWSTR`, a `[unique, string] wchar_t*`
+    WideString,
+    /// `PCSTR`, a `[unique, string] char*`
+    AnsiString,
+    /// `*const T`, a `[unique, size_is(..)] T*` sized by the `u32` field at `size_field`
+    Array {
+        element: BaseType,
+        size_field: usize,
+    },
+}
+
+impl FieldType {
+    fn is_pointer(self) -> bool {
+        !matches!(self, FieldType::Simple(_))
+    }
+
+    /// Size of the field in memory, which is also its alignment
+    fn size(self) -> usize {
+        match self {
+            FieldType::Simple(base_type) => base_type.size(),
+            // The layout follows the host, like the stack offsets of the procedures
+            FieldType::WideString | FieldType::AnsiString | FieldType::Array { .. } => {
+                std::mem::size_of::<usize>()
+            }
+        }
+    }
+
+    /// Format character of the field in the member layout
+    fn to_fc_value(self) -> u8 {
+        match self {
+            FieldType::Simple(base_type) => base_type.to_fc_value(),
+            FieldType::WideString | FieldType::AnsiString | FieldType::Array { .. } => FC_POINTER,
+        }
+    }
+}
+
+/// A field of a [`StructLayout`]
+struct LayoutField {
+    r#type: FieldType,
+    /// Padding preceding the field
+    padding: usize,
+    offset: usize,
+}
+
+/// `#[repr(C)]` layout of a struct
+struct StructLayout {
+    fields: Vec<LayoutField>,
+    /// Padding following the last field
+    trailing_padding: usize,
+    size: usize,
+    alignment: usize,
+}
+
+impl StructLayout {
+    fn new(field_types: &[FieldType]) -> Self {
+        let mut fields = vec![];
+        let mut offset = 0usize;
+        let mut alignment = 1;
+        for &field_type in field_types {
+            let field_alignment = field_type.size();
+            let padding = offset.next_multiple_of(field_alignment) - offset;
+            offset += padding;
+            fields.push(LayoutField {
+                r#type: field_type,
+                padding,
+                offset,
+            });
+            offset += field_type.size();
+            alignment = alignment.max(field_alignment);
+        }
+        let size = offset.next_multiple_of(alignment);
+        Self {
+            fields,
+            trailing_padding: size - offset,
+            size,
+            alignment,
+        }
+    }
+
+    /// Generates the `FC_STRUCT` fragment, padded to an even length like MIDL does
+    fn ndr_format(&self, memory_size: u16) -> Vec<u8> {
+        let mut format = vec![FC_STRUCT, (self.alignment - 1) as u8];
+        format.extend_from_slice(&memory_size.to_le_bytes());
+        self.push_members(&mut format);
+        format
+    }
+
+    /// Generates the `FC_BOGUS_STRUCT` fragment of a struct with pointers: the member
+    /// layout, then the pointer layout with a description of each `FC_POINTER` member,
+    /// then the arrays they point to. Offsets are relative to their own position, so the
+    /// fragment can be copied anywhere in the type format string.
+    fn bogus_ndr_format(&self, memory_size: u16) -> Vec<u8> {
+        let mut format = vec![FC_BOGUS_STRUCT, (self.alignment - 1) as u8];
+        format.extend_from_slice(&memory_size.to_le_bytes());
+        // No conformant array
+        format.extend_from_slice(&ndr_fc_short(0));
+        // Offset to the pointer layout, which follows the members
+        let pointer_layout_offset = format.len();
+        format.extend_from_slice(&ndr_fc_short(0));
+        self.push_members(&mut format);
+        patch_offset(&mut format, pointer_layout_offset);
+
+        // Unique pointers to conformant strings, or to arrays whose offset is filled once
+        // they are appended
+        let mut arrays = vec![];
+        for field in &self.fields {
+            match field.r#type {
+                FieldType::Simple(_) => {}
+                FieldType::WideString => {
+                    format.extend_from_slice(&[FC_UP, FC_SIMPLE_POINTER, FC_C_WSTRING, FC_PAD])
+                }
+                FieldType::AnsiString => {
+                    format.extend_from_slice(&[FC_UP, FC_SIMPLE_POINTER, FC_C_CSTRING, FC_PAD])
+                }
+                FieldType::Array {
+                    element,
+                    size_field,
+                } => {
+                    format.extend_from_slice(&[FC_UP, 0]);
+                    arrays.push((format.len(), element, self.fields[size_field].offset));
+                    format.extend_from_slice(&ndr_fc_short(0));
+                }
+            }
+        }
+
+        for (offset_position, element, size_offset) in arrays {
+            patch_offset(&mut format, offset_position);
+            // FC_CARRAY, alignment, element size
+            format.push(FC_CARRAY);
+            format.push((element.size() - 1) as u8);
+            format.extend_from_slice(&ndr_fc_short(element.size() as u16));
+            // Conformance descriptor: the `u32` field of the struct holding the pointer,
+            // which is unmarshalled before the array
+            format.push(FC_POINTER_CONFORMANCE | FC_ULONG);
+            format.push(0); // No operator
+            format.extend_from_slice(&ndr_fc_short(size_offset as u16));
+            format.extend_from_slice(&ndr_fc_short(FC_CORR_FLAGS_EARLY));
+            // Element type
+            format.push(element.to_fc_value());
+            format.push(FC_END);
+        }
+        format
+    }
+
+    /// Appends the member layout, padded to an even length like MIDL does
+    fn push_members(&self, format: &mut Vec<u8>) {
+        for field in &self.fields {
+            if field.padding > 0 {
+                format.push(FC_STRUCTPAD1 + (field.padding - 1) as u8);
+            }
+            format.push(field.r#type.to_fc_value());
+        }
+        if self.trailing_padding > 0 {
+            format.push(FC_STRUCTPAD1 + (self.trailing_padding - 1) as u8);
+        }
+        if format.len().is_multiple_of(2) {
+            format.push(FC_PAD);
+        }
+        format.push(FC_END);
+    }
+
+    /// Generates the `NDR64_POINTERS` entries: the offset of each pointer field, and what
+    /// it points to
+    fn ndr64_pointers(&self) -> Vec<proc_macro2::TokenStream> {
+        self.fields
+            .iter()
+            .filter_map(|field| {
+                let offset = field.offset as u32;
+                let pointee = match field.r#type {
+                    FieldType::Simple(_) => return None,
+                    FieldType::WideString => quote! { windows_rpc::ndr::NdrPointee::WideString },
+                    FieldType::AnsiString => quote! { windows_rpc::ndr::NdrPointee::AnsiString },
+                    FieldType::Array {
+                        element,
+                        size_field,
+                    } => {
+                        let format_char = element.to_ndr64_fc_value();
+                        let element_size = element.size() as u32;
+                        let size_offset = self.fields[size_field].offset as u32;
+                        quote! {
+                            windows_rpc::ndr::NdrPointee::Array {
+                                format_char: #format_char,
+                                element_size: #element_size,
+                                size_offset: #size_offset,
+                            }
+                        }
+                    }
+                };
+                Some(quote! { (#offset, #pointee) })
+            })
+            .collect()
+    }
+}
+
+/// Writes the offset from the short at `position` to the end of `format`
+fn patch_offset(format: &mut [u8], position: usize) {
+    let offset = (format.len() - position) as u16;
+    format[position..position + 2].copy_from_slice(&ndr_fc_short(offset));
+}
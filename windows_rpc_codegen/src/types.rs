@@ -0,0 +1,1588 @@
+use quote::{ToTokens, format_ident, quote};
+use syn::Type as SynType;
+
+use crate::constants::*;
+use crate::ndr::{CONTEXT_HANDLE_BUFFER_SIZE, OUT_BASE_TYPE_BUFFER_SIZE};
+
+/// `major.minor` version of an interface
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InterfaceVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+/// Integer and floating point types, transferred as NDR base types
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[repr(u8)]
+pub enum BaseType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    I64,
+    U64,
+    F32,
+    F64,
+}
+
+impl BaseType {
+    pub fn to_fc_value(self) -> u8 {
+        match self {
+            BaseType::U8 => 1,
+            BaseType::I8 => 2,
+            BaseType::U16 => 6,
+            BaseType::I16 => 7,
+            BaseType::U32 => 8,
+            BaseType::I32 => 9,
+            BaseType::I64 => 11,
+            BaseType::U64 => 11,
+            BaseType::F32 => FC_FLOAT,
+            BaseType::F64 => FC_DOUBLE,
+        }
+    }
+
+    /// Size in bytes of a value of this type
+    pub fn size(self) -> usize {
+        match self {
+            BaseType::U8 | BaseType::I8 => 1,
+            BaseType::U16 | BaseType::I16 => 2,
+            BaseType::U32 | BaseType::I32 | BaseType::F32 => 4,
+            BaseType::U64 | BaseType::I64 | BaseType::F64 => 8,
+        }
+    }
+
+    pub fn to_rust_type(self) -> proc_macro2::TokenStream {
+        match self {
+            BaseType::U8 => quote! { u8 },
+            BaseType::I8 => quote! { i8 },
+            BaseType::U16 => quote! { u16 },
+            BaseType::I16 => quote! { i16 },
+            BaseType::U32 => quote! { u32 },
+            BaseType::I32 => quote! { i32 },
+            BaseType::U64 => quote! { u64 },
+            BaseType::I64 => quote! { i64 },
+            BaseType::F32 => quote! { f32 },
+            BaseType::F64 => quote! { f64 },
+        }
+    }
+
+    /// Name of the Rust type
+    pub fn name(self) -> &'static str {
+        match self {
+            BaseType::U8 => "u8",
+            BaseType::I8 => "i8",
+            BaseType::U16 => "u16",
+            BaseType::I16 => "i16",
+            BaseType::U32 => "u32",
+            BaseType::I32 => "i32",
+            BaseType::U64 => "u64",
+            BaseType::I64 => "i64",
+            BaseType::F32 => "f32",
+            BaseType::F64 => "f64",
+        }
+    }
+
+    /// Name of the equivalent MIDL type
+    pub fn idl_name(self) -> &'static str {
+        match self {
+            BaseType::U8 => "byte",
+            BaseType::I8 => "small",
+            BaseType::U16 => "unsigned short",
+            BaseType::I16 => "short",
+            BaseType::U32 => "unsigned long",
+            BaseType::I32 => "long",
+            BaseType::U64 => "unsigned hyper",
+            BaseType::I64 => "hyper",
+            BaseType::F32 => "float",
+            BaseType::F64 => "double",
+        }
+    }
+
+    /// Name of the MIDL pipe type of the type, declared by the generated IDL, e.g.
+    /// `UNSIGNED_LONG_PIPE` for `typedef pipe unsigned long UNSIGNED_LONG_PIPE;`
+    pub fn idl_pipe_name(self) -> String {
+        format!("{}_PIPE", self.idl_name().to_uppercase().replace(' ', "_"))
+    }
+
+    /// Lowest and highest values of the type, for the integers that can be given a range:
+    /// NDR 2.0 range descriptors hold 32-bit bounds
+    pub fn range_bounds(self) -> Option<(i64, i64)> {
+        let bounds = match self {
+            BaseType::U8 => (0, u8::MAX.into()),
+            BaseType::I8 => (i8::MIN.into(), i8::MAX.into()),
+            BaseType::U16 => (0, u16::MAX.into()),
+            BaseType::I16 => (i16::MIN.into(), i16::MAX.into()),
+            BaseType::U32 => (0, u32::MAX.into()),
+            BaseType::I32 => (i32::MIN.into(), i32::MAX.into()),
+            BaseType::U64 | BaseType::I64 | BaseType::F32 | BaseType::F64 => return None,
+        };
+        Some(bounds)
+    }
+
+    /// Format character of an `FC_RANGE`, which the bounds are compared by: unlike
+    /// [`Self::to_fc_value()`], it has the signedness of the type
+    pub fn to_range_fc_value(self) -> u8 {
+        match self {
+            BaseType::U8 => FC_BYTE,
+            BaseType::I8 => FC_SMALL,
+            BaseType::U16 => FC_USHORT,
+            BaseType::I16 => FC_SHORT,
+            BaseType::U32 => FC_ULONG,
+            BaseType::I32 => FC_LONG,
+            _ => unreachable!("only integers of up to 32 bits have ranges"),
+        }
+    }
+
+    /// Range type of an `NDR64_RANGE_FORMAT`, with the signedness of the type
+    pub fn to_ndr64_range_fc_value(self) -> u8 {
+        match self {
+            BaseType::U8 => NDR64_FC_UINT8,
+            BaseType::I8 => NDR64_FC_SIGNED_INT8,
+            BaseType::U16 => NDR64_FC_UINT16,
+            BaseType::I16 => NDR64_FC_INT16,
+            BaseType::U32 => NDR64_FC_UINT32,
+            BaseType::I32 => NDR64_FC_INT32,
+            _ => unreachable!("only integers of up to 32 bits have ranges"),
+        }
+    }
+
+    pub fn to_ndr64_fc_value(self) -> u8 {
+        match self {
+            BaseType::U8 | BaseType::I8 => NDR64_FC_INT8,
+            BaseType::U16 | BaseType::I16 => NDR64_FC_INT16,
+            BaseType::U32 | BaseType::I32 => NDR64_FC_INT32,
+            BaseType::U64 | BaseType::I64 => NDR64_FC_INT64,
+            BaseType::F32 => NDR64_FC_FLOAT32,
+            BaseType::F64 => NDR64_FC_FLOAT64,
+        }
+    }
+}
+
+/// Kind of kernel object a system handle refers to, as named by `system_handle(kind)`:
+/// the `sh_*` values of MIDL's `[system_handle]`
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[repr(u8)]
+pub enum SystemHandleKind {
+    File,
+    Semaphore,
+    Event,
+    Mutex,
+    Process,
+    Token,
+    Section,
+    RegKey,
+    Thread,
+    Composition,
+    Socket,
+    Job,
+    Pipe,
+}
+
+impl SystemHandleKind {
+    const ALL: [SystemHandleKind; 13] = [
+        SystemHandleKind::File,
+        SystemHandleKind::Semaphore,
+        SystemHandleKind::Event,
+        SystemHandleKind::Mutex,
+        SystemHandleKind::Process,
+        SystemHandleKind::Token,
+        SystemHandleKind::Section,
+        SystemHandleKind::RegKey,
+        SystemHandleKind::Thread,
+        SystemHandleKind::Composition,
+        SystemHandleKind::Socket,
+        SystemHandleKind::Job,
+        SystemHandleKind::Pipe,
+    ];
+
+    /// Name of the kind in `system_handle(kind)`
+    pub fn name(self) -> &'static str {
+        match self {
+            SystemHandleKind::File => "file",
+            SystemHandleKind::Semaphore => "semaphore",
+            SystemHandleKind::Event => "event",
+            SystemHandleKind::Mutex => "mutex",
+            SystemHandleKind::Process => "process",
+            SystemHandleKind::Token => "token",
+            SystemHandleKind::Section => "section",
+            SystemHandleKind::RegKey => "reg_key",
+            SystemHandleKind::Thread => "thread",
+            SystemHandleKind::Composition => "composition",
+            SystemHandleKind::Socket => "socket",
+            SystemHandleKind::Job => "job",
+            SystemHandleKind::Pipe => "pipe",
+        }
+    }
+
+    /// Returns the kind named `name` in `system_handle(kind)`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Handle type byte of the `FC_SYSTEM_HANDLE` and `NDR64_SYSTEM_HANDLE_FORMAT`
+    pub fn to_fc_value(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns the kind of the handle type byte `value`
+    pub fn from_fc_value(value: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(value)).copied()
+    }
+
+    /// MIDL attribute declaring a handle of this kind, requesting `access` unless it is 0
+    pub fn idl_attribute(self, access: u32) -> String {
+        match access {
+            0 => format!("system_handle(sh_{})", self.name()),
+            _ => format!("system_handle(sh_{}, {access:#x})", self.name()),
+        }
+    }
+}
+
+/// Rust type a string parameter is taken as, sent the same way whatever it is
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum StringForm {
+    /// `&str`
+    #[default]
+    Str,
+    /// `String`, which the server handler receives without a copy of the decoded string
+    String,
+    /// `&String`
+    StringRef,
+    /// `Cow<'_, str>`, borrowed by the server handler
+    Cow,
+    /// `&OsStr`, converted without loss like the OS string forms below
+    OsStr,
+    /// `OsString`
+    OsString,
+    /// `&Path`
+    Path,
+    /// `PathBuf`
+    PathBuf,
+}
+
+impl StringForm {
+    /// Returns the Rust type of the parameter, as taken by server methods and the client
+    /// trait
+    pub fn to_rust_type(self) -> proc_macro2::TokenStream {
+        match self {
+            StringForm::Str => quote! { &str },
+            StringForm::String => quote! { String },
+            StringForm::StringRef => quote! { &String },
+            StringForm::Cow => quote! { std::borrow::Cow<'_, str> },
+            StringForm::OsStr => quote! { &std::ffi::OsStr },
+            StringForm::OsString => quote! { std::ffi::OsString },
+            StringForm::Path => quote! { &std::path::Path },
+            StringForm::PathBuf => quote! { std::path::PathBuf },
+        }
+    }
+
+    /// Name of the Rust type, as reported in the interface metadata
+    pub fn name(self) -> &'static str {
+        match self {
+            StringForm::Str => "&str",
+            StringForm::String => "String",
+            StringForm::StringRef => "&String",
+            StringForm::Cow => "Cow<'_, str>",
+            StringForm::OsStr => "&OsStr",
+            StringForm::OsString => "OsString",
+            StringForm::Path => "&Path",
+            StringForm::PathBuf => "PathBuf",
+        }
+    }
+
+    /// Whether client methods take the string by value
+    pub fn is_owned(self) -> bool {
+        matches!(
+            self,
+            StringForm::String | StringForm::Cow | StringForm::OsString | StringForm::PathBuf
+        )
+    }
+
+    /// Whether the server decodes the string into an `OsString`, keeping invalid UTF-16
+    pub fn is_os_string(self) -> bool {
+        matches!(
+            self,
+            StringForm::OsStr | StringForm::OsString | StringForm::Path | StringForm::PathBuf
+        )
+    }
+}
+
+/// Primitive types without an NDR mapping, which can't name structs
+const UNSUPPORTED_PRIMITIVES: &[&str] = &["bool", "char", "i128", "u128", "isize", "usize", "str"];
+
+/// Type of a parameter or return value
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum Type {
+    //Pointer(Box<Type>),
+    /// `&str` input parameter or `String` return value
+    String,
+    /// Integer or floating point number
+    Simple(BaseType),
+    /// `&[T]` input parameter, passed as a hidden `u32` length followed by a
+    /// conformant array sized by it, or `Vec<T>` return value, returned through hidden
+    /// `[out]` length and array pointer parameters
+    Slice(BaseType),
+    /// `&U16CStr` input parameter (borrowed by servers without decoding it), or
+    /// `U16CString` return value
+    WideString,
+    /// `&HSTRING` input parameter or `HSTRING` return value
+    HString,
+    /// `&str` input parameter marked `#[rpc(ansi)]`, converted to the ANSI code page and
+    /// passed as a conformant `char` string, for peers declaring it `char*`
+    AnsiString,
+    /// Struct implementing `NdrStruct`, by its path: `&T` input parameter, `&mut T` output
+    /// parameter or `T` return value
+    Struct(String),
+    /// Fieldless enum implementing `NdrEnum`, by its path, declared with `enums(...)`:
+    /// `T` input parameter or return value, passed as a 32-bit integer
+    Enum(String),
+    /// `Vec<String>` return value or `&mut Vec<String>` out parameter, sent back through
+    /// `[out]` length and array pointer parameters like `Vec<T>`, the array holding unique
+    /// pointers to the strings
+    StringVec,
+    /// `Option<T>` input parameter, passed as a unique pointer that is null for `None`, or
+    /// return value, returned through a hidden `[out]` pointer to a unique pointer
+    Optional(BaseType),
+    /// `Option<&str>` input parameter or `Option<String>` return value, passed like
+    /// strings but through unique pointers that are null for `None`
+    OptionalString,
+    /// `Option<&str>` input parameter marked `#[rpc(ansi)]`, passed like ANSI strings but
+    /// through a unique pointer that is null for `None`
+    OptionalAnsiString,
+    /// `BorrowedHandle<'_>` input parameter or `OwnedHandle` return value, declared with
+    /// `#[rpc(system_handle(kind, access))]` and duplicated into the receiving process
+    /// over ALPC. An `access` of 0 keeps the access of the sent handle.
+    SystemHandle { kind: SystemHandleKind, access: u32 },
+    /// `&ContextHandle` input parameter, `#[in_out] &mut ContextHandle` parameter or
+    /// `ContextHandle` return value: a context handle, held by clients as a
+    /// `windows_rpc::context::ContextHandle` and by servers as a `ServerContext`, which
+    /// the server runs down when the client's association tears down
+    ContextHandle,
+    /// `InPipe<T>` or `OutPipe<T>` parameter: an `[in]` or `[out]` pipe of numbers,
+    /// streamed in chunks once the other parameters are sent. Clients pass a
+    /// `windows_rpc::pipe::PipeSource` or `PipeSink` for it, and servers get a
+    /// `PipeReader` or `PipeWriter`.
+    Pipe(BaseType),
+}
+
+impl TryFrom<SynType> for Type {
+    type Error = syn::Error;
+
+    fn try_from(value: syn::Type) -> Result<Self, Self::Error> {
+        // Handle &str (input string parameter)
+        if let SynType::Reference(ref_type) = &value
+            && let SynType::Path(path) = &*ref_type.elem
+            && path.path.is_ident("str")
+        {
+            return Ok(Self::String);
+        }
+
+        // Handle &HSTRING (input string) and HSTRING (return value)
+        let path_type = match &value {
+            SynType::Reference(ref_type) => &*ref_type.elem,
+            _ => &value,
+        };
+        if let SynType::Path(path) = path_type
+            && path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "HSTRING")
+        {
+            return Ok(Self::HString);
+        }
+
+        // Handle &ContextHandle, &mut ContextHandle and ContextHandle (context handles),
+        // whose position in the signature tells how they are passed
+        if let SynType::Path(path) = path_type
+            && path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "ContextHandle")
+        {
+            return Ok(Self::ContextHandle);
+        }
+
+        // Handle InPipe<T> and OutPipe<T> (pipes), whose name tells their direction
+        if let SynType::Path(path) = &value
+            && let Some(segment) = path.path.segments.last()
+            && (segment.ident == "InPipe" || segment.ident == "OutPipe")
+            && let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments
+            && let Some(syn::GenericArgument::Type(element)) = arguments.args.first()
+        {
+            return match Self::try_from(element.clone()) {
+                Ok(Self::Simple(element)) => Ok(Self::Pipe(element)),
+                _ => Err(syn::Error::new_spanned(
+                    element,
+                    "Only pipes of integers and floating point numbers are supported",
+                )),
+            };
+        }
+
+        // Handle &U16CStr (input string borrowed as UTF-16)
+        if let SynType::Reference(ref_type) = &value
+            && let SynType::Path(path) = &*ref_type.elem
+            && path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "U16CStr")
+        {
+            return Ok(Self::WideString);
+        }
+
+        // Handle &[T] (input array)
+        if let SynType::Reference(ref_type) = &value
+            && ref_type.mutability.is_none()
+            && let SynType::Slice(slice) = &*ref_type.elem
+        {
+            return match Self::try_from((*slice.elem).clone()) {
+                Ok(Self::Simple(element)) => Ok(Self::Slice(element)),
+                _ => Err(syn::Error::new_spanned(
+                    &slice.elem,
+                    "Only slices of integers and floating point numbers are supported",
+                )),
+            };
+        }
+
+        // Handle Vec<T> (returned array)
+        if let SynType::Path(path) = &value
+            && let Some(segment) = path.path.segments.last()
+            && segment.ident == "Vec"
+            && let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments
+            && let Some(syn::GenericArgument::Type(element)) = arguments.args.first()
+        {
+            let is_string = matches!(element, SynType::Path(path) if path.path.is_ident("String"));
+            return match Self::try_from(element.clone()) {
+                Ok(Self::Simple(element)) => Ok(Self::Slice(element)),
+                Ok(Self::String) if is_string => Ok(Self::StringVec),
+                _ => Err(syn::Error::new_spanned(
+                    element,
+                    "Only vectors of integers, floating point numbers and `String` are supported",
+                )),
+            };
+        }
+
+        // Handle Option<T> (unique pointer)
+        if let SynType::Path(path) = &value
+            && let Some(segment) = path.path.segments.last()
+            && segment.ident == "Option"
+            && let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments
+            && let Some(syn::GenericArgument::Type(inner)) = arguments.args.first()
+        {
+            let is_str = matches!(inner, SynType::Reference(reference)
+                if matches!(&*reference.elem, SynType::Path(path) if path.path.is_ident("str")));
+            let is_string = matches!(inner, SynType::Path(path) if path.path.is_ident("String"));
+            return match Self::try_from(inner.clone()) {
+                Ok(Self::Simple(base_type)) => Ok(Self::Optional(base_type)),
+                Ok(Self::String) if is_str || is_string => Ok(Self::OptionalString),
+                _ => Err(syn::Error::new_spanned(
+                    inner,
+                    "Only `Option` of numbers, `&str` and `String` is supported",
+                )),
+            };
+        }
+
+        // Handle &T (input struct)
+        if let SynType::Reference(ref_type) = &value
+            && ref_type.mutability.is_none()
+            && let Ok(struct_type @ Self::Struct(_)) = Self::try_from((*ref_type.elem).clone())
+        {
+            return Ok(struct_type);
+        }
+
+        let SynType::Path(path) = &value else {
+            return Err(syn::Error::new_spanned(
+                value.to_token_stream(),
+                "Only functions are allowed on this trait",
+            ));
+        };
+        // Any other path names a struct deriving `NdrStruct`, which is checked when the
+        // generated code is compiled
+        let Some(ident) = path.path.get_ident() else {
+            return Ok(Self::Struct(
+                path.to_token_stream().to_string().replace(' ', ""),
+            ));
+        };
+        // FIXME: for each enum variant?
+        let res = if ident == "u8" {
+            Self::Simple(BaseType::U8)
+        } else if ident == "i8" {
+            Self::Simple(BaseType::I8)
+        } else if ident == "i16" {
+            Self::Simple(BaseType::I16)
+        } else if ident == "u16" {
+            Self::Simple(BaseType::U16)
+        } else if ident == "i32" {
+            Self::Simple(BaseType::I32)
+        } else if ident == "u32" {
+            Self::Simple(BaseType::U32)
+        } else if ident == "i64" {
+            Self::Simple(BaseType::I64)
+        } else if ident == "u64" {
+            Self::Simple(BaseType::U64)
+        } else if ident == "f32" {
+            Self::Simple(BaseType::F32)
+        } else if ident == "f64" {
+            Self::Simple(BaseType::F64)
+        } else if ident == "String" {
+            // String return type (output string)
+            Self::String
+        } else if ident == "U16CString" {
+            // Wide string return type (output string)
+            Self::WideString
+        } else if !UNSUPPORTED_PRIMITIVES
+            .iter()
+            .any(|primitive| ident == primitive)
+        {
+            Self::Struct(ident.to_string())
+        } else {
+            return Err(syn::Error::new_spanned(
+                ident.to_token_stream(),
+                "Unsupported type was used",
+            ));
+        };
+
+        Ok(res)
+    }
+}
+
+impl Type {
+    /// Returns the Rust type for input parameters
+    pub fn to_rust_type(&self) -> proc_macro2::TokenStream {
+        match self {
+            Type::String | Type::AnsiString => quote! { &str },
+            Type::WideString => quote! { &windows_rpc::wstr::U16CStr },
+            Type::HString => quote! { &windows::core::HSTRING },
+            Type::Simple(base_type) => base_type.to_rust_type(),
+            Type::Slice(base_type) => {
+                let element = base_type.to_rust_type();
+                quote! { &[#element] }
+            }
+            Type::Struct(_) => {
+                let path = self.struct_path();
+                quote! { &#path }
+            }
+            Type::Enum(_) => self.struct_path(),
+            Type::Optional(base_type) => {
+                let inner = base_type.to_rust_type();
+                quote! { std::option::Option<#inner> }
+            }
+            Type::OptionalString | Type::OptionalAnsiString => {
+                quote! { std::option::Option<&str> }
+            }
+            Type::SystemHandle { .. } => quote! { std::os::windows::io::BorrowedHandle<'_> },
+            Type::ContextHandle => quote! { &windows_rpc::context::ContextHandle },
+            Type::StringVec => {
+                unreachable!("string vectors are only return values and out parameters")
+            }
+            Type::Pipe(_) => unreachable!("pipes take their Rust type from their direction"),
+        }
+    }
+
+    /// Returns the path of a struct or enum type
+    pub fn struct_path(&self) -> proc_macro2::TokenStream {
+        match self {
+            Type::Struct(path) | Type::Enum(path) => {
+                path.parse().expect("struct paths are parsed from tokens")
+            }
+            _ => unreachable!("not a struct or enum type"),
+        }
+    }
+
+    /// Returns `<T as RpcTransmittable<Wire>>` for the `presented` type `T` sent as this
+    /// number or struct
+    pub fn transmittable(&self, presented: &str) -> proc_macro2::TokenStream {
+        let presented: proc_macro2::TokenStream = presented
+            .parse()
+            .expect("presented paths are parsed from tokens");
+        let wire = match self {
+            Type::Struct(_) => self.struct_path(),
+            _ => self.to_rust_type(),
+        };
+        quote! { <#presented as windows_rpc::RpcTransmittable<#wire>> }
+    }
+
+    /// Resolves a struct path declared with `enums(...)` to an enum
+    pub fn resolve_enum(self, enums: &[String]) -> Self {
+        match self {
+            Type::Struct(path) if enums.contains(&path) => Type::Enum(path),
+            other => other,
+        }
+    }
+
+    // TODO: Consider unifying with `to_rust_type` and add `is_in` `is_out` params
+    /// Returns the Rust type for return values (String instead of &str)
+    pub fn to_rust_return_type(&self) -> proc_macro2::TokenStream {
+        match self {
+            Type::String => quote! { String },
+            Type::WideString => quote! { windows_rpc::wstr::U16CString },
+            Type::HString => quote! { windows::core::HSTRING },
+            Type::Struct(_) => self.struct_path(),
+            Type::Slice(base_type) => {
+                let element = base_type.to_rust_type();
+                quote! { std::vec::Vec<#element> }
+            }
+            Type::StringVec => quote! { std::vec::Vec<String> },
+            Type::OptionalString => quote! { std::option::Option<String> },
+            Type::SystemHandle { .. } => quote! { std::os::windows::io::OwnedHandle },
+            Type::ContextHandle => quote! { windows_rpc::context::ContextHandle },
+            _ => self.to_rust_type(),
+        }
+    }
+
+    pub fn rust_type_to_abi(&self, name: syn::Ident) -> proc_macro2::TokenStream {
+        match self {
+            Type::String | Type::WideString | Type::HString => quote! {
+                windows_rpc::wstr::AsWideStr::to_wide_arg(#name).as_pcwstr().0
+            },
+            Type::AnsiString => quote! { windows_rpc::wstr::to_ansi(#name).as_ptr() },
+            // `f32` is promoted to `f64` by the variadic call, and converted back by the
+            // client stub as the FloatDoubleMask tells it
+            Type::Simple(BaseType::F32) => quote! { #name as f64 },
+            // Integers narrower than `int` are promoted like C's default argument
+            // promotions, which variadic calls require; the stub reads the low bytes
+            Type::Simple(BaseType::U8 | BaseType::U16) => quote! { u32::from(#name) },
+            Type::Simple(BaseType::I8 | BaseType::I16) => quote! { i32::from(#name) },
+            // Other simple types are passed as-is through the ABI
+            Type::Simple(_) => quote! { #name },
+            // The length was checked to fit by the client method, see `generate_method()`
+            Type::Slice(_) => {
+                let length = format_ident!("__{}_length", name);
+                quote! { #length, #name.as_ptr() }
+            }
+            Type::Struct(_) => quote! { std::ptr::from_ref(#name) },
+            Type::Enum(_) => quote! { windows_rpc::ndr::NdrEnum::to_wire(#name) },
+            // Points to the parameter, which outlives the call
+            Type::Optional(_) => {
+                quote! { #name.as_ref().map_or(std::ptr::null(), std::ptr::from_ref) }
+            }
+            // Encoded into a local variable by the client method, see `generate_method()`
+            Type::OptionalString | Type::OptionalAnsiString => {
+                unreachable!("optional strings are encoded before the call")
+            }
+            Type::StringVec => {
+                unreachable!("string vectors are only return values and out parameters")
+            }
+            // Duplicated into the server by the client stub, so the caller keeps its handle
+            Type::SystemHandle { .. } => {
+                quote! { std::os::windows::io::AsRawHandle::as_raw_handle(&#name) }
+            }
+            // Checked to be open by the client method, see `generate_method()`
+            Type::ContextHandle => quote! { #name.as_raw() },
+            // Wrapped by the client method, see `generate_method()`
+            Type::Pipe(_) => unreachable!("pipes are set up before the call"),
+        }
+    }
+
+    /// Names of the Rust and MIDL types, as reported in the interface metadata
+    pub fn metadata_names(&self, is_return: bool) -> (String, String) {
+        const IDL_STRING: &str = "[string] wchar_t*";
+        let (rust_type, idl_type) = match (self, is_return) {
+            (Type::Simple(base_type), _) => (base_type.name(), base_type.idl_name()),
+            (Type::String, false) => ("&str", IDL_STRING),
+            (Type::String, true) => ("String", IDL_STRING),
+            (Type::WideString, false) => ("&U16CStr", IDL_STRING),
+            (Type::WideString, true) => ("U16CString", IDL_STRING),
+            (Type::HString, false) => ("&HSTRING", IDL_STRING),
+            (Type::HString, true) => ("HSTRING", IDL_STRING),
+            (Type::AnsiString, _) => ("&str", "[string] char*"),
+            (Type::Slice(base_type), false) => {
+                return (
+                    format!("&[{}]", base_type.name()),
+                    format!("[size_is(length)] {}*", base_type.idl_name()),
+                );
+            }
+            (Type::Slice(base_type), true) => {
+                return (
+                    format!("Vec<{}>", base_type.name()),
+                    format!("[size_is(, *length)] {}**", base_type.idl_name()),
+                );
+            }
+            (Type::StringVec, _) => ("Vec<String>", "[size_is(, *length)] LPWSTR**"),
+            (Type::Struct(path), false) => return (format!("&{path}"), format!("{path}*")),
+            (Type::Struct(path), true) => return (path.clone(), path.clone()),
+            (Type::Enum(path), _) => return (path.clone(), format!("enum {path}")),
+            (Type::Optional(base_type), _) => {
+                return (
+                    format!("Option<{}>", base_type.name()),
+                    format!("[unique] {}*", base_type.idl_name()),
+                );
+            }
+            (Type::OptionalString, false) => ("Option<&str>", "[unique, string] wchar_t*"),
+            (Type::OptionalString, true) => ("Option<String>", "[unique, string] wchar_t*"),
+            (Type::OptionalAnsiString, _) => ("Option<&str>", "[unique, string] char*"),
+            (Type::SystemHandle { kind, access }, _) => {
+                let rust_type = if is_return {
+                    "OwnedHandle"
+                } else {
+                    "BorrowedHandle<'_>"
+                };
+                return (
+                    rust_type.to_string(),
+                    format!("[{}] HANDLE", kind.idl_attribute(*access)),
+                );
+            }
+            (Type::ContextHandle, false) => ("&ContextHandle", "[context_handle] void*"),
+            (Type::ContextHandle, true) => ("ContextHandle", "[context_handle] void*"),
+            (Type::Pipe(element), _) => {
+                return (
+                    format!("InPipe<{}>", element.name()),
+                    element.idl_pipe_name(),
+                );
+            }
+        };
+        (rust_type.to_string(), idl_type.to_string())
+    }
+
+    /// Type that NDR64 transfers enums as: NDR64 has no 16-bit enums
+    pub fn ndr64_enum() -> Type {
+        Type::Simple(BaseType::I32)
+    }
+
+    /// Whether the type is passed as a null-terminated wide string
+    pub fn is_string(&self) -> bool {
+        matches!(self, Type::String | Type::WideString | Type::HString)
+    }
+
+    /// Request buffer space reserved for an `[in]` value of the type: a base type or enum
+    /// with its worst-case alignment padding, or the hidden length of a slice. Values of
+    /// variable size are sized by the client stub instead.
+    pub fn constant_buffer_size(&self) -> usize {
+        match self {
+            Type::Simple(base_type) => 2 * base_type.size(),
+            Type::Enum(_) | Type::Slice(_) => 2 * BaseType::U32.size(),
+            Type::ContextHandle => CONTEXT_HANDLE_BUFFER_SIZE,
+            _ => 0,
+        }
+    }
+}
+
+/// Parameter of a method
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct Parameter {
+    pub r#type: Type,
+    pub name: String,
+    /// Whether the parameter is sent to the server; `false` with `is_out` for `&mut T`
+    pub is_in: bool,
+    /// Whether the parameter is a `&mut T` sent back to the client, only supported for
+    /// numbers, structs, slices and string vectors
+    pub is_out: bool,
+    /// Interface version that added the parameter, for trailing `#[since]` parameters
+    pub since: Option<InterfaceVersion>,
+    /// Value servers use for the parameter when called by older clients, as tokens
+    pub default: Option<String>,
+    /// `#[rpc(range(min, max))]`: inclusive bounds of an `[in]` integer, or of the length
+    /// of a slice, outside of which the server stub rejects the call
+    pub range: Option<(i64, i64)>,
+    /// `#[rpc(size_is(length))]`: name of the `u32` parameter holding the number of
+    /// elements of a slice, which then has no hidden length
+    pub size_is: Option<String>,
+    /// `#[rpc(ptr)]`: the option or struct is passed through a full pointer, which the
+    /// stubs look up in the call's pointer table, so aliased values are sent once
+    pub full_pointer: bool,
+    /// Path of the type taken by value instead of `r#type`, declared with
+    /// `transmit_as(...)` and converted to and from it with `RpcTransmittable`
+    pub presented: Option<String>,
+    /// Rust type a `Type::String` parameter is taken as, in place of `&str`
+    pub string_form: StringForm,
+}
+
+impl Parameter {
+    /// Creates an `[in]` parameter
+    pub fn new(name: impl Into<String>, r#type: Type) -> Self {
+        Parameter {
+            r#type,
+            name: name.into(),
+            is_in: true,
+            is_out: false,
+            since: None,
+            default: None,
+            range: None,
+            size_is: None,
+            full_pointer: false,
+            presented: None,
+            string_form: StringForm::Str,
+        }
+    }
+
+    /// Range of an `[in]` integer, checked with an `FC_RANGE` in place of its base type
+    pub fn value_range(&self) -> Option<(i64, i64)> {
+        self.range
+            .filter(|_| !self.is_out && matches!(self.r#type, Type::Simple(_)))
+    }
+
+    /// Range of the length of a slice, checked on its hidden length and its conformance
+    pub fn length_range(&self) -> Option<(i64, i64)> {
+        self.range.filter(|_| matches!(self.r#type, Type::Slice(_)))
+    }
+
+    /// Whether the parameter is a slice preceded by its hidden `u32` length, rather than
+    /// sized by another parameter
+    pub fn has_hidden_length(&self) -> bool {
+        matches!(self.r#type, Type::Slice(_)) && self.size_is.is_none()
+    }
+
+    /// Whether the parameter is a `&mut Vec<String>`, sent back through an `[out]` length
+    /// and an `[out]` array pointer
+    pub fn is_out_string_vec(&self) -> bool {
+        self.is_out && self.r#type == Type::StringVec
+    }
+
+    /// Number of stack slots the parameter occupies in the call ABI, two for a slice
+    /// with a hidden length and for a string vector
+    pub fn abi_slot_count(&self) -> usize {
+        1 + usize::from(self.has_hidden_length() || self.is_out_string_vec())
+    }
+
+    /// Request buffer space reserved for the parameter, see [`Type::constant_buffer_size()`].
+    /// The hidden length of a slice is sent even when the slice is `[out]`.
+    pub fn constant_buffer_size(&self) -> usize {
+        match self.r#type {
+            Type::Slice(_) if !self.has_hidden_length() => 0,
+            Type::Slice(_) => self.r#type.constant_buffer_size(),
+            _ if self.is_in => self.r#type.constant_buffer_size(),
+            _ => 0,
+        }
+    }
+
+    /// Whether the parameter is an `[out]` or `[in, out]` pointer to a base type, taken
+    /// as `&mut T`
+    pub fn is_out_base_type(&self) -> bool {
+        self.is_out && matches!(self.r#type, Type::Simple(_))
+    }
+
+    /// Returns the Rust type of the parameter, as taken by client and server methods
+    pub fn to_rust_type(&self) -> proc_macro2::TokenStream {
+        if let Some(presented) = &self.presented {
+            presented
+                .parse()
+                .expect("presented paths are parsed from tokens")
+        } else if let (Type::Slice(base_type), true) = (&self.r#type, self.is_out) {
+            let element = base_type.to_rust_type();
+            quote! { &mut [#element] }
+        } else if let Type::Pipe(element) = &self.r#type {
+            // Clients stream the elements from a source, or into a sink
+            let element = element.to_rust_type();
+            if self.is_out {
+                quote! { &mut dyn windows_rpc::pipe::PipeSink<#element> }
+            } else {
+                quote! { &mut dyn windows_rpc::pipe::PipeSource<#element> }
+            }
+        } else if self.is_out {
+            let rust_type = self.r#type.to_rust_return_type();
+            quote! { &mut #rust_type }
+        } else if self.r#type == Type::String {
+            self.string_form.to_rust_type()
+        } else {
+            self.r#type.to_rust_type()
+        }
+    }
+
+    /// Returns the Rust type of the parameter as taken by server methods, which only
+    /// differs from [`Self::to_rust_type()`] for context handles and pipes
+    pub fn to_server_rust_type(&self) -> proc_macro2::TokenStream {
+        match (&self.r#type, self.is_out) {
+            (Type::ContextHandle, false) => quote! { &windows_rpc::context::ServerContext },
+            (Type::ContextHandle, true) => quote! { &mut windows_rpc::context::ServerContext },
+            (Type::Pipe(element), false) => {
+                let element = element.to_rust_type();
+                quote! { &mut windows_rpc::pipe::PipeReader<'_, #element> }
+            }
+            (Type::Pipe(element), true) => {
+                let element = element.to_rust_type();
+                quote! { &mut windows_rpc::pipe::PipeWriter<'_, #element> }
+            }
+            _ => self.to_rust_type(),
+        }
+    }
+
+    /// Returns the flags of the `FC_BIND_CONTEXT` of a context handle: `[in]` handles
+    /// can't be null, while `[in, out]` ones are passed by pointer and are null once closed
+    pub fn context_flags(&self) -> u8 {
+        if self.is_out {
+            NDR_CONTEXT_HANDLE_IS_VIA_PTR | NDR_CONTEXT_HANDLE_IS_IN | NDR_CONTEXT_HANDLE_IS_OUT
+        } else {
+            NDR_CONTEXT_HANDLE_IS_IN | NDR_CONTEXT_HANDLE_CANNOT_BE_NULL
+        }
+    }
+
+    /// Names of the Rust and MIDL types of the parameter, as reported in the interface
+    /// metadata
+    pub fn metadata_names(&self) -> (String, String) {
+        if let Some(presented) = &self.presented {
+            let (_, idl_type) = self.r#type.metadata_names(false);
+            (presented.clone(), idl_type)
+        } else if let (Type::Pipe(element), true) = (&self.r#type, self.is_out) {
+            // `[out]` pipes are passed by pointer
+            (
+                format!("OutPipe<{}>", element.name()),
+                format!("{}*", element.idl_pipe_name()),
+            )
+        } else if let Type::Slice(base_type) = &self.r#type {
+            let reference = if self.is_out { "&mut " } else { "&" };
+            let length = self.size_is.as_deref().unwrap_or("length");
+            (
+                format!("{reference}[{}]", base_type.name()),
+                format!("[size_is({length})] {}*", base_type.idl_name()),
+            )
+        } else if self.is_out_string_vec() {
+            // Declared like the return value, whose type already points to the array
+            let (rust_type, idl_type) = self.r#type.metadata_names(true);
+            (format!("&mut {rust_type}"), idl_type)
+        } else if self.is_out {
+            let (rust_type, idl_type) = self.r#type.metadata_names(true);
+            (format!("&mut {rust_type}"), format!("{idl_type}*"))
+        } else if let Some((min, max)) = self.value_range() {
+            let (rust_type, idl_type) = self.r#type.metadata_names(false);
+            (rust_type, format!("[range({min}, {max})] {idl_type}"))
+        } else if self.full_pointer {
+            let (rust_type, idl_type) = self.r#type.metadata_names(false);
+            let idl_type = match idl_type.strip_prefix("[unique") {
+                Some(attributes) => format!("[ptr{attributes}"),
+                None => format!("[ptr] {idl_type}"),
+            };
+            (rust_type, idl_type)
+        } else if self.r#type == Type::String {
+            let (_, idl_type) = self.r#type.metadata_names(false);
+            (self.string_form.name().to_string(), idl_type)
+        } else {
+            self.r#type.metadata_names(false)
+        }
+    }
+
+    /// Generates the [PARAM_ATTRIBUTES](https://learn.microsoft.com/en-us/windows/win32/rpc/parameter-descriptors#the-oif-parameter-descriptors)
+    pub fn param_attributes(&self) -> u16 {
+        let mut attributes = 0;
+        if self.is_in {
+            attributes |= PARAM_ATTRIBUTES_IS_IN;
+        }
+        if self.is_out {
+            attributes |= PARAM_ATTRIBUTES_IS_OUT;
+        }
+
+        match self.r#type {
+            // Full pointers go through the pointer table, so they aren't simple refs either
+            Type::Struct(_) if self.full_pointer => {
+                attributes |= PARAM_ATTRIBUTES_MUST_SIZE | PARAM_ATTRIBUTES_MUST_FREE;
+            }
+            Type::String
+            | Type::WideString
+            | Type::HString
+            | Type::AnsiString
+            | Type::Slice(_)
+            | Type::Struct(_) => {
+                attributes |= PARAM_ATTRIBUTES_MUST_SIZE
+                    | PARAM_ATTRIBUTES_MUST_FREE
+                    | PARAM_ATTRIBUTES_IS_SIMPLE_REF;
+            }
+            Type::Simple(_) if self.is_out => {
+                attributes |= PARAM_ATTRIBUTES_IS_BASE_TYPE | PARAM_ATTRIBUTES_IS_SIMPLE_REF;
+                // The server stub allocates out-only values on its stack and passes a
+                // pointer to them
+                if !self.is_in {
+                    attributes |= PARAM_ATTRIBUTES_SERVER_ALLOC_SIZE_8;
+                }
+            }
+            // Ranged values are described by an `FC_RANGE`, which they are passed by value to
+            Type::Simple(_) if self.range.is_some() => attributes |= PARAM_ATTRIBUTES_IS_BY_VALUE,
+            Type::Simple(_) | Type::Enum(_) => attributes |= PARAM_ATTRIBUTES_IS_BASE_TYPE,
+            // Unique pointers may be null, so they aren't simple refs
+            Type::Optional(_) | Type::OptionalString | Type::OptionalAnsiString => {
+                attributes |= PARAM_ATTRIBUTES_MUST_SIZE | PARAM_ATTRIBUTES_MUST_FREE;
+            }
+            // Handles are passed by value to their `FC_SYSTEM_HANDLE`, and the server
+            // stub closes its duplicate once the call returns
+            Type::SystemHandle { .. } => {
+                attributes |= PARAM_ATTRIBUTES_MUST_SIZE
+                    | PARAM_ATTRIBUTES_MUST_FREE
+                    | PARAM_ATTRIBUTES_IS_BY_VALUE;
+            }
+            // Context handles have a fixed size; `[in, out]` ones point to the handle,
+            // and refer to their `FC_BIND_CONTEXT` directly
+            Type::ContextHandle if self.is_out => attributes |= PARAM_ATTRIBUTES_IS_SIMPLE_REF,
+            Type::ContextHandle => {}
+            // Pipes point to the pipe object the stubs pull or push the elements through,
+            // and refer to their `FC_PIPE` directly
+            Type::Pipe(_) => {
+                attributes |= PARAM_ATTRIBUTES_IS_PIPE | PARAM_ATTRIBUTES_IS_SIMPLE_REF
+            }
+            Type::StringVec => {
+                unreachable!("string vectors are only return values and out parameters")
+            }
+        }
+
+        attributes
+    }
+
+    pub fn ndr64_param_attributes(&self) -> u16 {
+        let mut attributes = 0;
+        if self.is_in {
+            attributes |= NDR64_IS_IN;
+        }
+        if self.is_out {
+            attributes |= NDR64_IS_OUT;
+        }
+
+        match self.r#type {
+            Type::Struct(_) if self.full_pointer => attributes |= NDR64_MUST_SIZE | NDR64_MUST_FREE,
+            Type::String
+            | Type::WideString
+            | Type::HString
+            | Type::AnsiString
+            | Type::Slice(_)
+            | Type::Struct(_) => {
+                // String, array and struct parameters need MustSize, MustFree, and SimpleRef flags
+                attributes |= NDR64_MUST_SIZE | NDR64_MUST_FREE | NDR64_IS_SIMPLE_REF;
+            }
+            Type::Simple(_) if self.is_out => {
+                attributes |= NDR64_IS_BASE_TYPE | NDR64_IS_SIMPLE_REF;
+                if !self.is_in {
+                    attributes |= NDR64_USE_CACHE;
+                }
+            }
+            Type::Simple(_) if self.range.is_some() => attributes |= NDR64_IS_BY_VALUE,
+            Type::Simple(_) | Type::Enum(_) => attributes |= NDR64_IS_BASE_TYPE | NDR64_IS_BY_VALUE,
+            Type::Optional(_) | Type::OptionalString | Type::OptionalAnsiString => {
+                attributes |= NDR64_MUST_SIZE | NDR64_MUST_FREE;
+            }
+            Type::SystemHandle { .. } => {
+                attributes |= NDR64_MUST_SIZE | NDR64_MUST_FREE | NDR64_IS_BY_VALUE;
+            }
+            Type::ContextHandle if self.is_out => attributes |= NDR64_IS_SIMPLE_REF,
+            Type::ContextHandle => {}
+            Type::Pipe(_) => attributes |= NDR64_IS_PIPE | NDR64_IS_SIMPLE_REF,
+            Type::StringVec => {
+                unreachable!("string vectors are only return values and out parameters")
+            }
+        }
+
+        attributes
+    }
+}
+
+/// Method of an interface, a procedure whose opnum is its index in the interface
+#[derive(Default, Clone)]
+pub struct Method {
+    pub return_type: Option<Type>,
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    /// `#[rpc(maybe)]`: the call is sent without waiting for a reply
+    pub maybe: bool,
+    /// `#[rpc(idempotent)]`: the runtime may retry the call
+    pub idempotent: bool,
+    /// `#[rpc(broadcast)]`: the call is sent to every server on the local network
+    pub broadcast: bool,
+    /// Path of the type returned instead of `return_type`, declared with `transmit_as(...)`
+    pub presented_return: Option<String>,
+    /// Error type of a `Result<T, E>` return, `u32` or an enum: the code handlers return
+    /// in place of the value, sent back through a hidden trailing `[out]` parameter
+    pub error: Option<Type>,
+    /// `#[rpc(error_status)]`: the `u32` error is the procedure's `error_status_t` return
+    /// value instead, and the value goes through hidden `[out]` parameters, as in
+    /// Microsoft's `[comm_status, fault_status]` interfaces
+    pub error_status: bool,
+    /// `#[rpc(hresult)]`: as `error_status`, with the `i32` error returned as an `HRESULT`,
+    /// negative on failure
+    pub hresult: bool,
+    /// `#[rpc(async)]`: the procedure is asynchronous, as with MIDL's ACF `[async]`. The
+    /// stubs take its `RPC_ASYNC_STATE` before the binding handle, and the call completes
+    /// with `RpcAsyncCompleteCall` on both sides.
+    pub r#async: bool,
+}
+
+impl Method {
+    /// Returns the `rpc_flags` of the procedure header, the `RPC_NCA_FLAGS_*` the stubs
+    /// put in the `RPC_MESSAGE` of a call.
+    pub fn rpc_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.maybe {
+            flags |= RPC_NCA_FLAGS_MAYBE;
+        }
+        // Broadcast calls are always idempotent
+        if self.idempotent || self.broadcast {
+            flags |= RPC_NCA_FLAGS_IDEMPOTENT;
+        }
+        if self.broadcast {
+            flags |= RPC_NCA_FLAGS_BROADCAST;
+        }
+        flags
+    }
+
+    /// Whether the procedure returns a status, the `error_status_t` of `error_status` methods
+    /// or the `HRESULT` of `hresult` ones, with the value in hidden `[out]` parameters
+    pub fn returns_status(&self) -> bool {
+        self.error_status || self.hresult
+    }
+
+    /// Returns the hidden `[out] unsigned long*` parameter the error code of a method
+    /// returning `Result<T, E>` is sent back through, zero when the call succeeded
+    pub fn error_parameter(&self) -> Option<Parameter> {
+        self.error
+            .as_ref()
+            .filter(|_| !self.returns_status())
+            .map(|_| Parameter {
+                is_in: false,
+                is_out: true,
+                ..Parameter::new(crate::idl::ERROR_NAME, Type::Simple(BaseType::U32))
+            })
+    }
+
+    /// Returns the hidden `[out]` pointer a number returned by an `error_status` or
+    /// `hresult` method is sent back through, as the procedure returns the status
+    pub fn value_parameter(&self) -> Option<Parameter> {
+        match &self.return_type {
+            Some(return_type @ Type::Simple(_)) if self.returns_status() => Some(Parameter {
+                is_in: false,
+                is_out: true,
+                ..Parameter::new(crate::idl::RETURN_NAME, return_type.clone())
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the parameters as sent on the wire: the declared ones, followed by the
+    /// hidden error or value parameter
+    pub fn wire_parameters(&self) -> Vec<Parameter> {
+        self.parameters
+            .iter()
+            .cloned()
+            .chain(self.error_parameter())
+            .chain(self.value_parameter())
+            .collect()
+    }
+
+    /// Returns the stack offset of the binding handle: the first slot, or the second one
+    /// of asynchronous procedures, whose `RPC_ASYNC_STATE` comes first
+    pub fn binding_stack_offset(&self, slot_size: usize) -> u16 {
+        if self.r#async { slot_size as u16 } else { 0 }
+    }
+
+    /// Returns the stack offset of each wire parameter's first slot, and the offset
+    /// following the last parameter (where the return value goes).
+    /// The parameters follow the binding handle.
+    pub fn stack_offsets(&self, slot_size: usize) -> (Vec<u16>, u16) {
+        let mut offset = self.binding_stack_offset(slot_size) as usize + slot_size;
+        let offsets = self
+            .wire_parameters()
+            .iter()
+            .map(|param| {
+                let param_offset = offset as u16;
+                offset += param.abi_slot_count() * slot_size;
+                param_offset
+            })
+            .collect();
+        (offsets, offset as u16)
+    }
+
+    /// Returns the stack offset of the hidden status parameter, through which the client
+    /// stub reports a failed call instead of raising an exception. The client passes it
+    /// last, right after the hidden out parameters of the return value, so it precedes a
+    /// base type return value, which isn't passed.
+    pub fn status_stack_offset(&self, slot_size: usize) -> u16 {
+        self.stack_offsets(slot_size).1 + (slot_size * self.out_return_param_count()) as u16
+    }
+
+    /// Returns the stack offset of the base type returned by value, following all the
+    /// parameters
+    pub fn base_type_return_stack_offset(&self, slot_size: usize) -> u16 {
+        self.status_stack_offset(slot_size) + slot_size as u16
+    }
+
+    /// Returns the size of the procedure's stack: the parameters, the hidden status
+    /// parameter and the return value
+    pub fn stack_size(&self, slot_size: usize) -> u16 {
+        let return_slots = 1 + usize::from(self.has_base_type_return());
+        self.status_stack_offset(slot_size) + (slot_size * return_slots) as u16
+    }
+
+    /// Returns the x64 `FloatDoubleMask` of the procedure: two bits per stack slot, `01`
+    /// for a `float` and `10` for a `double` passed by value, telling the stubs which
+    /// arguments go through floating point registers. It only has room for the first 8
+    /// slots, the first of which holds the binding handle (or the `RPC_ASYNC_STATE`).
+    pub fn float_double_mask(&self) -> u16 {
+        let (offsets, _) = self.stack_offsets(8);
+        let mut mask = 0;
+        for (param, offset) in self.parameters.iter().zip(offsets) {
+            let slot = offset / 8;
+            let bits = match param.r#type {
+                Type::Simple(BaseType::F32) if !param.is_out => 0b01,
+                Type::Simple(BaseType::F64) if !param.is_out => 0b10,
+                _ => continue,
+            };
+            if slot < 8 {
+                mask |= bits << (2 * slot);
+            }
+        }
+        mask
+    }
+
+    /// Returns the names of the `float` and `double` parameters passed by value past the
+    /// slots of the `FloatDoubleMask`. The stubs place them like integers, which ARM64
+    /// passes in the `x` registers left or on the stack rather than in the `v` registers,
+    /// so the server routines receive their bits as integers.
+    pub fn unmasked_float_params(&self) -> Vec<&str> {
+        let (offsets, _) = self.stack_offsets(8);
+        self.parameters
+            .iter()
+            .zip(offsets)
+            .filter(|(param, offset)| {
+                matches!(param.r#type, Type::Simple(BaseType::F32 | BaseType::F64))
+                    && !param.is_out
+                    && offset / 8 >= 8
+            })
+            .map(|(param, _)| param.name.as_str())
+            .collect()
+    }
+
+    /// Number of parameter descriptors, including hidden length parameters
+    pub fn abi_param_count(&self) -> usize {
+        self.wire_parameters()
+            .iter()
+            .map(Parameter::abi_slot_count)
+            .sum()
+    }
+
+    /// Number of `[out]` and `[in, out]` pointers to base types, including the hidden
+    /// error parameter and the lengths of string vector out parameters
+    pub fn out_base_type_count(&self) -> usize {
+        self.wire_parameters()
+            .iter()
+            .filter(|p| p.is_out_base_type() || p.is_out_string_vec())
+            .count()
+    }
+
+    /// Whether the return value is passed back through hidden out parameters
+    pub fn has_out_return(&self) -> bool {
+        self.out_return_param_count() > 0
+    }
+
+    /// Number of hidden out parameters the return value is passed back through: one for
+    /// strings, structs, options, handles and context handles, and a length and an array
+    /// pointer for vectors, of numbers or strings. Numbers returned by status methods are
+    /// hidden parameters of their own, see [`Self::value_parameter()`]
+    pub fn out_return_param_count(&self) -> usize {
+        match &self.return_type {
+            Some(
+                Type::String
+                | Type::WideString
+                | Type::HString
+                | Type::AnsiString
+                | Type::Struct(_)
+                | Type::Optional(_)
+                | Type::OptionalString
+                | Type::OptionalAnsiString
+                | Type::SystemHandle { .. }
+                | Type::ContextHandle,
+            ) => 1,
+            Some(Type::Slice(_) | Type::StringVec) => 2,
+            Some(Type::Simple(_) | Type::Enum(_)) | None => 0,
+            Some(Type::Pipe(_)) => unreachable!("pipes are only parameters"),
+        }
+    }
+
+    /// Returns the base type returned by value: the `error_status_t` of `error_status`
+    /// methods, the `HRESULT` of `hresult` ones, or an integer, floating point number or
+    /// enum return value
+    pub fn base_type_return(&self) -> Option<Type> {
+        match &self.return_type {
+            _ if self.error_status => Some(Type::Simple(BaseType::U32)),
+            _ if self.hresult => Some(Type::Simple(BaseType::I32)),
+            Some(return_type @ (Type::Simple(_) | Type::Enum(_))) => Some(return_type.clone()),
+            _ => None,
+        }
+    }
+
+    /// Whether a base type is returned by value, see [`Self::base_type_return()`]
+    pub fn has_base_type_return(&self) -> bool {
+        self.base_type_return().is_some()
+    }
+
+    /// Whether the return value is a vector, of numbers or strings, whose length the client
+    /// checks
+    pub fn has_slice_return(&self) -> bool {
+        matches!(self.return_type, Some(Type::Slice(_) | Type::StringVec))
+    }
+
+    /// Constant part of the request buffer size, for the `[in]` values of fixed size, so
+    /// requests of only such values aren't sized by the client stub
+    pub fn constant_client_buffer_size(&self) -> usize {
+        self.parameters
+            .iter()
+            .map(Parameter::constant_buffer_size)
+            .sum()
+    }
+
+    /// Constant part of the response buffer size. Out base types, context handles and the
+    /// length of a returned vector are never sized by the server stub, so they are always
+    /// counted, as is a base type return value.
+    pub fn constant_server_buffer_size(&self) -> usize {
+        let base_types = self.out_base_type_count()
+            + usize::from(self.has_slice_return())
+            + usize::from(self.has_base_type_return());
+        base_types * OUT_BASE_TYPE_BUFFER_SIZE
+            + self.out_context_handle_count() * CONTEXT_HANDLE_BUFFER_SIZE
+    }
+
+    /// Number of context handles sent back to the client: `[in, out]` parameters and the
+    /// return value
+    pub fn out_context_handle_count(&self) -> usize {
+        self.parameters
+            .iter()
+            .filter(|p| p.is_out && p.r#type == Type::ContextHandle)
+            .count()
+            + usize::from(self.return_type == Some(Type::ContextHandle))
+    }
+
+    /// Whether a parameter is a context handle
+    pub fn has_context_handle_param(&self) -> bool {
+        self.parameters
+            .iter()
+            .any(|p| p.r#type == Type::ContextHandle)
+    }
+
+    /// Whether a parameter is a pipe, whose elements are streamed after the others
+    pub fn has_pipe_param(&self) -> bool {
+        self.parameters
+            .iter()
+            .any(|p| matches!(p.r#type, Type::Pipe(_)))
+    }
+
+    /// Whether the client stub must size the request, for parameters of variable size
+    pub fn client_must_size(&self) -> bool {
+        self.parameters.iter().any(|p| {
+            p.is_in
+                && (p.r#type.is_string()
+                    || matches!(
+                        p.r#type,
+                        Type::AnsiString
+                            | Type::Slice(_)
+                            | Type::Struct(_)
+                            | Type::Optional(_)
+                            | Type::OptionalString
+                            | Type::OptionalAnsiString
+                            | Type::SystemHandle { .. }
+                    ))
+        })
+    }
+
+    /// Whether the server stub must size the response, for return values and out
+    /// parameters of variable size
+    pub fn server_must_size(&self) -> bool {
+        // Returned context handles have a constant size, like out base types
+        (self.has_out_return() && self.return_type != Some(Type::ContextHandle))
+            || self.parameters.iter().any(|p| {
+                p.is_out && matches!(p.r#type, Type::Struct(_) | Type::Slice(_) | Type::StringVec)
+            })
+    }
+
+    pub fn has_slice_param(&self) -> bool {
+        self.parameters
+            .iter()
+            .any(|p| matches!(p.r#type, Type::Slice(_)))
+    }
+
+    /// Whether a slice is `[out]` or `[in, out]`, or a string vector is `[out]`, whose
+    /// returned size the client checks
+    pub fn has_out_slice_param(&self) -> bool {
+        self.parameters
+            .iter()
+            .any(|p| p.is_out && matches!(p.r#type, Type::Slice(_) | Type::StringVec))
+    }
+
+    /// Returns the index of the parameter holding the length of a `size_is` slice
+    pub fn size_param_index(&self, param: &Parameter) -> Option<usize> {
+        let length = param.size_is.as_ref()?;
+        self.parameters.iter().position(|p| p.name == *length)
+    }
+
+    /// Whether a parameter is a full pointer, for which the stubs set up the pointer table
+    /// of the call
+    pub fn uses_full_pointers(&self) -> bool {
+        self.parameters.iter().any(|p| p.full_pointer)
+    }
+
+    /// Whether a parameter is a system handle, which the caller lends for the call only
+    pub fn has_system_handle_param(&self) -> bool {
+        self.parameters
+            .iter()
+            .any(|p| matches!(p.r#type, Type::SystemHandle { .. }))
+    }
+
+    /// Whether the correlation descriptors of the procedure carry ranges, set when the
+    /// length of a slice parameter has one
+    pub fn has_range_on_conformance(&self) -> bool {
+        self.parameters.iter().any(|p| p.length_range().is_some())
+    }
+}
+
+/// Transfer syntaxes an interface offers, from `transfer_syntax(...)`
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TransferSyntaxes {
+    /// Both NDR 2.0 and NDR64, NDR 2.0 being the first
+    #[default]
+    All,
+    /// Only NDR 2.0
+    Ndr20,
+    /// Only NDR64, which the RPC runtime implements on 64-bit Windows only
+    Ndr64,
+}
+
+impl TransferSyntaxes {
+    /// Whether the NDR 2.0 format strings are generated
+    pub fn has_ndr20(self) -> bool {
+        self != TransferSyntaxes::Ndr64
+    }
+
+    /// Whether the NDR64 format structures are generated
+    pub fn has_ndr64(self) -> bool {
+        self != TransferSyntaxes::Ndr20
+    }
+}
+
+/// An RPC interface, named after the trait it is declared by
+#[derive(Default, Clone)]
+pub struct Interface {
+    /// Name of the trait, which the generated types are named after, e.g. `{name}Client`
+    pub name: String,
+    pub uuid: u128,
+    pub version: InterfaceVersion,
+    pub methods: Vec<Method>,
+    /// `[callback]` procedures, which servers call on the client during one of its calls,
+    /// with opnums of their own
+    pub callbacks: Vec<Method>,
+    /// Type implementing `GenericHandle` that client methods bind through, if any
+    pub generic_handle: Option<syn::Type>,
+    /// Transfer syntaxes the metadata is generated for
+    pub transfer_syntaxes: TransferSyntaxes,
+}
+
+impl Interface {
+    /// Returns the textual identifier of the interface, the canonical GUID followed by the
+    /// version, e.g. `12345678-1234-1234-1234-123456789abc:1.0`
+    pub fn id_string(&self) -> String {
+        let guid = self.uuid;
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}:{}.{}",
+            guid >> 96,
+            (guid >> 80) & 0xffff,
+            (guid >> 64) & 0xffff,
+            (guid >> 48) & 0xffff,
+            guid & 0xffff_ffff_ffff,
+            self.version.major,
+            self.version.minor
+        )
+    }
+
+    /// Returns an iterator over all unique types in the interface (parameters and return types)
+    ///
+    /// Slices are described at runtime, so they are replaced by the types they refer to:
+    /// their element type and the `u32` type of their hidden length (string vectors by
+    /// `Type::String` and `u32`), and options by the
+    /// type they point to. Wide string types share the format of `Type::String`, enums the one of `Type::ndr64_enum()`, structs
+    /// bring their own formats, and handles and context handles are described at runtime.
+    pub fn unique_types(&self) -> impl Iterator<Item = Type> {
+        let mut seen = std::collections::HashSet::new();
+        self.methods
+            .iter()
+            .flat_map(|m| {
+                m.wire_parameters()
+                    .into_iter()
+                    .map(|p| p.r#type)
+                    .chain(m.return_type.clone())
+                    .chain(m.base_type_return())
+            })
+            .flat_map(|t| match &t {
+                Type::Slice(element) => vec![Type::Simple(*element), Type::Simple(BaseType::U32)],
+                Type::Enum(_) => vec![Type::ndr64_enum()],
+                Type::Optional(base_type) | Type::Pipe(base_type) => vec![Type::Simple(*base_type)],
+                Type::WideString | Type::HString | Type::OptionalString => vec![Type::String],
+                Type::StringVec => vec![Type::String, Type::Simple(BaseType::U32)],
+                Type::OptionalAnsiString => vec![Type::AnsiString],
+                Type::Struct(_) | Type::SystemHandle { .. } | Type::ContextHandle => vec![],
+                _ => vec![t],
+            })
+            .filter(move |t| seen.insert(t.clone()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Whether a method has pipes, which the runtime must not read the whole request of
+    /// before dispatching the call
+    pub fn has_pipes(&self) -> bool {
+        self.methods.iter().any(Method::has_pipe_param)
+    }
+
+    /// Returns the older versions that clients and servers keep call paths for, because
+    /// `#[since]` parameters were added after them, in ascending order.
+    ///
+    /// Each version includes the parameters added up to it: the base version `major.0`
+    /// has none, and every `#[since]` version below the interface version adds its own.
+    pub fn compat_versions(&self) -> Vec<InterfaceVersion> {
+        let mut versions: Vec<_> = self
+            .methods
+            .iter()
+            .flat_map(|m| m.parameters.iter().filter_map(|p| p.since))
+            .collect();
+        if versions.is_empty() {
+            return versions;
+        }
+        versions.push(InterfaceVersion {
+            major: self.version.major,
+            minor: 0,
+        });
+        versions.retain(|v| *v < self.version);
+        versions.sort();
+        versions.dedup();
+        versions
+    }
+
+    /// Returns the interface as declared at an older `version`, without the parameters
+    /// added after it. Its generated types are prefixed with `{name}Compat{major}x{minor}`.
+    pub fn at_version(&self, version: InterfaceVersion) -> Interface {
+        let methods = self
+            .methods
+            .iter()
+            .map(|method| Method {
+                parameters: method
+                    .parameters
+                    .iter()
+                    .filter(|p| p.since.is_none_or(|since| since <= version))
+                    // The older version has no call paths of its own
+                    .map(|p| Parameter {
+                        since: None,
+                        default: None,
+                        ..p.clone()
+                    })
+                    .collect(),
+                ..method.clone()
+            })
+            .collect();
+        Interface {
+            name: format!("{}Compat{}x{}", self.name, version.major, version.minor),
+            uuid: self.uuid,
+            version,
+            methods,
+            callbacks: vec![],
+            generic_handle: None,
+            transfer_syntaxes: self.transfer_syntaxes,
+        }
+    }
+
+    /// Returns the callbacks of the interface as an interface of their own, which servers
+    /// call through and clients serve. Its generated types are named `{name}Callbacks...`.
+    pub fn callback_interface(&self) -> Interface {
+        Interface {
+            name: format!("{}Callbacks", self.name),
+            uuid: self.uuid,
+            version: self.version,
+            methods: self.callbacks.clone(),
+            callbacks: vec![],
+            generic_handle: None,
+            transfer_syntaxes: self.transfer_syntaxes,
+        }
+    }
+}
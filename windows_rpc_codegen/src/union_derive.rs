@@ -0,0 +1,235 @@
+//! `#[derive(NdrUnion)]`, describing enums with payloads to NDR.
+//!
+//! A `#[repr(C, u16)]` or `#[repr(C, u32)]` enum is laid out as its discriminant followed
+//! by a union of the variant payloads, which is an encapsulated union
+//! (`FC_ENCAPSULATED_UNION`/`FC64_ENCAPSULATED_UNION`): each variant is an arm selected by
+//! its discriminant. Unknown discriminants are rejected by NDR, so received values are
+//! always valid. The layout is checked against the compiler's at compile time.
+//!
+//! Payloads are integers and floating point numbers, which NDR copies into the arms;
+//! strings are rejected.
+
+use quote::quote;
+use syn::{Data, DeriveInput, Fields};
+
+use crate::constants::{
+    FC_ENCAPSULATED_UNION, FC_UNION_ARM_SIMPLE, FC_UNION_NO_DEFAULT, NDR64_FC_ENCAPSULATED_UNION,
+};
+use crate::ndr::{ndr_fc_long, ndr_fc_short};
+use crate::types::{BaseType, Type};
+
+pub fn derive_ndr_union(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrUnion can only be derived for enums",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "NdrUnion cannot be derived for generic enums",
+        ));
+    }
+    let Some(discriminant_type) = repr_discriminant_type(&input) else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrUnion requires `#[repr(C, u16)]` or `#[repr(C, u32)]`",
+        ));
+    };
+
+    // Each variant with its discriminant and payload
+    let mut arms: Vec<(u32, Option<BaseType>)> = vec![];
+    let mut next_discriminant = 0u32;
+    for variant in &data.variants {
+        let discriminant = match &variant.discriminant {
+            Some((_, expr)) => parse_discriminant(expr)?,
+            None => next_discriminant,
+        };
+        // 16-bit discriminants are sign extended by NDR
+        if discriminant_type == BaseType::U16 && discriminant > i16::MAX as u32 {
+            return Err(syn::Error::new_spanned(
+                &variant.ident,
+                "`#[repr(C, u16)]` discriminants must be below 0x8000",
+            ));
+        }
+        next_discriminant = discriminant.wrapping_add(1);
+
+        let payload = match &variant.fields {
+            Fields::Unit => None,
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let field = &fields.unnamed[0];
+                match Type::try_from(field.ty.clone()) {
+                    Ok(Type::Simple(base_type)) => Some(base_type),
+                    // The arms are unmarshalled in place, where a `#[repr(C)]` enum has no
+                    // room for the pointer and buffer of a string
+                    Ok(payload) if payload.is_string() => {
+                        return Err(syn::Error::new_spanned(
+                            &field.ty,
+                            "String payloads are not supported by NdrUnion, pass strings as separate parameters",
+                        ));
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            &field.ty,
+                            "Only integer and floating point payloads are supported by NdrUnion",
+                        ));
+                    }
+                }
+            }
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &variant.fields,
+                    "NdrUnion variants carry at most one unnamed field",
+                ));
+            }
+        };
+        arms.push((discriminant, payload));
+    }
+    if arms.iter().all(|(_, payload)| payload.is_none()) {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "NdrUnion requires a variant with a payload, derive NdrEnum for fieldless enums",
+        ));
+    }
+
+    let layout = UnionLayout::new(discriminant_type, &arms);
+    let ndr_format = layout.ndr_format(&arms);
+    let ndr64_arms = arms.iter().map(|&(discriminant, payload)| {
+        let case_value = i64::from(discriminant);
+        match payload {
+            Some(base_type) => {
+                let format_char = base_type.to_ndr64_fc_value();
+                quote! { (#case_value, std::option::Option::Some(#format_char)) }
+            }
+            None => quote! { (#case_value, std::option::Option::None) },
+        }
+    });
+    let size = layout.size;
+    let ndr64_alignment = (layout.alignment - 1) as u8;
+    let ndr64_switch_type = discriminant_type.to_ndr64_fc_value();
+    let ndr64_memory_offset = layout.arms_offset as u32;
+    let ndr64_memory_size = layout.size as u32;
+    let name = &input.ident;
+
+    Ok(quote! {
+        const _: () = assert!(
+            std::mem::size_of::<#name>() == #size,
+            "the enum layout does not match its NdrUnion description",
+        );
+
+        unsafe impl windows_rpc::ndr::NdrUnion for #name {
+            const NDR_FORMAT: &'static [u8] = &[#(#ndr_format),*];
+            const NDR64_FORMAT: windows::Win32::System::Rpc::NDR64_ENCAPSULATED_UNION =
+                windows::Win32::System::Rpc::NDR64_ENCAPSULATED_UNION {
+                    FormatCode: #NDR64_FC_ENCAPSULATED_UNION,
+                    Alignment: #ndr64_alignment,
+                    Flags: 0,
+                    SwitchType: #ndr64_switch_type,
+                    MemoryOffset: #ndr64_memory_offset,
+                    MemorySize: #ndr64_memory_size,
+                    Reserved: 0,
+                };
+            const NDR64_ARMS: &'static [(i64, std::option::Option<u8>)] = &[#(#ndr64_arms),*];
+        }
+
+        impl windows_rpc::ndr::NdrType for #name {
+            fn ndr_format() -> &'static [u8] {
+                <Self as windows_rpc::ndr::NdrUnion>::NDR_FORMAT
+            }
+
+            fn ndr64_format() -> *mut core::ffi::c_void {
+                windows_rpc::ndr::ndr64_union_format::<Self>()
+            }
+        }
+    })
+}
+
+/// Returns the discriminant type of a `#[repr(C, u16)]` or `#[repr(C, u32)]` enum
+fn repr_discriminant_type(input: &DeriveInput) -> Option<BaseType> {
+    let mut repr_c = false;
+    let mut discriminant_type = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") {
+                repr_c = true;
+            } else if meta.path.is_ident("u16") {
+                discriminant_type = Some(BaseType::U16);
+            } else if meta.path.is_ident("u32") {
+                discriminant_type = Some(BaseType::U32);
+            }
+            Ok(())
+        });
+    }
+    discriminant_type.filter(|_| repr_c)
+}
+
+/// Parses an explicit discriminant, which must be an integer literal
+fn parse_discriminant(expr: &syn::Expr) -> syn::Result<u32> {
+    if let syn::Expr::Lit(syn::ExprLit {
+        lit: syn::Lit::Int(value),
+        ..
+    }) = expr
+    {
+        return value.base10_parse();
+    }
+    Err(syn::Error::new_spanned(
+        expr,
+        "NdrUnion discriminants must be integer literals",
+    ))
+}
+
+/// `#[repr(C, uN)]` layout of an enum: the discriminant, then the union of the payloads
+struct UnionLayout {
+    discriminant_type: BaseType,
+    /// Offset of the payloads, past the discriminant and its padding
+    arms_offset: usize,
+    size: usize,
+    alignment: usize,
+}
+
+impl UnionLayout {
+    fn new(discriminant_type: BaseType, arms: &[(u32, Option<BaseType>)]) -> Self {
+        // The size of base types is also their alignment
+        let arms_alignment = arms
+            .iter()
+            .filter_map(|(_, payload)| payload.map(BaseType::size))
+            .max()
+            .unwrap_or(1);
+        let arms_offset = discriminant_type.size().next_multiple_of(arms_alignment);
+        let alignment = discriminant_type.size().max(arms_alignment);
+        Self {
+            discriminant_type,
+            arms_offset,
+            size: (arms_offset + arms_alignment).next_multiple_of(alignment),
+            alignment,
+        }
+    }
+
+    /// Generates the `FC_ENCAPSULATED_UNION` fragment
+    fn ndr_format(&self, arms: &[(u32, Option<BaseType>)]) -> Vec<u8> {
+        let mut format = vec![
+            FC_ENCAPSULATED_UNION,
+            // The memory increment from the discriminant to the arms, and the
+            // discriminant type
+            ((self.arms_offset as u8) << 4) | self.discriminant_type.to_fc_value(),
+        ];
+        // Memory size of the arms, with the trailing padding of the enum
+        format.extend_from_slice(&ndr_fc_short((self.size - self.arms_offset) as u16));
+        format.extend_from_slice(&ndr_fc_short(arms.len() as u16));
+        for &(discriminant, payload) in arms {
+            format.extend_from_slice(&ndr_fc_long(discriminant));
+            // Empty arms have no description
+            let description = payload.map_or(0, |base_type| {
+                FC_UNION_ARM_SIMPLE | u16::from(base_type.to_fc_value())
+            });
+            format.extend_from_slice(&ndr_fc_short(description));
+        }
+        format.extend_from_slice(&ndr_fc_short(FC_UNION_NO_DEFAULT));
+        format
+    }
+}
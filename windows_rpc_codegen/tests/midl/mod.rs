@@ -0,0 +1,298 @@
+//! Differential checks of the NDR 2.0 format strings against MIDL's.
+//!
+//! Each `{name}.idl` in this directory is an interface, with the client and server stubs
+//! `{name}_c.c` and `{name}_s.c` midl.exe generates for it as the references. The
+//! interface is imported with `parse_idl_file()` and its proc and type format strings,
+//! procedure offsets and status parameter offsets are compared with the stubs', failing
+//! with a diff of the annotated listings of both.
+//!
+//! The references are generated on Windows, for the 64-bit layout the tests check on
+//! 64-bit hosts, along with an ACF `{name}.acf` adding the `[comm_status, fault_status]`
+//! parameter the generated clients pass their status through:
+//!
+//! ```text
+//! midl /Oicf /env x64 /protocol dce /cstub {name}_c.c /sstub {name}_s.c {name}.idl
+//! ```
+//!
+//! Structs, enums and unions are patched into the format strings when the generated code
+//! runs, so interfaces using them can't be compared.
+
+use std::path::Path;
+
+use windows_rpc_codegen::Interface;
+use windows_rpc_codegen::formats::{annotate_proc_format, annotate_type_format, formats};
+
+/// Lines of unchanged context shown around differences
+const CONTEXT: usize = 3;
+
+/// The NDR 2.0 format strings of a MIDL client or server stub
+pub struct MidlStub {
+    pub proc_format: Vec<u8>,
+    pub type_format: Vec<u8>,
+    pub proc_offsets: Vec<u16>,
+    /// The comm and fault status offsets of each procedure, only in client stubs
+    pub comm_fault_offsets: Vec<(u16, u16)>,
+}
+
+impl MidlStub {
+    /// Parses the `__MIDL_ProcFormatString`, `__MIDL_TypeFormatString`,
+    /// `_FormatStringOffsetTable` and, if any, `_CommFaultOffsets` initializers of a `_c.c`
+    /// or `_s.c` file
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let source = strip_comments(source);
+        let comm_fault_offsets = match initializer(&source, "_CommFaultOffsets") {
+            Ok(offsets) => parse_shorts(&offsets.replace(['{', '}'], " "))?
+                .chunks(2)
+                .map(|pair| match pair {
+                    &[comm, fault] => Ok((comm, fault)),
+                    _ => Err("unpaired comm and fault offsets".to_string()),
+                })
+                .collect::<Result<_, _>>()?,
+            Err(_) => vec![],
+        };
+        Ok(MidlStub {
+            proc_format: parse_format_string(&source, "_MIDL_ProcFormatString")?,
+            type_format: parse_format_string(&source, "_MIDL_TypeFormatString")?,
+            proc_offsets: parse_shorts(initializer(&source, "_FormatStringOffsetTable")?)?,
+            comm_fault_offsets,
+        })
+    }
+
+    /// Reads a reference of the interface file `idl`: the stub `{name}_{kind}.c` next to
+    /// it, `c` for the client and `s` for the server
+    pub fn read(idl: &Path, kind: &str) -> Result<Self, String> {
+        let stem = idl.file_stem().unwrap_or_default().to_string_lossy();
+        let stub = idl.with_file_name(format!("{stem}_{kind}.c"));
+        let source = std::fs::read_to_string(&stub)
+            .map_err(|error| format!("No MIDL reference {}: {error}", stub.display()))?;
+        Self::parse(&source).map_err(|error| format!("{}: {error}", stub.display()))
+    }
+}
+
+/// Compares the format strings generated for `interface` with `stub`, returning the
+/// annotated diffs of those that differ
+pub fn compare(interface: &Interface, stub: &MidlStub) -> Result<(), String> {
+    let formats = formats(interface);
+    if !formats.fixups.is_empty() {
+        return Err(format!(
+            "`{}` uses structs or enums, which are patched in at runtime",
+            interface.name
+        ));
+    }
+
+    let mut report = String::new();
+    if formats.proc_offsets != stub.proc_offsets {
+        report.push_str(&format!(
+            "Procedure offsets differ:\n- {:?}\n+ {:?}\n",
+            stub.proc_offsets, formats.proc_offsets
+        ));
+    }
+    if !stub.comm_fault_offsets.is_empty() {
+        // The status parameter is both the comm and the fault status
+        let offsets: Vec<(u16, u16)> = interface
+            .methods
+            .iter()
+            .map(|method| method.status_stack_offset(8))
+            .map(|offset| (offset, offset))
+            .collect();
+        if offsets != stub.comm_fault_offsets {
+            report.push_str(&format!(
+                "Comm and fault status offsets differ:\n- {:?}\n+ {offsets:?}\n",
+                stub.comm_fault_offsets
+            ));
+        }
+    }
+    if formats.proc_format != stub.proc_format {
+        let diff = diff(
+            &annotate_proc_format(&stub.proc_format, &stub.proc_offsets),
+            &annotate_proc_format(&formats.proc_format, &formats.proc_offsets),
+        );
+        report.push_str(&format!("Proc format strings differ:\n{diff}"));
+    }
+    if formats.type_format != stub.type_format {
+        let diff = diff(
+            &annotate_type_format(&stub.type_format),
+            &annotate_type_format(&formats.type_format),
+        );
+        report.push_str(&format!("Type format strings differ:\n{diff}"));
+    }
+
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "`{}` differs from MIDL's (- MIDL, + generated):\n{report}",
+            interface.name
+        ))
+    }
+}
+
+/// A line diff of two listings, with `-` lines only in `expected`, `+` lines only in
+/// `actual` and a few unchanged lines around them. Lines are matched without their
+/// `/* offset */` prefix, so that an inserted field doesn't change every following line.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let content = |line: &str| match line.split_once("*/") {
+        Some((prefix, rest)) if prefix.starts_with("/*") => rest.to_string(),
+        _ => line.to_string(),
+    };
+    let expected_content: Vec<String> = expected.iter().map(|line| content(line)).collect();
+    let actual_content: Vec<String> = actual.iter().map(|line| content(line)).collect();
+
+    // Longest common subsequence lengths of the suffixes
+    let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected_content[i] == actual_content[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected_content[i] == actual_content[j] {
+            lines.push((' ', actual[j]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(('-', expected[i]));
+            i += 1;
+        } else {
+            lines.push(('+', actual[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&index| lines[index].0 != ' ')
+        .collect();
+    let mut output = String::new();
+    let mut last_shown = None;
+    for (index, (marker, line)) in lines.iter().enumerate() {
+        let near_change = changed
+            .iter()
+            .any(|&change| index + CONTEXT >= change && index <= change + CONTEXT);
+        if !near_change {
+            continue;
+        }
+        if last_shown.is_some_and(|last| last + 1 < index) {
+            output.push_str("  ...\n");
+        }
+        output.push_str(&format!("{marker} {line}\n"));
+        last_shown = Some(index);
+    }
+    output
+}
+
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        match rest[start..].find("*/") {
+            Some(end) => rest = &rest[start + end + 2..],
+            None => rest = "",
+        }
+        stripped.push(' ');
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// The text between the outer braces of the initializer of the variable ending in `name`
+fn initializer<'a>(source: &'a str, name: &str) -> Result<&'a str, String> {
+    let definition = source
+        .find(&format!("{name} ="))
+        .or_else(|| source.find(&format!("{name}[] =")))
+        .ok_or_else(|| format!("no `{name}` in the stub"))?;
+    let start = definition
+        + source[definition..]
+            .find('{')
+            .ok_or_else(|| format!("no initializer for `{name}`"))?;
+    let mut depth = 0;
+    for (offset, char) in source[start..].char_indices() {
+        match char {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(&source[start + 1..start + offset]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(format!("unterminated initializer for `{name}`"))
+}
+
+/// Parses a `{ Pad, { bytes } }` format string initializer
+fn parse_format_string(source: &str, name: &str) -> Result<Vec<u8>, String> {
+    let outer = initializer(source, name)?;
+    let start = outer
+        .find('{')
+        .ok_or_else(|| format!("no format bytes in `{name}`"))?;
+    let end = outer
+        .rfind('}')
+        .ok_or_else(|| format!("no format bytes in `{name}`"))?;
+
+    let mut bytes = vec![];
+    for item in outer[start + 1..end].split(',') {
+        let item = item.trim();
+        if item.is_empty() {
+            continue;
+        }
+        if let Some(value) = macro_argument(item, "NdrFcShort") {
+            let value = parse_number(value)?;
+            bytes.extend_from_slice(&narrow(value, value as u16)?.to_le_bytes());
+        } else if let Some(value) = macro_argument(item, "NdrFcLong") {
+            let value = parse_number(value)?;
+            bytes.extend_from_slice(&narrow(value, value as u32)?.to_le_bytes());
+        } else {
+            let value = parse_number(item)?;
+            bytes.push(narrow(value, value as u8)?);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Parses comma separated 16-bit numbers
+fn parse_shorts(list: &str) -> Result<Vec<u16>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| parse_number(value).and_then(|value| narrow(value, value as u16)))
+        .collect()
+}
+
+/// The argument of `NAME( argument )`
+fn macro_argument<'a>(item: &'a str, name: &str) -> Option<&'a str> {
+    item.strip_prefix(name)?
+        .trim()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+        .map(str::trim)
+}
+
+fn parse_number(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("unexpected `{text}` in a format string"))
+}
+
+/// Checks that `value` fits in the type it was cast to as `narrowed`
+fn narrow<T: Into<u64> + Copy>(value: u64, narrowed: T) -> Result<T, String> {
+    if narrowed.into() == value {
+        Ok(narrowed)
+    } else {
+        Err(format!("{value:#x} is out of range in a format string"))
+    }
+}
@@ -0,0 +1,923 @@
+use windows_rpc_codegen::formats::{
+    annotate_ndr64_type_format, annotate_proc_format, annotate_type_format, formats,
+};
+use windows_rpc_codegen::{
+    BaseType, Interface, InterfaceVersion, Method, Parameter, SystemHandleKind, Type,
+    derive_ndr_struct, derive_ndr_union,
+};
+
+fn interface() -> Interface {
+    Interface {
+        name: "Formats".to_string(),
+        uuid: 0x4c1e9a3f_7d52_4b08_9e6a_2f3b8d1c5a70,
+        version: InterfaceVersion { major: 1, minor: 0 },
+        methods: vec![
+            Method {
+                name: "greet".to_string(),
+                parameters: vec![Parameter::new("name", Type::String)],
+                ..Method::default()
+            },
+            Method {
+                name: "sum".to_string(),
+                parameters: vec![Parameter::new("values", Type::Slice(BaseType::U32))],
+                ..Method::default()
+            },
+            Method {
+                name: "scale".to_string(),
+                parameters: vec![
+                    Parameter::new("point", Type::Struct("Point".to_string())),
+                    Parameter::new("factor", Type::Simple(BaseType::F64)),
+                ],
+                ..Method::default()
+            },
+        ],
+        ..Interface::default()
+    }
+}
+
+#[test]
+fn test_formats() {
+    let formats = formats(&interface());
+    assert_eq!(formats.proc_offsets.len(), 3);
+    assert_eq!(formats.proc_offsets[0], 0);
+    assert_eq!(formats.fixups.len(), 1);
+    let (position, r#type) = &formats.fixups[0];
+    assert!(*r#type == Type::Struct("Point".to_string()));
+    assert_eq!(&formats.proc_format[*position..*position + 2], &[0, 0]);
+}
+
+#[test]
+fn test_annotate_proc_format() {
+    let formats = formats(&interface());
+    let listing = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+
+    for opnum in 0..3 {
+        assert!(listing.contains(&format!("/* Procedure {opnum} */")));
+    }
+    assert!(
+        listing.contains("/* Oi flags:  has rpc flags, has comm or fault, new init routines, */")
+    );
+    assert!(listing.contains("0x32,\t/* FC_BIND_PRIMITIVE */"));
+    // The hidden length of the slice, then the array sized by it
+    assert!(listing.contains("NdrFcShort( 0x48 ),\t/* Flags:  in, base type, */"));
+    assert!(listing.contains("/* Flags:  must size, must free, in, simple ref, */"));
+    assert!(listing.contains("/* Ext flags:  new corr desc, srv corr check, */"));
+    // Every procedure is decoded, with only the terminating byte left
+    let last = listing.lines().last().unwrap();
+    assert!(last.ends_with("\t0x0,\t/* 0 */"), "{last}");
+    assert!(!listing.contains("Uncounted"));
+}
+
+#[test]
+fn test_annotate_type_format() {
+    // A string parameter and a `[size_is(length)]` array, as MIDL emits them
+    let format = [
+        0x00, 0x00, //
+        0x11, 0x08, 0x25, 0x5c, // FC_RP [simple_pointer] FC_C_WSTRING FC_PAD
+        0x11, 0x00, 0x02, 0x00, // FC_RP, offset 2
+        0x1b, 0x03, 0x04, 0x00, // FC_CARRAY, alignment 3, element size 4
+        0x29, 0x54, 0x08, 0x00, 0x01, 0x00, // correlation descriptor
+        0x08, 0x5b, // FC_LONG FC_END
+        0x00,
+    ];
+    let listing = annotate_type_format(&format);
+    let lines: Vec<_> = listing.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "/*    0 */\tNdrFcShort( 0x0 ),\t/* 0 */",
+            "/*    2 */\t0x11, 0x8,\t/* FC_RP [simple_pointer] */",
+            "/*    4 */\t0x25,\t/* FC_C_WSTRING */",
+            "/*    5 */\t0x5c,\t/* FC_PAD */",
+            "/*    6 */\t0x11, 0x0,\t/* FC_RP */",
+            "/*    8 */\tNdrFcShort( 0x2 ),\t/* Offset = 2 (10) */",
+            "/*   10 */\t0x1b, 0x3,\t/* FC_CARRAY 3 */",
+            "/*   12 */\tNdrFcShort( 0x4 ),\t/* Element size = 4 */",
+            "/*   14 */\t0x29,\t/* Corr desc: parameter, FC_ULONG */",
+            "/*   15 */\t0x54,\t/* FC_DEREFERENCE */",
+            "/*   16 */\tNdrFcShort( 0x8 ),\t/* Offset = 8 */",
+            "/*   18 */\tNdrFcShort( 0x1 ),\t/* Corr flags:  early, */",
+            "/*   20 */\t0x8,\t/* FC_LONG */",
+            "/*   21 */\t0x5b,\t/* FC_END */",
+            "/*   22 */\t0x0,\t/* 0 */",
+        ]
+    );
+}
+
+#[test]
+fn test_annotate_undecoded_bytes() {
+    // Bytes the listing doesn't decode are listed one per line
+    let listing = annotate_type_format(&[0x00, 0x00, 0xb4, 0x12, 0x00]);
+    assert!(listing.contains("/*    2 */\t0xb4,\t/* 180 */"));
+    assert!(listing.contains("/*    3 */\t0x12,\t/* 18 */"));
+
+    // A truncated procedure header
+    let listing = annotate_proc_format(&[0x00, 0x68, 0x00], &[0]);
+    assert_eq!(
+        listing
+            .lines()
+            .filter(|line| line.starts_with("/*"))
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn test_annotate_ndr64_type_format() {
+    let formats = formats(&interface());
+    let listing = annotate_ndr64_type_format(&formats.ndr64_type_format);
+    assert!(listing.contains("/* FC64_CONF_WCHAR_STRING */"));
+    assert!(listing.contains("/* FC64_INT32 */"));
+    assert!(listing.contains("/* FC64_FLOAT64 */"));
+}
+
+#[test]
+fn test_constant_buffer_sizes() {
+    let mut interface = interface();
+    interface.methods.splice(
+        0..0,
+        [
+            Method {
+                name: "add".to_string(),
+                parameters: vec![
+                    Parameter::new("a", Type::Simple(BaseType::I32)),
+                    Parameter::new("b", Type::Simple(BaseType::U64)),
+                ],
+                return_type: Some(Type::Simple(BaseType::I32)),
+                ..Method::default()
+            },
+            Method {
+                name: "split".to_string(),
+                parameters: vec![
+                    Parameter::new("value", Type::Simple(BaseType::U16)),
+                    Parameter {
+                        is_in: false,
+                        is_out: true,
+                        ..Parameter::new("high", Type::Simple(BaseType::U32))
+                    },
+                ],
+                ..Method::default()
+            },
+        ],
+    );
+    let formats = formats(&interface);
+    // Client and server buffer sizes, and the Oi2 flags of each procedure
+    let sizes: Vec<_> = formats
+        .proc_offsets
+        .iter()
+        .map(|&offset| {
+            let header = &formats.proc_format[usize::from(offset)..];
+            let short = |at: usize| u16::from_le_bytes([header[at], header[at + 1]]);
+            (short(14), short(16), header[18] & 0x03)
+        })
+        .collect();
+    assert_eq!(
+        sizes,
+        [
+            // Base types only: nothing is sized
+            (8 + 16, 16, 0),
+            (4, 16, 0),
+            // The string is sized
+            (0, 0, 0x02),
+            // The array is sized, on top of its length
+            (8, 0, 0x02),
+            // The struct is sized
+            (16, 0, 0x02),
+        ]
+    );
+}
+
+#[test]
+fn test_range_formats() {
+    let ranged = |name: &str, r#type, range| Parameter {
+        range: Some(range),
+        ..Parameter::new(name, r#type)
+    };
+    let interface = Interface {
+        methods: vec![
+            Method {
+                name: "offset".to_string(),
+                parameters: vec![ranged("delta", Type::Simple(BaseType::I16), (-100, 100))],
+                ..Method::default()
+            },
+            Method {
+                name: "sum".to_string(),
+                parameters: vec![
+                    ranged("values", Type::Slice(BaseType::U32), (0, 4)),
+                    Parameter::new("extra", Type::Slice(BaseType::U32)),
+                ],
+                ..Method::default()
+            },
+        ],
+        ..interface()
+    };
+    let formats = formats(&interface);
+
+    let types = annotate_type_format(&formats.type_format);
+    // The signed value and the length, compared with their signedness
+    assert!(types.contains("0xb7,\t/* FC_RANGE */\n/*    3 */\t0x6,\t/* FC_SHORT */"));
+    assert!(types.contains("NdrFcLong( 0xffffff9c ),\t/* Min = -100 */"));
+    assert!(types.contains("0x9,\t/* FC_ULONG */\n/*   14 */\tNdrFcLong( 0x0 ),\t/* Min = 0 */"));
+    // The arrays of the procedure with a ranged length carry ranges, bounded or not
+    assert!(types.contains("0x1, 0x0,\t/* Corr range */"));
+    assert!(types.contains("NdrFcLong( 0x4 ),\t/* Max = 4 */"));
+    assert!(types.contains("0x0, 0x0,\t/* No corr range */"));
+    let last = types.lines().last().unwrap();
+    assert!(last.ends_with("\t0x0,\t/* 0 */"), "{last}");
+
+    let procs = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    // Ranged values are passed by value to their range
+    assert_eq!(procs.matches("/* Flags:  in, by val, */").count(), 2);
+    assert_eq!(procs.matches("range on conformance").count(), 1);
+    assert!(!procs.contains("Uncounted"));
+}
+
+#[test]
+fn test_size_is_formats() {
+    let sized = |name: &str, r#type, length: &str| Parameter {
+        size_is: Some(length.to_string()),
+        ..Parameter::new(name, r#type)
+    };
+    let interface = Interface {
+        methods: vec![
+            Method {
+                name: "read".to_string(),
+                parameters: vec![
+                    Parameter {
+                        is_in: false,
+                        is_out: true,
+                        ..sized("buffer", Type::Slice(BaseType::U8), "length")
+                    },
+                    Parameter::new("length", Type::Simple(BaseType::U32)),
+                ],
+                ..Method::default()
+            },
+            Method {
+                name: "write".to_string(),
+                parameters: vec![
+                    Parameter::new("count", Type::Simple(BaseType::U32)),
+                    sized("values", Type::Slice(BaseType::U16), "count"),
+                ],
+                ..Method::default()
+            },
+        ],
+        ..interface()
+    };
+    let formats = formats(&interface);
+
+    let procs = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    // The arrays have no hidden lengths
+    assert_eq!(procs.matches("/* 2 parameters */").count(), 2);
+    assert!(procs.contains("/* Flags:  must size, must free, out, simple ref, */"));
+    assert!(procs.contains("/* Flags:  must size, must free, in, simple ref, */"));
+    // The client checks the size of the array it receives
+    assert_eq!(procs.matches("clt corr check").count(), 1);
+
+    let types = annotate_type_format(&formats.type_format);
+    // The length follows the out array, and precedes the in one
+    assert!(types.contains(
+        "NdrFcShort( 0x10 ),\t/* Offset = 16 */\n/*   14 */\tNdrFcShort( 0x0 ),\t/* Corr flags:  */"
+    ));
+    assert!(types.contains(
+        "NdrFcShort( 0x8 ),\t/* Offset = 8 */\n/*   30 */\tNdrFcShort( 0x1 ),\t/* Corr flags:  early, */"
+    ));
+}
+
+#[test]
+fn test_full_pointer_formats() {
+    let full = |name: &str, r#type| Parameter {
+        full_pointer: true,
+        ..Parameter::new(name, r#type)
+    };
+    let point = Type::Struct("Point".to_string());
+    let interface = Interface {
+        methods: vec![
+            Method {
+                name: "link".to_string(),
+                parameters: vec![full("from", point.clone()), full("to", point.clone())],
+                ..Method::default()
+            },
+            Method {
+                name: "pick".to_string(),
+                parameters: vec![
+                    full("value", Type::Optional(BaseType::I32)),
+                    full("name", Type::OptionalString),
+                    Parameter::new("point", point.clone()),
+                ],
+                ..Method::default()
+            },
+            Method {
+                name: "get".to_string(),
+                parameters: vec![Parameter::new("value", Type::Optional(BaseType::I32))],
+                ..Method::default()
+            },
+        ],
+        ..interface()
+    };
+    let formats = formats(&interface);
+
+    let procs = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    // Only procedures with full pointers set up the pointer table
+    assert_eq!(procs.matches("full ptr").count(), 2);
+    // Full pointers to structs aren't simple refs
+    assert_eq!(
+        procs
+            .matches("/* Flags:  must size, must free, in, */")
+            .count(),
+        5
+    );
+    assert_eq!(
+        procs
+            .matches("/* Flags:  must size, must free, in, simple ref, */")
+            .count(),
+        1
+    );
+
+    // Both struct parameters share the pointer, whose offset is filled at runtime
+    assert_eq!(formats.type_fixups.len(), 1);
+    let (position, r#type) = &formats.type_fixups[0];
+    assert!(*r#type == point);
+    assert_eq!(formats.type_format[*position - 2], 0x14);
+    assert_eq!(&formats.type_format[*position..*position + 2], &[0, 0]);
+    assert_eq!(formats.fixups.len(), 1);
+
+    let types = annotate_type_format(&formats.type_format);
+    assert_eq!(types.matches("FC_FP").count(), 3, "{types}");
+    assert_eq!(types.matches("FC_UP").count(), 1);
+}
+
+#[test]
+fn test_system_handle_formats() {
+    let event = Type::SystemHandle {
+        kind: SystemHandleKind::Event,
+        access: 0x1f0003,
+    };
+    let interface = Interface {
+        methods: vec![
+            Method {
+                name: "signal".to_string(),
+                parameters: vec![
+                    Parameter::new("first", event.clone()),
+                    Parameter::new("second", event.clone()),
+                ],
+                ..Method::default()
+            },
+            Method {
+                name: "open".to_string(),
+                return_type: Some(Type::SystemHandle {
+                    kind: SystemHandleKind::Section,
+                    access: 0,
+                }),
+                ..Method::default()
+            },
+        ],
+        ..interface()
+    };
+    let formats = formats(&interface);
+
+    let types = annotate_type_format(&formats.type_format);
+    // Parameters of the same kind and access share their description
+    assert_eq!(types.matches("/* FC_SYSTEM_HANDLE */").count(), 2);
+    assert!(types.contains("0x2,\t/* sh_event */"));
+    assert!(types.contains("NdrFcLong( 0x1f0003 ),\t/* Desired access = 0x1f0003 */"));
+    assert!(types.contains("0x6,\t/* sh_section */"));
+
+    let procs = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    assert_eq!(
+        procs
+            .matches("/* Flags:  must size, must free, in, by val, */")
+            .count(),
+        2
+    );
+    assert_eq!(
+        procs
+            .matches("/* Flags:  must size, must free, out, simple ref, srv alloc size=8, */")
+            .count(),
+        1
+    );
+    assert!(!procs.contains("Uncounted"));
+}
+
+#[test]
+fn test_context_handle_formats() {
+    let mut close = Parameter::new("context", Type::ContextHandle);
+    close.is_out = true;
+    let interface = Interface {
+        methods: vec![
+            Method {
+                name: "open".to_string(),
+                return_type: Some(Type::ContextHandle),
+                ..Method::default()
+            },
+            Method {
+                name: "read".to_string(),
+                parameters: vec![Parameter::new("context", Type::ContextHandle)],
+                return_type: Some(Type::Simple(BaseType::U32)),
+                ..Method::default()
+            },
+            Method {
+                name: "close".to_string(),
+                parameters: vec![close],
+                ..Method::default()
+            },
+        ],
+        ..interface()
+    };
+    let formats = formats(&interface);
+
+    let types = annotate_type_format(&formats.type_format);
+    assert_eq!(types.matches("/* FC_BIND_CONTEXT */").count(), 3);
+    assert!(types.contains("0xa0,\t/* Ctxt flags:  out, via ptr, */"));
+    assert!(types.contains("0x41,\t/* Ctxt flags:  cannot be null, in, */"));
+    assert!(types.contains("0xe0,\t/* Ctxt flags:  out, in, via ptr, */"));
+    assert!(types.contains("/* Rundown routine index = 0 */"));
+
+    let procs = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    // Their wire form has a constant size, which the server doesn't compute
+    assert!(!procs.contains("srv must size"));
+    assert_eq!(procs.matches("/* Flags:  out, simple ref, */").count(), 1);
+    assert_eq!(procs.matches("/* Flags:  in, */").count(), 1);
+    assert_eq!(
+        procs.matches("/* Flags:  in, out, simple ref, */").count(),
+        1
+    );
+
+    // The NDR64 formats are built at runtime
+    let code = formats.ndr64_descriptors.to_string();
+    assert_eq!(code.matches("NDR64_CONTEXT_HANDLE_FORMAT").count(), 3);
+}
+
+#[test]
+fn test_ansi_string_formats() {
+    let interface = Interface {
+        methods: vec![Method {
+            name: "open".to_string(),
+            parameters: vec![
+                Parameter::new("path", Type::AnsiString),
+                Parameter::new("name", Type::String),
+                Parameter::new("filter", Type::OptionalAnsiString),
+            ],
+            ..Method::default()
+        }],
+        ..interface()
+    };
+    let formats = formats(&interface);
+
+    let types = annotate_type_format(&formats.type_format);
+    assert!(types.contains("0x22,\t/* FC_C_CSTRING */"));
+    assert!(types.contains("0x25,\t/* FC_C_WSTRING */"));
+    // Optional ones are unique pointers to the same string
+    assert!(types.contains(
+        "0x12, 0x8,\t/* FC_UP [simple_pointer] */\n\
+         /*   12 */\t0x22,\t/* FC_C_CSTRING */"
+    ));
+
+    // `char` strings have their own descriptor, of 1-byte elements
+    let ndr64_types = annotate_ndr64_type_format(&formats.ndr64_type_format);
+    assert!(ndr64_types.contains(
+        "0x63,\t/* FC64_CONF_CHAR_STRING */\n\
+         /*    1 */\t0x0,\t/* Flags = 0x0 */\n\
+         /*    2 */\tNdrFcShort( 0x1 ),\t/* Element size = 1 */"
+    ));
+    assert!(ndr64_types.contains("0x64,\t/* FC64_CONF_WCHAR_STRING */"));
+}
+
+/// Returns the `NDR_FORMAT` fragment of the `NdrStruct` implementation in `code`
+fn struct_ndr_format(code: proc_macro2::TokenStream) -> Vec<u8> {
+    let file: syn::File = syn::parse2(code).unwrap();
+    for item in file.items {
+        let syn::Item::Impl(implementation) = item else {
+            continue;
+        };
+        for item in implementation.items {
+            if let syn::ImplItem::Const(constant) = item
+                && constant.ident == "NDR_FORMAT"
+                && let syn::Expr::Reference(reference) = constant.expr
+                && let syn::Expr::Array(array) = *reference.expr
+            {
+                return array
+                    .elems
+                    .iter()
+                    .map(|byte| match byte {
+                        syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Int(byte),
+                            ..
+                        }) => byte.base10_parse().unwrap(),
+                        _ => panic!("NDR_FORMAT holds bytes"),
+                    })
+                    .collect();
+            }
+        }
+    }
+    panic!("no NDR_FORMAT");
+}
+
+#[test]
+fn test_struct_pointer_formats() {
+    let code = derive_ndr_struct(syn::parse_quote! {
+        #[repr(C)]
+        struct Entry {
+            id: u32,
+            name: PCWSTR,
+            count: u32,
+            #[ndr(size_is(count))]
+            values: *const u16,
+            label: windows::core::PCSTR,
+        }
+    })
+    .unwrap();
+    let listing = annotate_type_format(&struct_ndr_format(code.clone()));
+    let lines: Vec<_> = listing.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "/*    0 */\t0x1a, 0x7,\t/* FC_BOGUS_STRUCT 7 */",
+            "/*    2 */\tNdrFcShort( 0x28 ),\t/* Memory size = 40 */",
+            "/*    4 */\tNdrFcShort( 0x0 ),\t/* No conformant array */",
+            "/*    6 */\tNdrFcShort( 0xa ),\t/* Offset = 10 (16) */",
+            "/*    8 */\t0x8,\t/* FC_LONG */",
+            "/*    9 */\t0x40,\t/* FC_STRUCTPAD4 */",
+            "/*   10 */\t0x36,\t/* FC_POINTER */",
+            "/*   11 */\t0x8,\t/* FC_LONG */",
+            "/*   12 */\t0x40,\t/* FC_STRUCTPAD4 */",
+            "/*   13 */\t0x36,\t/* FC_POINTER */",
+            "/*   14 */\t0x36,\t/* FC_POINTER */",
+            "/*   15 */\t0x5b,\t/* FC_END */",
+            "/*   16 */\t0x12, 0x8,\t/* FC_UP [simple_pointer] */",
+            "/*   18 */\t0x25,\t/* FC_C_WSTRING */",
+            "/*   19 */\t0x5c,\t/* FC_PAD */",
+            "/*   20 */\t0x12, 0x0,\t/* FC_UP */",
+            "/*   22 */\tNdrFcShort( 0x6 ),\t/* Offset = 6 (28) */",
+            "/*   24 */\t0x12, 0x8,\t/* FC_UP [simple_pointer] */",
+            "/*   26 */\t0x22,\t/* FC_C_CSTRING */",
+            "/*   27 */\t0x5c,\t/* FC_PAD */",
+            "/*   28 */\t0x1b, 0x1,\t/* FC_CARRAY 1 */",
+            "/*   30 */\tNdrFcShort( 0x2 ),\t/* Element size = 2 */",
+            "/*   32 */\t0x19,\t/* Corr desc: field pointer, FC_ULONG */",
+            "/*   33 */\t0x0,\t/* No operator */",
+            "/*   34 */\tNdrFcShort( 0x10 ),\t/* Offset = 16 */",
+            "/*   36 */\tNdrFcShort( 0x1 ),\t/* Corr flags:  early, */",
+            "/*   38 */\t0x6,\t/* FC_SHORT */",
+            "/*   39 */\t0x5b,\t/* FC_END */",
+        ]
+    );
+    // NDR64 describes the pointers by their offset
+    let code = code.to_string();
+    assert!(code.contains("FormatCode : 49u8"));
+    assert!(code.contains("(8u32 , windows_rpc :: ndr :: NdrPointee :: WideString)"));
+    assert!(code.contains("size_offset : 16u32"));
+    assert!(code.contains("(32u32 , windows_rpc :: ndr :: NdrPointee :: AnsiString)"));
+
+    // Structs without pointers are still copied as a block
+    let code = derive_ndr_struct(syn::parse_quote! {
+        #[repr(C)]
+        struct Point { x: i32, y: i32 }
+    })
+    .unwrap();
+    assert_eq!(
+        struct_ndr_format(code.clone()),
+        [0x15, 3, 8, 0, 0x09, 0x09, 0x5c, 0x5b]
+    );
+    assert!(!code.to_string().contains("NDR64_POINTERS"));
+
+    for (input, message) in [
+        (
+            quote::quote! { struct S { values: *const u32, count: u32 } },
+            "Array fields need `#[ndr(size_is(field))]`, naming the `u32` field holding their length",
+        ),
+        (
+            quote::quote! { struct S { #[ndr(size_is(count))] values: *const u32, count: u16 } },
+            "`size_is` must name a `u32` field of the struct",
+        ),
+        (
+            quote::quote! { struct S { #[ndr(size_is(count))] values: *mut u32, count: u32 } },
+            "Array fields must be `*const T` pointers to integers or floating point numbers",
+        ),
+        (
+            quote::quote! { struct S { #[ndr(size_is(count))] name: PCWSTR, count: u32 } },
+            "`size_is` is only supported on `*const T` fields",
+        ),
+        (
+            quote::quote! { struct S { name: String } },
+            "Only integer, floating point, string (`PCWSTR`, `PCSTR`) and array (`*const T`, `Vec<u16>`, `Vec<u8>`) fields are supported by NdrStruct",
+        ),
+    ] {
+        let input = quote::quote! { #[repr(C)] #input };
+        let error = derive_ndr_struct(syn::parse2(input).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_vec_field_formats() {
+    let code = derive_ndr_struct(syn::parse_quote! {
+        pub struct Entry {
+            id: u32,
+            #[ndr(string)]
+            name: Vec<u16>,
+            count: u32,
+            #[ndr(size_is(count))]
+            values: Vec<u16>,
+            #[ndr(string)]
+            label: Vec<u8>,
+        }
+    })
+    .unwrap();
+    // The wire struct is laid out like the one with string pointers
+    let pointers = derive_ndr_struct(syn::parse_quote! {
+        #[repr(C)]
+        struct Entry {
+            id: u32,
+            name: PCWSTR,
+            count: u32,
+            #[ndr(size_is(count))]
+            values: *const u16,
+            #[ndr(string)]
+            label: *const u8,
+        }
+    })
+    .unwrap();
+    assert_eq!(struct_ndr_format(code.clone()), struct_ndr_format(pointers));
+    let code = code.to_string();
+    assert!(code.contains("pub struct EntryWire"));
+    assert!(code.contains("name : * const u16"));
+    assert!(code.contains("impl windows_rpc :: RpcTransmittable < EntryWire > for Entry"));
+    assert!(code.contains("count : u32 :: try_from (self . values . len ())"));
+    assert!(!code.contains("# [ndr"));
+
+    for (input, message) in [
+        (
+            quote::quote! { struct S { values: Vec<u8> } },
+            "`Vec` fields need either `#[ndr(string)]` for strings or `#[ndr(size_is(field))]` for arrays",
+        ),
+        (
+            quote::quote! { struct S { #[ndr(string, size_is(count))] values: Vec<u8>, count: u32 } },
+            "`Vec` fields need either `#[ndr(string)]` for strings or `#[ndr(size_is(field))]` for arrays",
+        ),
+        (
+            quote::quote! {
+                struct S {
+                    #[ndr(size_is(count))] a: Vec<u8>,
+                    #[ndr(size_is(count))] b: Vec<u8>,
+                    count: u32,
+                }
+            },
+            "Each `Vec` field needs its own `size_is` field",
+        ),
+        (
+            quote::quote! { struct S(#[ndr(string)] Vec<u16>); },
+            "NdrStruct needs named fields for `Vec` fields",
+        ),
+        (
+            quote::quote! { #[repr(C)] struct S { values: *const u16 } },
+            "`*const u16` and `*const u8` fields need `#[ndr(string)]` for strings or `#[ndr(size_is(field))]` for arrays",
+        ),
+        (
+            quote::quote! { #[repr(C)] struct S { #[ndr(string)] values: *const u32 } },
+            "`string` is only supported on `*const u16` and `*const u8` fields",
+        ),
+        (
+            quote::quote! { #[repr(C)] struct S { #[ndr(string)] id: u32 } },
+            "`string` is only supported on `*const u16` and `*const u8` fields",
+        ),
+        (
+            quote::quote! { struct S { values: Vec<u32> } },
+            "NdrStruct requires `#[repr(C)]`",
+        ),
+    ] {
+        let error = derive_ndr_struct(syn::parse2(input).unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_union_derive_errors() {
+    for (input, message) in [
+        (
+            quote::quote! { enum Value { Int(i32), Text(String) } },
+            "String payloads are not supported by NdrUnion, pass strings as separate parameters",
+        ),
+        (
+            quote::quote! { enum Value { Int(i32), Text(&'static str) } },
+            "String payloads are not supported by NdrUnion, pass strings as separate parameters",
+        ),
+        (
+            quote::quote! { enum Value { Int(i32), Values(Vec<i32>) } },
+            "Only integer and floating point payloads are supported by NdrUnion",
+        ),
+    ] {
+        let input = quote::quote! { #[repr(C, u32)] #input };
+        let error = derive_ndr_union(syn::parse2(input).unwrap()).err().unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_string_vec_formats() {
+    let interface = Interface {
+        methods: vec![Method {
+            name: "names".to_string(),
+            return_type: Some(Type::StringVec),
+            ..Method::default()
+        }],
+        ..interface()
+    };
+    let formats = formats(&interface);
+    let listing = annotate_type_format(&formats.type_format);
+    let lines: Vec<_> = listing.lines().collect();
+    // A reference to the unique pointer to an array of unique string pointers
+    assert_eq!(
+        lines,
+        [
+            "/*    0 */\tNdrFcShort( 0x0 ),\t/* 0 */",
+            "/*    2 */\t0x11, 0x14,\t/* FC_RP [alloced_on_stack] [pointer_deref] */",
+            "/*    4 */\tNdrFcShort( 0x2 ),\t/* Offset = 2 (6) */",
+            "/*    6 */\t0x12, 0x0,\t/* FC_UP */",
+            "/*    8 */\tNdrFcShort( 0x2 ),\t/* Offset = 2 (10) */",
+            "/*   10 */\t0x21, 0x3,\t/* FC_BOGUS_ARRAY 3 */",
+            "/*   12 */\tNdrFcShort( 0x0 ),\t/* Number of elements = 0 */",
+            "/*   14 */\t0x29,\t/* Corr desc: parameter, FC_ULONG */",
+            "/*   15 */\t0x54,\t/* FC_DEREFERENCE */",
+            "/*   16 */\tNdrFcShort( 0x8 ),\t/* Offset = 8 */",
+            "/*   18 */\tNdrFcShort( 0x1 ),\t/* Corr flags:  early, */",
+            "/*   20 */\tNdrFcLong( 0xffffffff ),\t/* No variance */",
+            "/*   24 */\tNdrFcShort( 0x0 ),\t/* Corr flags:  */",
+            "/*   26 */\t0x12, 0x8,\t/* FC_UP [simple_pointer] */",
+            "/*   28 */\t0x25,\t/* FC_C_WSTRING */",
+            "/*   29 */\t0x5c,\t/* FC_PAD */",
+            "/*   30 */\t0x5c,\t/* FC_PAD */",
+            "/*   31 */\t0x5b,\t/* FC_END */",
+            "/*   32 */\t0x0,\t/* 0 */",
+        ]
+    );
+    // The hidden length, then the array sized by it
+    let listing = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    assert!(listing.contains("/* Flags:  out, base type, simple ref, srv alloc size=8, */"));
+    assert!(listing.contains("/* Flags:  must size, must free, out, srv alloc size=8, */"));
+    assert!(listing.contains("/* Ext flags:  new corr desc, clt corr check, */"));
+}
+
+#[test]
+fn test_string_vec_param_formats() {
+    // Out parameters are described like return values, sized by the length in their
+    // first slot, which is where the return value would go
+    let returned = formats(&Interface {
+        methods: vec![Method {
+            name: "names".to_string(),
+            return_type: Some(Type::StringVec),
+            ..Method::default()
+        }],
+        ..interface()
+    });
+    let out_interface = Interface {
+        methods: vec![Method {
+            name: "names".to_string(),
+            parameters: vec![Parameter {
+                is_in: false,
+                is_out: true,
+                ..Parameter::new("names", Type::StringVec)
+            }],
+            ..Method::default()
+        }],
+        ..interface()
+    };
+    let out_formats = formats(&out_interface);
+    assert_eq!(out_formats.type_format, returned.type_format);
+    assert_eq!(out_formats.proc_format, returned.proc_format);
+}
+
+#[test]
+fn test_error_status_formats() {
+    let interface = Interface {
+        methods: vec![Method {
+            name: "size".to_string(),
+            parameters: vec![Parameter::new("name", Type::String)],
+            return_type: Some(Type::Simple(BaseType::U64)),
+            error: Some(Type::Simple(BaseType::U32)),
+            error_status: true,
+            ..Method::default()
+        }],
+        ..interface()
+    };
+    let formats = formats(&interface);
+    let listing = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    // The value goes through an out parameter, and the error status is returned after the
+    // hidden status parameter
+    assert!(listing.contains("/* Oi2 flags:  clt must size, has return, has ext, */"));
+    assert!(listing.contains(
+        "/*   36 */\tNdrFcShort( 0x2150 ),\t/* Flags:  out, base type, simple ref, srv alloc size=8, */"
+    ));
+    assert!(
+        listing.contains("/*   42 */\tNdrFcShort( 0x70 ),\t/* Flags:  out, return, base type, */")
+    );
+    assert!(listing.contains("/*   44 */\tNdrFcShort( 0x20 ),\t/* Stack offset = 32 */"));
+    assert!(listing.contains("/*   46 */\t0x10,\t/* FC_ERROR_STATUS_T */"));
+}
+
+#[test]
+fn test_hresult_formats() {
+    let interface = Interface {
+        methods: vec![Method {
+            name: "size".to_string(),
+            parameters: vec![Parameter::new("name", Type::String)],
+            return_type: Some(Type::Simple(BaseType::U64)),
+            error: Some(Type::Simple(BaseType::I32)),
+            hresult: true,
+            ..Method::default()
+        }],
+        ..interface()
+    };
+    let formats = formats(&interface);
+    let listing = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    // Laid out as the `error_status_t` version, with the `HRESULT` returned as a long
+    assert!(listing.contains(
+        "/*   36 */\tNdrFcShort( 0x2150 ),\t/* Flags:  out, base type, simple ref, srv alloc size=8, */"
+    ));
+    assert!(
+        listing.contains("/*   42 */\tNdrFcShort( 0x70 ),\t/* Flags:  out, return, base type, */")
+    );
+    assert!(listing.contains("/*   44 */\tNdrFcShort( 0x20 ),\t/* Stack offset = 32 */"));
+    assert!(listing.contains("/*   46 */\t0x8,\t/* FC_LONG */"));
+}
+
+#[test]
+fn test_async_formats() {
+    let interface = Interface {
+        methods: vec![Method {
+            name: "add".to_string(),
+            parameters: vec![
+                Parameter::new("a", Type::Simple(BaseType::I32)),
+                Parameter::new("b", Type::Simple(BaseType::I32)),
+            ],
+            return_type: Some(Type::Simple(BaseType::I32)),
+            r#async: true,
+            ..Method::default()
+        }],
+        ..interface()
+    };
+    let formats = formats(&interface);
+    let listing = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    // The `RPC_ASYNC_STATE` takes the first stack slot, before the binding handle
+    assert!(listing.contains("/*   12 */\tNdrFcShort( 0x8 ),\t/* Stack offset = 8 */"));
+    assert!(listing.contains("/* Oi2 flags:  has return, has ext, has async handle, */"));
+    assert!(listing.contains("/*   32 */\tNdrFcShort( 0x10 ),\t/* Stack offset = 16 */"));
+    assert!(listing.contains("/*   44 */\tNdrFcShort( 0x28 ),\t/* Stack offset = 40 */"));
+    assert!(listing.contains("/*    8 */\tNdrFcShort( 0x30 ),\t/* Stack size = 48 */"));
+
+    // NDR64 binds the handle at the same offset, and flags the procedure as asynchronous
+    let code = formats.ndr64_descriptors.to_string();
+    let flags = code.split("NDR64_PROC_FORMAT { Flags : ").nth(1).unwrap();
+    let flags: u32 = flags[..flags.find("u32").unwrap()].parse().unwrap();
+    assert_eq!(flags & 0x200, 0x200, "IsAsync");
+    assert!(code.contains("StackOffset : 8u16"));
+    assert!(code.contains("StackOffset : 16u32"));
+}
+
+#[test]
+fn test_pipe_formats() {
+    let mut download = Parameter::new("data", Type::Pipe(BaseType::U8));
+    download.is_in = false;
+    download.is_out = true;
+    let interface = Interface {
+        methods: vec![
+            Method {
+                name: "upload".to_string(),
+                parameters: vec![Parameter::new("data", Type::Pipe(BaseType::U32))],
+                ..Method::default()
+            },
+            Method {
+                name: "download".to_string(),
+                parameters: vec![download],
+                ..Method::default()
+            },
+        ],
+        ..interface()
+    };
+    let formats = formats(&interface);
+
+    // Each element type is followed by the FC_PIPE the parameters refer to
+    let types = annotate_type_format(&formats.type_format);
+    assert!(types.contains("/*    2 */\t0x8,\t/* FC_LONG */"));
+    assert!(types.contains("/*    4 */\t0xb5,\t/* FC_PIPE */"));
+    assert!(types.contains("/*    6 */\tNdrFcShort( 0xfffc ),\t/* Offset = -4 (2) */"));
+    assert!(types.contains("/*   14 */\t0xb5,\t/* FC_PIPE */"));
+    assert!(types.contains("/*   15 */\t0x0,\t/* Alignment = 0 */"));
+
+    let procs = annotate_proc_format(&formats.proc_format, &formats.proc_offsets);
+    assert_eq!(procs.matches("has pipes,").count(), 2);
+    assert!(procs.contains("NdrFcShort( 0x10c ),\t/* Flags:  pipe, in, simple ref, */"));
+    assert!(procs.contains("NdrFcShort( 0x114 ),\t/* Flags:  pipe, out, simple ref, */"));
+    assert!(procs.contains("NdrFcShort( 0x4 ),\t/* Type offset = 4 */"));
+    assert!(procs.contains("NdrFcShort( 0xe ),\t/* Type offset = 14 */"));
+
+    // NDR64 flags the procedures as using pipes, whose formats are built at runtime
+    let code = formats.ndr64_descriptors.to_string();
+    assert_eq!(code.matches("NDR64_PIPE_FORMAT").count(), 2);
+    let flags = code.split("NDR64_PROC_FORMAT { Flags : ").nth(1).unwrap();
+    let flags: u32 = flags[..flags.find("u32").unwrap()].parse().unwrap();
+    assert_eq!(flags & 0x4000, 0x4000, "UsesPipes");
+}
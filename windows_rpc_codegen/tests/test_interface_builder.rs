@@ -0,0 +1,1312 @@
+use quote::quote;
+use windows_rpc_codegen::{
+    BaseType, Interface, InterfaceVersion, Method, Parameter, StringForm, SystemHandleKind,
+    TransferSyntaxes, Type, compile_interface, expand_rpc_interface, generate_idl, parse_idl_file,
+    parse_interface,
+};
+
+fn calculator() -> Interface {
+    Interface {
+        name: "Calculator".to_string(),
+        uuid: 0x12345678_1234_1234_1234_123456789abc,
+        version: InterfaceVersion { major: 1, minor: 0 },
+        methods: vec![
+            Method {
+                name: "add".to_string(),
+                parameters: vec![
+                    Parameter::new("a", Type::Simple(BaseType::I32)),
+                    Parameter::new("b", Type::Simple(BaseType::I32)),
+                ],
+                return_type: Some(Type::Simple(BaseType::I32)),
+                ..Method::default()
+            },
+            Method {
+                name: "greet".to_string(),
+                parameters: vec![Parameter::new("name", Type::String)],
+                return_type: Some(Type::String),
+                ..Method::default()
+            },
+            Method {
+                name: "ping".to_string(),
+                maybe: true,
+                ..Method::default()
+            },
+        ],
+        ..Interface::default()
+    }
+}
+
+#[test]
+fn test_matches_macro() {
+    let expanded = expand_rpc_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc), version(1.0) },
+        quote! {
+            trait Calculator {
+                fn add(a: i32, b: i32) -> i32;
+                fn greet(name: &str) -> String;
+                #[rpc(maybe)]
+                fn ping();
+            }
+        },
+    )
+    .unwrap();
+
+    let code = compile_interface(&calculator());
+    assert_eq!(code.to_string(), expanded.to_string());
+    syn::parse2::<syn::File>(code).unwrap();
+}
+
+#[test]
+fn test_single_transfer_syntax() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc), version(1.0), transfer_syntax(ndr20) },
+        quote! {
+            trait Calculator {
+                fn add(a: i32, b: i32) -> i32;
+            }
+        },
+    )
+    .unwrap();
+    assert_eq!(interface.transfer_syntaxes, TransferSyntaxes::Ndr20);
+
+    // Only the tables of the offered syntax are generated
+    let ndr20 = compile_interface(&interface).to_string();
+    assert!(ndr20.contains("proc_header"));
+    assert!(!ndr20.contains("ndr64_proc_table"));
+    assert!(!ndr20.contains("ndr64_dispatch"));
+
+    let ndr64 = compile_interface(&Interface {
+        transfer_syntaxes: TransferSyntaxes::Ndr64,
+        ..interface
+    })
+    .to_string();
+    assert!(ndr64.contains("ndr64_proc_table"));
+    assert!(!ndr64.contains("proc_header"));
+    assert!(!ndr64.contains("ndr_dispatch"));
+
+    let error = parse_interface(
+        quote! { transfer_syntax(dce) },
+        quote! { trait Calculator {} },
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "Expected `ndr20`, `ndr64` or `all` transfer syntax"
+    );
+}
+
+#[test]
+fn test_idl() {
+    assert_eq!(
+        generate_idl(&calculator()),
+        "// Generated by windows-rpc from the `Calculator` trait\n\
+         [\n    \
+             uuid(12345678-1234-1234-1234-123456789abc),\n    \
+             version(1.0),\n    \
+             pointer_default(unique)\n\
+         ]\n\
+         interface Calculator\n{\n    \
+             long add([in] handle_t binding, [in] long a, [in] long b);\n    \
+             void greet([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out, string] wchar_t** return_value);\n    \
+             [maybe] void ping([in] handle_t binding);\n\
+         }\n"
+    );
+}
+
+#[test]
+fn test_range() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc), version(1.0) },
+        quote! {
+            trait Bounded {
+                fn offset(#[rpc(range(-100, 100))] delta: i16);
+                fn sum(#[rpc(range(0, 4))] values: &[u32]);
+            }
+        },
+    )
+    .unwrap();
+    let ranges: Vec<_> = interface
+        .methods
+        .iter()
+        .map(|method| method.parameters[0].range)
+        .collect();
+    assert_eq!(ranges, [Some((-100, 100)), Some((0, 4))]);
+    // The range of a slice bounds its length
+    let idl = generate_idl(&interface);
+    assert!(
+        idl.contains("void offset([in] handle_t binding, [in, range(-100, 100)] short delta);")
+    );
+    assert!(idl.contains(
+        "[in, range(0, 4)] unsigned long values_length, [in, size_is(values_length)] unsigned long* values"
+    ));
+
+    for (method, message) in [
+        (
+            quote! { fn f(#[rpc(range(0, 1))] value: u64); },
+            "`range` is only supported on integers of up to 32 bits and slices",
+        ),
+        (
+            quote! { fn f(#[rpc(range(0, 1))] value: &mut u32); },
+            "`range` is only supported on integers of up to 32 bits and slices",
+        ),
+        (
+            quote! { fn f(#[rpc(range(-1, 1))] value: u8); },
+            "Expected a range within 0..=255, with the minimum first",
+        ),
+        (
+            quote! { fn f(#[rpc(range(2, 1))] value: i32); },
+            "Expected a range within -2147483648..=2147483647, with the minimum first",
+        ),
+        (
+            quote! { fn f(#[rpc(bounded)] value: i32); },
+            "Unknown parameter attribute: bounded",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Bounded { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_size_is() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc), version(1.0) },
+        quote! {
+            trait Buffers {
+                fn read(#[rpc(size_is(length))] buffer: &mut [u8], length: u32);
+                fn write(count: u32, #[rpc(size_is(count))] values: &[u16]);
+                fn fill(#[in_out] values: &mut [u32]);
+            }
+        },
+    )
+    .unwrap();
+    let sizes: Vec<_> = interface
+        .methods
+        .iter()
+        .map(|method| method.parameters[0].size_is.as_deref())
+        .collect();
+    assert_eq!(sizes, [Some("length"), None, None]);
+    assert_eq!(
+        interface.methods[1].parameters[1].size_is.as_deref(),
+        Some("count")
+    );
+    // Slices sized by another parameter have no hidden length
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "void read([in] handle_t binding, [out, size_is(length)] byte* buffer, \
+         [in] unsigned long length);"
+    ));
+    assert!(idl.contains(
+        "void write([in] handle_t binding, [in] unsigned long count, \
+         [in, size_is(count)] unsigned short* values);"
+    ));
+    assert!(idl.contains(
+        "void fill([in] handle_t binding, [in] unsigned long values_length, \
+         [in, out, size_is(values_length)] unsigned long* values);"
+    ));
+
+    for (method, message) in [
+        (
+            quote! { fn f(#[rpc(size_is(length))] value: u32, length: u32); },
+            "`size_is` is only supported on `&[T]` and `&mut [T]` parameters",
+        ),
+        (
+            quote! { fn f(#[rpc(size_is(length))] values: &[u8]); },
+            "`size_is` must name a `u32` parameter of the method",
+        ),
+        (
+            quote! { fn f(#[rpc(size_is(length))] values: &[u8], length: u16); },
+            "`size_is` must name a `u32` parameter of the method",
+        ),
+        (
+            quote! { fn f(#[rpc(size_is(length))] values: &[u8], length: &mut u32); },
+            "`size_is` must name a `u32` parameter of the method",
+        ),
+        (
+            quote! { fn f(#[rpc(range(0, 4), size_is(length))] values: &[u8], length: u32); },
+            "The range of a `size_is` slice goes on its length parameter",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Buffers { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_full_pointers() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc), version(1.0) },
+        quote! {
+            trait Graph {
+                fn link(#[rpc(ptr)] from: &Node, #[rpc(ptr)] to: &Node, weight: Option<u32>);
+                fn find(#[rpc(ptr)] id: Option<u32>, #[rpc(ptr)] name: Option<&str>);
+            }
+        },
+    )
+    .unwrap();
+    let full_pointers: Vec<Vec<_>> = interface
+        .methods
+        .iter()
+        .map(|method| method.parameters.iter().map(|p| p.full_pointer).collect())
+        .collect();
+    assert_eq!(full_pointers, [vec![true, true, false], vec![true, true]]);
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "void link([in] handle_t binding, [in, ptr] Node* from, [in, ptr] Node* to, \
+         [in, unique] unsigned long* weight);"
+    ));
+    assert!(idl.contains(
+        "void find([in] handle_t binding, [in, ptr] unsigned long* id, \
+         [in, ptr, string] const wchar_t* name);"
+    ));
+
+    for method in [
+        quote! { fn f(#[rpc(ptr)] value: u32); },
+        quote! { fn f(#[rpc(ptr)] name: &str); },
+        quote! { fn f(#[rpc(ptr)] node: &mut Node); },
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Graph { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "`ptr` is only supported on `Option<T>` and `&T` struct parameters"
+        );
+    }
+}
+
+#[test]
+fn test_transmit_as() {
+    let interface = parse_interface(
+        quote! {
+            guid(0x12345678_1234_1234_1234_123456789abc),
+            transmit_as(Duration = u64, uuid::Uuid = WireUuid)
+        },
+        quote! {
+            trait Jobs {
+                fn schedule(id: uuid::Uuid, delay: Duration) -> Duration;
+                fn next(after: &WireUuid) -> uuid::Uuid;
+            }
+        },
+    )
+    .unwrap();
+    let schedule = &interface.methods[0];
+    assert!(schedule.parameters[0].r#type == Type::Struct("WireUuid".to_string()));
+    assert_eq!(
+        schedule.parameters[0].presented.as_deref(),
+        Some("uuid::Uuid")
+    );
+    assert!(schedule.parameters[1].r#type == Type::Simple(BaseType::U64));
+    assert_eq!(
+        schedule.parameters[1].presented.as_deref(),
+        Some("Duration")
+    );
+    assert!(schedule.return_type == Some(Type::Simple(BaseType::U64)));
+    assert_eq!(schedule.presented_return.as_deref(), Some("Duration"));
+    assert_eq!(interface.methods[1].parameters[0].presented, None);
+    assert_eq!(
+        interface.methods[1].presented_return.as_deref(),
+        Some("uuid::Uuid")
+    );
+    // The IDL describes the types they are sent as
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "unsigned hyper schedule([in] handle_t binding, [in] WireUuid* id, \
+         [in] unsigned hyper delay);"
+    ));
+
+    for (method, message) in [
+        (
+            quote! { fn f(delay: &Duration); },
+            "`transmit_as` types are passed by value, as `T` parameters",
+        ),
+        (
+            quote! { fn f(delay: &mut Duration); },
+            "`transmit_as` types are not supported as `&mut` out parameters",
+        ),
+        (
+            quote! { fn f() -> &Duration; },
+            "`transmit_as` types are returned by value",
+        ),
+        (
+            quote! { fn f(#[rpc(ptr)] delay: Duration); },
+            "`ptr` is only supported on `Option<T>` and `&T` struct parameters",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc), transmit_as(Duration = u64) },
+            quote! { trait Jobs { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+
+    let error = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc), transmit_as(Duration = &str) },
+        quote! { trait Jobs { fn f(delay: Duration); } },
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        error.to_string(),
+        "`transmit_as` types are sent as numbers or structs"
+    );
+}
+
+#[test]
+fn test_system_handles() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Broker {
+                fn signal(#[rpc(system_handle(event, 0x1f0003))] event: BorrowedHandle<'_>);
+                #[rpc(idempotent, system_handle(section))]
+                fn open(size: u32) -> std::os::windows::io::OwnedHandle;
+            }
+        },
+    )
+    .unwrap();
+    assert!(
+        interface.methods[0].parameters[0].r#type
+            == Type::SystemHandle {
+                kind: SystemHandleKind::Event,
+                access: 0x1f0003,
+            }
+    );
+    assert!(
+        interface.methods[1].return_type
+            == Some(Type::SystemHandle {
+                kind: SystemHandleKind::Section,
+                access: 0,
+            })
+    );
+    assert!(interface.methods[1].idempotent);
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "void signal([in] handle_t binding, [in, system_handle(sh_event, 0x1f0003)] HANDLE event);"
+    ));
+    assert!(idl.contains(
+        "[idempotent] void open([in] handle_t binding, [in] unsigned long size, \
+         [out, system_handle(sh_section)] HANDLE* return_value);"
+    ));
+    // The generated code builds for both transfer syntaxes
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("NDR64_SYSTEM_HANDLE_FORMAT"));
+    assert!(code.contains("system_handle_from_return"));
+
+    for (method, message) in [
+        (
+            quote! { fn f(event: BorrowedHandle<'_>); },
+            "Handle parameters need a `#[rpc(system_handle(kind))]` attribute",
+        ),
+        (
+            quote! { fn f(#[rpc(system_handle(event))] event: OwnedHandle); },
+            "Handles are passed as `BorrowedHandle<'_>` parameters",
+        ),
+        (
+            quote! { fn f(#[rpc(system_handle(event))] event: &mut OwnedHandle); },
+            "Handles are passed as `BorrowedHandle<'_>` parameters",
+        ),
+        (
+            quote! { fn f(#[rpc(system_handle(event))] value: u32); },
+            "`system_handle` is only supported on `BorrowedHandle<'_>` parameters",
+        ),
+        (
+            quote! { fn f(#[rpc(system_handle(window))] event: BorrowedHandle<'_>); },
+            "Expected a handle kind: file, semaphore, event, mutex, process, token, section, \
+             reg_key, thread, composition, socket, job or pipe",
+        ),
+        (
+            quote! { fn f() -> OwnedHandle; },
+            "Returned handles need a `#[rpc(system_handle(kind))]` method attribute",
+        ),
+        (
+            quote! { #[rpc(system_handle(event))] fn f() -> BorrowedHandle<'static>; },
+            "Handles are returned as `OwnedHandle`",
+        ),
+        (
+            quote! { #[rpc(system_handle(event))] fn f() -> u32; },
+            "`system_handle` is only supported on methods returning `OwnedHandle`",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Broker { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_win32_structs() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Clock {
+                fn to_system_time(time: &windows::Win32::Foundation::FILETIME) -> SYSTEMTIME;
+                fn uptime(ticks: &mut windows_rpc::LARGE_INTEGER);
+            }
+        },
+    )
+    .unwrap();
+    let idl = generate_idl(&interface);
+    assert!(idl.contains("trait\nimport \"wtypes.idl\";\n\n["));
+    assert!(idl.contains(
+        "void to_system_time([in] handle_t binding, [in] FILETIME* time, \
+         [out] SYSTEMTIME* return_value);"
+    ));
+    assert!(idl.contains("void uptime([in] handle_t binding, [out] LARGE_INTEGER* ticks);"));
+    assert!(!generate_idl(&calculator()).contains("import"));
+
+    // The structs are imported as the Rust types supporting them
+    let path = std::env::temp_dir().join("windows_rpc_test_win32_structs.idl");
+    std::fs::write(&path, &idl).unwrap();
+    let imported = parse_idl_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let methods = &imported[0].methods;
+    assert!(
+        methods[0].parameters[0].r#type
+            == Type::Struct("windows::Win32::Foundation::FILETIME".to_string())
+    );
+    assert!(
+        methods[0].return_type
+            == Some(Type::Struct(
+                "windows::Win32::Foundation::SYSTEMTIME".to_string()
+            ))
+    );
+    assert!(
+        methods[1].parameters[0].r#type == Type::Struct("windows_rpc::LARGE_INTEGER".to_string())
+    );
+}
+
+#[test]
+fn test_ansi_strings() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Files {
+                fn open(#[rpc(ansi)] path: &str, name: &str) -> u32;
+            }
+        },
+    )
+    .unwrap();
+    let parameters = &interface.methods[0].parameters;
+    assert!(parameters[0].r#type == Type::AnsiString);
+    assert!(parameters[1].r#type == Type::String);
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "unsigned long open([in] handle_t binding, [in, string] const char* path, \
+         [in, string] const wchar_t* name);"
+    ));
+
+    // `char` strings are imported back as ANSI parameters
+    let path = std::env::temp_dir().join("windows_rpc_test_ansi_strings.idl");
+    for idl in [idl.clone(), idl.replace("const char* path", "LPCSTR path")] {
+        std::fs::write(&path, &idl).unwrap();
+        let imported = parse_idl_file(&path).unwrap();
+        let parameters = &imported[0].methods[0].parameters;
+        assert!(parameters[0].r#type == Type::AnsiString);
+        assert!(parameters[1].r#type == Type::String);
+    }
+    std::fs::remove_file(&path).unwrap();
+
+    for (method, message) in [
+        (
+            quote! { fn f(#[rpc(ansi)] value: u32); },
+            "`ansi` is only supported on `&str` and `Option<&str>` parameters",
+        ),
+        (
+            quote! { fn f(#[rpc(ansi)] value: &U16CStr); },
+            "`ansi` is only supported on `&str` and `Option<&str>` parameters",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Files { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_optional_ansi_strings() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Files {
+                fn find(#[rpc(ansi)] pattern: Option<&str>, #[rpc(ansi, ptr)] root: Option<&str>);
+            }
+        },
+    )
+    .unwrap();
+    let parameters = &interface.methods[0].parameters;
+    assert!(parameters[0].r#type == Type::OptionalAnsiString);
+    assert!(!parameters[0].full_pointer);
+    assert!(parameters[1].r#type == Type::OptionalAnsiString);
+    assert!(parameters[1].full_pointer);
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "void find([in] handle_t binding, [in, unique, string] const char* pattern, \
+         [in, ptr, string] const char* root);"
+    ));
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("decode_ansi"));
+
+    // Imported back with their pointer attributes
+    let path = std::env::temp_dir().join("windows_rpc_test_optional_ansi_strings.idl");
+    std::fs::write(&path, &idl).unwrap();
+    let imported = parse_idl_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let parameters = &imported[0].methods[0].parameters;
+    assert!(parameters[0].r#type == Type::OptionalAnsiString);
+    assert!(!parameters[0].full_pointer);
+    assert!(parameters[1].r#type == Type::OptionalAnsiString);
+    assert!(parameters[1].full_pointer);
+}
+
+#[test]
+fn test_owned_strings() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Names {
+                fn set(a: String, b: &String, c: Cow<'_, str>, d: std::borrow::Cow<str>, e: &str);
+            }
+        },
+    )
+    .unwrap();
+    let parameters = &interface.methods[0].parameters;
+    let forms = [
+        StringForm::String,
+        StringForm::StringRef,
+        StringForm::Cow,
+        StringForm::Cow,
+        StringForm::Str,
+    ];
+    for (parameter, form) in parameters.iter().zip(forms) {
+        assert!(parameter.r#type == Type::String);
+        assert!(parameter.string_form == form);
+    }
+    // Sent like `&str`
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "void set([in] handle_t binding, [in, string] const wchar_t* a, \
+         [in, string] const wchar_t* b, [in, string] const wchar_t* c, \
+         [in, string] const wchar_t* d, [in, string] const wchar_t* e);"
+    ));
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("into_string"));
+    assert!(code.contains("as_string"));
+
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Paths {
+                fn open(a: &OsStr, b: OsString, c: &std::path::Path, d: PathBuf);
+            }
+        },
+    )
+    .unwrap();
+    let parameters = &interface.methods[0].parameters;
+    let forms = [
+        StringForm::OsStr,
+        StringForm::OsString,
+        StringForm::Path,
+        StringForm::PathBuf,
+    ];
+    for (parameter, form) in parameters.iter().zip(forms) {
+        assert!(parameter.r#type == Type::String);
+        assert!(parameter.string_form == form);
+    }
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("to_os_string"));
+
+    for (method, message) in [
+        (
+            quote! { fn f(#[rpc(ansi)] value: String); },
+            "`ansi` is only supported on `&str` and `Option<&str>` parameters",
+        ),
+        (
+            quote! { fn f(value: Cow<'_, [u8]>); },
+            "Structs are passed by reference, as `&T` parameters",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Names { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_struct_pointers() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Catalog {
+                fn add(entry: &Entry) -> u32;
+                fn get(id: u32, entry: &mut Entry);
+                fn first() -> Entry;
+                fn tag(entry: &Entry, #[rpc(range(0, 16))] tags: &[u32]);
+            }
+        },
+    )
+    .unwrap();
+    let code = compile_interface(&interface).to_string();
+    // Structs with pointer fields are only input parameters...
+    assert!(code.contains(
+        "\"`Entry` has pointer fields, so `get` can only take it as an input parameter\""
+    ));
+    assert!(code.contains(
+        "\"`Entry` has pointer fields, so `first` can only take it as an input parameter\""
+    ));
+    assert!(!code.contains("so `add` can only"));
+    // ...their arrays have no ranges on conformance...
+    assert!(code.contains(
+        "\"`Entry` has arrays sized by its fields, which `tag` can't take along with ranged slices\""
+    ));
+    assert!(code.contains(":: HAS_CONFORMANCE"));
+    // ...and they aren't sent by the async calls
+    assert!(code.contains("where for < '__a > Entry : std :: marker :: Send"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+}
+
+#[test]
+fn test_string_vec() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Directory {
+                fn names(#[rpc(range(0, 64))] limit: u32) -> Vec<String>;
+            }
+        },
+    )
+    .unwrap();
+    assert!(interface.methods[0].return_type == Some(Type::StringVec));
+    let idl = generate_idl(&interface);
+    assert!(idl.contains("import \"wtypes.idl\";"));
+    assert!(idl.contains(
+        "void names([in] handle_t binding, [in, range(0, 64)] unsigned long limit, \
+         [out] unsigned long* return_value_length, \
+         [out, size_is(, *return_value_length)] LPWSTR** return_value);"
+    ));
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("from_midl_string_array"));
+    assert!(code.contains("to_midl_string_array"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    // Imported back as a `Vec<String>` return
+    let path = std::env::temp_dir().join("windows_rpc_test_string_vec.idl");
+    std::fs::write(&path, &idl).unwrap();
+    let imported = parse_idl_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(imported[0].methods[0].return_type == Some(Type::StringVec));
+    assert_eq!(imported[0].methods[0].parameters.len(), 1);
+
+    // Out parameters are declared like return values, with their length before them
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Directory {
+                fn list(names: &mut Vec<String>, limit: u32) -> u32;
+            }
+        },
+    )
+    .unwrap();
+    let method = &interface.methods[0];
+    assert!(method.parameters[0].r#type == Type::StringVec && method.parameters[0].is_out);
+    assert_eq!(method.abi_param_count(), 3);
+    let idl = generate_idl(&interface);
+    assert!(idl.contains("import \"wtypes.idl\";"));
+    assert!(idl.contains(
+        "unsigned long list([in] handle_t binding, [out] unsigned long* names_length, \
+         [out, size_is(, *names_length)] LPWSTR** names, [in] unsigned long limit);"
+    ));
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("names : & mut std :: vec :: Vec < String >"));
+    assert!(code.contains("from_midl_string_array (__names_array , __names_length)"));
+    assert!(code.contains("to_midl_string_array (& __names_converted)"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    // Imported back as an out parameter, as the procedure returns a number
+    std::fs::write(&path, &idl).unwrap();
+    let imported = parse_idl_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let parameter = &imported[0].methods[0].parameters[0];
+    assert!(parameter.r#type == Type::StringVec && parameter.is_out);
+    assert_eq!(imported[0].methods[0].parameters.len(), 2);
+
+    for (method, message) in [
+        (
+            quote! { fn f(names: Vec<String>); },
+            "Arrays of strings are only supported as `Vec<String>` return values and `&mut Vec<String>` out parameters",
+        ),
+        (
+            quote! { fn f(#[in_out] names: &mut Vec<String>); },
+            "`&mut Vec<String>` parameters are out only, `#[in_out]` is not supported",
+        ),
+        (
+            quote! { fn f(values: &mut Vec<u32>); },
+            "Arrays are passed as `&mut [T]` out parameters",
+        ),
+        (
+            quote! { fn f() -> Vec<bool>; },
+            "Only vectors of integers, floating point numbers and `String` are supported",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Directory { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_service_errors() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc), enums(Reason) },
+        quote! {
+            trait Files {
+                fn size(name: &str) -> Result<u64, u32>;
+                fn read(name: &str) -> Result<String, Reason>;
+                fn delete(name: &str) -> std::result::Result<(), u32>;
+            }
+        },
+    )
+    .unwrap();
+    assert!(interface.methods[0].return_type == Some(Type::Simple(BaseType::U64)));
+    assert!(interface.methods[0].error == Some(Type::Simple(BaseType::U32)));
+    assert!(interface.methods[1].error == Some(Type::Enum("Reason".to_string())));
+    assert!(interface.methods[2].return_type.is_none());
+
+    // The error code goes after the declared parameters, before the return value
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "void read([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out] unsigned long* service_error, [out, string] wchar_t** return_value);"
+    ));
+    assert!(idl.contains(
+        "void delete([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out] unsigned long* service_error);"
+    ));
+    assert_eq!(interface.methods[0].abi_param_count(), 2);
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("ServiceError < Reason >"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    for (method, message) in [
+        (
+            quote! { fn f() -> Result<u32, i32>; },
+            "Errors are returned as `u32` codes or enums declared with `enums(...)`",
+        ),
+        (
+            quote! { fn f() -> Result<u32, String>; },
+            "Errors are returned as `u32` codes or enums declared with `enums(...)`",
+        ),
+        (
+            quote! { #[rpc(system_handle(event))] fn f() -> Result<OwnedHandle, u32>; },
+            "Methods returning handles cannot return errors",
+        ),
+        (
+            quote! { #[rpc(maybe)] fn f() -> Result<(), u32>; },
+            "`#[rpc(maybe)]` methods cannot return values or have out parameters",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Files { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_error_status() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Files {
+                #[rpc(error_status)]
+                fn size(name: &str) -> Result<u64, u32>;
+                #[rpc(error_status)]
+                fn read(name: &str) -> Result<String, u32>;
+                #[rpc(idempotent, error_status)]
+                fn delete(name: &str) -> Result<(), u32>;
+            }
+        },
+    )
+    .unwrap();
+    assert!(interface.methods[0].error_status);
+    assert!(interface.methods[0].error == Some(Type::Simple(BaseType::U32)));
+
+    // The value goes through the last parameter, and the error code is the return value
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "error_status_t size([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out] unsigned hyper* return_value);"
+    ));
+    assert!(idl.contains(
+        "error_status_t read([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out, string] wchar_t** return_value);"
+    ));
+    assert!(idl.contains(
+        "[idempotent] error_status_t delete([in] handle_t binding, \
+         [in, string] const wchar_t* name);"
+    ));
+    assert_eq!(interface.methods[0].abi_param_count(), 2);
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("__return_value : * mut u64"));
+    assert!(!code.contains("__service_error : * mut u32"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    // Imported back as `error_status` methods
+    let path = std::env::temp_dir().join("windows_rpc_test_error_status.idl");
+    std::fs::write(&path, &idl).unwrap();
+    let imported = parse_idl_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    for (imported, method) in imported[0].methods.iter().zip(&interface.methods) {
+        assert!(imported.error_status);
+        assert!(imported.return_type == method.return_type);
+        assert_eq!(imported.parameters.len(), 1);
+    }
+
+    for (method, message) in [
+        (
+            quote! { #[rpc(error_status)] fn f() -> u32; },
+            "`error_status` is only supported on methods returning `Result<T, u32>`",
+        ),
+        (
+            quote! { #[rpc(error_status)] fn f() -> Result<Reason, u32>; },
+            "`error_status` methods cannot return enums",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc), enums(Reason) },
+            quote! { trait Files { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_hresult() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Files {
+                #[rpc(hresult)]
+                fn size(name: &str) -> Result<u64, i32>;
+                #[rpc(hresult)]
+                fn read(name: &str) -> Result<String, i32>;
+                #[rpc(hresult)]
+                fn delete(name: &str) -> Result<(), i32>;
+            }
+        },
+    )
+    .unwrap();
+    assert!(interface.methods[0].hresult);
+    assert!(!interface.methods[0].error_status);
+    assert!(interface.methods[0].error == Some(Type::Simple(BaseType::I32)));
+    assert!(interface.methods[0].base_type_return() == Some(Type::Simple(BaseType::I32)));
+
+    // The value goes through the last parameter, and the `HRESULT` of `wtypes.idl` is the
+    // return value
+    let idl = generate_idl(&interface);
+    assert!(idl.contains("import \"wtypes.idl\";"));
+    assert!(idl.contains(
+        "HRESULT size([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out] unsigned hyper* return_value);"
+    ));
+    assert!(idl.contains(
+        "HRESULT read([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out, string] wchar_t** return_value);"
+    ));
+    assert!(
+        idl.contains("HRESULT delete([in] handle_t binding, [in, string] const wchar_t* name);")
+    );
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("__return_value : * mut u64) -> i32"));
+    assert!(code.contains("__service_error < 0"));
+    assert!(code.contains("windows_rpc :: ServiceError < i32 >"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    // Imported back as `hresult` methods
+    let path = std::env::temp_dir().join("windows_rpc_test_hresult.idl");
+    std::fs::write(&path, &idl).unwrap();
+    let imported = parse_idl_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    for (imported, method) in imported[0].methods.iter().zip(&interface.methods) {
+        assert!(imported.hresult);
+        assert!(imported.error == Some(Type::Simple(BaseType::I32)));
+        assert!(imported.return_type == method.return_type);
+        assert_eq!(imported.parameters.len(), 1);
+    }
+
+    for (method, message) in [
+        (
+            quote! { #[rpc(hresult)] fn f() -> Result<u32, u32>; },
+            "`hresult` is only supported on methods returning `Result<T, i32>`",
+        ),
+        (
+            quote! { #[rpc(hresult)] fn f() -> Result<Reason, i32>; },
+            "`hresult` methods cannot return enums",
+        ),
+        (
+            quote! { #[rpc(error_status, hresult)] fn f() -> Result<(), i32>; },
+            "`hresult` and `error_status` are exclusive",
+        ),
+        (
+            quote! { fn f() -> Result<(), i32>; },
+            "Errors are returned as `u32` codes or enums declared with `enums(...)`",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc), enums(Reason) },
+            quote! { trait Files { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_unmasked_floats() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait CallAbi {
+                fn late_floats(a: i64, b: i64, c: i64, d: i64, e: i64, f: i64, g: f32, h: f64, i: f32) -> f64;
+            }
+        },
+    )
+    .unwrap();
+    let method = &interface.methods[0];
+    // The binding handle takes the first slot, so only `g` fits in the mask
+    assert_eq!(method.float_double_mask(), 0b01 << 14);
+    assert_eq!(method.unmasked_float_params(), ["h", "i"]);
+
+    // The server routine receives the others as integers
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("g : f32 , h : u64 , i : u64"));
+    assert!(code.contains("let h = f64 :: from_bits (h) ;"));
+    assert!(code.contains("let i = f32 :: from_bits (i as u32) ;"));
+}
+
+#[test]
+fn test_async() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Jobs {
+                #[rpc(async)]
+                fn add(a: i32, b: i32) -> i32;
+                #[rpc(async)]
+                fn split(value: u32, high: &mut u16) -> u16;
+                fn ping();
+            }
+        },
+    )
+    .unwrap();
+    assert!(interface.methods[0].r#async);
+    assert!(!interface.methods[2].r#async);
+    // The parameters follow the `RPC_ASYNC_STATE` and the binding handle
+    assert_eq!(interface.methods[0].binding_stack_offset(8), 8);
+    assert_eq!(interface.methods[0].stack_offsets(8).0, [16, 24]);
+
+    // `[async]` is an ACF attribute, absent from the IDL
+    let idl = generate_idl(&interface);
+    assert!(idl.contains("long add([in] handle_t binding, [in] long a, [in] long b);"));
+
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("Ndr64AsyncClientCall"));
+    assert!(code.contains("ndr_async_dispatch"));
+    assert!(code.contains("ndr64_async_dispatch"));
+    assert!(code.contains("windows_rpc :: dispatch :: invoke_async"));
+    // Awaited without a thread, unless the call writes through out parameters
+    assert!(code.contains("pub fn add_async (& self"));
+    assert!(!code.contains("fn split_async"));
+    assert!(code.contains("pub fn ping_async (self : & std :: sync :: Arc < Self >"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    let error = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! { trait Jobs { #[rpc(async, maybe)] fn f(); } },
+    )
+    .err()
+    .unwrap();
+    assert_eq!(error.to_string(), "`async` methods cannot be `maybe`");
+}
+
+#[test]
+fn test_context_handles() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Sessions {
+                fn open(name: &str) -> ContextHandle;
+                fn read(session: &ContextHandle) -> u32;
+                fn close(#[in_out] session: &mut windows_rpc::context::ContextHandle);
+            }
+        },
+    )
+    .unwrap();
+    assert!(interface.methods[0].return_type == Some(Type::ContextHandle));
+    assert!(interface.methods[1].parameters[0].r#type == Type::ContextHandle);
+    assert!(interface.methods[2].parameters[0].is_in);
+    assert!(interface.methods[2].parameters[0].is_out);
+
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "void open([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out, context_handle] void** return_value);"
+    ));
+    assert!(idl.contains(
+        "unsigned long read([in] handle_t binding, [in, context_handle] void* session);"
+    ));
+    assert!(
+        idl.contains(
+            "void close([in] handle_t binding, [in, out, context_handle] void** session);"
+        )
+    );
+
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("RUNDOWN_ROUTINES"));
+    assert!(code.contains("session . check_open () ?"));
+    assert!(code.contains("-> windows_rpc :: context :: ServerContext ;"));
+    assert!(code.contains("session : & mut windows_rpc :: context :: ServerContext"));
+    // The call borrows the handle, and only opening one can run on the executor
+    assert!(code.contains("pub fn open_async"));
+    assert!(!code.contains("fn read_async"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    for (method, message) in [
+        (
+            quote! { fn f(session: ContextHandle); },
+            "Context handles are passed as `&ContextHandle` or `#[in_out] &mut ContextHandle` parameters",
+        ),
+        (
+            quote! { fn f(session: &mut ContextHandle); },
+            "Context handles are opened as return values, `&mut ContextHandle` parameters must be `#[in_out]`",
+        ),
+        (
+            quote! { fn f() -> &ContextHandle; },
+            "Context handles are returned by value, as `ContextHandle`",
+        ),
+        (
+            quote! { fn f(#[since(1.1, default = 0)] session: &ContextHandle); },
+            "`#[since]` is not supported on context handles",
+        ),
+        (
+            quote! { #[rpc(async)] fn f() -> ContextHandle; },
+            "`async` methods cannot take or return context handles",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+            quote! { trait Sessions { #method } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_callbacks() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Copier {
+                fn copy(files: u32) -> u32;
+                #[rpc(callback)]
+                fn progress(copied: u32) -> u32;
+                fn cancel();
+                #[rpc(callback)]
+                fn label(name: &str) -> String;
+            }
+        },
+    )
+    .unwrap();
+    // Callbacks are numbered on their own
+    assert_eq!(interface.methods.len(), 2);
+    assert_eq!(interface.methods[1].name, "cancel");
+    assert_eq!(interface.callbacks.len(), 2);
+    assert_eq!(interface.callbacks[1].name, "label");
+
+    // They follow the methods in the IDL
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "    void cancel([in] handle_t binding);\n    \
+         [callback] unsigned long progress([in] handle_t binding, [in] unsigned long copied);\n    \
+         [callback] void label([in] handle_t binding, [in, string] const wchar_t* name, \
+         [out, string] wchar_t** return_value);\n}"
+    ));
+
+    // Imported back as callbacks
+    let path = std::env::temp_dir().join("windows_rpc_test_callbacks.idl");
+    std::fs::write(&path, &idl).unwrap();
+    let imported = parse_idl_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(imported[0].methods.len(), 2);
+    assert_eq!(imported[0].callbacks.len(), 2);
+    assert_eq!(imported[0].callbacks[0].name, "progress");
+
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("pub trait CopierCallbacks"));
+    assert!(code.contains("fn progress (& self , copied : u32) -> u32"));
+    assert!(code.contains("pub struct CopierCallbacksClient"));
+    assert!(code.contains("pub fn set_callbacks"));
+    assert!(code.contains("RPCFLG_HAS_CALLBACK"));
+    // The server has no handler for them
+    assert!(!code.contains("fn progress (copied : u32) -> u32"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    for (methods, message) in [
+        (
+            quote! { #[rpc(callback, async)] fn f(); },
+            "`callback` methods cannot be `async`",
+        ),
+        (
+            quote! { #[rpc(callback)] fn f(session: &ContextHandle); },
+            "`callback` methods cannot take or return context handles",
+        ),
+        (
+            quote! { #[rpc(callback)] fn f(#[since(1.1, default = 0)] a: u32); },
+            "`callback` methods cannot have `#[since]` parameters",
+        ),
+        (
+            quote! {
+                fn f(#[since(1.1, default = 0)] a: u32);
+                #[rpc(callback)] fn g();
+            },
+            "`#[since]` parameters are not supported with callbacks",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc), version(1.1) },
+            quote! { trait Copier { #methods } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
+
+#[test]
+fn test_pipes() {
+    let interface = parse_interface(
+        quote! { guid(0x12345678_1234_1234_1234_123456789abc) },
+        quote! {
+            trait Storage {
+                fn upload(data: InPipe<u32>) -> u64;
+                fn download(size: u32, data: OutPipe<u8>);
+                fn copy(data: InPipe<u32>, copy: OutPipe<u32>);
+            }
+        },
+    )
+    .unwrap();
+    let upload = &interface.methods[0].parameters[0];
+    assert!(upload.r#type == Type::Pipe(BaseType::U32));
+    assert!(upload.is_in && !upload.is_out);
+    let download = &interface.methods[1].parameters[1];
+    assert!(download.r#type == Type::Pipe(BaseType::U8));
+    assert!(!download.is_in && download.is_out);
+    assert!(interface.has_pipes());
+
+    // A typedef per element type, which the parameters are declared with
+    let idl = generate_idl(&interface);
+    assert!(idl.contains(
+        "    typedef pipe unsigned long UNSIGNED_LONG_PIPE;\n    \
+         typedef pipe byte BYTE_PIPE;\n\n"
+    ));
+    assert!(
+        idl.contains("unsigned hyper upload([in] handle_t binding, [in] UNSIGNED_LONG_PIPE data);")
+    );
+    assert!(idl.contains(
+        "void download([in] handle_t binding, [in] unsigned long size, [out] BYTE_PIPE* data);"
+    ));
+
+    let code = compile_interface(&interface).to_string();
+    assert!(code.contains("data : & mut dyn windows_rpc :: pipe :: PipeSource < u32 >"));
+    assert!(code.contains("data : & mut dyn windows_rpc :: pipe :: PipeSink < u8 >"));
+    assert!(code.contains("data : & mut windows_rpc :: pipe :: PipeReader < '_ , u32 >"));
+    assert!(code.contains("data : & mut windows_rpc :: pipe :: PipeWriter < '_ , u8 >"));
+    assert!(code.contains("RPC_INTERFACE_HAS_PIPES"));
+    // The call borrows the pipes, so it can't run on the executor
+    assert!(!code.contains("fn upload_async"));
+    syn::parse2::<syn::File>(compile_interface(&interface)).unwrap();
+
+    for (methods, message) in [
+        (
+            quote! { fn f(data: &InPipe<u32>); },
+            "Pipes are passed by value, as `InPipe<T>` or `OutPipe<T>` parameters",
+        ),
+        (
+            quote! { fn f() -> OutPipe<u32>; },
+            "Pipes are only supported as `InPipe<T>` and `OutPipe<T>` parameters",
+        ),
+        (
+            quote! { fn f(data: InPipe<String>); },
+            "Only pipes of integers and floating point numbers are supported",
+        ),
+        (
+            quote! { fn f(#[in_out] data: OutPipe<u32>); },
+            "`#[in_out]` is not supported on pipes, take an `InPipe<T>` and an `OutPipe<T>`",
+        ),
+        (
+            quote! { fn f(#[since(1.1, default = 0)] data: InPipe<u32>); },
+            "`#[since]` is not supported on pipes",
+        ),
+        (
+            quote! { #[rpc(maybe)] fn f(data: InPipe<u32>); },
+            "`maybe` and `broadcast` methods cannot have pipe parameters",
+        ),
+        (
+            quote! { #[rpc(async)] fn f(data: InPipe<u32>); },
+            "`async` methods cannot have pipe parameters",
+        ),
+        (
+            quote! { #[rpc(callback)] fn f(data: InPipe<u32>); },
+            "`callback` methods cannot have pipe parameters",
+        ),
+    ] {
+        let error = parse_interface(
+            quote! { guid(0x12345678_1234_1234_1234_123456789abc), version(1.1) },
+            quote! { trait Storage { #methods } },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error.to_string(), message);
+    }
+}
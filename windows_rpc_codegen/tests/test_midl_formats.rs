@@ -0,0 +1,215 @@
+mod midl;
+
+use std::path::Path;
+
+use midl::{MidlStub, compare, diff};
+use windows_rpc_codegen::formats::formats;
+use windows_rpc_codegen::{BaseType, Interface, InterfaceVersion, Method, Parameter, Type};
+
+fn interface() -> Interface {
+    Interface {
+        name: "Calculator".to_string(),
+        uuid: 0x12345678_1234_1234_1234_123456789abc,
+        version: InterfaceVersion { major: 1, minor: 0 },
+        methods: vec![
+            Method {
+                name: "add".to_string(),
+                parameters: vec![
+                    Parameter::new("a", Type::Simple(BaseType::I32)),
+                    Parameter::new("b", Type::Simple(BaseType::I32)),
+                ],
+                return_type: Some(Type::Simple(BaseType::I32)),
+                ..Method::default()
+            },
+            Method {
+                name: "greet".to_string(),
+                parameters: vec![Parameter::new("name", Type::String)],
+                return_type: Some(Type::String),
+                ..Method::default()
+            },
+        ],
+        ..Interface::default()
+    }
+}
+
+/// A client stub in MIDL's layout, with the format strings of `interface()`
+fn stub_source(interface: &Interface) -> String {
+    let formats = formats(interface);
+    let bytes = |format: &[u8]| {
+        format
+            .iter()
+            .map(|byte| format!("\t\t\t{byte:#x},\t\t/* {byte} */\n"))
+            .collect::<String>()
+    };
+    let offsets = formats
+        .proc_offsets
+        .iter()
+        .map(|offset| format!("    {offset}"))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!(
+        "typedef struct _c_MIDL_PROC_FORMAT_STRING\n    {{\n    short          Pad;\n    \
+         unsigned char  Format[ 1 ];\n    }} c_MIDL_PROC_FORMAT_STRING;\n\n\
+         extern const c_MIDL_PROC_FORMAT_STRING c__MIDL_ProcFormatString;\n\n\
+         static const c_MIDL_PROC_FORMAT_STRING c__MIDL_ProcFormatString =\n    {{\n        0,\n        {{\n\
+         {}        }}\n    }};\n\n\
+         static const c_MIDL_TYPE_FORMAT_STRING c__MIDL_TypeFormatString =\n    {{\n        0,\n        {{\n\
+         {}        }}\n    }};\n\n\
+         static const unsigned short Calculator_FormatStringOffsetTable[] =\n    {{\n{offsets}\n    }};\n",
+        bytes(&formats.proc_format),
+        bytes(&formats.type_format),
+    )
+}
+
+#[test]
+fn test_parse_stub() {
+    let source = "
+static const c_MIDL_PROC_FORMAT_STRING c__MIDL_ProcFormatString =
+    {
+        0,
+        {
+
+	/* Procedure add */
+
+			0x0,		/* 0 */
+			0x48,		/* Old Flags:  */
+/*  2 */	NdrFcLong( 0x0 ),	/* 0 */
+/*  6 */	NdrFcShort( 0x1 ),	/* 1 */
+			0x0
+        }
+    };
+
+static const c_MIDL_TYPE_FORMAT_STRING c__MIDL_TypeFormatString =
+    {
+        0,
+        {
+			NdrFcShort( 0x0 ),	/* 0 */
+/*  2 */
+			0x11, 0x8,	/* FC_RP [simple_pointer] */
+/*  4 */
+			0x25,		/* FC_C_WSTRING */
+			0x5c,		/* FC_PAD */
+
+			0x0
+        }
+    };
+
+static const unsigned short Calculator_FormatStringOffsetTable[] =
+    {
+    0,
+    36
+    };
+
+static const COMM_FAULT_OFFSETS Calculator_CommFaultOffsets[] =
+{
+	{ 0x18, 0x18 },	/* x64 Offsets */
+	{ 0x10, 0x10 }	/* x64 Offsets */
+};
+";
+    let stub = MidlStub::parse(source).unwrap();
+    assert_eq!(stub.proc_format, [0, 0x48, 0, 0, 0, 0, 1, 0, 0]);
+    assert_eq!(stub.type_format, [0, 0, 0x11, 0x08, 0x25, 0x5c, 0]);
+    assert_eq!(stub.proc_offsets, [0, 36]);
+    assert_eq!(stub.comm_fault_offsets, [(0x18, 0x18), (0x10, 0x10)]);
+
+    assert!(
+        MidlStub::parse("static const unsigned short T_FormatStringOffsetTable[] = {};").is_err()
+    );
+}
+
+#[test]
+fn test_compare() {
+    let interface = interface();
+    let stub = MidlStub::parse(&stub_source(&interface)).unwrap();
+    compare(&interface, &stub).unwrap();
+
+    // One more parameter in the header of the first procedure
+    let mut stub = stub;
+    stub.proc_format[19] += 1;
+    let report = compare(&interface, &stub).unwrap_err();
+    assert!(report.contains("Proc format strings differ"), "{report}");
+    let removed: Vec<_> = report
+        .lines()
+        .filter(|line| line.starts_with("- "))
+        .collect();
+    let added: Vec<_> = report
+        .lines()
+        .filter(|line| line.starts_with("+ "))
+        .collect();
+    // The return value descriptor is no longer counted as a parameter
+    assert_eq!(
+        removed,
+        [
+            "- /*   19 */\t0x3,\t/* 3 parameters */",
+            "- \t/* Return value */"
+        ],
+        "{report}"
+    );
+    assert_eq!(
+        added,
+        [
+            "+ /*   19 */\t0x2,\t/* 2 parameters */",
+            "+ \t/* Uncounted parameter */"
+        ],
+        "{report}"
+    );
+    assert!(!report.contains("Type format strings differ"));
+}
+
+#[test]
+fn test_diff_aligns_shifted_lines() {
+    let expected =
+        "/*    0 */\t0x1,\t/* a */\n/*    1 */\t0x2,\t/* b */\n/*    2 */\t0x3,\t/* c */\n";
+    let actual = "/*    0 */\t0x1,\t/* a */\n/*    1 */\t0x9,\t/* x */\n/*    2 */\t0x2,\t/* b */\n/*    3 */\t0x3,\t/* c */\n";
+    assert_eq!(
+        diff(expected, actual),
+        "  /*    0 */\t0x1,\t/* a */\n\
+         + /*    1 */\t0x9,\t/* x */\n\
+         \x20 /*    2 */\t0x2,\t/* b */\n\
+         \x20 /*    3 */\t0x3,\t/* c */\n"
+    );
+
+    // Unchanged lines away from the differences are elided
+    let lines: String = (0..20)
+        .map(|line| format!("/* {line} */\t{line},\n"))
+        .collect();
+    let changed = lines.replace("/* 15 */\t15,", "/* 15 */\t99,");
+    assert_eq!(
+        diff(&lines, &changed),
+        "  /* 12 */\t12,\n  /* 13 */\t13,\n  /* 14 */\t14,\n\
+         - /* 15 */\t15,\n+ /* 15 */\t99,\n\
+         \x20 /* 16 */\t16,\n  /* 17 */\t17,\n  /* 18 */\t18,\n"
+    );
+}
+
+/// Compares the interfaces in `tests/midl` with the client and server stubs midl.exe
+/// generated for them
+#[test]
+#[cfg(target_pointer_width = "64")]
+#[ignore = "the midl.exe references of tests/midl are not generated yet, see TODO.md"]
+fn test_midl_references() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/midl");
+    let mut failures = vec![];
+    for entry in std::fs::read_dir(&directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|extension| extension != "idl") {
+            continue;
+        }
+        let interfaces = windows_rpc_codegen::parse_idl_file(&path).unwrap();
+        for kind in ["c", "s"] {
+            let stub = match MidlStub::read(&path, kind) {
+                Ok(stub) => stub,
+                Err(error) => {
+                    failures.push(error);
+                    continue;
+                }
+            };
+            for interface in &interfaces {
+                if let Err(report) = compare(interface, &stub) {
+                    failures.push(report);
+                }
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,258 @@
+//! `windows-rpc-gen`: prints the code `windows-rpc` generates for the interfaces of a Rust
+//! or MIDL file, and their NDR format strings, to inspect or diff them without expanding
+//! macros or attaching a debugger.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::ExitCode;
+
+use quote::ToTokens;
+use windows_rpc_codegen::formats::{
+    annotate_ndr64_type_format, annotate_proc_format, annotate_type_format, formats,
+};
+use windows_rpc_codegen::{
+    Interface, Type, compile_interface, derive_ndr_enum, derive_ndr_struct, derive_ndr_union,
+    expand_rpc_interface_from_idl, parse_idl_file, parse_interface,
+};
+
+const USAGE: &str = "\
+Usage: windows-rpc-gen [--code] [--formats] [--ndr64] <FILE>
+
+Prints the code generated for the `#[rpc_interface]` traits and the `NdrStruct`,
+`NdrEnum` and `NdrUnion` derives of a Rust file, or for the interfaces of a MIDL
+(`.idl`) file, and the format strings of the interfaces. All sections are printed
+unless some are selected:
+
+    --code      the generated code
+    --formats   the NDR 2.0 proc and type format strings, as annotated hex
+    --ndr64     the NDR64 type format, and the code building the procedure descriptors
+";
+
+/// Sections to print
+#[derive(Clone, Copy)]
+struct Sections {
+    code: bool,
+    formats: bool,
+    ndr64: bool,
+}
+
+fn main() -> ExitCode {
+    let mut sections = Sections {
+        code: false,
+        formats: false,
+        ndr64: false,
+    };
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--code" => sections.code = true,
+            "--formats" => sections.formats = true,
+            "--ndr64" => sections.ndr64 = true,
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ if arg.starts_with('-') || path.is_some() => {
+                eprint!("{USAGE}");
+                return ExitCode::from(2);
+            }
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        eprint!("{USAGE}");
+        return ExitCode::from(2);
+    };
+    if !(sections.code || sections.formats || sections.ndr64) {
+        sections = Sections {
+            code: true,
+            formats: true,
+            ndr64: true,
+        };
+    }
+
+    match run(Path::new(&path), sections) {
+        Ok(output) => {
+            // Ignores a pager or `head` closing the output early
+            let _ = std::io::stdout().write_all(output.as_bytes());
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(path: &Path, sections: Sections) -> Result<String, String> {
+    let is_idl = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("idl"));
+    let (code, interfaces) = if is_idl {
+        load_idl(path).map_err(|error| format!("{}: {error}", path.display()))?
+    } else {
+        load_rust(path).map_err(|error| describe_error(path, &error))?
+    };
+
+    let mut output = String::new();
+    if sections.code {
+        let file: syn::File = syn::parse2(code).map_err(|error| error.to_string())?;
+        output.push_str(&prettyplease::unparse(&file));
+    }
+    for interface in &interfaces {
+        output.push_str(&print_formats(interface, sections));
+    }
+    Ok(output)
+}
+
+/// Parses the interfaces of a MIDL file, with the code `rpc_interface_from_idl!` generates
+/// for them and their types
+fn load_idl(path: &Path) -> syn::Result<(proc_macro2::TokenStream, Vec<Interface>)> {
+    // Relative paths would be taken relative to `CARGO_MANIFEST_DIR` when it is set
+    let path = &std::path::absolute(path).map_err(|error| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!("resolving path: {error}"),
+        )
+    })?;
+    let interfaces = parse_idl_file(path)?;
+    let path = syn::LitStr::new(&path.to_string_lossy(), proc_macro2::Span::call_site());
+    let code = expand_rpc_interface_from_idl(path.to_token_stream())?;
+    Ok((code, interfaces))
+}
+
+/// Parses the `#[rpc_interface]` traits of a Rust file, with the code generated for them
+/// and for the `Ndr*` derives of the file
+fn load_rust(path: &Path) -> syn::Result<(proc_macro2::TokenStream, Vec<Interface>)> {
+    let source = std::fs::read_to_string(path).map_err(|error| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!("reading file: {error}"),
+        )
+    })?;
+    let file = syn::parse_file(&source)?;
+
+    let mut code = proc_macro2::TokenStream::new();
+    let mut interfaces = vec![];
+    for item in file.items {
+        match item {
+            syn::Item::Trait(mut item) => {
+                let Some(index) = item
+                    .attrs
+                    .iter()
+                    .position(|attr| last_segment_is(attr.path(), "rpc_interface"))
+                else {
+                    continue;
+                };
+                let attr = item.attrs.remove(index);
+                let arguments = match attr.meta {
+                    syn::Meta::List(list) => list.tokens,
+                    meta => {
+                        meta.require_path_only()?;
+                        proc_macro2::TokenStream::new()
+                    }
+                };
+                let interface = parse_interface(arguments, item.to_token_stream())?;
+                code.extend(compile_interface(&interface));
+                interfaces.push(interface);
+            }
+            syn::Item::Struct(item) if derives(&item.attrs, "NdrStruct")? => {
+                code.extend(derive_ndr_struct(item.into())?);
+            }
+            syn::Item::Enum(item) if derives(&item.attrs, "NdrEnum")? => {
+                code.extend(derive_ndr_enum(item.into())?);
+            }
+            syn::Item::Enum(item) if derives(&item.attrs, "NdrUnion")? => {
+                code.extend(derive_ndr_union(item.into())?);
+            }
+            _ => {}
+        }
+    }
+    Ok((code, interfaces))
+}
+
+/// Whether the `#[derive]` attributes of an item list `derive`
+fn derives(attrs: &[syn::Attribute], derive: &str) -> syn::Result<bool> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let paths = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+        )?;
+        if paths.iter().any(|path| last_segment_is(path, derive)) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn last_segment_is(path: &syn::Path, name: &str) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
+
+/// Describes an error in a Rust file with its location
+fn describe_error(path: &Path, error: &syn::Error) -> String {
+    let start = error.span().start();
+    if start.line == 0 {
+        format!("{}: {error}", path.display())
+    } else {
+        format!(
+            "{}:{}:{}: {error}",
+            path.display(),
+            start.line,
+            start.column + 1
+        )
+    }
+}
+
+fn print_formats(interface: &Interface, sections: Sections) -> String {
+    let formats = formats(interface);
+    let mut output = String::new();
+    if sections.formats {
+        output.push_str(&format!(
+            "\n// {} ({}): NDR 2.0 proc format string\n",
+            interface.name,
+            interface.id_string()
+        ));
+        for (method, offset) in interface.methods.iter().zip(&formats.proc_offsets) {
+            output.push_str(&format!("// {} at {offset}\n", method.name));
+        }
+        for (position, r#type) in &formats.fixups {
+            let name = match r#type {
+                Type::Struct(name) => format!("type offset of `{name}`"),
+                Type::Enum(name) => format!("format character of `{name}`"),
+                _ => "runtime value".to_string(),
+            };
+            output.push_str(&format!("// {position}: {name}, filled at runtime\n"));
+        }
+        output.push_str(&annotate_proc_format(
+            &formats.proc_format,
+            &formats.proc_offsets,
+        ));
+        output.push_str(&format!(
+            "\n// {}: NDR 2.0 type format string, without struct and union fragments\n",
+            interface.name
+        ));
+        for (position, r#type) in &formats.type_fixups {
+            if let Type::Struct(name) = r#type {
+                output.push_str(&format!(
+                    "// {position}: offset to `{name}`, filled at runtime\n"
+                ));
+            }
+        }
+        output.push_str(&annotate_type_format(&formats.type_format));
+    }
+    if sections.ndr64 {
+        output.push_str(&format!("\n// {}: NDR64 type format\n", interface.name));
+        output.push_str(&annotate_ndr64_type_format(&formats.ndr64_type_format));
+        output.push_str(&format!(
+            "\n// {}: NDR64 procedure descriptors, built at runtime\n",
+            interface.name
+        ));
+        // The descriptors are a block expression, unparsed as the body of a function
+        let descriptors = &formats.ndr64_descriptors;
+        let function: syn::File = syn::parse_quote! { fn ndr64_descriptors() { #descriptors } };
+        output.push_str(&prettyplease::unparse(&function));
+    }
+    output
+}
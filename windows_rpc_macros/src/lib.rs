@@ -1,26 +1,19 @@
 //! Procedural macros for generating Windows RPC client and server code.
 //!
 //! This crate provides the [`macro@rpc_interface`] attribute macro that transforms
-//! Rust trait definitions into fully functional Windows RPC clients and servers.
+//! Rust trait definitions into fully functional Windows RPC clients and servers, and the
+//! [`macro@NdrStruct`], [`macro@NdrEnum`] and [`macro@NdrUnion`] derives for the structs and
+//! enums they pass.
+//!
+//! The code generation itself lives in the
+//! [`windows_rpc_codegen`](https://docs.rs/windows-rpc-codegen) crate.
 //!
 //! See the [`windows_rpc`](https://docs.rs/windows-rpc) crate for the main documentation and examples.
 
-mod client_codegen;
-#[allow(dead_code)]
-mod constants;
-mod ndr;
-mod ndr64;
-mod parse;
-mod server_codegen;
-mod types;
-
-use quote::ToTokens;
-use syn::{FnArg, ReturnType, TraitItem};
-
-use client_codegen::compile_client;
-use parse::InterfaceAttributes;
-use server_codegen::compile_server;
-use types::{Interface, Method, Parameter, Type};
+use windows_rpc_codegen::{
+    derive_ndr_enum, derive_ndr_struct, derive_ndr_union, expand_rpc_interface,
+    expand_rpc_interface_from_idl,
+};
 
 /// Generates Windows RPC client and server code from a trait definition.
 ///
@@ -35,11 +28,115 @@ use types::{Interface, Method, Parameter, Type};
 /// - `guid(...)` - A unique interface identifier (UUID/GUID) in hexadecimal format
 /// - `version(major.minor)` - The interface version number
 ///
+/// Alternatively, the trait can declare them as associated consts, with literal values:
+///
+/// ```rust,ignore
+/// #[rpc_interface]
+/// trait Calculator {
+///     const GUID: u128 = 0x12345678_1234_1234_1234_123456789abc;
+///     const VERSION: (u16, u16) = (1, 0);
+///
+///     fn add(a: i32, b: i32) -> i32;
+/// }
+/// ```
+///
+/// Either way, the generated client and server types expose them as `GUID` and `VERSION`
+/// associated consts (e.g. `CalculatorClient::GUID`), along with `INTERFACE_ID`, a stable
+/// textual identifier such as `12345678-1234-1234-1234-123456789abc:1.0`, and
+/// `interface_id()`, returning the `GUID` and version.
+///
+/// An optional `generic_handle(Type)` argument declares a user-defined binding handle, like
+/// a MIDL generic handle. `Type` must implement `windows_rpc::client_binding::GenericHandle`;
+/// the client is then created without a binding and every method takes a `&Type` first,
+/// which is bound when the call is made and unbound after it completes.
+///
+/// Parameters appended in a later minor version are marked
+/// `#[since(major.minor, default = value)]`. The client then gains `connect()` and
+/// `with_server_version()`, which call older servers without the parameters added after
+/// their version, and the server also registers the older versions, calling the
+/// implementation with `value` for the parameters their clients omit.
+///
+/// An optional `transfer_syntax(ndr64)` argument generates only the NDR64 tables, dropping
+/// the NDR 2.0 format strings and dispatch table. Such clients and servers only talk to
+/// peers that support NDR64, and only build for 64-bit targets. `transfer_syntax(ndr20)`
+/// conversely generates only the NDR 2.0 tables, for peers that predate NDR64, and offers
+/// the interface with the flags of the `Legacy` preset whatever the preset given.
+/// `transfer_syntax(all)` is the default.
+///
+/// An optional `lockfile("path")` argument locks the wire contract of the interface: its
+/// GUID, version and NDR descriptors are hashed and compared with the hash recorded for
+/// the trait name in the lockfile at `path`, relative to the crate's manifest directory.
+/// The build fails if they differ, so edits that would break deployed peers are caught.
+/// Building with `WINDOWS_RPC_UPDATE_LOCKFILE=1` records the current hash instead.
+///
+/// The MIDL definition of the interface is exposed as the `IDL` const of the client and
+/// server, so C/C++ peers can compile matching stubs with MIDL. An optional `idl("path")`
+/// argument also writes it to `path`, relative to the crate's manifest directory, whenever
+/// it changes. Structs, unions and enums are referred to by name, and their typedefs have
+/// to be provided to MIDL separately.
+///
+/// Likewise, the `HEADER` const holds a C header declaring the interface GUID, version and
+/// procedure prototypes, implemented by the client stubs MIDL generates from `IDL`, and an
+/// optional `header("path")` argument writes it to a file.
+///
+/// Methods marked `#[rpc(maybe)]` are sent without waiting for a reply, like MIDL's
+/// `[maybe]` procedures, and cannot return values or have out parameters. Methods marked
+/// `#[rpc(idempotent)]` may be executed more than once, letting the runtime retry them.
+/// Methods marked `#[rpc(broadcast)]` are sent to every server on the local network over
+/// `ncadg_ip_udp`, and are idempotent. Attributes combine, e.g. `#[rpc(maybe, idempotent)]`.
+/// Methods marked `#[rpc(async)]` are asynchronous procedures, like MIDL's ACF `[async]`:
+/// their `_async` client methods await the reply without a thread, and the server completes
+/// the call with `RpcAsyncCompleteCall`.
+/// Methods marked `#[rpc(callback)]` are callbacks, like MIDL's `[callback]`, which servers
+/// call on the client during one of its calls: they go to the generated `{Interface}Callbacks`
+/// trait, which clients implement and pass to `set_callbacks()`, and servers call them through
+/// `{Interface}CallbacksClient::current()`.
+///
+/// A parameter marked `#[rpc(range(min, max))]`, an integer of up to 32 bits or a slice
+/// whose length is bounded, fails calls with a value outside of `min..=max` with
+/// `RPC_S_INVALID_BOUND` before they reach the server's handler, like MIDL's `[range]`.
+/// A slice marked `#[rpc(size_is(length))]` is sized by the `u32` parameter `length`
+/// instead of a hidden length, like MIDL's `[size_is]`. An `Option<T>` or `&T` struct
+/// parameter marked `#[rpc(ptr)]` is a full pointer, like MIDL's `[ptr]`: a value several
+/// parameters point to is sent once, and arrives aliased. A `&str` parameter marked
+/// `#[rpc(ansi)]` is sent as a `[string] char*` in the system's ANSI code page, for peers
+/// that don't take wide strings, and an `Option<&str>` one as a `[unique, string] char*`.
+///
+/// An optional `enums(Type, ...)` argument lists the enums deriving [`macro@NdrEnum`] that
+/// the methods use. The macro can't see their definitions, and would otherwise take them
+/// for structs.
+///
+/// An optional `transmit_as(Type = Wire, ...)` argument lists the types the methods take
+/// by value that are sent as the number or struct `Wire`, like MIDL's `[transmit_as]`.
+/// They implement `windows_rpc::RpcTransmittable<Wire>`, and the IDL, C header and format
+/// strings describe `Wire`.
+///
+/// Kernel object handles are lent to the server as `BorrowedHandle<'_>` parameters marked
+/// `#[rpc(system_handle(kind, access))]`, and returned as `OwnedHandle` by methods marked
+/// `#[rpc(system_handle(kind, access))]`, like MIDL's `[system_handle]`. The runtime
+/// duplicates them into the receiving process with the optional `access` mask, so they
+/// can only be passed over ALPC (`ncalrpc`).
+///
+/// Context handles, like MIDL's `[context_handle]`, are opened by methods returning
+/// `windows_rpc::context::ContextHandle`, and passed as `&ContextHandle` or
+/// `#[in_out] &mut ContextHandle` parameters. Server handlers take and return
+/// `windows_rpc::context::ServerContext` instead.
+///
+/// Pipes, like MIDL's `pipe` types, are `InPipe<T>` and `OutPipe<T>` parameters of numbers
+/// that stream in chunks. Clients pass a `&mut dyn windows_rpc::pipe::PipeSource<T>` or
+/// `PipeSink<T>`, and server handlers take a `&mut windows_rpc::pipe::PipeReader<'_, T>` or
+/// `PipeWriter<'_, T>`.
+///
 /// # Generated Types
 ///
 /// For a trait named `MyInterface`, the macro generates:
 ///
-/// - **`MyInterfaceClient`** - A struct for making RPC calls to a server
+/// - **`MyInterfaceClient`** - A struct for making RPC calls to a server, whose methods return
+///   `Result<T, windows_rpc::RpcError>` with the status of a failed call (or
+///   `windows_rpc::ServiceError<E>` for methods returning `Result<T, E>`)
+/// - **`MyInterface`** - An object-safe trait with the client's methods, taking `&self`,
+///   implemented by `MyInterfaceClient`, so that code can be generic over the client, a
+///   local implementation or a mock
 /// - **`MyInterfaceServerImpl`** - A trait to implement for hosting a server
 /// - **`MyInterfaceServer`** - A struct that wraps your implementation and handles RPC dispatch
 ///
@@ -58,7 +155,62 @@ use types::{Interface, Method, Parameter, Type};
 /// | `i64` | FC_HYPER | Signed 64-bit integer |
 /// | `u64` | FC_HYPER | Unsigned 64-bit integer |
 /// | `&str` | Conformant string | Input parameters only |
-/// | `String` | Conformant string | Return values only |
+/// | `String` | Conformant string | Return values, and input parameters taken by value |
+/// | `&String` / `Cow<'_, str>` | Conformant string | Input parameters only |
+/// | `&OsStr` / `OsString` / `&Path` / `PathBuf` | Conformant string | Input parameters only, converted without loss |
+/// | `&U16CStr` | Conformant string | Input parameters only, borrowed by the server without decoding |
+/// | `#[rpc(ansi)] &str` / `Option<&str>` | Conformant `char` string, `[unique]` for options | Input parameters only, in the ANSI code page |
+/// | `U16CString` | Conformant string | Return values only |
+/// | `&HSTRING` / `HSTRING` | Conformant string | Input parameters / return values |
+/// | `&[u8]`, `&[i32]`, ... | Conformant array, sized by a hidden length or `size_is` | Input parameters |
+/// | `&mut [u8]`, `&mut [i32]`, ... | Conformant array, sized by a hidden length or `size_is` | Output parameters, or `#[in_out]` |
+/// | `Vec<u8>`, `Vec<i32>`, ... | Conformant array, sized by a hidden length | Return values only |
+/// | `Vec<String>` / `&mut Vec<String>` | Conformant array of `[unique]` string pointers, sized by a hidden length | Return values and output parameters |
+/// | `&mut i32`, `&mut u64`, ... | `[out]` pointer to the integer | Output parameters only |
+/// | `f32` | FC_FLOAT | 32-bit floating point number |
+/// | `f64` | FC_DOUBLE | 64-bit floating point number |
+/// | `&T` / `&mut T` / `T` | FC_STRUCT | Structs deriving [`macro@NdrStruct`] |
+/// | `&T` | FC_BOGUS_STRUCT | Structs deriving [`macro@NdrStruct`] with `PCWSTR`, `PCSTR` or `*const T` fields, input parameters only |
+/// | `&FILETIME` / `&mut SYSTEMTIME` / `LARGE_INTEGER` ... | FC_STRUCT | Win32 structs of `wtypes.idl`, like structs deriving [`macro@NdrStruct`] |
+/// | `T` | FC_ENUM16 / FC_ENUM32 | Enums deriving [`macro@NdrEnum`], listed in `enums(...)` |
+/// | `&T` / `T` | FC_ENCAPSULATED_UNION | Enums with payloads deriving [`macro@NdrUnion`] |
+/// | `Option<i32>`, `Option<f64>`, ... | `[unique]` pointer to the number | Null for `None` |
+/// | `Option<&str>` / `Option<String>` | `[unique]` conformant string | Input parameters / return values, null for `None` |
+/// | `T` | The format of `Wire` | Types listed in `transmit_as(T = Wire)` |
+/// | `BorrowedHandle<'_>` / `OwnedHandle` | FC_SYSTEM_HANDLE | Input parameters / return values marked `system_handle(kind)`, over ALPC only |
+/// | `&ContextHandle` / `ContextHandle` | FC_BIND_CONTEXT | Input (or `#[in_out] &mut`) parameters / return values opening a context |
+/// | `InPipe<T>` / `OutPipe<T>` | FC_PIPE | `[in]` / `[out]` parameters streaming numbers, on 64-bit targets |
+///
+/// `&mut` parameters are written by the server and copied back to the client's
+/// variable when the call returns. Their initial value is not sent to the server,
+/// unless the parameter is marked `#[in_out]`:
+///
+/// ```rust,ignore
+/// #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+/// trait Counter {
+///     // `[in, out] long* value`
+///     fn increment(#[in_out] value: &mut i32);
+/// }
+/// ```
+///
+/// Methods returning `Result<T, E>` send the error code of a failed handler, a `u32` or an
+/// enum listed in `enums(...)`, through a hidden `[out] unsigned long*` parameter; zero
+/// means success. Their client methods return `Result<T, windows_rpc::ServiceError<E>>`:
+///
+/// ```rust,ignore
+/// #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0))]
+/// trait Files {
+///     // `unsigned hyper size([in] handle_t binding, [in, string] const wchar_t* name,
+///     //     [out] unsigned long* service_error)`
+///     fn size(name: &str) -> Result<u64, u32>;
+/// }
+/// ```
+///
+/// Methods marked `#[rpc(error_status)]` return the `u32` error as an `error_status_t`
+/// instead, as most Microsoft interfaces do, with a returned number in a trailing `[out]`
+/// parameter: `error_status_t size([in] handle_t binding, [in, string] const wchar_t* name,
+/// [out] unsigned hyper* return_value)`. They can't return enums. `#[rpc(hresult)]` methods
+/// return an `i32` error as an `HRESULT` the same way, which clients report when negative.
 ///
 /// # Example
 ///
@@ -98,8 +250,8 @@ use types::{Interface, Method, Parameter, Type};
 ///     .expect("Failed to create binding");
 /// let client = CalculatorClient::new(binding);
 ///
-/// assert_eq!(client.add(10, 20), 30);
-/// assert_eq!(client.multiply(5, 6), 30);
+/// assert_eq!(client.add(10, 20).unwrap(), 30);
+/// assert_eq!(client.multiply(5, 6).unwrap(), 30);
 ///
 /// server.stop().expect("Failed to stop");
 /// ```
@@ -107,16 +259,16 @@ use types::{Interface, Method, Parameter, Type};
 /// # Limitations
 ///
 /// - Only ALPC (local RPC) protocol is currently supported
-/// - No support for input-output (`[in, out]`) parameters
-/// - No support for pointer types, structs, arrays, or other complex types
+/// - Input-output (`[in, out]`) parameters are limited to integers
+/// - No support for pointer types or other complex types; arrays and structs are limited to
+///   integer elements and fields
 /// - No interface security (authentication/authorization) support
-/// - No SEH exception handling
 ///
 /// # Panics
 ///
 /// The macro will fail to compile if:
 ///
-/// - The trait contains non-function items
+/// - The trait contains items other than functions and the `GUID`/`VERSION` consts
 /// - A method uses `self` receiver
 /// - An unsupported type is used in parameters or return values
 /// - The GUID format is invalid
@@ -125,82 +277,145 @@ pub fn rpc_interface(
     attr: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    match rpc_interface_inner(attr.into(), input.into()) {
+    match expand_rpc_interface(attr.into(), input.into()) {
         Ok(ts) => ts.into(),
         Err(e) => e.into_compile_error().into(),
     }
 }
 
-fn rpc_interface_inner(
-    attr: proc_macro2::TokenStream,
-    input: proc_macro2::TokenStream,
-) -> syn::Result<proc_macro2::TokenStream> {
-    // Parse interface attributes (guid and version)
-    let attrs: InterfaceAttributes = syn::parse2(attr)?;
-
-    let input_clone = input.clone();
-    let t: syn::ItemTrait = syn::parse2(input)?;
-
-    let mut methods = vec![];
-    for item in t.items {
-        let TraitItem::Fn(func) = item else {
-            return Err(syn::Error::new_spanned(
-                input_clone,
-                "Only functions are allowed on this trait",
-            ));
-        };
-
-        let return_type = match func.sig.output {
-            ReturnType::Default => None,
-            ReturnType::Type(_, t) => Some(Type::try_from(*t)?),
-        };
-
-        let mut params = vec![];
-        for param in func.sig.inputs {
-            let FnArg::Typed(typed) = param else {
-                return Err(syn::Error::new_spanned(
-                    input_clone,
-                    "Passing self is currently not supported",
-                ));
-            };
-
-            let syn::Pat::Ident(param_name) = *typed.pat else {
-                return Err(syn::Error::new_spanned(
-                    typed.pat.to_token_stream(),
-                    "Expected identifier",
-                ));
-            };
-
-            let param_type = Type::try_from(*typed.ty)?;
-
-            params.push(Parameter {
-                r#type: param_type,
-                name: param_name.ident.to_string(),
-                // FIXME: let mut affect this (can be in/out)
-                is_in: true,
-                is_out: false,
-            });
-        }
-
-        methods.push(Method {
-            return_type,
-            name: func.sig.ident.to_string(),
-            parameters: params,
-        });
+/// Generates the client and server of the interfaces of a MIDL file, like
+/// [`macro@rpc_interface`] does for the equivalent traits.
+///
+/// The path is relative to the crate's manifest directory. It can be followed by
+/// `rpc_interface` arguments applied to every interface, e.g. `lockfile("rpc.lock")`:
+///
+/// ```rust,ignore
+/// rpc_interface_from_idl!("idl/calculator.idl", transfer_syntax(ndr64));
+/// ```
+///
+/// Interfaces are named after the IDL interface in upper camel case, and methods and
+/// parameters after the IDL names in snake case. A subset of MIDL is supported:
+///
+/// - `uuid`, `version` and `pointer_default` interface attributes, and the `maybe`,
+///   `idempotent`, `broadcast` and `callback` procedure attributes
+/// - Integers and floats, including Windows typedefs such as `DWORD`, `enum` types, and
+///   pointers to structs, which have to be defined in Rust under the same name
+/// - `[in, string]` wide strings, `[in, unique]` and `[in, ptr]` pointers, and `[in]`, `[out]` and
+///   `[in, out]` `size_is` arrays sized by an `[in] unsigned long` parameter
+/// - `range` attributes on integers and array lengths
+/// - `[out]` and `[in, out]` pointers to integers, floats and structs
+/// - Trailing `[out]` strings, `T**` pointers and `size_is(, *length)` arrays of `void`
+///   procedures, which become the return value
+/// - Procedures returning `error_status_t`, which become `#[rpc(error_status)]` methods, and
+///   `HRESULT`, which become `#[rpc(hresult)]` methods
+///
+/// An explicit `handle_t` first parameter is dropped. `import` and `cpp_quote` are
+/// skipped, while typedefs, preprocessor directives and object interfaces fail the build.
+/// [`macro@rpc_interface`] generates IDL in this subset, as the `IDL` const.
+#[proc_macro]
+pub fn rpc_interface_from_idl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match expand_rpc_interface_from_idl(input.into()) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.into_compile_error().into(),
     }
+}
 
-    let interface = Interface {
-        name: t.ident.to_string(),
-        uuid: attrs.guid,
-        version: attrs.version,
-        methods,
-    };
+/// Describes a `#[repr(C)]` struct of numbers, strings and arrays to NDR, so that interfaces
+/// can take it as `&T` and `&mut T` parameters and return it.
+///
+/// The struct must also implement `Copy`. It implements `windows_rpc::ndr::NdrStruct`,
+/// see the [`windows_rpc::ndr`](https://docs.rs/windows-rpc/latest/windows_rpc/ndr/)
+/// module.
+///
+/// ```rust,ignore
+/// #[derive(NdrStruct, Clone, Copy)]
+/// #[repr(C)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+/// ```
+///
+/// `PCWSTR` and `PCSTR` fields are marshalled as strings, and `*const T` fields as arrays of
+/// numbers, sized by the `u32` field named in `#[ndr(size_is(...))]`:
+///
+/// ```rust,ignore
+/// #[derive(NdrStruct, Clone, Copy)]
+/// #[repr(C)]
+/// struct Entry {
+///     name: PCWSTR,
+///     count: u32,
+///     #[ndr(size_is(count))]
+///     values: *const u16,
+/// }
+/// ```
+///
+/// `*const u16` and `*const u8` fields are either strings, marked `#[ndr(string)]`, or
+/// arrays, marked `#[ndr(size_is(...))]`. `Vec<u16>` and `Vec<u8>` fields, with the same
+/// attributes, make the derive generate a `{Name}Wire` struct pointing into the vectors
+/// instead, and implement `RpcTransmittable<{Name}Wire>` for the struct, which interfaces
+/// then pass with `transmit_as(Name = NameWire)`. Such structs needn't be `#[repr(C)]` or
+/// `Copy`.
+#[proc_macro_derive(NdrStruct, attributes(ndr))]
+pub fn derive_ndr_struct_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse(input).and_then(derive_ndr_struct) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
 
-    let client_code = compile_client(&interface);
-    let server_code = compile_server(&interface);
+/// Describes a fieldless `#[repr(u16)]` or `#[repr(u32)]` enum to NDR, as `FC_ENUM16` or
+/// `FC_ENUM32`, so that interfaces listing it in `enums(...)` can take it as a parameter
+/// and return it.
+///
+/// The enum must also implement `Copy`. It implements `windows_rpc::ndr::NdrEnum`.
+/// Received values that match no variant fault the call on servers and panic on clients,
+/// unless a variant is marked `#[ndr(fallback)]`, which then stands for them:
+///
+/// ```rust,ignore
+/// #[derive(NdrEnum, Clone, Copy)]
+/// #[repr(u32)]
+/// enum Color {
+///     Red = 1,
+///     Green = 2,
+///     #[ndr(fallback)]
+///     Unknown = 0xffff,
+/// }
+///
+/// #[rpc_interface(guid(0x12345678_1234_1234_1234_123456789abc), version(1.0), enums(Color))]
+/// trait Palette {
+///     fn mix(a: Color, b: Color) -> Color;
+/// }
+/// ```
+#[proc_macro_derive(NdrEnum, attributes(ndr))]
+pub fn derive_ndr_enum_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse(input).and_then(derive_ndr_enum) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
 
-    Ok(quote::quote! {
-        #client_code
-        #server_code
-    })
+/// Describes a `#[repr(C, u16)]` or `#[repr(C, u32)]` enum whose variants carry an integer
+/// or floating point number to NDR, as an encapsulated union, so that interfaces can take
+/// it as a `&T` parameter and return it.
+///
+/// The enum must also implement `Copy`. It implements `windows_rpc::ndr::NdrUnion`.
+/// Received values with an unknown discriminant fail the call. String payloads are
+/// rejected, as the enum has no room for their buffer.
+///
+/// ```rust,ignore
+/// #[derive(NdrUnion, Clone, Copy)]
+/// #[repr(C, u32)]
+/// enum Value {
+///     Empty,
+///     Int(i32),
+///     Real(f64),
+/// }
+/// ```
+#[proc_macro_derive(NdrUnion)]
+pub fn derive_ndr_union_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse(input).and_then(derive_ndr_union) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
 }